@@ -449,10 +449,13 @@ impl Accounts {
                                     programdata_address,
                                 }) = account.state()
                                 {
-                                    if self
-                                        .accounts_db
-                                        .load_with_fixed_root(ancestors, &programdata_address)
+                                    if account_overrides
+                                        .and_then(|overrides| overrides.get(&programdata_address))
                                         .is_none()
+                                        && self
+                                            .accounts_db
+                                            .load_with_fixed_root(ancestors, &programdata_address)
+                                            .is_none()
                                     {
                                         error_counters.account_not_found += 1;
                                         return Err(TransactionError::ProgramAccountNotFound);
@@ -779,9 +782,22 @@ impl Accounts {
             .load_with_fixed_root(ancestors, &address_table_lookup.account_key)
             .map(|(account, _rent)| account)
             .ok_or(AddressLookupError::LookupTableAccountNotFound)?;
+        Self::lookup_table_addresses(
+            &table_account,
+            ancestors.max_slot(),
+            address_table_lookup,
+            slot_hashes,
+        )
+    }
 
+    /// Looks up the addresses selected by `address_table_lookup` in `table_account`
+    pub fn lookup_table_addresses(
+        table_account: &AccountSharedData,
+        current_slot: Slot,
+        address_table_lookup: &MessageAddressTableLookup,
+        slot_hashes: &SlotHashes,
+    ) -> std::result::Result<LoadedAddresses, AddressLookupError> {
         if table_account.owner() == &address_lookup_table::program::id() {
-            let current_slot = ancestors.max_slot();
             let lookup_table = AddressLookupTable::deserialize(table_account.data())
                 .map_err(|_ix_err| AddressLookupError::InvalidAccountData)?;
 
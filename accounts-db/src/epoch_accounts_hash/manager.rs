@@ -84,6 +84,11 @@ impl Manager {
             _ => None,
         }
     }
+
+    /// Returns whether an epoch accounts hash calculation is in-flight
+    pub fn is_in_flight(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), State::InFlight(_))
+    }
 }
 
 /// The EpochAccountsHash is calculated in the background via AccountsBackgroundService.  This enum
@@ -139,6 +144,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_in_flight() {
+        let epoch_accounts_hash = EpochAccountsHash::new(Hash::new_unique());
+        for (state, expected) in [
+            (State::Invalid, false),
+            (State::InFlight(123), true),
+            (State::Valid(epoch_accounts_hash, 5678), false),
+        ] {
+            let manager = Manager::_new(state);
+            assert_eq!(manager.is_in_flight(), expected);
+        }
+    }
+
     #[test]
     fn test_wait_epoch_accounts_hash() {
         // Test: State is Valid, no need to wait
@@ -13,8 +13,8 @@ use {
     borsh::BorshDeserialize,
     futures::{future::join_all, Future, FutureExt, TryFutureExt},
     solana_banks_interface::{
-        BanksRequest, BanksResponse, BanksTransactionResultWithMetadata,
-        BanksTransactionResultWithSimulation,
+        BanksRequest, BanksResponse, BanksTransactionResultWithInnerInstructions,
+        BanksTransactionResultWithMetadata, BanksTransactionResultWithSimulation,
     },
     solana_program::{
         clock::Slot, fee_calculator::FeeCalculator, hash::Hash, program_pack::Pack, pubkey::Pubkey,
@@ -152,6 +152,17 @@ impl BanksClient {
             .map_err(Into::into)
     }
 
+    pub fn process_transaction_with_inner_instructions_and_context(
+        &mut self,
+        ctx: Context,
+        transaction: impl Into<VersionedTransaction>,
+    ) -> impl Future<Output = Result<BanksTransactionResultWithInnerInstructions, BanksClientError>> + '_
+    {
+        self.inner
+            .process_transaction_with_inner_instructions_and_context(ctx, transaction.into())
+            .map_err(Into::into)
+    }
+
     pub fn simulate_transaction_with_commitment_and_context(
         &mut self,
         ctx: Context,
@@ -254,6 +265,17 @@ impl BanksClient {
         self.process_transaction_with_metadata_and_context(ctx, transaction.into())
     }
 
+    /// Process a transaction and return the result with metadata and the inner instructions
+    /// invoked by each of the transaction's instructions.
+    pub fn process_transaction_with_inner_instructions(
+        &mut self,
+        transaction: impl Into<VersionedTransaction>,
+    ) -> impl Future<Output = Result<BanksTransactionResultWithInnerInstructions, BanksClientError>> + '_
+    {
+        let ctx = context::current();
+        self.process_transaction_with_inner_instructions_and_context(ctx, transaction.into())
+    }
+
     /// Send a transaction and return any preflight (sanitization or simulation) errors, or return
     /// after the transaction has been rejected or reached the given level of commitment.
     pub fn process_transaction_with_preflight_and_commitment(
@@ -8,6 +8,7 @@ use {
         commitment_config::CommitmentLevel,
         fee_calculator::FeeCalculator,
         hash::Hash,
+        instruction::CompiledInstruction,
         message::Message,
         pubkey::Pubkey,
         signature::Signature,
@@ -39,6 +40,14 @@ pub struct TransactionSimulationDetails {
     pub return_data: Option<TransactionReturnData>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InnerInstruction {
+    pub instruction: CompiledInstruction,
+    /// Invocation stack height of the instruction, starting at 1 for transaction instructions
+    pub stack_height: u8,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionMetadata {
@@ -59,6 +68,15 @@ pub struct BanksTransactionResultWithMetadata {
     pub metadata: Option<TransactionMetadata>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanksTransactionResultWithInnerInstructions {
+    pub result: transaction::Result<()>,
+    pub metadata: Option<TransactionMetadata>,
+    /// Instructions invoked by each of the transaction's instructions, in invocation order.
+    /// `None` if the transaction was not executed.
+    pub inner_instructions: Option<Vec<Vec<InnerInstruction>>>,
+}
+
 #[tarpc::service]
 pub trait Banks {
     async fn send_transaction_with_context(transaction: VersionedTransaction);
@@ -100,6 +118,11 @@ pub trait Banks {
         message: Message,
         commitment: CommitmentLevel,
     ) -> Option<u64>;
+    // Requests are serialized by their index, so new methods go last to keep talking to older
+    // clients and servers
+    async fn process_transaction_with_inner_instructions_and_context(
+        transaction: VersionedTransaction,
+    ) -> BanksTransactionResultWithInnerInstructions;
 }
 
 #[cfg(test)]
@@ -4,9 +4,10 @@ use {
     futures::{future, prelude::stream::StreamExt},
     solana_accounts_db::transaction_results::TransactionExecutionResult,
     solana_banks_interface::{
-        Banks, BanksRequest, BanksResponse, BanksTransactionResultWithMetadata,
-        BanksTransactionResultWithSimulation, TransactionConfirmationStatus, TransactionMetadata,
-        TransactionSimulationDetails, TransactionStatus,
+        Banks, BanksRequest, BanksResponse, BanksTransactionResultWithInnerInstructions,
+        BanksTransactionResultWithMetadata, BanksTransactionResultWithSimulation, InnerInstruction,
+        TransactionConfirmationStatus, TransactionMetadata, TransactionSimulationDetails,
+        TransactionStatus,
     },
     solana_client::connection_cache::ConnectionCache,
     solana_runtime::{
@@ -170,6 +171,47 @@ fn verify_transaction(
     Ok(())
 }
 
+fn process_transaction_with_inner_instructions(
+    bank: &Bank,
+    transaction: VersionedTransaction,
+) -> BanksTransactionResultWithInnerInstructions {
+    match bank.process_transaction_with_metadata(transaction) {
+        TransactionExecutionResult::NotExecuted(error) => {
+            BanksTransactionResultWithInnerInstructions {
+                result: Err(error),
+                metadata: None,
+                inner_instructions: None,
+            }
+        }
+        TransactionExecutionResult::Executed { details, .. } => {
+            BanksTransactionResultWithInnerInstructions {
+                result: details.status,
+                metadata: Some(TransactionMetadata {
+                    compute_units_consumed: details.executed_units,
+                    log_messages: details.log_messages.unwrap_or_default(),
+                    return_data: details.return_data,
+                }),
+                inner_instructions: Some(
+                    details
+                        .inner_instructions
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|inner_instructions| {
+                            inner_instructions
+                                .into_iter()
+                                .map(|inner_instruction| InnerInstruction {
+                                    instruction: inner_instruction.instruction,
+                                    stack_height: inner_instruction.stack_height,
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                ),
+            }
+        }
+    }
+}
+
 fn simulate_transaction(
     bank: &Bank,
     transaction: VersionedTransaction,
@@ -364,22 +406,19 @@ impl Banks for BanksServer {
         transaction: VersionedTransaction,
     ) -> BanksTransactionResultWithMetadata {
         let bank = self.bank_forks.read().unwrap().working_bank();
-        match bank.process_transaction_with_metadata(transaction) {
-            TransactionExecutionResult::NotExecuted(error) => BanksTransactionResultWithMetadata {
-                result: Err(error),
-                metadata: None,
-            },
-            TransactionExecutionResult::Executed { details, .. } => {
-                BanksTransactionResultWithMetadata {
-                    result: details.status,
-                    metadata: Some(TransactionMetadata {
-                        compute_units_consumed: details.executed_units,
-                        log_messages: details.log_messages.unwrap_or_default(),
-                        return_data: details.return_data,
-                    }),
-                }
-            }
-        }
+        let BanksTransactionResultWithInnerInstructions {
+            result, metadata, ..
+        } = process_transaction_with_inner_instructions(&bank, transaction);
+        BanksTransactionResultWithMetadata { result, metadata }
+    }
+
+    async fn process_transaction_with_inner_instructions_and_context(
+        self,
+        _: Context,
+        transaction: VersionedTransaction,
+    ) -> BanksTransactionResultWithInnerInstructions {
+        let bank = self.bank_forks.read().unwrap().working_bank();
+        process_transaction_with_inner_instructions(&bank, transaction)
     }
 
     async fn get_account_with_commitment_and_context(
@@ -0,0 +1,305 @@
+//! Signed request authentication of registry requests.
+//!
+//! Every request that modifies the registry carries a token in its `Authorization` header, of
+//! the form `<PUBKEY>:<TIMESTAMP>:<NONCE>:<SIGNATURE>`. `SIGNATURE` is the off-chain message
+//! signature by `PUBKEY` of `solana-cargo-registry:<SERVER_URL>:<OPERATION>:<TIMESTAMP>:<NONCE>`,
+//! where `OPERATION` describes the request, e.g. `yank:foo:0.1.0` or
+//! `owners:foo:add:<PUBKEY>,<PUBKEY>`. The registry accepts a token
+//! once, and only within `TOKEN_VALIDITY` of its timestamp, so a token can't be replayed or
+//! reused for another request. Tokens are generated for each request by the registry's cargo
+//! credential provider, see `credential_provider`. The authenticated pubkey is then checked
+//! against the owners of the crate.
+use {
+    crate::publisher::Error,
+    solana_sdk::{
+        offchain_message::OffchainMessage,
+        pubkey::Pubkey,
+        signature::{Signature, Signer},
+        timing::timestamp,
+    },
+    std::{collections::HashMap, fmt, str::FromStr, sync::Mutex, time::Duration},
+};
+
+const AUTH_MESSAGE_PREFIX: &str = "solana-cargo-registry:";
+/// How far the timestamp of a token may be from the registry's clock
+const TOKEN_VALIDITY: Duration = Duration::from_secs(5 * 60);
+
+/// The registry operation a token authorizes
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Operation<'a> {
+    Publish {
+        name: &'a str,
+        vers: &'a str,
+        cksum: &'a str,
+    },
+    Yank {
+        name: &'a str,
+        vers: &'a str,
+    },
+    Unyank {
+        name: &'a str,
+        vers: &'a str,
+    },
+    Owners {
+        name: &'a str,
+        add: bool,
+        users: &'a [Pubkey],
+    },
+}
+
+impl fmt::Display for Operation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Publish { name, vers, cksum } => write!(f, "publish:{name}:{vers}:{cksum}"),
+            Operation::Yank { name, vers } => write!(f, "yank:{name}:{vers}"),
+            Operation::Unyank { name, vers } => write!(f, "unyank:{name}:{vers}"),
+            Operation::Owners { name, add, users } => {
+                let method = if *add { "add" } else { "remove" };
+                // The users are sorted so that the token doesn't depend on their order
+                let mut users: Vec<_> = users.iter().map(Pubkey::to_string).collect();
+                users.sort();
+                write!(f, "owners:{name}:{method}:{}", users.join(","))
+            }
+        }
+    }
+}
+
+fn auth_message(server_url: &str, operation: &Operation, timestamp: u64, nonce: u64) -> String {
+    format!("{AUTH_MESSAGE_PREFIX}{server_url}:{operation}:{timestamp}:{nonce}")
+}
+
+/// Creates the token authorizing `operation` on the registry at `server_url`
+pub(crate) fn make_token(
+    signer: &dyn Signer,
+    server_url: &str,
+    operation: &Operation,
+    timestamp: u64,
+    nonce: u64,
+) -> Result<String, Error> {
+    let signature = OffchainMessage::new(
+        0,
+        auth_message(server_url, operation, timestamp, nonce).as_bytes(),
+    )
+    .and_then(|message| message.sign(signer))
+    .map_err(|e| format!("Failed to sign the authentication message: {e}"))?;
+    Ok(format!(
+        "{}:{timestamp}:{nonce}:{signature}",
+        signer.pubkey()
+    ))
+}
+
+/// Returns the token of the request, before its body is consumed
+pub(crate) fn request_token(request: &hyper::Request<hyper::Body>) -> Result<String, Error> {
+    Ok(request
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .ok_or("Missing authorization token. Please configure the registry's credential provider")?
+        .to_str()
+        .map_err(|_| "Invalid authorization token")?
+        .trim()
+        .to_string())
+}
+
+pub(crate) struct Authenticator {
+    server_url: String,
+    /// Nonces of the tokens accepted within `TOKEN_VALIDITY`, and their timestamp
+    used_nonces: Mutex<HashMap<(Pubkey, u64), u64>>,
+}
+
+impl Authenticator {
+    pub(crate) fn new(server_url: &str) -> Self {
+        Self {
+            server_url: server_url.to_string(),
+            used_nonces: Mutex::default(),
+        }
+    }
+
+    /// Verifies that the token authorizes `operation`, and returns the pubkey that signed it
+    pub(crate) fn verify_token(
+        &self,
+        token: &str,
+        operation: &Operation,
+        now: u64,
+    ) -> Result<Pubkey, Error> {
+        let mut parts = token.splitn(4, ':');
+        let (Some(pubkey), Some(token_timestamp), Some(nonce), Some(signature)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(
+                "Malformed token. Expected <PUBKEY>:<TIMESTAMP>:<NONCE>:<SIGNATURE>".into(),
+            );
+        };
+        let pubkey = Pubkey::from_str(pubkey).map_err(|_| "Invalid pubkey in the token")?;
+        let token_timestamp =
+            u64::from_str(token_timestamp).map_err(|_| "Invalid timestamp in the token")?;
+        let nonce = u64::from_str(nonce).map_err(|_| "Invalid nonce in the token")?;
+        let signature =
+            Signature::from_str(signature).map_err(|_| "Invalid signature in the token")?;
+
+        let validity = TOKEN_VALIDITY.as_millis() as u64;
+        if token_timestamp.abs_diff(now) > validity {
+            return Err("The token expired. Please check the clock of this machine".into());
+        }
+        let message = OffchainMessage::new(
+            0,
+            auth_message(&self.server_url, operation, token_timestamp, nonce).as_bytes(),
+        )
+        .map_err(|e| format!("Failed to create the authentication message: {e}"))?;
+        if !message
+            .verify(&pubkey, &signature)
+            .map_err(|e| format!("Failed to verify the token: {e}"))?
+        {
+            return Err("Token signature verification failed".into());
+        }
+
+        let mut used_nonces = self
+            .used_nonces
+            .lock()
+            .map_err(|e| format!("Failed to lock the used nonces: {e}"))?;
+        // Expired tokens are rejected above, so their nonces don't need to be remembered
+        used_nonces.retain(|_, used_timestamp| now.abs_diff(*used_timestamp) <= validity);
+        if used_nonces
+            .insert((pubkey, nonce), token_timestamp)
+            .is_some()
+        {
+            return Err("The token was already used".into());
+        }
+        Ok(pubkey)
+    }
+
+    pub(crate) fn authenticate(
+        &self,
+        request: &hyper::Request<hyper::Body>,
+        operation: &Operation,
+    ) -> Result<Pubkey, Error> {
+        self.verify_token(&request_token(request)?, operation, timestamp())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, solana_sdk::signature::Keypair};
+
+    const SERVER_URL: &str = "http://0.0.0.0:8080";
+    const YANK: Operation = Operation::Yank {
+        name: "foo",
+        vers: "0.1.0",
+    };
+
+    #[test]
+    fn test_verify_token() {
+        let keypair = Keypair::new();
+        let authenticator = Authenticator::new(SERVER_URL);
+        let now = timestamp();
+        let token = make_token(&keypair, SERVER_URL, &YANK, now, 1).unwrap();
+        assert_eq!(
+            authenticator.verify_token(&token, &YANK, now).unwrap(),
+            keypair.pubkey()
+        );
+
+        // Tokens can't be replayed
+        assert!(authenticator.verify_token(&token, &YANK, now).is_err());
+
+        // Tokens are bound to the operation and the registry that they were created for
+        let token = make_token(&keypair, SERVER_URL, &YANK, now, 2).unwrap();
+        let unyank = Operation::Unyank {
+            name: "foo",
+            vers: "0.1.0",
+        };
+        assert!(authenticator.verify_token(&token, &unyank, now).is_err());
+        let other_authenticator = Authenticator::new("http://example.com");
+        assert!(other_authenticator
+            .verify_token(&token, &YANK, now)
+            .is_err());
+
+        // Owner tokens are bound to the method and the users, but not to their order
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let add_owners = Operation::Owners {
+            name: "foo",
+            add: true,
+            users: &[alice, bob],
+        };
+        let token = make_token(&keypair, SERVER_URL, &add_owners, now, 5).unwrap();
+        for operation in [
+            Operation::Owners {
+                name: "foo",
+                add: false,
+                users: &[alice, bob],
+            },
+            Operation::Owners {
+                name: "foo",
+                add: true,
+                users: &[alice],
+            },
+        ] {
+            assert!(authenticator.verify_token(&token, &operation, now).is_err());
+        }
+        let reordered = Operation::Owners {
+            name: "foo",
+            add: true,
+            users: &[bob, alice],
+        };
+        assert_eq!(
+            authenticator.verify_token(&token, &reordered, now).unwrap(),
+            keypair.pubkey()
+        );
+
+        // Tokens expire
+        let validity = TOKEN_VALIDITY.as_millis() as u64;
+        let token = make_token(&keypair, SERVER_URL, &YANK, now, 3).unwrap();
+        assert!(authenticator
+            .verify_token(&token, &YANK, now + validity + 1)
+            .is_err());
+        let token = make_token(&keypair, SERVER_URL, &YANK, now + validity + 1, 3).unwrap();
+        assert!(authenticator.verify_token(&token, &YANK, now).is_err());
+
+        // The pubkey must match the signer
+        let token = make_token(&keypair, SERVER_URL, &YANK, now, 4).unwrap();
+        let other = Keypair::new();
+        let (_, rest) = token.split_once(':').unwrap();
+        assert!(authenticator
+            .verify_token(&format!("{}:{}", other.pubkey(), rest), &YANK, now)
+            .is_err());
+
+        assert!(authenticator.verify_token("", &YANK, now).is_err());
+        assert!(authenticator
+            .verify_token(&keypair.pubkey().to_string(), &YANK, now)
+            .is_err());
+    }
+
+    #[test]
+    fn test_used_nonces_expire() {
+        let keypair = Keypair::new();
+        let authenticator = Authenticator::new(SERVER_URL);
+        let now = timestamp();
+        let token = make_token(&keypair, SERVER_URL, &YANK, now, 1).unwrap();
+        authenticator.verify_token(&token, &YANK, now).unwrap();
+        let later = now + TOKEN_VALIDITY.as_millis() as u64 * 2;
+        let token = make_token(&keypair, SERVER_URL, &YANK, later, 2).unwrap();
+        authenticator.verify_token(&token, &YANK, later).unwrap();
+        assert_eq!(authenticator.used_nonces.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let keypair = Keypair::new();
+        let authenticator = Authenticator::new(SERVER_URL);
+
+        let request = hyper::Request::builder()
+            .body(hyper::Body::empty())
+            .unwrap();
+        assert!(authenticator.authenticate(&request, &YANK).is_err());
+
+        let request = hyper::Request::builder()
+            .header(
+                hyper::header::AUTHORIZATION,
+                make_token(&keypair, SERVER_URL, &YANK, timestamp(), 1).unwrap(),
+            )
+            .body(hyper::Body::empty())
+            .unwrap();
+        assert_eq!(
+            authenticator.authenticate(&request, &YANK).unwrap(),
+            keypair.pubkey()
+        );
+    }
+}
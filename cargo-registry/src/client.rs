@@ -14,9 +14,10 @@ use {
     solana_rpc_client::rpc_client::RpcClient,
     solana_sdk::{
         commitment_config,
-        signature::{read_keypair_file, Keypair},
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
     },
-    std::{error, sync::Arc, time::Duration},
+    std::{error, path::PathBuf, sync::Arc, time::Duration},
 };
 
 pub struct ClientConfig<'a>(pub ProgramV4CommandConfig<'a>);
@@ -38,14 +39,19 @@ pub struct Client {
     pub rpc_client: Arc<RpcClient>,
     pub port: u16,
     pub server_url: String,
+    pub state_file: Option<PathBuf>,
     websocket_url: String,
-    commitment: commitment_config::CommitmentConfig,
+    pub commitment: commitment_config::CommitmentConfig,
     cli_signers: Vec<Keypair>,
     authority_signer_index: SignerIndex,
 }
 
 impl Client {
-    fn get_keypair(
+    pub fn authority_pubkey(&self) -> Pubkey {
+        self.cli_signers[self.authority_signer_index].pubkey()
+    }
+
+    pub(crate) fn get_keypair(
         matches: &ArgMatches<'_>,
         config_path: &str,
         name: &str,
@@ -125,6 +131,17 @@ impl Client {
                         "URL where the registry service will be hosted. Default: http://0.0.0.0:<port>",
                     ),
             )
+            .arg(
+                Arg::with_name("state_file")
+                    .long("state-file")
+                    .value_name("FILEPATH")
+                    .takes_value(true)
+                    .global(true)
+                    .help(
+                        "File where the registry index, including the crate owners and yanked \
+                        versions, is persisted across restarts. [default: kept in memory only]",
+                    ),
+            )
             .arg(
                 Arg::with_name("commitment")
                     .long("commitment")
@@ -217,6 +234,7 @@ impl Client {
             )),
             port,
             server_url,
+            state_file: matches.value_of("state_file").map(PathBuf::from),
             websocket_url,
             commitment,
             cli_signers: vec![payer_keypair, authority_keypair],
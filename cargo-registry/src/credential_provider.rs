@@ -0,0 +1,294 @@
+//! Cargo credential provider generating the signed tokens of the registry.
+//!
+//! Configure it for the registry in `.cargo/config.toml`, e.g.
+//! `credential-provider = ["solana-cargo-registry", "--keypair", "<KEYPAIR>"]`. Cargo then runs
+//! `solana-cargo-registry --keypair <KEYPAIR> --cargo-plugin` and asks it for a token for each
+//! operation, following https://doc.rust-lang.org/cargo/reference/credential-provider-protocol.html
+//!
+//! Cargo doesn't tell credential providers which owners `cargo owner` adds or removes, so the
+//! change has to be repeated in `SOLANA_CARGO_REGISTRY_OWNERS`, e.g.
+//! `SOLANA_CARGO_REGISTRY_OWNERS=add:<PUBKEY> cargo owner --add <PUBKEY> foo`.
+use {
+    crate::{
+        auth::{self, Operation},
+        client::Client,
+        publisher::Error,
+    },
+    clap::{crate_description, crate_name, App, Arg},
+    serde_json::{json, Value},
+    solana_cli_config::{Config, CONFIG_FILE},
+    solana_sdk::{pubkey::Pubkey, signature::Keypair, timing::timestamp},
+    std::{
+        io::{self, BufRead, Write},
+        str::FromStr,
+    },
+};
+
+const CARGO_PLUGIN_ARG: &str = "--cargo-plugin";
+const PROTOCOL_VERSION: u64 = 1;
+const OWNERS_ENV_VAR: &str = "SOLANA_CARGO_REGISTRY_OWNERS";
+
+/// The owner change that an `owners` token authorizes: whether the users are added, and the
+/// users
+type OwnersChange = (bool, Vec<Pubkey>);
+
+pub(crate) fn is_cargo_plugin() -> bool {
+    std::env::args().any(|arg| arg == CARGO_PLUGIN_ARG)
+}
+
+fn get_keypair() -> Result<Keypair, Box<dyn std::error::Error>> {
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(solana_version::version!())
+        .arg(
+            Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("FILEPATH")
+                .takes_value(true)
+                .help("Configuration file to use"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .short("k")
+                .long("keypair")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .help("Filepath of the keypair signing the registry requests"),
+        )
+        .arg(
+            Arg::with_name("cargo_plugin")
+                .long("cargo-plugin")
+                .help("Run as a cargo credential provider"),
+        )
+        .get_matches();
+
+    let config = match matches.value_of("config_file").or(CONFIG_FILE.as_deref()) {
+        Some(config_file) => Config::load(config_file).unwrap_or_default(),
+        None => Config::default(),
+    };
+    Client::get_keypair(&matches, &config.keypair_path, "keypair")
+}
+
+/// The server URL of the registry, which the tokens are bound to
+fn server_url(index_url: &str) -> &str {
+    let url = index_url.strip_prefix("sparse+").unwrap_or(index_url);
+    let url = url.strip_suffix('/').unwrap_or(url);
+    url.strip_suffix("/index").unwrap_or(url)
+}
+
+fn str_field<'a>(request: &'a Value, field: &str) -> Result<&'a str, Error> {
+    request[field]
+        .as_str()
+        .ok_or_else(|| format!("Missing {} in the credential request", field).into())
+}
+
+/// Parses an owner change of the form `add:<PUBKEY>,<PUBKEY>` or `remove:<PUBKEY>,<PUBKEY>`
+fn parse_owners_change(value: &str) -> Result<OwnersChange, Error> {
+    let invalid =
+        || format!("Invalid {OWNERS_ENV_VAR}. Expected add:<PUBKEY>,... or remove:<PUBKEY>,...");
+    let (method, users) = value.split_once(':').ok_or_else(invalid)?;
+    let add = match method {
+        "add" => true,
+        "remove" => false,
+        _ => return Err(invalid().into()),
+    };
+    let users = users
+        .split(',')
+        .map(|user| Pubkey::from_str(user.trim()).map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((add, users))
+}
+
+fn operation<'a>(
+    request: &'a Value,
+    owners_change: Option<&'a OwnersChange>,
+) -> Result<Option<Operation<'a>>, Error> {
+    let name = || str_field(request, "name");
+    let vers = || str_field(request, "vers");
+    Ok(Some(match str_field(request, "operation")? {
+        "publish" => Operation::Publish {
+            name: name()?,
+            vers: vers()?,
+            cksum: str_field(request, "cksum")?,
+        },
+        "yank" => Operation::Yank {
+            name: name()?,
+            vers: vers()?,
+        },
+        "unyank" => Operation::Unyank {
+            name: name()?,
+            vers: vers()?,
+        },
+        "owners" => {
+            let (add, users) = owners_change.ok_or_else(|| {
+                format!("Set {OWNERS_ENV_VAR} to the owner change to authorize, e.g. add:<PUBKEY>")
+            })?;
+            Operation::Owners {
+                name: name()?,
+                add: *add,
+                users,
+            }
+        }
+        // The index and the downloads don't require authentication
+        _ => return Ok(None),
+    }))
+}
+
+fn handle_request(
+    keypair: &Keypair,
+    request: &Value,
+    owners_change: Option<&OwnersChange>,
+) -> Result<Value, Error> {
+    if request["v"].as_u64() != Some(PROTOCOL_VERSION) {
+        return Err(format!("Unsupported credential request version: {}", request["v"]).into());
+    }
+    Ok(match str_field(request, "kind")? {
+        "get" => {
+            let Some(operation) = operation(request, owners_change)? else {
+                return Ok(json!({ "Err": { "kind": "operation-not-supported" } }));
+            };
+            let index_url = request["registry"]["index-url"]
+                .as_str()
+                .ok_or("Missing index-url in the credential request")?;
+            let token = auth::make_token(
+                keypair,
+                server_url(index_url),
+                &operation,
+                timestamp(),
+                rand::random(),
+            )?;
+            json!({
+                "Ok": {
+                    "kind": "get",
+                    "token": token,
+                    // Every token is single use
+                    "cache": "never",
+                    "operation_independent": false,
+                }
+            })
+        }
+        // There is no token to store, the keypair signs every request
+        "login" => json!({ "Ok": { "kind": "login" } }),
+        "logout" => json!({ "Ok": { "kind": "logout" } }),
+        _ => json!({ "Err": { "kind": "operation-not-supported" } }),
+    })
+}
+
+pub(crate) fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let keypair = get_keypair()?;
+    let owners_change = std::env::var(OWNERS_ENV_VAR)
+        .ok()
+        .map(|value| parse_owners_change(&value))
+        .transpose()?;
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", json!({ "v": [PROTOCOL_VERSION] }))?;
+    stdout.flush()?;
+
+    for line in io::stdin().lock().lines() {
+        let request: Value = serde_json::from_str(&line?)?;
+        let response = handle_request(&keypair, &request, owners_change.as_ref())
+            .unwrap_or_else(|e| json!({ "Err": { "kind": "other", "message": e.to_string() } }));
+        writeln!(stdout, "{}", response)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::auth::Authenticator, solana_sdk::signature::Signer};
+
+    #[test]
+    fn test_server_url() {
+        assert_eq!(
+            server_url("sparse+http://0.0.0.0:8080/index/"),
+            "http://0.0.0.0:8080"
+        );
+        assert_eq!(
+            server_url("sparse+http://0.0.0.0:8080/index"),
+            "http://0.0.0.0:8080"
+        );
+        assert_eq!(server_url("http://0.0.0.0:8080"), "http://0.0.0.0:8080");
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let keypair = Keypair::new();
+        let authenticator = Authenticator::new("http://0.0.0.0:8080");
+        let request = json!({
+            "v": 1,
+            "registry": { "index-url": "sparse+http://0.0.0.0:8080/index/", "name": "solana" },
+            "kind": "get",
+            "operation": "yank",
+            "name": "foo",
+            "vers": "0.1.0",
+            "args": [],
+        });
+        let response = handle_request(&keypair, &request, owners_change.as_ref()).unwrap();
+        assert_eq!(response["Ok"]["cache"], "never");
+        let token = response["Ok"]["token"].as_str().unwrap();
+        let operation = Operation::Yank {
+            name: "foo",
+            vers: "0.1.0",
+        };
+        assert_eq!(
+            authenticator
+                .verify_token(token, &operation, timestamp())
+                .unwrap(),
+            keypair.pubkey()
+        );
+
+        // Each request gets a new token
+        let response = handle_request(&keypair, &request, owners_change.as_ref()).unwrap();
+        let token = response["Ok"]["token"].as_str().unwrap();
+        assert!(authenticator
+            .verify_token(token, &operation, timestamp())
+            .is_ok());
+
+        let mut read_request = request.clone();
+        read_request["operation"] = json!("read");
+        assert_eq!(
+            handle_request(&keypair, &read_request, None).unwrap()["Err"]["kind"],
+            "operation-not-supported"
+        );
+
+        let mut publish_request = request.clone();
+        publish_request["operation"] = json!("publish");
+        assert!(handle_request(&keypair, &publish_request, None).is_err());
+
+        // Owner tokens require the owner change
+        let mut owners_request = request;
+        owners_request["operation"] = json!("owners");
+        assert!(handle_request(&keypair, &owners_request, None).is_err());
+        let user = Pubkey::new_unique();
+        let owners_change = parse_owners_change(&format!("remove:{user}")).unwrap();
+        assert_eq!(owners_change, (false, vec![user]));
+        let response = handle_request(&keypair, &owners_request, Some(&owners_change)).unwrap();
+        let token = response["Ok"]["token"].as_str().unwrap();
+        let operation = Operation::Owners {
+            name: "foo",
+            add: false,
+            users: &[user],
+        };
+        assert_eq!(
+            authenticator
+                .verify_token(token, &operation, timestamp())
+                .unwrap(),
+            keypair.pubkey()
+        );
+    }
+
+    #[test]
+    fn test_parse_owners_change() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            parse_owners_change(&format!("add:{alice}, {bob}")).unwrap(),
+            (true, vec![alice, bob])
+        );
+        assert!(parse_owners_change(&alice.to_string()).is_err());
+        assert!(parse_owners_change(&format!("set:{alice}")).is_err());
+        assert!(parse_owners_change("add:").is_err());
+        assert!(parse_owners_change("add:foo").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 //! The `registry_service` module implements the Solana cargo registry service.
 use {
     crate::{
+        auth::{Authenticator, Operation},
         client::Client,
         publisher::{Error, Publisher},
         sparse_index::RegistryIndex,
@@ -11,13 +12,17 @@ use {
         Method, Server,
     },
     log::*,
+    solana_sdk::{pubkey::Pubkey, timing::timestamp},
     std::{
         net::{IpAddr, Ipv4Addr, SocketAddr},
+        str::FromStr,
         sync::Arc,
     },
 };
 
+mod auth;
 mod client;
+mod credential_provider;
 mod publisher;
 
 mod response_builder;
@@ -32,14 +37,19 @@ impl CargoRegistryService {
         request: hyper::Request<hyper::Body>,
         client: Arc<Client>,
         index: Arc<RegistryIndex>,
+        authenticator: Arc<Authenticator>,
     ) -> hyper::Response<hyper::Body> {
         info!("Handling request to publish the crate");
+        let token = match auth::request_token(&request) {
+            Ok(token) => token,
+            Err(e) => return Self::unauthorized_response(e),
+        };
         let bytes = body::to_bytes(request.into_body()).await;
 
         match bytes {
             Ok(data) => {
                 let Ok(result) = tokio::task::spawn_blocking(move || {
-                    Publisher::publish_crate(data, client, index)
+                    Publisher::publish_crate(data, client, index, authenticator, token)
                 })
                 .await
                 else {
@@ -66,6 +76,17 @@ impl CargoRegistryService {
         }
     }
 
+    fn unauthorized_response(e: Error) -> hyper::Response<hyper::Body> {
+        response_builder::error_response(
+            hyper::StatusCode::UNAUTHORIZED,
+            format!("Failed to authenticate the request. {}", e).as_str(),
+        )
+    }
+
+    fn ok_response() -> hyper::Response<hyper::Body> {
+        response_builder::success_response_str(&serde_json::json!({ "ok": true }).to_string())
+    }
+
     fn get_crate_name_and_version(path: &str) -> Option<(&str, &str, &str)> {
         path.rsplit_once('/').and_then(|(remainder, version)| {
             remainder
@@ -76,9 +97,12 @@ impl CargoRegistryService {
 
     fn handle_yank_request(
         path: &str,
-        _request: &hyper::Request<hyper::Body>,
+        request: &hyper::Request<hyper::Body>,
+        authenticator: &Authenticator,
+        index: &RegistryIndex,
+        yanked: bool,
     ) -> hyper::Response<hyper::Body> {
-        let Some((path, _crate_name, _version)) = Self::get_crate_name_and_version(path) else {
+        let Some((path, crate_name, version)) = Self::get_crate_name_and_version(path) else {
             return response_builder::error_response(
                 hyper::StatusCode::BAD_REQUEST,
                 "Failed to parse the request.",
@@ -92,34 +116,29 @@ impl CargoRegistryService {
             );
         }
 
-        response_builder::error_response(
-            hyper::StatusCode::NOT_IMPLEMENTED,
-            "This command is not implemented yet",
-        )
-    }
-
-    fn handle_unyank_request(
-        path: &str,
-        _request: &hyper::Request<hyper::Body>,
-    ) -> hyper::Response<hyper::Body> {
-        let Some((path, _crate_name, _version)) = Self::get_crate_name_and_version(path) else {
-            return response_builder::error_response(
-                hyper::StatusCode::BAD_REQUEST,
-                "Failed to parse the request.",
-            );
+        let operation = if yanked {
+            Operation::Yank {
+                name: crate_name,
+                vers: version,
+            }
+        } else {
+            Operation::Unyank {
+                name: crate_name,
+                vers: version,
+            }
+        };
+        let requester = match authenticator.authenticate(request, &operation) {
+            Ok(requester) => requester,
+            Err(e) => return Self::unauthorized_response(e),
         };
 
-        if path.len() != PATH_PREFIX.len() {
-            return response_builder::error_response(
+        match index.set_yanked(crate_name, version, yanked, &requester) {
+            Ok(()) => Self::ok_response(),
+            Err(e) => response_builder::error_response(
                 hyper::StatusCode::BAD_REQUEST,
-                "Request length is incorrect",
-            );
+                format!("Failed to update the crate. {}", e).as_str(),
+            ),
         }
-
-        response_builder::error_response(
-            hyper::StatusCode::NOT_IMPLEMENTED,
-            "This command is not implemented yet",
-        )
     }
 
     fn get_crate_name(path: &str) -> Option<(&str, &str)> {
@@ -129,8 +148,9 @@ impl CargoRegistryService {
     fn handle_get_owners_request(
         path: &str,
         _request: &hyper::Request<hyper::Body>,
+        index: &RegistryIndex,
     ) -> hyper::Response<hyper::Body> {
-        let Some((path, _crate_name)) = Self::get_crate_name(path) else {
+        let Some((path, crate_name)) = Self::get_crate_name(path) else {
             return response_builder::error_response(
                 hyper::StatusCode::BAD_REQUEST,
                 "Failed to parse the request.",
@@ -144,17 +164,40 @@ impl CargoRegistryService {
             );
         }
 
-        response_builder::error_response(
-            hyper::StatusCode::NOT_IMPLEMENTED,
-            "This command is not implemented yet",
-        )
+        let owners = match index.get_owners(crate_name) {
+            Ok(owners) => owners,
+            Err(e) => {
+                return response_builder::error_response(
+                    hyper::StatusCode::NOT_FOUND,
+                    e.to_string().as_str(),
+                )
+            }
+        };
+
+        // Cargo identifies the owners by their login, which for this registry is the
+        // owner's pubkey. The first owner is always the upgrade authority of the program.
+        let users: Vec<_> = owners
+            .iter()
+            .enumerate()
+            .map(|(id, owner)| {
+                serde_json::json!({
+                    "id": id,
+                    "login": owner.to_string(),
+                    "name": (id == 0).then_some("program upgrade authority"),
+                })
+            })
+            .collect();
+        response_builder::success_response_str(&serde_json::json!({ "users": users }).to_string())
     }
 
-    fn handle_add_owners_request(
+    async fn handle_modify_owners_request(
         path: &str,
-        _request: &hyper::Request<hyper::Body>,
+        request: hyper::Request<hyper::Body>,
+        authenticator: &Authenticator,
+        index: &RegistryIndex,
+        add: bool,
     ) -> hyper::Response<hyper::Body> {
-        let Some((path, _crate_name)) = Self::get_crate_name(path) else {
+        let Some((path, crate_name)) = Self::get_crate_name(path) else {
             return response_builder::error_response(
                 hyper::StatusCode::BAD_REQUEST,
                 "Failed to parse the request.",
@@ -168,34 +211,61 @@ impl CargoRegistryService {
             );
         }
 
-        response_builder::error_response(
-            hyper::StatusCode::NOT_IMPLEMENTED,
-            "This command is not implemented yet",
-        )
-    }
+        let token = match auth::request_token(&request) {
+            Ok(token) => token,
+            Err(e) => return Self::unauthorized_response(e),
+        };
 
-    fn handle_delete_owners_request(
-        path: &str,
-        _request: &hyper::Request<hyper::Body>,
-    ) -> hyper::Response<hyper::Body> {
-        let Some((path, _crate_name)) = Self::get_crate_name(path) else {
+        #[derive(serde::Deserialize)]
+        struct OwnersRequest {
+            users: Vec<String>,
+        }
+
+        let Ok(bytes) = body::to_bytes(request.into_body()).await else {
             return response_builder::error_response(
                 hyper::StatusCode::BAD_REQUEST,
-                "Failed to parse the request.",
+                "Failed to receive the request data from the client.",
             );
         };
-
-        if path.len() != PATH_PREFIX.len() {
+        let Ok(owners) = serde_json::from_slice::<OwnersRequest>(&bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|request| {
+                request
+                    .users
+                    .iter()
+                    .map(|user| Pubkey::from_str(user).map_err(|e| e.to_string()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+        else {
             return response_builder::error_response(
                 hyper::StatusCode::BAD_REQUEST,
-                "Request length is incorrect",
+                "Failed to parse the owners. Owners must be given as pubkeys.",
             );
-        }
+        };
 
-        response_builder::error_response(
-            hyper::StatusCode::NOT_IMPLEMENTED,
-            "This command is not implemented yet",
-        )
+        // The token is bound to the owner change, so it's verified once the body is parsed
+        let operation = Operation::Owners {
+            name: crate_name,
+            add,
+            users: &owners,
+        };
+        let requester = match authenticator.verify_token(&token, &operation, timestamp()) {
+            Ok(requester) => requester,
+            Err(e) => return Self::unauthorized_response(e),
+        };
+
+        let result = if add {
+            index.add_owners(crate_name, &owners, &requester)
+        } else {
+            index.remove_owners(crate_name, &owners, &requester)
+        };
+        match result {
+            Ok(()) => Self::ok_response(),
+            Err(e) => response_builder::error_response(
+                hyper::StatusCode::BAD_REQUEST,
+                format!("Failed to update the crate owners. {}", e).as_str(),
+            ),
+        }
     }
 
     fn handle_get_crates_request(
@@ -224,6 +294,7 @@ impl CargoRegistryService {
         index: Arc<sparse_index::RegistryIndex>,
         request: hyper::Request<hyper::Body>,
         client: Arc<Client>,
+        authenticator: Arc<Authenticator>,
     ) -> Result<hyper::Response<hyper::Body>, Error> {
         let path = request.uri().path();
         if path.starts_with("/git") {
@@ -260,11 +331,18 @@ impl CargoRegistryService {
                             "Invalid length of the request.",
                         )
                     } else {
-                        Self::handle_publish_request(request, client.clone(), index.clone()).await
+                        Self::handle_publish_request(request, client, index, authenticator).await
                     }
                 }
-                "unyank" => Self::handle_unyank_request(path, &request),
-                "owners" => Self::handle_add_owners_request(path, &request),
+                "unyank" => {
+                    Self::handle_yank_request(path, &request, &authenticator, &index, false)
+                }
+                "owners" => {
+                    // The request body is consumed by the handler, so the path can't borrow from it
+                    let path = path.to_string();
+                    Self::handle_modify_owners_request(&path, request, &authenticator, &index, true)
+                        .await
+                }
                 _ => response_builder::error_response(
                     hyper::StatusCode::METHOD_NOT_ALLOWED,
                     "Unknown request",
@@ -272,15 +350,26 @@ impl CargoRegistryService {
             },
             Method::GET => match endpoint {
                 "crates" => Self::handle_get_crates_request(path, &request),
-                "owners" => Self::handle_get_owners_request(path, &request),
+                "owners" => Self::handle_get_owners_request(path, &request, &index),
                 _ => response_builder::error_response(
                     hyper::StatusCode::METHOD_NOT_ALLOWED,
                     "Unknown request",
                 ),
             },
             Method::DELETE => match endpoint {
-                "yank" => Self::handle_yank_request(path, &request),
-                "owners" => Self::handle_delete_owners_request(path, &request),
+                "yank" => Self::handle_yank_request(path, &request, &authenticator, &index, true),
+                "owners" => {
+                    // The request body is consumed by the handler, so the path can't borrow from it
+                    let path = path.to_string();
+                    Self::handle_modify_owners_request(
+                        &path,
+                        request,
+                        &authenticator,
+                        &index,
+                        false,
+                    )
+                    .await
+                }
                 _ => response_builder::error_response(
                     hyper::StatusCode::METHOD_NOT_ALLOWED,
                     "Unknown request",
@@ -296,21 +385,49 @@ impl CargoRegistryService {
 
 #[tokio::main]
 async fn main() {
+    if credential_provider::is_cargo_plugin() {
+        // Cargo talks to credential providers over stdio, so nothing else may be printed
+        if let Err(e) = credential_provider::run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     solana_logger::setup_with_default("solana=info");
     let client = Arc::new(Client::new().expect("Failed to get RPC Client instance"));
 
     let bind_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), client.port);
-    let index = Arc::new(sparse_index::RegistryIndex::new(
-        "/index",
-        &client.server_url,
-    ));
+    let index = Arc::new(
+        sparse_index::RegistryIndex::new(
+            "/index",
+            &client.server_url,
+            client.authority_pubkey(),
+            client.state_file.clone(),
+        )
+        .expect("Failed to load the registry index"),
+    );
+    let authenticator = Arc::new(Authenticator::new(&client.server_url));
+    info!(
+        "Authenticate by configuring the registry's credential provider in cargo, e.g. \
+        `credential-provider = [\"{}\", \"--keypair\", \"<KEYPAIR>\"]`",
+        std::env::args()
+            .next()
+            .unwrap_or_else(|| "solana-cargo-registry".to_string())
+    );
 
     let registry_service = make_service_fn(move |_| {
         let client_inner = client.clone();
         let index = index.clone();
+        let authenticator = authenticator.clone();
         async move {
             Ok::<_, Error>(service_fn(move |request| {
-                CargoRegistryService::handler(index.clone(), request, client_inner.clone())
+                CargoRegistryService::handler(
+                    index.clone(),
+                    request,
+                    client_inner.clone(),
+                    authenticator.clone(),
+                )
             }))
         }
     });
@@ -1,5 +1,6 @@
 use {
     crate::{
+        auth::{Authenticator, Operation},
         client::{Client, ClientConfig},
         sparse_index::{IndexEntry, RegistryIndex},
     },
@@ -11,8 +12,10 @@ use {
     sha2::{Digest, Sha256},
     solana_cli::program_v4::{process_deploy_program, read_and_verify_elf},
     solana_sdk::{
+        pubkey::Pubkey,
         signature::{Keypair, Signer},
         signer::EncodableKey,
+        timing::timestamp,
     },
     std::{
         collections::BTreeMap,
@@ -111,10 +114,27 @@ impl Publisher {
         Ok(library_name.to_string())
     }
 
+    /// Returns the current upgrade authority of the program, or `None` if the program
+    /// isn't deployed yet
+    fn program_authority(client: &Client, program_id: &Pubkey) -> Result<Option<Pubkey>, Error> {
+        let Some(account) = client
+            .rpc_client
+            .get_account_with_commitment(program_id, client.commitment)?
+            .value
+        else {
+            return Ok(None);
+        };
+        let state = solana_loader_v4_program::get_state(&account.data)
+            .map_err(|e| format!("Failed to get the program state: {}", e))?;
+        Ok(Some(state.authority_address))
+    }
+
     pub(crate) fn publish_crate(
         bytes: Bytes,
         client: Arc<Client>,
         index: Arc<RegistryIndex>,
+        authenticator: Arc<Authenticator>,
+        token: String,
     ) -> Result<(), Error> {
         let (meta_data, offset) = PackageMetaData::new(&bytes)?;
 
@@ -123,6 +143,17 @@ impl Publisher {
         let crate_bytes = bytes.slice(offset.saturating_add(length_size)..);
         let crate_cksum = format!("{:x}", Sha256::digest(&crate_bytes));
 
+        // The token is bound to the published crate, so it's verified once the crate is parsed
+        let publisher = authenticator.verify_token(
+            &token,
+            &Operation::Publish {
+                name: &meta_data.name,
+                vers: &meta_data.vers,
+                cksum: &crate_cksum,
+            },
+            timestamp(),
+        )?;
+
         let decoder = GzDecoder::new(crate_bytes.as_ref());
         let mut archive = Archive::new(decoder);
 
@@ -148,6 +179,25 @@ impl Publisher {
         ))
         .map_err(|e| format!("Failed to get keypair from the file: {}", e))?;
 
+        let _publish_lock = index.lock_publish()?;
+        let program_authority = Self::program_authority(&client, &program_keypair.pubkey())?;
+        if let Some(program_authority) = program_authority {
+            if program_authority != client.authority_pubkey() {
+                return Err(format!(
+                    "The registry is not the upgrade authority of program {}. Current authority: {}",
+                    program_keypair.pubkey(),
+                    program_authority
+                )
+                .into());
+            }
+        }
+        index.check_publish(
+            &meta_data.name,
+            &meta_data.vers,
+            &publisher,
+            program_authority,
+        )?;
+
         info!("Deploying program at {:?}", program_keypair.pubkey());
 
         process_deploy_program(
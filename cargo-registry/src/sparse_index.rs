@@ -3,9 +3,16 @@ use {
         publisher::{Dependency, Error, PackageMetaData},
         response_builder,
     },
-    log::info,
+    log::{info, warn},
     serde::{Deserialize, Serialize},
-    std::{collections::BTreeMap, sync::RwLock},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        path::PathBuf,
+        str::FromStr,
+        sync::{Mutex, MutexGuard, RwLock},
+    },
 };
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -17,10 +24,34 @@ struct RegistryConfig {
 pub(crate) struct RegistryIndex {
     pub(crate) index_root: String,
     config: String,
-    index: RwLock<BTreeMap<String, IndexEntry>>,
+    /// Upgrade authority of the programs deployed by the registry. It is implicitly an
+    /// owner of every crate.
+    upgrade_authority: Pubkey,
+    index: RwLock<BTreeMap<String, CrateEntry>>,
+    /// Serializes the publishes, so that the checks of a publish still hold once its program
+    /// is deployed and its entry inserted
+    publish_lock: Mutex<()>,
+    /// File where the index is persisted, so that published versions, owners and yanked
+    /// versions survive restarts of the registry
+    state_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Default)]
+struct CrateEntry {
+    /// All published versions of the crate, in the order they were published
+    versions: Vec<IndexEntry>,
+    /// Pubkeys allowed to manage the crate, in addition to the upgrade authority. Only the
+    /// upgrade authority can publish new versions.
+    owners: BTreeSet<Pubkey>,
+}
+
+impl CrateEntry {
+    fn is_owner(&self, requester: &Pubkey, upgrade_authority: &Pubkey) -> bool {
+        requester == upgrade_authority || self.owners.contains(requester)
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct IndexEntryDep {
     pub name: String,
     pub req: String,
@@ -49,7 +80,7 @@ impl From<Dependency> for IndexEntryDep {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct IndexEntry {
     pub name: String,
     pub vers: String,
@@ -60,6 +91,38 @@ pub(crate) struct IndexEntry {
     pub links: Option<String>,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedCrateEntry {
+    versions: Vec<IndexEntry>,
+    owners: Vec<String>,
+}
+
+impl From<&CrateEntry> for PersistedCrateEntry {
+    fn from(crate_entry: &CrateEntry) -> Self {
+        Self {
+            versions: crate_entry.versions.clone(),
+            owners: crate_entry.owners.iter().map(Pubkey::to_string).collect(),
+        }
+    }
+}
+
+impl TryFrom<PersistedCrateEntry> for CrateEntry {
+    type Error = Error;
+
+    fn try_from(persisted: PersistedCrateEntry) -> Result<Self, Error> {
+        Ok(Self {
+            versions: persisted.versions,
+            owners: persisted
+                .owners
+                .iter()
+                .map(|owner| Pubkey::from_str(owner))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Invalid crate owner: {}", e))?,
+        })
+    }
+}
+
 impl From<PackageMetaData> for IndexEntry {
     fn from(v: PackageMetaData) -> Self {
         IndexEntry {
@@ -75,7 +138,12 @@ impl From<PackageMetaData> for IndexEntry {
 }
 
 impl RegistryIndex {
-    pub(crate) fn new(root: &str, server_url: &str) -> Self {
+    pub(crate) fn new(
+        root: &str,
+        server_url: &str,
+        upgrade_authority: Pubkey,
+        state_path: Option<PathBuf>,
+    ) -> Result<Self, Error> {
         let registry_config = RegistryConfig {
             dl: format!("{}/api/v1/crates", server_url),
             api: Some(server_url.to_string()),
@@ -83,12 +151,53 @@ impl RegistryIndex {
         let config =
             serde_json::to_string(&registry_config).expect("Failed to create registry config");
 
+        let index = match &state_path {
+            Some(path) if path.exists() => Self::load(path)?,
+            _ => BTreeMap::new(),
+        };
+
         info!("Registry index is available at {}{}/", server_url, root);
-        Self {
+        Ok(Self {
             index_root: root.to_string(),
             config,
-            index: RwLock::new(BTreeMap::new()),
-        }
+            upgrade_authority,
+            index: RwLock::new(index),
+            publish_lock: Mutex::default(),
+            state_path,
+        })
+    }
+
+    fn load(path: &PathBuf) -> Result<BTreeMap<String, CrateEntry>, Error> {
+        let file = fs::File::open(path)
+            .map_err(|e| format!("Failed to open the index state {:?}: {}", path, e))?;
+        let persisted: BTreeMap<String, PersistedCrateEntry> = serde_json::from_reader(file)
+            .map_err(|e| format!("Failed to parse the index state {:?}: {}", path, e))?;
+        info!("Loaded {} crates from {:?}", persisted.len(), path);
+        persisted
+            .into_iter()
+            .map(|(name, crate_entry)| Ok((name, crate_entry.try_into()?)))
+            .collect()
+    }
+
+    /// Writes the index to the state file, if any. The file is replaced atomically, so that
+    /// a crash can't leave a truncated state behind.
+    fn save(&self, index: &BTreeMap<String, CrateEntry>) -> Result<(), Error> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        let persisted: BTreeMap<_, PersistedCrateEntry> = index
+            .iter()
+            .map(|(name, crate_entry)| (name, crate_entry.into()))
+            .collect();
+        let tmp_path = path.with_extension("tmp");
+        let result = serde_json::to_vec(&persisted)
+            .map_err(|e| e.to_string())
+            .and_then(|data| fs::write(&tmp_path, data).map_err(|e| e.to_string()))
+            .and_then(|()| fs::rename(&tmp_path, path).map_err(|e| e.to_string()));
+        result.map_err(|e| {
+            warn!("Failed to write the index state {:?}: {}", path, e);
+            format!("Failed to persist the registry index: {}", e).into()
+        })
     }
 
     pub(crate) fn handler(
@@ -118,14 +227,160 @@ impl RegistryIndex {
         self.handle_crate_lookup_request(path)
     }
 
+    /// Locks publishing. The lock must be held from `check_publish` until the program is
+    /// deployed and `insert_entry` returns, otherwise two first publishes of the same crate
+    /// could both pass the checks.
+    pub(crate) fn lock_publish(&self) -> Result<MutexGuard<()>, Error> {
+        self.publish_lock
+            .lock()
+            .map_err(|e| format!("Failed to lock publishing: {}", e).into())
+    }
+
+    /// Checks that `publisher` may publish version `vers` of the crate `name`. Only the
+    /// upgrade authority of the crate's program can publish it: `program_authority` is the
+    /// program's current upgrade authority on chain, or `None` if the program isn't deployed
+    /// yet, in which case it's deployed with the registry's upgrade authority.
+    pub(crate) fn check_publish(
+        &self,
+        name: &str,
+        vers: &str,
+        publisher: &Pubkey,
+        program_authority: Option<Pubkey>,
+    ) -> Result<(), Error> {
+        let program_authority = program_authority.unwrap_or(self.upgrade_authority);
+        if *publisher != program_authority {
+            return Err(format!(
+                "{} is not the upgrade authority of the program for crate {}. Current authority: {}",
+                publisher, name, program_authority
+            )
+            .into());
+        }
+        let read_index = self
+            .index
+            .read()
+            .map_err(|e| format!("Failed to lock the index for reading: {}", e))?;
+        if read_index
+            .get(name)
+            .is_some_and(|crate_entry| crate_entry.versions.iter().any(|entry| entry.vers == vers))
+        {
+            return Err(format!("Crate {}-{} is already published", name, vers).into());
+        }
+        Ok(())
+    }
+
     pub(crate) fn insert_entry(&self, entry: IndexEntry) -> Result<(), Error> {
         let mut write_index = self
             .index
             .write()
             .map_err(|e| format!("Failed to lock the index for writing: {}", e))?;
         info!("Inserting {}-{} in registry index", entry.name, entry.vers);
-        write_index.insert(entry.name.clone(), entry);
-        Ok(())
+        let name = entry.name.clone();
+        let previous = write_index.get(&name).cloned();
+        let crate_entry = write_index.entry(name.clone()).or_default();
+        crate_entry.versions.retain(|v| v.vers != entry.vers);
+        crate_entry.versions.push(entry);
+        self.save(&write_index).map_err(|e| {
+            match previous {
+                Some(previous) => write_index.insert(name, previous),
+                None => write_index.remove(&name),
+            };
+            e
+        })
+    }
+
+    fn modify_crate_entry<F>(&self, name: &str, requester: &Pubkey, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut CrateEntry) -> Result<(), Error>,
+    {
+        let mut write_index = self
+            .index
+            .write()
+            .map_err(|e| format!("Failed to lock the index for writing: {}", e))?;
+        let crate_entry = write_index
+            .get_mut(name)
+            .ok_or_else(|| format!("Crate {} is not in the registry", name))?;
+        if !crate_entry.is_owner(requester, &self.upgrade_authority) {
+            return Err(format!("{} is not an owner of crate {}", requester, name).into());
+        }
+        // The change is only kept if it could be persisted
+        let mut modified = crate_entry.clone();
+        f(&mut modified)?;
+        let previous = std::mem::replace(crate_entry, modified);
+        self.save(&write_index).map_err(|e| {
+            write_index.insert(name.to_string(), previous);
+            e
+        })
+    }
+
+    pub(crate) fn set_yanked(
+        &self,
+        name: &str,
+        vers: &str,
+        yanked: bool,
+        requester: &Pubkey,
+    ) -> Result<(), Error> {
+        self.modify_crate_entry(name, requester, |crate_entry| {
+            let entry = crate_entry
+                .versions
+                .iter_mut()
+                .find(|entry| entry.vers == vers)
+                .ok_or_else(|| format!("Crate {}-{} is not in the registry", name, vers))?;
+            info!(
+                "{} {}-{} in registry index",
+                if yanked { "Yanking" } else { "Unyanking" },
+                name,
+                vers
+            );
+            entry.yanked = yanked;
+            Ok(())
+        })
+    }
+
+    /// Returns the owners of the crate, starting with the upgrade authority of its program
+    pub(crate) fn get_owners(&self, name: &str) -> Result<Vec<Pubkey>, Error> {
+        let read_index = self
+            .index
+            .read()
+            .map_err(|e| format!("Failed to lock the index for reading: {}", e))?;
+        let crate_entry = read_index
+            .get(name)
+            .ok_or_else(|| format!("Crate {} is not in the registry", name))?;
+        Ok(std::iter::once(self.upgrade_authority)
+            .chain(crate_entry.owners.iter().copied())
+            .collect())
+    }
+
+    pub(crate) fn add_owners(
+        &self,
+        name: &str,
+        owners: &[Pubkey],
+        requester: &Pubkey,
+    ) -> Result<(), Error> {
+        self.modify_crate_entry(name, requester, |crate_entry| {
+            crate_entry.owners.extend(
+                owners
+                    .iter()
+                    .filter(|owner| **owner != self.upgrade_authority),
+            );
+            Ok(())
+        })
+    }
+
+    pub(crate) fn remove_owners(
+        &self,
+        name: &str,
+        owners: &[Pubkey],
+        requester: &Pubkey,
+    ) -> Result<(), Error> {
+        self.modify_crate_entry(name, requester, |crate_entry| {
+            if owners.contains(&self.upgrade_authority) {
+                return Err("The upgrade authority of the program cannot be removed".into());
+            }
+            for owner in owners {
+                crate_entry.owners.remove(owner);
+            }
+            Ok(())
+        })
     }
 
     fn get_crate_name_from_path(path: &str) -> Option<&str> {
@@ -167,7 +422,7 @@ impl RegistryIndex {
             );
         };
 
-        let Some(entry) = read_index.get(crate_name) else {
+        let Some(crate_entry) = read_index.get(crate_name) else {
             // The index currently doesn't contain the program entry.
             // Fetch the program information from the network using RPC client.
             // In the meanwhile, return empty success response, so that the registry
@@ -175,14 +430,20 @@ impl RegistryIndex {
             return response_builder::success_response();
         };
 
-        let Ok(response) = serde_json::to_string(entry) else {
+        // The index file contains one line of JSON per published version
+        let Ok(lines) = crate_entry
+            .versions
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+        else {
             return response_builder::error_response(
                 hyper::StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal error. index entry is corrupted",
             );
         };
 
-        response_builder::success_response_str(response.as_str())
+        response_builder::success_response_str(lines.join("\n").as_str())
     }
 }
 
@@ -190,6 +451,173 @@ impl RegistryIndex {
 mod test {
     use super::*;
 
+    fn make_entry(name: &str, vers: &str) -> IndexEntry {
+        IndexEntry {
+            name: name.to_string(),
+            vers: vers.to_string(),
+            deps: vec![],
+            cksum: String::new(),
+            features: BTreeMap::new(),
+            yanked: false,
+            links: None,
+        }
+    }
+
+    #[test]
+    fn test_publish_and_owners() {
+        let upgrade_authority = Pubkey::new_unique();
+        let index =
+            RegistryIndex::new("/index", "http://0.0.0.0:8080", upgrade_authority, None).unwrap();
+        let other = Pubkey::new_unique();
+
+        // A new crate can only be published by the authority its program is deployed with
+        assert!(index.check_publish("foo", "0.1.0", &other, None).is_err());
+        index
+            .check_publish("foo", "0.1.0", &upgrade_authority, None)
+            .unwrap();
+        index.insert_entry(make_entry("foo", "0.1.0")).unwrap();
+        assert_eq!(index.get_owners("foo").unwrap(), vec![upgrade_authority]);
+        assert!(index.get_owners("bar").is_err());
+
+        // Versions can't be published twice
+        assert!(index
+            .check_publish("foo", "0.1.0", &upgrade_authority, Some(upgrade_authority))
+            .is_err());
+        assert!(index
+            .check_publish("foo", "0.2.0", &upgrade_authority, Some(upgrade_authority))
+            .is_ok());
+
+        // Only owners can manage the owners
+        assert!(index.add_owners("foo", &[other], &other).is_err());
+        index
+            .add_owners("foo", &[other], &upgrade_authority)
+            .unwrap();
+        assert_eq!(
+            index.get_owners("foo").unwrap(),
+            vec![upgrade_authority, other]
+        );
+
+        // The upgrade authority is always an owner
+        assert!(index
+            .remove_owners("foo", &[upgrade_authority], &other)
+            .is_err());
+        index.remove_owners("foo", &[other], &other).unwrap();
+        assert_eq!(index.get_owners("foo").unwrap(), vec![upgrade_authority]);
+    }
+
+    #[test]
+    fn test_publish_requires_program_authority() {
+        let upgrade_authority = Pubkey::new_unique();
+        let index =
+            RegistryIndex::new("/index", "http://0.0.0.0:8080", upgrade_authority, None).unwrap();
+        let program_authority = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        index.insert_entry(make_entry("foo", "0.1.0")).unwrap();
+        index
+            .add_owners("foo", &[owner], &upgrade_authority)
+            .unwrap();
+
+        // The signer must be the program's upgrade authority on chain, even if it owns the
+        // crate in the index or is the registry's upgrade authority
+        for signer in [owner, upgrade_authority, Pubkey::new_unique()] {
+            assert!(index
+                .check_publish("foo", "0.2.0", &signer, Some(program_authority))
+                .is_err());
+            assert!(index
+                .check_publish("bar", "0.1.0", &signer, Some(program_authority))
+                .is_err());
+        }
+        index
+            .check_publish("foo", "0.2.0", &program_authority, Some(program_authority))
+            .unwrap();
+        index
+            .check_publish("bar", "0.1.0", &program_authority, Some(program_authority))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_yank() {
+        let upgrade_authority = Pubkey::new_unique();
+        let index =
+            RegistryIndex::new("/index", "http://0.0.0.0:8080", upgrade_authority, None).unwrap();
+        let publisher = Pubkey::new_unique();
+        index.insert_entry(make_entry("foo", "0.1.0")).unwrap();
+        index.insert_entry(make_entry("foo", "0.2.0")).unwrap();
+        index
+            .add_owners("foo", &[publisher], &upgrade_authority)
+            .unwrap();
+
+        let is_yanked = |vers: &str| {
+            index.index.read().unwrap()["foo"]
+                .versions
+                .iter()
+                .find(|entry| entry.vers == vers)
+                .unwrap()
+                .yanked
+        };
+
+        assert!(index
+            .set_yanked("foo", "0.1.0", true, &Pubkey::new_unique())
+            .is_err());
+        assert!(index.set_yanked("foo", "0.3.0", true, &publisher).is_err());
+        assert!(index.set_yanked("bar", "0.1.0", true, &publisher).is_err());
+
+        index.set_yanked("foo", "0.1.0", true, &publisher).unwrap();
+        assert!(is_yanked("0.1.0"));
+        assert!(!is_yanked("0.2.0"));
+
+        index
+            .set_yanked("foo", "0.1.0", false, &upgrade_authority)
+            .unwrap();
+        assert!(!is_yanked("0.1.0"));
+    }
+
+    #[test]
+    fn test_persist_index() {
+        let upgrade_authority = Pubkey::new_unique();
+        let publisher = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let state_dir = tempfile::tempdir().unwrap();
+        let state_path = state_dir.path().join("index.json");
+        let new_index = || {
+            RegistryIndex::new(
+                "/index",
+                "http://0.0.0.0:8080",
+                upgrade_authority,
+                Some(state_path.clone()),
+            )
+            .unwrap()
+        };
+
+        let index = new_index();
+        index.insert_entry(make_entry("foo", "0.1.0")).unwrap();
+        index
+            .add_owners("foo", &[publisher, other], &upgrade_authority)
+            .unwrap();
+        index.set_yanked("foo", "0.1.0", true, &other).unwrap();
+        drop(index);
+
+        // The owners and yanked versions survive a restart of the registry
+        let index = new_index();
+        let mut owners = index.get_owners("foo").unwrap();
+        assert_eq!(owners.remove(0), upgrade_authority);
+        assert_eq!(
+            BTreeSet::from_iter(owners),
+            BTreeSet::from([publisher, other])
+        );
+        assert!(index.index.read().unwrap()["foo"].versions[0].yanked);
+        assert!(index
+            .check_publish("foo", "0.1.0", &upgrade_authority, None)
+            .is_err());
+
+        // Changes that can't be persisted are rejected
+        fs::remove_dir_all(state_dir.path()).unwrap();
+        assert!(index.remove_owners("foo", &[other], &publisher).is_err());
+        assert_eq!(index.get_owners("foo").unwrap().len(), 3);
+        assert!(index.insert_entry(make_entry("bar", "0.1.0")).is_err());
+        assert!(index.get_owners("bar").is_err());
+    }
+
     #[test]
     fn test_get_crate_name_from_path() {
         assert_eq!(RegistryIndex::get_crate_name_from_path(""), None);
@@ -110,6 +110,10 @@ _ scripts/check-dev-context-only-utils.sh tree
 
 _ scripts/cargo-for-all-lock-files.sh -- "+${rust_nightly}" fmt --all -- --check
 
+# The fuzzing harness is excluded from the workspace, as cargo-fuzz expects
+_ cargo "+${rust_nightly}" clippy --manifest-path programs/fuzz/Cargo.toml --all-targets -- --deny=warnings
+_ cargo "+${rust_nightly}" fmt --manifest-path programs/fuzz/Cargo.toml --all -- --check
+
 _ ci/do-audit.sh
 
 echo --- ok
@@ -34,6 +34,10 @@ echo "Executing $testName"
 case $testName in
 test-stable)
   _ ci/intercept.sh cargo test --jobs "$JOBS" --all --tests --exclude solana-local-cluster ${V:+--verbose} -- --nocapture
+
+  # The fuzzing harness is its own workspace, as cargo-fuzz expects
+  _ cargo test --manifest-path programs/fuzz/Cargo.toml --lib ${V:+--verbose} -- --nocapture
+  _ cargo build --manifest-path programs/fuzz/Cargo.toml --bins ${V:+--verbose}
   ;;
 test-stable-sbf)
   # Clear the C dependency files, if dependency moves these files are not regenerated
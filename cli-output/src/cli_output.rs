@@ -2454,6 +2454,46 @@ impl fmt::Display for CliAddressLookupTableCreated {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CliAddressLookupTablesOptimized {
+    pub lookup_table_addresses: Vec<String>,
+    pub created_lookup_table_addresses: Vec<String>,
+    pub added_addresses: usize,
+    pub signatures: Vec<String>,
+}
+impl QuietDisplay for CliAddressLookupTablesOptimized {}
+impl VerboseDisplay for CliAddressLookupTablesOptimized {
+    fn write_str(&self, w: &mut dyn std::fmt::Write) -> std::fmt::Result {
+        write!(w, "{self}")?;
+        if !self.signatures.is_empty() {
+            writeln!(w, "{}", style("Signatures:").bold())?;
+            for signature in &self.signatures {
+                writeln!(w, "  {signature}")?;
+            }
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for CliAddressLookupTablesOptimized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln_name_value(f, "Addresses Added:", &self.added_addresses.to_string())?;
+        writeln!(f, "{}", style("Lookup Tables:").bold())?;
+        for lookup_table_address in &self.lookup_table_addresses {
+            if self
+                .created_lookup_table_addresses
+                .contains(lookup_table_address)
+            {
+                writeln!(f, "  {lookup_table_address} (created)")?;
+            } else {
+                writeln!(f, "  {lookup_table_address}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct ReturnSignersConfig {
     pub dump_transaction_message: bool,
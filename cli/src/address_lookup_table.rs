@@ -1,30 +1,44 @@
 use {
     crate::cli::{CliCommand, CliCommandInfo, CliConfig, CliError, ProcessResult},
-    clap::{App, AppSettings, Arg, ArgMatches, SubCommand},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    clap::{value_t_or_exit, values_t_or_exit, App, AppSettings, Arg, ArgMatches, SubCommand},
     solana_clap_utils::{self, input_parsers::*, input_validators::*, keypair::*},
-    solana_cli_output::{CliAddressLookupTable, CliAddressLookupTableCreated, CliSignature},
+    solana_cli_output::{
+        CliAddressLookupTable, CliAddressLookupTableCreated, CliAddressLookupTablesOptimized,
+        CliSignature,
+    },
     solana_remote_wallet::remote_wallet::RemoteWalletManager,
     solana_rpc_client::rpc_client::RpcClient,
-    solana_rpc_client_api::config::RpcSendTransactionConfig,
+    solana_rpc_client_api::{client_error::Error as ClientError, config::RpcSendTransactionConfig},
+    solana_rpc_client_lookup_table_utils::{
+        extend_lookup_table_instructions, new_lookup_table, wait_for_lookup_table_activation,
+    },
     solana_sdk::{
-        account::from_account,
+        account::{from_account, Account},
         address_lookup_table::{
             self,
             instruction::{
                 close_lookup_table, create_lookup_table, create_lookup_table_signed,
                 deactivate_lookup_table, extend_lookup_table, freeze_lookup_table,
             },
-            state::AddressLookupTable,
+            state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
         },
         clock::Clock,
         commitment_config::CommitmentConfig,
+        instruction::Instruction,
         message::Message,
-        pubkey::Pubkey,
+        pubkey::{Pubkey, PUBKEY_BYTES},
+        signature::Signature,
         signer::Signer,
         sysvar,
-        transaction::Transaction,
+        transaction::{Transaction, VersionedTransaction},
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        error, fs,
+        rc::Rc,
+        sync::Arc,
     },
-    std::{rc::Rc, sync::Arc},
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -58,6 +72,14 @@ pub enum AddressLookupTableCliCommand {
     ShowLookupTable {
         lookup_table_pubkey: Pubkey,
     },
+    OptimizeLookupTables {
+        input_files: Vec<String>,
+        input_instructions: bool,
+        lookup_table_pubkeys: Vec<Pubkey>,
+        authority_signer_index: SignerIndex,
+        payer_signer_index: SignerIndex,
+        min_occurrences: usize,
+    },
 }
 
 pub trait AddressLookupTableSubCommands {
@@ -234,6 +256,68 @@ impl AddressLookupTableSubCommands for App<'_, '_> {
                                 .help("Address of the lookup table to show")
                         )
                 )
+                .subcommand(
+                    SubCommand::with_name("optimize")
+                        .about("Create and extend lookup tables so that a set of transactions \
+                            can be compiled into the smallest possible v0 messages")
+                        .arg(
+                            Arg::with_name("input_files")
+                                .index(1)
+                                .value_name("FILEPATH")
+                                .takes_value(true)
+                                .multiple(true)
+                                .required(true)
+                                .help("Files with one base64 or base58 encoded, bincode serialized \
+                                    transaction per line. Empty lines and lines starting with `#` \
+                                    are ignored")
+                        )
+                        .arg(
+                            Arg::with_name("instructions")
+                                .long("instructions")
+                                .takes_value(false)
+                                .help("Treat each line of the input files as a serialized \
+                                    instruction instead of a transaction")
+                        )
+                        .arg(
+                            Arg::with_name("lookup_table")
+                                .long("lookup-table")
+                                .value_name("LOOKUP_TABLE_ADDRESS")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .validator(is_pubkey)
+                                .help("Existing lookup table to reuse. Addresses already stored in \
+                                    the table are not added again and the table is extended \
+                                    before new tables are created if it is owned by the \
+                                    authority. May be specified multiple times")
+                        )
+                        .arg(
+                            Arg::with_name("authority")
+                                .long("authority")
+                                .value_name("AUTHORITY_SIGNER")
+                                .takes_value(true)
+                                .validator(is_valid_signer)
+                                .help("Lookup table authority [default: the default configured keypair]")
+                        )
+                        .arg(
+                            Arg::with_name("payer")
+                                .long("payer")
+                                .value_name("PAYER_SIGNER")
+                                .takes_value(true)
+                                .validator(is_valid_signer)
+                                .help("Account that will pay rent fees for the created and extended lookup tables [default: the default configured keypair]")
+                        )
+                        .arg(
+                            Arg::with_name("min_occurrences")
+                                .long("min-occurrences")
+                                .value_name("COUNT")
+                                .takes_value(true)
+                                .default_value("1")
+                                .validator(is_parsable::<usize>)
+                                .help("Only add addresses that save space in at least this many of \
+                                    the input transactions")
+                        )
+                )
         )
     }
 }
@@ -463,6 +547,57 @@ pub fn parse_address_lookup_table_subcommand(
                 signers: vec![],
             }
         }
+        ("optimize", Some(matches)) => {
+            let input_files = values_t_or_exit!(matches, "input_files", String);
+            let lookup_table_pubkeys = values_of(matches, "lookup_table").unwrap_or_default();
+
+            let mut bulk_signers = vec![Some(
+                default_signer.signer_from_path(matches, wallet_manager)?,
+            )];
+
+            let authority_pubkey = if let Ok((authority_signer, Some(authority_pubkey))) =
+                signer_of(matches, "authority", wallet_manager)
+            {
+                bulk_signers.push(authority_signer);
+                Some(authority_pubkey)
+            } else {
+                Some(
+                    default_signer
+                        .signer_from_path(matches, wallet_manager)?
+                        .pubkey(),
+                )
+            };
+
+            let payer_pubkey = if let Ok((payer_signer, Some(payer_pubkey))) =
+                signer_of(matches, "payer", wallet_manager)
+            {
+                bulk_signers.push(payer_signer);
+                Some(payer_pubkey)
+            } else {
+                Some(
+                    default_signer
+                        .signer_from_path(matches, wallet_manager)?
+                        .pubkey(),
+                )
+            };
+
+            let signer_info =
+                default_signer.generate_unique_signers(bulk_signers, matches, wallet_manager)?;
+
+            CliCommandInfo {
+                command: CliCommand::AddressLookupTable(
+                    AddressLookupTableCliCommand::OptimizeLookupTables {
+                        input_files,
+                        input_instructions: matches.is_present("instructions"),
+                        lookup_table_pubkeys,
+                        authority_signer_index: signer_info.index_of(authority_pubkey).unwrap(),
+                        payer_signer_index: signer_info.index_of(payer_pubkey).unwrap(),
+                        min_occurrences: value_t_or_exit!(matches, "min_occurrences", usize),
+                    },
+                ),
+                signers: signer_info.signers,
+            }
+        }
         _ => unreachable!(),
     };
     Ok(response)
@@ -534,6 +669,23 @@ pub fn process_address_lookup_table_subcommand(
         AddressLookupTableCliCommand::ShowLookupTable {
             lookup_table_pubkey,
         } => process_show_lookup_table(&rpc_client, config, *lookup_table_pubkey),
+        AddressLookupTableCliCommand::OptimizeLookupTables {
+            input_files,
+            input_instructions,
+            lookup_table_pubkeys,
+            authority_signer_index,
+            payer_signer_index,
+            min_occurrences,
+        } => process_optimize_lookup_tables(
+            &rpc_client,
+            config,
+            input_files,
+            *input_instructions,
+            lookup_table_pubkeys,
+            *authority_signer_index,
+            *payer_signer_index,
+            *min_occurrences,
+        ),
     }
 }
 
@@ -860,3 +1012,532 @@ fn process_show_lookup_table(
                 .collect(),
         }))
 }
+
+fn decode_serialized_lines(path: &str) -> Result<Vec<Vec<Vec<u8>>>, Box<dyn error::Error>> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("Unable to read {path}: {err}"))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            // Base58 is a subset of the base64 alphabet, so keep both decodings around and
+            // let deserialization pick the one that makes sense
+            BASE64_STANDARD
+                .decode(line)
+                .into_iter()
+                .chain(bs58::decode(line).into_vec())
+                .collect()
+        })
+        .collect())
+}
+
+/// Returns the addresses referenced by a transaction that may be loaded from a lookup
+/// table, i.e. all static account keys that are neither signers nor invoked programs.
+/// Addresses already loaded from lookup tables by a v0 message are left out.
+fn lookup_table_candidates_from_transaction(transaction: &VersionedTransaction) -> Vec<Pubkey> {
+    let message = &transaction.message;
+    let num_signers = usize::from(message.header().num_required_signatures);
+    let program_indexes: HashSet<usize> = message
+        .instructions()
+        .iter()
+        .map(|instruction| usize::from(instruction.program_id_index))
+        .collect();
+    message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index >= num_signers && !program_indexes.contains(index))
+        .map(|(_, address)| *address)
+        .collect()
+}
+
+fn lookup_table_candidates_from_instruction(instruction: &Instruction) -> Vec<Pubkey> {
+    let mut addresses = vec![];
+    for account in &instruction.accounts {
+        if !account.is_signer
+            && account.pubkey != instruction.program_id
+            && !addresses.contains(&account.pubkey)
+        {
+            addresses.push(account.pubkey);
+        }
+    }
+    addresses
+}
+
+fn read_lookup_table_candidates(
+    input_files: &[String],
+    input_instructions: bool,
+) -> Result<Vec<Vec<Pubkey>>, Box<dyn error::Error>> {
+    let mut candidates = vec![];
+    for path in input_files {
+        for (line, decodings) in decode_serialized_lines(path)?.into_iter().enumerate() {
+            let addresses = if input_instructions {
+                decodings
+                    .iter()
+                    .find_map(|bytes| bincode::deserialize::<Instruction>(bytes).ok())
+                    .map(|instruction| lookup_table_candidates_from_instruction(&instruction))
+            } else {
+                decodings
+                    .iter()
+                    .filter_map(|bytes| bincode::deserialize::<VersionedTransaction>(bytes).ok())
+                    .find(|transaction| transaction.sanitize().is_ok())
+                    .map(|transaction| lookup_table_candidates_from_transaction(&transaction))
+            };
+            let kind = if input_instructions {
+                "instruction"
+            } else {
+                "transaction"
+            };
+            candidates.push(addresses.ok_or_else(|| {
+                format!("Unable to decode the {kind} on line {} of {path}", line + 1)
+            })?);
+        }
+    }
+    Ok(candidates)
+}
+
+// Loading an address from a lookup table replaces its 32 bytes by a 1 byte index
+const LOOKUP_TABLE_SAVINGS_PER_ADDRESS: usize = PUBKEY_BYTES - 1;
+// Every table that a transaction references adds the table address and the lengths of its
+// writable and readonly index vectors to the message
+const LOOKUP_TABLE_OVERHEAD: usize = PUBKEY_BYTES + 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum PlannedLookupTable {
+    Existing(Pubkey),
+    New(usize),
+}
+
+/// Sorts the addresses by decreasing number of occurrences, keeping the ones that occur at
+/// least `min_occurrences` times
+fn rank_lookup_table_addresses(
+    occurrences: HashMap<Pubkey, usize>,
+    min_occurrences: usize,
+) -> Vec<Pubkey> {
+    let mut ranked: Vec<(Pubkey, usize)> = occurrences
+        .into_iter()
+        .filter(|(_, count)| *count >= min_occurrences)
+        .collect();
+    ranked.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    ranked.into_iter().map(|(address, _)| address).collect()
+}
+
+/// Selects the addresses worth storing in lookup tables. Addresses referenced by more
+/// transactions come first so that they end up together in the same tables, which keeps
+/// the number of tables that each transaction has to reference low.
+///
+/// A transaction only benefits from a table if the addresses it loads from it save more
+/// than the cost of referencing the table, so occurrences in transactions that wouldn't
+/// reference the table of an address don't count. As dropping addresses changes which
+/// tables pay off, the selection is refined until it doesn't shrink anymore.
+fn select_lookup_table_addresses(
+    candidates: &[Vec<Pubkey>],
+    stored_addresses: &HashMap<Pubkey, Pubkey>,
+    extendable_tables: &[(Pubkey, usize)],
+    min_occurrences: usize,
+) -> Vec<Pubkey> {
+    let candidates: Vec<HashSet<Pubkey>> = candidates
+        .iter()
+        .map(|addresses| addresses.iter().copied().collect())
+        .collect();
+    let mut occurrences = HashMap::<Pubkey, usize>::new();
+    for address in candidates.iter().flatten() {
+        if !stored_addresses.contains_key(address) {
+            *occurrences.entry(*address).or_default() += 1;
+        }
+    }
+    let mut selected = rank_lookup_table_addresses(occurrences, min_occurrences);
+
+    loop {
+        let (extensions, new_tables) =
+            assign_lookup_table_addresses(selected.clone(), extendable_tables);
+        let mut planned_tables: HashMap<Pubkey, PlannedLookupTable> = stored_addresses
+            .iter()
+            .map(|(address, table)| (*address, PlannedLookupTable::Existing(*table)))
+            .collect();
+        for (table, addresses) in &extensions {
+            planned_tables.extend(
+                addresses
+                    .iter()
+                    .map(|address| (*address, PlannedLookupTable::Existing(*table))),
+            );
+        }
+        for (index, addresses) in new_tables.iter().enumerate() {
+            planned_tables.extend(
+                addresses
+                    .iter()
+                    .map(|address| (*address, PlannedLookupTable::New(index))),
+            );
+        }
+
+        let mut occurrences = HashMap::<Pubkey, usize>::new();
+        for addresses in &candidates {
+            let mut addresses_by_table = HashMap::<PlannedLookupTable, Vec<&Pubkey>>::new();
+            for address in addresses {
+                if let Some(table) = planned_tables.get(address) {
+                    addresses_by_table.entry(*table).or_default().push(address);
+                }
+            }
+            for addresses in addresses_by_table.into_values() {
+                if addresses.len() * LOOKUP_TABLE_SAVINGS_PER_ADDRESS > LOOKUP_TABLE_OVERHEAD {
+                    for address in addresses {
+                        if !stored_addresses.contains_key(address) {
+                            *occurrences.entry(*address).or_default() += 1;
+                        }
+                    }
+                }
+            }
+        }
+        let refined = rank_lookup_table_addresses(occurrences, min_occurrences);
+        if refined.len() == selected.len() {
+            return selected;
+        }
+        selected = refined;
+    }
+}
+
+/// Fills the remaining capacity of the extendable tables first, then splits the leftover
+/// addresses into new tables. Returns the planned extensions of the existing tables and
+/// the contents of each new table.
+fn assign_lookup_table_addresses(
+    addresses: Vec<Pubkey>,
+    extendable_tables: &[(Pubkey, usize)],
+) -> (Vec<(Pubkey, Vec<Pubkey>)>, Vec<Vec<Pubkey>>) {
+    let mut remaining = addresses.as_slice();
+    let mut extensions = vec![];
+    for (lookup_table_pubkey, len) in extendable_tables {
+        if remaining.is_empty() {
+            break;
+        }
+        let capacity = LOOKUP_TABLE_MAX_ADDRESSES.saturating_sub(*len);
+        if capacity == 0 {
+            continue;
+        }
+        let (assigned, rest) = remaining.split_at(capacity.min(remaining.len()));
+        extensions.push((*lookup_table_pubkey, assigned.to_vec()));
+        remaining = rest;
+    }
+    let new_tables = remaining
+        .chunks(LOOKUP_TABLE_MAX_ADDRESSES)
+        .map(<[Pubkey]>::to_vec)
+        .collect();
+    (extensions, new_tables)
+}
+
+fn send_lookup_table_instruction(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    instruction: Instruction,
+    signers: &[&dyn Signer],
+) -> Result<Signature, Box<dyn error::Error>> {
+    let blockhash = rpc_client.get_latest_blockhash()?;
+    let mut tx = Transaction::new_unsigned(Message::new(
+        &[instruction],
+        Some(&config.signers[0].pubkey()),
+    ));
+    tx.try_sign(signers, blockhash)?;
+    Ok(
+        rpc_client.send_and_confirm_transaction_with_spinner_and_config(
+            &tx,
+            config.commitment,
+            RpcSendTransactionConfig {
+                skip_preflight: false,
+                preflight_commitment: Some(config.commitment.commitment),
+                ..RpcSendTransactionConfig::default()
+            },
+        )?,
+    )
+}
+
+fn get_lookup_table_account(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    lookup_table_pubkey: &Pubkey,
+) -> Result<Account, Box<dyn error::Error>> {
+    let get_lookup_table_result =
+        rpc_client.get_account_with_commitment(lookup_table_pubkey, config.commitment)?;
+    let lookup_table_account = get_lookup_table_result.value.ok_or_else(|| {
+        format!("Lookup table account {lookup_table_pubkey} not found, was it already closed?")
+    })?;
+    if !address_lookup_table::program::check_id(&lookup_table_account.owner) {
+        return Err(format!(
+            "Lookup table account {lookup_table_pubkey} is not owned by the Address Lookup Table program",
+        )
+        .into());
+    }
+    Ok(lookup_table_account)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_optimize_lookup_tables(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    input_files: &[String],
+    input_instructions: bool,
+    lookup_table_pubkeys: &[Pubkey],
+    authority_signer_index: SignerIndex,
+    payer_signer_index: SignerIndex,
+    min_occurrences: usize,
+) -> ProcessResult {
+    let authority_signer = config.signers[authority_signer_index];
+    let payer_signer = config.signers[payer_signer_index];
+    let authority_address = authority_signer.pubkey();
+    let payer_address = payer_signer.pubkey();
+
+    let candidates = read_lookup_table_candidates(input_files, input_instructions)?;
+    if candidates.is_empty() {
+        return Err("The input files are empty".into());
+    }
+
+    let mut stored_addresses = HashMap::new();
+    let mut extendable_tables = vec![];
+    for lookup_table_pubkey in lookup_table_pubkeys {
+        let lookup_table_account =
+            get_lookup_table_account(rpc_client, config, lookup_table_pubkey)?;
+        let lookup_table = AddressLookupTable::deserialize(&lookup_table_account.data)?;
+        if lookup_table.meta.deactivation_slot != u64::MAX {
+            return Err(format!(
+                "Lookup table account {lookup_table_pubkey} is deactivated and can't be used by transactions",
+            )
+            .into());
+        }
+        if lookup_table.meta.authority == Some(authority_address) {
+            extendable_tables.push((*lookup_table_pubkey, lookup_table.addresses.len()));
+        }
+        for address in lookup_table.addresses.iter() {
+            stored_addresses
+                .entry(*address)
+                .or_insert(*lookup_table_pubkey);
+        }
+    }
+
+    let new_addresses = select_lookup_table_addresses(
+        &candidates,
+        &stored_addresses,
+        &extendable_tables,
+        min_occurrences,
+    );
+    let added_addresses = new_addresses.len();
+    let (mut extensions, new_tables) =
+        assign_lookup_table_addresses(new_addresses, &extendable_tables);
+
+    let mut signatures = vec![];
+    let mut created_lookup_table_addresses = vec![];
+    let mut last_recent_slot = None;
+    for addresses in new_tables {
+        let (create_lookup_table_ix, lookup_table_address, recent_slot) =
+            new_lookup_table::<ClientError>(
+                rpc_client,
+                &authority_address,
+                &payer_address,
+                last_recent_slot,
+            )?;
+        last_recent_slot = Some(recent_slot);
+        let signature = send_lookup_table_instruction(
+            rpc_client,
+            config,
+            create_lookup_table_ix,
+            &[config.signers[0], payer_signer],
+        )
+        .map_err(|err| format!("Create failed: {err}"))?;
+        signatures.push(signature.to_string());
+        created_lookup_table_addresses.push(lookup_table_address);
+        extensions.push((lookup_table_address, addresses));
+    }
+
+    for (lookup_table_pubkey, addresses) in &extensions {
+        for extend_lookup_table_ix in extend_lookup_table_instructions(
+            lookup_table_pubkey,
+            &authority_address,
+            &payer_address,
+            addresses,
+        ) {
+            let signature = send_lookup_table_instruction(
+                rpc_client,
+                config,
+                extend_lookup_table_ix,
+                &[config.signers[0], authority_signer, payer_signer],
+            )
+            .map_err(|err| format!("Extend failed: {err}"))?;
+            signatures.push(signature.to_string());
+        }
+    }
+    if !extensions.is_empty() {
+        wait_for_lookup_table_activation::<ClientError>(rpc_client)?;
+    }
+
+    let mut lookup_table_addresses: Vec<String> = lookup_table_pubkeys
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    lookup_table_addresses.extend(
+        created_lookup_table_addresses
+            .iter()
+            .map(ToString::to_string),
+    );
+    Ok(config
+        .output_format
+        .formatted_string(&CliAddressLookupTablesOptimized {
+            lookup_table_addresses,
+            created_lookup_table_addresses: created_lookup_table_addresses
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            added_addresses,
+            signatures,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            instruction::AccountMeta, message::VersionedMessage, signature::Keypair,
+            system_instruction,
+        },
+        std::io::Write,
+        tempfile::NamedTempFile,
+    };
+
+    #[test]
+    fn test_lookup_table_candidates() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let instructions = vec![
+            system_instruction::transfer(&payer.pubkey(), &to, 1),
+            Instruction::new_with_bytes(
+                program_id,
+                &[],
+                vec![
+                    AccountMeta::new_readonly(readonly, false),
+                    AccountMeta::new(payer.pubkey(), true),
+                ],
+            ),
+        ];
+        let transaction = VersionedTransaction::from(Transaction::new_unsigned(Message::new(
+            &instructions,
+            Some(&payer.pubkey()),
+        )));
+        let mut candidates = lookup_table_candidates_from_transaction(&transaction);
+        candidates.sort();
+        let mut expected = vec![to, readonly];
+        expected.sort();
+        assert_eq!(candidates, expected);
+
+        assert_eq!(
+            lookup_table_candidates_from_instruction(&instructions[1]),
+            vec![readonly]
+        );
+    }
+
+    #[test]
+    fn test_read_lookup_table_candidates() {
+        let payer = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(Message::new(
+                &[system_instruction::transfer(&payer, &to, 1)],
+                Some(&payer),
+            )),
+        };
+        let serialized = bincode::serialize(&transaction).unwrap();
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# comment").unwrap();
+        writeln!(file, "{}", BASE64_STANDARD.encode(&serialized)).unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "{}", bs58::encode(&serialized).into_string()).unwrap();
+        let path = file.path().to_str().unwrap().to_string();
+        assert_eq!(
+            read_lookup_table_candidates(&[path.clone()], false).unwrap(),
+            vec![vec![to], vec![to]]
+        );
+
+        let instruction = system_instruction::transfer(&payer, &to, 1);
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "{}",
+            BASE64_STANDARD.encode(bincode::serialize(&instruction).unwrap())
+        )
+        .unwrap();
+        let instructions_path = file.path().to_str().unwrap().to_string();
+        assert_eq!(
+            read_lookup_table_candidates(&[instructions_path], true).unwrap(),
+            vec![vec![to]]
+        );
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "not a transaction").unwrap();
+        let invalid_path = file.path().to_str().unwrap().to_string();
+        assert!(read_lookup_table_candidates(&[path, invalid_path], false).is_err());
+    }
+
+    #[test]
+    fn test_select_lookup_table_addresses() {
+        let shared = Pubkey::new_unique();
+        let stored = Pubkey::new_unique();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let candidates = vec![
+            vec![shared, stored, a, a],
+            vec![shared, stored, b],
+            vec![shared],
+        ];
+        let stored_table = Pubkey::new_unique();
+        let stored_addresses = HashMap::from([(stored, stored_table)]);
+
+        let selected = select_lookup_table_addresses(&candidates, &stored_addresses, &[], 1);
+        assert_eq!(selected.len(), 3);
+        assert_eq!(selected[0], shared);
+        assert_eq!(
+            selected[1..].iter().collect::<HashSet<_>>(),
+            HashSet::from([&a, &b])
+        );
+
+        // The last transaction doesn't reference a table for a single address, so `shared`
+        // only pays off in two transactions, in which it's alone once `a` and `b` are dropped
+        assert!(select_lookup_table_addresses(&candidates, &stored_addresses, &[], 2).is_empty());
+
+        // Addresses appended to the table of a stored address share its overhead
+        let selected =
+            select_lookup_table_addresses(&candidates, &stored_addresses, &[(stored_table, 1)], 2);
+        assert_eq!(selected, vec![shared]);
+        assert!(select_lookup_table_addresses(&candidates, &stored_addresses, &[], 4).is_empty());
+    }
+
+    #[test]
+    fn test_assign_lookup_table_addresses() {
+        let addresses: Vec<Pubkey> = (0..LOOKUP_TABLE_MAX_ADDRESSES + 10)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let full_table = Pubkey::new_unique();
+        let table = Pubkey::new_unique();
+
+        let (extensions, new_tables) = assign_lookup_table_addresses(
+            addresses.clone(),
+            &[
+                (full_table, LOOKUP_TABLE_MAX_ADDRESSES),
+                (table, LOOKUP_TABLE_MAX_ADDRESSES - 5),
+            ],
+        );
+        assert_eq!(extensions, vec![(table, addresses[..5].to_vec())]);
+        assert_eq!(
+            new_tables,
+            vec![
+                addresses[5..LOOKUP_TABLE_MAX_ADDRESSES + 5].to_vec(),
+                addresses[LOOKUP_TABLE_MAX_ADDRESSES + 5..].to_vec(),
+            ]
+        );
+
+        let (extensions, new_tables) =
+            assign_lookup_table_addresses(addresses[..3].to_vec(), &[(table, 0)]);
+        assert_eq!(extensions, vec![(table, addresses[..3].to_vec())]);
+        assert!(new_tables.is_empty());
+    }
+}
@@ -6,16 +6,23 @@ use {
         },
         cli::{process_command, CliCommand, CliConfig},
     },
-    solana_cli_output::{CliAddressLookupTable, CliAddressLookupTableCreated, OutputFormat},
+    solana_cli_output::{
+        CliAddressLookupTable, CliAddressLookupTableCreated, CliAddressLookupTablesOptimized,
+        OutputFormat,
+    },
     solana_faucet::faucet::run_local_faucet,
     solana_sdk::{
+        message::Message,
         native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
         signature::{Keypair, Signer},
+        system_instruction,
+        transaction::{Transaction, VersionedTransaction},
     },
     solana_streamer::socket::SocketAddrSpace,
     solana_test_validator::TestValidator,
-    std::str::FromStr,
+    std::{io::Write, str::FromStr},
+    tempfile::NamedTempFile,
 };
 
 #[test]
@@ -216,3 +223,121 @@ fn test_cli_create_and_deactivate_address_lookup_table() {
         assert_ne!(deactivation_slot, u64::MAX);
     }
 }
+
+#[test]
+fn test_cli_optimize_address_lookup_tables() {
+    let mint_keypair = Keypair::new();
+    let mint_pubkey = mint_keypair.pubkey();
+    let faucet_addr = run_local_faucet(mint_keypair, None);
+    let test_validator =
+        TestValidator::with_no_fees(mint_pubkey, Some(faucet_addr), SocketAddrSpace::Unspecified);
+
+    let mut config = CliConfig::recent_for_tests();
+    let keypair = Keypair::new();
+    config.json_rpc_url = test_validator.rpc_url();
+    config.signers = vec![&keypair];
+    config.output_format = OutputFormat::JsonCompact;
+
+    // Airdrop SOL for transaction fees
+    config.command = CliCommand::Airdrop {
+        pubkey: None,
+        lamports: 10 * LAMPORTS_PER_SOL,
+    };
+    process_command(&config).unwrap();
+
+    // Create a lookup table that already stores one of the addresses
+    config.command =
+        CliCommand::AddressLookupTable(AddressLookupTableCliCommand::CreateLookupTable {
+            authority_pubkey: keypair.pubkey(),
+            authority_signer_index: None,
+            payer_signer_index: 0,
+        });
+    let response: CliAddressLookupTableCreated =
+        serde_json::from_str(&process_command(&config).unwrap()).unwrap();
+    let lookup_table_pubkey = Pubkey::from_str(&response.lookup_table_address).unwrap();
+
+    let recipients: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    config.command =
+        CliCommand::AddressLookupTable(AddressLookupTableCliCommand::ExtendLookupTable {
+            lookup_table_pubkey,
+            authority_signer_index: 0,
+            payer_signer_index: 0,
+            new_addresses: vec![recipients[0]],
+        });
+    process_command(&config).unwrap();
+
+    // Every transaction references two addresses, so that loading them from a single table
+    // saves more than the cost of referencing the table
+    let shared = Pubkey::new_unique();
+    let mut transactions_file = NamedTempFile::new().unwrap();
+    for recipient in &recipients {
+        let transaction = VersionedTransaction::from(Transaction::new_unsigned(Message::new(
+            &[
+                system_instruction::transfer(&keypair.pubkey(), recipient, 1),
+                system_instruction::transfer(&keypair.pubkey(), &shared, 1),
+            ],
+            Some(&keypair.pubkey()),
+        )));
+        writeln!(
+            transactions_file,
+            "{}",
+            bs58::encode(bincode::serialize(&transaction).unwrap()).into_string()
+        )
+        .unwrap();
+    }
+    let input_files = vec![transactions_file.path().to_str().unwrap().to_string()];
+
+    // Existing tables owned by the authority are extended with the missing addresses
+    config.command =
+        CliCommand::AddressLookupTable(AddressLookupTableCliCommand::OptimizeLookupTables {
+            input_files: input_files.clone(),
+            input_instructions: false,
+            lookup_table_pubkeys: vec![lookup_table_pubkey],
+            authority_signer_index: 0,
+            payer_signer_index: 0,
+            min_occurrences: 1,
+        });
+    let response: CliAddressLookupTablesOptimized =
+        serde_json::from_str(&process_command(&config).unwrap()).unwrap();
+    assert_eq!(
+        response.lookup_table_addresses,
+        vec![lookup_table_pubkey.to_string()]
+    );
+    assert!(response.created_lookup_table_addresses.is_empty());
+    assert_eq!(response.added_addresses, 3);
+
+    config.command =
+        CliCommand::AddressLookupTable(AddressLookupTableCliCommand::ShowLookupTable {
+            lookup_table_pubkey,
+        });
+    let CliAddressLookupTable { addresses, .. } =
+        serde_json::from_str(&process_command(&config).unwrap()).unwrap();
+    let mut addresses: Vec<Pubkey> = addresses
+        .into_iter()
+        .map(|address| Pubkey::from_str(&address).unwrap())
+        .collect();
+    addresses.sort();
+    let mut expected_addresses = recipients.clone();
+    expected_addresses.push(shared);
+    expected_addresses.sort();
+    assert_eq!(addresses, expected_addresses);
+
+    // Without existing tables a new one is created
+    config.command =
+        CliCommand::AddressLookupTable(AddressLookupTableCliCommand::OptimizeLookupTables {
+            input_files,
+            input_instructions: false,
+            lookup_table_pubkeys: vec![],
+            authority_signer_index: 0,
+            payer_signer_index: 0,
+            min_occurrences: 1,
+        });
+    let response: CliAddressLookupTablesOptimized =
+        serde_json::from_str(&process_command(&config).unwrap()).unwrap();
+    assert_eq!(response.created_lookup_table_addresses.len(), 1);
+    assert_eq!(
+        response.lookup_table_addresses,
+        response.created_lookup_table_addresses
+    );
+    assert_eq!(response.added_addresses, 4);
+}
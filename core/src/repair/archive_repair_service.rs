@@ -0,0 +1,403 @@
+//! Fills gaps in the ledger which peers can no longer repair, from archives written by
+//! `solana-ledger-tool export-archive`.
+//!
+//! Slots above the local root which have stayed incomplete for a while are looked up in the
+//! archives of a directory. Shreds of slots an archive holds as rooted are checked against the
+//! slot leader's signature and inserted into the blockstore, so that replay can continue.
+
+use {
+    solana_ledger::{
+        blockstore::{blockstore_archive, Blockstore},
+        leader_schedule_cache::LeaderScheduleCache,
+    },
+    solana_measure::measure::Measure,
+    solana_runtime::{bank::Bank, bank_forks::BankForks},
+    solana_sdk::clock::Slot,
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, RwLock,
+        },
+        thread::{self, Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+const LOOP_INTERVAL: Duration = Duration::from_secs(10);
+// How long a slot has to stay incomplete before it is looked up in the archives, so that
+// regular repair gets a chance first.
+const ARCHIVE_REPAIR_DELAY: Duration = Duration::from_secs(30);
+// Number of slots above the root which are considered for repair.
+const MAX_ARCHIVE_REPAIR_SLOTS: Slot = 256;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ArchiveRepairStats {
+    candidate_slots: usize,
+    repaired_slots: usize,
+    repaired_shreds: usize,
+    unavailable_slots: usize,
+    invalid_slots: usize,
+}
+
+struct ArchiveRepair {
+    archive_dir: PathBuf,
+    // Rooted slots each archive in the directory holds, read once when the archive first shows
+    // up, or None if it could not be read.
+    archives: HashMap<PathBuf, Option<HashSet<Slot>>>,
+    // When each incomplete slot was first seen.
+    incomplete_since: HashMap<Slot, Instant>,
+    // Slots which an archive holds with shreds not signed by the slot leader; keyed by archive
+    // so that other archives are still looked up.
+    invalid: HashSet<(PathBuf, Slot)>,
+}
+
+pub struct ArchiveRepairService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl ArchiveRepairService {
+    pub fn new(
+        archive_dir: PathBuf,
+        blockstore: Arc<Blockstore>,
+        bank_forks: Arc<RwLock<BankForks>>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        let thread_hdl = Builder::new()
+            .name("solArchiveRepair".to_string())
+            .spawn(move || {
+                info!("Repairing from archives in {}", archive_dir.display());
+                let mut archive_repair = ArchiveRepair::new(archive_dir);
+                let mut last_run = None;
+                while !exit.load(Ordering::Relaxed) {
+                    if last_run.map_or(true, |last_run: Instant| last_run.elapsed() > LOOP_INTERVAL)
+                    {
+                        let root_bank = bank_forks.read().unwrap().root_bank();
+                        let mut measure = Measure::start("archive_repair");
+                        let stats = archive_repair.repair(
+                            &blockstore,
+                            &root_bank,
+                            &leader_schedule_cache,
+                            ARCHIVE_REPAIR_DELAY,
+                        );
+                        measure.stop();
+                        datapoint_info!(
+                            "archive_repair_service",
+                            ("candidate_slots", stats.candidate_slots, i64),
+                            ("repaired_slots", stats.repaired_slots, i64),
+                            ("repaired_shreds", stats.repaired_shreds, i64),
+                            ("unavailable_slots", stats.unavailable_slots, i64),
+                            ("invalid_slots", stats.invalid_slots, i64),
+                            ("elapsed_us", measure.as_us(), i64),
+                        );
+                        last_run = Some(Instant::now());
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+impl ArchiveRepair {
+    fn new(archive_dir: PathBuf) -> Self {
+        Self {
+            archive_dir,
+            archives: HashMap::default(),
+            incomplete_since: HashMap::default(),
+            invalid: HashSet::default(),
+        }
+    }
+
+    /// Returns the slots above the root, up to the highest slot in the blockstore, which have
+    /// been incomplete for longer than `delay`.
+    fn candidate_slots(
+        &mut self,
+        blockstore: &Blockstore,
+        root: Slot,
+        delay: Duration,
+    ) -> Vec<Slot> {
+        let highest_slot = match blockstore.highest_slot() {
+            Ok(Some(highest_slot)) => {
+                highest_slot.min(root.saturating_add(MAX_ARCHIVE_REPAIR_SLOTS))
+            }
+            Ok(None) => return vec![],
+            Err(err) => {
+                warn!("Failed to get the highest slot: {err:?}");
+                return vec![];
+            }
+        };
+        self.incomplete_since.retain(|slot, _| *slot > root);
+        self.invalid.retain(|(_, slot)| *slot > root);
+        let now = Instant::now();
+        (root + 1..=highest_slot)
+            .filter(|slot| !blockstore.is_full(*slot))
+            .filter(|slot| {
+                let incomplete_since = self.incomplete_since.entry(*slot).or_insert(now);
+                now.duration_since(*incomplete_since) >= delay
+            })
+            .collect()
+    }
+
+    /// Indexes the rooted slots of archives new to the directory, and forgets removed ones.
+    fn refresh_archives(&mut self) {
+        let entries = match fs::read_dir(&self.archive_dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!(
+                    "Failed to read archive directory {}: {err:?}",
+                    self.archive_dir.display()
+                );
+                return;
+            }
+        };
+        let paths: HashSet<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect();
+        self.archives.retain(|path, _| paths.contains(path));
+        self.invalid.retain(|(path, _)| paths.contains(path));
+        for path in paths {
+            self.archives.entry(path).or_insert_with_key(|path| {
+                match blockstore_archive::read_archive_rooted_slots(path) {
+                    Ok(rooted_slots) => Some(rooted_slots.into_iter().collect()),
+                    Err(err) => {
+                        warn!("Ignoring archive {}: {err}", path.display());
+                        None
+                    }
+                }
+            });
+        }
+    }
+
+    fn repair(
+        &mut self,
+        blockstore: &Blockstore,
+        root_bank: &Bank,
+        leader_schedule_cache: &LeaderScheduleCache,
+        delay: Duration,
+    ) -> ArchiveRepairStats {
+        let mut stats = ArchiveRepairStats::default();
+        let mut slots: HashSet<Slot> = self
+            .candidate_slots(blockstore, root_bank.slot(), delay)
+            .into_iter()
+            .collect();
+        stats.candidate_slots = slots.len();
+        if slots.is_empty() {
+            return stats;
+        }
+        self.refresh_archives();
+        let mut archives: Vec<(&Path, &HashSet<Slot>)> = self
+            .archives
+            .iter()
+            .filter_map(|(path, rooted_slots)| Some((path.as_path(), rooted_slots.as_ref()?)))
+            .collect();
+        archives.sort_unstable_by_key(|(path, _)| *path);
+        let mut unreadable = vec![];
+        for (path, rooted_slots) in archives {
+            // Only archives which hold some of the slots as rooted are read.
+            let covered: HashSet<Slot> = slots
+                .intersection(rooted_slots)
+                .copied()
+                .filter(|slot| !self.invalid.contains(&(path.to_path_buf(), *slot)))
+                .collect();
+            if covered.is_empty() {
+                continue;
+            }
+            let rooted_shreds = match blockstore_archive::read_archive_rooted_shreds(path, &covered)
+            {
+                Ok(rooted_shreds) => rooted_shreds,
+                Err(err) => {
+                    warn!("Failed to read archive {}: {err}", path.display());
+                    unreadable.push(path.to_path_buf());
+                    continue;
+                }
+            };
+            for (slot, shreds) in rooted_shreds {
+                slots.remove(&slot);
+                let Some(leader) = leader_schedule_cache.slot_leader_at(slot, Some(root_bank))
+                else {
+                    warn!(
+                        "Unknown leader for slot {slot} in archive {}",
+                        path.display()
+                    );
+                    continue;
+                };
+                if !shreds.iter().all(|shred| shred.verify(&leader)) {
+                    warn!(
+                        "Shreds for slot {slot} in archive {} are not signed by the slot leader",
+                        path.display()
+                    );
+                    stats.invalid_slots += 1;
+                    self.invalid.insert((path.to_path_buf(), slot));
+                    continue;
+                }
+                let num_shreds = shreds.len();
+                match blockstore.insert_shreds(shreds, Some(leader_schedule_cache), false) {
+                    Ok(_) => {
+                        info!("Repaired slot {slot} from archive {}", path.display());
+                        stats.repaired_slots += 1;
+                        stats.repaired_shreds += num_shreds;
+                    }
+                    Err(err) => error!("Failed to insert shreds for slot {slot}: {err:?}"),
+                }
+            }
+        }
+        for path in unreadable {
+            self.archives.insert(path, None);
+        }
+        // No archive holds what is left of the slots as rooted.
+        stats.unavailable_slots = slots.len();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_entry::entry::create_ticks,
+        solana_ledger::{
+            genesis_utils::{create_genesis_config_with_leader, GenesisConfigInfo},
+            get_tmp_ledger_path_auto_delete,
+            shred::{ProcessShredsStats, ReedSolomonCache, Shred, Shredder},
+        },
+        solana_sdk::{
+            hash::Hash,
+            signature::{Keypair, Signer},
+        },
+    };
+
+    fn make_shreds(slot: Slot, parent_slot: Slot, keypair: &Keypair) -> Vec<Shred> {
+        let entries = create_ticks(8, 0, Hash::new_unique());
+        let (data_shreds, _) = Shredder::new(slot, parent_slot, 0, 0)
+            .unwrap()
+            .entries_to_shreds(
+                keypair,
+                &entries,
+                true, // is_last_in_slot
+                0,    // next_shred_index
+                0,    // next_code_index
+                true, // merkle_variant
+                &ReedSolomonCache::default(),
+                &mut ProcessShredsStats::default(),
+            );
+        data_shreds
+    }
+
+    #[test]
+    fn test_archive_repair() {
+        let leader_keypair = Keypair::new();
+        let GenesisConfigInfo { genesis_config, .. } =
+            create_genesis_config_with_leader(10_000, &leader_keypair.pubkey(), 1_000);
+        let root_bank = Bank::new_for_tests(&genesis_config);
+        let leader_schedule_cache = LeaderScheduleCache::new_from_bank(&root_bank);
+
+        // Slots 1 and 2 are rooted in the archive, slot 3 is not, and slot 4 is signed by
+        // someone other than the leader.
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        for (slot, parent_slot) in [(1, 0), (2, 1), (3, 2)] {
+            let shreds = make_shreds(slot, parent_slot, &leader_keypair);
+            source.insert_shreds(shreds, None, false).unwrap();
+        }
+        let shreds = make_shreds(4, 3, &Keypair::new());
+        source.insert_shreds(shreds, None, false).unwrap();
+        source.set_roots([1, 2, 4].iter()).unwrap();
+        let archive_dir = tempfile::tempdir().unwrap();
+        source
+            .export_archive(1, 4, &archive_dir.path().join("1-4.archive"))
+            .unwrap();
+
+        // The target is missing slots 1 to 4, and has an incomplete slot 5 and a full slot 6.
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        let mut shreds = make_shreds(5, 4, &leader_keypair);
+        shreds.truncate(1);
+        target.insert_shreds(shreds, None, false).unwrap();
+        let shreds = make_shreds(6, 5, &leader_keypair);
+        target.insert_shreds(shreds, None, false).unwrap();
+
+        let mut archive_repair = ArchiveRepair::new(archive_dir.path().to_path_buf());
+        // Slots only become candidates once they have been incomplete for the delay.
+        let stats = archive_repair.repair(
+            &target,
+            &root_bank,
+            &leader_schedule_cache,
+            Duration::from_secs(3600),
+        );
+        assert_eq!(stats, ArchiveRepairStats::default());
+
+        let stats =
+            archive_repair.repair(&target, &root_bank, &leader_schedule_cache, Duration::ZERO);
+        assert_eq!(stats.candidate_slots, 5);
+        assert_eq!(stats.repaired_slots, 2);
+        assert_eq!(stats.invalid_slots, 1);
+        // Slot 3 is not rooted in the archive; slot 5 is not in it.
+        assert_eq!(stats.unavailable_slots, 2);
+        assert!(target.is_full(1));
+        assert!(target.is_full(2));
+        assert!(!target.is_full(3));
+        assert!(!target.is_full(4));
+        let archive_path = archive_dir.path().join("1-4.archive");
+        assert_eq!(
+            archive_repair.archives[&archive_path],
+            Some(HashSet::from([1, 2, 4])),
+        );
+        assert_eq!(
+            archive_repair.invalid,
+            HashSet::from([(archive_path.clone(), 4)])
+        );
+
+        // Slot 4 is not looked up in the same archive again, so the archive, which holds none
+        // of the other slots as rooted, is not read at all; a corrupted archive shows that.
+        let mut bytes = fs::read(&archive_path).unwrap();
+        let len = bytes.len();
+        bytes[len - 1] ^= 0xff;
+        fs::write(&archive_path, bytes).unwrap();
+        let stats =
+            archive_repair.repair(&target, &root_bank, &leader_schedule_cache, Duration::ZERO);
+        assert_eq!(stats.candidate_slots, 3);
+        assert_eq!(stats.repaired_slots, 0);
+        assert_eq!(stats.unavailable_slots, 3);
+        assert_eq!(stats.invalid_slots, 0);
+        assert!(archive_repair.archives[&archive_path].is_some());
+
+        // A second archive holding slots 3 and 4 as rooted and signed by the leader repairs
+        // them.
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        for (slot, parent_slot) in [(3, 2), (4, 3)] {
+            let shreds = make_shreds(slot, parent_slot, &leader_keypair);
+            source.insert_shreds(shreds, None, false).unwrap();
+        }
+        source.set_roots([3, 4].iter()).unwrap();
+        source
+            .export_archive(3, 4, &archive_dir.path().join("3-4.archive"))
+            .unwrap();
+        let stats =
+            archive_repair.repair(&target, &root_bank, &leader_schedule_cache, Duration::ZERO);
+        assert_eq!(stats.candidate_slots, 3);
+        assert_eq!(stats.repaired_slots, 2);
+        assert_eq!(stats.invalid_slots, 0);
+        assert!(target.is_full(3));
+        assert!(target.is_full(4));
+
+        // Once an archive is removed, it and its invalid slots are forgotten.
+        fs::remove_file(&archive_path).unwrap();
+        let stats =
+            archive_repair.repair(&target, &root_bank, &leader_schedule_cache, Duration::ZERO);
+        assert_eq!(stats.candidate_slots, 1);
+        assert!(!archive_repair.archives.contains_key(&archive_path));
+        assert!(archive_repair.invalid.is_empty());
+    }
+}
@@ -1,17 +1,14 @@
 use {
-    bincode::Options,
     crossbeam_channel::Sender,
     futures::future::TryJoin,
-    itertools::Itertools,
     log::error,
     quinn::{
         ClientConfig, ConnectError, Connecting, Connection, ConnectionError, Endpoint,
-        EndpointConfig, ReadToEndError, RecvStream, SendStream, ServerConfig, TokioRuntime,
-        TransportConfig, VarInt, WriteError,
+        EndpointConfig, ReadExactError, ReadToEndError, RecvStream, SendStream, ServerConfig,
+        TokioRuntime, TransportConfig, VarInt, WriteError,
     },
     rcgen::RcgenError,
     rustls::{Certificate, PrivateKey},
-    serde_bytes::ByteBuf,
     solana_quic_client::nonblocking::quic_client::SkipServerVerification,
     solana_sdk::{packet::PACKET_DATA_SIZE, pubkey::Pubkey, signature::Keypair},
     solana_streamer::{
@@ -19,7 +16,7 @@ use {
     },
     std::{
         collections::{hash_map::Entry, HashMap},
-        io::{Cursor, Error as IoError},
+        io::Error as IoError,
         net::{IpAddr, SocketAddr, UdpSocket},
         ops::Deref,
         sync::Arc,
@@ -88,11 +85,17 @@ pub(crate) enum Error {
     ChannelSendError,
     #[error("Invalid Identity: {0:?}")]
     InvalidIdentity(SocketAddr),
+    #[error("Invalid Response Size: {0}")]
+    InvalidResponseSize(u64),
     #[error(transparent)]
     IoError(#[from] IoError),
     #[error("No Response Received")]
     NoResponseReceived,
     #[error(transparent)]
+    ReadExactError(#[from] ReadExactError),
+    #[error("read_exact Timeout")]
+    ReadExactTimeout,
+    #[error(transparent)]
     ReadToEndError(#[from] ReadToEndError),
     #[error("read_to_end Timeout")]
     ReadToEndTimeout,
@@ -381,34 +384,44 @@ async fn send_request(
     let (mut send_stream, mut recv_stream) = connection.open_bi().await?;
     send_stream.write_all(&bytes).await?;
     send_stream.finish().await?;
-    // Each response is at most PACKET_DATA_SIZE bytes and requires
-    // an additional 8 bytes to encode its length.
-    let size = PACKET_DATA_SIZE
-        .saturating_add(8)
-        .saturating_mul(num_expected_responses);
-    let response = tokio::time::timeout(READ_TIMEOUT_DURATION, recv_stream.read_to_end(size))
-        .await
-        .map_err(|_| Error::ReadToEndTimeout)??;
     let remote_address = connection.remote_address();
-    let mut cursor = Cursor::new(&response[..]);
-    std::iter::repeat_with(|| {
-        bincode::options()
-            .with_limit(response.len() as u64)
-            .with_fixint_encoding()
-            .allow_trailing_bytes()
-            .deserialize_from::<_, ByteBuf>(&mut cursor)
-            .map(ByteBuf::into_vec)
-            .ok()
-    })
-    .while_some()
-    .try_for_each(|chunk| {
+    // Forward each response as soon as it is read off the stream, so that
+    // batched requests do not have to wait for the last of their responses.
+    // The timeout applies to each response, as a batch may take far longer to
+    // serve in full than a single shred.
+    for _ in 0..num_expected_responses {
+        let chunk = tokio::time::timeout(READ_TIMEOUT_DURATION, read_response(&mut recv_stream))
+            .await
+            .map_err(|_| Error::ReadExactTimeout)??;
+        let Some(chunk) = chunk else {
+            break;
+        };
         response_sender
             .send((remote_address, chunk))
             .map_err(|err| {
                 close_quic_endpoint(endpoint);
                 Error::from(err)
-            })
-    })
+            })?;
+    }
+    Ok(())
+}
+
+// Reads a response of at most PACKET_DATA_SIZE bytes, prefixed with its
+// length encoded in 8 bytes. Returns None once the stream is finished.
+async fn read_response(recv_stream: &mut RecvStream) -> Result<Option<Vec<u8>>, Error> {
+    let mut size = [0u8; 8];
+    match recv_stream.read_exact(&mut size).await {
+        Ok(()) => (),
+        Err(ReadExactError::FinishedEarly) => return Ok(None),
+        Err(err) => return Err(Error::from(err)),
+    }
+    let size = u64::from_le_bytes(size);
+    if size > PACKET_DATA_SIZE as u64 {
+        return Err(Error::InvalidResponseSize(size));
+    }
+    let mut chunk = vec![0u8; size as usize];
+    recv_stream.read_exact(&mut chunk).await?;
+    Ok(Some(chunk))
 }
 
 async fn get_connection(
@@ -64,6 +64,8 @@ use {
 
 /// the number of slots to respond with when responding to `Orphan` requests
 pub const MAX_ORPHAN_REPAIR_RESPONSES: usize = 11;
+/// the number of consecutive shred indices spanned by a `ShredIndexBatch`
+pub const MAX_SHRED_INDEX_BATCH_SIZE: u64 = 1024;
 // Number of slots to cache their respective repair peers and sampling weights.
 pub(crate) const REPAIR_PEERS_CACHE_CAPACITY: usize = 128;
 // Limit cache entries ttl in order to avoid re-using outdated data.
@@ -99,6 +101,65 @@ pub enum ShredRepairType {
     HighestShred(Slot, u64),
     /// Requesting the missing shred at a particular index
     Shred(Slot, u64),
+    /// Requesting the missing shreds at several indices of the same slot;
+    /// only served over QUIC
+    ShredBatch(Slot, ShredIndexBatch),
+}
+
+/// Set of shred indices within `MAX_SHRED_INDEX_BATCH_SIZE` of the first one
+/// inserted, stored as a bitmap.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq, AbiExample)]
+pub struct ShredIndexBatch {
+    start: u64,
+    bits: [u64; MAX_SHRED_INDEX_BATCH_SIZE as usize / 64],
+}
+
+impl ShredIndexBatch {
+    pub fn new(start: u64) -> Self {
+        let mut batch = Self {
+            start,
+            bits: [0u64; MAX_SHRED_INDEX_BATCH_SIZE as usize / 64],
+        };
+        batch.insert(start);
+        batch
+    }
+
+    /// Returns false if the index is out of the batch's range.
+    pub fn insert(&mut self, index: u64) -> bool {
+        let Some(offset) = index.checked_sub(self.start) else {
+            return false;
+        };
+        if offset >= MAX_SHRED_INDEX_BATCH_SIZE {
+            return false;
+        }
+        self.bits[offset as usize / 64] |= 1u64 << (offset % 64);
+        true
+    }
+
+    pub fn contains(&self, index: u64) -> bool {
+        match index.checked_sub(self.start) {
+            Some(offset) if offset < MAX_SHRED_INDEX_BATCH_SIZE => {
+                self.bits[offset as usize / 64] & (1u64 << (offset % 64)) != 0
+            }
+            _ => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns the shred indices in increasing order. A batch received from a
+    /// peer may set bits past u64::MAX, which are ignored.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..MAX_SHRED_INDEX_BATCH_SIZE)
+            .filter(|offset| self.bits[*offset as usize / 64] & (1u64 << (offset % 64)) != 0)
+            .map_while(|offset| self.start.checked_add(offset))
+    }
 }
 
 impl ShredRepairType {
@@ -106,7 +167,8 @@ impl ShredRepairType {
         match self {
             ShredRepairType::Orphan(slot)
             | ShredRepairType::HighestShred(slot, _)
-            | ShredRepairType::Shred(slot, _) => *slot,
+            | ShredRepairType::Shred(slot, _)
+            | ShredRepairType::ShredBatch(slot, _) => *slot,
         }
     }
 }
@@ -117,6 +179,7 @@ impl RequestResponse for ShredRepairType {
         match self {
             ShredRepairType::Orphan(_) => MAX_ORPHAN_REPAIR_RESPONSES as u32,
             ShredRepairType::Shred(_, _) | ShredRepairType::HighestShred(_, _) => 1,
+            ShredRepairType::ShredBatch(_, shred_indices) => shred_indices.len() as u32,
         }
     }
     fn verify_response(&self, response_shred: &Shred) -> bool {
@@ -128,7 +191,48 @@ impl RequestResponse for ShredRepairType {
             ShredRepairType::Shred(slot, index) => {
                 response_shred.slot() == *slot && response_shred.index() as u64 == *index
             }
+            ShredRepairType::ShredBatch(slot, shred_indices) => {
+                response_shred.slot() == *slot
+                    && shred_indices.contains(response_shred.index() as u64)
+            }
+        }
+    }
+}
+
+/// Coalesces repairs of individual shreds from the same slot into
+/// `ShredRepairType::ShredBatch` requests, each taking the place of the first
+/// repair it absorbed, so that the order of repairs is otherwise preserved.
+pub(crate) fn batch_shred_repairs(repairs: Vec<ShredRepairType>) -> Vec<ShredRepairType> {
+    // Index into the output of the latest batch of each slot.
+    let mut batches = HashMap::<Slot, usize>::new();
+    let mut out = Vec::with_capacity(repairs.len());
+    for repair in repairs {
+        if let ShredRepairType::Shred(slot, shred_index) = repair {
+            if let Some(&k) = batches.get(&slot) {
+                if add_shred_index(&mut out[k], shred_index) {
+                    continue;
+                }
+            }
+            batches.insert(slot, out.len());
         }
+        out.push(repair);
+    }
+    out
+}
+
+// Returns false if the shred index does not fit in the repair.
+fn add_shred_index(repair: &mut ShredRepairType, shred_index: u64) -> bool {
+    match *repair {
+        ShredRepairType::Shred(slot, start) => {
+            let mut shred_indices = ShredIndexBatch::new(start);
+            let added = shred_indices.insert(shred_index);
+            if added {
+                *repair = ShredRepairType::ShredBatch(slot, shred_indices);
+            }
+            added
+        }
+        ShredRepairType::ShredBatch(_, ref mut shred_indices) => shred_indices.insert(shred_index),
+        ShredRepairType::Orphan(_) | ShredRepairType::HighestShred(_, _) => false,
     }
 }
 
@@ -173,11 +277,14 @@ struct ServeRepairStats {
     total_response_bytes_unstaked: usize,
     processed: usize,
     window_index: usize,
+    window_index_batch: usize,
+    window_index_batch_shreds: usize,
     highest_window_index: usize,
     orphan: usize,
     pong: usize,
     ancestor_hashes: usize,
     window_index_misses: usize,
+    window_index_batch_misses: usize,
     ping_cache_check_failed: usize,
     pings_sent: usize,
     decode_time_us: u64,
@@ -190,6 +297,7 @@ struct ServeRepairStats {
     err_sig_verify: usize,
     err_unsigned: usize,
     err_id_mismatch: usize,
+    err_batch_over_udp: usize,
 }
 
 #[derive(Debug, AbiExample, Deserialize, Serialize)]
@@ -217,7 +325,7 @@ pub(crate) type Ping = ping_pong::Ping<[u8; REPAIR_PING_TOKEN_SIZE]>;
 
 /// Window protocol messages
 #[derive(Debug, AbiEnumVisitor, AbiExample, Deserialize, Serialize)]
-#[frozen_abi(digest = "3VzVe3kMrG6ijkVPyCGeJVA9hQjWcFEZbAQPc5Zizrjm")]
+#[frozen_abi(digest = "4r6EfaNL18S4kGu6AMFReEGjWLe9niFzzjmqcUfGmB8z")]
 pub enum RepairProtocol {
     LegacyWindowIndex(LegacyContactInfo, Slot, u64),
     LegacyHighestWindowIndex(LegacyContactInfo, Slot, u64),
@@ -245,6 +353,11 @@ pub enum RepairProtocol {
         header: RepairRequestHeader,
         slot: Slot,
     },
+    WindowIndexBatch {
+        header: RepairRequestHeader,
+        slot: Slot,
+        shred_indices: ShredIndexBatch,
+    },
 }
 
 const REPAIR_REQUEST_PONG_SERIALIZED_BYTES: usize = PUBKEY_BYTES + HASH_BYTES + SIGNATURE_BYTES;
@@ -281,6 +394,7 @@ impl RepairProtocol {
             Self::HighestWindowIndex { header, .. } => &header.sender,
             Self::Orphan { header, .. } => &header.sender,
             Self::AncestorHashes { header, .. } => &header.sender,
+            Self::WindowIndexBatch { header, .. } => &header.sender,
         }
     }
 
@@ -297,7 +411,8 @@ impl RepairProtocol {
             | Self::WindowIndex { .. }
             | Self::HighestWindowIndex { .. }
             | Self::Orphan { .. }
-            | Self::AncestorHashes { .. } => true,
+            | Self::AncestorHashes { .. }
+            | Self::WindowIndexBatch { .. } => true,
         }
     }
 
@@ -307,6 +422,7 @@ impl RepairProtocol {
             | RepairProtocol::HighestWindowIndex { .. }
             | RepairProtocol::AncestorHashes { .. } => 1,
             RepairProtocol::Orphan { .. } => MAX_ORPHAN_REPAIR_RESPONSES,
+            RepairProtocol::WindowIndexBatch { shred_indices, .. } => shred_indices.len(),
             RepairProtocol::Pong(_) => 0, // no response
             RepairProtocol::LegacyWindowIndex(_, _, _)
             | RepairProtocol::LegacyHighestWindowIndex(_, _, _)
@@ -432,6 +548,25 @@ impl ServeRepair {
                     }
                     (batch, "WindowIndexWithNonce")
                 }
+                RepairProtocol::WindowIndexBatch {
+                    header: RepairRequestHeader { nonce, .. },
+                    slot,
+                    shred_indices,
+                } => {
+                    stats.window_index_batch += 1;
+                    stats.window_index_batch_shreds += shred_indices.len();
+                    let batch = Self::run_window_batch_request(
+                        recycler,
+                        from_addr,
+                        blockstore,
+                        *slot,
+                        shred_indices,
+                        *nonce,
+                    );
+                    let num_found = batch.as_ref().map(PacketBatch::len).unwrap_or_default();
+                    stats.window_index_batch_misses += shred_indices.len() - num_found;
+                    (batch, "WindowIndexBatch")
+                }
                 RepairProtocol::HighestWindowIndex {
                     header: RepairRequestHeader { nonce, .. },
                     slot,
@@ -755,6 +890,12 @@ impl ServeRepair {
             ),
             ("self_repair", stats.err_self_repair, i64),
             ("window_index", stats.window_index, i64),
+            ("window_index_batch", stats.window_index_batch, i64),
+            (
+                "window_index_batch_shreds",
+                stats.window_index_batch_shreds,
+                i64
+            ),
             (
                 "request-highest-window-index",
                 stats.highest_window_index,
@@ -768,6 +909,11 @@ impl ServeRepair {
             ),
             ("pong", stats.pong, i64),
             ("window_index_misses", stats.window_index_misses, i64),
+            (
+                "window_index_batch_misses",
+                stats.window_index_batch_misses,
+                i64
+            ),
             (
                 "ping_cache_check_failed",
                 stats.ping_cache_check_failed,
@@ -785,6 +931,7 @@ impl ServeRepair {
             ("err_sig_verify", stats.err_sig_verify, i64),
             ("err_unsigned", stats.err_unsigned, i64),
             ("err_id_mismatch", stats.err_id_mismatch, i64),
+            ("err_batch_over_udp", stats.err_batch_over_udp, i64),
         );
 
         *stats = ServeRepairStats::default();
@@ -863,7 +1010,8 @@ impl ServeRepair {
             RepairProtocol::WindowIndex { header, .. }
             | RepairProtocol::HighestWindowIndex { header, .. }
             | RepairProtocol::Orphan { header, .. }
-            | RepairProtocol::AncestorHashes { header, .. } => {
+            | RepairProtocol::AncestorHashes { header, .. }
+            | RepairProtocol::WindowIndexBatch { header, .. } => {
                 if &header.recipient != my_id {
                     return Err(Error::from(RepairVerifyError::IdMismatch));
                 }
@@ -909,7 +1057,8 @@ impl ServeRepair {
             match request {
                 RepairProtocol::WindowIndex { .. }
                 | RepairProtocol::HighestWindowIndex { .. }
-                | RepairProtocol::Orphan { .. } => {
+                | RepairProtocol::Orphan { .. }
+                | RepairProtocol::WindowIndexBatch { .. } => {
                     let ping = RepairResponse::Ping(ping);
                     Packet::from_data(Some(from_addr), ping).ok()
                 }
@@ -961,6 +1110,15 @@ impl ServeRepair {
             response_sender,
         } in requests.into_iter()
         {
+            // Batched requests are answered with up to MAX_SHRED_INDEX_BATCH_SIZE
+            // shreds, so are only served over QUIC, which verifies the
+            // requester's address, and not over UDP.
+            if matches!(&request, RepairProtocol::WindowIndexBatch { .. })
+                && response_sender.is_none()
+            {
+                stats.err_batch_over_udp += 1;
+                continue;
+            }
             if !data_budget.check(request.max_response_bytes()) {
                 stats.dropped_requests_outbound_bandwidth += 1;
                 continue;
@@ -1181,6 +1339,18 @@ impl ServeRepair {
                     slot: *slot,
                 }
             }
+            ShredRepairType::ShredBatch(slot, shred_indices) => {
+                for shred_index in shred_indices.iter() {
+                    repair_stats
+                        .shred
+                        .update(repair_peer_id, *slot, shred_index);
+                }
+                RepairProtocol::WindowIndexBatch {
+                    header,
+                    slot: *slot,
+                    shred_indices: *shred_indices,
+                }
+            }
         };
         Self::repair_proto_to_bytes(&request_proto, identity_keypair)
     }
@@ -1287,6 +1457,35 @@ impl ServeRepair {
         ))
     }
 
+    fn run_window_batch_request(
+        recycler: &PacketBatchRecycler,
+        from_addr: &SocketAddr,
+        blockstore: &Blockstore,
+        slot: Slot,
+        shred_indices: &ShredIndexBatch,
+        nonce: Nonce,
+    ) -> Option<PacketBatch> {
+        let packets: Vec<Packet> = shred_indices
+            .iter()
+            .filter_map(|shred_index| {
+                repair_response::repair_response_packet(
+                    blockstore,
+                    slot,
+                    shred_index,
+                    from_addr,
+                    nonce,
+                )
+            })
+            .collect();
+        (!packets.is_empty()).then(|| {
+            PacketBatch::new_unpinned_with_recycler_data(
+                recycler,
+                "run_window_batch_request",
+                packets,
+            )
+        })
+    }
+
     fn run_highest_window_request(
         recycler: &PacketBatchRecycler,
         from_addr: &SocketAddr,
@@ -1966,6 +2165,127 @@ mod tests {
         Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_shred_index_batch() {
+        let mut batch = ShredIndexBatch::new(100);
+        assert!(batch.insert(101));
+        assert!(batch.insert(100 + MAX_SHRED_INDEX_BATCH_SIZE - 1));
+        assert!(!batch.insert(99));
+        assert!(!batch.insert(100 + MAX_SHRED_INDEX_BATCH_SIZE));
+        assert!(batch.contains(100));
+        assert!(!batch.contains(102));
+        assert_eq!(batch.len(), 3);
+        assert_eq!(
+            batch.iter().collect::<Vec<_>>(),
+            vec![100, 101, 100 + MAX_SHRED_INDEX_BATCH_SIZE - 1]
+        );
+        let request = ShredRepairType::ShredBatch(7, batch);
+        assert_eq!(request.num_expected_responses(), 3);
+        // Indices past u64::MAX, only set by peers, are dropped
+        let mut bits = [0u64; MAX_SHRED_INDEX_BATCH_SIZE as usize / 64];
+        bits[0] = 0b111;
+        let overflowing = ShredIndexBatch {
+            start: u64::MAX - 1,
+            bits,
+        };
+        assert_eq!(
+            overflowing.iter().collect::<Vec<_>>(),
+            vec![u64::MAX - 1, u64::MAX]
+        );
+        assert_eq!(overflowing.len(), 2);
+        let overflowing = ShredIndexBatch {
+            start: u64::MAX,
+            bits: [u64::MAX - 1; MAX_SHRED_INDEX_BATCH_SIZE as usize / 64],
+        };
+        assert!(overflowing.is_empty());
+        let shred = Shred::new_from_data(7, 101, 1, &[], ShredFlags::empty(), 0, 0, 0);
+        assert!(request.verify_response(&shred));
+        let shred = Shred::new_from_data(7, 102, 1, &[], ShredFlags::empty(), 0, 0, 0);
+        assert!(!request.verify_response(&shred));
+        let shred = Shred::new_from_data(8, 101, 1, &[], ShredFlags::empty(), 0, 0, 0);
+        assert!(!request.verify_response(&shred));
+    }
+
+    #[test]
+    fn test_batch_shred_repairs() {
+        let repairs = vec![
+            ShredRepairType::Shred(5, 1),
+            ShredRepairType::Orphan(9),
+            ShredRepairType::Shred(6, 4),
+            ShredRepairType::Shred(5, 3),
+            ShredRepairType::HighestShred(5, 10),
+            ShredRepairType::Shred(5, 1 + MAX_SHRED_INDEX_BATCH_SIZE),
+            ShredRepairType::Shred(5, 2 + MAX_SHRED_INDEX_BATCH_SIZE),
+        ];
+        let mut batch = ShredIndexBatch::new(1);
+        batch.insert(3);
+        let mut next_batch = ShredIndexBatch::new(1 + MAX_SHRED_INDEX_BATCH_SIZE);
+        next_batch.insert(2 + MAX_SHRED_INDEX_BATCH_SIZE);
+        assert_eq!(
+            batch_shred_repairs(repairs),
+            vec![
+                ShredRepairType::ShredBatch(5, batch),
+                ShredRepairType::Orphan(9),
+                ShredRepairType::Shred(6, 4),
+                ShredRepairType::HighestShred(5, 10),
+                ShredRepairType::ShredBatch(5, next_batch),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_window_batch_request() {
+        let recycler = PacketBatchRecycler::default();
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+            let (slot, nonce) = (2, 9);
+            let mut shred_indices = ShredIndexBatch::new(1);
+            shred_indices.insert(2);
+            shred_indices.insert(5);
+            let rv = ServeRepair::run_window_batch_request(
+                &recycler,
+                &socketaddr_any!(),
+                &blockstore,
+                slot,
+                &shred_indices,
+                nonce,
+            );
+            assert!(rv.is_none());
+            let shreds: Vec<_> = [1, 3, 5]
+                .into_iter()
+                .map(|index| {
+                    Shred::new_from_data(slot, index, 1, &[], ShredFlags::empty(), 0, 2, 0)
+                })
+                .collect();
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+
+            let rv = ServeRepair::run_window_batch_request(
+                &recycler,
+                &socketaddr_any!(),
+                &blockstore,
+                slot,
+                &shred_indices,
+                nonce,
+            )
+            .expect("packets");
+            let request = ShredRepairType::ShredBatch(slot, shred_indices);
+            verify_responses(&request, rv.iter());
+            let indices: Vec<u32> = rv
+                .iter()
+                .map(|p| {
+                    assert_eq!(repair_response::nonce(p).unwrap(), nonce);
+                    Shred::new_from_serialized_shred(p.data(..).unwrap().to_vec())
+                        .unwrap()
+                        .index()
+                })
+                .collect();
+            assert_eq!(indices, vec![1, 5]);
+        }
+
+        Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+    }
+
     fn new_test_cluster_info() -> ClusterInfo {
         let keypair = Arc::new(Keypair::new());
         let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), timestamp());
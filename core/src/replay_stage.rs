@@ -35,6 +35,7 @@ use {
     crossbeam_channel::{Receiver, RecvTimeoutError, Sender},
     lazy_static::lazy_static,
     rayon::{prelude::*, ThreadPool},
+    solana_accounts_db::accounts_db::CalcAccountsHashDataSource,
     solana_entry::entry::VerifyRecyclers,
     solana_geyser_plugin_manager::block_metadata_notifier_interface::BlockMetadataNotifierLock,
     solana_gossip::cluster_info::ClusterInfo,
@@ -60,6 +61,7 @@ use {
         accounts_background_service::AbsRequestSender,
         bank::{bank_hash_details, Bank, NewBankOptions},
         bank_forks::{BankForks, MAX_ROOT_DISTANCE_FOR_VOTE_ONLY},
+        bank_overrides::BankOverrides,
         commitment::BlockCommitmentCache,
         prioritization_fee_cache::PrioritizationFeeCache,
     },
@@ -247,6 +249,8 @@ pub struct ReplayStageConfig {
     // duplicate voting which can lead to slashing.
     pub wait_to_vote_slot: Option<Slot>,
     pub replay_slots_concurrently: bool,
+    // Changes requested through the test validator's admin RPC, only warps are applied here
+    pub bank_overrides: Option<Arc<BankOverrides>>,
 }
 
 #[derive(Default)]
@@ -520,6 +524,7 @@ impl ReplayStage {
             tower_storage,
             wait_to_vote_slot,
             replay_slots_concurrently,
+            bank_overrides,
         } = config;
 
         trace!("replay stage");
@@ -1031,6 +1036,22 @@ impl ReplayStage {
                 // may add a bank that will not included in either of these maps.
                 drop(ancestors);
                 drop(descendants);
+                if let Some(bank_overrides) = &bank_overrides {
+                    if let Some(reset_blockhash) = Self::maybe_warp_to_slot(
+                        bank_overrides,
+                        &my_pubkey,
+                        &vote_account,
+                        &blockstore,
+                        &bank_forks,
+                        &poh_recorder,
+                        &leader_schedule_cache,
+                        &mut progress,
+                        &mut heaviest_subtree_fork_choice,
+                    ) {
+                        last_reset = reset_blockhash;
+                        tpu_has_bank = false;
+                    }
+                }
                 if !tpu_has_bank {
                     Self::maybe_start_leader(
                         &my_pubkey,
@@ -2520,6 +2541,88 @@ impl ReplayStage {
         }
     }
 
+    /// Warps a validator that is the only leader of its cluster to the slot requested through
+    /// `BankOverrides::warp_to_slot`. A frozen bank is warped to the slot before it from the bank
+    /// PoH was reset to, and PoH is reset onto the warped bank so that the next leader bank is
+    /// the requested slot. The request is left pending while a leader bank is in progress or an
+    /// epoch accounts hash calculation is in-flight. Returns the blockhash PoH was reset to.
+    #[allow(clippy::too_many_arguments)]
+    fn maybe_warp_to_slot(
+        bank_overrides: &BankOverrides,
+        my_pubkey: &Pubkey,
+        vote_account: &Pubkey,
+        blockstore: &Blockstore,
+        bank_forks: &RwLock<BankForks>,
+        poh_recorder: &RwLock<PohRecorder>,
+        leader_schedule_cache: &LeaderScheduleCache,
+        progress: &mut ProgressMap,
+        heaviest_subtree_fork_choice: &mut HeaviestSubtreeForkChoice,
+    ) -> Option<Hash> {
+        let parent_slot = {
+            let poh_recorder = poh_recorder.read().unwrap();
+            if poh_recorder.has_bank() {
+                return None;
+            }
+            poh_recorder.start_slot()
+        };
+        let parent = bank_forks.read().unwrap().get(parent_slot)?;
+        // Bank::warp_from_parent() calculates the epoch accounts hash itself
+        if parent
+            .rc
+            .accounts
+            .accounts_db
+            .epoch_accounts_hash_manager
+            .is_in_flight()
+        {
+            return None;
+        }
+        let warp_slot = bank_overrides.take_warp_slot()?;
+        // The warped bank is frozen, the leader bank at `warp_slot` is built on it
+        let pre_warp_slot = warp_slot.saturating_sub(1);
+        if pre_warp_slot <= parent_slot || bank_forks.read().unwrap().get(pre_warp_slot).is_some() {
+            warn!("Ignoring warp to slot {warp_slot}, PoH is already at slot {parent_slot}");
+            return None;
+        }
+        info!("Warping from slot {parent_slot} to slot {warp_slot}");
+
+        // An accounts hash calculation from storages will occur in warp_from_parent() below. This
+        // requires that the accounts cache has been flushed, which requires the parent slot to be
+        // rooted. The sole leader has no other fork that could be rooted instead.
+        parent.squash();
+        parent.force_flush_accounts_cache();
+        let warp_bank = Bank::warp_from_parent(
+            parent.clone(),
+            my_pubkey,
+            pre_warp_slot,
+            CalcAccountsHashDataSource::Storages,
+        );
+        let warp_bank = bank_forks.write().unwrap().insert(warp_bank);
+        let prev_leader_slot = progress.get_bank_prev_leader_slot(&warp_bank);
+        let mut fork_progress = ForkProgress::new_from_bank(
+            &warp_bank,
+            my_pubkey,
+            vote_account,
+            prev_leader_slot,
+            0,
+            0,
+        );
+        fork_progress.fork_stats.bank_hash = Some(warp_bank.hash());
+        progress.insert(pre_warp_slot, fork_progress);
+        heaviest_subtree_fork_choice.add_new_leaf_slot(
+            (warp_bank.slot(), warp_bank.hash()),
+            Some((parent.slot(), parent.hash())),
+        );
+
+        Self::reset_poh_recorder(
+            my_pubkey,
+            blockstore,
+            warp_bank.clone(),
+            poh_recorder,
+            leader_schedule_cache,
+        );
+        Some(warp_bank.last_blockhash())
+    }
+
     fn reset_poh_recorder(
         my_pubkey: &Pubkey,
         blockstore: &Blockstore,
@@ -8280,4 +8383,76 @@ pub(crate) mod tests {
         assert_eq!(reset_fork, Some(4));
         assert_eq!(failures, vec![HeaviestForkFailures::LockedOut(4),]);
     }
+
+    #[test]
+    fn test_maybe_warp_to_slot() {
+        let ReplayBlockstoreComponents {
+            blockstore,
+            validator_node_to_vote_keys,
+            my_pubkey,
+            leader_schedule_cache,
+            poh_recorder,
+            vote_simulator,
+            ..
+        } = replay_blockstore_components(None, 1, None::<GenerateVotes>);
+        let VoteSimulator {
+            bank_forks,
+            mut progress,
+            mut heaviest_subtree_fork_choice,
+            ..
+        } = vote_simulator;
+        let my_vote_pubkey = validator_node_to_vote_keys[&my_pubkey];
+        let bank_overrides = BankOverrides::default();
+        let mut maybe_warp_to_slot = || {
+            ReplayStage::maybe_warp_to_slot(
+                &bank_overrides,
+                &my_pubkey,
+                &my_vote_pubkey,
+                &blockstore,
+                &bank_forks,
+                &poh_recorder,
+                &leader_schedule_cache,
+                &mut progress,
+                &mut heaviest_subtree_fork_choice,
+            )
+        };
+        // Nothing happens until a warp is requested.
+        assert_eq!(maybe_warp_to_slot(), None);
+
+        // warpSlot: the leader bank at slot 10 is built on a frozen bank at slot 9.
+        bank_overrides.warp_to_slot(10);
+        let reset_blockhash = maybe_warp_to_slot().unwrap();
+        // warpEpoch: the leader bank at the first slot of epoch 2 runs the epoch boundary.
+        let epoch_schedule = bank_forks
+            .read()
+            .unwrap()
+            .root_bank()
+            .epoch_schedule()
+            .clone();
+        let epoch_slot = epoch_schedule.get_first_slot_in_epoch(2);
+        bank_overrides.warp_to_slot(epoch_slot);
+        let epoch_reset_blockhash = maybe_warp_to_slot().unwrap();
+        // Warping backwards is ignored.
+        bank_overrides.warp_to_slot(5);
+        assert_eq!(maybe_warp_to_slot(), None);
+        assert_eq!(bank_overrides.take_warp_slot(), None);
+
+        let warp_bank = bank_forks.read().unwrap().get(9).unwrap();
+        assert!(warp_bank.is_frozen());
+        assert_eq!(warp_bank.parent_slot(), 0);
+        assert_eq!(warp_bank.last_blockhash(), reset_blockhash);
+        assert!(progress.get(&9).is_some());
+        assert!(heaviest_subtree_fork_choice.contains_block(&(9, warp_bank.hash())));
+
+        let epoch_warp_bank = bank_forks.read().unwrap().get(epoch_slot - 1).unwrap();
+        assert!(epoch_warp_bank.is_frozen());
+        assert_eq!(epoch_warp_bank.parent_slot(), 9);
+        assert_eq!(epoch_warp_bank.epoch(), 1);
+        assert_eq!(epoch_warp_bank.last_blockhash(), epoch_reset_blockhash);
+        assert_eq!(
+            heaviest_subtree_fork_choice.best_overall_slot(),
+            (epoch_slot - 1, epoch_warp_bank.hash())
+        );
+        assert_eq!(poh_recorder.read().unwrap().start_slot(), epoch_slot - 1);
+    }
 }
@@ -42,7 +42,8 @@ use {
     },
     solana_runtime::{
         accounts_background_service::AbsRequestSender, bank_forks::BankForks,
-        commitment::BlockCommitmentCache, prioritization_fee_cache::PrioritizationFeeCache,
+        bank_overrides::BankOverrides, commitment::BlockCommitmentCache,
+        prioritization_fee_cache::PrioritizationFeeCache,
     },
     solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Keypair},
     solana_turbine::retransmit_stage::RetransmitStage,
@@ -88,6 +89,10 @@ pub struct TvuConfig {
     pub repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>,
     pub wait_for_vote_to_start_leader: bool,
     pub replay_slots_concurrently: bool,
+    // Slots to repair instead of the ones picked by repair weights, set during wen_restart
+    pub wen_restart_repair_slots: Option<Arc<RwLock<Vec<Slot>>>>,
+    // Changes requested through the test validator's admin RPC
+    pub bank_overrides: Option<Arc<BankOverrides>>,
 }
 
 impl Tvu {
@@ -205,6 +210,7 @@ impl Tvu {
                 repair_whitelist: tvu_config.repair_whitelist,
                 cluster_info: cluster_info.clone(),
                 cluster_slots: cluster_slots.clone(),
+                wen_restart_repair_slots: tvu_config.wen_restart_repair_slots,
             };
             WindowService::new(
                 blockstore.clone(),
@@ -256,6 +262,7 @@ impl Tvu {
             tower_storage: tower_storage.clone(),
             wait_to_vote_slot,
             replay_slots_concurrently: tvu_config.replay_slots_concurrently,
+            bank_overrides: tvu_config.bank_overrides,
         };
 
         let (voting_sender, voting_receiver) = unbounded();
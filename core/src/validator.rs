@@ -16,7 +16,10 @@ use {
         },
         ledger_metric_report_service::LedgerMetricReportService,
         poh_timing_report_service::PohTimingReportService,
-        repair::{self, serve_repair::ServeRepair, serve_repair_service::ServeRepairService},
+        repair::{
+            self, archive_repair_service::ArchiveRepairService, serve_repair::ServeRepair,
+            serve_repair_service::ServeRepairService,
+        },
         rewards_recorder_service::{RewardsRecorderSender, RewardsRecorderService},
         sample_performance_service::SamplePerformanceService,
         sigverify,
@@ -80,6 +83,7 @@ use {
         },
         rpc::JsonRpcConfig,
         rpc_completed_slots_service::RpcCompletedSlotsService,
+        rpc_duplicate_block_proof_service::RpcDuplicateBlockProofService,
         rpc_pubsub_service::{PubSubConfig, PubSubService},
         rpc_service::JsonRpcService,
         rpc_subscriptions::RpcSubscriptions,
@@ -119,7 +123,10 @@ use {
     solana_streamer::{socket::SocketAddrSpace, streamer::StakedNodes},
     solana_turbine::{self, broadcast_stage::BroadcastStageType},
     solana_vote_program::vote_state,
-    solana_wen_restart::wen_restart::wait_for_wen_restart,
+    solana_wen_restart::wen_restart::{
+        mark_wen_restart_done, wait_for_wen_restart, wen_restart_status, WenRestartConfig,
+        WenRestartStatus,
+    },
     std::{
         collections::{HashMap, HashSet},
         net::SocketAddr,
@@ -219,6 +226,9 @@ pub struct ValidatorConfig {
     pub known_validators: Option<HashSet<Pubkey>>, // None = trust all
     pub repair_validators: Option<HashSet<Pubkey>>, // None = repair from all
     pub repair_whitelist: Arc<RwLock<HashSet<Pubkey>>>, // Empty = repair with all
+    /// Directory of archives written by `solana-ledger-tool export-archive` to repair missing
+    /// rooted slots from
+    pub repair_archive_dir: Option<PathBuf>,
     pub gossip_validators: Option<HashSet<Pubkey>>, // None = gossip with all
     pub accounts_hash_fault_injector: Option<AccountsHashFaultInjector>,
     pub accounts_hash_interval_slots: u64,
@@ -232,6 +242,8 @@ pub struct ValidatorConfig {
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
     pub contact_debug_interval: u64,
     pub contact_save_interval: u64,
+    /// Bytes per second the node may send in gossip push messages, pull requests and pull responses
+    pub gossip_egress_budget: Option<u64>,
     pub send_transaction_service_config: send_transaction_service::Config,
     pub no_poh_speed_test: bool,
     pub no_os_memory_stats_reporting: bool,
@@ -301,6 +313,8 @@ impl Default for ValidatorConfig {
             debug_keys: None,
             contact_debug_interval: DEFAULT_CONTACT_DEBUG_INTERVAL_MILLIS,
             contact_save_interval: DEFAULT_CONTACT_SAVE_INTERVAL_MILLIS,
+            gossip_egress_budget: None,
+            repair_archive_dir: None,
             send_transaction_service_config: send_transaction_service::Config::default(),
             no_poh_speed_test: true,
             no_os_memory_stats_reporting: true,
@@ -443,6 +457,7 @@ pub struct Validator {
     json_rpc_service: Option<JsonRpcService>,
     pubsub_service: Option<PubSubService>,
     rpc_completed_slots_service: JoinHandle<()>,
+    rpc_duplicate_block_proof_service: JoinHandle<()>,
     optimistically_confirmed_bank_tracker: Option<OptimisticallyConfirmedBankTracker>,
     transaction_status_service: Option<TransactionStatusService>,
     rewards_recorder_service: Option<RewardsRecorderService>,
@@ -454,6 +469,7 @@ pub struct Validator {
     stats_reporter_service: StatsReporterService,
     gossip_service: GossipService,
     serve_repair_service: ServeRepairService,
+    archive_repair_service: Option<ArchiveRepairService>,
     completed_data_sets_service: CompletedDataSetsService,
     snapshot_packager_service: Option<SnapshotPackagerService>,
     poh_recorder: Arc<RwLock<PohRecorder>>,
@@ -474,6 +490,7 @@ pub struct Validator {
     repair_quic_endpoint: Endpoint,
     repair_quic_endpoint_runtime: Option<TokioRuntime>,
     repair_quic_endpoint_join_handle: repair::quic_endpoint::AsyncTryJoinHandle,
+    wen_restart_thread: Option<JoinHandle<()>>,
 }
 
 impl Validator {
@@ -501,6 +518,38 @@ impl Validator {
         info!("identity pubkey: {id}");
         info!("vote account pubkey: {vote_account}");
 
+        // Once wen_restart generated the restart snapshot, the validator restarts on it like a
+        // manual restart with --wait-for-supermajority, --expected-bank-hash and
+        // --expected-shred-version would.
+        let wen_restart_status = config
+            .wen_restart_proto_path
+            .as_ref()
+            .map(wen_restart_status)
+            .transpose()
+            .map_err(|err| format!("Failed to read wen_restart progress: {err:?}"))?;
+        let wen_restart_validator_config;
+        let config = match wen_restart_status {
+            Some(WenRestartStatus::WaitingForSupermajority {
+                slot,
+                bankhash,
+                shred_version,
+            }) => {
+                info!(
+                    "wen_restart waiting for supermajority at slot {slot} bank hash {bankhash} \
+                    shred version {shred_version}"
+                );
+                wen_restart_validator_config = ValidatorConfig {
+                    wait_for_supermajority: Some(slot),
+                    expected_bank_hash: Some(bankhash),
+                    expected_shred_version: Some(shred_version),
+                    ..config.clone()
+                };
+                &wen_restart_validator_config
+            }
+            _ => config,
+        };
+        let in_wen_restart = wen_restart_status == Some(WenRestartStatus::InProgress);
+
         if !config.no_os_network_stats_reporting {
             verify_net_stats_access()
                 .map_err(|err| format!("Failed to access network stats: {err:?}"))?;
@@ -736,6 +785,9 @@ impl Validator {
             socket_addr_space,
         );
         cluster_info.set_contact_debug_interval(config.contact_debug_interval);
+        if let Some(gossip_egress_budget) = config.gossip_egress_budget {
+            cluster_info.set_egress_budget(gossip_egress_budget);
+        }
         cluster_info.set_entrypoints(cluster_entrypoints);
         cluster_info.restore_contact_info(ledger_path, config.contact_save_interval);
         let cluster_info = Arc::new(cluster_info);
@@ -1100,11 +1152,21 @@ impl Validator {
         )
         .map_err(|err| format!("wait_for_supermajority failed: {err:?}"))?;
 
+        if waited_for_supermajority
+            && matches!(
+                wen_restart_status,
+                Some(WenRestartStatus::WaitingForSupermajority { .. })
+            )
+        {
+            mark_wen_restart_done(config.wen_restart_proto_path.as_ref().unwrap())
+                .map_err(|err| format!("Failed to update wen_restart progress: {err:?}"))?;
+        }
+
         let ledger_metric_report_service =
             LedgerMetricReportService::new(blockstore.clone(), exit.clone());
 
-        let wait_for_vote_to_start_leader =
-            !waited_for_supermajority && !config.no_wait_for_vote_to_start_leader;
+        let wait_for_vote_to_start_leader = in_wen_restart
+            || (!waited_for_supermajority && !config.no_wait_for_vote_to_start_leader);
 
         let poh_service = PohService::new(
             poh_recorder.clone(),
@@ -1134,6 +1196,16 @@ impl Validator {
             exit.clone(),
         );
 
+        let (duplicate_slot_proof_sender, duplicate_slot_proof_receiver) = unbounded();
+        blockstore.add_duplicate_slot_proof_signal(duplicate_slot_proof_sender);
+        let rpc_duplicate_block_proof_service = RpcDuplicateBlockProofService::spawn(
+            duplicate_slot_proof_receiver,
+            blockstore.clone(),
+            leader_schedule_cache.clone(),
+            rpc_subscriptions.clone(),
+            exit.clone(),
+        );
+
         let (banking_tracer, tracer_thread) =
             BankingTracer::new((config.banking_trace_dir_byte_limit > 0).then_some((
                 &blockstore.banking_trace_path(),
@@ -1211,7 +1283,7 @@ impl Validator {
             )
             .unwrap();
 
-        let in_wen_restart = config.wen_restart_proto_path.is_some() && !waited_for_supermajority;
+        let wen_restart_repair_slots = in_wen_restart.then(|| Arc::new(RwLock::new(Vec::new())));
         let tower = match process_blockstore.process_to_create_tower() {
             Ok(tower) => {
                 info!("Tower state: {:?}", tower);
@@ -1268,10 +1340,17 @@ impl Validator {
                 repair_whitelist: config.repair_whitelist.clone(),
                 wait_for_vote_to_start_leader,
                 replay_slots_concurrently: config.replay_slots_concurrently,
+                wen_restart_repair_slots: wen_restart_repair_slots.clone(),
+                bank_overrides: config.runtime_config.bank_overrides.clone(),
             },
             &max_slots,
             block_metadata_notifier,
-            config.wait_to_vote_slot,
+            // Never vote during wen_restart, the cluster restarts from the heaviest fork instead
+            if in_wen_restart {
+                Some(Slot::MAX)
+            } else {
+                config.wait_to_vote_slot
+            },
             accounts_background_request_sender,
             config.runtime_config.log_messages_bytes_limit,
             &connection_cache,
@@ -1282,20 +1361,45 @@ impl Validator {
             repair_quic_endpoint_sender,
         )?;
 
-        if in_wen_restart {
-            info!("Waiting for wen_restart phase one to finish");
-            match wait_for_wen_restart(
-                &config.wen_restart_proto_path.clone().unwrap(),
-                last_vote,
+        let archive_repair_service = config.repair_archive_dir.clone().map(|archive_dir| {
+            ArchiveRepairService::new(
+                archive_dir,
                 blockstore.clone(),
-                cluster_info.clone(),
-            ) {
-                Ok(()) => {
-                    return Err("wen_restart phase one completedy".to_string());
-                }
-                Err(e) => return Err(format!("wait_for_wen_restart failed: {e:?}")),
+                bank_forks.clone(),
+                leader_schedule_cache.clone(),
+                exit.clone(),
+            )
+        });
+
+        let wen_restart_thread = in_wen_restart.then(|| {
+            let wen_restart_config = WenRestartConfig {
+                wen_restart_path: config.wen_restart_proto_path.clone().unwrap(),
+                last_vote,
+                blockstore: blockstore.clone(),
+                cluster_info: cluster_info.clone(),
+                bank_forks: bank_forks.clone(),
+                wen_restart_repair_slots,
+                wait_for_supermajority_threshold_percent: WAIT_FOR_SUPERMAJORITY_THRESHOLD_PERCENT,
+                snapshot_config: config.snapshot_config.clone(),
+                genesis_config_hash: genesis_config.hash(),
+                exit: exit.clone(),
             };
-        }
+            let validator_exit = config.validator_exit.clone();
+            Builder::new()
+                .name("solWenRestart".to_string())
+                .spawn(move || {
+                    info!("Waiting for wen_restart to generate the restart snapshot");
+                    match wait_for_wen_restart(wen_restart_config) {
+                        Ok(()) => info!(
+                            "wen_restart generated the restart snapshot, the validator exits \
+                            and waits for supermajority once restarted"
+                        ),
+                        Err(err) => error!("wait_for_wen_restart failed: {err:?}"),
+                    }
+                    validator_exit.write().unwrap().exit();
+                })
+                .unwrap()
+        });
 
         let tpu = Tpu::new(
             &cluster_info,
@@ -1352,9 +1456,11 @@ impl Validator {
             stats_reporter_service,
             gossip_service,
             serve_repair_service,
+            archive_repair_service,
             json_rpc_service,
             pubsub_service,
             rpc_completed_slots_service,
+            rpc_duplicate_block_proof_service,
             optimistically_confirmed_bank_tracker,
             transaction_status_service,
             rewards_recorder_service,
@@ -1384,6 +1490,7 @@ impl Validator {
             repair_quic_endpoint,
             repair_quic_endpoint_runtime,
             repair_quic_endpoint_join_handle,
+            wen_restart_thread,
         })
     }
 
@@ -1426,6 +1533,10 @@ impl Validator {
     }
 
     pub fn join(self) {
+        if let Some(wen_restart_thread) = self.wen_restart_thread {
+            wen_restart_thread.join().expect("wen_restart");
+        }
+
         drop(self.bank_forks);
         drop(self.cluster_info);
 
@@ -1444,6 +1555,10 @@ impl Validator {
             .join()
             .expect("rpc_completed_slots_service");
 
+        self.rpc_duplicate_block_proof_service
+            .join()
+            .expect("rpc_duplicate_block_proof_service");
+
         if let Some(optimistically_confirmed_bank_tracker) =
             self.optimistically_confirmed_bank_tracker
         {
@@ -1497,6 +1612,11 @@ impl Validator {
         self.serve_repair_service
             .join()
             .expect("serve_repair_service");
+        if let Some(archive_repair_service) = self.archive_repair_service {
+            archive_repair_service
+                .join()
+                .expect("archive_repair_service");
+        }
         self.repair_quic_endpoint_runtime
             .map(|runtime| runtime.block_on(self.repair_quic_endpoint_join_handle))
             .transpose()
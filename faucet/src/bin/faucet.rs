@@ -3,13 +3,14 @@ use {
     log::*,
     solana_clap_utils::input_parsers::{lamports_of_sol, value_of},
     solana_faucet::{
-        faucet::{run_faucet, Faucet, FAUCET_PORT},
+        faucet::{run_faucet, Faucet, TokenAirdropConfig, FAUCET_PORT},
         socketaddr,
     },
     solana_sdk::signature::read_keypair_file,
     std::{
         collections::HashSet,
         net::{IpAddr, Ipv4Addr, SocketAddr},
+        path::PathBuf,
         sync::{Arc, Mutex},
         thread,
     },
@@ -67,6 +68,30 @@ async fn main() {
                     recipient address will be used to check request limits instead",
                 ),
         )
+        .arg(
+            Arg::with_name("airdrop_token")
+                .long("airdrop-token")
+                .value_name("MINT[:SOURCE[:PER_REQUEST_CAP[:PER_TIME_CAP]]]")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|value| value.parse::<TokenAirdropConfig>().map(|_| ()))
+                .help(
+                    "Allow airdrops of an SPL token mint. SOURCE is `mint` if the faucet \
+                    keypair is the mint authority (default), or `transfer` to send tokens out \
+                    of the faucet's associated token account. Caps are in the mint's base \
+                    units and apply per request, and per IP and recipient for the time slice",
+                ),
+        )
+        .arg(
+            Arg::with_name("quota_cache")
+                .long("quota-cache")
+                .value_name("PATH")
+                .takes_value(true)
+                .help(
+                    "File in which to persist request limit totals, so they survive \
+                    faucet restarts within a time slice",
+                ),
+        )
         .get_matches();
 
     let faucet_keypair = read_keypair_file(matches.value_of("keypair").unwrap())
@@ -81,15 +106,27 @@ async fn main() {
         .into_iter()
         .collect();
 
+    let airdrop_tokens =
+        values_t!(matches.values_of("airdrop_token"), TokenAirdropConfig).unwrap_or_default();
+
     let faucet_addr = socketaddr!(Ipv4Addr::UNSPECIFIED, FAUCET_PORT);
 
-    let faucet = Arc::new(Mutex::new(Faucet::new_with_allowed_ips(
+    let mut faucet = Faucet::new_with_allowed_ips(
         faucet_keypair,
         time_slice,
         per_time_cap,
         per_request_cap,
         allowed_ips,
-    )));
+    );
+    for config in airdrop_tokens {
+        faucet.add_airdrop_token(config);
+    }
+    if let Some(quota_cache) = matches.value_of("quota_cache") {
+        faucet
+            .set_quota_cache_path(PathBuf::from(quota_cache))
+            .expect("failed to load faucet quota cache");
+    }
+    let faucet = Arc::new(Mutex::new(faucet));
 
     let faucet1 = faucet.clone();
     thread::spawn(move || loop {
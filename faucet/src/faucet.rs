@@ -3,6 +3,15 @@
 //! The Solana Faucet builds and sends airdrop transactions,
 //! checking requests against a single-request cap and a per-IP limit
 //! for a given time time_slice.
+//!
+//! Besides SOL, the faucet can be configured to airdrop SPL tokens, either by
+//! minting them (when the faucet keypair is the mint authority) or by transferring
+//! them out of the faucet's associated token account. Token airdrops are limited
+//! per-request, per-IP and per-recipient, with separate caps for each mint.
+//!
+//! The accumulated per-IP and per-recipient totals can optionally be persisted to a
+//! file, so that restarting the faucet does not reset the quotas of the current
+//! time slice.
 
 use {
     bincode::{deserialize, serialize, serialized_size},
@@ -17,15 +26,23 @@ use {
         message::Message,
         native_token::lamports_to_sol,
         packet::PACKET_DATA_SIZE,
+        program_error::ProgramError,
         pubkey::Pubkey,
         signature::{Keypair, Signer},
         system_instruction,
+        timing::timestamp,
         transaction::Transaction,
     },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account_idempotent,
+    },
     std::{
         collections::{HashMap, HashSet},
+        fs,
         io::{Read, Write},
         net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+        path::{Path, PathBuf},
+        str::FromStr,
         sync::{Arc, Mutex},
         thread,
         time::Duration,
@@ -73,6 +90,24 @@ pub enum FaucetError {
 
     #[error("limit reached; req: ◎{0}, to: {1}, current: ◎{2}, cap: ◎{3}")]
     PerTimeCapExceeded(f64, String, f64, f64),
+
+    #[error("airdrops of mint {0} are not supported by this faucet")]
+    UnsupportedMint(Pubkey),
+
+    #[error("token request too large; req: {0}, mint: {1}, cap: {2}")]
+    TokenPerRequestCapExceeded(u64, Pubkey, u64),
+
+    #[error("token limit reached; req: {0}, mint: {1}, to: {2}, current: {3}, cap: {4}")]
+    TokenPerTimeCapExceeded(u64, Pubkey, String, u64, u64),
+
+    #[error("token instruction error: {0}")]
+    TokenInstruction(#[from] ProgramError),
+
+    #[error("quota cache error: {0}")]
+    QuotaCache(#[from] serde_json::Error),
+
+    #[error("invalid faucet request tag: {0}")]
+    InvalidRequestTag(u32),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -82,6 +117,158 @@ pub enum FaucetRequest {
         to: Pubkey,
         blockhash: Hash,
     },
+    GetAirdropTokens {
+        /// Amount of tokens, in the mint's base units
+        amount: u64,
+        mint: Pubkey,
+        /// Wallet address; tokens are sent to its associated token account
+        to: Pubkey,
+        blockhash: Hash,
+    },
+}
+
+impl FaucetRequest {
+    /// Returns the serialized size of the request with the given bincode variant tag,
+    /// including the tag itself
+    fn serialized_size_for_tag(tag: u32) -> Result<usize, FaucetError> {
+        let request = match tag {
+            0 => FaucetRequest::GetAirdrop {
+                lamports: u64::default(),
+                to: Pubkey::default(),
+                blockhash: Hash::default(),
+            },
+            1 => FaucetRequest::GetAirdropTokens {
+                amount: u64::default(),
+                mint: Pubkey::default(),
+                to: Pubkey::default(),
+                blockhash: Hash::default(),
+            },
+            _ => return Err(FaucetError::InvalidRequestTag(tag)),
+        };
+        Ok(serialized_size(&request)? as usize)
+    }
+}
+
+/// How the faucet sources the tokens of an airdropped mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAirdropSource {
+    /// The faucet keypair is the mint authority and mints new tokens to the recipient
+    Mint,
+    /// Tokens are transferred out of the faucet keypair's associated token account
+    Transfer,
+}
+
+impl FromStr for TokenAirdropSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mint" => Ok(Self::Mint),
+            "transfer" => Ok(Self::Transfer),
+            _ => Err(format!("invalid token airdrop source: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAirdropConfig {
+    pub mint: Pubkey,
+    pub source: TokenAirdropSource,
+    /// Request limit for a single request, in the mint's base units
+    pub per_request_cap: Option<u64>,
+    /// Request limit per IP and per recipient for a time slice, in the mint's base units
+    pub per_time_cap: Option<u64>,
+}
+
+impl FromStr for TokenAirdropConfig {
+    type Err = String;
+
+    /// Parses `MINT[:SOURCE[:PER_REQUEST_CAP[:PER_TIME_CAP]]]`, where `SOURCE` is `mint` or
+    /// `transfer` and defaults to `mint`. An empty cap means no limit.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let mint = parts
+            .next()
+            .unwrap_or_default()
+            .parse::<Pubkey>()
+            .map_err(|err| format!("invalid mint address: {err}"))?;
+        let source = match parts.next() {
+            Some(source) => source.parse()?,
+            None => TokenAirdropSource::Mint,
+        };
+        let parse_cap = |cap: Option<&str>| -> Result<Option<u64>, String> {
+            match cap {
+                None | Some("") => Ok(None),
+                Some(cap) => cap
+                    .parse()
+                    .map(Some)
+                    .map_err(|err| format!("invalid cap {cap}: {err}")),
+            }
+        };
+        let per_request_cap = parse_cap(parts.next())?;
+        let per_time_cap = parse_cap(parts.next())?;
+        if parts.next().is_some() {
+            return Err(format!("too many fields in token airdrop config: {s}"));
+        }
+        Ok(Self {
+            mint,
+            source,
+            per_request_cap,
+            per_time_cap,
+        })
+    }
+}
+
+#[derive(Default)]
+struct TokenQuotaCache {
+    ip_cache: HashMap<IpAddr, u64>,
+    address_cache: HashMap<Pubkey, u64>,
+}
+
+/// On-disk representation of the faucet's request caches
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct PersistedQuotaCache {
+    ip_cache: HashMap<String, u64>,
+    address_cache: HashMap<String, u64>,
+}
+
+impl PersistedQuotaCache {
+    fn new(ip_cache: &HashMap<IpAddr, u64>, address_cache: &HashMap<Pubkey, u64>) -> Self {
+        Self {
+            ip_cache: ip_cache
+                .iter()
+                .map(|(ip, total)| (ip.to_string(), *total))
+                .collect(),
+            address_cache: address_cache
+                .iter()
+                .map(|(address, total)| (address.to_string(), *total))
+                .collect(),
+        }
+    }
+
+    fn into_caches(self) -> (HashMap<IpAddr, u64>, HashMap<Pubkey, u64>) {
+        let ip_cache = self
+            .ip_cache
+            .into_iter()
+            .filter_map(|(ip, total)| Some((ip.parse().ok()?, total)))
+            .collect();
+        let address_cache = self
+            .address_cache
+            .into_iter()
+            .filter_map(|(address, total)| Some((address.parse().ok()?, total)))
+            .collect();
+        (ip_cache, address_cache)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct PersistedQuotas {
+    /// Time the caches were last cleared, in milliseconds since the UNIX epoch
+    last_cleared: u64,
+    lamports: PersistedQuotaCache,
+    tokens: HashMap<String, PersistedQuotaCache>,
 }
 
 pub enum FaucetTransaction {
@@ -97,6 +284,10 @@ pub struct Faucet {
     per_time_cap: Option<u64>,
     per_request_cap: Option<u64>,
     allowed_ips: HashSet<IpAddr>,
+    token_configs: HashMap<Pubkey, TokenAirdropConfig>,
+    token_caches: HashMap<Pubkey, TokenQuotaCache>,
+    caches_last_cleared: u64,
+    quota_cache_path: Option<PathBuf>,
 }
 
 impl Faucet {
@@ -141,6 +332,95 @@ impl Faucet {
             per_time_cap,
             per_request_cap,
             allowed_ips,
+            token_configs: HashMap::new(),
+            token_caches: HashMap::new(),
+            caches_last_cleared: timestamp(),
+            quota_cache_path: None,
+        }
+    }
+
+    /// Enables airdrops of the configured SPL token mint
+    pub fn add_airdrop_token(&mut self, config: TokenAirdropConfig) {
+        if let Some((per_request_cap, per_time_cap)) =
+            config.per_request_cap.zip(config.per_time_cap)
+        {
+            if per_time_cap < per_request_cap {
+                warn!(
+                    "mint {}: per_time_cap {} < per_request_cap {}; \
+                    maximum single requests will fail",
+                    config.mint, per_time_cap, per_request_cap,
+                );
+            }
+        }
+        self.token_configs.insert(config.mint, config);
+    }
+
+    /// Persists the request caches to `path` after every request, restoring them from
+    /// `path` first if it holds the caches of a still-running time slice
+    pub fn set_quota_cache_path(&mut self, path: PathBuf) -> Result<(), FaucetError> {
+        if path.exists() {
+            let quotas: PersistedQuotas = serde_json::from_slice(&fs::read(&path)?)?;
+            let elapsed = timestamp().saturating_sub(quotas.last_cleared);
+            if u128::from(elapsed) < self.time_slice.as_millis() {
+                info!("Restoring faucet quotas from {}", path.display());
+                self.restore_quotas(quotas);
+            } else {
+                info!(
+                    "Ignoring expired faucet quotas in {}; time slice has elapsed",
+                    path.display()
+                );
+            }
+        }
+        self.quota_cache_path = Some(path);
+        self.save_quota_cache();
+        Ok(())
+    }
+
+    fn restore_quotas(&mut self, quotas: PersistedQuotas) {
+        (self.ip_cache, self.address_cache) = quotas.lamports.into_caches();
+        self.token_caches = quotas
+            .tokens
+            .into_iter()
+            .filter_map(|(mint, cache)| {
+                let (ip_cache, address_cache) = cache.into_caches();
+                Some((
+                    mint.parse().ok()?,
+                    TokenQuotaCache {
+                        ip_cache,
+                        address_cache,
+                    },
+                ))
+            })
+            .collect();
+        self.caches_last_cleared = quotas.last_cleared;
+    }
+
+    fn persisted_quotas(&self) -> PersistedQuotas {
+        PersistedQuotas {
+            last_cleared: self.caches_last_cleared,
+            lamports: PersistedQuotaCache::new(&self.ip_cache, &self.address_cache),
+            tokens: self
+                .token_caches
+                .iter()
+                .map(|(mint, cache)| {
+                    (
+                        mint.to_string(),
+                        PersistedQuotaCache::new(&cache.ip_cache, &cache.address_cache),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn save_quota_cache(&self) {
+        if let Some(path) = &self.quota_cache_path {
+            if let Err(err) = write_quota_cache(path, &self.persisted_quotas()) {
+                warn!(
+                    "Failed to persist faucet quotas to {}: {}",
+                    path.display(),
+                    err
+                );
+            }
         }
     }
 
@@ -164,9 +444,71 @@ impl Faucet {
         Ok(())
     }
 
+    pub fn check_token_time_request_limit(
+        &mut self,
+        mint: Pubkey,
+        request_amount: u64,
+        ip: Option<IpAddr>,
+        to: Pubkey,
+    ) -> Result<(), FaucetError> {
+        let per_time_cap = self
+            .token_configs
+            .get(&mint)
+            .ok_or(FaucetError::UnsupportedMint(mint))?
+            .per_time_cap;
+        let cache = self.token_caches.entry(mint).or_default();
+        let ip_total = ip.map(|ip| {
+            let total = cache.ip_cache.entry(ip).or_default();
+            *total = total.saturating_add(request_amount);
+            (ip.to_string(), *total)
+        });
+        let address_total = cache.address_cache.entry(to).or_default();
+        *address_total = address_total.saturating_add(request_amount);
+        let address_total = (to.to_string(), *address_total);
+
+        for (requester, new_total) in ip_total.into_iter().chain(Some(address_total)) {
+            datapoint_info!(
+                "faucet-token-airdrop",
+                ("request_amount", request_amount, i64),
+                ("mint", mint.to_string(), String),
+                ("requester", requester, String),
+                ("new_total", new_total, i64)
+            );
+            if let Some(cap) = per_time_cap {
+                if new_total > cap {
+                    return Err(FaucetError::TokenPerTimeCapExceeded(
+                        request_amount,
+                        mint,
+                        requester,
+                        new_total,
+                        cap,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn clear_caches(&mut self) {
         self.ip_cache.clear();
         self.address_cache.clear();
+        self.token_caches.clear();
+        self.caches_last_cleared = timestamp();
+        self.save_quota_cache();
+    }
+
+    fn memo_transaction(&self, memo: String, blockhash: Hash) -> FaucetTransaction {
+        let faucet_pubkey = self.faucet_keypair.pubkey();
+        let memo_instruction = Instruction {
+            program_id: Pubkey::from(spl_memo::id().to_bytes()),
+            accounts: vec![],
+            data: memo.as_bytes().to_vec(),
+        };
+        let message = Message::new(&[memo_instruction], Some(&faucet_pubkey));
+        FaucetTransaction::Memo((
+            Transaction::new(&[&self.faucet_keypair], message, blockhash),
+            memo,
+        ))
     }
 
     /// Checks per-request and per-time-ip limits; if both pass, this method returns a signed
@@ -201,16 +543,7 @@ impl Faucet {
                                 lamports_to_sol(cap),
                             )
                         );
-                        let memo_instruction = Instruction {
-                            program_id: Pubkey::from(spl_memo::id().to_bytes()),
-                            accounts: vec![],
-                            data: memo.as_bytes().to_vec(),
-                        };
-                        let message = Message::new(&[memo_instruction], Some(&mint_pubkey));
-                        return Ok(FaucetTransaction::Memo((
-                            Transaction::new(&[&self.faucet_keypair], message, blockhash),
-                            memo,
-                        )));
+                        return Ok(self.memo_transaction(memo, blockhash));
                     }
                 }
                 if !ip.is_loopback() && !self.allowed_ips.contains(&ip) {
@@ -227,6 +560,69 @@ impl Faucet {
                     blockhash,
                 )))
             }
+            FaucetRequest::GetAirdropTokens {
+                amount,
+                mint,
+                to,
+                blockhash,
+            } => {
+                let faucet_pubkey = self.faucet_keypair.pubkey();
+                info!(
+                    "Requesting airdrop of {} of mint {} to {:?}",
+                    amount, mint, to
+                );
+
+                let config = *self
+                    .token_configs
+                    .get(&mint)
+                    .ok_or(FaucetError::UnsupportedMint(mint))?;
+                if let Some(cap) = config.per_request_cap {
+                    if amount > cap {
+                        let memo = format!(
+                            "{}",
+                            FaucetError::TokenPerRequestCapExceeded(amount, mint, cap)
+                        );
+                        return Ok(self.memo_transaction(memo, blockhash));
+                    }
+                }
+                let ip = (!ip.is_loopback() && !self.allowed_ips.contains(&ip)).then_some(ip);
+                self.check_token_time_request_limit(mint, amount, ip, to)?;
+
+                let recipient_token_address = get_associated_token_address(&to, &mint);
+                let create_instruction = create_associated_token_account_idempotent(
+                    &faucet_pubkey,
+                    &to,
+                    &mint,
+                    &spl_token::id(),
+                );
+                let airdrop_instruction = match config.source {
+                    TokenAirdropSource::Mint => spl_token::instruction::mint_to(
+                        &spl_token::id(),
+                        &mint,
+                        &recipient_token_address,
+                        &faucet_pubkey,
+                        &[],
+                        amount,
+                    )?,
+                    TokenAirdropSource::Transfer => spl_token::instruction::transfer(
+                        &spl_token::id(),
+                        &get_associated_token_address(&faucet_pubkey, &mint),
+                        &recipient_token_address,
+                        &faucet_pubkey,
+                        &[],
+                        amount,
+                    )?,
+                };
+                let message = Message::new(
+                    &[create_instruction, airdrop_instruction],
+                    Some(&faucet_pubkey),
+                );
+                Ok(FaucetTransaction::Airdrop(Transaction::new(
+                    &[&self.faucet_keypair],
+                    message,
+                    blockhash,
+                )))
+            }
         }
     }
 
@@ -240,6 +636,7 @@ impl Faucet {
 
         info!("Airdrop transaction requested...{:?}", req);
         let res = self.build_airdrop_transaction(req, ip);
+        self.save_quota_cache();
         match res {
             Ok(tx) => {
                 let tx = match tx {
@@ -284,14 +681,44 @@ pub fn request_airdrop_transaction(
         "request_airdrop_transaction: faucet_addr={} id={} lamports={} blockhash={}",
         faucet_addr, id, lamports, blockhash
     );
+    request_transaction(
+        faucet_addr,
+        FaucetRequest::GetAirdrop {
+            lamports,
+            blockhash,
+            to: *id,
+        },
+    )
+}
 
+pub fn request_airdrop_tokens_transaction(
+    faucet_addr: &SocketAddr,
+    id: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    blockhash: Hash,
+) -> Result<Transaction, FaucetError> {
+    info!(
+        "request_airdrop_tokens_transaction: faucet_addr={} id={} mint={} amount={} blockhash={}",
+        faucet_addr, id, mint, amount, blockhash
+    );
+    request_transaction(
+        faucet_addr,
+        FaucetRequest::GetAirdropTokens {
+            amount,
+            mint: *mint,
+            to: *id,
+            blockhash,
+        },
+    )
+}
+
+fn request_transaction(
+    faucet_addr: &SocketAddr,
+    req: FaucetRequest,
+) -> Result<Transaction, FaucetError> {
     let mut stream = TcpStream::connect_timeout(faucet_addr, Duration::new(3, 0))?;
     stream.set_read_timeout(Some(Duration::new(10, 0)))?;
-    let req = FaucetRequest::GetAirdrop {
-        lamports,
-        blockhash,
-        to: *id,
-    };
     let req = serialize(&req).expect("serialize faucet request");
     stream.write_all(&req)?;
 
@@ -325,6 +752,14 @@ pub fn request_airdrop_transaction(
     Ok(transaction)
 }
 
+fn write_quota_cache(path: &Path, quotas: &PersistedQuotas) -> Result<(), FaucetError> {
+    // Write to a temporary file first so a crash mid-write can't corrupt the cache
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, serde_json::to_vec(quotas)?)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
 pub fn run_local_faucet_with_port(
     faucet_keypair: Keypair,
     sender: Sender<Result<SocketAddr, String>>,
@@ -406,16 +841,21 @@ async fn process(
     mut stream: TokioTcpStream,
     faucet: Arc<Mutex<Faucet>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut request = vec![
-        0u8;
-        serialized_size(&FaucetRequest::GetAirdrop {
-            lamports: u64::default(),
-            to: Pubkey::default(),
-            blockhash: Hash::default(),
-        })
-        .unwrap() as usize
-    ];
-    while stream.read_exact(&mut request).await.is_ok() {
+    // Requests are bincode-encoded enums; read the variant tag first to learn the size of
+    // the rest of the request
+    let mut tag = [0u8; 4];
+    while stream.read_exact(&mut tag).await.is_ok() {
+        let request_size = match FaucetRequest::serialized_size_for_tag(u32::from_le_bytes(tag)) {
+            Ok(request_size) => request_size,
+            Err(e) => {
+                info!("Error in request: {}", e);
+                stream.write_all(&ERROR_RESPONSE).await?;
+                break;
+            }
+        };
+        let mut request = vec![0u8; request_size];
+        request[..tag.len()].copy_from_slice(&tag);
+        stream.read_exact(&mut request[tag.len()..]).await?;
         trace!("{:?}", request);
 
         let response = {
@@ -677,4 +1117,261 @@ mod tests {
         let bad_bytes = "bad bytes".as_bytes();
         assert!(faucet.process_faucet_request(bad_bytes, ip).is_err());
     }
+
+    #[test]
+    fn test_token_airdrop_config_from_str() {
+        let mint = Pubkey::new_unique();
+        assert_eq!(
+            mint.to_string().parse::<TokenAirdropConfig>().unwrap(),
+            TokenAirdropConfig {
+                mint,
+                source: TokenAirdropSource::Mint,
+                per_request_cap: None,
+                per_time_cap: None,
+            }
+        );
+        assert_eq!(
+            format!("{mint}:transfer:10:100")
+                .parse::<TokenAirdropConfig>()
+                .unwrap(),
+            TokenAirdropConfig {
+                mint,
+                source: TokenAirdropSource::Transfer,
+                per_request_cap: Some(10),
+                per_time_cap: Some(100),
+            }
+        );
+        assert_eq!(
+            format!("{mint}:mint::100")
+                .parse::<TokenAirdropConfig>()
+                .unwrap(),
+            TokenAirdropConfig {
+                mint,
+                source: TokenAirdropSource::Mint,
+                per_request_cap: None,
+                per_time_cap: Some(100),
+            }
+        );
+        assert!("bad".parse::<TokenAirdropConfig>().is_err());
+        assert!(format!("{mint}:burn")
+            .parse::<TokenAirdropConfig>()
+            .is_err());
+        assert!(format!("{mint}:mint:1:2:3")
+            .parse::<TokenAirdropConfig>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_faucet_build_airdrop_tokens_transaction() {
+        let to = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let blockhash = Hash::default();
+        let request = FaucetRequest::GetAirdropTokens {
+            amount: 2,
+            mint,
+            to,
+            blockhash,
+        };
+        let ip = socketaddr!([203, 0, 113, 1], 1234).ip();
+
+        // Unconfigured mints are rejected
+        let keypair = Keypair::new();
+        let mut faucet = Faucet::new(keypair, None, None, None);
+        assert!(matches!(
+            faucet.build_airdrop_transaction(request, ip),
+            Err(FaucetError::UnsupportedMint(_))
+        ));
+
+        // Mint source
+        let keypair = Keypair::new();
+        let faucet_pubkey = keypair.pubkey();
+        let mut faucet = Faucet::new(keypair, None, None, None);
+        faucet.add_airdrop_token(TokenAirdropConfig {
+            mint,
+            source: TokenAirdropSource::Mint,
+            per_request_cap: None,
+            per_time_cap: Some(3),
+        });
+        if let FaucetTransaction::Airdrop(tx) =
+            faucet.build_airdrop_transaction(request, ip).unwrap()
+        {
+            let recipient_token_address = get_associated_token_address(&to, &mint);
+            let expected_message = Message::new(
+                &[
+                    create_associated_token_account_idempotent(
+                        &faucet_pubkey,
+                        &to,
+                        &mint,
+                        &spl_token::id(),
+                    ),
+                    spl_token::instruction::mint_to(
+                        &spl_token::id(),
+                        &mint,
+                        &recipient_token_address,
+                        &faucet_pubkey,
+                        &[],
+                        2,
+                    )
+                    .unwrap(),
+                ],
+                Some(&faucet_pubkey),
+            );
+            assert_eq!(tx.signatures.len(), 1);
+            assert_eq!(tx.message, expected_message);
+        } else {
+            panic!("token airdrop should succeed");
+        }
+
+        // Per-recipient cap applies across IPs, including loopback
+        let loopback = socketaddr!(Ipv4Addr::LOCALHOST, 0).ip();
+        assert!(matches!(
+            faucet.build_airdrop_transaction(request, loopback),
+            Err(FaucetError::TokenPerTimeCapExceeded(..))
+        ));
+
+        // Per-IP cap applies across recipients
+        let request1 = FaucetRequest::GetAirdropTokens {
+            amount: 2,
+            mint,
+            to: Pubkey::new_unique(),
+            blockhash,
+        };
+        assert!(matches!(
+            faucet.build_airdrop_transaction(request1, ip),
+            Err(FaucetError::TokenPerTimeCapExceeded(..))
+        ));
+
+        // SOL quotas are tracked separately from token quotas
+        assert!(faucet.address_cache.is_empty());
+        faucet.clear_caches();
+        assert!(faucet.build_airdrop_transaction(request1, ip).is_ok());
+
+        // Transfer source
+        let keypair = Keypair::new();
+        let faucet_pubkey = keypair.pubkey();
+        let mut faucet = Faucet::new(keypair, None, None, None);
+        faucet.add_airdrop_token(TokenAirdropConfig {
+            mint,
+            source: TokenAirdropSource::Transfer,
+            per_request_cap: Some(1),
+            per_time_cap: None,
+        });
+        if let FaucetTransaction::Memo((_tx, memo)) =
+            faucet.build_airdrop_transaction(request, ip).unwrap()
+        {
+            assert_eq!(
+                memo,
+                format!("token request too large; req: 2, mint: {mint}, cap: 1")
+            );
+        } else {
+            panic!("token airdrop attempt should result in memo tx");
+        }
+        let request = FaucetRequest::GetAirdropTokens {
+            amount: 1,
+            mint,
+            to,
+            blockhash,
+        };
+        if let FaucetTransaction::Airdrop(tx) =
+            faucet.build_airdrop_transaction(request, ip).unwrap()
+        {
+            let expected_instruction = spl_token::instruction::transfer(
+                &spl_token::id(),
+                &get_associated_token_address(&faucet_pubkey, &mint),
+                &get_associated_token_address(&to, &mint),
+                &faucet_pubkey,
+                &[],
+                1,
+            )
+            .unwrap();
+            let message = tx.message();
+            assert_eq!(message.instructions.len(), 2);
+            assert_eq!(message.instructions[1].data, expected_instruction.data);
+        } else {
+            panic!("token airdrop should succeed");
+        }
+    }
+
+    #[test]
+    fn test_process_faucet_request_sizes() {
+        for req in [
+            FaucetRequest::GetAirdrop {
+                lamports: 1,
+                to: Pubkey::new_unique(),
+                blockhash: Hash::default(),
+            },
+            FaucetRequest::GetAirdropTokens {
+                amount: 1,
+                mint: Pubkey::new_unique(),
+                to: Pubkey::new_unique(),
+                blockhash: Hash::default(),
+            },
+        ] {
+            let bytes = serialize(&req).unwrap();
+            let tag = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+            assert_eq!(
+                FaucetRequest::serialized_size_for_tag(tag).unwrap(),
+                bytes.len()
+            );
+        }
+        assert!(FaucetRequest::serialized_size_for_tag(2).is_err());
+    }
+
+    #[test]
+    fn test_quota_cache_persistence() {
+        let quota_dir = tempfile::tempdir().unwrap();
+        let path = quota_dir.path().join("faucet-quotas.json");
+        let mint = Pubkey::new_unique();
+        let token_config = TokenAirdropConfig {
+            mint,
+            source: TokenAirdropSource::Mint,
+            per_request_cap: None,
+            per_time_cap: Some(2),
+        };
+        let to = Pubkey::new_unique();
+        let ip = socketaddr!([203, 0, 113, 1], 1234).ip();
+        let request = FaucetRequest::GetAirdropTokens {
+            amount: 2,
+            mint,
+            to,
+            blockhash: Hash::default(),
+        };
+
+        let mut faucet = Faucet::new(Keypair::new(), None, Some(2), None);
+        faucet.add_airdrop_token(token_config);
+        faucet.set_quota_cache_path(path.clone()).unwrap();
+        faucet
+            .process_faucet_request(&serialize(&request).unwrap(), ip)
+            .unwrap();
+        let sol_request = FaucetRequest::GetAirdrop {
+            lamports: 1,
+            to,
+            blockhash: Hash::default(),
+        };
+        faucet
+            .process_faucet_request(&serialize(&sol_request).unwrap(), ip)
+            .unwrap();
+
+        // A restarted faucet picks up where the previous one left off
+        let mut faucet = Faucet::new(Keypair::new(), None, Some(2), None);
+        faucet.add_airdrop_token(token_config);
+        faucet.set_quota_cache_path(path.clone()).unwrap();
+        assert_eq!(faucet.ip_cache.get(&ip), Some(&1));
+        assert!(faucet
+            .process_faucet_request(&serialize(&request).unwrap(), ip)
+            .is_err());
+
+        // Quotas from an elapsed time slice are ignored
+        let mut quotas = faucet.persisted_quotas();
+        quotas.last_cleared = 0;
+        write_quota_cache(&path, &quotas).unwrap();
+        let mut faucet = Faucet::new(Keypair::new(), None, Some(2), None);
+        faucet.add_airdrop_token(token_config);
+        faucet.set_quota_cache_path(path).unwrap();
+        assert!(faucet.ip_cache.is_empty());
+        assert!(faucet.token_caches.is_empty());
+        assert!(faucet
+            .process_faucet_request(&serialize(&request).unwrap(), ip)
+            .is_ok());
+    }
 }
@@ -1,5 +1,9 @@
 use {
-    solana_faucet::faucet::{request_airdrop_transaction, run_local_faucet},
+    crossbeam_channel::unbounded,
+    solana_faucet::faucet::{
+        request_airdrop_tokens_transaction, request_airdrop_transaction, run_faucet,
+        run_local_faucet, Faucet, TokenAirdropConfig, TokenAirdropSource,
+    },
     solana_sdk::{
         hash::Hash,
         message::Message,
@@ -7,6 +11,12 @@ use {
         system_instruction,
         transaction::Transaction,
     },
+    std::{
+        net::{Ipv4Addr, SocketAddr},
+        sync::{Arc, Mutex},
+        thread,
+    },
+    tokio::runtime::Runtime,
 };
 
 #[test]
@@ -24,3 +34,44 @@ fn test_local_faucet() {
     let result = request_airdrop_transaction(&faucet_addr, &to, lamports, blockhash);
     assert_eq!(expected_tx, result.unwrap());
 }
+
+#[test]
+fn test_local_faucet_tokens() {
+    let keypair = Keypair::new();
+    let faucet_pubkey = keypair.pubkey();
+    let mint = solana_sdk::pubkey::new_rand();
+    let to = solana_sdk::pubkey::new_rand();
+    let blockhash = Hash::new(to.as_ref());
+
+    let mut faucet = Faucet::new(keypair, None, None, None);
+    faucet.add_airdrop_token(TokenAirdropConfig {
+        mint,
+        source: TokenAirdropSource::Mint,
+        per_request_cap: None,
+        per_time_cap: None,
+    });
+    let faucet = Arc::new(Mutex::new(faucet));
+    let (sender, receiver) = unbounded();
+    thread::spawn(move || {
+        let faucet_addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0));
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(run_faucet(faucet, faucet_addr, Some(sender)));
+    });
+    let faucet_addr = receiver.recv().unwrap().unwrap();
+
+    let transaction =
+        request_airdrop_tokens_transaction(&faucet_addr, &to, &mint, 50, blockhash).unwrap();
+    assert_eq!(transaction.message.account_keys[0], faucet_pubkey);
+    assert_eq!(transaction.message.instructions.len(), 2);
+    assert!(transaction.verify().is_ok());
+
+    // Plain SOL airdrops still work over the same protocol
+    let transaction = request_airdrop_transaction(&faucet_addr, &to, 50, blockhash).unwrap();
+    assert_eq!(transaction.message.instructions.len(), 1);
+
+    // Unconfigured mints are refused
+    let other_mint = solana_sdk::pubkey::new_rand();
+    assert!(
+        request_airdrop_tokens_transaction(&faucet_addr, &to, &other_mint, 50, blockhash).is_err()
+    );
+}
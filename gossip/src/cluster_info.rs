@@ -31,11 +31,15 @@ use {
         crds_gossip_pull::{
             CrdsFilter, CrdsTimeouts, ProcessPullStats, CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS,
         },
+        crds_gossip_push::{CRDS_GOSSIP_PUSH_FANOUT, PUSH_MESSAGE_HEADER_SIZE},
+        crds_recorder::{CrdsRecord, CrdsRecordSource},
         crds_value::{
             self, AccountsHashes, CrdsData, CrdsValue, CrdsValueLabel, EpochSlotsIndex, LowestSlot,
-            NodeInstance, SnapshotHashes, Version, Vote, MAX_WALLCLOCK,
+            NodeInstance, RestartHeaviestFork, RestartLastVotedForkSlots, SnapshotHashes, Version,
+            Vote, MAX_WALLCLOCK,
         },
         duplicate_shred::DuplicateShred,
+        egress_budget::{adapt_push_fanout, EgressBudget},
         epoch_slots::EpochSlots,
         gossip_error::GossipError,
         ping_pong::{self, PingCache, Pong},
@@ -111,7 +115,7 @@ const MAX_GOSSIP_TRAFFIC: usize = 128_000_000 / PACKET_DATA_SIZE;
 /// Max size of serialized crds-values in a Protocol::PushMessage packet. This
 /// is equal to PACKET_DATA_SIZE minus serialized size of an empty push
 /// message: Protocol::PushMessage(Pubkey::default(), Vec::default())
-const PUSH_MESSAGE_MAX_PAYLOAD_SIZE: usize = PACKET_DATA_SIZE - 44;
+const PUSH_MESSAGE_MAX_PAYLOAD_SIZE: usize = PACKET_DATA_SIZE - PUSH_MESSAGE_HEADER_SIZE;
 pub(crate) const DUPLICATE_SHRED_MAX_PAYLOAD_SIZE: usize = PACKET_DATA_SIZE - 115;
 /// Maximum number of hashes in AccountsHashes a node publishes
 /// such that the serialized size of the push/pull message stays below
@@ -153,6 +157,8 @@ pub enum ClusterInfoError {
     BadGossipAddress,
     #[error("TooManyIncrementalSnapshotHashes")]
     TooManyIncrementalSnapshotHashes,
+    #[error("EmptyLastVotedForkSlots")]
+    EmptyLastVotedForkSlots,
 }
 
 pub struct ClusterInfo {
@@ -163,6 +169,8 @@ pub struct ClusterInfo {
     /// Network entrypoints
     entrypoints: RwLock<Vec<LegacyContactInfo>>,
     outbound_budget: DataBudget,
+    /// Caps push messages, pull requests and pull responses, if set
+    egress_budget: Option<EgressBudget>,
     my_contact_info: RwLock<ContactInfo>,
     ping_cache: Mutex<PingCache>,
     stats: GossipStats,
@@ -173,6 +181,8 @@ pub struct ClusterInfo {
     instance: RwLock<NodeInstance>,
     contact_info_path: PathBuf,
     socket_addr_space: SocketAddrSpace,
+    /// If set, every crds value received is also sent here, see crds_recorder
+    crds_record_sender: Option<Sender<Vec<CrdsRecord>>>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, AbiExample)]
@@ -267,7 +277,7 @@ pub fn make_accounts_hashes_message(
 pub(crate) type Ping = ping_pong::Ping<[u8; GOSSIP_PING_TOKEN_SIZE]>;
 
 // TODO These messages should go through the gpu pipeline for spam filtering
-#[frozen_abi(digest = "CVvKB495YW6JN4w1rWwajyZmG5wvNhmD97V99rSv9fGw")]
+#[frozen_abi(digest = "Eki9wm9mhEEQdw48YbBso2gqehwv1PTHPBZ4YhrioqLc")]
 #[derive(Serialize, Deserialize, Debug, AbiEnumVisitor, AbiExample)]
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum Protocol {
@@ -284,10 +294,64 @@ pub(crate) enum Protocol {
 }
 
 impl Protocol {
-    fn par_verify(self, stats: &GossipStats) -> Option<Self> {
+    /// Crds values carried by the message.
+    fn crds_values(&self) -> &[CrdsValue] {
         match self {
-            Protocol::PullRequest(_, ref caller) => {
-                if caller.verify() {
+            Protocol::PullRequest(_, caller) => std::slice::from_ref(caller),
+            Protocol::PullResponse(_, values) | Protocol::PushMessage(_, values) => values,
+            Protocol::PruneMessage(..) | Protocol::PingMessage(_) | Protocol::PongMessage(_) => &[],
+        }
+    }
+
+    /// Verifies the signatures of the crds values carried by the message.
+    fn par_verify_crds_values(&self) -> Vec<bool> {
+        self.crds_values()
+            .par_iter()
+            .map(Signable::verify)
+            .collect()
+    }
+
+    /// Crds values carried by the message, along with whether their signatures
+    /// verify, as returned by par_verify_crds_values.
+    fn crds_records(
+        &self,
+        from: SocketAddr,
+        received_at: u64,
+        crds_values_verified: &[bool],
+    ) -> Vec<CrdsRecord> {
+        let source = match self {
+            Protocol::PullRequest(..) => CrdsRecordSource::PullRequest,
+            Protocol::PullResponse(..) => CrdsRecordSource::PullResponse,
+            Protocol::PushMessage(..) => CrdsRecordSource::PushMessage,
+            Protocol::PruneMessage(..) | Protocol::PingMessage(_) | Protocol::PongMessage(_) => {
+                return Vec::default()
+            }
+        };
+        self.crds_values()
+            .iter()
+            .zip(crds_values_verified)
+            .map(|(value, &verified)| CrdsRecord {
+                received_at,
+                from,
+                source,
+                verified,
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    /// Drops the crds values whose signatures did not verify, as returned by
+    /// par_verify_crds_values, and verifies the signatures of other messages.
+    fn filter_verified(self, crds_values_verified: Vec<bool>, stats: &GossipStats) -> Option<Self> {
+        fn retain_verified(data: Vec<CrdsValue>, verified: Vec<bool>) -> Vec<CrdsValue> {
+            data.into_iter()
+                .zip(verified)
+                .filter_map(|(value, verified)| verified.then_some(value))
+                .collect()
+        }
+        match self {
+            Protocol::PullRequest(..) => {
+                if crds_values_verified == [true] {
                     Some(self)
                 } else {
                     stats.gossip_pull_request_verify_fail.add_relaxed(1);
@@ -296,7 +360,7 @@ impl Protocol {
             }
             Protocol::PullResponse(from, data) => {
                 let size = data.len();
-                let data: Vec<_> = data.into_par_iter().filter(Signable::verify).collect();
+                let data = retain_verified(data, crds_values_verified);
                 if size != data.len() {
                     stats
                         .gossip_pull_response_verify_fail
@@ -310,7 +374,7 @@ impl Protocol {
             }
             Protocol::PushMessage(from, data) => {
                 let size = data.len();
-                let data: Vec<_> = data.into_par_iter().filter(Signable::verify).collect();
+                let data = retain_verified(data, crds_values_verified);
                 if size != data.len() {
                     stats
                         .gossip_push_msg_verify_fail
@@ -394,7 +458,8 @@ fn retain_staked(values: &mut Vec<CrdsValue>, stakes: &HashMap<Pubkey, u64>) {
             CrdsData::LowestSlot(_, _)
             | CrdsData::LegacyVersion(_)
             | CrdsData::DuplicateShred(_, _)
-            | CrdsData::RestartLastVotedForkSlots(_) => {
+            | CrdsData::RestartLastVotedForkSlots(_)
+            | CrdsData::RestartHeaviestFork(_) => {
                 let stake = stakes.get(&value.pubkey()).copied();
                 stake.unwrap_or_default() >= MIN_STAKE_FOR_GOSSIP
             }
@@ -415,6 +480,7 @@ impl ClusterInfo {
             keypair: RwLock::new(keypair),
             entrypoints: RwLock::default(),
             outbound_budget: DataBudget::default(),
+            egress_budget: None,
             my_contact_info: RwLock::new(contact_info),
             ping_cache: Mutex::new(PingCache::new(
                 GOSSIP_PING_CACHE_TTL,
@@ -429,6 +495,7 @@ impl ClusterInfo {
             contact_info_path: PathBuf::default(),
             contact_save_interval: 0, // disabled
             socket_addr_space,
+            crds_record_sender: None,
         };
         me.insert_self();
         me.push_self();
@@ -439,6 +506,18 @@ impl ClusterInfo {
         self.contact_debug_interval = new;
     }
 
+    /// Caps the bytes per second sent in push messages, pull requests and
+    /// pull responses, adapting push fanout to fit.
+    pub fn set_egress_budget(&mut self, bytes_per_second: u64) {
+        self.egress_budget = Some(EgressBudget::new(bytes_per_second));
+    }
+
+    /// Records every crds value received, before signature verification, to
+    /// the given sender.
+    pub fn set_crds_record_sender(&mut self, sender: Sender<Vec<CrdsRecord>>) {
+        self.crds_record_sender = Some(sender);
+    }
+
     pub fn socket_addr_space(&self) -> &SocketAddrSpace {
         &self.socket_addr_space
     }
@@ -980,6 +1059,48 @@ impl ClusterInfo {
             .push(message);
     }
 
+    /// Pushes the last voted fork of this node during a wen_restart. Only the most recent
+    /// `MAX_SLOTS_PER_ENTRY` slots of the fork are sent.
+    pub fn push_restart_last_voted_fork_slots(
+        &self,
+        fork: &[Slot],
+        last_vote_bankhash: Hash,
+    ) -> Result<(), ClusterInfoError> {
+        let mut last_voted_fork_slots = RestartLastVotedForkSlots::new(
+            self.id(),
+            timestamp(),
+            last_vote_bankhash,
+            self.my_shred_version(),
+        );
+        if last_voted_fork_slots.fill(fork) == 0 {
+            return Err(ClusterInfoError::EmptyLastVotedForkSlots);
+        }
+        last_voted_fork_slots.deflate();
+        let message = CrdsData::RestartLastVotedForkSlots(last_voted_fork_slots);
+        self.push_message(CrdsValue::new_signed(message, &self.keypair()));
+        Ok(())
+    }
+
+    /// Pushes the heaviest fork this node selected during a wen_restart, along with the stake
+    /// it observed taking part in the restart.
+    pub fn push_restart_heaviest_fork(
+        &self,
+        last_slot: Slot,
+        last_slot_hash: Hash,
+        observed_stake: u64,
+    ) {
+        let heaviest_fork = RestartHeaviestFork {
+            from: self.id(),
+            wallclock: timestamp(),
+            last_slot,
+            last_slot_hash,
+            observed_stake,
+            shred_version: self.my_shred_version(),
+        };
+        let message = CrdsData::RestartHeaviestFork(heaviest_fork);
+        self.push_message(CrdsValue::new_signed(message, &self.keypair()));
+    }
+
     pub fn push_accounts_hashes(&self, accounts_hashes: Vec<(Slot, Hash)>) {
         if accounts_hashes.len() > MAX_ACCOUNTS_HASHES {
             warn!(
@@ -1217,6 +1338,41 @@ impl ClusterInfo {
             .collect()
     }
 
+    /// Returns last-voted-fork-slots of a wen_restart inserted since the given cursor.
+    /// Excludes entries from nodes with a different shred version.
+    pub fn get_restart_last_voted_fork_slots(
+        &self,
+        cursor: &mut Cursor,
+    ) -> Vec<RestartLastVotedForkSlots> {
+        let self_shred_version = self.my_shred_version();
+        let gossip_crds = self.gossip.crds.read().unwrap();
+        gossip_crds
+            .get_entries(cursor)
+            .filter_map(|entry| match &entry.value.data {
+                CrdsData::RestartLastVotedForkSlots(slots) => Some(slots),
+                _ => None,
+            })
+            .filter(|slots| slots.shred_version == self_shred_version)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns heaviest forks of a wen_restart inserted since the given cursor.
+    /// Excludes entries from nodes with a different shred version.
+    pub fn get_restart_heaviest_fork(&self, cursor: &mut Cursor) -> Vec<RestartHeaviestFork> {
+        let self_shred_version = self.my_shred_version();
+        let gossip_crds = self.gossip.crds.read().unwrap();
+        gossip_crds
+            .get_entries(cursor)
+            .filter_map(|entry| match &entry.value.data {
+                CrdsData::RestartHeaviestFork(fork) => Some(fork),
+                _ => None,
+            })
+            .filter(|fork| fork.shred_version == self_shred_version)
+            .cloned()
+            .collect()
+    }
+
     /// Returns duplicate-shreds inserted since the given cursor.
     pub(crate) fn get_duplicate_shreds(&self, cursor: &mut Cursor) -> Vec<DuplicateShred> {
         let gossip_crds = self.gossip.crds.read().unwrap();
@@ -1501,18 +1657,46 @@ impl ClusterInfo {
             .map(CrdsData::LegacyContactInfo)
             .expect("Operator must spin up node with valid contact-info");
         let self_info = CrdsValue::new_signed(self_info, &self.keypair());
-        let pulls = pulls
+        let pulls: Vec<_> = pulls
             .into_iter()
-            .filter_map(|(peer, filters)| Some((peer.gossip().ok()?, filters)))
-            .flat_map(|(addr, filters)| repeat(addr).zip(filters))
-            .map(|(gossip_addr, filter)| {
+            .filter_map(|(peer, filters)| Some((*peer.pubkey(), peer.gossip().ok()?, filters)))
+            .flat_map(|(pubkey, addr, filters)| repeat((pubkey, addr)).zip(filters))
+            .map(|((pubkey, gossip_addr), filter)| {
                 let request = Protocol::PullRequest(filter, self_info.clone());
-                (gossip_addr, request)
-            });
+                (pubkey, gossip_addr, request)
+            })
+            .collect();
         self.stats
             .new_pull_requests_pings_count
             .add_relaxed(pings.len() as u64);
-        (pings, pulls.collect())
+        let Some(egress_budget) = &self.egress_budget else {
+            let pulls = pulls.into_iter().map(|(_, addr, request)| (addr, request));
+            return (pings, pulls.collect());
+        };
+        egress_budget.refill();
+        // Pulls from entrypoints go first, as the node may not know of any
+        // other peers yet.
+        let entrypoints: HashSet<Pubkey> = self
+            .entrypoints
+            .read()
+            .unwrap()
+            .iter()
+            .map(|entrypoint| *entrypoint.pubkey())
+            .collect();
+        let (pulls, stats) = egress_budget.filter_by_stake(pulls, |node| {
+            if entrypoints.contains(node) {
+                u64::MAX
+            } else {
+                stakes.get(node).copied().unwrap_or_default()
+            }
+        });
+        self.stats
+            .egress_budget_dropped_pull_requests
+            .add_relaxed(stats.dropped_packets as u64);
+        self.stats
+            .egress_budget_dropped_bytes
+            .add_relaxed(stats.dropped_bytes as u64);
+        (pings, pulls)
     }
 
     fn drain_push_queue(&self) -> Vec<CrdsValue> {
@@ -1530,10 +1714,16 @@ impl ClusterInfo {
     }
     fn new_push_requests(&self, stakes: &HashMap<Pubkey, u64>) -> Vec<(SocketAddr, Protocol)> {
         let self_id = self.id();
-        let (mut push_messages, num_entries, num_nodes) = {
+        let bytes_available = self.egress_budget.as_ref().map(EgressBudget::refill);
+        let (mut push_messages, num_entries, num_nodes, held_back) = {
             let _st = ScopedTimer::from(&self.stats.new_push_requests);
-            self.gossip
-                .new_push_messages(&self_id, self.drain_push_queue(), timestamp(), stakes)
+            self.gossip.new_push_messages(
+                &self_id,
+                self.drain_push_queue(),
+                timestamp(),
+                stakes,
+                bytes_available.unwrap_or(usize::MAX),
+            )
         };
         self.stats
             .push_fanout_num_entries
@@ -1565,6 +1755,27 @@ impl ClusterInfo {
                     .map(move |payload| (peer, Protocol::PushMessage(self_id, payload)))
             })
             .collect();
+        if let (Some(egress_budget), Some(bytes_available)) = (&self.egress_budget, bytes_available)
+        {
+            // The values pushed were held to the budget, so these bytes are
+            // available unless pull responses took them meanwhile.
+            let sent_bytes = messages
+                .iter()
+                .map(|(_, message)| bincode::serialized_size(message).unwrap_or_default())
+                .sum::<u64>() as usize;
+            egress_budget.consume(sent_bytes);
+            if held_back {
+                self.stats.egress_budget_held_back_pushes.add_relaxed(1);
+            }
+            let push = &self.gossip.push;
+            push.set_push_fanout(adapt_push_fanout(
+                push.push_fanout(),
+                CRDS_GOSSIP_PUSH_FANOUT,
+                held_back,
+                sent_bytes,
+                bytes_available,
+            ));
+        }
         self.stats
             .new_push_requests_num
             .add_relaxed(messages.len() as u64);
@@ -2062,6 +2273,9 @@ impl ClusterInfo {
         }
         let mut rng = rand::thread_rng();
         let shuffle = WeightedShuffle::new("handle-pull-requests", &scores).shuffle(&mut rng);
+        if let Some(egress_budget) = &self.egress_budget {
+            egress_budget.refill();
+        }
         let mut total_bytes = 0;
         let mut sent = 0;
         for (addr, response) in shuffle.map(|i| &responses[i]) {
@@ -2070,14 +2284,21 @@ impl ClusterInfo {
             match Packet::from_data(Some(addr), response) {
                 Err(err) => error!("failed to write pull-response packet: {:?}", err),
                 Ok(packet) => {
-                    if self.outbound_budget.take(packet.meta().size) {
-                        total_bytes += packet.meta().size;
-                        packet_batch.push(packet);
-                        sent += 1;
-                    } else {
+                    if !self.outbound_budget.take(packet.meta().size) {
                         self.stats.gossip_pull_request_no_budget.add_relaxed(1);
                         break;
                     }
+                    if let Some(egress_budget) = &self.egress_budget {
+                        if !egress_budget.take(packet.meta().size) {
+                            self.stats
+                                .egress_budget_dropped_pull_responses
+                                .add_relaxed((responses.len() - sent) as u64);
+                            break;
+                        }
+                    }
+                    total_bytes += packet.meta().size;
+                    packet_batch.push(packet);
+                    sent += 1;
                 }
             }
         }
@@ -2534,7 +2755,18 @@ impl ClusterInfo {
         let verify_packet = |packet: Packet| {
             let protocol: Protocol = packet.deserialize_slice(..).ok()?;
             protocol.sanitize().ok()?;
-            let protocol = protocol.par_verify(&self.stats)?;
+            let crds_values_verified = protocol.par_verify_crds_values();
+            if let Some(sender) = &self.crds_record_sender {
+                let records = protocol.crds_records(
+                    packet.meta().socket_addr(),
+                    timestamp(),
+                    &crds_values_verified,
+                );
+                if !records.is_empty() {
+                    let _ = sender.send(records);
+                }
+            }
+            let protocol = protocol.filter_verified(crds_values_verified, &self.stats)?;
             Some((packet.meta().socket_addr(), protocol))
         };
         let packets: Vec<_> = {
@@ -3657,11 +3889,12 @@ mod tests {
             &SocketAddrSpace::Unspecified,
         );
         //check that all types of gossip messages are signed correctly
-        let (push_messages, _, _) = cluster_info.gossip.new_push_messages(
+        let (push_messages, _, _, _) = cluster_info.gossip.new_push_messages(
             &cluster_info.id(),
             cluster_info.drain_push_queue(),
             timestamp(),
             &stakes,
+            usize::MAX, // max_egress_bytes
         );
         // there should be some pushes ready
         assert!(!push_messages.is_empty());
@@ -3688,6 +3921,57 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_egress_budget() {
+        let keypair = Keypair::new();
+        let peer_keypair = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let peer = ContactInfo::new_localhost(&peer_keypair.pubkey(), 0);
+        let mut cluster_info = ClusterInfo::new(
+            contact_info,
+            Arc::new(keypair),
+            SocketAddrSpace::Unspecified,
+        );
+        let stakes = HashMap::<Pubkey, u64>::default();
+        cluster_info.ping_cache.lock().unwrap().mock_pong(
+            *peer.pubkey(),
+            peer.gossip().unwrap(),
+            Instant::now(),
+        );
+        cluster_info.insert_info(peer);
+        cluster_info.gossip.refresh_push_active_set(
+            &cluster_info.keypair(),
+            cluster_info.my_shred_version(),
+            &stakes,
+            None, // gossip validators
+            &cluster_info.ping_cache,
+            &mut Vec::new(), // pings
+            &SocketAddrSpace::Unspecified,
+        );
+        assert_eq!(
+            cluster_info.gossip.push.push_fanout(),
+            CRDS_GOSSIP_PUSH_FANOUT
+        );
+        // Nothing accrues before the first push messages, so the contact info
+        // is held back.
+        cluster_info.set_egress_budget(1_000);
+        assert!(cluster_info.new_push_requests(&stakes).is_empty());
+        assert_eq!(
+            cluster_info.gossip.push.push_fanout(),
+            CRDS_GOSSIP_PUSH_FANOUT - 1
+        );
+        // Values held back are pushed once the budget refills.
+        sleep(Duration::from_millis(500));
+        let push_requests = cluster_info.new_push_requests(&stakes);
+        assert_eq!(push_requests.len(), 1);
+        let (_, Protocol::PushMessage(_, values)) = &push_requests[0] else {
+            panic!("not a push message");
+        };
+        assert!(values
+            .iter()
+            .any(|value| value.pubkey() == cluster_info.id()));
+    }
+
     #[test]
     fn test_refresh_vote() {
         let keypair = Arc::new(Keypair::new());
@@ -4264,6 +4548,51 @@ mod tests {
         assert_eq!(prune_message.sanitize(), Err(SanitizeError::InvalidValue));
     }
 
+    #[test]
+    fn test_protocol_crds_records() {
+        let mut rng = rand::thread_rng();
+        let from = SocketAddr::from(([127, 0, 0, 1], 8001));
+        let values = vec![
+            CrdsValue::new_rand(&mut rng, None),
+            CrdsValue::new_unsigned(CrdsData::LegacyContactInfo(LegacyContactInfo::default())),
+            CrdsValue::new_rand(&mut rng, None),
+        ];
+        let protocol = Protocol::PushMessage(Pubkey::new_unique(), values.clone());
+        let packet = Packet::from_data(Some(&from), &protocol).unwrap();
+        let protocol: Protocol = packet.deserialize_slice(..).unwrap();
+        assert_eq!(protocol.sanitize(), Ok(()));
+
+        let crds_values_verified = protocol.par_verify_crds_values();
+        assert_eq!(crds_values_verified, [true, false, true]);
+        let records =
+            protocol.crds_records(packet.meta().socket_addr(), 1234, &crds_values_verified);
+        assert_eq!(records.len(), values.len());
+        for (record, value, verified) in izip!(&records, &values, &crds_values_verified) {
+            assert_eq!(record.received_at, 1234);
+            assert_eq!(record.from, from);
+            assert_eq!(record.source, CrdsRecordSource::PushMessage);
+            assert_eq!(record.verified, *verified);
+            assert_eq!(&record.value, value);
+        }
+
+        // The records reuse the verification, which also filters the message
+        let stats = GossipStats::default();
+        let Some(Protocol::PushMessage(_, verified_values)) =
+            protocol.filter_verified(crds_values_verified, &stats)
+        else {
+            panic!("push message should have verified values");
+        };
+        assert_eq!(verified_values, [values[0].clone(), values[2].clone()]);
+
+        // Only messages carrying crds values are recorded
+        let protocol = Protocol::PingMessage(Ping::new_rand(&mut rng, &Keypair::new()).unwrap());
+        let crds_values_verified = protocol.par_verify_crds_values();
+        assert!(crds_values_verified.is_empty());
+        assert!(protocol
+            .crds_records(from, 1234, &crds_values_verified)
+            .is_empty());
+    }
+
     // computes the maximum size for pull request blooms
     fn max_bloom_size() -> usize {
         let filter_size = serialized_size(&CrdsFilter::default())
@@ -4559,4 +4888,134 @@ mod tests {
             assert_eq!(shred_data.chunk_index() as usize, i);
         }
     }
+
+    #[test]
+    fn test_push_restart_last_voted_fork_slots() {
+        let keypair = Arc::new(Keypair::new());
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = ClusterInfo::new(contact_info, keypair, SocketAddrSpace::Unspecified);
+        let slots = cluster_info.get_restart_last_voted_fork_slots(&mut Cursor::default());
+        assert!(slots.is_empty());
+        assert!(cluster_info
+            .push_restart_last_voted_fork_slots(&[], Hash::default())
+            .is_err());
+        let mut update: Vec<Slot> = vec![0];
+        for i in 0..81 {
+            for j in 0..1000 {
+                update.push(i * 1050 + j);
+            }
+        }
+        assert!(cluster_info
+            .push_restart_last_voted_fork_slots(&update, Hash::default())
+            .is_ok());
+        cluster_info.flush_push_queue();
+
+        let mut cursor = Cursor::default();
+        let slots = cluster_info.get_restart_last_voted_fork_slots(&mut cursor);
+        assert_eq!(slots.len(), 1);
+        let retrieved_slots = slots[0].to_slots(0);
+        assert!(retrieved_slots[0] < 69000);
+        assert_eq!(retrieved_slots.last(), Some(84999).as_ref());
+
+        let slots = cluster_info.get_restart_last_voted_fork_slots(&mut cursor);
+        assert!(slots.is_empty());
+
+        // Test with different shred versions.
+        let mut rng = rand::thread_rng();
+        let node_pubkey = Pubkey::new_unique();
+        let mut node = LegacyContactInfo::new_rand(&mut rng, Some(node_pubkey));
+        node.set_shred_version(42);
+        let mut slots = RestartLastVotedForkSlots::new_rand(&mut rng, Some(node_pubkey));
+        slots.shred_version = 42;
+        let entries = vec![
+            CrdsValue::new_unsigned(CrdsData::LegacyContactInfo(node)),
+            CrdsValue::new_unsigned(CrdsData::RestartLastVotedForkSlots(slots)),
+        ];
+        {
+            let mut gossip_crds = cluster_info.gossip.crds.write().unwrap();
+            for entry in entries {
+                assert!(gossip_crds
+                    .insert(entry, /*now=*/ 0, GossipRoute::LocalMessage)
+                    .is_ok());
+            }
+        }
+        // Should exclude other node's last-voted-fork-slot because of different
+        // shred-version.
+        let slots = cluster_info.get_restart_last_voted_fork_slots(&mut Cursor::default());
+        assert_eq!(slots.len(), 1);
+        assert_eq!(slots[0].from, cluster_info.id());
+
+        // Match shred versions.
+        {
+            let mut node = cluster_info.my_contact_info.write().unwrap();
+            node.set_shred_version(42);
+        }
+        assert!(cluster_info
+            .push_restart_last_voted_fork_slots(&update, Hash::default())
+            .is_ok());
+        cluster_info.flush_push_queue();
+        // Should now include both slots.
+        let slots = cluster_info.get_restart_last_voted_fork_slots(&mut Cursor::default());
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0].from, node_pubkey);
+        assert_eq!(slots[1].from, cluster_info.id());
+    }
+
+    #[test]
+    fn test_push_restart_heaviest_fork() {
+        let keypair = Arc::new(Keypair::new());
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = ClusterInfo::new(contact_info, keypair, SocketAddrSpace::Unspecified);
+        let forks = cluster_info.get_restart_heaviest_fork(&mut Cursor::default());
+        assert!(forks.is_empty());
+        let hash = Hash::new_unique();
+        cluster_info.push_restart_heaviest_fork(42, hash, 100);
+        cluster_info.flush_push_queue();
+
+        let mut cursor = Cursor::default();
+        let forks = cluster_info.get_restart_heaviest_fork(&mut cursor);
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0].from, cluster_info.id());
+        assert_eq!(forks[0].last_slot, 42);
+        assert_eq!(forks[0].last_slot_hash, hash);
+        assert_eq!(forks[0].observed_stake, 100);
+        assert!(cluster_info
+            .get_restart_heaviest_fork(&mut cursor)
+            .is_empty());
+
+        // Should exclude other node's heaviest fork because of different shred-version.
+        let mut rng = rand::thread_rng();
+        let node_pubkey = Pubkey::new_unique();
+        let mut node = LegacyContactInfo::new_rand(&mut rng, Some(node_pubkey));
+        node.set_shred_version(42);
+        let mut fork = RestartHeaviestFork::new_rand(&mut rng, Some(node_pubkey));
+        fork.shred_version = 42;
+        let entries = vec![
+            CrdsValue::new_unsigned(CrdsData::LegacyContactInfo(node)),
+            CrdsValue::new_unsigned(CrdsData::RestartHeaviestFork(fork)),
+        ];
+        {
+            let mut gossip_crds = cluster_info.gossip.crds.write().unwrap();
+            for entry in entries {
+                assert!(gossip_crds
+                    .insert(entry, /*now=*/ 0, GossipRoute::LocalMessage)
+                    .is_ok());
+            }
+        }
+        let forks = cluster_info.get_restart_heaviest_fork(&mut Cursor::default());
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0].from, cluster_info.id());
+
+        // Match shred versions.
+        {
+            let mut node = cluster_info.my_contact_info.write().unwrap();
+            node.set_shred_version(42);
+        }
+        cluster_info.push_restart_heaviest_fork(42, hash, 100);
+        cluster_info.flush_push_queue();
+        let forks = cluster_info.get_restart_heaviest_fork(&mut Cursor::default());
+        assert_eq!(forks.len(), 2);
+        assert_eq!(forks[0].from, node_pubkey);
+        assert_eq!(forks[1].from, cluster_info.id());
+    }
 }
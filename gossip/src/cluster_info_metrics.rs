@@ -91,6 +91,10 @@ impl<'a, T> Drop for TimedGuard<'a, T> {
 pub struct GossipStats {
     pub(crate) all_tvu_peers: Counter,
     pub(crate) bad_prune_destination: Counter,
+    pub(crate) egress_budget_dropped_bytes: Counter,
+    pub(crate) egress_budget_dropped_pull_requests: Counter,
+    pub(crate) egress_budget_dropped_pull_responses: Counter,
+    pub(crate) egress_budget_held_back_pushes: Counter,
     pub(crate) entrypoint2: Counter,
     pub(crate) entrypoint: Counter,
     pub(crate) epoch_slots_filled: Counter,
@@ -439,6 +443,27 @@ pub(crate) fn submit_gossip_stats(
             stats.push_fanout_num_nodes.clear(),
             i64
         ),
+        ("push_fanout", gossip.push.push_fanout() as i64, i64),
+        (
+            "egress_budget_held_back_pushes",
+            stats.egress_budget_held_back_pushes.clear(),
+            i64
+        ),
+        (
+            "egress_budget_dropped_pull_requests",
+            stats.egress_budget_dropped_pull_requests.clear(),
+            i64
+        ),
+        (
+            "egress_budget_dropped_pull_responses",
+            stats.egress_budget_dropped_pull_responses.clear(),
+            i64
+        ),
+        (
+            "egress_budget_dropped_bytes",
+            stats.egress_budget_dropped_bytes.clear(),
+            i64
+        ),
         (
             "push_message_pushes",
             stats.push_message_pushes.clear(),
@@ -637,6 +662,8 @@ pub(crate) fn submit_gossip_stats(
             crds_stats.pull.counts[12],
             i64
         ),
+        ("RestartHeaviestFork-push", crds_stats.push.counts[13], i64),
+        ("RestartHeaviestFork-pull", crds_stats.pull.counts[13], i64),
         (
             "all-push",
             crds_stats.push.counts.iter().sum::<usize>(),
@@ -684,6 +711,8 @@ pub(crate) fn submit_gossip_stats(
             crds_stats.pull.fails[12],
             i64
         ),
+        ("RestartHeaviestFork-push", crds_stats.push.fails[13], i64),
+        ("RestartHeaviestFork-pull", crds_stats.pull.fails[13], i64),
         ("all-push", crds_stats.push.fails.iter().sum::<usize>(), i64),
         ("all-pull", crds_stats.pull.fails.iter().sum::<usize>(), i64),
     );
@@ -103,7 +103,7 @@ pub enum GossipRoute<'a> {
     PushMessage(/*from:*/ &'a Pubkey),
 }
 
-type CrdsCountsArray = [usize; 13];
+type CrdsCountsArray = [usize; 14];
 
 pub(crate) struct CrdsDataStats {
     pub(crate) counts: CrdsCountsArray,
@@ -144,6 +144,11 @@ impl Cursor {
     fn consume(&mut self, ordinal: u64) {
         self.0 = self.0.max(ordinal + 1);
     }
+
+    // Moves the cursor back so that the value is returned again.
+    pub(crate) fn rewind(&mut self, value: &VersionedCrdsValue) {
+        self.0 = self.0.min(value.ordinal);
+    }
 }
 
 impl VersionedCrdsValue {
@@ -722,6 +727,7 @@ impl CrdsDataStats {
             CrdsData::SnapshotHashes(_) => 10,
             CrdsData::ContactInfo(_) => 11,
             CrdsData::RestartLastVotedForkSlots(_) => 12,
+            CrdsData::RestartHeaviestFork(_) => 13,
             // Update CrdsCountsArray if new items are added here.
         }
     }
@@ -75,10 +75,12 @@ impl CrdsGossip {
         pending_push_messages: Vec<CrdsValue>,
         now: u64,
         stakes: &HashMap<Pubkey, u64>,
+        max_egress_bytes: usize,
     ) -> (
         HashMap<Pubkey, Vec<CrdsValue>>,
         usize, // number of values
         usize, // number of push messages
+        bool,  // whether values were held back to stay within max_egress_bytes
     ) {
         {
             let mut crds = self.crds.write().unwrap();
@@ -86,7 +88,8 @@ impl CrdsGossip {
                 let _ = crds.insert(entry, now, GossipRoute::LocalMessage);
             }
         }
-        self.push.new_push_messages(pubkey, &self.crds, now, stakes)
+        self.push
+            .new_push_messages(pubkey, &self.crds, now, stakes, max_egress_bytes)
     }
 
     pub(crate) fn push_duplicate_shred<F>(
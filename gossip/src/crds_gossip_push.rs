@@ -42,7 +42,10 @@ use {
     },
 };
 
-const CRDS_GOSSIP_PUSH_FANOUT: usize = 9;
+pub(crate) const CRDS_GOSSIP_PUSH_FANOUT: usize = 9;
+// The bytes bincode adds to the values of a push message: the enum variant,
+// the sender's pubkey and the number of values.
+pub(crate) const PUSH_MESSAGE_HEADER_SIZE: usize = 44;
 // With a fanout of 9, a 2000 node cluster should only take ~3.5 hops to converge.
 // However since pushes are stake weighed, some trailing nodes
 // might need more time to receive values. 30 seconds should be plenty.
@@ -63,7 +66,8 @@ pub struct CrdsGossipPush {
     /// This cache represents a lagging view of which validators
     /// currently have this node in their `active_set`
     received_cache: Mutex<ReceivedCache>,
-    push_fanout: usize,
+    /// Adapted to the egress budget, if the node has one; see egress_budget.
+    push_fanout: AtomicUsize,
     pub(crate) msg_timeout: u64,
     pub prune_timeout: u64,
     pub num_total: AtomicUsize,
@@ -79,7 +83,7 @@ impl Default for CrdsGossipPush {
             active_set: RwLock::default(),
             crds_cursor: Mutex::default(),
             received_cache: Mutex::new(ReceivedCache::new(2 * CRDS_UNIQUE_PUBKEY_CAPACITY)),
-            push_fanout: CRDS_GOSSIP_PUSH_FANOUT,
+            push_fanout: AtomicUsize::new(CRDS_GOSSIP_PUSH_FANOUT),
             msg_timeout: CRDS_GOSSIP_PUSH_MSG_TIMEOUT_MS,
             prune_timeout: CRDS_GOSSIP_PRUNE_MSG_TIMEOUT_MS,
             num_total: AtomicUsize::default(),
@@ -120,6 +124,14 @@ impl CrdsGossipPush {
             .into_group_map()
     }
 
+    pub(crate) fn push_fanout(&self) -> usize {
+        self.push_fanout.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_push_fanout(&self, push_fanout: usize) {
+        self.push_fanout.store(push_fanout, Ordering::Relaxed);
+    }
+
     fn wallclock_window(&self, now: u64) -> impl RangeBounds<u64> {
         now.saturating_sub(self.msg_timeout)..=now.saturating_add(self.msg_timeout)
     }
@@ -169,21 +181,28 @@ impl CrdsGossipPush {
     /// peers.
     /// The list of push messages is created such that all the randomly selected peers have not
     /// pruned the source addresses.
+    /// Values which would take the push messages over `max_egress_bytes` are held back, and
+    /// returned again by the next call.
     pub(crate) fn new_push_messages(
         &self,
         pubkey: &Pubkey, // This node.
         crds: &RwLock<Crds>,
         now: u64,
         stakes: &HashMap<Pubkey, u64>,
+        max_egress_bytes: usize,
     ) -> (
         HashMap<Pubkey, Vec<CrdsValue>>,
         usize, // number of values
         usize, // number of push messages
+        bool,  // whether values were held back to stay within max_egress_bytes
     ) {
+        let push_fanout = self.push_fanout();
         let active_set = self.active_set.read().unwrap();
         let mut num_pushes = 0;
         let mut num_values = 0;
         let mut total_bytes: usize = 0;
+        let mut egress_bytes: usize = 0;
+        let mut held_back = None;
         let mut push_messages: HashMap<Pubkey, Vec<CrdsValue>> = HashMap::new();
         let wallclock_window = self.wallclock_window(now);
         let mut crds_cursor = self.crds_cursor.lock().unwrap();
@@ -196,32 +215,47 @@ impl CrdsGossipPush {
                 // until the cluster has upgraded.
                 !matches!(&entry.value.data, CrdsData::ContactInfo(_))
             })
-            .map(|entry| &entry.value)
-            .filter(|value| wallclock_window.contains(&value.wallclock()));
-        for value in entries {
+            .filter(|entry| wallclock_window.contains(&entry.value.wallclock()));
+        for entry in entries {
+            let value = &entry.value;
             let serialized_size = serialized_size(&value).unwrap();
             total_bytes = total_bytes.saturating_add(serialized_size as usize);
             if total_bytes > self.max_bytes {
                 break;
             }
-            num_values += 1;
             let origin = value.pubkey();
-            let nodes = active_set.get_nodes(
-                pubkey,
-                &origin,
-                |node| value.should_force_push(node),
-                stakes,
-            );
-            for node in nodes.take(self.push_fanout) {
+            let nodes: Vec<_> = active_set
+                .get_nodes(
+                    pubkey,
+                    &origin,
+                    |node| value.should_force_push(node),
+                    stakes,
+                )
+                .take(push_fanout)
+                .collect();
+            // Counts a packet header for each value, as packets hold at least
+            // one value each.
+            let value_egress_bytes =
+                (serialized_size as usize + PUSH_MESSAGE_HEADER_SIZE).saturating_mul(nodes.len());
+            egress_bytes = egress_bytes.saturating_add(value_egress_bytes);
+            if egress_bytes > max_egress_bytes {
+                held_back = Some(entry.clone());
+                break;
+            }
+            num_values += 1;
+            for node in nodes {
                 push_messages.entry(*node).or_default().push(value.clone());
                 num_pushes += 1;
             }
         }
+        if let Some(entry) = &held_back {
+            crds_cursor.rewind(entry);
+        }
         drop(crds);
         drop(crds_cursor);
         drop(active_set);
         self.num_pushes.fetch_add(num_pushes, Ordering::Relaxed);
-        (push_messages, num_values, num_pushes)
+        (push_messages, num_values, num_pushes, held_back.is_some())
     }
 
     /// Add the `from` to the peer's filter of nodes.
@@ -433,11 +467,73 @@ mod tests {
                 &crds,
                 0,
                 &HashMap::<Pubkey, u64>::default(), // stakes
+                usize::MAX,                         // max_egress_bytes
             )
             .0,
             expected
         );
     }
+
+    #[test]
+    fn test_new_push_messages_held_back() {
+        let now = timestamp();
+        let mut crds = Crds::default();
+        let push = CrdsGossipPush::default();
+        let mut ping_cache = new_ping_cache();
+        let peer = ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), 0);
+        ping_cache.mock_pong(*peer.pubkey(), peer.gossip().unwrap(), Instant::now());
+        let peer = CrdsValue::new_unsigned(CrdsData::LegacyContactInfo(peer));
+        assert_eq!(
+            crds.insert(peer.clone(), now, GossipRoute::LocalMessage),
+            Ok(())
+        );
+        let crds = RwLock::new(crds);
+        let ping_cache = Mutex::new(ping_cache);
+        push.refresh_push_active_set(
+            &crds,
+            &HashMap::new(), // stakes
+            None,            // gossip_validtors
+            &Keypair::new(),
+            0, // self_shred_version
+            &ping_cache,
+            &mut Vec::new(), // pings
+            &SocketAddrSpace::Unspecified,
+        );
+
+        let new_msgs: Vec<_> = (0..2)
+            .map(|_| {
+                CrdsValue::new_unsigned(CrdsData::LegacyContactInfo(ContactInfo::new_localhost(
+                    &solana_sdk::pubkey::new_rand(),
+                    0,
+                )))
+            })
+            .collect();
+        let origins: HashSet<_> = new_msgs.iter().map(CrdsValue::pubkey).collect();
+        assert_eq!(
+            push.process_push_message(&crds, vec![(Pubkey::default(), new_msgs.clone())], 0),
+            origins
+        );
+        let max_egress_bytes =
+            serialized_size(&new_msgs[0]).unwrap() as usize + PUSH_MESSAGE_HEADER_SIZE;
+        let mut new_push_messages = || {
+            push.new_push_messages(
+                &Pubkey::default(),
+                &crds,
+                0,
+                &HashMap::<Pubkey, u64>::default(), // stakes
+                max_egress_bytes,
+            )
+        };
+        // Only the first value fits, the second is held back for the next
+        // round.
+        for (new_msg, held_back) in new_msgs.iter().zip([true, false]) {
+            let (push_messages, _, _, was_held_back) = new_push_messages();
+            assert_eq!(was_held_back, held_back);
+            assert_eq!(push_messages[&peer.label().pubkey()], vec![new_msg.clone()]);
+        }
+        assert!(new_push_messages().0.is_empty());
+    }
+
     #[test]
     fn test_personalized_push_messages() {
         let now = timestamp();
@@ -497,6 +593,7 @@ mod tests {
                 &crds,
                 now,
                 &HashMap::<Pubkey, u64>::default(), // stakes
+                usize::MAX,                         // max_egress_bytes
             )
             .0,
             expected
@@ -548,6 +645,7 @@ mod tests {
                 &crds,
                 0,
                 &HashMap::<Pubkey, u64>::default(), // stakes
+                usize::MAX,                         // max_egress_bytes
             )
             .0,
             expected
@@ -589,6 +687,7 @@ mod tests {
                 &crds,
                 0,
                 &HashMap::<Pubkey, u64>::default(), // stakes
+                usize::MAX,                         // max_egress_bytes
             )
             .0,
             expected
@@ -0,0 +1,441 @@
+//! Records the crds values a node receives over gossip, and analyzes the
+//! recordings offline.
+//!
+//! A recording is a short header followed by a stream of bincode encoded
+//! [`CrdsRecord`]s, one for every crds value received in a push message, a
+//! pull response or a pull request, including the ones that fail signature
+//! verification.
+
+use {
+    crate::crds_value::{CrdsData, CrdsValue, CrdsValueLabel},
+    bincode::ErrorKind,
+    crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender},
+    solana_sdk::{
+        hash::{hash, Hash},
+        pubkey::Pubkey,
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs::File,
+        io::{self, BufReader, BufWriter, Read, Write},
+        net::SocketAddr,
+        path::Path,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        thread::{self, Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+const RECORDING_MAGIC: &[u8; 8] = b"SOLCRDS\0";
+const RECORDING_VERSION: u32 = 1;
+
+/// The gossip message a crds value was received in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CrdsRecordSource {
+    PullRequest,
+    PullResponse,
+    PushMessage,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrdsRecord {
+    /// Local timestamp (ms) at which the value was received
+    pub received_at: u64,
+    /// Address of the node which relayed the value
+    pub from: SocketAddr,
+    pub source: CrdsRecordSource,
+    /// Whether the value's signature verified against its origin
+    pub verified: bool,
+    pub value: CrdsValue,
+}
+
+impl CrdsRecord {
+    /// The node which created and signed the value
+    pub fn origin(&self) -> Pubkey {
+        self.value.pubkey()
+    }
+
+    pub fn wallclock(&self) -> u64 {
+        self.value.wallclock()
+    }
+
+    /// Milliseconds between the origin signing the value and it being
+    /// received; negative if the clocks of the two nodes are skewed.
+    pub fn delay(&self) -> i64 {
+        (self.received_at as i64).saturating_sub(self.wallclock() as i64)
+    }
+}
+
+pub(crate) fn crds_data_kind(data: &CrdsData) -> &'static str {
+    match data {
+        CrdsData::LegacyContactInfo(_) => "LegacyContactInfo",
+        CrdsData::Vote(_, _) => "Vote",
+        CrdsData::LowestSlot(_, _) => "LowestSlot",
+        CrdsData::LegacySnapshotHashes(_) => "LegacySnapshotHashes",
+        CrdsData::AccountsHashes(_) => "AccountsHashes",
+        CrdsData::EpochSlots(_, _) => "EpochSlots",
+        CrdsData::LegacyVersion(_) => "LegacyVersion",
+        CrdsData::Version(_) => "Version",
+        CrdsData::NodeInstance(_) => "NodeInstance",
+        CrdsData::DuplicateShred(_, _) => "DuplicateShred",
+        CrdsData::SnapshotHashes(_) => "SnapshotHashes",
+        CrdsData::ContactInfo(_) => "ContactInfo",
+        CrdsData::RestartLastVotedForkSlots(_) => "RestartLastVotedForkSlots",
+        CrdsData::RestartHeaviestFork(_) => "RestartHeaviestFork",
+    }
+}
+
+/// Appends the records sent to it to a recording.
+pub struct CrdsRecordWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CrdsRecordWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(RECORDING_MAGIC)?;
+        writer.write_all(&RECORDING_VERSION.to_le_bytes())?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, record: &CrdsRecord) -> bincode::Result<()> {
+        bincode::serialize_into(&mut self.writer, record)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Iterates over the records of a recording. A record truncated by the
+/// recorder being killed mid-write ends the iteration.
+pub struct CrdsRecordReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> CrdsRecordReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        if &magic != RECORDING_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a crds recording",
+            ));
+        }
+        let version = u32::from_le_bytes(version);
+        if version != RECORDING_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported crds recording version: {version}"),
+            ));
+        }
+        Ok(Self { reader })
+    }
+}
+
+impl CrdsRecordReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> Iterator for CrdsRecordReader<R> {
+    type Item = bincode::Result<CrdsRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match bincode::deserialize_from(&mut self.reader) {
+            Ok(record) => Some(Ok(record)),
+            Err(err) => match *err {
+                ErrorKind::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => None,
+                _ => Some(Err(err)),
+            },
+        }
+    }
+}
+
+/// Writes the records received from gossip to a file on a background
+/// thread, so that recording does not hold up packet processing.
+pub struct CrdsRecorder {
+    sender: Sender<Vec<CrdsRecord>>,
+    num_records: Arc<AtomicU64>,
+    thread_hdl: JoinHandle<()>,
+}
+
+impl CrdsRecorder {
+    pub fn new(path: &Path, exit: Arc<AtomicBool>) -> io::Result<Self> {
+        let mut writer = CrdsRecordWriter::new(BufWriter::new(File::create(path)?))?;
+        let (sender, receiver) = unbounded();
+        let num_records = Arc::<AtomicU64>::default();
+        let thread_hdl = {
+            let num_records = num_records.clone();
+            Builder::new()
+                .name("solCrdsRecorder".to_string())
+                .spawn(move || {
+                    if let Err(err) = run_recorder(&mut writer, &receiver, &num_records, &exit) {
+                        error!("crds recorder: {}", err);
+                    }
+                })
+                .unwrap()
+        };
+        Ok(Self {
+            sender,
+            num_records,
+            thread_hdl,
+        })
+    }
+
+    /// Sender to be handed to ClusterInfo::set_crds_record_sender
+    pub fn sender(&self) -> Sender<Vec<CrdsRecord>> {
+        self.sender.clone()
+    }
+
+    /// Number of records written so far
+    pub fn num_records(&self) -> u64 {
+        self.num_records.load(Ordering::Relaxed)
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+fn run_recorder<W: Write>(
+    writer: &mut CrdsRecordWriter<W>,
+    receiver: &Receiver<Vec<CrdsRecord>>,
+    num_records: &AtomicU64,
+    exit: &AtomicBool,
+) -> bincode::Result<()> {
+    const RECV_TIMEOUT: Duration = Duration::from_secs(1);
+    let write_records =
+        |writer: &mut CrdsRecordWriter<W>, records: Vec<CrdsRecord>| -> bincode::Result<()> {
+            for record in &records {
+                writer.write(record)?;
+            }
+            num_records.fetch_add(records.len() as u64, Ordering::Relaxed);
+            Ok(())
+        };
+    while !exit.load(Ordering::Relaxed) {
+        let records = match receiver.recv_timeout(RECV_TIMEOUT) {
+            Ok(records) => records,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        for records in std::iter::once(records).chain(receiver.try_iter()) {
+            write_records(writer, records)?;
+        }
+        // Flush every batch so that a recorder which is killed rather than
+        // stopped loses at most the values received since.
+        writer.flush()?;
+    }
+    // Records sent before the exit was signaled are still written.
+    for records in receiver.try_iter() {
+        write_records(writer, records)?;
+    }
+    Ok(writer.flush()?)
+}
+
+#[derive(Debug, Default)]
+pub struct OriginStats {
+    pub num_values: usize,
+    pub num_bytes: u64,
+    /// Number of values received, by kind of crds data
+    pub kinds: BTreeMap<&'static str, usize>,
+    /// Values whose signature did not verify
+    pub num_invalid: usize,
+    /// Values older than one already received for the same label; these
+    /// would fail to insert into the crds table.
+    pub num_stale: usize,
+    /// Values identical to the newest one already received for the label
+    pub num_duplicate: usize,
+    /// Propagation delays (ms) of the first receipt of each new value
+    pub delays: Vec<i64>,
+}
+
+impl OriginStats {
+    /// Returns the given percentile of the propagation delays
+    pub fn delay_percentile(&self, percentile: usize) -> Option<i64> {
+        if self.delays.is_empty() {
+            return None;
+        }
+        let mut delays = self.delays.clone();
+        delays.sort_unstable();
+        let index = (delays.len() - 1) * percentile.min(100) / 100;
+        Some(delays[index])
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CrdsAnalysis {
+    pub num_records: usize,
+    pub sources: HashMap<CrdsRecordSource, usize>,
+    pub origins: HashMap<Pubkey, OriginStats>,
+    // Newest wallclock and value hash received for each label.
+    latest: HashMap<CrdsValueLabel, (u64, Hash)>,
+}
+
+impl CrdsAnalysis {
+    pub fn add(&mut self, record: &CrdsRecord) {
+        self.num_records += 1;
+        *self.sources.entry(record.source).or_default() += 1;
+        let stats = self.origins.entry(record.origin()).or_default();
+        stats.num_values += 1;
+        stats.num_bytes += record.value.size();
+        *stats
+            .kinds
+            .entry(crds_data_kind(&record.value.data))
+            .or_default() += 1;
+        if !record.verified {
+            // The origin of a value which fails verification is unauthenticated,
+            // so it must not shadow the genuine values for the label.
+            stats.num_invalid += 1;
+            return;
+        }
+        let wallclock = record.wallclock();
+        let value_hash = hash(&bincode::serialize(&record.value).unwrap());
+        match self.latest.get(&record.value.label()) {
+            Some((latest_wallclock, _)) if wallclock < *latest_wallclock => {
+                stats.num_stale += 1;
+            }
+            Some((latest_wallclock, latest_hash))
+                if wallclock == *latest_wallclock && value_hash == *latest_hash =>
+            {
+                stats.num_duplicate += 1;
+            }
+            _ => {
+                stats.delays.push(record.delay());
+                self.latest
+                    .insert(record.value.label(), (wallclock, value_hash));
+            }
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a CrdsRecord> for CrdsAnalysis {
+    fn from_iter<I: IntoIterator<Item = &'a CrdsRecord>>(records: I) -> Self {
+        let mut analysis = Self::default();
+        for record in records {
+            analysis.add(record);
+        }
+        analysis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::crds_value::{LowestSlot, NodeInstance},
+        rand::Rng,
+        solana_sdk::signature::{Keypair, Signer},
+        std::net::{IpAddr, Ipv4Addr},
+    };
+
+    fn new_record(value: CrdsValue, received_at: u64, verified: bool) -> CrdsRecord {
+        CrdsRecord {
+            received_at,
+            from: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8001),
+            source: CrdsRecordSource::PushMessage,
+            verified,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_crds_record_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let records: Vec<_> = (0..16)
+            .map(|_| {
+                let value = CrdsValue::new_rand(&mut rng, None);
+                new_record(value, rng.gen(), rng.gen())
+            })
+            .collect();
+        let mut writer = CrdsRecordWriter::new(Vec::<u8>::new()).unwrap();
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        let mut buffer = writer.writer;
+        let reader = CrdsRecordReader::new(&buffer[..]).unwrap();
+        let read: Vec<_> = reader.collect::<bincode::Result<_>>().unwrap();
+        assert_eq!(read, records);
+
+        // A truncated trailing record ends the iteration.
+        buffer.truncate(buffer.len() - 1);
+        let reader = CrdsRecordReader::new(&buffer[..]).unwrap();
+        let read: Vec<_> = reader.collect::<bincode::Result<_>>().unwrap();
+        assert_eq!(read, records[..records.len() - 1]);
+
+        // Anything else is rejected up front.
+        assert!(CrdsRecordReader::new(&[0u8; 16][..]).is_err());
+    }
+
+    #[test]
+    fn test_run_recorder_drains_on_exit() {
+        let mut rng = rand::thread_rng();
+        let records: Vec<_> = (0..4)
+            .map(|_| new_record(CrdsValue::new_rand(&mut rng, None), rng.gen(), true))
+            .collect();
+        let (sender, receiver) = unbounded();
+        sender.send(records[..2].to_vec()).unwrap();
+        sender.send(records[2..].to_vec()).unwrap();
+        // Records already sent are written even though the exit was signaled.
+        let exit = AtomicBool::new(true);
+        let num_records = AtomicU64::default();
+        let mut writer = CrdsRecordWriter::new(Vec::<u8>::new()).unwrap();
+        run_recorder(&mut writer, &receiver, &num_records, &exit).unwrap();
+        assert_eq!(num_records.load(Ordering::Relaxed), 4);
+        let reader = CrdsRecordReader::new(&writer.writer[..]).unwrap();
+        let read: Vec<_> = reader.collect::<bincode::Result<_>>().unwrap();
+        assert_eq!(read, records);
+    }
+
+    #[test]
+    fn test_crds_analysis() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let new_instance = |wallclock| {
+            let data = CrdsData::NodeInstance(NodeInstance::new(
+                &mut rand::thread_rng(),
+                pubkey,
+                wallclock,
+            ));
+            CrdsValue::new_signed(data, &keypair)
+        };
+        let older = new_instance(1_000);
+        let newer = new_instance(2_000);
+        let lowest_slot = CrdsValue::new_signed(
+            CrdsData::LowestSlot(0, LowestSlot::new(pubkey, 42, 1_500)),
+            &keypair,
+        );
+        let invalid = CrdsValue::new_unsigned(newer.data.clone());
+        let records = vec![
+            new_record(older.clone(), 1_100, true),
+            new_record(newer.clone(), 2_300, true),
+            // Duplicate of the newest value.
+            new_record(newer, 2_500, true),
+            // Superseded by a newer value.
+            new_record(older, 2_600, true),
+            new_record(lowest_slot, 1_400, true),
+            new_record(invalid, 2_700, false),
+        ];
+        let analysis: CrdsAnalysis = records.iter().collect();
+        assert_eq!(analysis.num_records, 6);
+        assert_eq!(analysis.sources[&CrdsRecordSource::PushMessage], 6);
+        assert_eq!(analysis.origins.len(), 1);
+        let stats = &analysis.origins[&pubkey];
+        assert_eq!(stats.num_values, 6);
+        assert_eq!(stats.kinds["NodeInstance"], 5);
+        assert_eq!(stats.kinds["LowestSlot"], 1);
+        assert_eq!(stats.num_invalid, 1);
+        assert_eq!(stats.num_stale, 1);
+        assert_eq!(stats.num_duplicate, 1);
+        assert_eq!(stats.delays, vec![100, 300, -100]);
+        assert_eq!(stats.delay_percentile(0), Some(-100));
+        assert_eq!(stats.delay_percentile(50), Some(100));
+        assert_eq!(stats.delay_percentile(100), Some(300));
+    }
+}
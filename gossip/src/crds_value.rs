@@ -95,6 +95,7 @@ pub enum CrdsData {
     SnapshotHashes(SnapshotHashes),
     ContactInfo(ContactInfo),
     RestartLastVotedForkSlots(RestartLastVotedForkSlots),
+    RestartHeaviestFork(RestartHeaviestFork),
 }
 
 impl Sanitize for CrdsData {
@@ -134,6 +135,7 @@ impl Sanitize for CrdsData {
             CrdsData::SnapshotHashes(val) => val.sanitize(),
             CrdsData::ContactInfo(node) => node.sanitize(),
             CrdsData::RestartLastVotedForkSlots(slots) => slots.sanitize(),
+            CrdsData::RestartHeaviestFork(fork) => fork.sanitize(),
         }
     }
 }
@@ -571,6 +573,40 @@ impl RestartLastVotedForkSlots {
     }
 }
 
+/// The heaviest fork a validator selected during a wen_restart, and the stake it observed
+/// taking part in the restart
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq, AbiExample, Debug)]
+pub struct RestartHeaviestFork {
+    pub from: Pubkey,
+    pub wallclock: u64,
+    pub last_slot: Slot,
+    pub last_slot_hash: Hash,
+    pub observed_stake: u64,
+    pub shred_version: u16,
+}
+
+impl Sanitize for RestartHeaviestFork {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        sanitize_wallclock(self.wallclock)?;
+        self.from.sanitize()?;
+        self.last_slot_hash.sanitize()
+    }
+}
+
+impl RestartHeaviestFork {
+    /// New random RestartHeaviestFork for tests and benchmarks.
+    pub fn new_rand<R: Rng>(rng: &mut R, pubkey: Option<Pubkey>) -> Self {
+        Self {
+            from: pubkey.unwrap_or_else(solana_sdk::pubkey::new_rand),
+            wallclock: new_rand_timestamp(rng),
+            last_slot: rng.gen_range(0..1000),
+            last_slot_hash: Hash::new_unique(),
+            observed_stake: rng.gen_range(1..u64::MAX),
+            shred_version: 1,
+        }
+    }
+}
+
 /// Type of the replicated value
 /// These are labels for values in a record that is associated with `Pubkey`
 #[derive(PartialEq, Hash, Eq, Clone, Debug)]
@@ -588,6 +624,7 @@ pub enum CrdsValueLabel {
     SnapshotHashes(Pubkey),
     ContactInfo(Pubkey),
     RestartLastVotedForkSlots(Pubkey),
+    RestartHeaviestFork(Pubkey),
 }
 
 impl fmt::Display for CrdsValueLabel {
@@ -614,6 +651,9 @@ impl fmt::Display for CrdsValueLabel {
             CrdsValueLabel::RestartLastVotedForkSlots(_) => {
                 write!(f, "RestartLastVotedForkSlots({})", self.pubkey())
             }
+            CrdsValueLabel::RestartHeaviestFork(_) => {
+                write!(f, "RestartHeaviestFork({})", self.pubkey())
+            }
         }
     }
 }
@@ -634,6 +674,7 @@ impl CrdsValueLabel {
             CrdsValueLabel::SnapshotHashes(p) => *p,
             CrdsValueLabel::ContactInfo(pubkey) => *pubkey,
             CrdsValueLabel::RestartLastVotedForkSlots(p) => *p,
+            CrdsValueLabel::RestartHeaviestFork(p) => *p,
         }
     }
 }
@@ -685,6 +726,7 @@ impl CrdsValue {
             CrdsData::SnapshotHashes(hash) => hash.wallclock,
             CrdsData::ContactInfo(node) => node.wallclock(),
             CrdsData::RestartLastVotedForkSlots(slots) => slots.wallclock,
+            CrdsData::RestartHeaviestFork(fork) => fork.wallclock,
         }
     }
     pub fn pubkey(&self) -> Pubkey {
@@ -702,6 +744,7 @@ impl CrdsValue {
             CrdsData::SnapshotHashes(hash) => hash.from,
             CrdsData::ContactInfo(node) => *node.pubkey(),
             CrdsData::RestartLastVotedForkSlots(slots) => slots.from,
+            CrdsData::RestartHeaviestFork(fork) => fork.from,
         }
     }
     pub fn label(&self) -> CrdsValueLabel {
@@ -723,6 +766,7 @@ impl CrdsValue {
             CrdsData::RestartLastVotedForkSlots(_) => {
                 CrdsValueLabel::RestartLastVotedForkSlots(self.pubkey())
             }
+            CrdsData::RestartHeaviestFork(_) => CrdsValueLabel::RestartHeaviestFork(self.pubkey()),
         }
     }
     pub fn contact_info(&self) -> Option<&LegacyContactInfo> {
@@ -1223,4 +1267,33 @@ mod test {
         assert_eq!(retrived_slots.first(), Some(&11));
         assert_eq!(retrived_slots.last(), Some(&last_slot));
     }
+
+    #[test]
+    fn test_restart_heaviest_fork() {
+        let keypair = Keypair::new();
+        let fork = RestartHeaviestFork {
+            from: keypair.pubkey(),
+            wallclock: timestamp(),
+            last_slot: 53,
+            last_slot_hash: Hash::new_unique(),
+            observed_stake: 800_000,
+            shred_version: 21,
+        };
+        let value = CrdsValue::new_signed(CrdsData::RestartHeaviestFork(fork.clone()), &keypair);
+        assert_eq!(value.sanitize(), Ok(()));
+        assert!(value.verify());
+        let label = value.label();
+        assert_eq!(label, CrdsValueLabel::RestartHeaviestFork(keypair.pubkey()));
+        assert_eq!(label.pubkey(), keypair.pubkey());
+        assert_eq!(value.wallclock(), fork.wallclock);
+
+        let bad_value = CrdsValue::new_signed(
+            CrdsData::RestartHeaviestFork(RestartHeaviestFork {
+                wallclock: MAX_WALLCLOCK,
+                ..fork
+            }),
+            &keypair,
+        );
+        assert_eq!(bad_value.sanitize(), Err(SanitizeError::ValueOutOfBounds));
+    }
 }
@@ -126,10 +126,11 @@ impl DuplicateShredHandler {
                 .ok_or(Error::UnknownSlotLeader(slot))?;
             let (shred1, shred2) = duplicate_shred::into_shreds(&pubkey, chunks)?;
             if !self.blockstore.has_duplicate_shreds_in_slot(slot) {
-                self.blockstore.store_duplicate_slot(
+                self.blockstore.store_duplicate_slot_with_leader(
                     slot,
                     shred1.into_payload(),
                     shred2.into_payload(),
+                    pubkey,
                 )?;
             }
             self.consumed.insert(slot, true);
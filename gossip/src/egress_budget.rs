@@ -0,0 +1,240 @@
+//! Caps the bytes per second a node sends in gossip push messages, pull
+//! requests and pull responses.
+//!
+//! Values which do not fit in the budget are held back from push messages and
+//! pushed in a later round, rather than dropped. The push fanout adapts to the
+//! budget: it shrinks each round values had to be held back and grows back, up
+//! to the default fanout, while the budget is largely unused. Pull requests
+//! which do not fit are dropped, those addressed to the lowest staked nodes
+//! first, and so are the pull responses ranked last for sending. Since they
+//! take from what is left after push messages, pulls become less frequent as
+//! the budget tightens.
+
+use {
+    serde::Serialize,
+    solana_sdk::{packet::PACKET_DATA_SIZE, pubkey::Pubkey},
+    std::{
+        cmp::Reverse,
+        net::SocketAddr,
+        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+        time::Instant,
+    },
+};
+
+// Allow the budget to build up to this many milliseconds worth of bytes.
+const MAX_BUDGET_MS: u64 = 500;
+pub(crate) const MIN_PUSH_FANOUT: usize = 2;
+
+pub(crate) struct EgressBudget {
+    bytes: AtomicUsize,
+    bytes_per_second: u64,
+    start: Instant,
+    // Milliseconds since start up to which the budget has been credited.
+    credited_until: AtomicU64,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct EgressBudgetStats {
+    pub(crate) sent_bytes: usize,
+    pub(crate) dropped_packets: usize,
+    pub(crate) dropped_bytes: usize,
+}
+
+impl EgressBudget {
+    pub(crate) fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes: AtomicUsize::default(),
+            bytes_per_second,
+            start: Instant::now(),
+            credited_until: AtomicU64::default(),
+        }
+    }
+
+    /// Credits the budget with the bytes accrued since it was last refilled,
+    /// and returns the bytes available.
+    pub(crate) fn refill(&self) -> usize {
+        let now = u64::try_from(self.start.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.refill_at(now)
+    }
+
+    fn refill_at(&self, now: u64) -> usize {
+        let max_bytes = self.bytes_per_second.saturating_mul(MAX_BUDGET_MS) / 1000;
+        // Small budgets may build up further, so that push messages holding
+        // even the largest value can eventually be sent at the minimum fanout.
+        let max_bytes = usize::try_from(max_bytes)
+            .unwrap_or(usize::MAX)
+            .max(MIN_PUSH_FANOUT * PACKET_DATA_SIZE);
+        let mut credited_until = self.credited_until.load(Ordering::Acquire);
+        let bytes = loop {
+            let elapsed = now.saturating_sub(credited_until);
+            let bytes = self
+                .bytes_per_second
+                .saturating_mul(elapsed.min(MAX_BUDGET_MS))
+                / 1000;
+            if bytes == 0 {
+                return self.bytes.load(Ordering::Acquire);
+            }
+            // Leave the fraction of a byte accrued so far to the next refill,
+            // so that small budgets still fill up.
+            let credited = if elapsed > MAX_BUDGET_MS {
+                now
+            } else {
+                credited_until + bytes.saturating_mul(1000) / self.bytes_per_second
+            };
+            credited_until = match self.credited_until.compare_exchange_weak(
+                credited_until,
+                credited,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break usize::try_from(bytes).unwrap_or(usize::MAX),
+                Err(credited_until) => credited_until,
+            };
+        };
+        let credit = |available: usize| available.saturating_add(bytes).min(max_bytes);
+        let available = self
+            .bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |available| {
+                Some(credit(available))
+            })
+            .unwrap();
+        credit(available)
+    }
+
+    /// Takes `bytes` from the budget if they are available.
+    pub(crate) fn take(&self, bytes: usize) -> bool {
+        self.bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |available| {
+                available.checked_sub(bytes)
+            })
+            .is_ok()
+    }
+
+    /// Takes `bytes` from the budget, or all of it if less is available.
+    pub(crate) fn consume(&self, bytes: usize) {
+        let _ = self
+            .bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |available| {
+                Some(available.saturating_sub(bytes))
+            });
+    }
+
+    /// Retains the packets the budget allows, preferring those addressed to
+    /// the highest staked nodes.
+    pub(crate) fn filter_by_stake<T: Serialize>(
+        &self,
+        mut packets: Vec<(/*node:*/ Pubkey, SocketAddr, T)>,
+        stake: impl Fn(&Pubkey) -> u64,
+    ) -> (Vec<(SocketAddr, T)>, EgressBudgetStats) {
+        // Stable sort, so packets to the same node keep their order.
+        packets.sort_by_key(|(node, _, _)| Reverse(stake(node)));
+        let mut stats = EgressBudgetStats::default();
+        let packets = packets
+            .into_iter()
+            .filter_map(|(_, addr, packet)| {
+                let size = bincode::serialized_size(&packet).unwrap_or(u64::MAX);
+                let size = usize::try_from(size).unwrap_or(usize::MAX);
+                if self.take(size) {
+                    stats.sent_bytes += size;
+                    Some((addr, packet))
+                } else {
+                    stats.dropped_packets += 1;
+                    stats.dropped_bytes = stats.dropped_bytes.saturating_add(size);
+                    None
+                }
+            })
+            .collect();
+        (packets, stats)
+    }
+}
+
+/// Returns the push fanout for the next round, given the fanout of the last
+/// round and how it fared against the budget.
+pub(crate) fn adapt_push_fanout(
+    fanout: usize,
+    max_fanout: usize,
+    held_back: bool,
+    sent_bytes: usize,
+    bytes_available: usize,
+) -> usize {
+    if held_back {
+        fanout.saturating_sub(1).max(MIN_PUSH_FANOUT)
+    } else if sent_bytes.saturating_mul(2) < bytes_available {
+        fanout.saturating_add(1).min(max_fanout)
+    } else {
+        fanout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            collections::HashMap,
+            net::{IpAddr, Ipv4Addr},
+        },
+    };
+
+    #[test]
+    fn test_filter_by_stake() {
+        let budget = EgressBudget::new(10_000);
+        assert_eq!(budget.refill_at(100), 1_000);
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8001);
+        let nodes: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let stakes: HashMap<_, _> = nodes.iter().copied().zip([1, 4, 3, 2]).collect();
+        // Each packet serializes to 8 + 300 bytes, so only 3 fit the budget.
+        let packets: Vec<_> = nodes
+            .iter()
+            .enumerate()
+            .map(|(k, node)| (*node, addr, vec![k as u8; 300]))
+            .collect();
+        let (packets, stats) = budget.filter_by_stake(packets, |node| stakes[node]);
+        let sent: Vec<_> = packets.iter().map(|(_, packet)| packet[0]).collect();
+        assert_eq!(sent, vec![1, 2, 3]);
+        assert_eq!(
+            stats,
+            EgressBudgetStats {
+                sent_bytes: 3 * 308,
+                dropped_packets: 1,
+                dropped_bytes: 308,
+            }
+        );
+    }
+
+    #[test]
+    fn test_refill() {
+        let budget = EgressBudget::new(10_000);
+        assert_eq!(budget.refill_at(0), 0);
+        assert_eq!(budget.refill_at(50), 500);
+        // Each elapsed millisecond is only credited once.
+        assert_eq!(budget.refill_at(50), 500);
+        assert!(budget.take(300));
+        assert!(!budget.take(300));
+        assert_eq!(budget.refill_at(80), 500);
+        // The budget builds up to MAX_BUDGET_MS worth of bytes.
+        assert_eq!(budget.refill_at(10_000), 5_000);
+        budget.consume(6_000);
+        assert_eq!(budget.refill_at(10_000), 0);
+
+        // Fractions of a byte carry over to the next refill.
+        let budget = EgressBudget::new(10);
+        assert_eq!(budget.refill_at(60), 0);
+        assert_eq!(budget.refill_at(120), 1);
+        assert_eq!(budget.refill_at(180), 1);
+        assert_eq!(budget.refill_at(200), 2);
+    }
+
+    #[test]
+    fn test_adapt_push_fanout() {
+        assert_eq!(adapt_push_fanout(9, 9, true, 1_000, 1_000), 8);
+        assert_eq!(
+            adapt_push_fanout(MIN_PUSH_FANOUT, 9, true, 1_000, 1_000),
+            MIN_PUSH_FANOUT
+        );
+        // Underused budget.
+        assert_eq!(adapt_push_fanout(5, 9, false, 400, 1_000), 6);
+        assert_eq!(adapt_push_fanout(9, 9, false, 400, 1_000), 9);
+        assert_eq!(adapt_push_fanout(5, 9, false, 600, 1_000), 5);
+    }
+}
@@ -10,12 +10,14 @@ pub mod crds_gossip;
 pub mod crds_gossip_error;
 pub mod crds_gossip_pull;
 pub mod crds_gossip_push;
+pub mod crds_recorder;
 pub mod crds_shards;
 pub mod crds_value;
 mod deprecated;
 pub mod duplicate_shred;
 pub mod duplicate_shred_handler;
 pub mod duplicate_shred_listener;
+mod egress_budget;
 pub mod epoch_slots;
 pub mod gossip_error;
 pub mod gossip_service;
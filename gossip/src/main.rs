@@ -5,21 +5,37 @@ use {
         crate_description, crate_name, value_t, value_t_or_exit, App, AppSettings, Arg, ArgMatches,
         SubCommand,
     },
+    log::*,
     solana_clap_utils::{
         hidden_unless_forced,
         input_parsers::{keypair_of, pubkeys_of},
-        input_validators::{is_keypair_or_ask_keyword, is_port, is_pubkey},
+        input_validators::{is_keypair_or_ask_keyword, is_parsable, is_port, is_pubkey},
     },
     solana_gossip::{
-        gossip_service::discover, legacy_contact_info::LegacyContactInfo as ContactInfo,
+        cluster_info::ClusterInfo,
+        crds_recorder::{
+            CrdsAnalysis, CrdsRecordReader, CrdsRecordSource, CrdsRecorder, OriginStats,
+        },
+        gossip_service::{discover, GossipService},
+        legacy_contact_info::LegacyContactInfo as ContactInfo,
+    },
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
     },
-    solana_sdk::pubkey::Pubkey,
     solana_streamer::socket::SocketAddrSpace,
     std::{
+        collections::BTreeMap,
         error,
         net::{IpAddr, Ipv4Addr, SocketAddr},
+        path::Path,
         process::exit,
-        time::Duration,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::sleep,
+        time::{Duration, Instant},
     },
 };
 
@@ -152,6 +168,46 @@ fn parse_matches() -> ArgMatches<'static> {
                         .value_name("SECONDS")
                         .takes_value(true)
                         .help("Maximum time to wait in seconds [default: wait forever]"),
+                )
+                .arg(
+                    Arg::with_name("record_crds")
+                        .long("record-crds")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .conflicts_with_all(&["num_nodes", "num_nodes_exactly", "node_pubkey"])
+                        .help("Stay in gossip until --timeout, recording every CRDS value \
+                               received to FILE for later use with `analyze-crds`"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("analyze-crds")
+                .about("Report on the CRDS values recorded with `spy --record-crds`")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("recording")
+                        .index(1)
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("CRDS recording to analyze"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("NUM")
+                        .takes_value(true)
+                        .default_value("50")
+                        .validator(is_parsable::<usize>)
+                        .help("Report only the NUM origins with the most values"),
+                )
+                .arg(
+                    Arg::with_name("origin")
+                        .long("origin")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .multiple(true)
+                        .help("Also break down the values of this origin by kind"),
                 ),
         )
         .get_matches()
@@ -243,6 +299,17 @@ fn process_spy(matches: &ArgMatches, socket_addr_space: SocketAddrSpace) -> std:
             .expect("unable to find an available gossip port")
         }),
     );
+    if let Some(recording) = matches.value_of("record_crds") {
+        return record_crds(
+            Path::new(recording),
+            identity_keypair,
+            entrypoint_addr,
+            &gossip_addr,
+            shred_version,
+            timeout.map(Duration::from_secs),
+            socket_addr_space,
+        );
+    }
     let discover_timeout = Duration::from_secs(timeout.unwrap_or(u64::MAX));
     let (_all_peers, validators) = discover(
         identity_keypair,
@@ -267,6 +334,150 @@ fn process_spy(matches: &ArgMatches, socket_addr_space: SocketAddrSpace) -> std:
     Ok(())
 }
 
+fn record_crds(
+    recording: &Path,
+    identity_keypair: Option<Keypair>,
+    entrypoint_addr: Option<SocketAddr>,
+    gossip_addr: &SocketAddr,
+    shred_version: u16,
+    timeout: Option<Duration>,
+    socket_addr_space: SocketAddrSpace,
+) -> std::io::Result<()> {
+    const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+    let keypair = identity_keypair.unwrap_or_else(Keypair::new);
+    let exit = Arc::new(AtomicBool::new(false));
+    let recorder = CrdsRecorder::new(recording, exit.clone())?;
+    let (node, gossip_socket, ip_echo) =
+        ClusterInfo::gossip_node(keypair.pubkey(), gossip_addr, shred_version);
+    let mut cluster_info = ClusterInfo::new(node, Arc::new(keypair), socket_addr_space);
+    cluster_info.set_crds_record_sender(recorder.sender());
+    if let Some(entrypoint_addr) = entrypoint_addr {
+        cluster_info.set_entrypoint(ContactInfo::new_gossip_entry_point(&entrypoint_addr));
+    }
+    let cluster_info = Arc::new(cluster_info);
+    let gossip_service = GossipService::new(
+        &cluster_info,
+        None, // bank_forks
+        gossip_socket,
+        None, // gossip_validators
+        true, // should_check_duplicate_instance
+        None, // stats_reporter_sender
+        exit.clone(),
+    );
+    let _ip_echo_server = ip_echo
+        .map(|tcp_listener| solana_net_utils::ip_echo_server(tcp_listener, Some(shred_version)));
+    info!("Node Id: {}", cluster_info.id());
+    info!("Recording CRDS values to {}", recording.display());
+
+    let start = Instant::now();
+    let mut last_report = Instant::now();
+    while timeout
+        .map(|timeout| start.elapsed() < timeout)
+        .unwrap_or(true)
+    {
+        sleep(Duration::from_millis(100));
+        if last_report.elapsed() > REPORT_INTERVAL {
+            info!(
+                "Recorded {} CRDS values, {} gossip peers",
+                recorder.num_records(),
+                cluster_info.all_peers().len(),
+            );
+            last_report = Instant::now();
+        }
+    }
+    exit.store(true, Ordering::Relaxed);
+    gossip_service.join().unwrap();
+    recorder.join().unwrap();
+    Ok(())
+}
+
+fn process_analyze_crds(matches: &ArgMatches) -> std::io::Result<()> {
+    let recording = value_t_or_exit!(matches, "recording", String);
+    let limit = value_t_or_exit!(matches, "limit", usize);
+    let origins = pubkeys_of(matches, "origin").unwrap_or_default();
+
+    let mut analysis = CrdsAnalysis::default();
+    for record in CrdsRecordReader::open(Path::new(&recording))? {
+        match record {
+            Ok(record) => analysis.add(&record),
+            Err(err) => {
+                eprintln!("Failed to read CRDS recording {recording}: {err}");
+                exit(1);
+            }
+        }
+    }
+
+    let num_from = |source| analysis.sources.get(&source).copied().unwrap_or_default();
+    println!(
+        "{} CRDS values: {} push messages, {} pull responses, {} pull requests",
+        analysis.num_records,
+        num_from(CrdsRecordSource::PushMessage),
+        num_from(CrdsRecordSource::PullResponse),
+        num_from(CrdsRecordSource::PullRequest),
+    );
+    let mut kinds = BTreeMap::<&str, usize>::new();
+    for stats in analysis.origins.values() {
+        for (kind, count) in &stats.kinds {
+            *kinds.entry(kind).or_default() += count;
+        }
+    }
+    for (kind, count) in kinds {
+        println!("  {kind:<26} {count:>10}");
+    }
+    println!();
+
+    let mut sorted: Vec<_> = analysis.origins.iter().collect();
+    sorted.sort_by_key(|(pubkey, stats)| (std::cmp::Reverse(stats.num_values), **pubkey));
+    println!(
+        "{:<44} {:>10} {:>12} {:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        "Origin",
+        "Values",
+        "Bytes",
+        "Invalid",
+        "Stale",
+        "Duplicate",
+        "Delay p50",
+        "Delay p90",
+        "Delay max",
+    );
+    let delay = |stats: &OriginStats, percentile| {
+        stats
+            .delay_percentile(percentile)
+            .map(|delay| format!("{delay}ms"))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    for (pubkey, stats) in sorted.iter().take(limit) {
+        println!(
+            "{:<44} {:>10} {:>12} {:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+            pubkey.to_string(),
+            stats.num_values,
+            stats.num_bytes,
+            stats.num_invalid,
+            stats.num_stale,
+            stats.num_duplicate,
+            delay(stats, 50),
+            delay(stats, 90),
+            delay(stats, 100),
+        );
+    }
+    if sorted.len() > limit {
+        println!("... and {} more origins", sorted.len() - limit);
+    }
+
+    for origin in origins {
+        println!();
+        let Some(stats) = analysis.origins.get(&origin) else {
+            println!("{origin}: no values recorded");
+            continue;
+        };
+        println!("{origin}:");
+        for (kind, count) in &stats.kinds {
+            println!("  {kind:<26} {count:>10}");
+        }
+    }
+    Ok(())
+}
+
 fn parse_entrypoint(matches: &ArgMatches) -> Option<SocketAddr> {
     matches.value_of("entrypoint").map(|entrypoint| {
         solana_net_utils::parse_host_port(entrypoint).unwrap_or_else(|e| {
@@ -337,6 +548,9 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         ("rpc-url", Some(matches)) => {
             process_rpc_url(matches, socket_addr_space)?;
         }
+        ("analyze-crds", Some(matches)) => {
+            process_analyze_crds(matches)?;
+        }
         _ => unreachable!(),
     }
 
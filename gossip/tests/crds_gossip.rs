@@ -352,7 +352,7 @@ fn network_run_push(
                 (
                     node_pubkey,
                     node.gossip
-                        .new_push_messages(&node_pubkey, vec![], now, &stakes)
+                        .new_push_messages(&node_pubkey, vec![], now, &stakes, usize::MAX)
                         .0,
                 )
             })
@@ -0,0 +1,520 @@
+//! The historical subset of the Solana RPC interface, served from a blockstore alone.
+//!
+//! Only rooted data is served. A blockstore opened with secondary access has no view of the
+//! validator's banks, so requests with `confirmed` commitment are answered as if they asked for
+//! `finalized`.
+use {
+    jsonrpc_core::{Error, Metadata, Result},
+    jsonrpc_derive::rpc,
+    log::*,
+    solana_ledger::{
+        blockstore::{Blockstore, SignatureInfosForAddress},
+        blockstore_db::BlockstoreError,
+    },
+    solana_rpc_client_api::{
+        config::{
+            RpcBlockConfig, RpcBlocksConfigWrapper, RpcEncodingConfigWrapper,
+            RpcSignaturesForAddressConfig, RpcTransactionConfig,
+        },
+        custom_error::RpcCustomError,
+        request::{
+            MAX_GET_CONFIRMED_BLOCKS_RANGE, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
+        },
+        response::RpcConfirmedTransactionStatusWithSignature,
+    },
+    solana_sdk::{
+        clock::{Slot, UnixTimestamp},
+        commitment_config::CommitmentConfig,
+        pubkey::Pubkey,
+        signature::Signature,
+    },
+    solana_transaction_status::{
+        BlockEncodingOptions, ConfirmedBlock, EncodedConfirmedTransactionWithStatusMeta,
+        TransactionConfirmationStatus, UiConfirmedBlock, UiTransactionEncoding,
+    },
+    std::{cmp::max, sync::Arc},
+};
+
+#[derive(Clone)]
+pub struct HistoryRpcRequestProcessor {
+    blockstore: Arc<Blockstore>,
+    genesis_creation_time: UnixTimestamp,
+}
+
+impl Metadata for HistoryRpcRequestProcessor {}
+
+impl HistoryRpcRequestProcessor {
+    pub fn new(blockstore: Arc<Blockstore>, genesis_creation_time: UnixTimestamp) -> Self {
+        Self {
+            blockstore,
+            genesis_creation_time,
+        }
+    }
+
+    fn check_blockstore_root<T>(
+        &self,
+        result: &std::result::Result<T, BlockstoreError>,
+        slot: Slot,
+    ) -> Result<()> {
+        if let Err(err) = result {
+            debug!(
+                "check_blockstore_root, slot: {:?}, max root: {:?}, err: {:?}",
+                slot,
+                self.blockstore.max_root(),
+                err
+            );
+            if slot >= self.blockstore.max_root() {
+                return Err(RpcCustomError::BlockNotAvailable { slot }.into());
+            }
+            if self.blockstore.is_skipped(slot) {
+                return Err(RpcCustomError::SlotSkipped { slot }.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn check_slot_cleaned_up<T>(
+        &self,
+        result: &std::result::Result<T, BlockstoreError>,
+        slot: Slot,
+    ) -> Result<()> {
+        let first_available_block = self
+            .blockstore
+            .get_first_available_block()
+            .unwrap_or_default();
+        let err: Error = RpcCustomError::BlockCleanedUp {
+            slot,
+            first_available_block,
+        }
+        .into();
+        if let Err(BlockstoreError::SlotCleanedUp) = result {
+            return Err(err);
+        }
+        if slot < first_available_block {
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    pub fn get_block(
+        &self,
+        slot: Slot,
+        config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+    ) -> Result<Option<UiConfirmedBlock>> {
+        let config = config
+            .map(|config| config.convert_to_current())
+            .unwrap_or_default();
+        let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Json);
+        let encoding_options = BlockEncodingOptions {
+            transaction_details: config.transaction_details.unwrap_or_default(),
+            show_rewards: config.rewards.unwrap_or(true),
+            max_supported_transaction_version: config.max_supported_transaction_version,
+        };
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+
+        let result = self.blockstore.get_rooted_block(slot, true);
+        self.check_blockstore_root(&result, slot)?;
+        self.check_slot_cleaned_up(&result, slot)?;
+        result
+            .ok()
+            .map(ConfirmedBlock::from)
+            .map(|confirmed_block| -> Result<UiConfirmedBlock> {
+                let mut encoded_block = confirmed_block
+                    .encode_with_options(encoding, encoding_options)
+                    .map_err(RpcCustomError::from)?;
+                if slot == 0 {
+                    encoded_block.block_time = Some(self.genesis_creation_time);
+                    encoded_block.block_height = Some(0);
+                }
+                Ok(encoded_block)
+            })
+            .transpose()
+    }
+
+    pub fn get_blocks(
+        &self,
+        start_slot: Slot,
+        end_slot: Option<Slot>,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<Slot>> {
+        check_is_at_least_confirmed(commitment.unwrap_or_default())?;
+
+        let end_slot = end_slot
+            .unwrap_or_else(|| start_slot.saturating_add(MAX_GET_CONFIRMED_BLOCKS_RANGE))
+            .min(self.blockstore.max_root());
+        if end_slot < start_slot {
+            return Ok(vec![]);
+        }
+        if end_slot - start_slot > MAX_GET_CONFIRMED_BLOCKS_RANGE {
+            return Err(Error::invalid_params(format!(
+                "Slot range too large; max {MAX_GET_CONFIRMED_BLOCKS_RANGE}"
+            )));
+        }
+
+        let lowest_blockstore_slot = self
+            .blockstore
+            .get_first_available_block()
+            .unwrap_or_default();
+        Ok(self
+            .blockstore
+            .rooted_slot_iterator(max(start_slot, lowest_blockstore_slot))
+            .map_err(|_| Error::internal_error())?
+            .take_while(|&slot| slot <= end_slot)
+            .collect())
+    }
+
+    pub fn get_transaction(
+        &self,
+        signature: Signature,
+        config: Option<RpcEncodingConfigWrapper<RpcTransactionConfig>>,
+    ) -> Result<Option<EncodedConfirmedTransactionWithStatusMeta>> {
+        let config = config
+            .map(|config| config.convert_to_current())
+            .unwrap_or_default();
+        let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Json);
+        check_is_at_least_confirmed(config.commitment.unwrap_or_default())?;
+
+        self.blockstore
+            .get_rooted_transaction(signature)
+            .unwrap_or(None)
+            .map(|confirmed_transaction| {
+                Ok(confirmed_transaction
+                    .encode(encoding, config.max_supported_transaction_version)
+                    .map_err(RpcCustomError::from)?)
+            })
+            .transpose()
+    }
+
+    pub fn get_signatures_for_address(
+        &self,
+        address: Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+        commitment: Option<CommitmentConfig>,
+        min_context_slot: Option<Slot>,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        check_is_at_least_confirmed(commitment.unwrap_or_default())?;
+
+        let highest_root = self.blockstore.max_root();
+        if highest_root < min_context_slot.unwrap_or_default() {
+            return Err(RpcCustomError::MinContextSlotNotReached {
+                context_slot: highest_root,
+            }
+            .into());
+        }
+        let SignatureInfosForAddress { infos, .. } = self
+            .blockstore
+            .get_confirmed_signatures_for_address2(address, highest_root, before, until, limit)
+            .map_err(|err| Error::invalid_params(format!("{err}")))?;
+        Ok(infos
+            .into_iter()
+            .map(|info| {
+                let mut item: RpcConfirmedTransactionStatusWithSignature = info.into();
+                item.confirmation_status = Some(TransactionConfirmationStatus::Finalized);
+                item
+            })
+            .collect())
+    }
+}
+
+fn check_is_at_least_confirmed(commitment: CommitmentConfig) -> Result<()> {
+    if !commitment.is_at_least_confirmed() {
+        return Err(Error::invalid_params(
+            "Method does not support commitment below `confirmed`",
+        ));
+    }
+    Ok(())
+}
+
+fn verify_pubkey(input: &str) -> Result<Pubkey> {
+    input
+        .parse()
+        .map_err(|e| Error::invalid_params(format!("Invalid param: {e:?}")))
+}
+
+fn verify_signature(input: &str) -> Result<Signature> {
+    input
+        .parse()
+        .map_err(|e| Error::invalid_params(format!("Invalid param: {e:?}")))
+}
+
+pub mod rpc_history {
+    use super::*;
+    #[rpc]
+    pub trait History {
+        type Metadata;
+
+        #[rpc(meta, name = "getBlock")]
+        fn get_block(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+        ) -> Result<Option<UiConfirmedBlock>>;
+
+        #[rpc(meta, name = "getBlocks")]
+        fn get_blocks(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            config: Option<RpcBlocksConfigWrapper>,
+            commitment: Option<CommitmentConfig>,
+        ) -> Result<Vec<Slot>>;
+
+        #[rpc(meta, name = "getTransaction")]
+        fn get_transaction(
+            &self,
+            meta: Self::Metadata,
+            signature_str: String,
+            config: Option<RpcEncodingConfigWrapper<RpcTransactionConfig>>,
+        ) -> Result<Option<EncodedConfirmedTransactionWithStatusMeta>>;
+
+        #[rpc(meta, name = "getSignaturesForAddress")]
+        fn get_signatures_for_address(
+            &self,
+            meta: Self::Metadata,
+            address: String,
+            config: Option<RpcSignaturesForAddressConfig>,
+        ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>>;
+    }
+
+    pub struct HistoryImpl;
+    impl History for HistoryImpl {
+        type Metadata = HistoryRpcRequestProcessor;
+
+        fn get_block(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            config: Option<RpcEncodingConfigWrapper<RpcBlockConfig>>,
+        ) -> Result<Option<UiConfirmedBlock>> {
+            debug!("get_block rpc request received: {:?}", slot);
+            meta.get_block(slot, config)
+        }
+
+        fn get_blocks(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Slot,
+            config: Option<RpcBlocksConfigWrapper>,
+            commitment: Option<CommitmentConfig>,
+        ) -> Result<Vec<Slot>> {
+            let (end_slot, maybe_commitment) =
+                config.map(|config| config.unzip()).unwrap_or_default();
+            debug!(
+                "get_blocks rpc request received: {}-{:?}",
+                start_slot, end_slot
+            );
+            meta.get_blocks(start_slot, end_slot, commitment.or(maybe_commitment))
+        }
+
+        fn get_transaction(
+            &self,
+            meta: Self::Metadata,
+            signature_str: String,
+            config: Option<RpcEncodingConfigWrapper<RpcTransactionConfig>>,
+        ) -> Result<Option<EncodedConfirmedTransactionWithStatusMeta>> {
+            debug!("get_transaction rpc request received: {:?}", signature_str);
+            let signature = verify_signature(&signature_str)?;
+            meta.get_transaction(signature, config)
+        }
+
+        fn get_signatures_for_address(
+            &self,
+            meta: Self::Metadata,
+            address: String,
+            config: Option<RpcSignaturesForAddressConfig>,
+        ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+            let RpcSignaturesForAddressConfig {
+                before,
+                until,
+                limit,
+                commitment,
+                min_context_slot,
+            } = config.unwrap_or_default();
+            let address = verify_pubkey(&address)?;
+            let before = before
+                .map(|ref before| verify_signature(before))
+                .transpose()?;
+            let until = until.map(|ref until| verify_signature(until)).transpose()?;
+            let limit = limit.unwrap_or(MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT);
+            if limit == 0 || limit > MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT {
+                return Err(Error::invalid_params(format!(
+                    "Invalid limit; max {MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT}"
+                )));
+            }
+            meta.get_signatures_for_address(
+                address,
+                before,
+                until,
+                limit,
+                commitment,
+                min_context_slot,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{rpc_history::*, *},
+        jsonrpc_core::{MetaIoHandler, Response, Value},
+        solana_entry::entry::{create_ticks, next_entry},
+        solana_ledger::{blockstore::entries_to_test_shreds, get_tmp_ledger_path_auto_delete},
+        solana_rpc_client_api::custom_error::JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+        solana_sdk::{
+            hash::Hash,
+            signature::{Keypair, Signer},
+            system_transaction,
+        },
+        solana_transaction_status::TransactionStatusMeta,
+    };
+
+    struct TestSetup {
+        io: MetaIoHandler<HistoryRpcRequestProcessor>,
+        meta: HistoryRpcRequestProcessor,
+        payer: Pubkey,
+        signature: Signature,
+        _ledger_path: tempfile::TempDir,
+    }
+
+    // Slots 1 and 2 are rooted and slot 3 isn't. Slot 2 holds a single transfer.
+    fn setup() -> TestSetup {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let payer = Keypair::new();
+        let transaction =
+            system_transaction::transfer(&payer, &Pubkey::new_unique(), 42, Hash::default());
+        let signature = transaction.signatures[0];
+
+        for (slot, parent_slot) in [(1, 0), (2, 1), (3, 2)] {
+            let mut entries = create_ticks(2, 0, Hash::default());
+            if slot == 2 {
+                entries.push(next_entry(&Hash::default(), 1, vec![transaction.clone()]));
+            }
+            let shreds = entries_to_test_shreds(&entries, slot, parent_slot, true, 0, true);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        blockstore.set_roots([1, 2].iter()).unwrap();
+        let keys = transaction.message.account_keys.iter().collect::<Vec<_>>();
+        blockstore
+            .write_transaction_status(
+                2,
+                signature,
+                keys,
+                vec![],
+                TransactionStatusMeta {
+                    fee: 5000,
+                    pre_balances: vec![100, 0, 1],
+                    post_balances: vec![53, 42, 1],
+                    ..TransactionStatusMeta::default()
+                },
+                0,
+            )
+            .unwrap();
+
+        let mut io = MetaIoHandler::default();
+        io.extend_with(HistoryImpl.to_delegate());
+        TestSetup {
+            io,
+            meta: HistoryRpcRequestProcessor::new(Arc::new(blockstore), 0),
+            payer: payer.pubkey(),
+            signature,
+            _ledger_path: ledger_path,
+        }
+    }
+
+    fn request(setup: &TestSetup, method: &str, params: Value) -> Value {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response = setup
+            .io
+            .handle_request_sync(&request.to_string(), setup.meta.clone())
+            .unwrap();
+        let response: Response = serde_json::from_str(&response).unwrap();
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[test]
+    fn test_get_blocks() {
+        let setup = setup();
+        // Slot 1 is the first root, whose parent blockhash is unknown
+        let response = request(&setup, "getBlocks", serde_json::json!([0]));
+        assert_eq!(response["result"], serde_json::json!([2]));
+        let response = request(&setup, "getBlocks", serde_json::json!([2, 3]));
+        assert_eq!(response["result"], serde_json::json!([2]));
+        let response = request(
+            &setup,
+            "getBlocks",
+            serde_json::json!([0, {"commitment": "processed"}]),
+        );
+        assert!(response["error"].is_object());
+    }
+
+    #[test]
+    fn test_get_block() {
+        let setup = setup();
+        let response = request(&setup, "getBlock", serde_json::json!([2]));
+        let block = &response["result"];
+        assert_eq!(block["parentSlot"], 1);
+        assert_eq!(block["transactions"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            block["transactions"][0]["transaction"]["signatures"][0],
+            setup.signature.to_string()
+        );
+
+        // Not rooted yet
+        let response = request(&setup, "getBlock", serde_json::json!([3]));
+        assert_eq!(
+            response["error"]["code"],
+            serde_json::json!(JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE)
+        );
+    }
+
+    #[test]
+    fn test_get_transaction() {
+        let setup = setup();
+        let response = request(
+            &setup,
+            "getTransaction",
+            serde_json::json!([setup.signature.to_string()]),
+        );
+        assert_eq!(response["result"]["slot"], 2);
+        assert_eq!(response["result"]["meta"]["fee"], 5000);
+
+        let response = request(
+            &setup,
+            "getTransaction",
+            serde_json::json!([Signature::default().to_string()]),
+        );
+        assert_eq!(response["result"], Value::Null);
+    }
+
+    #[test]
+    fn test_get_signatures_for_address() {
+        let setup = setup();
+        let response = request(
+            &setup,
+            "getSignaturesForAddress",
+            serde_json::json!([setup.payer.to_string()]),
+        );
+        let signatures = response["result"].as_array().unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0]["signature"], setup.signature.to_string());
+        assert_eq!(signatures[0]["slot"], 2);
+        assert_eq!(signatures[0]["confirmationStatus"], "finalized");
+
+        let response = request(
+            &setup,
+            "getSignaturesForAddress",
+            serde_json::json!([setup.payer.to_string(), {"minContextSlot": 3}]),
+        );
+        assert!(response["error"].is_object());
+    }
+}
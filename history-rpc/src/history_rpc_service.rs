@@ -0,0 +1,150 @@
+//! Serves the historical RPC subset over HTTP, while keeping a secondary blockstore caught up
+//! with the validator that owns it.
+
+use {
+    crate::history_rpc::{rpc_history::*, HistoryRpcRequestProcessor},
+    crossbeam_channel::unbounded,
+    jsonrpc_core::MetaIoHandler,
+    jsonrpc_http_server::{
+        hyper, AccessControlAllowOrigin, CloseHandle, DomainsValidation, ServerBuilder,
+    },
+    log::*,
+    solana_ledger::blockstore::Blockstore,
+    solana_sdk::clock::UnixTimestamp,
+    std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{self, sleep, Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+pub struct HistoryRpcConfig {
+    pub rpc_threads: usize,
+    pub max_request_body_size: usize,
+    /// How often the blockstore replays the writes of the primary
+    pub catch_up_interval: Duration,
+}
+
+pub struct HistoryRpcService {
+    server_hdl: JoinHandle<()>,
+    catch_up_hdl: JoinHandle<()>,
+    close_handle: CloseHandle,
+    exit: Arc<AtomicBool>,
+}
+
+impl HistoryRpcService {
+    pub fn new(
+        rpc_addr: SocketAddr,
+        config: HistoryRpcConfig,
+        blockstore: Arc<Blockstore>,
+        genesis_creation_time: UnixTimestamp,
+    ) -> Result<Self, String> {
+        info!("history rpc bound to {:?}", rpc_addr);
+        let exit = Arc::new(AtomicBool::new(false));
+        let catch_up_hdl = {
+            let blockstore = blockstore.clone();
+            let exit = exit.clone();
+            Builder::new()
+                .name("solHistCatchUp".to_string())
+                .spawn(move || catch_up_with_primary(&blockstore, config.catch_up_interval, &exit))
+                .unwrap()
+        };
+
+        let request_processor = HistoryRpcRequestProcessor::new(blockstore, genesis_creation_time);
+        // See JsonRpcService for why requests are processed on a shared multi-threaded runtime
+        // rather than by jsonrpc's own event loops
+        let runtime = Arc::new(
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(config.rpc_threads)
+                .thread_name("solHistRpcEl")
+                .enable_all()
+                .build()
+                .expect("Runtime"),
+        );
+        let max_request_body_size = config.max_request_body_size;
+        let (close_handle_sender, close_handle_receiver) = unbounded();
+        let server_hdl = Builder::new()
+            .name("solHistRpcSvc".to_string())
+            .spawn(move || {
+                let mut io = MetaIoHandler::default();
+                io.extend_with(HistoryImpl.to_delegate());
+
+                let server = ServerBuilder::with_meta_extractor(
+                    io,
+                    move |_req: &hyper::Request<hyper::Body>| request_processor.clone(),
+                )
+                .event_loop_executor(runtime.handle().clone())
+                .threads(1)
+                .cors(DomainsValidation::AllowOnly(vec![
+                    AccessControlAllowOrigin::Any,
+                ]))
+                .cors_max_age(86400)
+                .max_request_body_size(max_request_body_size)
+                .start_http(&rpc_addr);
+
+                match server {
+                    Ok(server) => {
+                        close_handle_sender.send(Ok(server.close_handle())).unwrap();
+                        server.wait();
+                    }
+                    Err(err) => {
+                        warn!(
+                            "History RPC service unavailable error: {:?}. \n\
+                               Also, check that port {} is not already in use by another application",
+                            err,
+                            rpc_addr.port()
+                        );
+                        close_handle_sender.send(Err(err.to_string())).unwrap();
+                    }
+                }
+            })
+            .unwrap();
+
+        let close_handle = match close_handle_receiver.recv().unwrap() {
+            Ok(close_handle) => close_handle,
+            Err(err) => {
+                exit.store(true, Ordering::Relaxed);
+                return Err(err);
+            }
+        };
+        Ok(Self {
+            server_hdl,
+            catch_up_hdl,
+            close_handle,
+            exit,
+        })
+    }
+
+    pub fn exit(&self) {
+        self.exit.store(true, Ordering::Relaxed);
+        self.close_handle.close();
+    }
+
+    /// Waits for the service to be stopped by [`HistoryRpcService::exit`]
+    pub fn join(self) -> thread::Result<()> {
+        self.server_hdl.join()?;
+        self.exit.store(true, Ordering::Relaxed);
+        self.catch_up_hdl.join()
+    }
+}
+
+fn catch_up_with_primary(blockstore: &Blockstore, interval: Duration, exit: &AtomicBool) {
+    let mut max_root = blockstore.max_root();
+    while !exit.load(Ordering::Relaxed) {
+        match blockstore.try_catch_up_with_primary() {
+            Ok(()) => {
+                let new_max_root = blockstore.max_root();
+                if new_max_root != max_root {
+                    debug!("caught up with primary, max root: {}", new_max_root);
+                    max_root = new_max_root;
+                }
+            }
+            Err(err) => warn!("Failed to catch up with primary blockstore: {:?}", err),
+        }
+        sleep(interval);
+    }
+}
@@ -0,0 +1,3 @@
+#![allow(clippy::arithmetic_side_effects)]
+pub mod history_rpc;
+pub mod history_rpc_service;
@@ -0,0 +1,128 @@
+//! Serves historical RPC requests from a validator's ledger, so that they don't compete with
+//! the validator's own RPC service
+use {
+    clap::{crate_description, crate_name, value_t_or_exit, App, Arg},
+    log::*,
+    solana_clap_utils::input_validators::is_parsable,
+    solana_history_rpc::history_rpc_service::{HistoryRpcConfig, HistoryRpcService},
+    solana_ledger::{
+        blockstore::Blockstore,
+        blockstore_options::{
+            AccessType, BlockstoreOptions, LedgerColumnOptions, ShredStorageType,
+        },
+    },
+    solana_sdk::genesis_config::GenesisConfig,
+    std::{net::SocketAddr, path::PathBuf, process::exit, sync::Arc, time::Duration},
+};
+
+// Matches the validator's default --rpc-max-request-body-size
+const MAX_REQUEST_BODY_SIZE: usize = 50 * (1 << 10);
+
+fn main() {
+    solana_logger::setup_with_default("solana=info");
+
+    let default_rpc_threads = num_cpus::get().to_string();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(solana_version::version!())
+        .arg(
+            Arg::with_name("ledger_path")
+                .short("l")
+                .long("ledger")
+                .value_name("DIR")
+                .takes_value(true)
+                .required(true)
+                .help("Ledger directory of the validator to serve"),
+        )
+        .arg(
+            Arg::with_name("rpc_bind_address")
+                .long("rpc-bind-address")
+                .value_name("HOST")
+                .takes_value(true)
+                .validator(solana_net_utils::is_host)
+                .default_value("127.0.0.1")
+                .help("IP address to bind the RPC port"),
+        )
+        .arg(
+            Arg::with_name("rpc_port")
+                .long("rpc-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .validator(is_parsable::<u16>)
+                .default_value("8899")
+                .help("Port for the RPC service"),
+        )
+        .arg(
+            Arg::with_name("rpc_threads")
+                .long("rpc-threads")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_rpc_threads)
+                .help("Number of threads to use for servicing RPC requests"),
+        )
+        .arg(
+            Arg::with_name("catch_up_interval")
+                .long("catch-up-interval")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value("1000")
+                .help("How often to read the validator's latest writes to the ledger"),
+        )
+        .get_matches();
+
+    let ledger_path = PathBuf::from(value_t_or_exit!(matches, "ledger_path", String));
+    let rpc_bind_address =
+        solana_net_utils::parse_host(matches.value_of("rpc_bind_address").unwrap())
+            .expect("invalid rpc_bind_address");
+    let rpc_port = value_t_or_exit!(matches, "rpc_port", u16);
+    let config = HistoryRpcConfig {
+        rpc_threads: value_t_or_exit!(matches, "rpc_threads", usize),
+        max_request_body_size: MAX_REQUEST_BODY_SIZE,
+        catch_up_interval: Duration::from_millis(value_t_or_exit!(
+            matches,
+            "catch_up_interval",
+            u64
+        )),
+    };
+
+    let genesis_config = GenesisConfig::load(&ledger_path).unwrap_or_else(|err| {
+        eprintln!("Failed to load genesis config from {ledger_path:?}: {err}");
+        exit(1);
+    });
+    let shred_storage_type = ShredStorageType::from_ledger_path(&ledger_path, None)
+        .unwrap_or(ShredStorageType::RocksLevel);
+    let blockstore = Blockstore::open_with_options(
+        &ledger_path,
+        BlockstoreOptions {
+            access_type: AccessType::Secondary,
+            column_options: LedgerColumnOptions {
+                shred_storage_type,
+                ..LedgerColumnOptions::default()
+            },
+            ..BlockstoreOptions::default()
+        },
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to open blockstore at {ledger_path:?}: {err:?}");
+        exit(1);
+    });
+    info!(
+        "Serving {:?} from max root {}",
+        ledger_path,
+        blockstore.max_root()
+    );
+
+    let service = HistoryRpcService::new(
+        SocketAddr::new(rpc_bind_address, rpc_port),
+        config,
+        Arc::new(blockstore),
+        genesis_config.creation_time,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to start history RPC service: {err}");
+        exit(1);
+    });
+    service.join().unwrap();
+}
@@ -1,6 +1,6 @@
 use {
     crate::{
-        config::{Config, ExplicitRelease},
+        config::{Config, ExplicitRelease, PreviousRelease},
         stop_process::stop_process,
         update_manifest::{SignedUpdateManifest, UpdateManifest},
     },
@@ -15,13 +15,14 @@ use {
         hash::{Hash, Hasher},
         message::Message,
         pubkey::Pubkey,
-        signature::{read_keypair_file, Keypair, Signable, Signer},
+        signature::{read_keypair_file, Keypair, Signable, Signature, Signer},
         transaction::Transaction,
     },
     std::{
         fs::{self, File},
         io::{self, BufReader, Read},
         path::{Path, PathBuf},
+        str::FromStr,
         time::{Duration, Instant, SystemTime},
     },
     tempfile::TempDir,
@@ -35,6 +36,14 @@ pub struct ReleaseVersion {
     channel: String,
 }
 
+impl ReleaseVersion {
+    /// Returns the version of a tagged release. Releases built from a branch, like the edge
+    /// and beta channels, have no version.
+    fn semver(&self) -> Option<semver::Version> {
+        semver_of(&self.channel).ok()
+    }
+}
+
 static TRUCK: Emoji = Emoji("🚚 ", "");
 static LOOKING_GLASS: Emoji = Emoji("🔍 ", "");
 static BULLET: Emoji = Emoji("• ", "* ");
@@ -187,6 +196,127 @@ fn extract_release_archive(
     Ok(())
 }
 
+/// Name of the release archive, and of its detached signature, kept in the release directory
+/// so that the release can be verified again when it is installed from the cache
+const CACHED_RELEASE_ARCHIVE: &str = "solana-release.tar.bz2";
+const CACHED_RELEASE_SIGNATURE: &str = "solana-release.tar.bz2.sig";
+
+/// Extracts the release `archive` into `release_dir`. If the archive was verified against
+/// `signature`, both are kept in the release directory.
+fn install_release_archive(
+    archive: &Path,
+    signature: Option<&Signature>,
+    release_dir: &Path,
+) -> Result<(), String> {
+    extract_release_archive(archive, release_dir)
+        .map_err(|err| format!("Unable to extract {archive:?} to {release_dir:?}: {err}"))?;
+    if let Some(signature) = signature {
+        let cached_archive = release_dir.join(CACHED_RELEASE_ARCHIVE);
+        fs::copy(archive, &cached_archive)
+            .map_err(|err| format!("Unable to copy {archive:?} to {cached_archive:?}: {err}"))?;
+        let cached_signature = release_dir.join(CACHED_RELEASE_SIGNATURE);
+        fs::write(&cached_signature, signature.to_string())
+            .map_err(|err| format!("Unable to write {cached_signature:?}: {err}"))?;
+    }
+    Ok(())
+}
+
+/// Whether the release in `release_dir` can be installed from the cache. When releases must
+/// be signed, only releases cached along with their signed archive can.
+fn is_release_cached(config: &Config, release_dir: &Path) -> bool {
+    release_dir.exists()
+        && (config.release_signers.is_empty()
+            || release_dir.join(CACHED_RELEASE_SIGNATURE).exists())
+}
+
+/// Verifies the signature of the cached release in `release_dir` again, and extracts its
+/// archive anew so that the installed files are the ones that were signed
+fn verify_cached_release(release_dir: &Path, release_signers: &[Pubkey]) -> Result<(), String> {
+    let temp_dir = TempDir::new().map_err(|err| format!("Unable to create temp dir: {err}"))?;
+    let archive = temp_dir.path().join(CACHED_RELEASE_ARCHIVE);
+    let cached_archive = release_dir.join(CACHED_RELEASE_ARCHIVE);
+    fs::copy(&cached_archive, &archive)
+        .map_err(|err| format!("Unable to copy {cached_archive:?}: {err}"))?;
+    let cached_signature = release_dir.join(CACHED_RELEASE_SIGNATURE);
+    let signature = fs::read_to_string(&cached_signature)
+        .map_err(|err| format!("Unable to read {cached_signature:?}: {err}"))?;
+    let signature = Signature::from_str(signature.trim())
+        .map_err(|err| format!("Invalid release signature {cached_signature:?}: {err}"))?;
+
+    match verify_archive_signature(&archive, &signature, release_signers)? {
+        Some(signer) => {
+            println_name_value(&format!("{BULLET}release signed by:"), &signer.to_string())
+        }
+        None => {
+            return Err(format!(
+                "Cached release {release_dir:?} was not signed by a trusted release signer"
+            ))
+        }
+    }
+    install_release_archive(&archive, Some(&signature), release_dir)
+}
+
+/// URL of the detached signature of the release archive at `download_url`
+fn release_signature_url(download_url: &str) -> String {
+    format!("{download_url}.sig")
+}
+
+/// Returns the signer among `release_signers` that produced `signature` over the contents of
+/// `archive`, if any
+fn verify_archive_signature(
+    archive: &Path,
+    signature: &Signature,
+    release_signers: &[Pubkey],
+) -> Result<Option<Pubkey>, String> {
+    let archive_data =
+        fs::read(archive).map_err(|err| format!("Unable to read {archive:?}: {err}"))?;
+    Ok(release_signers
+        .iter()
+        .find(|signer| signature.verify(signer.as_ref(), &archive_data))
+        .copied())
+}
+
+/// Downloads the detached signature of the release archive at `download_url` and checks that
+/// it was produced over `archive` by one of `release_signers`
+fn verify_release_signature(
+    download_url: &str,
+    archive: &Path,
+    release_signers: &[Pubkey],
+) -> Result<Signature, String> {
+    let signature_url = release_signature_url(download_url);
+    let signature = reqwest::blocking::get(&signature_url)
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|err| format!("Unable to download release signature {signature_url}: {err}"))?;
+    let signature = Signature::from_str(signature.trim())
+        .map_err(|err| format!("Invalid release signature {signature_url}: {err}"))?;
+
+    match verify_archive_signature(archive, &signature, release_signers)? {
+        Some(signer) => {
+            println_name_value(&format!("{BULLET}release signed by:"), &signer.to_string());
+            Ok(signature)
+        }
+        None => Err(format!(
+            "Release signature {signature_url} was not produced by a trusted release signer"
+        )),
+    }
+}
+
+/// Returns an error if installing `new_version` would downgrade the active release, unless
+/// `force` is set
+fn check_downgrade(
+    active_version: Option<&semver::Version>,
+    new_version: &semver::Version,
+    force: bool,
+) -> Result<(), String> {
+    match active_version {
+        Some(active_version) if new_version < active_version && !force => Err(format!(
+            "Refusing to downgrade from {active_version} to {new_version}. Use --force to override"
+        )),
+        _ => Ok(()),
+    }
+}
+
 fn load_release_version(version_yml: &Path) -> Result<ReleaseVersion, String> {
     let file = File::open(version_yml)
         .map_err(|err| format!("Unable to open {version_yml:?}: {err:?}"))?;
@@ -539,25 +669,33 @@ pub fn init(
     update_manifest_pubkey: &Pubkey,
     no_modify_path: bool,
     explicit_release: Option<ExplicitRelease>,
+    release_signers: Option<Vec<Pubkey>>,
+    force: bool,
 ) -> Result<(), String> {
     let config = {
         // Write new config file only if different, so that running |solana-install init|
         // repeatedly doesn't unnecessarily re-download
         let mut current_config = Config::load(config_file).unwrap_or_default();
         current_config.current_update_manifest = None;
-        let config = Config::new(
+        let mut config = Config::new(
             data_dir,
             json_rpc_url,
             update_manifest_pubkey,
             explicit_release,
         );
+        // Re-initializing must not silently disable the verification of the releases
+        config.release_signers =
+            release_signers.unwrap_or_else(|| current_config.release_signers.clone());
+        // Keep the rollback history across re-initializations
+        config.max_previous_releases = current_config.max_previous_releases;
+        config.previous_releases = current_config.previous_releases.clone();
         if current_config != config {
             config.save(config_file)?;
         }
         config
     };
 
-    init_or_update(config_file, true, false)?;
+    init_or_update(config_file, true, false, force)?;
 
     let path_modified = if !no_modify_path {
         add_to_path(config.active_release_bin_dir().to_str().unwrap())
@@ -684,7 +822,7 @@ pub fn info(config_file: &str, local_info_only: bool, eval: bool) -> Result<(),
     if local_info_only {
         Ok(())
     } else {
-        update(config_file, true).map(|_| ())
+        update(config_file, true, false).map(|_| ())
     }
 }
 
@@ -778,6 +916,27 @@ pub fn deploy(
     Ok(())
 }
 
+pub fn sign_release(archive: &str, keypair_file: &str) -> Result<(), String> {
+    let keypair = read_keypair_file(keypair_file)
+        .map_err(|err| format!("Unable to read {keypair_file}: {err}"))?;
+    let archive_data =
+        fs::read(archive).map_err(|err| format!("Unable to read {archive}: {err}"))?;
+    let signature = keypair.sign_message(&archive_data);
+
+    let signature_file = release_signature_url(archive);
+    fs::write(&signature_file, signature.to_string())
+        .map_err(|err| format!("Unable to write {signature_file}: {err}"))?;
+
+    println_name_value("Release signer:", &keypair.pubkey().to_string());
+    println_name_value("Signature file:", &signature_file);
+    println!(
+        "  {}{}",
+        INFORMATION,
+        style("Upload the signature file next to the release archive").bold()
+    );
+    Ok(())
+}
+
 #[cfg(windows)]
 fn symlink_dir<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> std::io::Result<()> {
     std::os::windows::fs::symlink_dir(src, dst)
@@ -817,6 +976,14 @@ pub fn gc(config_file: &str) -> Result<(), String> {
         .collect::<Vec<_>>();
     releases.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap()); // order by newest releases
 
+    // Never remove the releases that can be rolled back to
+    releases.retain(|(release_path, _)| {
+        !config
+            .previous_releases
+            .iter()
+            .any(|previous_release| previous_release.release_dir == *release_path)
+    });
+
     const MAX_CACHE_LEN: usize = 5;
     if releases.len() > MAX_CACHE_LEN {
         let old_releases = releases.split_off(MAX_CACHE_LEN);
@@ -961,12 +1128,27 @@ pub enum SemverUpdateType {
     _Minor,
 }
 
-pub fn update(config_file: &str, check_only: bool) -> Result<bool, String> {
-    init_or_update(config_file, false, check_only)
+pub fn update(config_file: &str, check_only: bool, force: bool) -> Result<bool, String> {
+    init_or_update(config_file, false, check_only, force)
 }
 
-pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Result<bool, String> {
+pub fn init_or_update(
+    config_file: &str,
+    is_init: bool,
+    check_only: bool,
+    force: bool,
+) -> Result<bool, String> {
     let mut config = Config::load(config_file)?;
+    let active_release_version =
+        load_release_version(&config.active_release_dir().join("version.yml")).ok();
+    let previous_release = fs::read_link(config.active_release_dir())
+        .ok()
+        .and_then(|active_release| active_release.parent().map(Path::to_path_buf))
+        .map(|release_dir| PreviousRelease {
+            release_dir,
+            explicit_release: config.explicit_release.clone(),
+            update_manifest: config.current_update_manifest.clone(),
+        });
 
     let semver_update_type = if is_init {
         SemverUpdateType::Fixed
@@ -996,9 +1178,7 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
                     }
                     Some(release_semver) => {
                         if release_semver == *current_release_semver {
-                            if let Ok(active_release_version) = load_release_version(
-                                &config.active_release_dir().join("version.yml"),
-                            ) {
+                            if let Some(active_release_version) = &active_release_version {
                                 if format!("v{current_release_semver}")
                                     == active_release_version.channel
                                 {
@@ -1009,11 +1189,19 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
                                 }
                             }
                         }
+                        check_downgrade(
+                            active_release_version
+                                .as_ref()
+                                .and_then(ReleaseVersion::semver)
+                                .as_ref(),
+                            &semver_of(&release_semver)?,
+                            force,
+                        )?;
                         config.explicit_release =
                             Some(ExplicitRelease::Semver(release_semver.clone()));
 
                         let release_dir = config.release_dir(&release_semver);
-                        let download_url_and_sha256 = if release_dir.exists() {
+                        let download_url_and_sha256 = if is_release_cached(&config, &release_dir) {
                             // Release already present in the cache
                             None
                         } else {
@@ -1031,6 +1219,17 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
                         .map_err(|err| format!("Unable to download {version_url}: {err}"))?;
 
                 let update_release_version = load_release_version(&temp_file)?;
+                // The channel is resolved to the release it currently points to
+                if let Some(update_release_semver) = update_release_version.semver() {
+                    check_downgrade(
+                        active_release_version
+                            .as_ref()
+                            .and_then(ReleaseVersion::semver)
+                            .as_ref(),
+                        &update_release_semver,
+                        force,
+                    )?;
+                }
 
                 let release_id = format!("{}-{}", release_channel, update_release_version.commit);
                 let release_dir = config.release_dir(&release_id);
@@ -1039,7 +1238,9 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
 
                 let download_url = release_channel_download_url(release_channel);
 
-                if !current_release_version_yml.exists() {
+                if !current_release_version_yml.exists()
+                    || !is_release_cached(&config, &release_dir)
+                {
                     (
                         format!(
                             "{} commit {}",
@@ -1114,8 +1315,10 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
         }
 
         if let Some(ref current_update_manifest) = config.current_update_manifest {
-            if update_manifest.timestamp_secs < current_update_manifest.timestamp_secs {
-                return Err("Unable to update to an older version".to_string());
+            if update_manifest.timestamp_secs < current_update_manifest.timestamp_secs && !force {
+                return Err(
+                    "Unable to update to an older version. Use --force to override".to_string(),
+                );
             }
         }
         config.current_update_manifest = Some(update_manifest.clone());
@@ -1144,9 +1347,18 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
         let (_temp_dir, temp_archive, _temp_archive_sha256) =
             download_to_temp(&download_url, archive_sha256.as_ref())
                 .map_err(|err| format!("Unable to download {download_url}: {err}"))?;
-        extract_release_archive(&temp_archive, &release_dir).map_err(|err| {
-            format!("Unable to extract {temp_archive:?} to {release_dir:?}: {err}")
-        })?;
+        let signature = if config.release_signers.is_empty() {
+            None
+        } else {
+            Some(verify_release_signature(
+                &download_url,
+                &temp_archive,
+                &config.release_signers,
+            )?)
+        };
+        install_release_archive(&temp_archive, signature.as_ref(), &release_dir)?;
+    } else if !config.release_signers.is_empty() {
+        verify_cached_release(&release_dir, &config.release_signers)?;
     }
 
     let release_target = load_release_target(&release_dir)
@@ -1163,6 +1375,34 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
         let _ = fs::remove_file(path);
     }
 
+    activate_release(&config, &release_dir)?;
+    if let Some(previous_release) = previous_release {
+        if previous_release.release_dir != release_dir {
+            config.push_previous_release(previous_release);
+        }
+    }
+
+    config.save(config_file)?;
+    gc(config_file)?;
+
+    if is_init {
+        println!(
+            "  {}{}",
+            SPARKLE,
+            style(format!("{updated_version} initialized")).bold()
+        );
+    } else {
+        println!(
+            "  {}{}",
+            SPARKLE,
+            style(format!("Update successful to {updated_version}")).bold()
+        );
+    }
+    Ok(true)
+}
+
+/// Points the active release at the release in `release_dir`
+fn activate_release(config: &Config, release_dir: &Path) -> Result<(), String> {
     let _ = fs::remove_dir_all(config.active_release_dir());
     symlink_dir(
         release_dir.join("solana-release"),
@@ -1175,25 +1415,49 @@ pub fn init_or_update(config_file: &str, is_init: bool, check_only: bool) -> Res
             config.active_release_dir(),
             err
         )
+    })
+}
+
+pub fn rollback(config_file: &str) -> Result<(), String> {
+    let mut config = Config::load(config_file)?;
+
+    let previous_release = config
+        .previous_releases
+        .pop()
+        .ok_or_else(|| "No previous release to roll back to".to_string())?;
+    load_release_target(&previous_release.release_dir).map_err(|err| {
+        format!(
+            "Unable to load previous release from {:?}: {}",
+            previous_release.release_dir, err
+        )
     })?;
+    if !config.release_signers.is_empty() {
+        verify_cached_release(&previous_release.release_dir, &config.release_signers)?;
+    }
 
+    activate_release(&config, &previous_release.release_dir)?;
+    config.explicit_release = previous_release.explicit_release;
+    config.current_update_manifest = previous_release.update_manifest;
     config.save(config_file)?;
-    gc(config_file)?;
 
-    if is_init {
-        println!(
-            "  {}{}",
-            SPARKLE,
-            style(format!("{updated_version} initialized")).bold()
-        );
-    } else {
+    let release_id = previous_release
+        .release_dir
+        .file_name()
+        .map(|release_id| release_id.to_string_lossy().to_string())
+        .unwrap_or_default();
+    println!(
+        "  {}{}",
+        SPARKLE,
+        style(format!("Rolled back to {release_id}")).bold()
+    );
+    if config.explicit_release.is_none() {
         println!(
             "  {}{}",
-            SPARKLE,
-            style(format!("Update successful to {updated_version}")).bold()
+            INFORMATION,
+            style("`solana-install run` will apply the latest update manifest again").bold()
         );
     }
-    Ok(true)
+    Ok(())
 }
 
 pub fn run(
@@ -1255,7 +1519,7 @@ pub fn run(
         };
 
         if config.explicit_release.is_none() && now.elapsed().as_secs() > config.update_poll_secs {
-            match update(config_file, false) {
+            match update(config_file, false, false) {
                 Ok(true) => {
                     // Update successful, kill current process so it will be restart
                     if let Some(ref mut child) = child_option {
@@ -1315,3 +1579,93 @@ pub fn list(config_file: &str) -> Result<(), String> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tempfile::NamedTempFile};
+
+    #[test]
+    fn test_verify_archive_signature() {
+        let mut archive = NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut archive, b"solana-release").unwrap();
+
+        let signer = Keypair::new();
+        let other_signer = Keypair::new();
+        let signature = signer.sign_message(b"solana-release");
+
+        assert_eq!(
+            verify_archive_signature(
+                archive.path(),
+                &signature,
+                &[other_signer.pubkey(), signer.pubkey()]
+            ),
+            Ok(Some(signer.pubkey()))
+        );
+        assert_eq!(
+            verify_archive_signature(archive.path(), &signature, &[other_signer.pubkey()]),
+            Ok(None)
+        );
+        let bad_signature = signer.sign_message(b"solana-release-2");
+        assert_eq!(
+            verify_archive_signature(archive.path(), &bad_signature, &[signer.pubkey()]),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_verify_cached_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = temp_dir.path().join("download");
+        {
+            let encoder =
+                bzip2::write::BzEncoder::new(File::create(&archive).unwrap(), Default::default());
+            let mut builder = tar::Builder::new(encoder);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(7);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "solana-release/version.yml", &b"signed\n"[..])
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let signer = Keypair::new();
+        let signature = signer.sign_message(&fs::read(&archive).unwrap());
+        let release_dir = temp_dir.path().join("releases").join("1.18.0");
+        fs::create_dir_all(release_dir.parent().unwrap()).unwrap();
+        install_release_archive(&archive, Some(&signature), &release_dir).unwrap();
+
+        // Files modified in the cache are restored from the signed archive
+        let version_yml = release_dir.join("solana-release").join("version.yml");
+        fs::write(&version_yml, "tampered\n").unwrap();
+        verify_cached_release(&release_dir, &[signer.pubkey()]).unwrap();
+        assert_eq!(fs::read_to_string(&version_yml).unwrap(), "signed\n");
+        assert!(verify_cached_release(&release_dir, &[Keypair::new().pubkey()]).is_err());
+
+        // A modified cached archive fails the verification
+        let cached_archive = release_dir.join(CACHED_RELEASE_ARCHIVE);
+        let mut archive_data = fs::read(&cached_archive).unwrap();
+        archive_data.push(0);
+        fs::write(&cached_archive, archive_data).unwrap();
+        assert!(verify_cached_release(&release_dir, &[signer.pubkey()]).is_err());
+    }
+
+    #[test]
+    fn test_check_downgrade() {
+        let v1 = semver::Version::parse("1.16.0").unwrap();
+        let v2 = semver::Version::parse("1.17.0").unwrap();
+        assert!(check_downgrade(None, &v1, false).is_ok());
+        assert!(check_downgrade(Some(&v1), &v2, false).is_ok());
+        assert!(check_downgrade(Some(&v2), &v2, false).is_ok());
+        assert!(check_downgrade(Some(&v2), &v1, false).is_err());
+        assert!(check_downgrade(Some(&v2), &v1, true).is_ok());
+
+        let release_version = |channel: &str| ReleaseVersion {
+            target: String::default(),
+            commit: String::default(),
+            channel: channel.to_string(),
+        };
+        assert_eq!(release_version("v1.17.0").semver(), Some(v2));
+        assert_eq!(release_version("edge").semver(), None);
+    }
+}
@@ -9,7 +9,7 @@ use {
     },
 };
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum ExplicitRelease {
     Semver(String),
     Channel(String),
@@ -24,6 +24,29 @@ pub struct Config {
     pub explicit_release: Option<ExplicitRelease>,
     pub releases_dir: PathBuf,
     active_release_dir: PathBuf,
+    /// If not empty, release archives must carry a detached signature by one of these keys
+    #[serde(default)]
+    pub release_signers: Vec<Pubkey>,
+    /// Number of previously active releases to keep around for `solana-install rollback`
+    #[serde(default = "default_max_previous_releases")]
+    pub max_previous_releases: usize,
+    /// Previously active releases, most recent last
+    #[serde(default)]
+    pub previous_releases: Vec<PreviousRelease>,
+}
+
+/// A previously active release that can be rolled back to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PreviousRelease {
+    pub release_dir: PathBuf,
+    pub explicit_release: Option<ExplicitRelease>,
+    pub update_manifest: Option<UpdateManifest>,
+}
+
+pub const DEFAULT_MAX_PREVIOUS_RELEASES: usize = 3;
+
+fn default_max_previous_releases() -> usize {
+    DEFAULT_MAX_PREVIOUS_RELEASES
 }
 
 const LEGACY_FMT_LOAD_ERR: &str =
@@ -44,6 +67,9 @@ impl Config {
             explicit_release,
             releases_dir: PathBuf::from(data_dir).join("releases"),
             active_release_dir: PathBuf::from(data_dir).join("active_release"),
+            release_signers: vec![],
+            max_previous_releases: DEFAULT_MAX_PREVIOUS_RELEASES,
+            previous_releases: vec![],
         }
     }
 
@@ -126,6 +152,19 @@ impl Config {
     pub fn release_dir(&self, release_id: &str) -> PathBuf {
         self.releases_dir.join(release_id)
     }
+
+    /// Records the release in `release_dir` as the most recent previous release, forgetting
+    /// the oldest ones beyond `max_previous_releases`
+    pub fn push_previous_release(&mut self, previous_release: PreviousRelease) {
+        self.previous_releases
+            .retain(|release| release.release_dir != previous_release.release_dir);
+        self.previous_releases.push(previous_release);
+        let excess = self
+            .previous_releases
+            .len()
+            .saturating_sub(self.max_previous_releases);
+        self.previous_releases.drain(..excess);
+    }
 }
 
 #[cfg(test)]
@@ -197,6 +236,9 @@ update_poll_secs: 3600
 explicit_release: null
 releases_dir: {root_dir}/releases
 active_release_dir: {root_dir}/active_release
+release_signers: []
+max_previous_releases: 3
+previous_releases: []
 "
             ),
         );
@@ -269,7 +311,38 @@ active_release_dir: {root_dir}/active_release
                 explicit_release: Some(ExplicitRelease::Semver(String::from("1.13.6"))),
                 releases_dir: PathBuf::from(format!("{root_dir}/releases")),
                 active_release_dir: PathBuf::from(format!("{root_dir}/active_release")),
+                release_signers: vec![],
+                max_previous_releases: DEFAULT_MAX_PREVIOUS_RELEASES,
+                previous_releases: vec![],
             },
         );
     }
+
+    #[test]
+    fn test_push_previous_release() {
+        let mut config = Config::new("/tmp", "http://localhost:8899", &Pubkey::default(), None);
+        config.max_previous_releases = 2;
+        let previous_release = |id: &str| PreviousRelease {
+            release_dir: config.release_dir(id),
+            explicit_release: Some(ExplicitRelease::Semver(id.to_string())),
+            update_manifest: None,
+        };
+        let (v1, v2, v3) = (
+            previous_release("1.0.0"),
+            previous_release("1.0.1"),
+            previous_release("1.0.2"),
+        );
+
+        config.push_previous_release(v1.clone());
+        config.push_previous_release(v2.clone());
+        assert_eq!(config.previous_releases, vec![v1.clone(), v2.clone()]);
+
+        // The oldest release is forgotten
+        config.push_previous_release(v3.clone());
+        assert_eq!(config.previous_releases, vec![v2.clone(), v3.clone()]);
+
+        // Releases are only recorded once
+        config.push_previous_release(v2.clone());
+        assert_eq!(config.previous_releases, vec![v3, v2]);
+    }
 }
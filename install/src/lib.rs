@@ -5,7 +5,7 @@ extern crate lazy_static;
 use {
     clap::{crate_description, crate_name, App, AppSettings, Arg, ArgMatches, SubCommand},
     solana_clap_utils::{
-        input_parsers::pubkey_of,
+        input_parsers::{pubkey_of, pubkeys_of},
         input_validators::{is_pubkey, is_url},
     },
 };
@@ -64,6 +64,8 @@ fn handle_init(matches: &ArgMatches<'_>, config_file: &str) -> Result<(), String
     let data_dir = matches.value_of("data_dir").unwrap();
     let no_modify_path = matches.is_present("no_modify_path");
     let explicit_release = explicit_release_of(matches, "explicit_release");
+    let release_signers = pubkeys_of(matches, "release_signer");
+    let force = matches.is_present("force");
 
     if update_manifest_pubkey.is_none() && explicit_release.is_none() {
         Err(format!(
@@ -78,10 +80,33 @@ fn handle_init(matches: &ArgMatches<'_>, config_file: &str) -> Result<(), String
             &update_manifest_pubkey.unwrap_or_default(),
             no_modify_path,
             explicit_release,
+            release_signers,
+            force,
         )
     }
 }
 
+fn release_signer_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("release_signer")
+        .long("release-signer")
+        .value_name("PUBKEY")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .validator(is_pubkey)
+        .help(
+            "Only install release archives with a detached signature by this key. \
+            May be specified multiple times. Replaces the release signers of a previous \
+            init, which are kept if no release signer is given",
+        )
+}
+
+fn force_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("force")
+        .long("force")
+        .help("Allow installing a release older than the active release")
+}
+
 pub fn main() -> Result<(), String> {
     solana_logger::setup();
 
@@ -150,7 +175,9 @@ pub fn main() -> Result<(), String> {
                         .conflicts_with_all(&["json_rpc_url", "update_manifest_pubkey"])
                         .validator(is_explicit_release)
                         .help("The release version or channel to install"),
-                ),
+                )
+                .arg(release_signer_arg())
+                .arg(force_arg()),
         )
         .subcommand(
             SubCommand::with_name("info")
@@ -217,8 +244,38 @@ pub fn main() -> Result<(), String> {
         .subcommand(
             SubCommand::with_name("update")
                 .about("Checks for an update, and if available downloads and applies it")
+                .setting(AppSettings::DisableVersion)
+                .arg(force_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("rollback")
+                .about("Switches back to the previously active release")
                 .setting(AppSettings::DisableVersion),
         )
+        .subcommand(
+            SubCommand::with_name("sign-release")
+                .about("Creates the detached signature of a release archive")
+                .setting(AppSettings::DisableVersion)
+                .arg({
+                    let arg = Arg::with_name("keypair_file")
+                        .short("k")
+                        .long("keypair")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Keypair file of the release signer");
+                    match *defaults::USER_KEYPAIR {
+                        Some(ref config_file) => arg.default_value(config_file),
+                        None => arg,
+                    }
+                })
+                .arg(
+                    Arg::with_name("archive")
+                        .index(1)
+                        .required(true)
+                        .help("Release archive to sign. The signature is written to <ARCHIVE>.sig"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("run")
                 .about("Runs a program while periodically checking and applying software updates")
@@ -263,7 +320,15 @@ pub fn main() -> Result<(), String> {
             )
         }
         ("gc", Some(_matches)) => command::gc(config_file),
-        ("update", Some(_matches)) => command::update(config_file, false).map(|_| ()),
+        ("update", Some(matches)) => {
+            command::update(config_file, false, matches.is_present("force")).map(|_| ())
+        }
+        ("rollback", Some(_matches)) => command::rollback(config_file),
+        ("sign-release", Some(matches)) => {
+            let archive = matches.value_of("archive").unwrap();
+            let keypair_file = matches.value_of("keypair_file").unwrap();
+            command::sign_release(archive, keypair_file)
+        }
         ("run", Some(matches)) => {
             let program_name = matches.value_of("program_name").unwrap();
             let program_arguments = matches
@@ -341,6 +406,8 @@ pub fn main_init() -> Result<(), String> {
                 .validator(is_explicit_release)
                 .help("The release version or channel to install"),
         )
+        .arg(release_signer_arg())
+        .arg(force_arg())
         .get_matches();
 
     let config_file = matches.value_of("config_file").unwrap();
@@ -6,7 +6,7 @@ use {
         crate_description, crate_name, value_t, value_t_or_exit, values_t_or_exit, App,
         AppSettings, Arg, ArgMatches, SubCommand,
     },
-    dashmap::DashMap,
+    dashmap::{DashMap, DashSet},
     itertools::Itertools,
     log::*,
     regex::Regex,
@@ -45,6 +45,7 @@ use {
             BLOCKSTORE_DIRECTORY_ROCKS_FIFO,
         },
         blockstore_processor::ProcessOptions,
+        leader_schedule_cache::LeaderScheduleCache,
         shred::Shred,
         use_snapshot_archives_at_startup::{self, UseSnapshotArchivesAtStartup},
     },
@@ -74,6 +75,7 @@ use {
         pubkey::Pubkey,
         rent::Rent,
         shred_version::compute_shred_version,
+        signature::Signature,
         stake::{self, state::StakeStateV2},
         system_program,
         transaction::{
@@ -1021,24 +1023,87 @@ fn get_latest_optimistic_slots(
     }
 }
 
-/// Finds the accounts needed to replay slots `snapshot_slot` to `ending_slot`.
-/// Removes all other accounts from accounts_db, and updates the accounts hash
-/// and capitalization. This is used by the --minimize option in create-snapshot
+/// Transactions that a minimized snapshot must be able to replay
+enum MinimizedTransactions {
+    /// Every transaction in the slot range, along with the rest of those slots
+    All,
+    /// Only the transactions with these signatures
+    Signatures(HashSet<Signature>),
+    /// Only the transactions with a top-level instruction invoking one of these programs
+    Programs(HashSet<Pubkey>),
+}
+
+/// Finds the accounts needed to replay `minimized_transactions` from slots
+/// `snapshot_slot` to `ending_slot`. Removes all other accounts from
+/// accounts_db, and updates the accounts hash and capitalization. This is used
+/// by the --minimize option in create-snapshot
 /// Returns true if the minimized snapshot may be incomplete.
 fn minimize_bank_for_snapshot(
     blockstore: &Blockstore,
     bank: &Bank,
     snapshot_slot: Slot,
     ending_slot: Slot,
+    minimized_transactions: &MinimizedTransactions,
 ) -> bool {
+    let found_signatures = DashSet::new();
+    let transaction_filter = |transaction: &VersionedTransaction| match minimized_transactions {
+        MinimizedTransactions::All => true,
+        MinimizedTransactions::Signatures(signatures) => {
+            let found = transaction
+                .signatures
+                .first()
+                .filter(|signature| signatures.contains(signature));
+            if let Some(signature) = found {
+                found_signatures.insert(*signature);
+            }
+            found.is_some()
+        }
+        MinimizedTransactions::Programs(program_ids) => {
+            let account_keys = transaction.message.static_account_keys();
+            transaction
+                .message
+                .instructions()
+                .iter()
+                .filter_map(|instruction| {
+                    account_keys.get(usize::from(instruction.program_id_index))
+                })
+                .any(|program_id| program_ids.contains(program_id))
+        }
+    };
     let ((transaction_account_set, possibly_incomplete), transaction_accounts_measure) = measure!(
-        blockstore.get_accounts_used_in_range(bank, snapshot_slot, ending_slot),
+        blockstore.get_accounts_used_by_transactions_in_range(
+            bank,
+            snapshot_slot,
+            ending_slot,
+            transaction_filter,
+        ),
         "get transaction accounts"
     );
     let total_accounts_len = transaction_account_set.len();
     info!("Added {total_accounts_len} accounts from transactions. {transaction_accounts_measure}");
 
-    SnapshotMinimizer::minimize(bank, snapshot_slot, ending_slot, transaction_account_set);
+    if let MinimizedTransactions::Signatures(signatures) = minimized_transactions {
+        for signature in signatures {
+            if !found_signatures.contains(signature) {
+                warn!(
+                    "Transaction {signature} not found in slots {snapshot_slot} to {ending_slot}"
+                );
+            }
+        }
+    }
+
+    match minimized_transactions {
+        MinimizedTransactions::All => {
+            SnapshotMinimizer::minimize(bank, snapshot_slot, ending_slot, transaction_account_set)
+        }
+        MinimizedTransactions::Signatures(_) | MinimizedTransactions::Programs(_) => {
+            SnapshotMinimizer::minimize_for_transactions(
+                bank,
+                snapshot_slot,
+                transaction_account_set,
+            )
+        }
+    }
     possibly_incomplete
 }
 
@@ -1433,6 +1498,46 @@ fn main() {
                     .help("Target db"),
             )
         )
+        .subcommand(
+            SubCommand::with_name("export-archive")
+            .about("Export full slots with their transaction statuses, rewards, block times \
+                    and bank hashes into a compressed archive file")
+            .arg(&starting_slot_arg)
+            .arg(&ending_slot_arg)
+            .arg(
+                Arg::with_name("archive_file")
+                    .index(1)
+                    .value_name("ARCHIVE_FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Archive file to write"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("import-archive")
+            .about("Verify an archive written by export-archive against the leader schedule \
+                    of the loaded bank and import it into the ledger")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&accounts_hash_cache_path_arg)
+            .arg(&accounts_index_bins)
+            .arg(&accounts_index_limit)
+            .arg(&disable_disk_index)
+            .arg(&accountsdb_verify_refcounts)
+            .arg(&accounts_db_skip_initial_hash_calc_arg)
+            .arg(&halt_at_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(&use_snapshot_archives_at_startup)
+            .arg(
+                Arg::with_name("archive_file")
+                    .index(1)
+                    .value_name("ARCHIVE_FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Archive file to import"),
+            )
+        )
         .subcommand(
             SubCommand::with_name("slot")
             .about("Print the contents of one or more slots")
@@ -1898,6 +2003,33 @@ fn main() {
                     .value_name("ENDING_SLOT")
                     .help("Ending slot for minimized snapshot creation")
             )
+            .arg(
+                Arg::with_name("minimized_transactions")
+                    .long("minimized-transaction")
+                    .takes_value(true)
+                    .value_name("SIGNATURE")
+                    .multiple(true)
+                    .validator(is_parsable::<Signature>)
+                    .requires("minimized")
+                    .help("Only keep the accounts, programs and sysvars needed to execute the \
+                          transactions with these signatures. The transactions must be in the \
+                          slot range of the minimized snapshot. Bank hashes of slots replayed \
+                          from the snapshot will not match the cluster's.")
+            )
+            .arg(
+                Arg::with_name("minimized_programs")
+                    .long("minimized-program")
+                    .takes_value(true)
+                    .value_name("PROGRAM_ID")
+                    .multiple(true)
+                    .validator(is_pubkey)
+                    .requires("minimized")
+                    .conflicts_with("minimized_transactions")
+                    .help("Only keep the accounts, programs and sysvars needed to execute the \
+                          transactions that invoke these programs in the slot range of the \
+                          minimized snapshot. Bank hashes of slots replayed from the snapshot \
+                          will not match the cluster's.")
+            )
             .arg(
                 Arg::with_name("snapshot_archive_format")
                     .long("snapshot-archive-format")
@@ -1997,6 +2129,31 @@ fn main() {
                     .takes_value(true)
                     .help("Output file in the csv format"),
             )
+        ).subcommand(
+            SubCommand::with_name("staked-nodes")
+            .about("Print the stake of each node as JSON, e.g. for use with \
+                    solana-turbine-simulator")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&accounts_hash_cache_path_arg)
+            .arg(&accounts_index_bins)
+            .arg(&accounts_index_limit)
+            .arg(&disable_disk_index)
+            .arg(&accountsdb_verify_refcounts)
+            .arg(&accounts_db_skip_initial_hash_calc_arg)
+            .arg(&halt_at_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(&use_snapshot_archives_at_startup)
+            .arg(
+                Arg::with_name("epoch")
+                    .long("epoch")
+                    .value_name("EPOCH")
+                    .takes_value(true)
+                    .validator(is_parsable::<Epoch>)
+                    .help("Print the stakes of this epoch [default: the epoch of the \
+                           loaded bank]"),
+            )
         ).subcommand(
             SubCommand::with_name("purge")
             .about("Delete a range of slots from the ledger")
@@ -2141,6 +2298,11 @@ fn main() {
                 .about("Output statistics in JSON format about \
                         all column families in the ledger rocksdb")
         )
+        .subcommand(
+            SubCommand::with_name("column-sizes")
+                .about("Print the on-disk size and compression ratio of \
+                        all column families in the ledger rocksdb")
+        )
         .subcommand(
             SubCommand::with_name("compute-slot-cost")
             .about("runs cost_model over the block at the given slots, \
@@ -2266,6 +2428,90 @@ fn main() {
                     }
                 }
             }
+            ("export-archive", Some(arg_matches)) => {
+                let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+                let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or(Slot::MAX);
+                let archive_file =
+                    PathBuf::from(value_t_or_exit!(arg_matches, "archive_file", String));
+
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::Secondary,
+                    wal_recovery_mode,
+                    force_update_to_open,
+                    enforce_ulimit_nofile,
+                );
+                match blockstore.export_archive(starting_slot, ending_slot, &archive_file) {
+                    Ok(summary) => println!(
+                        "Exported {} slots ({} to {}), {} shreds and {} transactions to {}",
+                        summary.num_slots,
+                        summary.first_slot,
+                        summary.last_slot,
+                        summary.num_shreds,
+                        summary.num_transactions,
+                        archive_file.display()
+                    ),
+                    Err(err) => {
+                        eprintln!("Failed to export {}: {err}", archive_file.display());
+                        exit(1);
+                    }
+                }
+            }
+            ("import-archive", Some(arg_matches)) => {
+                let archive_file =
+                    PathBuf::from(value_t_or_exit!(arg_matches, "archive_file", String));
+
+                let halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
+                let process_options = ProcessOptions {
+                    new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                    halt_at_slot,
+                    run_verification: false,
+                    accounts_db_config: Some(get_accounts_db_config(&ledger_path, arg_matches)),
+                    use_snapshot_archives_at_startup: value_t_or_exit!(
+                        arg_matches,
+                        use_snapshot_archives_at_startup::cli::NAME,
+                        UseSnapshotArchivesAtStartup
+                    ),
+                    ..ProcessOptions::default()
+                };
+                let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+                let blockstore = Arc::new(open_blockstore(
+                    &ledger_path,
+                    AccessType::Primary,
+                    wal_recovery_mode,
+                    force_update_to_open,
+                    enforce_ulimit_nofile,
+                ));
+                let (bank_forks, ..) = load_and_process_ledger(
+                    arg_matches,
+                    &genesis_config,
+                    blockstore.clone(),
+                    process_options,
+                    snapshot_archive_path,
+                    incremental_snapshot_archive_path,
+                )
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load ledger: {err:?}");
+                    exit(1);
+                });
+                let bank = bank_forks.read().unwrap().working_bank();
+                let leader_schedule_cache = LeaderScheduleCache::new_from_bank(&bank);
+                let slot_leader = |slot| leader_schedule_cache.slot_leader_at(slot, Some(&bank));
+                match blockstore.import_archive(&archive_file, slot_leader) {
+                    Ok(summary) => println!(
+                        "Imported {} slots, {} shreds and {} transactions in {}..={}",
+                        summary.num_slots,
+                        summary.num_shreds,
+                        summary.num_transactions,
+                        summary.first_slot,
+                        summary.last_slot
+                    ),
+                    Err(err) => {
+                        eprintln!("Failed to import {}: {err}", archive_file.display());
+                        exit(1);
+                    }
+                }
+            }
             ("genesis", Some(arg_matches)) => {
                 let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
                 let print_accounts = arg_matches.is_present("accounts");
@@ -2897,6 +3143,17 @@ fn main() {
                 } else {
                     None
                 };
+                let minimized_transactions = if arg_matches.is_present("minimized_transactions") {
+                    MinimizedTransactions::Signatures(
+                        values_t_or_exit!(arg_matches, "minimized_transactions", Signature)
+                            .into_iter()
+                            .collect(),
+                    )
+                } else if let Some(program_ids) = pubkeys_of(arg_matches, "minimized_programs") {
+                    MinimizedTransactions::Programs(program_ids.into_iter().collect())
+                } else {
+                    MinimizedTransactions::All
+                };
 
                 let snapshot_type_str = if is_incremental {
                     "incremental "
@@ -3166,6 +3423,7 @@ fn main() {
                                 &bank,
                                 snapshot_slot,
                                 ending_slot.unwrap(),
+                                &minimized_transactions,
                             )
                         } else {
                             false
@@ -3898,6 +4156,60 @@ fn main() {
                     }
                 }
             }
+            ("staked-nodes", Some(arg_matches)) => {
+                let halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
+                let process_options = ProcessOptions {
+                    new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                    halt_at_slot,
+                    run_verification: false,
+                    accounts_db_config: Some(get_accounts_db_config(&ledger_path, arg_matches)),
+                    use_snapshot_archives_at_startup: value_t_or_exit!(
+                        arg_matches,
+                        use_snapshot_archives_at_startup::cli::NAME,
+                        UseSnapshotArchivesAtStartup
+                    ),
+                    ..ProcessOptions::default()
+                };
+                let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    get_access_type(&process_options),
+                    wal_recovery_mode,
+                    force_update_to_open,
+                    enforce_ulimit_nofile,
+                );
+                let (bank_forks, ..) = load_and_process_ledger(
+                    arg_matches,
+                    &genesis_config,
+                    Arc::new(blockstore),
+                    process_options,
+                    snapshot_archive_path,
+                    incremental_snapshot_archive_path,
+                )
+                .unwrap_or_else(|err| {
+                    eprintln!("Failed to load ledger: {err:?}");
+                    exit(1);
+                });
+                let bank = bank_forks.read().unwrap().working_bank();
+                let epoch = value_t!(arg_matches, "epoch", Epoch).unwrap_or_else(|_| bank.epoch());
+                let Some(staked_nodes) = bank.epoch_staked_nodes(epoch) else {
+                    eprintln!(
+                        "Error: stakes for epoch {epoch} are not available in the bank at slot {}",
+                        bank.slot()
+                    );
+                    exit(1);
+                };
+                let staked_nodes: BTreeMap<String, u64> = staked_nodes
+                    .iter()
+                    .map(|(pubkey, stake)| (pubkey.to_string(), *stake))
+                    .collect();
+                eprintln!(
+                    "{} staked nodes in epoch {epoch} at slot {}",
+                    staked_nodes.len(),
+                    bank.slot()
+                );
+                println!("{}", serde_json::to_string_pretty(&staked_nodes).unwrap());
+            }
             ("purge", Some(arg_matches)) => {
                 let start_slot = value_t_or_exit!(arg_matches, "start_slot", Slot);
                 let end_slot = value_t!(arg_matches, "end_slot", Slot).ok();
@@ -4177,6 +4489,55 @@ fn main() {
                     .db(),
                 );
             }
+            ("column-sizes", _) => {
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::Secondary,
+                    wal_recovery_mode,
+                    force_update_to_open,
+                    enforce_ulimit_nofile,
+                );
+                let column_size_stats = blockstore.column_size_stats().unwrap_or_else(|err| {
+                    eprintln!("Failed to read column sizes: {err:?}");
+                    exit(1);
+                });
+                let format_ratio = |raw_size: u64, data_block_size: u64| {
+                    if data_block_size == 0 {
+                        "-".to_string()
+                    } else {
+                        format!("{:.2}", raw_size as f64 / data_block_size as f64)
+                    }
+                };
+                println!(
+                    "{:<28} {:>14} {:>16} {:>16} {:>8}",
+                    "Column", "Entries", "On disk", "Uncompressed", "Ratio"
+                );
+                let (mut num_entries, mut on_disk, mut raw_size, mut data_block_size) =
+                    (0, 0, 0, 0);
+                for stats in &column_size_stats {
+                    println!(
+                        "{:<28} {:>14} {:>16} {:>16} {:>8}",
+                        stats.name,
+                        stats.num_entries,
+                        stats.total_sst_files_size,
+                        stats.raw_size(),
+                        format_ratio(stats.raw_size(), stats.data_block_size),
+                    );
+                    num_entries += stats.num_entries;
+                    on_disk += stats.total_sst_files_size;
+                    raw_size += stats.raw_size();
+                    data_block_size += stats.data_block_size;
+                }
+                println!(
+                    "{:<28} {:>14} {:>16} {:>16} {:>8}",
+                    "Total",
+                    num_entries,
+                    on_disk,
+                    raw_size,
+                    format_ratio(raw_size, data_block_size),
+                );
+                println!("\nSizes are in bytes and exclude data not yet flushed from memtables");
+            }
             ("compute-slot-cost", Some(arg_matches)) => {
                 let blockstore = open_blockstore(
                     &ledger_path,
@@ -6,8 +6,8 @@ use {
     crate::{
         ancestor_iterator::AncestorIterator,
         blockstore_db::{
-            columns as cf, Column, ColumnIndexDeprecation, Database, IteratorDirection,
-            IteratorMode, LedgerColumn, Result, WriteBatch,
+            columns as cf, Column, ColumnIndexDeprecation, ColumnSizeStats, Database,
+            IteratorDirection, IteratorMode, LedgerColumn, Result, WriteBatch,
         },
         blockstore_meta::*,
         blockstore_options::{
@@ -81,6 +81,7 @@ use {
     thiserror::Error,
     trees::{Tree, TreeWalk},
 };
+pub mod blockstore_archive;
 pub mod blockstore_purge;
 pub use {
     crate::{
@@ -117,6 +118,8 @@ pub const MAX_DATA_SHREDS_PER_SLOT: usize = 32_768;
 
 pub type CompletedSlotsSender = Sender<Vec<Slot>>;
 pub type CompletedSlotsReceiver = Receiver<Vec<Slot>>;
+pub type DuplicateSlotProofSender = Sender<Slot>;
+pub type DuplicateSlotProofReceiver = Receiver<Slot>;
 type CompletedRanges = Vec<(u32, u32)>;
 
 #[derive(Default)]
@@ -218,6 +221,7 @@ pub struct Blockstore {
     insert_shreds_lock: Mutex<()>,
     new_shreds_signals: Mutex<Vec<Sender<bool>>>,
     completed_slots_senders: Mutex<Vec<CompletedSlotsSender>>,
+    duplicate_slot_proof_senders: Mutex<Vec<DuplicateSlotProofSender>>,
     pub shred_timing_point_sender: Option<PohTimingSender>,
     pub lowest_cleanup_slot: RwLock<Slot>,
     pub slots_stats: SlotsStats,
@@ -354,6 +358,7 @@ impl Blockstore {
             optimistic_slots_cf,
             new_shreds_signals: Mutex::default(),
             completed_slots_senders: Mutex::default(),
+            duplicate_slot_proof_senders: Mutex::default(),
             shred_timing_point_sender: None,
             insert_shreds_lock: Mutex::<()>::default(),
             last_root,
@@ -510,6 +515,12 @@ impl Blockstore {
         self.db.live_files_metadata()
     }
 
+    /// Makes the latest writes of the primary blockstore visible to this one, which must have
+    /// been opened with `AccessType::Secondary`
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()
+    }
+
     pub fn slot_data_iterator(
         &self,
         slot: Slot,
@@ -723,6 +734,32 @@ impl Blockstore {
         self.optimistic_slots_cf.submit_rocksdb_cf_metrics();
     }
 
+    /// Returns the size of each column family on disk and before compression
+    pub fn column_size_stats(&self) -> Result<Vec<ColumnSizeStats>> {
+        Ok(vec![
+            self.meta_cf.size_stats()?,
+            self.dead_slots_cf.size_stats()?,
+            self.duplicate_slots_cf.size_stats()?,
+            self.roots_cf.size_stats()?,
+            self.erasure_meta_cf.size_stats()?,
+            self.orphans_cf.size_stats()?,
+            self.index_cf.size_stats()?,
+            self.data_shred_cf.size_stats()?,
+            self.code_shred_cf.size_stats()?,
+            self.transaction_status_cf.size_stats()?,
+            self.address_signatures_cf.size_stats()?,
+            self.transaction_memos_cf.size_stats()?,
+            self.transaction_status_index_cf.size_stats()?,
+            self.rewards_cf.size_stats()?,
+            self.blocktime_cf.size_stats()?,
+            self.perf_samples_cf.size_stats()?,
+            self.block_height_cf.size_stats()?,
+            self.program_costs_cf.size_stats()?,
+            self.bank_hash_cf.size_stats()?,
+            self.optimistic_slots_cf.size_stats()?,
+        ])
+    }
+
     fn try_shred_recovery(
         &self,
         erasure_metas: &HashMap<ErasureSetId, ErasureMeta>,
@@ -903,6 +940,7 @@ impl Blockstore {
                         &mut index_meta_time_us,
                         &mut duplicate_shreds,
                         is_trusted,
+                        leader_schedule,
                         shred_source,
                         metrics,
                     );
@@ -1081,6 +1119,11 @@ impl Blockstore {
         self.completed_slots_senders.lock().unwrap().push(s);
     }
 
+    /// Signals the slot of every duplicate slot proof stored
+    pub fn add_duplicate_slot_proof_signal(&self, s: DuplicateSlotProofSender) {
+        self.duplicate_slot_proof_senders.lock().unwrap().push(s);
+    }
+
     pub fn get_new_shred_signals_len(&self) -> usize {
         self.new_shreds_signals.lock().unwrap().len()
     }
@@ -1092,6 +1135,7 @@ impl Blockstore {
     pub fn drop_signal(&self) {
         self.new_shreds_signals.lock().unwrap().clear();
         self.completed_slots_senders.lock().unwrap().clear();
+        self.duplicate_slot_proof_senders.lock().unwrap().clear();
     }
 
     /// Range-delete all entries which prefix matches the specified `slot`,
@@ -1171,6 +1215,7 @@ impl Blockstore {
         index_meta_time_us: &mut u64,
         duplicate_shreds: &mut Vec<PossibleDuplicateShred>,
         is_trusted: bool,
+        leader_schedule: Option<&LeaderScheduleCache>,
         shred_source: ShredSource,
         metrics: &mut BlockstoreInsertionMetrics,
     ) -> bool {
@@ -1215,14 +1260,14 @@ impl Blockstore {
             );
             if let Some(conflicting_shred) = conflicting_shred {
                 if !self.has_duplicate_shreds_in_slot(slot) {
-                    if self
-                        .store_duplicate_slot(
-                            slot,
-                            conflicting_shred.clone(),
-                            shred.payload().clone(),
-                        )
-                        .is_err()
-                    {
+                    let leader_pubkey = leader_schedule
+                        .and_then(|leader_schedule| leader_schedule.slot_leader_at(slot, None));
+                    let proof = DuplicateSlotProofV2::new(
+                        conflicting_shred.clone(),
+                        shred.payload().clone(),
+                        leader_pubkey,
+                    );
+                    if self.put_duplicate_slot_proof(slot, proof).is_err() {
                         warn!("bad duplicate store..");
                     }
 
@@ -1503,10 +1548,12 @@ impl Blockstore {
                     )
                     .into_owned();
 
-                if self
-                    .store_duplicate_slot(slot, ending_shred.clone(), shred.payload().clone())
-                    .is_err()
-                {
+                let proof = DuplicateSlotProofV2::new(
+                    ending_shred.clone(),
+                    shred.payload().clone(),
+                    leader_pubkey,
+                );
+                if self.put_duplicate_slot_proof(slot, proof).is_err() {
                     warn!("store duplicate error");
                 }
                 duplicate_shreds.push(PossibleDuplicateShred::LastIndexConflict(
@@ -1542,10 +1589,12 @@ impl Blockstore {
                     )
                     .into_owned();
 
-                if self
-                    .store_duplicate_slot(slot, ending_shred.clone(), shred.payload().clone())
-                    .is_err()
-                {
+                let proof = DuplicateSlotProofV2::new(
+                    ending_shred.clone(),
+                    shred.payload().clone(),
+                    leader_pubkey,
+                );
+                if self.put_duplicate_slot_proof(slot, proof).is_err() {
                     warn!("store duplicate error");
                 }
                 duplicate_shreds.push(PossibleDuplicateShred::LastIndexConflict(
@@ -2872,6 +2921,21 @@ impl Blockstore {
         starting_slot: Slot,
         ending_slot: Slot,
     ) -> (DashSet<Pubkey>, bool) {
+        self.get_accounts_used_by_transactions_in_range(bank, starting_slot, ending_slot, |_| true)
+    }
+
+    /// Like `get_accounts_used_in_range`, but only collects the accounts of transactions for
+    /// which `transaction_filter` returns true.
+    pub fn get_accounts_used_by_transactions_in_range<F>(
+        &self,
+        bank: &Bank,
+        starting_slot: Slot,
+        ending_slot: Slot,
+        transaction_filter: F,
+    ) -> (DashSet<Pubkey>, bool)
+    where
+        F: Fn(&VersionedTransaction) -> bool + Sync,
+    {
         let result = DashSet::new();
         let lookup_tables = DashSet::new();
         let possible_cpi_alt_extend = AtomicBool::new(false);
@@ -2887,30 +2951,34 @@ impl Blockstore {
             .for_each(|slot| {
                 if let Ok(entries) = self.get_slot_entries(slot, 0) {
                     entries.into_par_iter().for_each(|entry| {
-                        entry.transactions.into_iter().for_each(|tx| {
-                            // Attempt to verify transaction and load addresses from the current bank,
-                            // or manually scan the transaction for addresses if the transaction.
-                            if let Ok(tx) = bank.fully_verify_transaction(tx.clone()) {
-                                add_to_set(&result, tx.message().account_keys().iter());
-                            } else {
-                                add_to_set(&result, tx.message.static_account_keys());
-                                if let Some(lookups) = tx.message.address_table_lookups() {
-                                    add_to_set(
-                                        &lookup_tables,
-                                        lookups.iter().map(|lookup| &lookup.account_key),
-                                    );
-                                }
+                        entry
+                            .transactions
+                            .into_iter()
+                            .filter(|tx| transaction_filter(tx))
+                            .for_each(|tx| {
+                                // Attempt to verify transaction and load addresses from the current bank,
+                                // or manually scan the transaction for addresses if the transaction.
+                                if let Ok(tx) = bank.fully_verify_transaction(tx.clone()) {
+                                    add_to_set(&result, tx.message().account_keys().iter());
+                                } else {
+                                    add_to_set(&result, tx.message.static_account_keys());
+                                    if let Some(lookups) = tx.message.address_table_lookups() {
+                                        add_to_set(
+                                            &lookup_tables,
+                                            lookups.iter().map(|lookup| &lookup.account_key),
+                                        );
+                                    }
 
-                                let tx = SanitizedVersionedTransaction::try_from(tx)
-                                    .expect("transaction failed to sanitize");
+                                    let tx = SanitizedVersionedTransaction::try_from(tx)
+                                        .expect("transaction failed to sanitize");
 
-                                let alt_scan_extensions = scan_transaction(&tx);
-                                add_to_set(&result, &alt_scan_extensions.accounts);
-                                if alt_scan_extensions.possibly_incomplete {
-                                    possible_cpi_alt_extend.store(true, Ordering::Relaxed);
+                                    let alt_scan_extensions = scan_transaction(&tx);
+                                    add_to_set(&result, &alt_scan_extensions.accounts);
+                                    if alt_scan_extensions.possibly_incomplete {
+                                        possible_cpi_alt_extend.store(true, Ordering::Relaxed);
+                                    }
                                 }
-                            }
-                        });
+                            });
                     });
                 }
             });
@@ -3037,6 +3105,81 @@ impl Blockstore {
         })
     }
 
+    /// Returns the entry of `slot` holding the transaction with `signature`, along with the
+    /// entry's index in the slot, the data shreds its serialized bytes are spread over, and the
+    /// byte offset of the entry within the concatenated data of those shreds.
+    pub fn get_transaction_entry_with_shreds(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+    ) -> Result<Option<(usize, Entry, Vec<Shred>, usize)>> {
+        let (completed_ranges, _) = self.get_completed_ranges(slot, 0)?;
+        if self.is_dead(slot) {
+            return Err(BlockstoreError::DeadSlot);
+        }
+        // Completed ranges end below `consumed`, so shreds up to there are all present and each
+        // sits at the position of its index.
+        let data_shreds = self.get_data_shreds_for_slot(slot, 0)?;
+        let mut num_entries = 0;
+        for (start_index, end_index) in completed_ranges {
+            let shreds = data_shreds
+                .get(start_index as usize..=end_index as usize)
+                .ok_or_else(|| {
+                    BlockstoreError::InvalidShredData(Box::new(bincode::ErrorKind::Custom(
+                        format!(
+                            "Missing shreds for slot {slot}, indices {start_index}..={end_index}"
+                        ),
+                    )))
+                })?;
+            let deshred_payload = Shredder::deshred(shreds).map_err(|err| {
+                BlockstoreError::InvalidShredData(Box::new(bincode::ErrorKind::Custom(format!(
+                    "Could not reconstruct data block from constituent shreds, error: {err:?}"
+                ))))
+            })?;
+            let mut entries: Vec<Entry> = bincode::deserialize(&deshred_payload)?;
+            let Some(position) = entries.iter().position(|entry| {
+                entry
+                    .transactions
+                    .iter()
+                    .any(|transaction| transaction.signatures.first() == Some(signature))
+            }) else {
+                num_entries += entries.len();
+                continue;
+            };
+            // Byte range of the entry in the data block, past the length prefix of the vector.
+            let serialized_size =
+                |entry: &Entry| bincode::serialized_size(entry).map(|size| size as usize);
+            let start = entries[..position]
+                .iter()
+                .map(serialized_size)
+                .sum::<bincode::Result<usize>>()?
+                + std::mem::size_of::<u64>();
+            let end = start + serialized_size(&entries[position])?;
+            let mut offset = 0;
+            let mut shreds_offset = None;
+            let shreds = shreds
+                .iter()
+                .filter(|shred| {
+                    let size = shred.data().map(<[u8]>::len).unwrap_or_default();
+                    let overlaps = offset < end && start < offset + size;
+                    if overlaps {
+                        shreds_offset.get_or_insert(offset);
+                    }
+                    offset += size;
+                    overlaps
+                })
+                .cloned()
+                .collect();
+            return Ok(Some((
+                num_entries + position,
+                entries.swap_remove(position),
+                shreds,
+                start - shreds_offset.unwrap_or_default(),
+            )));
+        }
+        Ok(None)
+    }
+
     fn get_any_valid_slot_entries(&self, slot: Slot, start_index: u64) -> Vec<Entry> {
         let (completed_ranges, slot_meta) = self
             .get_completed_ranges(slot, start_index)
@@ -3228,24 +3371,56 @@ impl Blockstore {
         self.duplicate_slots_cf.delete(slot)
     }
 
-    pub fn get_first_duplicate_proof(&self) -> Option<(Slot, DuplicateSlotProof)> {
+    pub fn get_first_duplicate_proof(&self) -> Option<(Slot, DuplicateSlotProofV2)> {
         let mut iter = self
             .db
             .iter::<cf::DuplicateSlots>(IteratorMode::From(0, IteratorDirection::Forward))
             .unwrap();
-        iter.next()
-            .map(|(slot, proof_bytes)| (slot, deserialize(&proof_bytes).unwrap()))
+        iter.next().map(|(slot, proof_bytes)| {
+            (
+                slot,
+                deserialize_duplicate_slot_proof(&proof_bytes).unwrap(),
+            )
+        })
     }
 
     pub fn store_duplicate_slot(&self, slot: Slot, shred1: Vec<u8>, shred2: Vec<u8>) -> Result<()> {
-        let duplicate_slot_proof = DuplicateSlotProof::new(shred1, shred2);
-        self.duplicate_slots_cf.put(slot, &duplicate_slot_proof)
+        self.put_duplicate_slot_proof(slot, DuplicateSlotProofV2::new(shred1, shred2, None))
     }
 
-    pub fn get_duplicate_slot(&self, slot: u64) -> Option<DuplicateSlotProof> {
-        self.duplicate_slots_cf
-            .get(slot)
-            .expect("fetch from DuplicateSlots column family failed")
+    /// Stores a duplicate slot proof along with the slot leader who signed
+    /// both shreds.
+    pub fn store_duplicate_slot_with_leader(
+        &self,
+        slot: Slot,
+        shred1: Vec<u8>,
+        shred2: Vec<u8>,
+        leader: Pubkey,
+    ) -> Result<()> {
+        let duplicate_slot_proof = DuplicateSlotProofV2::new(shred1, shred2, Some(leader));
+        self.put_duplicate_slot_proof(slot, duplicate_slot_proof)
+    }
+
+    fn put_duplicate_slot_proof(&self, slot: Slot, proof: DuplicateSlotProofV2) -> Result<()> {
+        // Always write as the current version.
+        let bytes =
+            serialize(&proof).expect("`DuplicateSlotProofV2` can be serialized with `bincode`");
+        self.duplicate_slots_cf.put_bytes(slot, &bytes)?;
+        for sender in self.duplicate_slot_proof_senders.lock().unwrap().iter() {
+            let _ = sender.send(slot);
+        }
+        Ok(())
+    }
+
+    pub fn get_duplicate_slot(&self, slot: u64) -> Option<DuplicateSlotProofV2> {
+        let bytes = self
+            .duplicate_slots_cf
+            .get_bytes(slot)
+            .expect("fetch from DuplicateSlots column family failed")?;
+        Some(
+            deserialize_duplicate_slot_proof(&bytes)
+                .expect("deserialize from DuplicateSlots column family failed"),
+        )
     }
 
     // `new_shred` is assumed to have slot and index equal to the given slot and index.
@@ -3264,7 +3439,7 @@ impl Blockstore {
 
     pub fn has_duplicate_shreds_in_slot(&self, slot: Slot) -> bool {
         self.duplicate_slots_cf
-            .get(slot)
+            .get_bytes(slot)
             .expect("fetch from DuplicateSlots column family failed")
             .is_some()
     }
@@ -4526,7 +4701,11 @@ pub mod tests {
     use {
         super::*,
         crate::{
-            blockstore_options::{BlockstoreRocksFifoOptions, ShredStorageType},
+            blockstore_db::ColumnName,
+            blockstore_options::{
+                BlockstoreCompressionType, BlockstoreRocksFifoOptions, BlockstoreZstdOptions,
+                ShredStorageType, MAX_ZSTD_DICTIONARY_SIZE,
+            },
             genesis_utils::{create_genesis_config, GenesisConfigInfo},
             leader_schedule::{FixedSchedule, LeaderSchedule},
             shred::{max_ticks_per_n_shreds, ShredFlags},
@@ -4636,6 +4815,75 @@ pub mod tests {
             .exists());
     }
 
+    #[test]
+    fn test_zstd_compression() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let options = || BlockstoreOptions {
+            column_options: LedgerColumnOptions {
+                compression_type: BlockstoreCompressionType::Zstd(BlockstoreZstdOptions {
+                    level: 9,
+                    max_dict_bytes: 16 * 1024,
+                }),
+                ..LedgerColumnOptions::default()
+            },
+            ..BlockstoreOptions::default()
+        };
+        let signature = Signature::new_unique();
+        let status = TransactionStatusMeta {
+            fee: 42,
+            log_messages: Some(vec!["Program log: compressible".to_string(); 16]),
+            ..TransactionStatusMeta::default()
+        };
+        {
+            let blockstore = Blockstore::open_with_options(ledger_path.path(), options()).unwrap();
+            blockstore
+                .write_transaction_status(1, signature, vec![], vec![], status.clone(), 0)
+                .unwrap();
+        }
+
+        let blockstore = Blockstore::open_with_options(ledger_path.path(), options()).unwrap();
+        assert_eq!(
+            blockstore.read_transaction_status((signature, 1)).unwrap(),
+            Some(status)
+        );
+        let column_names: Vec<_> = blockstore
+            .column_size_stats()
+            .unwrap()
+            .into_iter()
+            .map(|stats| stats.name)
+            .collect();
+        assert!(column_names.contains(&cf::TransactionStatus::NAME));
+        assert!(column_names.contains(&cf::ShredData::NAME));
+    }
+
+    #[test]
+    fn test_zstd_dictionary_size_too_large() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let options = |max_dict_bytes| BlockstoreOptions {
+            column_options: LedgerColumnOptions {
+                compression_type: BlockstoreCompressionType::Zstd(BlockstoreZstdOptions {
+                    max_dict_bytes,
+                    ..BlockstoreZstdOptions::default()
+                }),
+                ..LedgerColumnOptions::default()
+            },
+            ..BlockstoreOptions::default()
+        };
+        assert_matches!(
+            Blockstore::open_with_options(
+                ledger_path.path(),
+                options(MAX_ZSTD_DICTIONARY_SIZE + 1)
+            ),
+            Err(BlockstoreError::InvalidZstdDictionarySize(size))
+                if size == MAX_ZSTD_DICTIONARY_SIZE + 1
+        );
+        assert!(Blockstore::open_with_options(
+            ledger_path.path(),
+            options(MAX_ZSTD_DICTIONARY_SIZE)
+        )
+        .is_ok());
+    }
+
     #[test]
     fn test_insert_get_bytes() {
         // Create enough entries to ensure there are at least two shreds created
@@ -5092,6 +5340,47 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_get_transaction_entry_with_shreds() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let entries = make_slot_entries_with_transactions(100);
+        let shreds = entries_to_test_shreds(
+            &entries, 1, 0, true, 0, true, // merkle_variant
+        );
+        assert!(shreds.len() > 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+
+        for position in [0, 52, 198] {
+            let signature = entries[position].transactions[0].signatures[0];
+            let (index, entry, shreds, offset) = blockstore
+                .get_transaction_entry_with_shreds(1, &signature)
+                .unwrap()
+                .unwrap();
+            assert_eq!(index, position);
+            assert_eq!(entry, entries[position]);
+            // The shreds are consecutive and their data holds the serialized entry.
+            assert!(shreds
+                .windows(2)
+                .all(|shreds| shreds[0].index() + 1 == shreds[1].index()));
+            let data: Vec<u8> = shreds
+                .iter()
+                .flat_map(|shred| shred.data().unwrap())
+                .copied()
+                .collect();
+            let entry = bincode::serialize(&entry).unwrap();
+            assert_eq!(data[offset..offset + entry.len()], entry);
+            // The first shred holds the start of the entry.
+            assert!(offset < shreds[0].data().unwrap().len());
+        }
+        assert_eq!(
+            blockstore
+                .get_transaction_entry_with_shreds(1, &Signature::new_unique())
+                .unwrap(),
+            None
+        );
+    }
+
     // This test seems to be unnecessary with introduction of data shreds. There are no
     // guarantees that a particular shred index contains a complete entry
     #[test]
@@ -6691,6 +6980,7 @@ pub mod tests {
             &mut index_meta_time_us,
             &mut vec![],
             false,
+            None,
             ShredSource::Turbine,
             &mut BlockstoreInsertionMetrics::default(),
         ));
@@ -6706,6 +6996,7 @@ pub mod tests {
             &mut index_meta_time_us,
             &mut duplicate_shreds,
             false,
+            None,
             ShredSource::Turbine,
             &mut BlockstoreInsertionMetrics::default(),
         ));
@@ -9314,6 +9605,32 @@ pub mod tests {
         let duplicate_proof = blockstore.get_duplicate_slot(slot).unwrap();
         assert_eq!(duplicate_proof.shred1, *shred.payload());
         assert_eq!(duplicate_proof.shred2, *duplicate_shred.payload());
+        assert_eq!(duplicate_proof.leader, None);
+
+        // Proofs stored before the leader was recorded are still read.
+        let legacy_proof =
+            DuplicateSlotProof::new(shred.payload().clone(), duplicate_shred.payload().clone());
+        blockstore
+            .duplicate_slots_cf
+            .put_bytes(slot, &serialize(&legacy_proof).unwrap())
+            .unwrap();
+        let duplicate_proof = blockstore.get_duplicate_slot(slot).unwrap();
+        assert_eq!(duplicate_proof.shred1, *shred.payload());
+        assert_eq!(duplicate_proof.shred2, *duplicate_shred.payload());
+        assert_eq!(duplicate_proof.leader, None);
+
+        let leader = leader_keypair.pubkey();
+        blockstore
+            .store_duplicate_slot_with_leader(
+                slot,
+                shred.payload().clone(),
+                duplicate_shred.payload().clone(),
+                leader,
+            )
+            .unwrap();
+        let (proof_slot, duplicate_proof) = blockstore.get_first_duplicate_proof().unwrap();
+        assert_eq!(proof_slot, slot);
+        assert_eq!(duplicate_proof.leader, Some(leader));
     }
 
     #[test]
@@ -0,0 +1,990 @@
+//! Moves a range of slots between blockstores through a single file.
+//!
+//! An archive starts with [`ARCHIVE_MAGIC`] and the format version, followed by a zstd stream of
+//! length-prefixed, bincode-encoded [`ArchiveRecord`]s: a header, one record per slot, and a
+//! footer holding the number of slots and a hash over every preceding record. Transaction
+//! statuses and rewards are kept in the protobuf encoding the blockstore itself uses.
+
+use {
+    super::*,
+    prost::Message,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        hash::Hasher,
+        message::{v0::LoadedAddresses, SimpleAddressLoader},
+        transaction::{MessageHash, SanitizedTransaction},
+    },
+    solana_storage_proto::convert::generated,
+    solana_transaction_status::Reward,
+    std::io::{BufReader, BufWriter, Read, Write as IoWrite},
+};
+
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"SOLLEDGR";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const ARCHIVE_COMPRESSION_LEVEL: i32 = 3;
+// Far larger than any slot record can legitimately get
+const MAX_RECORD_SIZE: u64 = 1 << 30;
+
+#[derive(Error, Debug)]
+pub enum BlockstoreArchiveError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("blockstore error: {0}")]
+    Blockstore(#[from] BlockstoreError),
+
+    #[error("serialization error: {0}")]
+    Serialize(#[from] bincode::Error),
+
+    #[error("not a blockstore archive")]
+    InvalidMagic,
+
+    #[error("unsupported archive version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("malformed archive: {0}")]
+    Malformed(String),
+
+    #[error("archive checksum mismatch: expected {expected}, computed {computed}")]
+    ChecksumMismatch { expected: Hash, computed: Hash },
+
+    #[error(
+        "bank hash mismatch for slot {slot}: archive has {archive}, blockstore has {blockstore}"
+    )]
+    BankHashMismatch {
+        slot: Slot,
+        archive: Hash,
+        blockstore: Hash,
+    },
+
+    #[error("slot {0} is incomplete")]
+    IncompleteSlot(Slot),
+
+    #[error("invalid shred in slot {0}: {1}")]
+    InvalidShred(Slot, String),
+
+    #[error("unknown leader for slot {0}")]
+    UnknownLeader(Slot),
+
+    #[error("shred {index} of slot {slot} is not signed by the slot leader {leader}")]
+    InvalidShredSignature {
+        slot: Slot,
+        index: u32,
+        leader: Pubkey,
+    },
+}
+
+type Result<T> = std::result::Result<T, BlockstoreArchiveError>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+    pub num_slots: usize,
+    pub num_shreds: usize,
+    pub num_transactions: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveHeader {
+    first_slot: Slot,
+    last_slot: Slot,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveFooter {
+    num_slots: u64,
+    checksum: Hash,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TransactionRecord {
+    index: u32,
+    signature: Signature,
+    // generated::TransactionStatusMeta
+    status: Option<Vec<u8>>,
+    memos: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SlotRecord {
+    slot: Slot,
+    parent_slot: Option<Slot>,
+    is_root: bool,
+    // (frozen hash, is duplicate confirmed)
+    bank_hash: Option<(Hash, bool)>,
+    block_time: Option<UnixTimestamp>,
+    block_height: Option<u64>,
+    // generated::Rewards
+    rewards: Option<Vec<u8>>,
+    transactions: Vec<TransactionRecord>,
+    data_shreds: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum ArchiveRecord {
+    Header(ArchiveHeader),
+    Slot(Box<SlotRecord>),
+    Footer(ArchiveFooter),
+}
+
+struct ArchiveWriter<W: IoWrite> {
+    encoder: zstd::stream::write::Encoder<'static, W>,
+    hasher: Hasher,
+}
+
+impl<W: IoWrite> ArchiveWriter<W> {
+    fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(ARCHIVE_MAGIC)?;
+        writer.write_all(&ARCHIVE_VERSION.to_le_bytes())?;
+        let mut encoder = zstd::stream::write::Encoder::new(writer, ARCHIVE_COMPRESSION_LEVEL)?;
+        encoder.include_checksum(true)?;
+        Ok(Self {
+            encoder,
+            hasher: Hasher::default(),
+        })
+    }
+
+    fn write_record(&mut self, record: &ArchiveRecord) -> Result<()> {
+        let bytes = bincode::serialize(record)?;
+        let len = (bytes.len() as u64).to_le_bytes();
+        self.hasher.hash(&len);
+        self.hasher.hash(&bytes);
+        self.encoder.write_all(&len)?;
+        self.encoder.write_all(&bytes)?;
+        Ok(())
+    }
+
+    fn finish(mut self, num_slots: u64) -> Result<W> {
+        let footer = ArchiveRecord::Footer(ArchiveFooter {
+            num_slots,
+            checksum: self.hasher.clone().result(),
+        });
+        let bytes = bincode::serialize(&footer)?;
+        self.encoder
+            .write_all(&(bytes.len() as u64).to_le_bytes())?;
+        self.encoder.write_all(&bytes)?;
+        Ok(self.encoder.finish()?)
+    }
+}
+
+type ArchiveDecoder = zstd::stream::read::Decoder<'static, BufReader<fs::File>>;
+
+/// Opens an archive, checking its magic and version, and returns a reader over its records.
+fn open_archive(archive_path: &Path) -> Result<ArchiveDecoder> {
+    let mut reader = BufReader::new(fs::File::open(archive_path)?);
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != ARCHIVE_MAGIC {
+        return Err(BlockstoreArchiveError::InvalidMagic);
+    }
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != ARCHIVE_VERSION {
+        return Err(BlockstoreArchiveError::UnsupportedVersion(version));
+    }
+    Ok(zstd::stream::read::Decoder::with_buffer(reader)?)
+}
+
+fn read_record(decoder: &mut ArchiveDecoder, hasher: &mut Hasher) -> Result<ArchiveRecord> {
+    let mut len = [0u8; 8];
+    decoder.read_exact(&mut len)?;
+    let size = u64::from_le_bytes(len);
+    if size > MAX_RECORD_SIZE {
+        return Err(BlockstoreArchiveError::Malformed(format!(
+            "record of {size} bytes"
+        )));
+    }
+    let mut bytes = vec![0u8; size as usize];
+    decoder.read_exact(&mut bytes)?;
+    let record = bincode::deserialize(&bytes)?;
+    if !matches!(record, ArchiveRecord::Footer(_)) {
+        hasher.hash(&len);
+        hasher.hash(&bytes);
+    }
+    Ok(record)
+}
+
+fn read_header(decoder: &mut ArchiveDecoder, hasher: &mut Hasher) -> Result<ArchiveHeader> {
+    match read_record(decoder, hasher)? {
+        ArchiveRecord::Header(header) => Ok(header),
+        ArchiveRecord::Slot(_) | ArchiveRecord::Footer(_) => Err(
+            BlockstoreArchiveError::Malformed("missing header".to_string()),
+        ),
+    }
+}
+
+/// Reads an archive's records, calling `process_slot` on each slot record in order. Fails unless
+/// the archive is complete and its checksum matches.
+fn read_archive(
+    archive_path: &Path,
+    mut process_slot: impl FnMut(SlotRecord) -> Result<()>,
+) -> Result<ArchiveHeader> {
+    let mut decoder = open_archive(archive_path)?;
+    let mut hasher = Hasher::default();
+    let header = read_header(&mut decoder, &mut hasher)?;
+    let mut num_slots = 0;
+    let mut first_slot = None;
+    let mut last_slot = None;
+    loop {
+        match read_record(&mut decoder, &mut hasher)? {
+            ArchiveRecord::Header(_) => {
+                return Err(BlockstoreArchiveError::Malformed(
+                    "duplicate header".to_string(),
+                ));
+            }
+            ArchiveRecord::Slot(slot_record) => {
+                let slot = slot_record.slot;
+                if slot < header.first_slot || slot > header.last_slot {
+                    return Err(BlockstoreArchiveError::Malformed(format!(
+                        "slot {slot} outside of {}..={}",
+                        header.first_slot, header.last_slot
+                    )));
+                }
+                if last_slot.is_some_and(|last_slot| slot <= last_slot) {
+                    return Err(BlockstoreArchiveError::Malformed(format!(
+                        "slot {slot} out of order"
+                    )));
+                }
+                first_slot.get_or_insert(slot);
+                last_slot = Some(slot);
+                num_slots += 1;
+                process_slot(*slot_record)?;
+            }
+            ArchiveRecord::Footer(footer) => {
+                let computed = hasher.result();
+                if footer.checksum != computed {
+                    return Err(BlockstoreArchiveError::ChecksumMismatch {
+                        expected: footer.checksum,
+                        computed,
+                    });
+                }
+                if footer.num_slots != num_slots {
+                    return Err(BlockstoreArchiveError::Malformed(format!(
+                        "footer lists {} slots, found {num_slots}",
+                        footer.num_slots
+                    )));
+                }
+                if first_slot != Some(header.first_slot) || last_slot != Some(header.last_slot) {
+                    return Err(BlockstoreArchiveError::Malformed(format!(
+                        "header lists slots {}..={}, found {first_slot:?}..={last_slot:?}",
+                        header.first_slot, header.last_slot
+                    )));
+                }
+                break;
+            }
+        }
+    }
+    if decoder.read(&mut [0u8; 1])? != 0 {
+        return Err(BlockstoreArchiveError::Malformed(
+            "trailing data after footer".to_string(),
+        ));
+    }
+    Ok(header)
+}
+
+fn verify_slot_record(slot_record: &SlotRecord) -> Result<()> {
+    let slot = slot_record.slot;
+    if slot_record.data_shreds.is_empty() {
+        return Err(BlockstoreArchiveError::IncompleteSlot(slot));
+    }
+    for shred in &slot_record.data_shreds {
+        match shred::layout::get_slot(shred) {
+            Some(shred_slot) if shred_slot == slot => (),
+            Some(shred_slot) => {
+                return Err(BlockstoreArchiveError::InvalidShred(
+                    slot,
+                    format!("shred for slot {shred_slot}"),
+                ));
+            }
+            None => {
+                return Err(BlockstoreArchiveError::InvalidShred(
+                    slot,
+                    "truncated shred".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn verify_shred_signatures(slot_record: &SlotRecord, leader: &Pubkey) -> Result<()> {
+    let slot = slot_record.slot;
+    for shred in &slot_record.data_shreds {
+        let shred = Shred::new_from_serialized_shred(shred.clone())
+            .map_err(|err| BlockstoreArchiveError::InvalidShred(slot, err.to_string()))?;
+        if !shred.verify(leader) {
+            return Err(BlockstoreArchiveError::InvalidShredSignature {
+                slot,
+                index: shred.index(),
+                leader: *leader,
+            });
+        }
+    }
+    Ok(())
+}
+
+// The accounts the transaction locked, as the validator recorded them when it executed the
+// transaction
+fn transaction_account_locks(
+    transaction: &VersionedTransaction,
+    loaded_addresses: &LoadedAddresses,
+) -> Result<(Vec<Pubkey>, Vec<Pubkey>)> {
+    let transaction = SanitizedTransaction::try_create(
+        transaction.clone(),
+        MessageHash::Compute,
+        None,
+        SimpleAddressLoader::Enabled(loaded_addresses.clone()),
+    )
+    .map_err(|err| BlockstoreArchiveError::Malformed(err.to_string()))?;
+    let account_locks = transaction.get_account_locks_unchecked();
+    Ok((
+        account_locks.writable.into_iter().copied().collect(),
+        account_locks.readonly.into_iter().copied().collect(),
+    ))
+}
+
+/// Returns the first and last slot the archive at `archive_path` holds, reading only its header
+pub fn read_archive_slot_range(archive_path: &Path) -> Result<(Slot, Slot)> {
+    let mut decoder = open_archive(archive_path)?;
+    let header = read_header(&mut decoder, &mut Hasher::default())?;
+    Ok((header.first_slot, header.last_slot))
+}
+
+/// Returns the slots the archive at `archive_path` holds which were rooted when it was exported,
+/// in slot order. Fails unless the whole archive is complete and matches its checksum.
+pub fn read_archive_rooted_slots(archive_path: &Path) -> Result<Vec<Slot>> {
+    let mut rooted_slots = vec![];
+    read_archive(archive_path, |slot_record| {
+        if slot_record.is_root {
+            rooted_slots.push(slot_record.slot);
+        }
+        Ok(())
+    })?;
+    Ok(rooted_slots)
+}
+
+/// Returns the data shreds of those of `slots` which the archive at `archive_path` holds and
+/// which were rooted when it was exported, in slot order. Fails unless the whole archive is
+/// complete and matches its checksum.
+pub fn read_archive_rooted_shreds(
+    archive_path: &Path,
+    slots: &HashSet<Slot>,
+) -> Result<Vec<(Slot, Vec<Shred>)>> {
+    let mut rooted_shreds = vec![];
+    read_archive(archive_path, |slot_record| {
+        let slot = slot_record.slot;
+        if !slot_record.is_root || !slots.contains(&slot) {
+            return Ok(());
+        }
+        verify_slot_record(&slot_record)?;
+        let shreds = slot_record
+            .data_shreds
+            .into_iter()
+            .map(|shred| {
+                Shred::new_from_serialized_shred(shred)
+                    .map_err(|err| BlockstoreArchiveError::InvalidShred(slot, err.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        rooted_shreds.push((slot, shreds));
+        Ok(())
+    })?;
+    Ok(rooted_shreds)
+}
+
+/// Checks that the archive at `archive_path` is complete and matches its checksum, without
+/// touching any blockstore
+pub fn verify_archive(archive_path: &Path) -> Result<ArchiveSummary> {
+    let mut summary = ArchiveSummary::default();
+    let header = read_archive(archive_path, |slot_record| {
+        verify_slot_record(&slot_record)?;
+        summary.num_slots += 1;
+        summary.num_shreds += slot_record.data_shreds.len();
+        summary.num_transactions += slot_record.transactions.len();
+        Ok(())
+    })?;
+    summary.first_slot = header.first_slot;
+    summary.last_slot = header.last_slot;
+    Ok(summary)
+}
+
+impl Blockstore {
+    /// Writes the full slots in `starting_slot..=ending_slot` to an archive at `archive_path`,
+    /// with their transaction statuses, rewards, block times, block heights, bank hashes and
+    /// whether they are rooted. Slots that are incomplete or dead are skipped; the archive's
+    /// header records the first and last slot actually written. The archive is written to a
+    /// temporary file first, so a partial archive never appears at `archive_path`.
+    pub fn export_archive(
+        &self,
+        starting_slot: Slot,
+        ending_slot: Slot,
+        archive_path: &Path,
+    ) -> Result<ArchiveSummary> {
+        let mut slots = vec![];
+        for (slot, slot_meta) in self.slot_meta_iterator(starting_slot)? {
+            if slot > ending_slot {
+                break;
+            }
+            if !slot_meta.is_full() || self.is_dead(slot) {
+                warn!("Skipping slot {slot}, which is incomplete or dead");
+                continue;
+            }
+            slots.push((slot, slot_meta));
+        }
+        let (Some((first_slot, _)), Some((last_slot, _))) = (slots.first(), slots.last()) else {
+            return Err(BlockstoreArchiveError::Malformed(format!(
+                "no full slots in {starting_slot}..={ending_slot}"
+            )));
+        };
+        let mut summary = ArchiveSummary {
+            first_slot: *first_slot,
+            last_slot: *last_slot,
+            ..ArchiveSummary::default()
+        };
+
+        let mut tmp_path = archive_path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let write_archive = |summary: &mut ArchiveSummary| -> Result<()> {
+            let file = fs::File::create(&tmp_path)?;
+            let mut writer = ArchiveWriter::new(BufWriter::new(file))?;
+            writer.write_record(&ArchiveRecord::Header(ArchiveHeader {
+                first_slot: summary.first_slot,
+                last_slot: summary.last_slot,
+            }))?;
+            for (slot, slot_meta) in slots {
+                let slot_record = self.slot_record(slot, slot_meta)?;
+                summary.num_slots += 1;
+                summary.num_shreds += slot_record.data_shreds.len();
+                summary.num_transactions += slot_record.transactions.len();
+                writer.write_record(&ArchiveRecord::Slot(Box::new(slot_record)))?;
+            }
+            let file = writer
+                .finish(summary.num_slots as u64)?
+                .into_inner()
+                .map_err(|err| err.into_error())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, archive_path)?;
+            Ok(())
+        };
+        if let Err(err) = write_archive(&mut summary) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(err);
+        }
+        Ok(summary)
+    }
+
+    fn slot_record(&self, slot: Slot, slot_meta: SlotMeta) -> Result<SlotRecord> {
+        let data_shreds: Vec<Vec<u8>> = self
+            .slot_data_iterator(slot, 0)?
+            .map(|(_, shred)| shred.into_vec())
+            .collect();
+        let mut transactions = vec![];
+        let signatures = self
+            .get_slot_entries(slot, 0)?
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .map(|transaction| transaction.signatures[0]);
+        for (index, signature) in signatures.enumerate() {
+            let status = self
+                .read_transaction_status((signature, slot))?
+                .map(|status| generated::TransactionStatusMeta::from(status).encode_to_vec());
+            let memos = self.read_transaction_memos(signature, slot)?;
+            transactions.push(TransactionRecord {
+                index: u32::try_from(index)
+                    .map_err(|_| BlockstoreError::TransactionIndexOverflow)?,
+                signature,
+                status,
+                memos,
+            });
+        }
+        let rewards = self
+            .read_rewards(slot)?
+            .map(|rewards| generated::Rewards::from(rewards).encode_to_vec());
+        let bank_hash = self
+            .bank_hash_cf
+            .get(slot)?
+            .map(|versioned| (versioned.frozen_hash(), versioned.is_duplicate_confirmed()));
+
+        Ok(SlotRecord {
+            slot,
+            parent_slot: slot_meta.parent_slot,
+            is_root: self.is_root(slot),
+            bank_hash,
+            block_time: self.blocktime_cf.get(slot)?,
+            block_height: self.block_height_cf.get(slot)?,
+            rewards,
+            transactions,
+            data_shreds,
+        })
+    }
+
+    /// Imports an archive written by [`Blockstore::export_archive`]. The whole archive is
+    /// verified before anything is written: its checksum, its shreds and their signatures by
+    /// the leader `slot_leader` returns for their slot, and its bank hashes against the ones
+    /// already stored in this blockstore.
+    pub fn import_archive(
+        &self,
+        archive_path: &Path,
+        slot_leader: impl Fn(Slot) -> Option<Pubkey>,
+    ) -> Result<ArchiveSummary> {
+        read_archive(archive_path, |slot_record| {
+            verify_slot_record(&slot_record)?;
+            let leader = slot_leader(slot_record.slot)
+                .ok_or(BlockstoreArchiveError::UnknownLeader(slot_record.slot))?;
+            verify_shred_signatures(&slot_record, &leader)?;
+            if let (Some((archive, _)), Some(blockstore)) =
+                (slot_record.bank_hash, self.get_bank_hash(slot_record.slot))
+            {
+                if archive != blockstore {
+                    return Err(BlockstoreArchiveError::BankHashMismatch {
+                        slot: slot_record.slot,
+                        archive,
+                        blockstore,
+                    });
+                }
+            }
+            Ok(())
+        })?;
+
+        let mut summary = ArchiveSummary::default();
+        let mut roots = vec![];
+        let header = read_archive(archive_path, |slot_record| {
+            let slot = slot_record.slot;
+            summary.num_slots += 1;
+            summary.num_shreds += slot_record.data_shreds.len();
+            summary.num_transactions += slot_record.transactions.len();
+            if slot_record.is_root {
+                roots.push(slot);
+            }
+            self.import_slot_record(slot_record)
+        })?;
+        self.set_roots(roots.iter())?;
+
+        summary.first_slot = header.first_slot;
+        summary.last_slot = header.last_slot;
+        Ok(summary)
+    }
+
+    fn import_slot_record(&self, slot_record: SlotRecord) -> Result<()> {
+        let SlotRecord {
+            slot,
+            parent_slot: _,
+            is_root: _,
+            bank_hash,
+            block_time,
+            block_height,
+            rewards,
+            transactions,
+            data_shreds,
+        } = slot_record;
+
+        let shreds = data_shreds
+            .into_iter()
+            .map(|shred| {
+                Shred::new_from_serialized_shred(shred)
+                    .map_err(|err| BlockstoreArchiveError::InvalidShred(slot, err.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.insert_shreds(shreds, None, false)?;
+        if !self.is_full(slot) {
+            return Err(BlockstoreArchiveError::IncompleteSlot(slot));
+        }
+
+        // The statuses are only meaningful for the transactions the shreds actually hold
+        let entry_transactions: Vec<VersionedTransaction> = self
+            .get_slot_entries(slot, 0)?
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .collect();
+        if entry_transactions.len() != transactions.len() {
+            return Err(BlockstoreArchiveError::Malformed(format!(
+                "slot {slot} has {} transactions, archive lists {}",
+                entry_transactions.len(),
+                transactions.len()
+            )));
+        }
+        for (transaction, record) in entry_transactions.iter().zip(transactions) {
+            if transaction.signatures[0] != record.signature {
+                return Err(BlockstoreArchiveError::Malformed(format!(
+                    "unexpected transaction {} in slot {slot}",
+                    record.signature
+                )));
+            }
+            if let Some(status) = record.status {
+                let status = generated::TransactionStatusMeta::decode(status.as_slice())
+                    .map_err(|err| BlockstoreArchiveError::Malformed(err.to_string()))?;
+                let status = TransactionStatusMeta::try_from(status)
+                    .map_err(|err| BlockstoreArchiveError::Malformed(err.to_string()))?;
+                let (writable_keys, readonly_keys) =
+                    transaction_account_locks(transaction, &status.loaded_addresses)?;
+                self.write_transaction_status(
+                    slot,
+                    record.signature,
+                    writable_keys.iter().collect(),
+                    readonly_keys.iter().collect(),
+                    status.clone(),
+                    record.index as usize,
+                )?;
+            }
+            if let Some(memos) = record.memos {
+                self.write_transaction_memos(&record.signature, slot, memos)?;
+            }
+        }
+
+        if let Some(rewards) = rewards {
+            let rewards = generated::Rewards::decode(rewards.as_slice())
+                .map_err(|err| BlockstoreArchiveError::Malformed(err.to_string()))?;
+            self.write_rewards(slot, Vec::<Reward>::from(rewards))?;
+        }
+        if let Some(block_time) = block_time {
+            self.cache_block_time(slot, block_time)?;
+        }
+        if let Some(block_height) = block_height {
+            self.cache_block_height(slot, block_height)?;
+        }
+        if let Some((frozen_hash, is_duplicate_confirmed)) = bank_hash {
+            self.insert_bank_hash(slot, frozen_hash, is_duplicate_confirmed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::blockstore::tests::make_slot_entries_with_transactions,
+        assert_matches::assert_matches,
+        solana_entry::entry::next_entry_mut,
+        solana_sdk::{
+            instruction::CompiledInstruction,
+            message::{v0, MessageHeader, VersionedMessage},
+            sysvar,
+            transaction::TransactionError,
+        },
+        solana_transaction_status::RewardType,
+    };
+
+    fn insert_entries(
+        blockstore: &Blockstore,
+        slot: Slot,
+        parent_slot: Slot,
+        leader: &Keypair,
+        entries: &[Entry],
+    ) {
+        let (shreds, _) = Shredder::new(slot, parent_slot, 0, 0)
+            .unwrap()
+            .entries_to_shreds(
+                leader,
+                entries,
+                true, // is_last_in_slot
+                0,    // next_shred_index
+                0,    // next_code_index
+                true, // merkle_variant
+                &ReedSolomonCache::default(),
+                &mut ProcessShredsStats::default(),
+            );
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+    }
+
+    fn insert_slot(
+        blockstore: &Blockstore,
+        slot: Slot,
+        parent_slot: Slot,
+        leader: &Keypair,
+    ) -> Vec<Signature> {
+        let entries = make_slot_entries_with_transactions(4);
+        let signatures = entries
+            .iter()
+            .flat_map(|entry| &entry.transactions)
+            .map(|transaction| transaction.signatures[0])
+            .collect();
+        insert_entries(blockstore, slot, parent_slot, leader, &entries);
+        signatures
+    }
+
+    fn status(fee: u64) -> TransactionStatusMeta {
+        TransactionStatusMeta {
+            status: Err(TransactionError::AccountNotFound),
+            fee,
+            pre_balances: vec![1, 2],
+            post_balances: vec![3, 4],
+            log_messages: Some(vec!["log".to_string()]),
+            ..TransactionStatusMeta::default()
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let leader = Keypair::new();
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        let mut signatures = HashMap::new();
+        for (slot, parent_slot) in [(1, 0), (2, 1), (3, 2)] {
+            signatures.insert(slot, insert_slot(&source, slot, parent_slot, &leader));
+            for signature in &signatures[&slot] {
+                source
+                    .write_transaction_status(slot, *signature, vec![], vec![], status(slot), 0)
+                    .unwrap();
+            }
+            source
+                .write_transaction_memos(&signatures[&slot][0], slot, "memo".to_string())
+                .unwrap();
+            source.cache_block_time(slot, slot as i64 * 10).unwrap();
+            source.cache_block_height(slot, slot).unwrap();
+            source.insert_bank_hash(slot, Hash::new_unique(), false);
+        }
+        let rewards = vec![Reward {
+            pubkey: Pubkey::new_unique().to_string(),
+            lamports: 42,
+            post_balance: 43,
+            reward_type: Some(RewardType::Staking),
+            commission: Some(5),
+        }];
+        source.write_rewards(2, rewards.clone()).unwrap();
+        source.set_roots([1, 2].iter()).unwrap();
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("slots.archive");
+        let summary = source.export_archive(2, 3, &archive_path).unwrap();
+        assert_eq!(summary.num_slots, 2);
+        assert_eq!(summary.num_transactions, 8);
+        assert_eq!(verify_archive(&archive_path).unwrap(), summary);
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        assert_eq!(
+            target
+                .import_archive(&archive_path, |_| Some(leader.pubkey()))
+                .unwrap(),
+            summary
+        );
+
+        assert!(!target.is_full(1));
+        for slot in [2, 3] {
+            assert!(target.is_full(slot));
+            assert_eq!(
+                target.get_slot_entries(slot, 0).unwrap(),
+                source.get_slot_entries(slot, 0).unwrap()
+            );
+            for signature in &signatures[&slot] {
+                assert_eq!(
+                    target.read_transaction_status((*signature, slot)).unwrap(),
+                    Some(status(slot))
+                );
+            }
+            assert_eq!(
+                target
+                    .read_transaction_memos(signatures[&slot][0], slot)
+                    .unwrap(),
+                Some("memo".to_string())
+            );
+            assert_eq!(
+                target.blocktime_cf.get(slot).unwrap(),
+                Some(slot as i64 * 10)
+            );
+            assert_eq!(target.block_height_cf.get(slot).unwrap(), Some(slot));
+            assert_eq!(target.get_bank_hash(slot), source.get_bank_hash(slot));
+        }
+        assert_eq!(target.read_rewards(2).unwrap(), Some(rewards));
+        assert!(target.is_root(2));
+        assert!(!target.is_root(3));
+    }
+
+    #[test]
+    fn test_read_archive_rooted_shreds() {
+        let leader = Keypair::new();
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        for (slot, parent_slot) in [(1, 0), (2, 1), (3, 2), (4, 3)] {
+            insert_slot(&source, slot, parent_slot, &leader);
+        }
+        source.set_roots([1, 2, 3].iter()).unwrap();
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("slots.archive");
+        let summary = source.export_archive(1, 10, &archive_path).unwrap();
+        assert_eq!((summary.first_slot, summary.last_slot), (1, 4));
+        assert_eq!(read_archive_slot_range(&archive_path).unwrap(), (1, 4));
+        assert!(!archive_dir.path().join("slots.archive.tmp").exists());
+        assert_eq!(
+            read_archive_rooted_slots(&archive_path).unwrap(),
+            vec![1, 2, 3]
+        );
+
+        // Slot 4 is not rooted, and slot 5 is not in the archive.
+        let slots = HashSet::from([2, 3, 4, 5]);
+        let rooted_shreds = read_archive_rooted_shreds(&archive_path, &slots).unwrap();
+        assert_eq!(
+            rooted_shreds
+                .iter()
+                .map(|(slot, _)| *slot)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        for (slot, shreds) in rooted_shreds {
+            let expected: Vec<u64> = source
+                .slot_data_iterator(slot, 0)
+                .unwrap()
+                .map(|((_, index), _)| index)
+                .collect();
+            assert!(shreds.iter().all(|shred| shred.slot() == slot));
+            let indices: Vec<u64> = shreds
+                .iter()
+                .map(|shred| u64::from(shred.index()))
+                .collect();
+            assert_eq!(indices, expected);
+        }
+    }
+
+    #[test]
+    fn test_archive_corrupted() {
+        let leader = Keypair::new();
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        insert_slot(&source, 1, 0, &leader);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("slots.archive");
+        source.export_archive(0, 1, &archive_path).unwrap();
+
+        let mut bytes = fs::read(&archive_path).unwrap();
+        let len = bytes.len();
+        bytes[len / 2] ^= 0xff;
+        fs::write(&archive_path, &bytes).unwrap();
+        assert!(verify_archive(&archive_path).is_err());
+
+        bytes[0] = b'X';
+        fs::write(&archive_path, &bytes).unwrap();
+        assert_matches!(
+            verify_archive(&archive_path),
+            Err(BlockstoreArchiveError::InvalidMagic)
+        );
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        assert!(target
+            .import_archive(&archive_path, |_| Some(leader.pubkey()))
+            .is_err());
+        assert!(!target.is_full(1));
+    }
+
+    #[test]
+    fn test_archive_bank_hash_mismatch() {
+        let leader = Keypair::new();
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        insert_slot(&source, 1, 0, &leader);
+        let archive_hash = Hash::new_unique();
+        source.insert_bank_hash(1, archive_hash, false);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("slots.archive");
+        source.export_archive(0, 1, &archive_path).unwrap();
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        let blockstore_hash = Hash::new_unique();
+        target.insert_bank_hash(1, blockstore_hash, false);
+        assert_matches!(
+            target.import_archive(&archive_path, |_| Some(leader.pubkey())),
+            Err(BlockstoreArchiveError::BankHashMismatch { slot: 1, archive, blockstore })
+                if archive == archive_hash && blockstore == blockstore_hash
+        );
+        assert!(!target.is_full(1));
+    }
+
+    #[test]
+    fn test_archive_leader_signatures() {
+        let leader = Keypair::new();
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        insert_slot(&source, 1, 0, &leader);
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("slots.archive");
+        source.export_archive(0, 1, &archive_path).unwrap();
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        assert_matches!(
+            target.import_archive(&archive_path, |_| None),
+            Err(BlockstoreArchiveError::UnknownLeader(1))
+        );
+        let other_leader = Pubkey::new_unique();
+        assert_matches!(
+            target.import_archive(&archive_path, |_| Some(other_leader)),
+            Err(BlockstoreArchiveError::InvalidShredSignature { slot: 1, leader, .. })
+                if leader == other_leader
+        );
+        assert!(!target.is_full(1));
+
+        target
+            .import_archive(&archive_path, |slot| (slot == 1).then(|| leader.pubkey()))
+            .unwrap();
+        assert!(target.is_full(1));
+    }
+
+    #[test]
+    fn test_archive_address_signatures() {
+        let leader = Keypair::new();
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+
+        // Sysvars are never write locked, even if a lookup table loads them as writable
+        let payer = Keypair::new();
+        let message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer.pubkey(), Pubkey::new_unique()],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction::new(1, &(), vec![0, 2])],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        });
+        let transaction = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![sysvar::clock::id()],
+            readonly: vec![],
+        };
+        let signature = transaction.signatures[0];
+        let entries = vec![next_entry_mut(&mut Hash::default(), 0, vec![transaction])];
+        insert_entries(&source, 1, 0, &leader, &entries);
+        let status = TransactionStatusMeta {
+            loaded_addresses,
+            ..status(1)
+        };
+        source
+            .write_transaction_status(1, signature, vec![], vec![], status, 0)
+            .unwrap();
+
+        let archive_dir = tempfile::tempdir().unwrap();
+        let archive_path = archive_dir.path().join("slots.archive");
+        source.export_archive(0, 1, &archive_path).unwrap();
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        target
+            .import_archive(&archive_path, |_| Some(leader.pubkey()))
+            .unwrap();
+        let writeable = |address: Pubkey| {
+            target
+                .address_signatures_cf
+                .get((address, 1, 0, signature))
+                .unwrap()
+                .unwrap()
+                .writeable
+        };
+        assert!(writeable(payer.pubkey()));
+        assert!(!writeable(sysvar::clock::id()));
+    }
+}
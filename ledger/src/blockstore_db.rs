@@ -9,7 +9,8 @@ use {
             PERF_METRIC_OP_NAME_WRITE_BATCH,
         },
         blockstore_options::{
-            AccessType, BlockstoreOptions, LedgerColumnOptions, ShredStorageType,
+            AccessType, BlockstoreCompressionType, BlockstoreOptions, LedgerColumnOptions,
+            ShredStorageType, MAX_ZSTD_DICTIONARY_SIZE,
         },
     },
     bincode::{deserialize, serialize},
@@ -148,6 +149,8 @@ pub enum BlockstoreError {
     MissingTransactionMetadata,
     #[error("transaction-index overflow")]
     TransactionIndexOverflow,
+    #[error("zstd dictionary size {0} exceeds {MAX_ZSTD_DICTIONARY_SIZE} bytes")]
+    InvalidZstdDictionarySize(u32),
 }
 pub type Result<T> = std::result::Result<T, BlockstoreError>;
 
@@ -206,7 +209,8 @@ pub mod columns {
     /// The duplicate slots column
     ///
     /// * index type: `u64` (see [`SlotColumn`])
-    /// * value type: [`blockstore_meta::DuplicateSlotProof`]
+    /// * value type: [`blockstore_meta::DuplicateSlotProofV2`], or
+    ///   [`blockstore_meta::DuplicateSlotProof`] for proofs stored before it
     pub struct DuplicateSlots;
 
     #[derive(Debug)]
@@ -394,6 +398,16 @@ impl Rocks {
         let access_type = options.access_type.clone();
         let recovery_mode = options.recovery_mode.clone();
 
+        if let BlockstoreCompressionType::Zstd(zstd_options) =
+            &options.column_options.compression_type
+        {
+            if zstd_options.dictionary_sizes().is_none() {
+                return Err(BlockstoreError::InvalidZstdDictionarySize(
+                    zstd_options.max_dict_bytes,
+                ));
+            }
+        }
+
         fs::create_dir_all(path)?;
 
         // Use default database options
@@ -690,12 +704,27 @@ impl Rocks {
         }
     }
 
+    /// Retrieves the specified RocksDB string property of the current
+    /// column family.
+    fn get_string_property_cf(
+        &self,
+        cf: &ColumnFamily,
+        name: &'static std::ffi::CStr,
+    ) -> Result<Option<String>> {
+        Ok(self.db.property_value_cf(cf, name)?)
+    }
+
     fn live_files_metadata(&self) -> Result<Vec<LiveFile>> {
         match self.db.live_files() {
             Ok(live_files) => Ok(live_files),
             Err(e) => Err(BlockstoreError::RocksDb(e)),
         }
     }
+
+    fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()?;
+        Ok(())
+    }
 }
 
 pub trait Column {
@@ -1151,9 +1180,6 @@ impl SlotColumn for columns::DuplicateSlots {}
 impl ColumnName for columns::DuplicateSlots {
     const NAME: &'static str = DUPLICATE_SLOTS_CF;
 }
-impl TypedColumn for columns::DuplicateSlots {
-    type Type = blockstore_meta::DuplicateSlotProof;
-}
 
 impl SlotColumn for columns::Orphans {}
 impl ColumnName for columns::Orphans {
@@ -1234,6 +1260,53 @@ pub struct Database {
     column_options: Arc<LedgerColumnOptions>,
 }
 
+/// The size of a column family's SST files, on disk and before compression.
+/// Data that hasn't been flushed from memtables yet is not included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnSizeStats {
+    pub name: &'static str,
+    pub num_entries: u64,
+    // RocksDB's internal property key: "rocksdb.total-sst-files-size"
+    pub total_sst_files_size: u64,
+    // The remaining fields come from "rocksdb.aggregated-table-properties"
+    pub raw_key_size: u64,
+    pub raw_value_size: u64,
+    // The size of the data blocks, which are the only compressed part of
+    // an SST file
+    pub data_block_size: u64,
+}
+
+impl ColumnSizeStats {
+    fn new(name: &'static str, total_sst_files_size: u64, table_properties: &str) -> Self {
+        let table_properties: HashMap<&str, u64> = table_properties
+            .split(';')
+            .filter_map(|property| {
+                let (key, value) = property.split_once('=')?;
+                Some((key.trim(), value.trim().parse().ok()?))
+            })
+            .collect();
+        let property = |key| table_properties.get(key).copied().unwrap_or_default();
+        Self {
+            name,
+            num_entries: property("# entries"),
+            total_sst_files_size,
+            raw_key_size: property("raw key size"),
+            raw_value_size: property("raw value size"),
+            data_block_size: property("data block size"),
+        }
+    }
+
+    pub fn raw_size(&self) -> u64 {
+        self.raw_key_size.saturating_add(self.raw_value_size)
+    }
+
+    /// Returns how many times smaller the data blocks are than the data they
+    /// hold, or `None` if the column has no SST files
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.data_block_size > 0).then(|| self.raw_size() as f64 / self.data_block_size as f64)
+    }
+}
+
 #[derive(Debug)]
 pub struct LedgerColumn<C>
 where
@@ -1446,6 +1519,12 @@ impl Database {
         self.backend.live_files_metadata()
     }
 
+    /// Replays the primary's latest writes into a database opened with
+    /// `AccessType::Secondary`
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.backend.try_catch_up_with_primary()
+    }
+
     pub fn compact_range_cf<C: Column + ColumnName>(&self, from: &[u8], to: &[u8]) {
         let cf = self.cf_handle::<C>();
         self.backend.db.compact_range_cf(cf, Some(from), Some(to));
@@ -1568,6 +1647,19 @@ where
         self.backend.get_int_property_cf(self.handle(), name)
     }
 
+    pub fn size_stats(&self) -> Result<ColumnSizeStats> {
+        let total_sst_files_size = self.get_int_property(RocksProperties::TOTAL_SST_FILES_SIZE)?;
+        let table_properties = self
+            .backend
+            .get_string_property_cf(self.handle(), RocksProperties::AGGREGATED_TABLE_PROPERTIES)?
+            .unwrap_or_default();
+        Ok(ColumnSizeStats::new(
+            C::NAME,
+            u64::try_from(total_sst_files_size).unwrap_or_default(),
+            &table_properties,
+        ))
+    }
+
     pub fn delete(&self, key: C::Index) -> Result<()> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
             self.column_options.rocks_perf_sample_interval,
@@ -1962,6 +2054,16 @@ fn process_cf_options_advanced<C: 'static + Column + ColumnName>(
                 .compression_type
                 .to_rocksdb_compression_type(),
         );
+        if let BlockstoreCompressionType::Zstd(zstd_options) = &column_options.compression_type {
+            let (max_dict_bytes, max_train_dict_bytes) = zstd_options
+                .dictionary_sizes()
+                .expect("dictionary size is checked when opening the blockstore");
+            // The window bits and strategy are only used by zlib, these are RocksDB's defaults
+            cf_options.set_compression_options(-14, zstd_options.level, 0, max_dict_bytes);
+            if max_dict_bytes > 0 {
+                cf_options.set_zstd_max_train_dict_bytes(max_train_dict_bytes);
+            }
+        }
     }
 }
 
@@ -2209,6 +2311,32 @@ pub mod tests {
         assert!(!should_enable_cf_compaction("something else"));
     }
 
+    #[test]
+    fn test_column_size_stats() {
+        let table_properties = "# data blocks=2; # entries=100; # deletions=0; \
+            raw key size=1600; raw average key size=16; raw value size=6400; \
+            raw average value size=64; data block size=2000; index block size (user-key? 0, \
+            delta-value? 0)=50; filter block size=0; ";
+        let stats = ColumnSizeStats::new("test", 2100, table_properties);
+        assert_eq!(
+            stats,
+            ColumnSizeStats {
+                name: "test",
+                num_entries: 100,
+                total_sst_files_size: 2100,
+                raw_key_size: 1600,
+                raw_value_size: 6400,
+                data_block_size: 2000,
+            }
+        );
+        assert_eq!(stats.raw_size(), 8000);
+        assert_eq!(stats.compression_ratio(), Some(4.0));
+
+        let stats = ColumnSizeStats::new("empty", 0, "");
+        assert_eq!(stats.num_entries, 0);
+        assert_eq!(stats.compression_ratio(), None);
+    }
+
     impl<C> LedgerColumn<C>
     where
         C: ColumnIndexDeprecation + ProtobufColumn + ColumnName,
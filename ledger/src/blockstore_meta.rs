@@ -5,9 +5,11 @@ use {
     solana_sdk::{
         clock::{Slot, UnixTimestamp},
         hash::Hash,
+        pubkey::Pubkey,
     },
     std::{
         collections::BTreeSet,
+        io::ErrorKind,
         ops::{Range, RangeBounds},
     },
 };
@@ -146,6 +148,50 @@ pub struct DuplicateSlotProof {
     pub shred2: Vec<u8>,
 }
 
+/// A [`DuplicateSlotProof`] along with the slot leader, who signed both
+/// shreds, if it was known when the proof was stored. Proofs are stored as
+/// this version; proofs stored before it are [`DuplicateSlotProof`]s, whose
+/// encoding is a prefix of this one.
+#[derive(Deserialize, Serialize)]
+pub struct DuplicateSlotProofV2 {
+    #[serde(with = "serde_bytes")]
+    pub shred1: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub shred2: Vec<u8>,
+    pub leader: Option<Pubkey>,
+}
+
+impl From<DuplicateSlotProof> for DuplicateSlotProofV2 {
+    fn from(proof: DuplicateSlotProof) -> Self {
+        DuplicateSlotProofV2 {
+            shred1: proof.shred1,
+            shred2: proof.shred2,
+            leader: None,
+        }
+    }
+}
+
+/// Deserializes a stored duplicate slot proof of either version.
+pub(crate) fn deserialize_duplicate_slot_proof(
+    data: &[u8],
+) -> bincode::Result<DuplicateSlotProofV2> {
+    bincode::deserialize::<DuplicateSlotProofV2>(data).or_else(|err| {
+        match &*err {
+            bincode::ErrorKind::Io(io_err) if matches!(io_err.kind(), ErrorKind::UnexpectedEof) => {
+                // Not enough bytes to deserialize as `DuplicateSlotProofV2`.
+            }
+            _ => return Err(err),
+        }
+        let proof = bincode::deserialize::<DuplicateSlotProof>(data)?;
+        // A truncated `DuplicateSlotProofV2` starts with a `DuplicateSlotProof`
+        // too, but does not end with it.
+        if bincode::serialized_size(&proof)? != data.len() as u64 {
+            return Err(err);
+        }
+        Ok(proof.into())
+    })
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ErasureMetaStatus {
     CanRecover,
@@ -402,6 +448,16 @@ impl DuplicateSlotProof {
     }
 }
 
+impl DuplicateSlotProofV2 {
+    pub(crate) fn new(shred1: Vec<u8>, shred2: Vec<u8>, leader: Option<Pubkey>) -> Self {
+        DuplicateSlotProofV2 {
+            shred1,
+            shred2,
+            leader,
+        }
+    }
+}
+
 #[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct TransactionStatusIndexMeta {
     pub max_slot: Slot,
@@ -668,4 +724,36 @@ mod test {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_deserialize_duplicate_slot_proof() {
+        let legacy = DuplicateSlotProof::new(vec![1, 2, 3], vec![4, 5, 6]);
+        let legacy_bytes = bincode::serialize(&legacy).unwrap();
+        let proof = deserialize_duplicate_slot_proof(&legacy_bytes).unwrap();
+        assert_eq!(proof.shred1, legacy.shred1);
+        assert_eq!(proof.shred2, legacy.shred2);
+        assert_eq!(proof.leader, None);
+
+        for leader in [None, Some(Pubkey::new_unique())] {
+            let proof = DuplicateSlotProofV2::new(vec![1, 2, 3], vec![4, 5, 6], leader);
+            let bytes = bincode::serialize(&proof).unwrap();
+            // `DuplicateSlotProof` is a prefix of `DuplicateSlotProofV2`.
+            assert!(bytes.starts_with(&legacy_bytes));
+            let proof = deserialize_duplicate_slot_proof(&bytes).unwrap();
+            assert_eq!(proof.shred1, legacy.shred1);
+            assert_eq!(proof.shred2, legacy.shred2);
+            assert_eq!(proof.leader, leader);
+        }
+
+        // A proof with a truncated leader is not taken for a legacy one.
+        let proof =
+            DuplicateSlotProofV2::new(vec![1, 2, 3], vec![4, 5, 6], Some(Pubkey::new_unique()));
+        let bytes = bincode::serialize(&proof).unwrap();
+        assert!(deserialize_duplicate_slot_proof(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut invalid_tag = legacy_bytes.clone();
+        invalid_tag.push(2);
+        assert!(deserialize_duplicate_slot_proof(&invalid_tag).is_err());
+        assert!(deserialize_duplicate_slot_proof(&legacy_bytes[..legacy_bytes.len() - 1]).is_err());
+    }
 }
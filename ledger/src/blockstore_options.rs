@@ -119,6 +119,7 @@ impl LedgerColumnOptions {
             BlockstoreCompressionType::Snappy => "Snappy",
             BlockstoreCompressionType::Lz4 => "Lz4",
             BlockstoreCompressionType::Zlib => "Zlib",
+            BlockstoreCompressionType::Zstd(_) => "Zstd",
         }
     }
 }
@@ -248,6 +249,7 @@ pub enum BlockstoreCompressionType {
     Snappy,
     Lz4,
     Zlib,
+    Zstd(BlockstoreZstdOptions),
 }
 
 impl Default for BlockstoreCompressionType {
@@ -263,6 +265,50 @@ impl BlockstoreCompressionType {
             Self::Snappy => RocksCompressionType::Snappy,
             Self::Lz4 => RocksCompressionType::Lz4,
             Self::Zlib => RocksCompressionType::Zlib,
+            Self::Zstd(_) => RocksCompressionType::Zstd,
+        }
+    }
+}
+
+pub const DEFAULT_ZSTD_COMPRESSION_LEVEL: i32 = 3;
+// RocksDB trains the dictionary on samples of about this many times its size,
+// as zstd recommends
+const ZSTD_DICTIONARY_TRAINING_FACTOR: u32 = 100;
+// RocksDB takes the dictionary and training sample sizes as C ints
+pub const MAX_ZSTD_DICTIONARY_SIZE: u32 = i32::MAX as u32 / ZSTD_DICTIONARY_TRAINING_FACTOR;
+
+#[derive(Debug, Clone)]
+pub struct BlockstoreZstdOptions {
+    // The zstd compression level, from 1 (fastest) to 22 (smallest).
+    // Default: DEFAULT_ZSTD_COMPRESSION_LEVEL.
+    pub level: i32,
+    // The size of the dictionary that RocksDB trains from samples of each
+    // new SST file and stores along with it. Small values such as
+    // transaction status protobufs compress much better with a dictionary.
+    // Default: 0, which disables dictionary compression.
+    // At most MAX_ZSTD_DICTIONARY_SIZE.
+    pub max_dict_bytes: u32,
+}
+
+impl BlockstoreZstdOptions {
+    /// Returns the dictionary size and the size of the samples it is trained
+    /// on, or None if they don't fit in RocksDB's options
+    pub(crate) fn dictionary_sizes(&self) -> Option<(i32, i32)> {
+        let max_train_dict_bytes = self
+            .max_dict_bytes
+            .checked_mul(ZSTD_DICTIONARY_TRAINING_FACTOR)?;
+        Some((
+            i32::try_from(self.max_dict_bytes).ok()?,
+            i32::try_from(max_train_dict_bytes).ok()?,
+        ))
+    }
+}
+
+impl Default for BlockstoreZstdOptions {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_ZSTD_COMPRESSION_LEVEL,
+            max_dict_bytes: 0,
         }
     }
 }
@@ -274,6 +274,30 @@ impl ShredId {
     }
 }
 
+/// Proof that a merkle shred belongs to the erasure batch whose merkle root the
+/// slot leader signed. The leaf is the hash of the shred's payload past the
+/// signature and up to the embedded merkle proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerkleInclusionProof {
+    /// Index of the shred's leaf in the erasure batch.
+    pub index: usize,
+    pub leaf: Hash,
+    /// Sibling nodes on the path from the leaf to the root, truncated to
+    /// their first 20 bytes.
+    pub proof: Vec<[u8; 20]>,
+    pub root: Hash,
+}
+
+impl MerkleInclusionProof {
+    /// Returns true if the proof recovers its merkle root from its leaf, and
+    /// the root is signed by `pubkey`.
+    #[must_use]
+    pub fn verify(&self, signature: &Signature, pubkey: &Pubkey) -> bool {
+        merkle::verify_inclusion_proof(self)
+            && signature.verify(pubkey.as_ref(), self.root.as_ref())
+    }
+}
+
 /// Tuple which identifies erasure coding set that the shred belongs to.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub(crate) struct ErasureSetId(Slot, /*fec_set_index:*/ u32);
@@ -530,6 +554,19 @@ impl Shred {
         }
     }
 
+    /// Returns the proof that the shred belongs to the erasure batch whose
+    /// merkle root is signed. Fails for legacy shreds, which sign their
+    /// payload instead.
+    pub fn merkle_inclusion_proof(&self) -> Result<MerkleInclusionProof, Error> {
+        match self {
+            Self::ShredCode(ShredCode::Merkle(shred)) => shred.inclusion_proof(),
+            Self::ShredData(ShredData::Merkle(shred)) => shred.inclusion_proof(),
+            Self::ShredCode(ShredCode::Legacy(_)) | Self::ShredData(ShredData::Legacy(_)) => {
+                Err(Error::InvalidShredVariant)
+            }
+        }
+    }
+
     // Returns true if the erasure coding of the two shreds mismatch.
     pub(crate) fn erasure_mismatch(&self, other: &Self) -> Result<bool, Error> {
         match (self, other) {
@@ -9,9 +9,9 @@ use {
             traits::{
                 Shred as ShredTrait, ShredCode as ShredCodeTrait, ShredData as ShredDataTrait,
             },
-            CodingShredHeader, DataShredHeader, Error, ProcessShredsStats, ShredCommonHeader,
-            ShredFlags, ShredVariant, DATA_SHREDS_PER_FEC_BLOCK, SIZE_OF_CODING_SHRED_HEADERS,
-            SIZE_OF_DATA_SHRED_HEADERS, SIZE_OF_SIGNATURE,
+            CodingShredHeader, DataShredHeader, Error, MerkleInclusionProof, ProcessShredsStats,
+            ShredCommonHeader, ShredFlags, ShredVariant, DATA_SHREDS_PER_FEC_BLOCK,
+            SIZE_OF_CODING_SHRED_HEADERS, SIZE_OF_DATA_SHRED_HEADERS, SIZE_OF_SIGNATURE,
         },
         shredder::{self, ReedSolomonCache},
     },
@@ -175,6 +175,14 @@ impl ShredData {
         get_merkle_node(&self.payload, SIZE_OF_SIGNATURE..proof_offset)
     }
 
+    pub(super) fn inclusion_proof(&self) -> Result<MerkleInclusionProof, Error> {
+        make_inclusion_proof(
+            self.erasure_shard_index()?,
+            self.merkle_node()?,
+            self.merkle_proof()?,
+        )
+    }
+
     fn from_recovered_shard(signature: &Signature, mut shard: Vec<u8>) -> Result<Self, Error> {
         let shard_size = shard.len();
         if shard_size + SIZE_OF_SIGNATURE > Self::SIZE_OF_PAYLOAD {
@@ -287,6 +295,14 @@ impl ShredCode {
         get_merkle_node(&self.payload, SIZE_OF_SIGNATURE..proof_offset)
     }
 
+    pub(super) fn inclusion_proof(&self) -> Result<MerkleInclusionProof, Error> {
+        make_inclusion_proof(
+            self.erasure_shard_index()?,
+            self.merkle_node()?,
+            self.merkle_proof()?,
+        )
+    }
+
     fn from_recovered_shard(
         common_header: ShredCommonHeader,
         coding_header: CodingShredHeader,
@@ -565,6 +581,31 @@ where
         .ok_or(Error::InvalidMerkleProof)
 }
 
+fn make_inclusion_proof<'a, I>(
+    index: usize, // leaf index ~ shred's erasure shard index.
+    leaf: Hash,
+    proof: I,
+) -> Result<MerkleInclusionProof, Error>
+where
+    I: IntoIterator<Item = &'a MerkleProofEntry>,
+{
+    let proof: Vec<MerkleProofEntry> = proof.into_iter().copied().collect();
+    let root = get_merkle_root(index, leaf, &proof)?;
+    Ok(MerkleInclusionProof {
+        index,
+        leaf,
+        proof,
+        root,
+    })
+}
+
+pub(super) fn verify_inclusion_proof(proof: &MerkleInclusionProof) -> bool {
+    matches!(
+        get_merkle_root(proof.index, proof.leaf, &proof.proof),
+        Ok(root) if root == proof.root
+    )
+}
+
 fn get_merkle_proof(
     shred: &[u8],
     proof_offset: usize, // Where the merkle proof starts.
@@ -1414,6 +1455,17 @@ mod test {
             let key = ShredId::new(slot, index, shred_type);
             let merkle_root = shred.merkle_root().unwrap();
             assert!(signature.verify(pubkey.as_ref(), merkle_root.as_ref()));
+            // Verify the inclusion proof.
+            let proof = shred::Shred::from(shred.clone())
+                .merkle_inclusion_proof()
+                .unwrap();
+            assert_eq!(proof.leaf, shred.merkle_node().unwrap());
+            assert_eq!(proof.root, merkle_root);
+            assert!(proof.verify(&signature, &pubkey));
+            assert!(!proof.verify(&signature, &Pubkey::new_unique()));
+            let mut other = proof.clone();
+            other.leaf = Hash::new_unique();
+            assert!(!other.verify(&signature, &pubkey));
             // Verify shred::layout api.
             let shred = shred.payload();
             assert_eq!(shred::layout::get_signature(shred), Some(signature));
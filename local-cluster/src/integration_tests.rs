@@ -26,7 +26,7 @@ use {
     solana_ledger::{
         ancestor_iterator::AncestorIterator,
         blockstore::{Blockstore, PurgeType},
-        blockstore_meta::DuplicateSlotProof,
+        blockstore_meta::DuplicateSlotProofV2,
         blockstore_options::{AccessType, BlockstoreOptions},
         leader_schedule::{FixedSchedule, LeaderSchedule},
     },
@@ -155,7 +155,10 @@ pub fn wait_for_last_vote_in_tower_to_land_in_ledger(
 }
 
 /// Waits roughly 10 seconds for duplicate proof to appear in blockstore at `dup_slot`. Returns proof if found.
-pub fn wait_for_duplicate_proof(ledger_path: &Path, dup_slot: Slot) -> Option<DuplicateSlotProof> {
+pub fn wait_for_duplicate_proof(
+    ledger_path: &Path,
+    dup_slot: Slot,
+) -> Option<DuplicateSlotProofV2> {
     for _ in 0..10 {
         let duplicate_fork_validator_blockstore = open_blockstore(ledger_path);
         if let Some((found_dup_slot, found_duplicate_proof)) =
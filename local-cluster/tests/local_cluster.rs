@@ -84,6 +84,7 @@ use {
     },
     solana_vote::vote_parser,
     solana_vote_program::{vote_state::MAX_LOCKOUT_HISTORY, vote_transaction},
+    solana_wen_restart::wen_restart::{wen_restart_status, WenRestartStatus},
     std::{
         collections::{BTreeSet, HashMap, HashSet},
         fs,
@@ -2398,6 +2399,123 @@ fn test_hard_fork_with_gap_in_roots() {
     assert_eq!(&slots_b[slots_b.len() - roots_b.len()..].to_vec(), &roots_b);
 }
 
+#[test]
+#[serial]
+fn test_wen_restart() {
+    solana_logger::setup_with_default(RUST_LOG_FILTER);
+
+    // First set up the cluster with 2 nodes
+    let slots_per_epoch = 2048;
+    let node_stakes = vec![60 * DEFAULT_NODE_STAKE, 40 * DEFAULT_NODE_STAKE];
+    let validator_config = ValidatorConfig {
+        snapshot_config: LocalCluster::create_dummy_load_only_snapshot_config(),
+        ..ValidatorConfig::default_for_test()
+    };
+    let mut config = ClusterConfig {
+        cluster_lamports: DEFAULT_CLUSTER_LAMPORTS + node_stakes.iter().sum::<u64>(),
+        node_stakes: node_stakes.clone(),
+        validator_configs: make_identical_validator_configs(&validator_config, node_stakes.len()),
+        slots_per_epoch,
+        stakers_slot_offset: slots_per_epoch,
+        skip_warmup_slots: true,
+        ..ClusterConfig::default()
+    };
+    let mut cluster = LocalCluster::new(&mut config, SocketAddrSpace::Unspecified);
+    cluster.check_for_new_roots(16, "test_wen_restart", SocketAddrSpace::Unspecified);
+    let original_shred_version = cluster.entry_point_info.shred_version();
+
+    // Simulate an outage of the whole cluster
+    let entry_point_pubkey = *cluster.entry_point_info.pubkey();
+    let mut validators = cluster.get_node_pubkeys();
+    // The entry point restarts first so the other validators find it.
+    validators.sort_by_key(|pubkey| *pubkey != entry_point_pubkey);
+    let validator_infos: Vec<_> = validators
+        .iter()
+        .map(|pubkey| cluster.exit_node(pubkey))
+        .collect();
+
+    // Restart all validators in wen_restart mode, they exit once the restart snapshot is generated
+    let mut wen_restart_proto_paths = vec![];
+    for (pubkey, mut validator_info) in validators.iter().zip(validator_infos) {
+        let ledger_path = validator_info.info.ledger_path.clone();
+        let wen_restart_proto_path = ledger_path.join("wen_restart_progress.proto");
+        validator_info.config.wen_restart_proto_path = Some(wen_restart_proto_path.clone());
+        validator_info
+            .config
+            .snapshot_config
+            .incremental_snapshot_archives_dir = ledger_path;
+        wen_restart_proto_paths.push(wen_restart_proto_path);
+        cluster.restart_node(pubkey, validator_info, SocketAddrSpace::Unspecified);
+    }
+    let mut restart_slot = None;
+    for wen_restart_proto_path in &wen_restart_proto_paths {
+        let now = Instant::now();
+        let (slot, shred_version) = loop {
+            if let Ok(WenRestartStatus::WaitingForSupermajority {
+                slot,
+                shred_version,
+                ..
+            }) = wen_restart_status(wen_restart_proto_path)
+            {
+                break (slot, shred_version);
+            }
+            assert!(
+                now.elapsed() < Duration::from_secs(180),
+                "wen_restart did not generate the restart snapshot"
+            );
+            sleep(Duration::from_millis(100));
+        };
+        assert_ne!(shred_version, original_shred_version);
+        // All validators agree on the restart slot
+        assert_eq!(*restart_slot.get_or_insert(slot), slot);
+    }
+    info!("wen_restart snapshot generated at slot {restart_slot:?}");
+
+    // Restart all validators again with the same config, they wait for supermajority on the
+    // restart snapshot before resuming. Spawn threads because wait_for_supermajority blocks in
+    // Validator::new()!
+    let cluster = Arc::new(Mutex::new(cluster));
+    let restart_threads: Vec<_> = validators
+        .iter()
+        .map(|pubkey| {
+            let mut validator_info = cluster.lock().unwrap().exit_node(pubkey);
+            let restart_context = cluster
+                .lock()
+                .unwrap()
+                .create_restart_context(pubkey, &mut validator_info);
+            let cluster = cluster.clone();
+            let pubkey = *pubkey;
+            std::thread::spawn(move || {
+                let restarted_validator_info = LocalCluster::restart_node_with_context(
+                    validator_info,
+                    restart_context,
+                    SocketAddrSpace::Unspecified,
+                );
+                cluster
+                    .lock()
+                    .unwrap()
+                    .add_node(&pubkey, restarted_validator_info);
+            })
+        })
+        .collect();
+    for restart_thread in restart_threads {
+        restart_thread.join().unwrap();
+    }
+    for wen_restart_proto_path in &wen_restart_proto_paths {
+        assert_eq!(
+            wen_restart_status(wen_restart_proto_path).unwrap(),
+            WenRestartStatus::Done
+        );
+    }
+
+    // new slots should be rooted after the cluster relaunch
+    cluster.lock().unwrap().check_for_new_roots(
+        16,
+        "test_wen_restart",
+        SocketAddrSpace::Unspecified,
+    );
+}
+
 #[test]
 #[serial]
 fn test_restart_tower_rollback() {
@@ -694,16 +694,45 @@ macro_rules! with_mock_invoke_context {
 }
 
 pub fn mock_process_instruction<F: FnMut(&mut InvokeContext), G: FnMut(&mut InvokeContext)>(
+    loader_id: &Pubkey,
+    program_indices: Vec<IndexOfAccount>,
+    instruction_data: &[u8],
+    transaction_accounts: Vec<TransactionAccount>,
+    instruction_account_metas: Vec<AccountMeta>,
+    expected_result: Result<(), InstructionError>,
+    process_instruction: ProcessInstructionWithContext,
+    pre_adjustments: F,
+    post_adjustments: G,
+) -> Vec<AccountSharedData> {
+    let (result, transaction_accounts) = mock_process_instruction_unchecked(
+        loader_id,
+        program_indices,
+        instruction_data,
+        transaction_accounts,
+        instruction_account_metas,
+        process_instruction,
+        pre_adjustments,
+        post_adjustments,
+    );
+    assert_eq!(result, expected_result);
+    transaction_accounts
+}
+
+/// Same as [`mock_process_instruction`], but returns the result of the instruction instead of
+/// asserting it, for callers like fuzzers which can not know the expected result up front
+pub fn mock_process_instruction_unchecked<
+    F: FnMut(&mut InvokeContext),
+    G: FnMut(&mut InvokeContext),
+>(
     loader_id: &Pubkey,
     mut program_indices: Vec<IndexOfAccount>,
     instruction_data: &[u8],
     mut transaction_accounts: Vec<TransactionAccount>,
     instruction_account_metas: Vec<AccountMeta>,
-    expected_result: Result<(), InstructionError>,
     process_instruction: ProcessInstructionWithContext,
     mut pre_adjustments: F,
     mut post_adjustments: G,
-) -> Vec<AccountSharedData> {
+) -> (Result<(), InstructionError>, Vec<AccountSharedData>) {
     let mut instruction_accounts: Vec<InstructionAccount> =
         Vec::with_capacity(instruction_account_metas.len());
     for (instruction_account_index, account_meta) in instruction_account_metas.iter().enumerate() {
@@ -746,11 +775,10 @@ pub fn mock_process_instruction<F: FnMut(&mut InvokeContext), G: FnMut(&mut Invo
         &mut 0,
         &mut ExecuteTimings::default(),
     );
-    assert_eq!(result, expected_result);
     post_adjustments(&mut invoke_context);
     let mut transaction_accounts = transaction_context.deconstruct_without_keys().unwrap();
     transaction_accounts.pop();
-    transaction_accounts
+    (result, transaction_accounts)
 }
 
 #[cfg(test)]
@@ -42,6 +42,7 @@ use {
         signature::{Keypair, Signer},
         stable_layout::stable_instruction::StableInstruction,
         sysvar::{Sysvar, SysvarId},
+        transaction::VersionedTransaction,
     },
     solana_vote_program::vote_state::{self, VoteState, VoteStateVersions},
     std::{
@@ -67,9 +68,11 @@ pub use {
     solana_banks_interface::BanksTransactionResultWithMetadata,
     solana_program_runtime::invoke_context::InvokeContext,
     solana_sdk::transaction_context::IndexOfAccount,
+    transaction_trace::{Invocation, TransactionTrace},
 };
 
 pub mod programs;
+pub mod transaction_trace;
 
 /// Errors from the program test environment
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -392,6 +395,11 @@ impl solana_sdk::program_stubs::SyscallStubs for SyscallStubs {
         Some((*program_id, data.to_vec()))
     }
 
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let invoke_context = get_invoke_context();
+        stable_log::program_data(&invoke_context.get_log_collector(), fields);
+    }
+
     fn sol_set_return_data(&self, data: &[u8]) {
         let invoke_context = get_invoke_context();
         let transaction_context = &mut invoke_context.transaction_context;
@@ -707,6 +715,30 @@ impl ProgramTest {
         }
     }
 
+    /// Add a SBF program owned by loader-v4 to the test environment.
+    ///
+    /// `program_name` will also be used to locate the SBF shared object in the current or fixtures
+    /// directory. Without an `authority` the program is deployed finalized.
+    pub fn add_loader_v4_program(
+        &mut self,
+        program_name: &str,
+        program_id: Pubkey,
+        authority: Option<Pubkey>,
+    ) {
+        let program_file = find_file(&format!("{program_name}.so"))
+            .unwrap_or_else(|| panic!("Unable to locate program {program_name}"));
+        let data = read_file(&program_file);
+        info!(
+            "\"{}\" SBF program (loader-v4) from {}",
+            program_name,
+            program_file.display()
+        );
+        self.accounts.push((
+            program_id,
+            programs::loader_v4_program_account(&data, authority, &Rent::default()),
+        ));
+    }
+
     /// Add a builtin program to the test environment.
     ///
     /// Note that builtin programs are responsible for their own `stable_log` output.
@@ -927,6 +959,12 @@ pub trait ProgramTestBanksClientExt {
     async fn get_new_blockhash(&mut self, blockhash: &Hash) -> io::Result<(Hash, FeeCalculator)>;
     /// Get a new latest blockhash, similar in spirit to RpcClient::get_latest_blockhash()
     async fn get_new_latest_blockhash(&mut self, blockhash: &Hash) -> io::Result<Hash>;
+    /// Process a transaction and return its result along with every program invocation it
+    /// made, their compute units, logs, events and return data
+    async fn process_transaction_with_trace(
+        &mut self,
+        transaction: impl Into<VersionedTransaction> + Send,
+    ) -> Result<TransactionTrace, BanksClientError>;
 }
 
 #[async_trait]
@@ -982,6 +1020,18 @@ impl ProgramTestBanksClientExt for BanksClient {
             ),
         ))
     }
+
+    async fn process_transaction_with_trace(
+        &mut self,
+        transaction: impl Into<VersionedTransaction> + Send,
+    ) -> Result<TransactionTrace, BanksClientError> {
+        let transaction = transaction.into();
+        let message = transaction.message.clone();
+        let result = self
+            .process_transaction_with_inner_instructions(transaction)
+            .await?;
+        Ok(TransactionTrace::new(&message, result))
+    }
 }
 
 struct DroppableTask<T>(Arc<AtomicBool>, JoinHandle<T>);
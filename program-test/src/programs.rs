@@ -1,6 +1,7 @@
 use solana_sdk::{
     account::{Account, AccountSharedData},
     bpf_loader_upgradeable::UpgradeableLoaderState,
+    loader_v4::{self, LoaderV4State, LoaderV4Status},
     pubkey::Pubkey,
     rent::Rent,
 };
@@ -94,3 +95,32 @@ pub fn spl_programs(rent: &Rent) -> Vec<(Pubkey, AccountSharedData)> {
         })
         .collect()
 }
+
+/// Returns a deployed loader-v4 program account holding `elf`.
+///
+/// Without an `authority_address` the program is finalized and can not be modified.
+pub fn loader_v4_program_account(
+    elf: &[u8],
+    authority_address: Option<Pubkey>,
+    rent: &Rent,
+) -> AccountSharedData {
+    let status = if authority_address.is_some() {
+        LoaderV4Status::Deployed
+    } else {
+        LoaderV4Status::Finalized
+    };
+    // Mirrors the `#[repr(C)]` layout of `LoaderV4State`: slot, authority, status
+    let mut data = Vec::with_capacity(LoaderV4State::program_data_offset() + elf.len());
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(authority_address.unwrap_or_default().as_ref());
+    data.extend_from_slice(&(status as u64).to_le_bytes());
+    debug_assert_eq!(data.len(), LoaderV4State::program_data_offset());
+    data.extend_from_slice(elf);
+    AccountSharedData::from(Account {
+        lamports: rent.minimum_balance(data.len()).max(1),
+        data,
+        owner: loader_v4::id(),
+        executable: true,
+        rent_epoch: 0,
+    })
+}
@@ -0,0 +1,386 @@
+//! A structured view of a processed transaction, built from its logs and recorded inner
+//! instructions, with assertions for use in tests
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    solana_banks_interface::{
+        BanksTransactionResultWithInnerInstructions, InnerInstruction, TransactionMetadata,
+    },
+    solana_sdk::{
+        instruction::CompiledInstruction,
+        message::VersionedMessage,
+        pubkey::Pubkey,
+        transaction::{self, TransactionError},
+        transaction_context::TransactionReturnData,
+    },
+    std::{fmt::Write, str::FromStr},
+};
+
+/// A program invocation, either by one of the transaction's instructions or through CPI
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Invocation {
+    pub program_id: Pubkey,
+    /// Starts at 1 for the transaction's instructions
+    pub stack_height: usize,
+    /// `None` if the inner instruction wasn't recorded
+    pub instruction: Option<CompiledInstruction>,
+    /// Compute units consumed by the invocation, including the programs it invoked. Only
+    /// reported by SBF programs, native `processor!` programs have to be replaced by their SBF
+    /// build with `ProgramTest::prefer_bpf` to measure them.
+    pub compute_units_consumed: Option<u64>,
+    /// Messages logged by the program, without the "Program log: " prefix
+    pub logs: Vec<String>,
+    /// Data emitted by the program with `sol_log_data`, e.g. events
+    pub events: Vec<Vec<Vec<u8>>>,
+    pub return_data: Option<Vec<u8>>,
+    /// The error the invocation failed with, as logged
+    pub error: Option<String>,
+    pub inner_invocations: Vec<Invocation>,
+}
+
+impl Invocation {
+    fn new(program_id: Pubkey, stack_height: usize) -> Self {
+        Self {
+            program_id,
+            stack_height,
+            ..Self::default()
+        }
+    }
+
+    /// Returns this invocation followed by all the invocations it made, in invocation order
+    pub fn iter(&self) -> impl Iterator<Item = &Invocation> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let invocation = stack.pop()?;
+            stack.extend(invocation.inner_invocations.iter().rev());
+            Some(invocation)
+        })
+    }
+}
+
+/// The result of processing a transaction, with every program invocation it made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionTrace {
+    pub result: transaction::Result<()>,
+    /// `None` if the transaction was not executed, e.g. because it failed sanitization
+    pub compute_units_consumed: Option<u64>,
+    pub return_data: Option<TransactionReturnData>,
+    pub log_messages: Vec<String>,
+    /// One invocation per instruction that got executed before the transaction completed or
+    /// failed. Invocations that didn't fit into the truncated logs are missing.
+    pub invocations: Vec<Invocation>,
+}
+
+impl TransactionTrace {
+    pub fn new(
+        message: &VersionedMessage,
+        result: BanksTransactionResultWithInnerInstructions,
+    ) -> Self {
+        let BanksTransactionResultWithInnerInstructions {
+            result,
+            metadata,
+            inner_instructions,
+        } = result;
+        let Some(TransactionMetadata {
+            log_messages,
+            compute_units_consumed,
+            return_data,
+        }) = metadata
+        else {
+            return Self {
+                result,
+                compute_units_consumed: None,
+                return_data: None,
+                log_messages: vec![],
+                invocations: vec![],
+            };
+        };
+
+        let inner_instructions = inner_instructions.unwrap_or_default();
+        let mut invocations = parse_invocations(&log_messages);
+        for (index, invocation) in invocations.iter_mut().enumerate() {
+            invocation.instruction = message.instructions().get(index).cloned();
+            if let Some(inner_instructions) = inner_instructions.get(index) {
+                assign_inner_instructions(
+                    &mut invocation.inner_invocations,
+                    &mut inner_instructions.iter(),
+                );
+            }
+        }
+
+        Self {
+            result,
+            compute_units_consumed: Some(compute_units_consumed),
+            return_data,
+            log_messages,
+            invocations,
+        }
+    }
+
+    /// Returns all invocations, in invocation order
+    pub fn iter(&self) -> impl Iterator<Item = &Invocation> {
+        self.invocations.iter().flat_map(Invocation::iter)
+    }
+
+    /// Returns all invocations of `program_id`, in invocation order
+    pub fn invocations_of<'a>(
+        &'a self,
+        program_id: &'a Pubkey,
+    ) -> impl Iterator<Item = &'a Invocation> + 'a {
+        self.iter()
+            .filter(move |invocation| invocation.program_id == *program_id)
+    }
+
+    /// Returns the compute units consumed by each of the transaction's instructions
+    pub fn instruction_compute_units(&self) -> Vec<Option<u64>> {
+        self.invocations
+            .iter()
+            .map(|invocation| invocation.compute_units_consumed)
+            .collect()
+    }
+
+    fn panic_with_logs(&self, message: &str) -> ! {
+        panic!(
+            "{message}\ntransaction logs:\n  {}",
+            self.log_messages.join("\n  ")
+        );
+    }
+
+    /// Panics, printing the logs, unless the transaction succeeded
+    pub fn assert_success(&self) -> &Self {
+        if let Err(err) = &self.result {
+            self.panic_with_logs(&format!("transaction failed: {err}"));
+        }
+        self
+    }
+
+    /// Panics, printing the logs, unless the transaction failed with `error`
+    pub fn assert_error(&self, error: &TransactionError) -> &Self {
+        match &self.result {
+            Err(err) if err == error => self,
+            Err(err) => self.panic_with_logs(&format!(
+                "transaction failed with {err:?}, expected {error:?}"
+            )),
+            Ok(()) => self.panic_with_logs(&format!(
+                "transaction succeeded, expected it to fail with {error:?}"
+            )),
+        }
+    }
+
+    /// Panics unless the transaction consumed at most `budget` compute units
+    pub fn assert_compute_units_within(&self, budget: u64) -> &Self {
+        let Some(consumed) = self.compute_units_consumed else {
+            self.panic_with_logs(&format!(
+                "compute units not reported, the transaction wasn't executed: {:?}",
+                self.result
+            ));
+        };
+        if consumed > budget {
+            self.panic_with_logs(&format!(
+                "transaction consumed {consumed} compute units, exceeding its budget of {budget} \
+                 by {}",
+                consumed - budget
+            ));
+        }
+        self
+    }
+
+    /// Panics unless each of the transaction's instructions consumed at most the compute units
+    /// in the matching entry of `budgets`, listing every instruction against its budget. Also
+    /// panics if an instruction's program didn't report its compute units, see
+    /// [`Invocation::compute_units_consumed`].
+    pub fn assert_instruction_compute_units_within(&self, budgets: &[u64]) -> &Self {
+        if budgets.len() != self.invocations.len() {
+            self.panic_with_logs(&format!(
+                "{} budgets for {} executed instructions",
+                budgets.len(),
+                self.invocations.len()
+            ));
+        }
+        let mut report = String::new();
+        let mut failed = false;
+        for (index, (invocation, budget)) in self.invocations.iter().zip(budgets).enumerate() {
+            let Some(consumed) = invocation.compute_units_consumed else {
+                failed = true;
+                let _ = writeln!(
+                    report,
+                    "  instruction {index} ({}): ? of {budget}  <-- not reported, use \
+                     `ProgramTest::prefer_bpf` for native programs",
+                    invocation.program_id
+                );
+                continue;
+            };
+            let diff = i128::from(consumed) - i128::from(*budget);
+            let marker = if consumed > *budget {
+                failed = true;
+                "  <-- over budget"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                report,
+                "  instruction {index} ({}): {consumed} of {budget} ({diff:+}){marker}",
+                invocation.program_id
+            );
+        }
+        if failed {
+            self.panic_with_logs(&format!(
+                "compute unit budgets not met:\n{}",
+                report.trim_end()
+            ));
+        }
+        self
+    }
+
+    /// Panics unless some invocation logged `message`
+    pub fn assert_log(&self, message: &str) -> &Self {
+        if !self
+            .iter()
+            .any(|invocation| invocation.logs.iter().any(|log| log == message))
+        {
+            self.panic_with_logs(&format!("no program logged {message:?}"));
+        }
+        self
+    }
+
+    /// Panics unless `program_id` emitted `event` with `sol_log_data`
+    pub fn assert_event(&self, program_id: &Pubkey, event: &[&[u8]]) -> &Self {
+        if !self.invocations_of(program_id).any(|invocation| {
+            invocation
+                .events
+                .iter()
+                .any(|emitted| emitted.iter().map(Vec::as_slice).eq(event.iter().copied()))
+        }) {
+            self.panic_with_logs(&format!("{program_id} didn't emit {event:?}"));
+        }
+        self
+    }
+}
+
+// Inner instructions are recorded in invocation order, i.e. a pre-order traversal of the
+// invocation tree
+fn assign_inner_instructions<'a>(
+    invocations: &mut [Invocation],
+    inner_instructions: &mut impl Iterator<Item = &'a InnerInstruction>,
+) {
+    for invocation in invocations {
+        let Some(inner_instruction) = inner_instructions.next() else {
+            return;
+        };
+        if invocation.stack_height == usize::from(inner_instruction.stack_height) {
+            invocation.instruction = Some(inner_instruction.instruction.clone());
+        }
+        assign_inner_instructions(&mut invocation.inner_invocations, inner_instructions);
+    }
+}
+
+fn parse_invocations(log_messages: &[String]) -> Vec<Invocation> {
+    let mut invocations = vec![];
+    // Each open invocation with the number of its "invoke" lines that weren't closed yet.
+    // Native programs log their invocations themselves, on top of the runtime logging them,
+    // and the program-test CPI stub logs the callee at the caller's stack height, so a single
+    // invocation can be logged several times in a row.
+    let mut stack: Vec<(Invocation, usize)> = vec![];
+
+    fn finish(stack: &mut Vec<(Invocation, usize)>, invocations: &mut Vec<Invocation>) {
+        if let Some((invocation, _)) = stack.pop() {
+            match stack.last_mut() {
+                Some((parent, _)) => parent.inner_invocations.push(invocation),
+                None => invocations.push(invocation),
+            }
+        }
+    }
+
+    // Closes one "invoke" line of the innermost invocation of `program_id`, finishing the
+    // invocations it made that were left open, e.g. by a failed CPI
+    fn close(
+        stack: &mut Vec<(Invocation, usize)>,
+        invocations: &mut Vec<Invocation>,
+        program_id: &Pubkey,
+        error: Option<&str>,
+    ) {
+        let Some(position) = stack
+            .iter()
+            .rposition(|(invocation, _)| invocation.program_id == *program_id)
+        else {
+            return;
+        };
+        while stack.len() > position + 1 {
+            finish(stack, invocations);
+        }
+        let (invocation, open) = stack.last_mut().unwrap();
+        if invocation.error.is_none() {
+            invocation.error = error.map(str::to_string);
+        }
+        *open -= 1;
+        if *open == 0 {
+            finish(stack, invocations);
+        }
+    }
+
+    for log in log_messages {
+        if let Some(message) = log.strip_prefix("Program log: ") {
+            if let Some((invocation, _)) = stack.last_mut() {
+                invocation.logs.push(message.to_string());
+            }
+        } else if let Some(data) = log.strip_prefix("Program data: ") {
+            if let Some((invocation, _)) = stack.last_mut() {
+                invocation.events.push(
+                    data.split(' ')
+                        .filter_map(|field| BASE64_STANDARD.decode(field).ok())
+                        .collect(),
+                );
+            }
+        } else if let Some(return_data) = log.strip_prefix("Program return: ") {
+            let data = return_data
+                .split_once(' ')
+                .and_then(|(_, data)| BASE64_STANDARD.decode(data).ok());
+            if let Some((invocation, _)) = stack.last_mut() {
+                invocation.return_data = data;
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let Some((program_id, event)) = rest.split_once(' ') else {
+                continue;
+            };
+            let Ok(program_id) = Pubkey::from_str(program_id) else {
+                continue;
+            };
+            if let Some(stack_height) = event
+                .strip_prefix("invoke [")
+                .and_then(|event| event.strip_suffix(']'))
+                .and_then(|stack_height| stack_height.parse().ok())
+            {
+                match stack.last_mut() {
+                    Some((invocation, open))
+                        if invocation.program_id == program_id
+                            && (invocation.stack_height..=invocation.stack_height + 1)
+                                .contains(&stack_height)
+                            && invocation.logs.is_empty()
+                            && invocation.events.is_empty()
+                            && invocation.inner_invocations.is_empty() =>
+                    {
+                        invocation.stack_height = stack_height;
+                        *open += 1;
+                    }
+                    _ => stack.push((Invocation::new(program_id, stack_height), 1)),
+                }
+            } else if let Some(consumed) = event
+                .strip_prefix("consumed ")
+                .and_then(|event| event.split_once(' '))
+                .and_then(|(consumed, _)| consumed.parse().ok())
+            {
+                if let Some((invocation, _)) = stack.last_mut() {
+                    invocation.compute_units_consumed = Some(consumed);
+                }
+            } else if event == "success" {
+                close(&mut stack, &mut invocations, &program_id, None);
+            } else if let Some(error) = event.strip_prefix("failed: ") {
+                close(&mut stack, &mut invocations, &program_id, Some(error));
+            }
+        }
+    }
+    // Invocations cut off by log truncation or a failure outside of the program
+    while !stack.is_empty() {
+        finish(&mut stack, &mut invocations);
+    }
+    invocations
+}
@@ -0,0 +1,79 @@
+use {
+    solana_program_test::{programs::loader_v4_program_account, ProgramTest},
+    solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        loader_v4::{self, LoaderV4State, LoaderV4Status},
+        pubkey::Pubkey,
+        rent::Rent,
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+};
+
+// Exits with 42 when executed. Relative to the crate root, which is the current directory of
+// tests and one of the directories SBF programs are looked up in.
+const RODATA_SECTION_PROGRAM: &str = "../programs/loader-v4/test_elfs/out/rodata_section";
+
+#[tokio::test]
+async fn loader_v4_program_present() {
+    let elf = include_bytes!("../src/programs/spl_memo-3.0.0.so");
+    let upgradeable_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let finalized_id = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        upgradeable_id,
+        loader_v4_program_account(elf, Some(authority), &Rent::default()).into(),
+    );
+    program_test.add_account(
+        finalized_id,
+        loader_v4_program_account(elf, None, &Rent::default()).into(),
+    );
+    let (mut banks_client, _, _) = program_test.start().await;
+
+    let offset = LoaderV4State::program_data_offset();
+    for (program_id, authority, status) in [
+        (upgradeable_id, authority, LoaderV4Status::Deployed),
+        (finalized_id, Pubkey::default(), LoaderV4Status::Finalized),
+    ] {
+        let account = banks_client.get_account(program_id).await.unwrap().unwrap();
+        assert_eq!(account.owner, loader_v4::id());
+        assert!(account.executable);
+        assert_eq!(&account.data[8..40], authority.as_ref());
+        assert_eq!(account.data[40..offset], (status as u64).to_le_bytes());
+        assert_eq!(&account.data[offset..], elf);
+    }
+}
+
+#[tokio::test]
+async fn loader_v4_program_executes() {
+    let deployed_id = Pubkey::new_unique();
+    let finalized_id = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_loader_v4_program(
+        RODATA_SECTION_PROGRAM,
+        deployed_id,
+        Some(Pubkey::new_unique()),
+    );
+    program_test.add_loader_v4_program(RODATA_SECTION_PROGRAM, finalized_id, None);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    for program_id in [deployed_id, finalized_id] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(program_id, &[], vec![])],
+            Some(&payer.pubkey()),
+            &[&payer],
+            recent_blockhash,
+        );
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(42))
+        );
+    }
+}
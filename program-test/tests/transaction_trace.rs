@@ -0,0 +1,241 @@
+use {
+    solana_program_test::{processor, ProgramTest, ProgramTestBanksClientExt},
+    solana_sdk::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        log::sol_log_data,
+        msg,
+        program::{invoke, set_return_data},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    std::str::FromStr,
+};
+
+fn caller_process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    msg!("caller");
+    sol_log_data(&[b"event", input]);
+    invoke(
+        &Instruction {
+            program_id: *accounts[0].key,
+            accounts: vec![],
+            data: input.to_vec(),
+        },
+        &[accounts[0].clone()],
+    )
+}
+
+fn callee_process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    msg!("callee");
+    if input.is_empty() {
+        return Err(ProgramError::Custom(42));
+    }
+    set_return_data(input);
+    Ok(())
+}
+
+async fn setup() -> (
+    solana_program_test::ProgramTestContext,
+    Pubkey, // caller
+    Pubkey, // callee
+) {
+    let caller_program_id = Pubkey::new_unique();
+    let callee_program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "caller",
+        caller_program_id,
+        processor!(caller_process_instruction),
+    );
+    program_test.add_program(
+        "callee",
+        callee_program_id,
+        processor!(callee_process_instruction),
+    );
+    let context = program_test.start_with_context().await;
+    (context, caller_program_id, callee_program_id)
+}
+
+#[tokio::test]
+async fn transaction_trace() {
+    let (mut context, caller_program_id, callee_program_id) = setup().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: caller_program_id,
+            accounts: vec![AccountMeta::new_readonly(callee_program_id, false)],
+            data: vec![1, 2, 3],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let trace = context
+        .banks_client
+        .process_transaction_with_trace(transaction)
+        .await
+        .unwrap();
+    trace
+        .assert_success()
+        .assert_log("caller")
+        .assert_log("callee")
+        .assert_event(&caller_program_id, &[b"event", &[1, 2, 3]]);
+
+    assert_eq!(trace.invocations.len(), 1);
+    let caller = &trace.invocations[0];
+    assert_eq!(caller.program_id, caller_program_id);
+    assert_eq!(caller.stack_height, 1);
+    assert_eq!(caller.instruction.as_ref().unwrap().data, vec![1, 2, 3]);
+    assert_eq!(caller.logs, vec!["caller".to_string()]);
+
+    assert_eq!(caller.inner_invocations.len(), 1);
+    let callee = &caller.inner_invocations[0];
+    assert_eq!(callee.program_id, callee_program_id);
+    assert_eq!(callee.stack_height, 2);
+    assert_eq!(callee.instruction.as_ref().unwrap().data, vec![1, 2, 3]);
+    assert_eq!(callee.logs, vec!["callee".to_string()]);
+    assert_eq!(callee.error, None);
+
+    assert_eq!(trace.invocations_of(&callee_program_id).count(), 1);
+    let return_data = trace.return_data.as_ref().unwrap();
+    assert_eq!(return_data.program_id, callee_program_id);
+    assert_eq!(return_data.data, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn transaction_trace_failure() {
+    let (mut context, caller_program_id, callee_program_id) = setup().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: caller_program_id,
+            accounts: vec![AccountMeta::new_readonly(callee_program_id, false)],
+            data: vec![],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let trace = context
+        .banks_client
+        .process_transaction_with_trace(transaction)
+        .await
+        .unwrap();
+    trace.assert_error(&TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(42),
+    ));
+
+    let callee = &trace.invocations[0].inner_invocations[0];
+    assert_eq!(callee.program_id, callee_program_id);
+    assert_eq!(callee.error.as_deref(), Some("custom program error: 0x2a"));
+    assert!(trace.invocations[0].error.is_some());
+}
+
+#[tokio::test]
+#[should_panic(expected = "transaction failed")]
+async fn transaction_trace_assert_success() {
+    let (mut context, caller_program_id, callee_program_id) = setup().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: caller_program_id,
+            accounts: vec![AccountMeta::new_readonly(callee_program_id, false)],
+            data: vec![],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction_with_trace(transaction)
+        .await
+        .unwrap()
+        .assert_success();
+}
+
+// A built SBF program, which unlike native `processor!` programs reports its compute units
+fn memo_program_id() -> Pubkey {
+    Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap()
+}
+
+async fn memo_trace() -> solana_program_test::TransactionTrace {
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(true);
+    let mut context = program_test.start_with_context().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: memo_program_id(),
+            accounts: vec![],
+            data: b"hello".to_vec(),
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction_with_trace(transaction)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn transaction_trace_compute_units() {
+    let trace = memo_trace().await;
+    trace.assert_success();
+    assert_eq!(trace.invocations[0].program_id, memo_program_id());
+    let consumed = trace.instruction_compute_units()[0].unwrap();
+    assert!(consumed > 0);
+    assert!(trace.compute_units_consumed.unwrap() >= consumed);
+    trace
+        .assert_compute_units_within(trace.compute_units_consumed.unwrap())
+        .assert_instruction_compute_units_within(&[consumed]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "over budget")]
+async fn transaction_trace_instruction_compute_units_exceeded() {
+    let trace = memo_trace().await;
+    let consumed = trace.instruction_compute_units()[0].unwrap();
+    trace.assert_instruction_compute_units_within(&[consumed - 1]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "exceeding its budget")]
+async fn transaction_trace_compute_units_exceeded() {
+    let trace = memo_trace().await;
+    let consumed = trace.compute_units_consumed.unwrap();
+    trace.assert_compute_units_within(consumed - 1);
+}
+
+#[tokio::test]
+#[should_panic(expected = "not reported")]
+async fn transaction_trace_compute_units_not_reported() {
+    let (mut context, caller_program_id, callee_program_id) = setup().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: caller_program_id,
+            accounts: vec![AccountMeta::new_readonly(callee_program_id, false)],
+            data: vec![1, 2, 3],
+        }],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction_with_trace(transaction)
+        .await
+        .unwrap()
+        .assert_success()
+        .assert_instruction_compute_units_within(&[u64::MAX]);
+}
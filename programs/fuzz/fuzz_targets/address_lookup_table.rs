@@ -0,0 +1,13 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    solana_builtin_programs_fuzz::{
+        address_lookup_table::{self, AddressLookupTableInstruction},
+        run, FuzzInput,
+    },
+};
+
+fuzz_target!(|input: FuzzInput<AddressLookupTableInstruction>| {
+    run(&address_lookup_table::builtin(), &input);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    solana_builtin_programs_fuzz::{
+        run,
+        stake::{self, StakeInstruction},
+        FuzzInput,
+    },
+};
+
+fuzz_target!(|input: FuzzInput<StakeInstruction>| {
+    run(&stake::builtin(), &input);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    solana_builtin_programs_fuzz::{
+        run,
+        system::{self, SystemInstruction},
+        FuzzInput,
+    },
+};
+
+fuzz_target!(|input: FuzzInput<SystemInstruction>| {
+    run(&system::builtin(), &input);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    solana_builtin_programs_fuzz::{
+        run,
+        vote::{self, VoteInstruction},
+        FuzzInput,
+    },
+};
+
+fuzz_target!(|input: FuzzInput<VoteInstruction>| {
+    run(&vote::builtin(), &input);
+});
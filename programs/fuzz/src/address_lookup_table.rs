@@ -0,0 +1,133 @@
+//! Instructions of the address lookup table program
+
+use {
+    crate::{Builtin, Key, SLOT_HASHES_LEN},
+    arbitrary::Arbitrary,
+    solana_sdk::{
+        address_lookup_table::{
+            instruction::{
+                close_lookup_table, create_lookup_table, create_lookup_table_signed,
+                deactivate_lookup_table, derive_lookup_table_address, extend_lookup_table,
+                freeze_lookup_table,
+            },
+            program::id,
+            state::LOOKUP_TABLE_META_SIZE,
+        },
+        clock::{Clock, Slot},
+        instruction::Instruction,
+        pubkey::Pubkey,
+    },
+};
+
+/// Slot used to derive a table address, as the distance to the current slot
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub struct RecentSlot(u8);
+
+impl RecentSlot {
+    fn slot(self, clock: &Clock) -> Slot {
+        // Mostly slots present in the `SlotHashes` sysvar, sometimes older ones
+        clock
+            .slot
+            .saturating_sub(1 + u64::from(self.0) % SLOT_HASHES_LEN.saturating_mul(2))
+    }
+}
+
+/// Address of a lookup table, either derived like `create_lookup_table()` does or from the pool
+#[derive(Arbitrary, Debug)]
+pub enum Table {
+    Derived {
+        authority: Key,
+        recent_slot: RecentSlot,
+    },
+    Pool(Key),
+}
+
+impl Table {
+    fn pubkey(&self, clock: &Clock) -> Pubkey {
+        match self {
+            Table::Derived {
+                authority,
+                recent_slot,
+            } => derive_lookup_table_address(&authority.pubkey(), recent_slot.slot(clock)).0,
+            Table::Pool(key) => key.pubkey(),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum AddressLookupTableInstruction {
+    CreateLookupTable {
+        authority: Key,
+        payer: Key,
+        recent_slot: RecentSlot,
+        signed: bool,
+    },
+    FreezeLookupTable {
+        table: Table,
+        authority: Key,
+    },
+    ExtendLookupTable {
+        table: Table,
+        authority: Key,
+        payer: Option<Key>,
+        new_addresses: Vec<Key>,
+    },
+    DeactivateLookupTable {
+        table: Table,
+        authority: Key,
+    },
+    CloseLookupTable {
+        table: Table,
+        authority: Key,
+        recipient: Key,
+    },
+}
+
+pub fn builtin() -> Builtin<AddressLookupTableInstruction> {
+    Builtin {
+        program_id: id(),
+        state_size: LOOKUP_TABLE_META_SIZE,
+        check_rent_state: true,
+        build_instruction,
+    }
+}
+
+fn build_instruction(instruction: &AddressLookupTableInstruction, clock: &Clock) -> Instruction {
+    match instruction {
+        AddressLookupTableInstruction::CreateLookupTable {
+            authority,
+            payer,
+            recent_slot,
+            signed,
+        } => {
+            let create = if *signed {
+                create_lookup_table_signed
+            } else {
+                create_lookup_table
+            };
+            create(authority.pubkey(), payer.pubkey(), recent_slot.slot(clock)).0
+        }
+        AddressLookupTableInstruction::FreezeLookupTable { table, authority } => {
+            freeze_lookup_table(table.pubkey(clock), authority.pubkey())
+        }
+        AddressLookupTableInstruction::ExtendLookupTable {
+            table,
+            authority,
+            payer,
+            new_addresses,
+        } => extend_lookup_table(
+            table.pubkey(clock),
+            authority.pubkey(),
+            payer.map(Key::pubkey),
+            new_addresses.iter().copied().map(Key::pubkey).collect(),
+        ),
+        AddressLookupTableInstruction::DeactivateLookupTable { table, authority } => {
+            deactivate_lookup_table(table.pubkey(clock), authority.pubkey())
+        }
+        AddressLookupTableInstruction::CloseLookupTable {
+            table,
+            authority,
+            recipient,
+        } => close_lookup_table(table.pubkey(clock), authority.pubkey(), recipient.pubkey()),
+    }
+}
@@ -0,0 +1,520 @@
+//! Structure-aware fuzzing harness for the builtin programs.
+//!
+//! A fuzz input is a small pool of accounts and a sequence of instructions built with each
+//! program's own instruction constructors over addresses from that pool. Instructions may be
+//! mutated (raw data, account metas) before they are processed, and the writes of every
+//! successful instruction are kept, so that later instructions run against the resulting state.
+//! Invariants are checked after every instruction and violations panic, which libFuzzer reports
+//! as a crash.
+//!
+//! Targets are run from this directory with `cargo +nightly fuzz run <target>`, where `<target>`
+//! is one of `system`, `stake`, `vote` or `address_lookup_table`. Crashing inputs are written to
+//! `artifacts/<target>/` and can be replayed with `cargo +nightly fuzz run <target> <artifact>`.
+
+pub mod address_lookup_table;
+pub mod stake;
+pub mod system;
+pub mod vote;
+
+use {
+    arbitrary::Arbitrary,
+    solana_program_runtime::{
+        invoke_context::{mock_process_instruction_unchecked, ProcessInstructionWithContext},
+        loaded_programs::{LoadedProgram, LoadedProgramsForTxBatch},
+    },
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        address_lookup_table,
+        clock::{Clock, Slot, UnixTimestamp},
+        epoch_schedule::EpochSchedule,
+        hash::{hashv, Hash},
+        instruction::{AccountMeta, Instruction},
+        native_loader,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        recent_blockhashes_account,
+        rent::Rent,
+        slot_hashes::SlotHashes,
+        stake,
+        stake_history::StakeHistory,
+        system_instruction::MAX_PERMITTED_DATA_LENGTH,
+        system_program,
+        sysvar::{self, recent_blockhashes::IterItem},
+        vote,
+    },
+    std::{collections::BTreeMap, sync::Arc},
+};
+
+/// Number of addresses instructions choose their accounts from
+pub const POOL_SIZE: usize = 8;
+/// Upper bound of an account's initial balance, which keeps balances far from overflowing
+const MAX_INITIAL_LAMPORTS: u64 = 1_000_000 * LAMPORTS_PER_SOL;
+/// Number of slots before the current one which are present in the `SlotHashes` sysvar
+pub(crate) const SLOT_HASHES_LEN: u64 = 32;
+/// Number of entries in the `RecentBlockhashes` sysvar
+const RECENT_BLOCKHASHES_LEN: u64 = 8;
+
+thread_local! {
+    /// Every builtin the harness knows about, so that instructions can invoke each other.
+    /// The invoke context only borrows the cache, so it is leaked once per thread.
+    static PROGRAM_CACHE: &'static LoadedProgramsForTxBatch = Box::leak(Box::new(program_cache()));
+}
+
+fn builtins() -> [(Pubkey, ProcessInstructionWithContext); 4] {
+    [
+        (
+            system_program::id(),
+            solana_system_program::system_processor::process_instruction,
+        ),
+        (
+            stake::program::id(),
+            solana_stake_program::stake_instruction::process_instruction,
+        ),
+        (
+            vote::program::id(),
+            solana_vote_program::vote_processor::process_instruction,
+        ),
+        (
+            address_lookup_table::program::id(),
+            solana_address_lookup_table_program::processor::process_instruction,
+        ),
+    ]
+}
+
+fn program_cache() -> LoadedProgramsForTxBatch {
+    let mut cache = LoadedProgramsForTxBatch::default();
+    for (program_id, process_instruction) in builtins() {
+        cache.replenish(
+            program_id,
+            Arc::new(LoadedProgram::new_builtin(0, 0, process_instruction)),
+        );
+    }
+    cache
+}
+
+/// A builtin program under test
+pub struct Builtin<I> {
+    pub program_id: Pubkey,
+    /// Size of the program's account state, used by [`AccountData::ProgramState`]
+    pub state_size: usize,
+    /// Whether accounts owned by the program must follow the runtime's rent state transitions.
+    /// Only the runtime enforces those, so programs which move lamports out of accounts they do
+    /// not own (e.g. the system program) can not be held to them.
+    pub check_rent_state: bool,
+    /// Builds the instruction to process from its fuzzed description
+    pub build_instruction: fn(&I, &Clock) -> Instruction,
+}
+
+/// Index into the pool of addresses
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub struct Key(u8);
+
+impl Key {
+    pub fn pubkey(self) -> Pubkey {
+        pool_pubkey(usize::from(self.0) % POOL_SIZE)
+    }
+}
+
+fn pool_pubkey(index: usize) -> Pubkey {
+    Pubkey::new_from_array([index as u8 + 1; 32])
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum AccountData {
+    Empty,
+    /// Zeroed data of the size of the program's account state
+    ProgramState,
+    Bytes(Vec<u8>),
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct FuzzAccount {
+    pub lamports: u64,
+    pub data: AccountData,
+    /// Owned by the program under test if set, by the system program otherwise
+    pub owned_by_program: bool,
+}
+
+impl FuzzAccount {
+    fn to_account_shared_data<I>(&self, builtin: &Builtin<I>) -> AccountSharedData {
+        let data = match &self.data {
+            AccountData::Empty => Vec::new(),
+            AccountData::ProgramState => vec![0; builtin.state_size],
+            AccountData::Bytes(bytes) => bytes.clone(),
+        };
+        let owner = if self.owned_by_program {
+            builtin.program_id
+        } else {
+            system_program::id()
+        };
+        AccountSharedData::from(solana_sdk::account::Account {
+            lamports: self.lamports % MAX_INITIAL_LAMPORTS.saturating_add(1),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+}
+
+/// Changes applied to a well-formed instruction before it is processed
+#[derive(Arbitrary, Debug)]
+pub enum Mutation {
+    None,
+    ReplaceData(Vec<u8>),
+    TruncateData(u8),
+    ToggleSigner(u8),
+    ToggleWritable(u8),
+    ReplaceAccount { index: u8, key: Key },
+    RemoveAccount(u8),
+}
+
+impl Mutation {
+    fn apply(&self, instruction: &mut Instruction) {
+        match self {
+            Mutation::None => {}
+            Mutation::ReplaceData(data) => instruction.data = data.clone(),
+            Mutation::TruncateData(len) => instruction.data.truncate(usize::from(*len)),
+            Mutation::ToggleSigner(index) => {
+                if let Some(meta) = nth_account_mut(&mut instruction.accounts, *index) {
+                    meta.is_signer = !meta.is_signer;
+                }
+            }
+            Mutation::ToggleWritable(index) => {
+                if let Some(meta) = nth_account_mut(&mut instruction.accounts, *index) {
+                    meta.is_writable = !meta.is_writable;
+                }
+            }
+            Mutation::ReplaceAccount { index, key } => {
+                if let Some(meta) = nth_account_mut(&mut instruction.accounts, *index) {
+                    meta.pubkey = key.pubkey();
+                }
+            }
+            Mutation::RemoveAccount(index) => {
+                if !instruction.accounts.is_empty() {
+                    let index = usize::from(*index) % instruction.accounts.len();
+                    instruction.accounts.remove(index);
+                }
+            }
+        }
+    }
+}
+
+fn nth_account_mut(accounts: &mut [AccountMeta], index: u8) -> Option<&mut AccountMeta> {
+    if accounts.is_empty() {
+        return None;
+    }
+    let index = usize::from(index) % accounts.len();
+    accounts.get_mut(index)
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct FuzzInstruction<I> {
+    pub instruction: I,
+    pub mutation: Mutation,
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct FuzzInput<I> {
+    pub accounts: [FuzzAccount; POOL_SIZE],
+    pub slot: u32,
+    pub unix_timestamp: UnixTimestamp,
+    pub instructions: Vec<FuzzInstruction<I>>,
+}
+
+/// Processes the instructions of `input` in sequence and checks the invariants after each one
+pub fn run<I>(builtin: &Builtin<I>, input: &FuzzInput<I>) {
+    let clock = clock(input);
+    let environment = environment_accounts(builtin, &clock);
+    let mut accounts: BTreeMap<Pubkey, AccountSharedData> = input
+        .accounts
+        .iter()
+        .enumerate()
+        .map(|(index, account)| (pool_pubkey(index), account.to_account_shared_data(builtin)))
+        .collect();
+    for fuzz_instruction in &input.instructions {
+        let mut instruction = (builtin.build_instruction)(&fuzz_instruction.instruction, &clock);
+        fuzz_instruction.mutation.apply(&mut instruction);
+        process_instruction(builtin, &instruction, &environment, &mut accounts);
+    }
+}
+
+fn clock<I>(input: &FuzzInput<I>) -> Clock {
+    let epoch_schedule = EpochSchedule::default();
+    let slot = u64::from(input.slot).saturating_add(SLOT_HASHES_LEN);
+    Clock {
+        slot,
+        epoch_start_timestamp: input.unix_timestamp,
+        epoch: epoch_schedule.get_epoch(slot),
+        leader_schedule_epoch: epoch_schedule.get_leader_schedule_epoch(slot),
+        unix_timestamp: input.unix_timestamp,
+    }
+}
+
+pub(crate) fn slot_hash(slot: Slot) -> Hash {
+    hashv(&[&slot.to_le_bytes()])
+}
+
+/// Sysvars, configuration and program accounts, which are never written back
+#[allow(deprecated)]
+fn environment_accounts<I>(
+    builtin: &Builtin<I>,
+    clock: &Clock,
+) -> Vec<(Pubkey, AccountSharedData)> {
+    let slot_hashes = SlotHashes::new(
+        &(clock.slot.saturating_sub(SLOT_HASHES_LEN)..clock.slot)
+            .map(|slot| (slot, slot_hash(slot)))
+            .collect::<Vec<_>>(),
+    );
+    let blockhashes = (0..RECENT_BLOCKHASHES_LEN)
+        .map(|index| slot_hash(clock.slot.saturating_sub(index)))
+        .collect::<Vec<_>>();
+    let recent_blockhashes = recent_blockhashes_account::create_account_with_data_for_test(
+        blockhashes
+            .iter()
+            .enumerate()
+            .map(|(index, hash)| IterItem(index as u64, hash, 5_000)),
+    );
+    let stake_config =
+        solana_stake_program::config::create_account(1, &stake::config::Config::default());
+
+    let mut accounts = vec![
+        (
+            sysvar::clock::id(),
+            sysvar::create_account_shared_data_for_test(clock),
+        ),
+        (
+            sysvar::rent::id(),
+            sysvar::create_account_shared_data_for_test(&Rent::default()),
+        ),
+        (
+            sysvar::epoch_schedule::id(),
+            sysvar::create_account_shared_data_for_test(&EpochSchedule::default()),
+        ),
+        (
+            sysvar::stake_history::id(),
+            sysvar::create_account_shared_data_for_test(&StakeHistory::default()),
+        ),
+        (
+            sysvar::slot_hashes::id(),
+            sysvar::create_account_shared_data_for_test(&slot_hashes),
+        ),
+        (sysvar::recent_blockhashes::id(), recent_blockhashes),
+        (stake::config::id(), stake_config),
+    ];
+    // The program under test is provided by `mock_process_instruction_unchecked()`, the others
+    // are only reachable through CPI
+    for (program_id, _) in builtins() {
+        if program_id != builtin.program_id {
+            let mut account = AccountSharedData::new(1, 0, &native_loader::id());
+            account.set_executable(true);
+            accounts.push((program_id, account));
+        }
+    }
+    accounts
+}
+
+fn process_instruction<I>(
+    builtin: &Builtin<I>,
+    instruction: &Instruction,
+    environment: &[(Pubkey, AccountSharedData)],
+    accounts: &mut BTreeMap<Pubkey, AccountSharedData>,
+) {
+    for meta in &instruction.accounts {
+        if meta.pubkey != builtin.program_id
+            && !environment.iter().any(|(key, _)| *key == meta.pubkey)
+        {
+            accounts
+                .entry(meta.pubkey)
+                .or_insert_with(|| AccountSharedData::new(0, 0, &system_program::id()));
+        }
+    }
+    let transaction_accounts: Vec<(Pubkey, AccountSharedData)> = accounts
+        .iter()
+        .map(|(key, account)| (*key, account.clone()))
+        .chain(environment.iter().cloned())
+        .collect();
+
+    let process = || {
+        mock_process_instruction_unchecked(
+            &builtin.program_id,
+            Vec::new(),
+            &instruction.data,
+            transaction_accounts.clone(),
+            instruction.accounts.clone(),
+            builtins()
+                .into_iter()
+                .find(|(program_id, _)| *program_id == builtin.program_id)
+                .map(|(_, process_instruction)| process_instruction)
+                .expect("builtin under test is registered"),
+            |invoke_context| {
+                invoke_context.programs_loaded_for_tx_batch = PROGRAM_CACHE.with(|cache| *cache);
+            },
+            |_invoke_context| {},
+        )
+    };
+    let (result, post_accounts) = process();
+    let (replayed_result, replayed_post_accounts) = process();
+    assert_eq!(
+        result, replayed_result,
+        "result is not deterministic for {instruction:?}",
+    );
+    assert_eq!(
+        post_accounts, replayed_post_accounts,
+        "account state is not deterministic for {instruction:?}",
+    );
+
+    if result.is_ok() {
+        check_invariants(builtin, instruction, &transaction_accounts, &post_accounts);
+        for ((_key, account), post_account) in accounts.iter_mut().zip(post_accounts) {
+            *account = post_account;
+        }
+    }
+}
+
+fn check_invariants<I>(
+    builtin: &Builtin<I>,
+    instruction: &Instruction,
+    pre_accounts: &[(Pubkey, AccountSharedData)],
+    post_accounts: &[AccountSharedData],
+) {
+    let rent = Rent::default();
+    let pre_lamports: u128 = pre_accounts
+        .iter()
+        .map(|(_key, account)| u128::from(account.lamports()))
+        .sum();
+    let post_lamports: u128 = post_accounts
+        .iter()
+        .map(|account| u128::from(account.lamports()))
+        .sum();
+    assert_eq!(
+        pre_lamports, post_lamports,
+        "lamports were created or destroyed by {instruction:?}",
+    );
+
+    for ((key, pre_account), post_account) in pre_accounts.iter().zip(post_accounts) {
+        let is_writable = instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == *key && meta.is_writable);
+        if !is_writable {
+            assert_eq!(
+                pre_account, post_account,
+                "read-only account {key} was modified by {instruction:?}",
+            );
+            continue;
+        }
+        assert!(
+            post_account.data().len() as u64 <= MAX_PERMITTED_DATA_LENGTH,
+            "account {key} grew beyond the maximum data length by {instruction:?}",
+        );
+        if builtin.check_rent_state
+            && post_account.owner() == &builtin.program_id
+            && pre_account != post_account
+        {
+            assert!(
+                rent_state_transition_allowed(&rent, pre_account, post_account),
+                "account {key} was left rent-paying by {instruction:?}",
+            );
+        }
+    }
+}
+
+/// Mirrors the runtime's rent state check: an account may only be rent-paying afterwards if it
+/// already was, kept its size and did not gain lamports
+fn rent_state_transition_allowed(
+    rent: &Rent,
+    pre_account: &AccountSharedData,
+    post_account: &AccountSharedData,
+) -> bool {
+    let is_rent_paying = |account: &AccountSharedData| {
+        account.lamports() > 0 && !rent.is_exempt(account.lamports(), account.data().len())
+    };
+    !is_rent_paying(post_account)
+        || (is_rent_paying(pre_account)
+            && pre_account.data().len() == post_account.data().len()
+            && post_account.lamports() <= pre_account.lamports())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, arbitrary::Unstructured};
+
+    fn pseudo_random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn run_seeds<'a, I: Arbitrary<'a>>(builtin: &Builtin<I>, bytes: &'a [Vec<u8>]) {
+        for bytes in bytes {
+            if let Ok(input) = FuzzInput::<I>::arbitrary(&mut Unstructured::new(bytes)) {
+                run(builtin, &input);
+            }
+        }
+    }
+
+    fn seeds() -> Vec<Vec<u8>> {
+        (0..64)
+            .map(|seed| pseudo_random_bytes(seed, 4096))
+            .collect()
+    }
+
+    #[test]
+    fn test_system_seeds() {
+        run_seeds(&system::builtin(), &seeds());
+    }
+
+    #[test]
+    fn test_stake_seeds() {
+        run_seeds(&stake::builtin(), &seeds());
+    }
+
+    #[test]
+    fn test_vote_seeds() {
+        run_seeds(&vote::builtin(), &seeds());
+    }
+
+    #[test]
+    fn test_address_lookup_table_seeds() {
+        run_seeds(&address_lookup_table::builtin(), &seeds());
+    }
+
+    #[test]
+    fn test_rent_state_transition_allowed() {
+        let rent = Rent::default();
+        let exempt = rent.minimum_balance(0);
+        let account = |lamports| AccountSharedData::new(lamports, 0, &Pubkey::default());
+        assert!(rent_state_transition_allowed(
+            &rent,
+            &account(0),
+            &account(exempt)
+        ));
+        assert!(rent_state_transition_allowed(
+            &rent,
+            &account(exempt),
+            &account(0)
+        ));
+        assert!(!rent_state_transition_allowed(
+            &rent,
+            &account(exempt),
+            &account(1)
+        ));
+        assert!(rent_state_transition_allowed(
+            &rent,
+            &account(2),
+            &account(1)
+        ));
+        assert!(!rent_state_transition_allowed(
+            &rent,
+            &account(1),
+            &account(2)
+        ));
+    }
+}
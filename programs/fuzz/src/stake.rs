@@ -0,0 +1,238 @@
+//! Instructions of the stake program
+
+use {
+    crate::{Builtin, Key},
+    arbitrary::Arbitrary,
+    solana_sdk::{
+        clock::{Clock, Epoch, UnixTimestamp},
+        instruction::Instruction,
+        stake::{
+            instruction::{self as stake_instruction, LockupArgs},
+            program::id,
+            state::{Authorized, Lockup, StakeAuthorize, StakeStateV2},
+        },
+    },
+};
+
+#[derive(Arbitrary, Debug)]
+pub enum Authority {
+    Staker,
+    Withdrawer,
+}
+
+impl From<&Authority> for StakeAuthorize {
+    fn from(authority: &Authority) -> Self {
+        match authority {
+            Authority::Staker => StakeAuthorize::Staker,
+            Authority::Withdrawer => StakeAuthorize::Withdrawer,
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum StakeInstruction {
+    Initialize {
+        stake: Key,
+        staker: Key,
+        withdrawer: Key,
+        unix_timestamp: UnixTimestamp,
+        epoch: Epoch,
+        custodian: Key,
+    },
+    InitializeChecked {
+        stake: Key,
+        staker: Key,
+        withdrawer: Key,
+    },
+    Authorize {
+        stake: Key,
+        authority: Key,
+        new_authority: Key,
+        kind: Authority,
+        custodian: Option<Key>,
+    },
+    AuthorizeChecked {
+        stake: Key,
+        authority: Key,
+        new_authority: Key,
+        kind: Authority,
+        custodian: Option<Key>,
+    },
+    DelegateStake {
+        stake: Key,
+        staker: Key,
+        vote: Key,
+    },
+    Split {
+        stake: Key,
+        staker: Key,
+        lamports: u64,
+        split_stake: Key,
+    },
+    Merge {
+        destination: Key,
+        source: Key,
+        staker: Key,
+    },
+    Withdraw {
+        stake: Key,
+        withdrawer: Key,
+        to: Key,
+        lamports: u64,
+        custodian: Option<Key>,
+    },
+    Deactivate {
+        stake: Key,
+        staker: Key,
+    },
+    SetLockup {
+        stake: Key,
+        unix_timestamp: Option<UnixTimestamp>,
+        epoch: Option<Epoch>,
+        new_custodian: Option<Key>,
+        custodian: Key,
+    },
+    GetMinimumDelegation,
+    DeactivateDelinquent {
+        stake: Key,
+        delinquent_vote: Key,
+        reference_vote: Key,
+    },
+}
+
+pub fn builtin() -> Builtin<StakeInstruction> {
+    Builtin {
+        program_id: id(),
+        state_size: StakeStateV2::size_of(),
+        check_rent_state: true,
+        build_instruction,
+    }
+}
+
+fn build_instruction(instruction: &StakeInstruction, _clock: &Clock) -> Instruction {
+    match instruction {
+        StakeInstruction::Initialize {
+            stake,
+            staker,
+            withdrawer,
+            unix_timestamp,
+            epoch,
+            custodian,
+        } => stake_instruction::initialize(
+            &stake.pubkey(),
+            &Authorized {
+                staker: staker.pubkey(),
+                withdrawer: withdrawer.pubkey(),
+            },
+            &Lockup {
+                unix_timestamp: *unix_timestamp,
+                epoch: *epoch,
+                custodian: custodian.pubkey(),
+            },
+        ),
+        StakeInstruction::InitializeChecked {
+            stake,
+            staker,
+            withdrawer,
+        } => stake_instruction::initialize_checked(
+            &stake.pubkey(),
+            &Authorized {
+                staker: staker.pubkey(),
+                withdrawer: withdrawer.pubkey(),
+            },
+        ),
+        StakeInstruction::Authorize {
+            stake,
+            authority,
+            new_authority,
+            kind,
+            custodian,
+        } => stake_instruction::authorize(
+            &stake.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+            kind.into(),
+            custodian.map(Key::pubkey).as_ref(),
+        ),
+        StakeInstruction::AuthorizeChecked {
+            stake,
+            authority,
+            new_authority,
+            kind,
+            custodian,
+        } => stake_instruction::authorize_checked(
+            &stake.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+            kind.into(),
+            custodian.map(Key::pubkey).as_ref(),
+        ),
+        StakeInstruction::DelegateStake {
+            stake,
+            staker,
+            vote,
+        } => stake_instruction::delegate_stake(&stake.pubkey(), &staker.pubkey(), &vote.pubkey()),
+        // `split()` also allocates and assigns the split account, only keep the split itself
+        StakeInstruction::Split {
+            stake,
+            staker,
+            lamports,
+            split_stake,
+        } => stake_instruction::split(
+            &stake.pubkey(),
+            &staker.pubkey(),
+            *lamports,
+            &split_stake.pubkey(),
+        )
+        .pop()
+        .unwrap(),
+        StakeInstruction::Merge {
+            destination,
+            source,
+            staker,
+        } => stake_instruction::merge(&destination.pubkey(), &source.pubkey(), &staker.pubkey())
+            .pop()
+            .unwrap(),
+        StakeInstruction::Withdraw {
+            stake,
+            withdrawer,
+            to,
+            lamports,
+            custodian,
+        } => stake_instruction::withdraw(
+            &stake.pubkey(),
+            &withdrawer.pubkey(),
+            &to.pubkey(),
+            *lamports,
+            custodian.map(Key::pubkey).as_ref(),
+        ),
+        StakeInstruction::Deactivate { stake, staker } => {
+            stake_instruction::deactivate_stake(&stake.pubkey(), &staker.pubkey())
+        }
+        StakeInstruction::SetLockup {
+            stake,
+            unix_timestamp,
+            epoch,
+            new_custodian,
+            custodian,
+        } => stake_instruction::set_lockup(
+            &stake.pubkey(),
+            &LockupArgs {
+                unix_timestamp: *unix_timestamp,
+                epoch: *epoch,
+                custodian: new_custodian.map(Key::pubkey),
+            },
+            &custodian.pubkey(),
+        ),
+        StakeInstruction::GetMinimumDelegation => stake_instruction::get_minimum_delegation(),
+        StakeInstruction::DeactivateDelinquent {
+            stake,
+            delinquent_vote,
+            reference_vote,
+        } => stake_instruction::deactivate_delinquent_stake(
+            &stake.pubkey(),
+            &delinquent_vote.pubkey(),
+            &reference_vote.pubkey(),
+        ),
+    }
+}
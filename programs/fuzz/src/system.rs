@@ -0,0 +1,204 @@
+//! Instructions of the system program
+
+use {
+    crate::{Builtin, Key},
+    arbitrary::Arbitrary,
+    solana_sdk::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        nonce::State as NonceState,
+        pubkey::Pubkey,
+        system_instruction::{self, SystemInstruction as Ix},
+        system_program, sysvar,
+    },
+};
+
+#[derive(Arbitrary, Debug)]
+pub enum Owner {
+    System,
+    Key(Key),
+}
+
+impl Owner {
+    fn pubkey(&self) -> Pubkey {
+        match self {
+            Owner::System => system_program::id(),
+            Owner::Key(key) => key.pubkey(),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum SystemInstruction {
+    CreateAccount {
+        from: Key,
+        to: Key,
+        lamports: u64,
+        space: u16,
+        owner: Owner,
+    },
+    Assign {
+        account: Key,
+        owner: Owner,
+    },
+    Transfer {
+        from: Key,
+        to: Key,
+        lamports: u64,
+    },
+    Allocate {
+        account: Key,
+        space: u16,
+    },
+    CreateAccountWithSeed {
+        from: Key,
+        base: Key,
+        seed: u8,
+        lamports: u64,
+        space: u16,
+        owner: Owner,
+    },
+    TransferWithSeed {
+        base: Key,
+        seed: u8,
+        owner: Owner,
+        to: Key,
+        lamports: u64,
+    },
+    InitializeNonceAccount {
+        nonce: Key,
+        authority: Key,
+    },
+    AdvanceNonceAccount {
+        nonce: Key,
+        authority: Key,
+    },
+    WithdrawNonceAccount {
+        nonce: Key,
+        authority: Key,
+        to: Key,
+        lamports: u64,
+    },
+    AuthorizeNonceAccount {
+        nonce: Key,
+        authority: Key,
+        new_authority: Key,
+    },
+    UpgradeNonceAccount {
+        nonce: Key,
+    },
+}
+
+pub fn builtin() -> Builtin<SystemInstruction> {
+    Builtin {
+        program_id: system_program::id(),
+        state_size: NonceState::size(),
+        check_rent_state: false,
+        build_instruction,
+    }
+}
+
+fn build_instruction(instruction: &SystemInstruction, _clock: &Clock) -> Instruction {
+    match instruction {
+        SystemInstruction::CreateAccount {
+            from,
+            to,
+            lamports,
+            space,
+            owner,
+        } => system_instruction::create_account(
+            &from.pubkey(),
+            &to.pubkey(),
+            *lamports,
+            u64::from(*space),
+            &owner.pubkey(),
+        ),
+        SystemInstruction::Assign { account, owner } => {
+            system_instruction::assign(&account.pubkey(), &owner.pubkey())
+        }
+        SystemInstruction::Transfer { from, to, lamports } => {
+            system_instruction::transfer(&from.pubkey(), &to.pubkey(), *lamports)
+        }
+        SystemInstruction::Allocate { account, space } => {
+            system_instruction::allocate(&account.pubkey(), u64::from(*space))
+        }
+        SystemInstruction::CreateAccountWithSeed {
+            from,
+            base,
+            seed,
+            lamports,
+            space,
+            owner,
+        } => {
+            let seed = seed.to_string();
+            let to = Pubkey::create_with_seed(&base.pubkey(), &seed, &owner.pubkey()).unwrap();
+            system_instruction::create_account_with_seed(
+                &from.pubkey(),
+                &to,
+                &base.pubkey(),
+                &seed,
+                *lamports,
+                u64::from(*space),
+                &owner.pubkey(),
+            )
+        }
+        SystemInstruction::TransferWithSeed {
+            base,
+            seed,
+            owner,
+            to,
+            lamports,
+        } => {
+            let seed = seed.to_string();
+            let from = Pubkey::create_with_seed(&base.pubkey(), &seed, &owner.pubkey()).unwrap();
+            system_instruction::transfer_with_seed(
+                &from,
+                &base.pubkey(),
+                seed,
+                &owner.pubkey(),
+                &to.pubkey(),
+                *lamports,
+            )
+        }
+        // `create_nonce_account()` also creates the account, so initialize it directly
+        SystemInstruction::InitializeNonceAccount { nonce, authority } => {
+            #[allow(deprecated)]
+            let account_metas = vec![
+                AccountMeta::new(nonce.pubkey(), false),
+                AccountMeta::new_readonly(sysvar::recent_blockhashes::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+            ];
+            Instruction::new_with_bincode(
+                system_program::id(),
+                &Ix::InitializeNonceAccount(authority.pubkey()),
+                account_metas,
+            )
+        }
+        SystemInstruction::AdvanceNonceAccount { nonce, authority } => {
+            system_instruction::advance_nonce_account(&nonce.pubkey(), &authority.pubkey())
+        }
+        SystemInstruction::WithdrawNonceAccount {
+            nonce,
+            authority,
+            to,
+            lamports,
+        } => system_instruction::withdraw_nonce_account(
+            &nonce.pubkey(),
+            &authority.pubkey(),
+            &to.pubkey(),
+            *lamports,
+        ),
+        SystemInstruction::AuthorizeNonceAccount {
+            nonce,
+            authority,
+            new_authority,
+        } => system_instruction::authorize_nonce_account(
+            &nonce.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        ),
+        SystemInstruction::UpgradeNonceAccount { nonce } => {
+            system_instruction::upgrade_nonce_account(nonce.pubkey())
+        }
+    }
+}
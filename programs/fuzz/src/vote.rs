@@ -0,0 +1,244 @@
+//! Instructions of the vote program
+
+use {
+    crate::{slot_hash, Builtin, Key, SLOT_HASHES_LEN},
+    arbitrary::Arbitrary,
+    solana_sdk::{
+        clock::{Clock, Slot},
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        sysvar,
+        vote::{
+            instruction::{self as vote_instruction, VoteInstruction as Ix},
+            program::id,
+            state::{Vote, VoteAuthorize, VoteInit, VoteState, VoteStateUpdate},
+        },
+    },
+};
+
+#[derive(Arbitrary, Debug)]
+pub enum Authority {
+    Voter,
+    Withdrawer,
+}
+
+impl From<&Authority> for VoteAuthorize {
+    fn from(authority: &Authority) -> Self {
+        match authority {
+            Authority::Voter => VoteAuthorize::Voter,
+            Authority::Withdrawer => VoteAuthorize::Withdrawer,
+        }
+    }
+}
+
+/// Slots voted on, as distances to the current slot
+#[derive(Arbitrary, Debug)]
+pub struct VotedSlots {
+    pub slot_offsets: Vec<u8>,
+    /// Vote for the bank hashes in the `SlotHashes` sysvar instead of a made up one
+    pub correct_hash: bool,
+}
+
+impl VotedSlots {
+    fn slots(&self, clock: &Clock) -> Vec<Slot> {
+        let mut slots: Vec<Slot> = self
+            .slot_offsets
+            .iter()
+            .map(|offset| {
+                clock
+                    .slot
+                    .saturating_sub(1 + u64::from(*offset) % SLOT_HASHES_LEN)
+            })
+            .collect();
+        slots.sort_unstable();
+        slots.dedup();
+        slots
+    }
+
+    fn hash(&self, slots: &[Slot]) -> Hash {
+        match slots.last() {
+            Some(slot) if self.correct_hash => slot_hash(*slot),
+            _ => Hash::default(),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum VoteInstruction {
+    InitializeAccount {
+        vote: Key,
+        node: Key,
+        voter: Key,
+        withdrawer: Key,
+        commission: u8,
+    },
+    Authorize {
+        vote: Key,
+        authority: Key,
+        new_authority: Key,
+        kind: Authority,
+    },
+    AuthorizeChecked {
+        vote: Key,
+        authority: Key,
+        new_authority: Key,
+        kind: Authority,
+    },
+    Vote {
+        vote: Key,
+        voter: Key,
+        slots: VotedSlots,
+    },
+    UpdateVoteState {
+        vote: Key,
+        voter: Key,
+        slots: VotedSlots,
+        confirmation_counts: Vec<u8>,
+        compact: bool,
+    },
+    Withdraw {
+        vote: Key,
+        withdrawer: Key,
+        lamports: u64,
+        to: Key,
+    },
+    UpdateValidatorIdentity {
+        vote: Key,
+        withdrawer: Key,
+        node: Key,
+    },
+    UpdateCommission {
+        vote: Key,
+        withdrawer: Key,
+        commission: u8,
+    },
+}
+
+pub fn builtin() -> Builtin<VoteInstruction> {
+    Builtin {
+        program_id: id(),
+        state_size: VoteState::size_of(),
+        check_rent_state: true,
+        build_instruction,
+    }
+}
+
+fn build_instruction(instruction: &VoteInstruction, clock: &Clock) -> Instruction {
+    match instruction {
+        // `create_account()` also creates the account, so initialize it directly
+        VoteInstruction::InitializeAccount {
+            vote,
+            node,
+            voter,
+            withdrawer,
+            commission,
+        } => Instruction::new_with_bincode(
+            id(),
+            &Ix::InitializeAccount(VoteInit {
+                node_pubkey: node.pubkey(),
+                authorized_voter: voter.pubkey(),
+                authorized_withdrawer: withdrawer.pubkey(),
+                commission: *commission,
+            }),
+            vec![
+                AccountMeta::new(vote.pubkey(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(node.pubkey(), true),
+            ],
+        ),
+        VoteInstruction::Authorize {
+            vote,
+            authority,
+            new_authority,
+            kind,
+        } => vote_instruction::authorize(
+            &vote.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+            kind.into(),
+        ),
+        VoteInstruction::AuthorizeChecked {
+            vote,
+            authority,
+            new_authority,
+            kind,
+        } => vote_instruction::authorize_checked(
+            &vote.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+            kind.into(),
+        ),
+        VoteInstruction::Vote { vote, voter, slots } => {
+            let voted_slots = slots.slots(clock);
+            let hash = slots.hash(&voted_slots);
+            vote_instruction::vote(
+                &vote.pubkey(),
+                &voter.pubkey(),
+                Vote::new(voted_slots, hash),
+            )
+        }
+        VoteInstruction::UpdateVoteState {
+            vote,
+            voter,
+            slots,
+            confirmation_counts,
+            compact,
+        } => {
+            let voted_slots = slots.slots(clock);
+            let hash = slots.hash(&voted_slots);
+            let lockouts: Vec<(Slot, u32)> = voted_slots
+                .iter()
+                .enumerate()
+                .map(|(index, slot)| {
+                    let confirmation_count = confirmation_counts
+                        .get(index)
+                        .map(|count| u32::from(*count))
+                        .unwrap_or(1);
+                    (*slot, confirmation_count)
+                })
+                .collect();
+            let mut vote_state_update = VoteStateUpdate::from(lockouts);
+            vote_state_update.hash = hash;
+            vote_state_update.timestamp = Some(clock.unix_timestamp);
+            if *compact {
+                vote_instruction::compact_update_vote_state(
+                    &vote.pubkey(),
+                    &voter.pubkey(),
+                    vote_state_update,
+                )
+            } else {
+                vote_instruction::update_vote_state(
+                    &vote.pubkey(),
+                    &voter.pubkey(),
+                    vote_state_update,
+                )
+            }
+        }
+        VoteInstruction::Withdraw {
+            vote,
+            withdrawer,
+            lamports,
+            to,
+        } => vote_instruction::withdraw(
+            &vote.pubkey(),
+            &withdrawer.pubkey(),
+            *lamports,
+            &to.pubkey(),
+        ),
+        VoteInstruction::UpdateValidatorIdentity {
+            vote,
+            withdrawer,
+            node,
+        } => vote_instruction::update_validator_identity(
+            &vote.pubkey(),
+            &withdrawer.pubkey(),
+            &node.pubkey(),
+        ),
+        VoteInstruction::UpdateCommission {
+            vote,
+            withdrawer,
+            commission,
+        } => vote_instruction::update_commission(&vote.pubkey(), &withdrawer.pubkey(), *commission),
+    }
+}
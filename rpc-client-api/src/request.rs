@@ -40,6 +40,7 @@ pub enum RpcRequest {
         note = "Please use RpcRequest::GetTransaction instead"
     )]
     GetConfirmedTransaction,
+    GetDuplicateBlockProofs,
     GetEpochInfo,
     GetEpochSchedule,
     #[deprecated(
@@ -105,6 +106,7 @@ pub enum RpcRequest {
     GetTokenSupply,
     GetTransaction,
     GetTransactionCount,
+    GetTransactionInclusionProof,
     GetVersion,
     GetVoteAccounts,
     IsBlockhashValid,
@@ -112,6 +114,7 @@ pub enum RpcRequest {
     RegisterNode,
     RequestAirdrop,
     SendTransaction,
+    SimulateBundle,
     SimulateTransaction,
     SignVote,
 }
@@ -136,6 +139,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetConfirmedBlocksWithLimit => "getConfirmedBlocksWithLimit",
             RpcRequest::GetConfirmedSignaturesForAddress2 => "getConfirmedSignaturesForAddress2",
             RpcRequest::GetConfirmedTransaction => "getConfirmedTransaction",
+            RpcRequest::GetDuplicateBlockProofs => "getDuplicateBlockProofs",
             RpcRequest::GetEpochInfo => "getEpochInfo",
             RpcRequest::GetEpochSchedule => "getEpochSchedule",
             RpcRequest::GetFeeCalculatorForBlockhash => "getFeeCalculatorForBlockhash",
@@ -181,6 +185,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetTokenLargestAccounts => "getTokenLargestAccounts",
             RpcRequest::GetTransaction => "getTransaction",
             RpcRequest::GetTransactionCount => "getTransactionCount",
+            RpcRequest::GetTransactionInclusionProof => "getTransactionInclusionProof",
             RpcRequest::GetVersion => "getVersion",
             RpcRequest::GetVoteAccounts => "getVoteAccounts",
             RpcRequest::IsBlockhashValid => "isBlockhashValid",
@@ -188,6 +193,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::RegisterNode => "registerNode",
             RpcRequest::RequestAirdrop => "requestAirdrop",
             RpcRequest::SendTransaction => "sendTransaction",
+            RpcRequest::SimulateBundle => "simulateBundle",
             RpcRequest::SimulateTransaction => "simulateTransaction",
             RpcRequest::SignVote => "signVote",
         };
@@ -205,6 +211,10 @@ pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
 pub const NUM_LARGEST_ACCOUNTS: usize = 20;
 pub const MAX_GET_PROGRAM_ACCOUNT_FILTERS: usize = 4;
 pub const MAX_GET_SLOT_LEADERS: usize = 5000;
+pub const MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT: usize = 1_000;
+pub const MAX_GET_DUPLICATE_BLOCK_PROOFS_LEADER_SLOT_RANGE: u64 = 10_000;
+pub const MAX_SIMULATE_BUNDLE_TRANSACTIONS: usize = 10;
+pub const MAX_SIMULATE_ACCOUNT_OVERRIDES: usize = 100;
 
 // Limit the length of the `epoch_credits` array for each validator in a `get_vote_accounts`
 // response
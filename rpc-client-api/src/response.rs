@@ -361,6 +361,54 @@ pub struct RpcIdentity {
     pub identity: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcDuplicateBlockProof {
+    pub slot: Slot,
+    /// Identity of the leader which produced the duplicate block, as base-58
+    /// encoded string, if known
+    pub leader: Option<String>,
+    /// The two conflicting shreds, as base-64 encoded strings
+    pub shred1: String,
+    pub shred2: String,
+}
+
+/// Proof that a transaction was included in a block, checked against the
+/// signatures of the slot leader rather than trusting the RPC node
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransactionInclusionProof {
+    pub slot: Slot,
+    /// Identity of the slot leader, as base-58 encoded string
+    pub leader: String,
+    /// Index of the entry holding the transaction among the entries of the slot
+    pub entry_index: usize,
+    /// Bincode serialized entry, as base-64 encoded string
+    pub entry: String,
+    /// Byte offset of the serialized entry within the concatenated data of the shreds
+    pub entry_offset: usize,
+    /// Data shreds holding the serialized entry, in index order
+    pub shreds: Vec<RpcShredInclusionProof>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcShredInclusionProof {
+    pub index: u32,
+    /// Shred payload, as base-64 encoded string
+    pub shred: String,
+    /// Index of the shred in its erasure batch's merkle tree
+    pub leaf_index: usize,
+    /// Merkle tree leaf of the shred, as base-58 encoded string
+    pub leaf: String,
+    /// Sibling nodes from the leaf up to the root, as base-58 encoded strings
+    pub proof: Vec<String>,
+    /// Merkle root of the erasure batch, as base-58 encoded string
+    pub merkle_root: String,
+    /// Slot leader's signature of the merkle root, as base-58 encoded string
+    pub signature: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcVote {
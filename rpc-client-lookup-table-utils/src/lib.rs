@@ -0,0 +1,411 @@
+//! Address lookup table helpers for tools that send many similar transactions.
+//!
+//! The addresses that the transactions have in common, or that fill them up, are stored in
+//! lookup tables, and the instructions of consecutive messages are packed into v0
+//! transactions loading those addresses from the tables.
+//!
+//! The functions return this crate's [`Error`], converted into the caller's error type.
+
+use {
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::client_error::{Error as ClientError, ErrorKind as ClientErrorKind},
+    solana_sdk::{
+        address_lookup_table::{
+            self,
+            instruction::{create_lookup_table, extend_lookup_table},
+            state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
+            AddressLookupTableAccount,
+        },
+        clock::{Slot, DEFAULT_MS_PER_SLOT},
+        commitment_config::CommitmentConfig,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        message::{v0, CompileError, Message, VersionedMessage},
+        packet::PACKET_DATA_SIZE,
+        pubkey::Pubkey,
+        signature::{Signature, Signer},
+        transaction::Transaction,
+    },
+    std::{collections::HashSet, ops::Range, thread::sleep, time::Duration},
+    thiserror::Error,
+};
+
+// Keeps extend transactions comfortably below the packet size limit
+const EXTEND_LOOKUP_TABLE_CHUNK_SIZE: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("client error: {0}")]
+    Client(#[from] ClientError),
+    #[error("{0} is not an address lookup table")]
+    NotALookupTable(Pubkey),
+    #[error("invalid lookup table {0}: {1}")]
+    InvalidLookupTable(Pubkey, InstructionError),
+    #[error("lookup table {0} is deactivated")]
+    Deactivated(Pubkey),
+    #[error("message compile error: {0}")]
+    Compile(#[from] CompileError),
+}
+
+impl From<Error> for ClientError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Client(err) => err,
+            err => ClientErrorKind::Custom(err.to_string()).into(),
+        }
+    }
+}
+
+/// A v0 message packing the instructions of the `messages` range of the messages it was
+/// built from.
+pub struct BatchedMessage {
+    pub message: VersionedMessage,
+    pub messages: Range<usize>,
+}
+
+/// Returns the instructions of a legacy message.
+pub fn message_instructions(message: &Message) -> Vec<Instruction> {
+    message
+        .instructions
+        .iter()
+        .map(|instruction| Instruction {
+            program_id: message.account_keys[usize::from(instruction.program_id_index)],
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|index| {
+                    let index = usize::from(*index);
+                    AccountMeta {
+                        pubkey: message.account_keys[index],
+                        is_signer: message.is_signer(index),
+                        is_writable: message.is_writable(index),
+                    }
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+/// Returns the addresses used by `messages` that may be loaded from a lookup table, i.e.
+/// all addresses that are neither signers nor invoked programs.
+pub fn lookup_table_candidates(messages: &[Message]) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    let mut addresses = vec![];
+    for message in messages {
+        for (index, address) in message.account_keys.iter().enumerate() {
+            if !message.is_signer(index)
+                && !message.is_key_called_as_program(index)
+                && seen.insert(*address)
+            {
+                addresses.push(*address);
+            }
+        }
+    }
+    addresses
+}
+
+/// Fetches an active lookup table.
+pub fn fetch_lookup_table<E: From<Error>>(
+    client: &RpcClient,
+    lookup_table_address: &Pubkey,
+) -> Result<AddressLookupTableAccount, E> {
+    let account = client
+        .get_account(lookup_table_address)
+        .map_err(Error::from)?;
+    if !address_lookup_table::program::check_id(&account.owner) {
+        return Err(Error::NotALookupTable(*lookup_table_address).into());
+    }
+    let lookup_table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|err| Error::InvalidLookupTable(*lookup_table_address, err))?;
+    if lookup_table.meta.deactivation_slot != Slot::MAX {
+        return Err(Error::Deactivated(*lookup_table_address).into());
+    }
+    Ok(AddressLookupTableAccount {
+        key: *lookup_table_address,
+        addresses: lookup_table.addresses.to_vec(),
+    })
+}
+
+fn send_fee_payer_instruction(
+    client: &RpcClient,
+    fee_payer: &dyn Signer,
+    instruction: Instruction,
+) -> Result<Signature, ClientError> {
+    let blockhash = client.get_latest_blockhash()?;
+    let transaction = Transaction::new(
+        &[fee_payer],
+        Message::new(&[instruction], Some(&fee_payer.pubkey())),
+        blockhash,
+    );
+    client.send_and_confirm_transaction_with_spinner(&transaction)
+}
+
+/// Returns the instruction creating a new lookup table of `authority`, funded by `payer`,
+/// and the table's address. The address is derived from a finalized slot later than
+/// `last_recent_slot` that no existing table of `authority` was derived from, which is
+/// returned as well.
+pub fn new_lookup_table<E: From<Error>>(
+    client: &RpcClient,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    last_recent_slot: Option<Slot>,
+) -> Result<(Instruction, Pubkey, Slot), E> {
+    loop {
+        let slot = client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .map_err(Error::from)?;
+        if last_recent_slot.map_or(true, |last_recent_slot| slot > last_recent_slot) {
+            let (create_lookup_table_ix, lookup_table_address) =
+                create_lookup_table(*authority, *payer, slot);
+            let account = client
+                .get_account_with_commitment(&lookup_table_address, client.commitment())
+                .map_err(Error::from)?;
+            if account.value.is_none() {
+                return Ok((create_lookup_table_ix, lookup_table_address, slot));
+            }
+        }
+        sleep(Duration::from_millis(DEFAULT_MS_PER_SLOT));
+    }
+}
+
+/// Returns the instructions appending `addresses` to a lookup table, each small enough to
+/// be sent in a transaction of its own.
+pub fn extend_lookup_table_instructions(
+    lookup_table_address: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    addresses
+        .chunks(EXTEND_LOOKUP_TABLE_CHUNK_SIZE)
+        .map(|chunk| {
+            extend_lookup_table(
+                *lookup_table_address,
+                *authority,
+                Some(*payer),
+                chunk.to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// Waits until addresses appended to lookup tables by confirmed transactions can be loaded.
+pub fn wait_for_lookup_table_activation<E: From<Error>>(client: &RpcClient) -> Result<(), E> {
+    // Addresses appended to a table can only be loaded by transactions in later slots
+    let last_extended_slot = client.get_slot().map_err(Error::from)?;
+    while client.get_slot().map_err(Error::from)? <= last_extended_slot {
+        sleep(Duration::from_millis(DEFAULT_MS_PER_SLOT));
+    }
+    Ok(())
+}
+
+/// Creates as many lookup tables as needed to hold `addresses`, with the fee payer as
+/// authority, and waits until the addresses can be loaded by transactions. In a dry run the
+/// tables are only planned, nothing is sent.
+pub fn create_lookup_tables<E: From<Error>>(
+    client: &RpcClient,
+    fee_payer: &dyn Signer,
+    addresses: &[Pubkey],
+    dry_run: bool,
+) -> Result<Vec<AddressLookupTableAccount>, E> {
+    let fee_payer_pubkey = fee_payer.pubkey();
+    let mut lookup_tables = vec![];
+    let mut last_recent_slot = None;
+    for table_addresses in addresses.chunks(LOOKUP_TABLE_MAX_ADDRESSES) {
+        // Lookup table addresses are derived from the authority and a recent slot, so
+        // every table needs a slot of its own
+        let (create_lookup_table_ix, lookup_table_address, recent_slot) = new_lookup_table::<Error>(
+            client,
+            &fee_payer_pubkey,
+            &fee_payer_pubkey,
+            last_recent_slot,
+        )?;
+        last_recent_slot = Some(recent_slot);
+
+        if !dry_run {
+            send_fee_payer_instruction(client, fee_payer, create_lookup_table_ix)
+                .map_err(Error::from)?;
+            for extend_lookup_table_ix in extend_lookup_table_instructions(
+                &lookup_table_address,
+                &fee_payer_pubkey,
+                &fee_payer_pubkey,
+                table_addresses,
+            ) {
+                send_fee_payer_instruction(client, fee_payer, extend_lookup_table_ix)
+                    .map_err(Error::from)?;
+            }
+        }
+        lookup_tables.push(AddressLookupTableAccount {
+            key: lookup_table_address,
+            addresses: table_addresses.to_vec(),
+        });
+    }
+
+    if !dry_run && !lookup_tables.is_empty() {
+        wait_for_lookup_table_activation::<Error>(client)?;
+    }
+    Ok(lookup_tables)
+}
+
+/// Returns the size of a signed transaction carrying `message`.
+pub fn serialized_transaction_size(message: &VersionedMessage) -> usize {
+    let num_signatures = usize::from(message.header().num_required_signatures);
+    // Signature count prefix, which fits in a single byte for any valid transaction
+    1 + num_signatures * std::mem::size_of::<Signature>() + message.serialize().len()
+}
+
+/// Packs the instructions of consecutive `messages` into as few v0 messages as possible,
+/// keeping each transaction within the packet size limit.
+pub fn batch_messages<E: From<Error>>(
+    messages: &[Message],
+    fee_payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<BatchedMessage>, E> {
+    let compile = |instructions: &[Instruction]| -> Result<VersionedMessage, Error> {
+        Ok(VersionedMessage::V0(v0::Message::try_compile(
+            fee_payer,
+            instructions,
+            lookup_tables,
+            Hash::default(), // populated by a real blockhash for submission
+        )?))
+    };
+
+    let mut batches: Vec<BatchedMessage> = vec![];
+    let mut instructions = vec![];
+    let mut batch_start = 0;
+    for (index, message) in messages.iter().enumerate() {
+        let message_instructions = message_instructions(message);
+        if index > batch_start {
+            let mut candidate = instructions.clone();
+            candidate.extend(message_instructions.iter().cloned());
+            let fits = compile(&candidate)
+                .map(|message| serialized_transaction_size(&message) <= PACKET_DATA_SIZE)
+                .unwrap_or(false);
+            if fits {
+                instructions = candidate;
+                continue;
+            }
+            batches.push(BatchedMessage {
+                message: compile(&instructions)?,
+                messages: batch_start..index,
+            });
+            batch_start = index;
+        }
+        instructions = message_instructions;
+    }
+    if !instructions.is_empty() {
+        batches.push(BatchedMessage {
+            message: compile(&instructions)?,
+            messages: batch_start..messages.len(),
+        });
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::system_instruction};
+
+    #[test]
+    fn test_message_instructions() {
+        let payer = Pubkey::new_unique();
+        let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let instructions = vec![
+            system_instruction::transfer(&payer, &recipients[0], 1),
+            system_instruction::transfer(&payer, &recipients[1], 2),
+        ];
+        let message = Message::new(&instructions, Some(&payer));
+        assert_eq!(message_instructions(&message), instructions);
+        assert_eq!(lookup_table_candidates(&[message]), recipients);
+    }
+
+    #[test]
+    fn test_extend_lookup_table_instructions() {
+        let lookup_table = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let addresses: Vec<Pubkey> = (0..EXTEND_LOOKUP_TABLE_CHUNK_SIZE * 2 + 1)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let instructions =
+            extend_lookup_table_instructions(&lookup_table, &authority, &payer, &addresses);
+        assert_eq!(
+            instructions,
+            addresses
+                .chunks(EXTEND_LOOKUP_TABLE_CHUNK_SIZE)
+                .map(|chunk| extend_lookup_table(
+                    lookup_table,
+                    authority,
+                    Some(payer),
+                    chunk.to_vec()
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(instructions.len(), 3);
+    }
+
+    #[test]
+    fn test_serialized_transaction_size() {
+        let payer = Pubkey::new_unique();
+        let message = Message::new(
+            &[system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer),
+        );
+        let transaction = Transaction::new_unsigned(message.clone());
+        assert_eq!(
+            serialized_transaction_size(&VersionedMessage::Legacy(message)),
+            transaction.message_data().len() + 1 + std::mem::size_of::<Signature>()
+        );
+    }
+
+    #[test]
+    fn test_batch_messages() {
+        let payer = Pubkey::new_unique();
+        let recipients: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        let messages: Vec<Message> = recipients
+            .iter()
+            .map(|recipient| {
+                Message::new(
+                    &[system_instruction::transfer(&payer, recipient, 1)],
+                    Some(&payer),
+                )
+            })
+            .collect();
+
+        // Without lookup tables the recipients' addresses quickly fill a transaction
+        let batches = batch_messages::<Error>(&messages, &payer, &[]).unwrap();
+        let lookup_tables = vec![AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: lookup_table_candidates(&messages),
+        }];
+        let batches_with_lookup_table =
+            batch_messages::<Error>(&messages, &payer, &lookup_tables).unwrap();
+        assert!(batches_with_lookup_table.len() < batches.len());
+
+        for batches in [batches, batches_with_lookup_table] {
+            let mut next_message = 0;
+            for batch in batches {
+                assert_eq!(batch.messages.start, next_message);
+                assert!(serialized_transaction_size(&batch.message) <= PACKET_DATA_SIZE);
+                assert_eq!(batch.message.instructions().len(), batch.messages.len());
+                next_message = batch.messages.end;
+            }
+            assert_eq!(next_message, messages.len());
+        }
+    }
+
+    #[test]
+    fn test_client_error_from_error() {
+        let lookup_table = Pubkey::new_unique();
+        let client_error = ClientError::from(Error::Deactivated(lookup_table));
+        assert!(matches!(
+            client_error.kind(),
+            ClientErrorKind::Custom(message) if message.contains(&lookup_table.to_string())
+        ));
+    }
+}
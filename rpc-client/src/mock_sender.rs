@@ -352,6 +352,16 @@ impl RpcSender for MockSender {
                     return_data: None,
                 },
             })?,
+            "simulateBundle" => serde_json::to_value(Response {
+                context: RpcResponseContext { slot: 1, api_version: None },
+                value: vec![RpcSimulateTransactionResult {
+                    err: None,
+                    logs: None,
+                    accounts: None,
+                    units_consumed: None,
+                    return_data: None,
+                }],
+            })?,
             "getMinimumBalanceForRentExemption" => json![20],
             "getVersion" => {
                 let version = Version::default();
@@ -1412,6 +1412,86 @@ impl RpcClient {
         .await
     }
 
+    /// Simulates sending an ordered bundle of transactions.
+    ///
+    /// Each transaction observes the account writes of the transactions
+    /// before it, and nothing is committed. Simulation stops at the first
+    /// transaction that fails, so the returned vector contains one
+    /// [`RpcSimulateTransactionResult`] per executed transaction, the last of
+    /// which carries the error if the bundle failed.
+    ///
+    /// Accounts to report after each transaction are passed in the
+    /// [`accounts`] field of [`RpcSimulateBundleConfig`], which must be empty
+    /// or have one entry per transaction.
+    ///
+    /// [`accounts`]: solana_rpc_client_api::config::RpcSimulateBundleConfig::accounts
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`simulateBundle`] RPC method.
+    ///
+    /// [`simulateBundle`]: https://docs.solana.com/api/http#simulatebundle
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_rpc_client_api::{
+    /// #     client_error::Error,
+    /// #     config::RpcSimulateBundleConfig,
+    /// # };
+    /// # use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+    /// # use solana_sdk::{
+    /// #     signature::Signer,
+    /// #     signer::keypair::Keypair,
+    /// #     system_transaction,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// // Transfer lamports from Alice to Bob, then from Bob to Carol
+    /// #     let alice = Keypair::new();
+    /// #     let bob = Keypair::new();
+    /// #     let carol = Keypair::new();
+    /// #     let lamports = 50;
+    /// let latest_blockhash = rpc_client.get_latest_blockhash().await?;
+    /// let tx1 = system_transaction::transfer(&alice, &bob.pubkey(), lamports, latest_blockhash);
+    /// let tx2 = system_transaction::transfer(&bob, &carol.pubkey(), lamports, latest_blockhash);
+    /// let result = rpc_client.simulate_bundle_with_config(
+    ///     &[tx1, tx2],
+    ///     RpcSimulateBundleConfig::default(),
+    /// ).await?;
+    /// assert!(result.value.iter().all(|result| result.err.is_none()));
+    /// #     Ok::<(), Error>(())
+    /// # })?;
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub async fn simulate_bundle_with_config(
+        &self,
+        transactions: &[impl SerializableTransaction],
+        config: RpcSimulateBundleConfig,
+    ) -> RpcResult<Vec<RpcSimulateTransactionResult>> {
+        let encoding = if let Some(encoding) = config.encoding {
+            encoding
+        } else {
+            self.default_cluster_transaction_encoding().await?
+        };
+        let commitment = config.commitment.unwrap_or_default();
+        let commitment = self.maybe_map_commitment(commitment).await?;
+        let config = RpcSimulateBundleConfig {
+            encoding: Some(encoding),
+            commitment: Some(commitment),
+            ..config
+        };
+        let serialized_encoded = transactions
+            .iter()
+            .map(|transaction| serialize_and_encode(transaction, encoding))
+            .collect::<ClientResult<Vec<_>>>()?;
+        self.send(
+            RpcRequest::SimulateBundle,
+            json!([serialized_encoded, config]),
+        )
+        .await
+    }
+
     /// Returns the highest slot information that the node has snapshots for.
     ///
     /// This will find the highest full snapshot slot, and the highest incremental snapshot slot
@@ -5035,6 +5115,7 @@ impl RpcClient {
             RpcRequestAirdropConfig {
                 commitment: Some(self.commitment()),
                 recent_blockhash: Some(recent_blockhash.to_string()),
+                ..RpcRequestAirdropConfig::default()
             },
         )
         .await
@@ -21,19 +21,21 @@ use {
     solana_sdk::{
         commitment_config::CommitmentConfig,
         hash::Hash,
+        instruction::{Instruction, InstructionError},
         pubkey::Pubkey,
         rent::Rent,
         signature::{Keypair, Signature, Signer},
         system_transaction,
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     },
     solana_streamer::socket::SocketAddrSpace,
-    solana_test_validator::TestValidator,
+    solana_test_validator::{LoaderV4ProgramInfo, TestValidator, TestValidatorGenesis},
     solana_tpu_client::tpu_client::DEFAULT_TPU_CONNECTION_POOL_SIZE,
     solana_transaction_status::TransactionStatus,
     std::{
         collections::HashSet,
         net::UdpSocket,
+        path::PathBuf,
         sync::{
             atomic::{AtomicUsize, Ordering},
             Arc,
@@ -542,3 +544,40 @@ fn deserialize_rpc_error() -> ClientResult<()> {
         }
     }
 }
+
+#[test]
+fn test_loader_v4_programs() {
+    solana_logger::setup();
+
+    // Exits with 42 when executed
+    let program_name = "../programs/loader-v4/test_elfs/out/rodata_section";
+    let deployed_id = Pubkey::new_unique();
+    let finalized_id = Pubkey::new_unique();
+    let (test_validator, payer) = TestValidatorGenesis::default()
+        .add_loader_v4_program(program_name, deployed_id, Some(Pubkey::new_unique()))
+        .add_loader_v4_programs_with_path(&[LoaderV4ProgramInfo {
+            program_id: finalized_id,
+            authority: None,
+            program_path: PathBuf::from(format!("{program_name}.so")),
+        }])
+        .start();
+    let rpc_client = test_validator.get_rpc_client();
+
+    let blockhash = rpc_client.get_latest_blockhash().unwrap();
+    for program_id in [deployed_id, finalized_id] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(program_id, &[], vec![])],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        let result = rpc_client.simulate_transaction(&transaction).unwrap();
+        assert_eq!(
+            result.value.err,
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(42)
+            ))
+        );
+    }
+}
@@ -18,15 +18,16 @@ use {
         accounts_index::{AccountIndex, AccountSecondaryIndexes, IndexKey, ScanConfig},
         inline_spl_token::{SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
         inline_spl_token_2022::{self, ACCOUNTTYPE_ACCOUNT},
+        rent_collector::RENT_EXEMPT_RENT_EPOCH,
     },
     solana_client::connection_cache::{ConnectionCache, Protocol},
     solana_entry::entry::Entry,
-    solana_faucet::faucet::request_airdrop_transaction,
+    solana_faucet::faucet::{request_airdrop_tokens_transaction, request_airdrop_transaction},
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
     solana_ledger::{
         blockstore::{Blockstore, SignatureInfosForAddress},
         blockstore_db::BlockstoreError,
-        blockstore_meta::{PerfSample, PerfSampleV1, PerfSampleV2},
+        blockstore_meta::{DuplicateSlotProofV2, PerfSample, PerfSampleV1, PerfSampleV2},
         get_tmp_ledger_path,
         leader_schedule_cache::LeaderScheduleCache,
     },
@@ -40,9 +41,12 @@ use {
         request::{
             TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
             MAX_GET_CONFIRMED_BLOCKS_RANGE, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
-            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
-            MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS,
-            MAX_RPC_VOTE_ACCOUNT_INFO_EPOCH_CREDITS_HISTORY, NUM_LARGEST_ACCOUNTS,
+            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE,
+            MAX_GET_DUPLICATE_BLOCK_PROOFS_LEADER_SLOT_RANGE, MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT,
+            MAX_GET_PROGRAM_ACCOUNT_FILTERS, MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
+            MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS,
+            MAX_RPC_VOTE_ACCOUNT_INFO_EPOCH_CREDITS_HISTORY, MAX_SIMULATE_ACCOUNT_OVERRIDES,
+            MAX_SIMULATE_BUNDLE_TRANSACTIONS, NUM_LARGEST_ACCOUNTS,
         },
         response::{Response as RpcResponse, *},
     },
@@ -2178,6 +2182,125 @@ impl JsonRpcRequestProcessor {
             })
             .collect())
     }
+
+    fn get_duplicate_block_proofs(
+        &self,
+        start_slot: Slot,
+        leader: Option<Pubkey>,
+        limit: usize,
+    ) -> Result<Vec<RpcDuplicateBlockProof>> {
+        let duplicate_slots = self
+            .blockstore
+            .duplicate_slots_iterator(start_slot)
+            .map_err(|err| Error::invalid_params(format!("{err:?}")))?;
+        // Proofs of a leader may be sparse, so only a range of slots is scanned
+        // for them, rather than every slot until `limit` of them are found.
+        let end_slot = if leader.is_some() {
+            start_slot.saturating_add(MAX_GET_DUPLICATE_BLOCK_PROOFS_LEADER_SLOT_RANGE)
+        } else {
+            Slot::MAX
+        };
+        Ok(duplicate_slots
+            .take_while(|slot| *slot < end_slot)
+            .filter_map(|slot| {
+                let proof = self.blockstore.get_duplicate_slot(slot)?;
+                let slot_leader = proof
+                    .leader
+                    .or_else(|| self.leader_schedule_cache.slot_leader_at(slot, None));
+                if leader.is_some() && slot_leader != leader {
+                    return None;
+                }
+                Some(rpc_duplicate_block_proof(slot, proof, slot_leader))
+            })
+            .take(limit)
+            .collect())
+    }
+
+    fn get_transaction_inclusion_proof(
+        &self,
+        slot: Slot,
+        signature: Signature,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Option<RpcTransactionInclusionProof>> {
+        let commitment = commitment.unwrap_or_default();
+        check_is_at_least_confirmed(commitment)?;
+        let is_available = if slot
+            <= self
+                .block_commitment_cache
+                .read()
+                .unwrap()
+                .highest_super_majority_root()
+        {
+            self.blockstore.is_root(slot)
+        } else {
+            commitment.is_confirmed()
+                && self
+                    .bank(Some(CommitmentConfig::confirmed()))
+                    .status_cache_ancestors()
+                    .contains(&slot)
+        };
+        if !is_available {
+            return Err(RpcCustomError::BlockNotAvailable { slot }.into());
+        }
+        let leader = self
+            .leader_schedule_cache
+            .slot_leader_at(slot, None)
+            .ok_or(RpcCustomError::BlockNotAvailable { slot })?;
+        let Some((entry_index, entry, shreds, entry_offset)) = self
+            .blockstore
+            .get_transaction_entry_with_shreds(slot, &signature)
+            .map_err(|_| RpcCustomError::BlockNotAvailable { slot })?
+        else {
+            return Ok(None);
+        };
+        let shreds = shreds
+            .into_iter()
+            .map(|shred| {
+                let proof = shred.merkle_inclusion_proof().map_err(|_| {
+                    Error::invalid_params(format!(
+                        "Slot {slot} was not broadcast with merkle shreds"
+                    ))
+                })?;
+                Ok(RpcShredInclusionProof {
+                    index: shred.index(),
+                    leaf_index: proof.index,
+                    leaf: proof.leaf.to_string(),
+                    proof: proof
+                        .proof
+                        .iter()
+                        .map(|node| bs58::encode(node).into_string())
+                        .collect(),
+                    merkle_root: proof.root.to_string(),
+                    signature: shred.signature().to_string(),
+                    shred: BASE64_STANDARD.encode(shred.payload()),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(RpcTransactionInclusionProof {
+            slot,
+            leader: leader.to_string(),
+            entry_index,
+            entry: BASE64_STANDARD.encode(serialize(&entry).unwrap()),
+            entry_offset,
+            shreds,
+        }))
+    }
+}
+
+/// Proofs stored before the leader was recorded alongside them, or by code
+/// paths which do not know it, have the leader filled in by the caller from
+/// the leader schedule.
+pub(crate) fn rpc_duplicate_block_proof(
+    slot: Slot,
+    proof: DuplicateSlotProofV2,
+    leader: Option<Pubkey>,
+) -> RpcDuplicateBlockProof {
+    RpcDuplicateBlockProof {
+        slot,
+        leader: leader.map(|leader| leader.to_string()),
+        shred1: BASE64_STANDARD.encode(proof.shred1),
+        shred2: BASE64_STANDARD.encode(proof.shred2),
+    }
 }
 
 fn optimize_filters(filters: &mut [RpcFilterType]) {
@@ -2319,6 +2442,98 @@ fn encode_account<T: ReadableAccount>(
     }
 }
 
+fn encode_simulation_accounts(
+    config_accounts: Option<RpcSimulateTransactionAccountsConfig>,
+    number_of_accounts: usize,
+    simulation_failed: bool,
+    post_simulation_accounts: &[(Pubkey, AccountSharedData)],
+) -> Result<Option<Vec<Option<UiAccount>>>> {
+    let Some(config_accounts) = config_accounts else {
+        return Ok(None);
+    };
+    let accounts_encoding = config_accounts
+        .encoding
+        .unwrap_or(UiAccountEncoding::Base64);
+
+    if accounts_encoding == UiAccountEncoding::Binary
+        || accounts_encoding == UiAccountEncoding::Base58
+    {
+        return Err(Error::invalid_params("base58 encoding not supported"));
+    }
+
+    if config_accounts.addresses.len() > number_of_accounts {
+        return Err(Error::invalid_params(format!(
+            "Too many accounts provided; max {number_of_accounts}"
+        )));
+    }
+
+    if simulation_failed {
+        Ok(Some(vec![None; config_accounts.addresses.len()]))
+    } else {
+        Ok(Some(
+            config_accounts
+                .addresses
+                .iter()
+                .map(|address_str| {
+                    let address = verify_pubkey(address_str)?;
+                    post_simulation_accounts
+                        .iter()
+                        .find(|(key, _account)| key == &address)
+                        .map(|(pubkey, account)| {
+                            encode_account(account, pubkey, accounts_encoding, None)
+                        })
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ))
+    }
+}
+
+fn verify_account_overrides(
+    account_overrides: HashMap<String, RpcSimulateTransactionAccountOverride>,
+) -> Result<HashMap<Pubkey, AccountSharedData>> {
+    if account_overrides.len() > MAX_SIMULATE_ACCOUNT_OVERRIDES {
+        return Err(Error::invalid_params(format!(
+            "Too many account overrides provided; max {MAX_SIMULATE_ACCOUNT_OVERRIDES}"
+        )));
+    }
+    account_overrides
+        .into_iter()
+        .map(|(address, account_override)| {
+            let pubkey = verify_pubkey(&address)?;
+            let RpcSimulateTransactionAccountOverride {
+                lamports,
+                owner,
+                data,
+                executable,
+                rent_epoch,
+            } = account_override;
+            let account = UiAccount {
+                lamports,
+                data,
+                owner,
+                executable,
+                rent_epoch: rent_epoch.unwrap_or(RENT_EXEMPT_RENT_EPOCH),
+                space: None,
+            }
+            .decode::<AccountSharedData>()
+            .ok_or_else(|| {
+                Error::invalid_params(format!(
+                    "invalid account override for {address}: owner must be a valid pubkey and \
+                     data must be base58, base64 or base64+zstd encoded"
+                ))
+            })?;
+            if account.data().len() as u64 > system_instruction::MAX_PERMITTED_DATA_LENGTH {
+                return Err(Error::invalid_params(format!(
+                    "invalid account override for {address}: data exceeds {} bytes",
+                    system_instruction::MAX_PERMITTED_DATA_LENGTH
+                )));
+            }
+            Ok((pubkey, account))
+        })
+        .collect()
+}
+
 /// Analyze custom filters to determine if the result will be a subset of spl-token accounts by
 /// owner.
 /// NOTE: `optimize_filters()` should almost always be called before using this method because of
@@ -3324,6 +3539,14 @@ pub mod rpc_full {
             config: Option<RpcSimulateTransactionConfig>,
         ) -> Result<RpcResponse<RpcSimulateTransactionResult>>;
 
+        #[rpc(meta, name = "simulateBundle")]
+        fn simulate_bundle(
+            &self,
+            meta: Self::Metadata,
+            data: Vec<String>,
+            config: Option<RpcSimulateBundleConfig>,
+        ) -> Result<RpcResponse<Vec<RpcSimulateTransactionResult>>>;
+
         #[rpc(meta, name = "minimumLedgerSlot")]
         fn minimum_ledger_slot(&self, meta: Self::Metadata) -> Result<Slot>;
 
@@ -3415,6 +3638,22 @@ pub mod rpc_full {
             meta: Self::Metadata,
             pubkey_strs: Option<Vec<String>>,
         ) -> Result<Vec<RpcPrioritizationFee>>;
+
+        #[rpc(meta, name = "getDuplicateBlockProofs")]
+        fn get_duplicate_block_proofs(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcDuplicateBlockProofsConfig>,
+        ) -> Result<Vec<RpcDuplicateBlockProof>>;
+
+        #[rpc(meta, name = "getTransactionInclusionProof")]
+        fn get_transaction_inclusion_proof(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            signature_str: String,
+            config: Option<RpcTransactionInclusionProofConfig>,
+        ) -> Result<Option<RpcTransactionInclusionProof>>;
     }
 
     pub struct FullImpl;
@@ -3567,13 +3806,27 @@ pub mod rpc_full {
                 .get_blockhash_last_valid_block_height(&blockhash)
                 .unwrap_or(0);
 
-            let transaction =
+            let transaction = if let Some(mint) = &config.mint {
+                let mint = verify_pubkey(mint)?;
+                request_airdrop_tokens_transaction(
+                    &faucet_addr,
+                    &pubkey,
+                    &mint,
+                    lamports,
+                    blockhash,
+                )
+                .map_err(|err| {
+                    info!("request_airdrop_tokens_transaction failed: {:?}", err);
+                    Error::internal_error()
+                })?
+            } else {
                 request_airdrop_transaction(&faucet_addr, &pubkey, lamports, blockhash).map_err(
                     |err| {
                         info!("request_airdrop_transaction failed: {:?}", err);
                         Error::internal_error()
                     },
-                )?;
+                )?
+            };
 
             let wire_transaction = serialize(&transaction).map_err(|err| {
                 info!("request_airdrop: serialize error: {:?}", err);
@@ -3626,7 +3879,7 @@ pub mod rpc_full {
                 min_context_slot,
             })?;
 
-            let transaction = sanitize_transaction(unsanitized_tx, preflight_bank)?;
+            let transaction = sanitize_transaction_for_bank(unsanitized_tx, preflight_bank)?;
             let signature = *transaction.signature();
 
             let mut last_valid_block_height = preflight_bank
@@ -3720,7 +3973,10 @@ pub mod rpc_full {
                 encoding,
                 accounts: config_accounts,
                 min_context_slot,
+                account_overrides,
             } = config.unwrap_or_default();
+            let account_overrides =
+                verify_account_overrides(account_overrides.unwrap_or_default())?;
             let tx_encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
             let binary_encoding = tx_encoding.into_binary_encoding().ok_or_else(|| {
                 Error::invalid_params(format!(
@@ -3745,7 +4001,7 @@ pub mod rpc_full {
                     .set_recent_blockhash(bank.last_blockhash());
             }
 
-            let transaction = sanitize_transaction(unsanitized_tx, bank)?;
+            let transaction = sanitize_transaction_for_bank(unsanitized_tx, bank)?;
             if sig_verify {
                 verify_transaction(&transaction, &bank.feature_set)?;
             }
@@ -3757,48 +4013,14 @@ pub mod rpc_full {
                 post_simulation_accounts,
                 units_consumed,
                 return_data,
-            } = bank.simulate_transaction(transaction);
+            } = bank.simulate_transaction_with_account_overrides(transaction, &account_overrides);
 
-            let accounts = if let Some(config_accounts) = config_accounts {
-                let accounts_encoding = config_accounts
-                    .encoding
-                    .unwrap_or(UiAccountEncoding::Base64);
-
-                if accounts_encoding == UiAccountEncoding::Binary
-                    || accounts_encoding == UiAccountEncoding::Base58
-                {
-                    return Err(Error::invalid_params("base58 encoding not supported"));
-                }
-
-                if config_accounts.addresses.len() > number_of_accounts {
-                    return Err(Error::invalid_params(format!(
-                        "Too many accounts provided; max {number_of_accounts}"
-                    )));
-                }
-
-                if result.is_err() {
-                    Some(vec![None; config_accounts.addresses.len()])
-                } else {
-                    Some(
-                        config_accounts
-                            .addresses
-                            .iter()
-                            .map(|address_str| {
-                                let address = verify_pubkey(address_str)?;
-                                post_simulation_accounts
-                                    .iter()
-                                    .find(|(key, _account)| key == &address)
-                                    .map(|(pubkey, account)| {
-                                        encode_account(account, pubkey, accounts_encoding, None)
-                                    })
-                                    .transpose()
-                            })
-                            .collect::<Result<Vec<_>>>()?,
-                    )
-                }
-            } else {
-                None
-            };
+            let accounts = encode_simulation_accounts(
+                config_accounts,
+                number_of_accounts,
+                result.is_err(),
+                &post_simulation_accounts,
+            )?;
 
             Ok(new_response(
                 bank,
@@ -3812,6 +4034,101 @@ pub mod rpc_full {
             ))
         }
 
+        fn simulate_bundle(
+            &self,
+            meta: Self::Metadata,
+            data: Vec<String>,
+            config: Option<RpcSimulateBundleConfig>,
+        ) -> Result<RpcResponse<Vec<RpcSimulateTransactionResult>>> {
+            debug!("simulate_bundle rpc request received");
+            let RpcSimulateBundleConfig {
+                sig_verify,
+                replace_recent_blockhash,
+                commitment,
+                encoding,
+                accounts: config_accounts,
+                min_context_slot,
+            } = config.unwrap_or_default();
+            if data.is_empty() {
+                return Err(Error::invalid_params("No transactions provided"));
+            }
+            if data.len() > MAX_SIMULATE_BUNDLE_TRANSACTIONS {
+                return Err(Error::invalid_params(format!(
+                    "Too many transactions provided; max {MAX_SIMULATE_BUNDLE_TRANSACTIONS}"
+                )));
+            }
+            if !config_accounts.is_empty() && config_accounts.len() != data.len() {
+                return Err(Error::invalid_params(format!(
+                    "accounts must be empty or contain one entry per transaction; expected {}",
+                    data.len()
+                )));
+            }
+            if replace_recent_blockhash && sig_verify {
+                return Err(Error::invalid_params(
+                    "sigVerify may not be used with replaceRecentBlockhash",
+                ));
+            }
+            let tx_encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
+            let binary_encoding = tx_encoding.into_binary_encoding().ok_or_else(|| {
+                Error::invalid_params(format!(
+                    "unsupported encoding: {tx_encoding}. Supported encodings: base58, base64"
+                ))
+            })?;
+
+            let bank = &*meta.get_bank_with_config(RpcContextConfig {
+                commitment,
+                min_context_slot,
+            })?;
+            let transactions = data
+                .into_iter()
+                .map(|data| {
+                    let (_, mut unsanitized_tx) =
+                        decode_and_deserialize::<VersionedTransaction>(data, binary_encoding)?;
+                    if replace_recent_blockhash {
+                        unsanitized_tx
+                            .message
+                            .set_recent_blockhash(bank.last_blockhash());
+                    }
+                    let transaction = sanitize_transaction_for_bank(unsanitized_tx, bank)?;
+                    if sig_verify {
+                        verify_transaction(&transaction, &bank.feature_set)?;
+                    }
+                    Ok(transaction)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut config_accounts = config_accounts.into_iter();
+            let results = bank
+                .simulate_bundle(&transactions)
+                .into_iter()
+                .zip(transactions.iter())
+                .map(|(simulation_result, transaction)| {
+                    let TransactionSimulationResult {
+                        result,
+                        logs,
+                        post_simulation_accounts,
+                        units_consumed,
+                        return_data,
+                    } = simulation_result;
+                    let accounts = encode_simulation_accounts(
+                        config_accounts.next().flatten(),
+                        transaction.message().account_keys().len(),
+                        result.is_err(),
+                        &post_simulation_accounts,
+                    )?;
+                    Ok(RpcSimulateTransactionResult {
+                        err: result.err(),
+                        logs: Some(logs),
+                        accounts,
+                        units_consumed: Some(units_consumed),
+                        return_data: return_data.map(|return_data| return_data.into()),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(new_response(bank, results))
+        }
+
         fn minimum_ledger_slot(&self, meta: Self::Metadata) -> Result<Slot> {
             debug!("minimum_ledger_slot rpc request received");
             meta.minimum_ledger_slot()
@@ -4022,6 +4339,42 @@ pub mod rpc_full {
                 .collect::<Result<Vec<_>>>()?;
             meta.get_recent_prioritization_fees(pubkeys)
         }
+
+        fn get_duplicate_block_proofs(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcDuplicateBlockProofsConfig>,
+        ) -> Result<Vec<RpcDuplicateBlockProof>> {
+            debug!("get_duplicate_block_proofs rpc request received: {config:?}");
+            let config = config.unwrap_or_default();
+            let limit = config.limit.unwrap_or(MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT);
+            if limit > MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT {
+                return Err(Error::invalid_params(format!(
+                    "Invalid limit; max {MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT}"
+                )));
+            }
+            let leader = config.leader.as_deref().map(verify_pubkey).transpose()?;
+            meta.get_duplicate_block_proofs(config.start_slot.unwrap_or_default(), leader, limit)
+        }
+
+        fn get_transaction_inclusion_proof(
+            &self,
+            meta: Self::Metadata,
+            slot: Slot,
+            signature_str: String,
+            config: Option<RpcTransactionInclusionProofConfig>,
+        ) -> Result<Option<RpcTransactionInclusionProof>> {
+            debug!(
+                "get_transaction_inclusion_proof rpc request received: {:?} {:?}",
+                slot, signature_str
+            );
+            let signature = verify_signature(&signature_str)?;
+            meta.get_transaction_inclusion_proof(
+                slot,
+                signature,
+                config.and_then(|config| config.commitment),
+            )
+        }
     }
 }
 
@@ -4521,6 +4874,39 @@ fn sanitize_transaction(
         .map_err(|err| Error::invalid_params(format!("invalid transaction: {err}")))
 }
 
+/// Sanitizes a transaction against `bank`, having the bank's `MissingAccountsLoader`, if any,
+/// fetch the accounts the transaction references first, so that banking doesn't wait for them
+fn sanitize_transaction_for_bank(
+    transaction: VersionedTransaction,
+    bank: &Bank,
+) -> Result<SanitizedTransaction> {
+    let address_table_lookups = transaction
+        .message
+        .address_table_lookups()
+        .unwrap_or_default();
+    let mut pubkeys = transaction.message.static_account_keys().to_vec();
+    pubkeys.extend(
+        address_table_lookups
+            .iter()
+            .map(|address_table_lookup| address_table_lookup.account_key),
+    );
+    bank.prefetch_missing_accounts(&pubkeys);
+    let has_address_table_lookups = !address_table_lookups.is_empty();
+
+    let transaction = sanitize_transaction(transaction, bank)?;
+    // The addresses loaded from lookup tables are only known once the tables are
+    if has_address_table_lookups {
+        let pubkeys: Vec<Pubkey> = transaction
+            .message()
+            .account_keys()
+            .iter()
+            .copied()
+            .collect();
+        bank.prefetch_missing_accounts(&pubkeys);
+    }
+    Ok(transaction)
+}
+
 pub fn create_validator_exit(exit: Arc<AtomicBool>) -> Arc<RwLock<Exit>> {
     let mut validator_exit = Exit::default();
     validator_exit.register_exit(Box::new(move || exit.store(true, Ordering::Relaxed)));
@@ -4635,7 +5021,11 @@ pub mod tests {
         solana_ledger::{
             blockstore_meta::PerfSampleV2,
             blockstore_processor::fill_blockstore_slot_with_ticks,
-            genesis_utils::{create_genesis_config, GenesisConfigInfo},
+            genesis_utils::{
+                bootstrap_validator_stake_lamports, create_genesis_config,
+                create_genesis_config_with_leader, GenesisConfigInfo,
+            },
+            shred::{MerkleInclusionProof, ProcessShredsStats, ReedSolomonCache, Shred, Shredder},
         },
         solana_rpc_client_api::{
             custom_error::{
@@ -4658,7 +5048,7 @@ pub mod tests {
             clock::MAX_RECENT_BLOCKHASHES,
             compute_budget::ComputeBudgetInstruction,
             fee_calculator::{FeeRateGovernor, DEFAULT_BURN_PERCENT},
-            hash::{hash, Hash},
+            hash::{hash, hashv, Hash},
             instruction::InstructionError,
             message::{
                 v0::{self, MessageAddressTableLookup},
@@ -4666,7 +5056,7 @@ pub mod tests {
             },
             nonce::{self, state::DurableNonce},
             rpc_port,
-            signature::{Keypair, Signer},
+            signature::{Keypair, Signer, SIGNATURE_BYTES},
             slot_hashes::SlotHashes,
             system_program, system_transaction,
             timing::slot_duration_from_slots_per_year,
@@ -4747,6 +5137,7 @@ pub mod tests {
         meta: JsonRpcRequestProcessor,
         identity: Pubkey,
         mint_keypair: Keypair,
+        leader_keypair: Keypair,
         leader_vote_keypair: Arc<Keypair>,
         blockstore: Arc<Blockstore>,
         bank_forks: Arc<RwLock<BankForks>>,
@@ -4764,10 +5155,13 @@ pub mod tests {
         }
 
         fn start_with_config(config: JsonRpcConfig) -> Self {
-            let (bank_forks, mint_keypair, leader_vote_keypair) =
-                new_bank_forks_with_config(BankTestConfig {
+            let leader_keypair = Keypair::new();
+            let (bank_forks, mint_keypair, leader_vote_keypair) = new_bank_forks_with_config(
+                BankTestConfig {
                     secondary_indexes: config.account_indexes.clone(),
-                });
+                },
+                &leader_keypair.pubkey(),
+            );
 
             let ledger_path = get_tmp_ledger_path!();
             let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
@@ -4821,6 +5215,7 @@ pub mod tests {
                 meta,
                 identity,
                 mint_keypair,
+                leader_keypair,
                 leader_vote_keypair,
                 bank_forks,
                 blockstore,
@@ -6106,6 +6501,173 @@ pub mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_rpc_simulate_transaction_with_account_overrides() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let rent_exempt_amount = bank.get_minimum_balance_for_rent_exemption(0);
+        let recent_blockhash = bank.confirmed_last_blockhash();
+        let RpcHandler {
+            ref meta, ref io, ..
+        } = rpc;
+
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let tx = system_transaction::transfer(
+            &rpc.mint_keypair,
+            &bob_pubkey,
+            rent_exempt_amount,
+            recent_blockhash,
+        );
+        let tx_serialized_encoded = BASE64_STANDARD.encode(serialize(&tx).unwrap());
+
+        bank.freeze();
+
+        // Bob does not exist on-chain, but simulates as if he held 1 SOL
+        let req = format!(
+            r#"{{"jsonrpc":"2.0",
+                 "id":1,
+                 "method":"simulateTransaction",
+                 "params":[
+                   "{tx_serialized_encoded}",
+                   {{
+                     "encoding": "base64",
+                     "accounts": {{"addresses": ["{bob_pubkey}"]}},
+                     "accountOverrides": {{
+                       "{bob_pubkey}": {{
+                         "lamports": 1000000000,
+                         "owner": "11111111111111111111111111111111",
+                         "data": ["", "base64"]
+                       }}
+                     }}
+                   }}
+                 ]
+            }}"#,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: RpcResponse<RpcSimulateTransactionResult> =
+            parse_success_result(serde_json::from_str(&res.unwrap()).unwrap());
+        assert_eq!(result.value.err, None);
+        assert_eq!(
+            result.value.accounts.unwrap()[0].as_ref().unwrap().lamports,
+            1_000_000_000 + rent_exempt_amount
+        );
+        assert_eq!(bank.get_balance(&bob_pubkey), 0);
+
+        // Overrides must name a valid owner
+        let req = format!(
+            r#"{{"jsonrpc":"2.0",
+                 "id":1,
+                 "method":"simulateTransaction",
+                 "params":[
+                   "{tx_serialized_encoded}",
+                   {{
+                     "encoding": "base64",
+                     "accountOverrides": {{
+                       "{bob_pubkey}": {{
+                         "lamports": 1,
+                         "owner": "not a pubkey",
+                         "data": ["", "base64"]
+                       }}
+                     }}
+                   }}
+                 ]
+            }}"#,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let (code, message) = parse_failure_response(serde_json::from_str(&res.unwrap()).unwrap());
+        assert_eq!(code, error::ErrorCode::InvalidParams.code());
+        assert!(message.starts_with(&format!("invalid account override for {bob_pubkey}")));
+    }
+
+    #[test]
+    fn test_rpc_simulate_bundle() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let rent_exempt_amount = bank.get_minimum_balance_for_rent_exemption(0);
+        let recent_blockhash = bank.confirmed_last_blockhash();
+        let RpcHandler {
+            ref meta, ref io, ..
+        } = rpc;
+
+        // Bob only exists once the first transaction has funded him
+        let bob = Keypair::new();
+        let carol_pubkey = solana_sdk::pubkey::new_rand();
+        let fund_bob = system_transaction::transfer(
+            &rpc.mint_keypair,
+            &bob.pubkey(),
+            rent_exempt_amount * 2,
+            recent_blockhash,
+        );
+        let bob_pays_carol = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &bob.pubkey(),
+                &carol_pubkey,
+                rent_exempt_amount,
+            )],
+            Some(&rpc.mint_keypair.pubkey()),
+            &[&rpc.mint_keypair, &bob],
+            recent_blockhash,
+        );
+        let fund_bob = BASE64_STANDARD.encode(serialize(&fund_bob).unwrap());
+        let bob_pays_carol = BASE64_STANDARD.encode(serialize(&bob_pays_carol).unwrap());
+
+        bank.freeze();
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0",
+                 "id":1,
+                 "method":"simulateBundle",
+                 "params":[
+                   ["{fund_bob}", "{bob_pays_carol}"],
+                   {{
+                     "sigVerify": true,
+                     "encoding": "base64",
+                     "accounts": [
+                       null,
+                       {{"addresses": ["{}", "{carol_pubkey}"]}}
+                     ]
+                   }}
+                 ]
+            }}"#,
+            bob.pubkey(),
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: RpcResponse<Vec<RpcSimulateTransactionResult>> =
+            parse_success_result(serde_json::from_str(&res.unwrap()).unwrap());
+        let results = result.value;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].err, None);
+        assert_eq!(results[0].accounts, None);
+        assert_eq!(results[1].err, None);
+        let accounts = results[1].accounts.as_ref().unwrap();
+        assert_eq!(accounts[0].as_ref().unwrap().lamports, rent_exempt_amount);
+        assert_eq!(accounts[1].as_ref().unwrap().lamports, rent_exempt_amount);
+
+        // Nothing was committed, so Bob cannot pay Carol on his own
+        assert_eq!(bank.get_balance(&bob.pubkey()), 0);
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateBundle","params":[["{bob_pays_carol}", "{fund_bob}"], {{"encoding": "base64"}}]}}"#,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: RpcResponse<Vec<RpcSimulateTransactionResult>> =
+            parse_success_result(serde_json::from_str(&res.unwrap()).unwrap());
+        let results = result.value;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].err.is_some());
+
+        // Accounts configs must line up with the transactions
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateBundle","params":[["{fund_bob}", "{bob_pays_carol}"], {{"encoding": "base64", "accounts": [null]}}]}}"#,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let (code, message) = parse_failure_response(serde_json::from_str(&res.unwrap()).unwrap());
+        assert_eq!(code, error::ErrorCode::InvalidParams.code());
+        assert_eq!(
+            message,
+            "accounts must be empty or contain one entry per transaction; expected 2"
+        );
+    }
+
     #[test]
     #[should_panic(expected = "simulation bank must be frozen")]
     fn test_rpc_simulate_transaction_panic_on_unfrozen_bank() {
@@ -6595,18 +7157,23 @@ pub mod tests {
     }
 
     fn new_bank_forks() -> (Arc<RwLock<BankForks>>, Keypair, Arc<Keypair>) {
-        new_bank_forks_with_config(BankTestConfig::default())
+        new_bank_forks_with_config(BankTestConfig::default(), &solana_sdk::pubkey::new_rand())
     }
 
     fn new_bank_forks_with_config(
         config: BankTestConfig,
+        leader_pubkey: &Pubkey,
     ) -> (Arc<RwLock<BankForks>>, Keypair, Arc<Keypair>) {
         let GenesisConfigInfo {
             mut genesis_config,
             mint_keypair,
             voting_keypair,
             ..
-        } = create_genesis_config(TEST_MINT_LAMPORTS);
+        } = create_genesis_config_with_leader(
+            TEST_MINT_LAMPORTS,
+            leader_pubkey,
+            bootstrap_validator_stake_lamports(),
+        );
 
         genesis_config.rent.lamports_per_byte_year = 50;
         genesis_config.rent.exemption_threshold = 2.0;
@@ -7122,6 +7689,203 @@ pub mod tests {
         assert_eq!(result, Vec::<Slot>::new());
     }
 
+    #[test]
+    fn test_get_duplicate_block_proofs() {
+        let rpc = RpcHandler::start();
+        let other_leader = Pubkey::new_unique();
+        // Stored without the leader, which is then taken from the leader schedule.
+        rpc.blockstore
+            .store_duplicate_slot(3, vec![1, 2], vec![3, 4])
+            .unwrap();
+        rpc.blockstore
+            .store_duplicate_slot_with_leader(5, vec![5, 6], vec![7, 8], other_leader)
+            .unwrap();
+        let proof_at_3 = RpcDuplicateBlockProof {
+            slot: 3,
+            leader: Some(rpc.leader_pubkey().to_string()),
+            shred1: BASE64_STANDARD.encode([1, 2]),
+            shred2: BASE64_STANDARD.encode([3, 4]),
+        };
+        let proof_at_5 = RpcDuplicateBlockProof {
+            slot: 5,
+            leader: Some(other_leader.to_string()),
+            shred1: BASE64_STANDARD.encode([5, 6]),
+            shred2: BASE64_STANDARD.encode([7, 8]),
+        };
+
+        let request = create_test_request("getDuplicateBlockProofs", None);
+        let result: Vec<RpcDuplicateBlockProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![proof_at_3.clone(), proof_at_5.clone()]);
+
+        let request = create_test_request(
+            "getDuplicateBlockProofs",
+            Some(json!([{"startSlot": 4u64}])),
+        );
+        let result: Vec<RpcDuplicateBlockProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![proof_at_5.clone()]);
+
+        let request = create_test_request(
+            "getDuplicateBlockProofs",
+            Some(json!([{"leader": other_leader.to_string()}])),
+        );
+        let result: Vec<RpcDuplicateBlockProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![proof_at_5.clone()]);
+
+        // Only a range of slots is scanned for a leader's proofs
+        let far_slot = 5 + MAX_GET_DUPLICATE_BLOCK_PROOFS_LEADER_SLOT_RANGE;
+        rpc.blockstore
+            .store_duplicate_slot_with_leader(far_slot, vec![9], vec![10], other_leader)
+            .unwrap();
+        let request = create_test_request(
+            "getDuplicateBlockProofs",
+            Some(json!([{"leader": other_leader.to_string(), "startSlot": 5u64}])),
+        );
+        let result: Vec<RpcDuplicateBlockProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![proof_at_5]);
+        let request = create_test_request(
+            "getDuplicateBlockProofs",
+            Some(json!([{"leader": other_leader.to_string(), "startSlot": 6u64}])),
+        );
+        let result: Vec<RpcDuplicateBlockProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].slot, far_slot);
+
+        let request = create_test_request("getDuplicateBlockProofs", Some(json!([{"limit": 1}])));
+        let result: Vec<RpcDuplicateBlockProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![proof_at_3]);
+
+        let request = create_test_request(
+            "getDuplicateBlockProofs",
+            Some(json!([{"limit": MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT + 1}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            format!("Invalid limit; max {MAX_GET_DUPLICATE_BLOCK_PROOFS_LIMIT}"),
+        );
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn test_get_transaction_inclusion_proof() {
+        let rpc = RpcHandler::start();
+        // The block is shredded and signed by the slot leader, so that its proofs verify.
+        let bank = rpc.working_bank();
+        let (keypair1, keypair2, keypair3) = (Keypair::new(), Keypair::new(), Keypair::new());
+        let (entries, signatures) = create_test_transaction_entries(
+            vec![&rpc.mint_keypair, &keypair1, &keypair2, &keypair3],
+            bank.clone(),
+        );
+        let (shreds, _) = Shredder::new(bank.slot(), bank.parent_slot(), 0, 0)
+            .unwrap()
+            .entries_to_shreds(
+                &rpc.leader_keypair,
+                &entries,
+                true, // is_last_in_slot
+                0,    // next_shred_index
+                0,    // next_code_index
+                true, // merkle_variant
+                &ReedSolomonCache::default(),
+                &mut ProcessShredsStats::default(),
+            );
+        rpc.blockstore.insert_shreds(shreds, None, false).unwrap();
+        rpc.blockstore
+            .set_roots(std::iter::once(&bank.slot()))
+            .unwrap();
+
+        let request = create_test_request(
+            "getTransactionInclusionProof",
+            Some(json!([0u64, signatures[1].to_string()])),
+        );
+        let result: Option<RpcTransactionInclusionProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let result = result.unwrap();
+        assert_eq!(result.slot, 0);
+        assert_eq!(result.leader, rpc.leader_pubkey().to_string());
+        assert_eq!(result.entry_index, 1);
+        let entry_bytes = BASE64_STANDARD.decode(&result.entry).unwrap();
+        let entry: Entry = deserialize(&entry_bytes).unwrap();
+        assert_eq!(entry.transactions[0].signatures[0], signatures[1]);
+        assert!(!result.shreds.is_empty());
+        let leader = rpc.leader_pubkey();
+        assert_eq!(leader, rpc.leader_keypair.pubkey());
+        let mut data = vec![];
+        for rpc_shred in result.shreds {
+            let payload = BASE64_STANDARD.decode(&rpc_shred.shred).unwrap();
+            // Check the proof as returned, against the slot leader and the returned payload: the
+            // leaf is the hash of the payload past the signature, up to the merkle proof at its
+            // end.
+            let rpc_proof = MerkleInclusionProof {
+                index: rpc_shred.leaf_index,
+                leaf: Hash::from_str(&rpc_shred.leaf).unwrap(),
+                proof: rpc_shred
+                    .proof
+                    .iter()
+                    .map(|node| bs58::decode(node).into_vec().unwrap().try_into().unwrap())
+                    .collect(),
+                root: Hash::from_str(&rpc_shred.merkle_root).unwrap(),
+            };
+            let signature = Signature::from_str(&rpc_shred.signature).unwrap();
+            assert!(rpc_proof.verify(&signature, &leader));
+            assert!(!rpc_proof.verify(&signature, &Pubkey::new_unique()));
+            let proof_offset = payload.len() - rpc_proof.proof.len() * 20;
+            assert_eq!(
+                rpc_proof.leaf,
+                hashv(&[
+                    b"\x00SOLANA_MERKLE_SHREDS_LEAF",
+                    &payload[SIGNATURE_BYTES..proof_offset]
+                ])
+            );
+            assert_eq!(payload[..SIGNATURE_BYTES], signature.as_ref()[..]);
+            let shred = Shred::new_from_serialized_shred(payload).unwrap();
+            data.extend_from_slice(shred.data().unwrap());
+            let proof = shred.merkle_inclusion_proof().unwrap();
+            assert_eq!(rpc_proof, proof);
+            assert_eq!(rpc_shred.index, shred.index());
+            assert_eq!(rpc_shred.leaf_index, proof.index);
+            assert_eq!(rpc_shred.leaf, proof.leaf.to_string());
+            assert_eq!(
+                rpc_shred.proof,
+                proof
+                    .proof
+                    .iter()
+                    .map(|node| bs58::encode(node).into_string())
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(rpc_shred.merkle_root, proof.root.to_string());
+            assert_eq!(rpc_shred.signature, shred.signature().to_string());
+        }
+
+        // The shreds' data holds the entry at the returned offset.
+        assert_eq!(
+            data[result.entry_offset..result.entry_offset + entry_bytes.len()],
+            entry_bytes
+        );
+
+        // The transaction is not in the block.
+        let request = create_test_request(
+            "getTransactionInclusionProof",
+            Some(json!([0u64, Signature::new_unique().to_string()])),
+        );
+        let result: Option<RpcTransactionInclusionProof> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, None);
+
+        // The block is neither rooted nor confirmed.
+        let request = create_test_request(
+            "getTransactionInclusionProof",
+            Some(json!([1u64, signatures[1].to_string()])),
+        );
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE);
+    }
+
     #[test]
     fn test_get_block_time() {
         let rpc = RpcHandler::start();
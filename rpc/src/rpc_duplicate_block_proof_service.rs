@@ -0,0 +1,62 @@
+use {
+    crate::{rpc::rpc_duplicate_block_proof, rpc_subscriptions::RpcSubscriptions},
+    crossbeam_channel::RecvTimeoutError,
+    solana_ledger::{
+        blockstore::{Blockstore, DuplicateSlotProofReceiver},
+        leader_schedule_cache::LeaderScheduleCache,
+    },
+    std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+const DUPLICATE_BLOCK_PROOF_REPORT_SLEEP_MS: u64 = 100;
+
+/// Notifies pubsub subscribers of each duplicate block proof stored in
+/// blockstore.
+pub struct RpcDuplicateBlockProofService;
+impl RpcDuplicateBlockProofService {
+    pub fn spawn(
+        duplicate_slot_proof_receiver: DuplicateSlotProofReceiver,
+        blockstore: Arc<Blockstore>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
+        rpc_subscriptions: Arc<RpcSubscriptions>,
+        exit: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        Builder::new()
+            .name("solRpcDupBlock".to_string())
+            .spawn(move || loop {
+                // received exit signal, shutdown the service
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                match duplicate_slot_proof_receiver
+                    .recv_timeout(Duration::from_millis(DUPLICATE_BLOCK_PROOF_REPORT_SLEEP_MS))
+                {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        info!("RpcDuplicateBlockProofService channel disconnected, exiting.");
+                        break;
+                    }
+                    Ok(slot) => {
+                        let Some(proof) = blockstore.get_duplicate_slot(slot) else {
+                            continue;
+                        };
+                        let leader = proof
+                            .leader
+                            .or_else(|| leader_schedule_cache.slot_leader_at(slot, None));
+                        rpc_subscriptions.notify_duplicate_block_proof(rpc_duplicate_block_proof(
+                            slot, proof, leader,
+                        ));
+                    }
+                }
+            })
+            .unwrap()
+    }
+}
@@ -24,8 +24,8 @@ use {
             RpcTransactionLogsFilter,
         },
         response::{
-            Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
-            RpcSignatureResult, RpcVersionInfo, RpcVote, SlotInfo, SlotUpdate,
+            Response as RpcResponse, RpcBlockUpdate, RpcDuplicateBlockProof, RpcKeyedAccount,
+            RpcLogsResponse, RpcSignatureResult, RpcVersionInfo, RpcVote, SlotInfo, SlotUpdate,
         },
     },
     solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
@@ -245,6 +245,30 @@ pub trait RpcSolPubSub {
         meta: Option<Self::Metadata>,
         id: PubSubSubscriptionId,
     ) -> Result<bool>;
+
+    // Get notification when a duplicate block proof is stored
+    #[pubsub(
+        subscription = "duplicateBlockProofNotification",
+        subscribe,
+        name = "duplicateBlockProofSubscribe"
+    )]
+    fn duplicate_block_proof_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RpcDuplicateBlockProof>,
+    );
+
+    // Unsubscribe from duplicate block proof notification subscription.
+    #[pubsub(
+        subscription = "duplicateBlockProofNotification",
+        unsubscribe,
+        name = "duplicateBlockProofUnsubscribe"
+    )]
+    fn duplicate_block_proof_unsubscribe(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: PubSubSubscriptionId,
+    ) -> Result<bool>;
 }
 
 pub use internal::RpcSolPubSubInternal;
@@ -350,6 +374,14 @@ mod internal {
         #[rpc(name = "rootUnsubscribe")]
         fn root_unsubscribe(&self, id: SubscriptionId) -> Result<bool>;
 
+        // Get notification when a duplicate block proof is stored
+        #[rpc(name = "duplicateBlockProofSubscribe")]
+        fn duplicate_block_proof_subscribe(&self) -> Result<SubscriptionId>;
+
+        // Unsubscribe from duplicate block proof notification subscription.
+        #[rpc(name = "duplicateBlockProofUnsubscribe")]
+        fn duplicate_block_proof_unsubscribe(&self, id: SubscriptionId) -> Result<bool>;
+
         // Get the current solana version running on the node
         #[rpc(name = "getVersion")]
         fn get_version(&self) -> Result<RpcVersionInfo>;
@@ -590,6 +622,14 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
         self.unsubscribe(id)
     }
 
+    fn duplicate_block_proof_subscribe(&self) -> Result<SubscriptionId> {
+        self.subscribe(SubscriptionParams::DuplicateBlockProof)
+    }
+
+    fn duplicate_block_proof_unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
+        self.unsubscribe(id)
+    }
+
     fn get_version(&self) -> Result<RpcVersionInfo> {
         let version = solana_version::Version::default();
         Ok(RpcVersionInfo {
@@ -1405,6 +1445,38 @@ mod tests {
         assert!(rpc.vote_unsubscribe(sub_id).is_ok());
     }
 
+    #[test]
+    #[serial]
+    fn test_duplicate_block_proof_subscribe() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
+        let max_complete_rewards_slot = Arc::new(AtomicU64::default());
+        let rpc_subscriptions = Arc::new(RpcSubscriptions::default_with_bank_forks(
+            max_complete_transaction_status_slot,
+            max_complete_rewards_slot,
+            bank_forks,
+        ));
+        let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&rpc_subscriptions);
+        let sub_id = rpc.duplicate_block_proof_subscribe().unwrap();
+
+        rpc_subscriptions.notify_duplicate_block_proof(RpcDuplicateBlockProof {
+            slot: 7,
+            leader: Some(Pubkey::default().to_string()),
+            shred1: "AQI=".to_string(),
+            shred2: "AwQ=".to_string(),
+        });
+        let response = receiver.recv();
+        assert_eq!(
+            response,
+            r#"{"jsonrpc":"2.0","method":"duplicateBlockProofNotification","params":{"result":{"slot":7,"leader":"11111111111111111111111111111111","shred1":"AQI=","shred2":"AwQ="},"subscription":0}}"#
+        );
+
+        assert!(rpc.duplicate_block_proof_unsubscribe(42.into()).is_err());
+        assert!(rpc.duplicate_block_proof_unsubscribe(sub_id).is_ok());
+    }
+
     #[test]
     fn test_get_version() {
         let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
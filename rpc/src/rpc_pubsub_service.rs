@@ -131,6 +131,7 @@ struct SentNotificationStats {
     num_slots_updates: AtomicUsize,
     num_root: AtomicUsize,
     num_vote: AtomicUsize,
+    num_duplicate_block_proof: AtomicUsize,
     num_block: AtomicUsize,
     total_creation_to_queue_time_us: AtomicU64,
     last_report: AtomicInterval,
@@ -181,6 +182,11 @@ impl SentNotificationStats {
                     self.num_vote.swap(0, Ordering::Relaxed) as i64,
                     i64
                 ),
+                (
+                    "num_duplicate_block_proof",
+                    self.num_duplicate_block_proof.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
                 (
                     "num_block",
                     self.num_block.swap(0, Ordering::Relaxed) as i64,
@@ -232,6 +238,11 @@ fn increment_sent_notification_stats(
         SubscriptionParams::Vote => {
             stats.num_vote.fetch_add(1, Ordering::Relaxed);
         }
+        SubscriptionParams::DuplicateBlockProof => {
+            stats
+                .num_duplicate_block_proof
+                .fetch_add(1, Ordering::Relaxed);
+        }
         SubscriptionParams::Block(_) => {
             stats.num_block.fetch_add(1, Ordering::Relaxed);
         }
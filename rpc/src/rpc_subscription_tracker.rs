@@ -50,6 +50,7 @@ pub enum SubscriptionParams {
     SlotsUpdates,
     Root,
     Vote,
+    DuplicateBlockProof,
 }
 
 impl SubscriptionParams {
@@ -64,6 +65,7 @@ impl SubscriptionParams {
             SubscriptionParams::Block(_) => "blockNotification",
             SubscriptionParams::Root => "rootNotification",
             SubscriptionParams::Vote => "voteNotification",
+            SubscriptionParams::DuplicateBlockProof => "duplicateBlockProofNotification",
         }
     }
 
@@ -77,7 +79,8 @@ impl SubscriptionParams {
             SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
             | SubscriptionParams::Root
-            | SubscriptionParams::Vote => None,
+            | SubscriptionParams::Vote
+            | SubscriptionParams::DuplicateBlockProof => None,
         }
     }
 
@@ -91,7 +94,8 @@ impl SubscriptionParams {
             SubscriptionParams::Root
             | SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
-            | SubscriptionParams::Vote => return false,
+            | SubscriptionParams::Vote
+            | SubscriptionParams::DuplicateBlockProof => return false,
         };
         !commitment.is_confirmed()
     }
@@ -106,7 +110,8 @@ impl SubscriptionParams {
             SubscriptionParams::Root
             | SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
-            | SubscriptionParams::Vote => return false,
+            | SubscriptionParams::Vote
+            | SubscriptionParams::DuplicateBlockProof => return false,
         };
         commitment.is_confirmed()
     }
@@ -118,6 +123,7 @@ impl SubscriptionParams {
                 | SubscriptionParams::SlotsUpdates
                 | SubscriptionParams::Root
                 | SubscriptionParams::Vote
+                | SubscriptionParams::DuplicateBlockProof
         )
     }
 }
@@ -22,8 +22,8 @@ use {
     solana_rayon_threadlimit::get_thread_count,
     solana_rpc_client_api::response::{
         ProcessedSignatureResult, ReceivedSignatureResult, Response as RpcResponse, RpcBlockUpdate,
-        RpcBlockUpdateError, RpcKeyedAccount, RpcLogsResponse, RpcResponseContext,
-        RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
+        RpcBlockUpdateError, RpcDuplicateBlockProof, RpcKeyedAccount, RpcLogsResponse,
+        RpcResponseContext, RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
     },
     solana_runtime::{
         bank::{Bank, TransactionLogInfo},
@@ -96,6 +96,7 @@ pub enum NotificationEntry {
     SlotUpdate(SlotUpdate),
     Vote((Pubkey, VoteTransaction, Signature)),
     Root(Slot),
+    DuplicateBlockProof(RpcDuplicateBlockProof),
     Bank(CommitmentSlots),
     Gossip(Slot),
     SignaturesReceived((Slot, Vec<Signature>)),
@@ -107,6 +108,9 @@ impl std::fmt::Debug for NotificationEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             NotificationEntry::Root(root) => write!(f, "Root({root})"),
+            NotificationEntry::DuplicateBlockProof(proof) => {
+                write!(f, "DuplicateBlockProof({})", proof.slot)
+            }
             NotificationEntry::Vote(vote) => write!(f, "Vote({vote:?})"),
             NotificationEntry::Slot(slot_info) => write!(f, "Slot({slot_info:?})"),
             NotificationEntry::SlotUpdate(slot_update) => {
@@ -744,6 +748,10 @@ impl RpcSubscriptions {
         self.enqueue_notification(NotificationEntry::Vote((vote_pubkey, vote, signature)));
     }
 
+    pub fn notify_duplicate_block_proof(&self, proof: RpcDuplicateBlockProof) {
+        self.enqueue_notification(NotificationEntry::DuplicateBlockProof(proof));
+    }
+
     pub fn notify_roots(&self, mut rooted_slots: Vec<Slot>) {
         rooted_slots.sort_unstable();
         rooted_slots.into_iter().for_each(|root| {
@@ -855,6 +863,19 @@ impl RpcSubscriptions {
                                 notifier.notify(root, sub, false);
                             }
                         }
+                        NotificationEntry::DuplicateBlockProof(ref proof) => {
+                            if let Some(sub) = subscriptions
+                                .node_progress_watchers()
+                                .get(&SubscriptionParams::DuplicateBlockProof)
+                            {
+                                debug!("duplicate block proof notify: {}", proof.slot);
+                                inc_new_counter_info!(
+                                    "rpc-subscription-notify-duplicate-block-proof",
+                                    1
+                                );
+                                notifier.notify(proof, sub, false);
+                            }
+                        }
                         NotificationEntry::Bank(commitment_slots) => {
                             const SOURCE: &str = "bank";
                             RpcSubscriptions::notify_watchers(
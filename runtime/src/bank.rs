@@ -150,7 +150,7 @@ use {
         instruction::InstructionError,
         lamports::LamportsError,
         loader_v4::{self, LoaderV4State, LoaderV4Status},
-        message::{AccountKeys, SanitizedMessage},
+        message::{AccountKeys, SanitizedMessage, SimpleAddressLoader},
         native_loader,
         native_token::LAMPORTS_PER_SOL,
         nonce::{self, state::DurableNonce, NONCED_TX_MARKER_IX_INDEX},
@@ -195,7 +195,7 @@ use {
                 AtomicBool, AtomicI64, AtomicU64, AtomicUsize,
                 Ordering::{self, AcqRel, Acquire, Relaxed},
             },
-            Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard,
+            Arc, LockResult, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
         },
         thread::Builder,
         time::{Duration, Instant},
@@ -567,6 +567,7 @@ impl PartialEq for Bank {
             loaded_programs_cache: _,
             check_program_modification_slot: _,
             epoch_reward_status: _,
+            load_missing_accounts_lock: _,
             // Ignore new fields explicitly if they do not impact PartialEq.
             // Adding ".." will remove compile-time checks that if a new field
             // is added to the struct, this PartialEq is accordingly updated.
@@ -1073,6 +1074,7 @@ impl Bank {
             loaded_programs_cache: Arc::<RwLock<LoadedPrograms>>::default(),
             check_program_modification_slot: false,
             epoch_reward_status: EpochRewardStatus::default(),
+            load_missing_accounts_lock: Mutex::default(),
         };
 
         let accounts_data_size_initial = bank.get_total_accounts_stats().unwrap().data_len as u64;
@@ -1424,6 +1426,7 @@ impl Bank {
             loaded_programs_cache: parent.loaded_programs_cache.clone(),
             check_program_modification_slot: false,
             epoch_reward_status: parent.epoch_reward_status.clone(),
+            load_missing_accounts_lock: Mutex::default(),
         };
 
         let (_, ancestors_time_us) = measure_us!({
@@ -1859,6 +1862,7 @@ impl Bank {
             loaded_programs_cache: Arc::<RwLock<LoadedPrograms>>::default(),
             check_program_modification_slot: false,
             epoch_reward_status: EpochRewardStatus::default(),
+            load_missing_accounts_lock: Mutex::default(),
         };
         bank.finish_init(
             genesis_config,
@@ -2155,6 +2159,44 @@ impl Bank {
         }
     }
 
+    /// Applies the changes requested through `RuntimeConfig::bank_overrides`, if any
+    pub(crate) fn apply_bank_overrides(&self) {
+        let Some(bank_overrides) = &self.runtime_config.bank_overrides else {
+            return;
+        };
+        // A frozen bank, e.g. a warped one, can't be modified, the next bank applies the changes
+        if self.freeze_started() {
+            return;
+        }
+        for (pubkey, account) in bank_overrides.take_accounts() {
+            self.store_account_and_update_capitalization(&pubkey, &account);
+        }
+        if let Some(unix_timestamp) = bank_overrides.unix_timestamp() {
+            self.set_sysvar_for_tests(&sysvar::clock::Clock {
+                unix_timestamp,
+                ..self.clock()
+            });
+        } else if bank_overrides.take_unix_timestamp_cleared() {
+            // Unlike `update_clock`, don't keep the overridden timestamp if it is ahead
+            let max_allowable_drift = MaxAllowableDrift {
+                fast: MAX_ALLOWABLE_DRIFT_PERCENTAGE_FAST,
+                slow: MAX_ALLOWABLE_DRIFT_PERCENTAGE_SLOW_V2,
+            };
+            let clock = self.clock();
+            let epoch_start_slot = self.epoch_schedule().get_first_slot_in_epoch(clock.epoch);
+            let unix_timestamp = self
+                .get_timestamp_estimate(
+                    max_allowable_drift,
+                    Some((epoch_start_slot, clock.epoch_start_timestamp)),
+                )
+                .unwrap_or_else(|| self.unix_timestamp_from_genesis());
+            self.set_sysvar_for_tests(&sysvar::clock::Clock {
+                unix_timestamp,
+                ..self.clock()
+            });
+        }
+    }
+
     pub fn set_sysvar_for_tests<T>(&self, sysvar: &T)
     where
         T: Sysvar + SysvarId,
@@ -4349,17 +4391,148 @@ impl Bank {
         &self,
         transaction: SanitizedTransaction,
     ) -> TransactionSimulationResult {
-        let account_keys = transaction.message().account_keys();
-        let number_of_accounts = account_keys.len();
-        let account_overrides = self.get_account_overrides_for_simulation(&account_keys);
-        let batch = self.prepare_unlocked_batch_from_single_tx(&transaction);
+        let account_overrides =
+            self.get_account_overrides_for_simulation(&transaction.message().account_keys());
+        self.simulate_transaction_with_overrides(&transaction, &account_overrides, None)
+            .0
+    }
+
+    /// Run transactions against a frozen bank without committing the results, substituting the
+    /// given accounts for the bank's own state. Overridden programs must already be deployed.
+    pub fn simulate_transaction_with_account_overrides(
+        &self,
+        transaction: SanitizedTransaction,
+        overridden_accounts: &HashMap<Pubkey, AccountSharedData>,
+    ) -> TransactionSimulationResult {
+        assert!(self.is_frozen(), "simulation bank must be frozen");
+
+        let account_overrides = self.get_account_overrides_for_simulation_with(
+            &transaction.message().account_keys(),
+            overridden_accounts,
+        );
+        self.simulate_transaction_with_overrides(&transaction, &account_overrides, None)
+            .0
+    }
+
+    /// Run a sequence of transactions against a frozen bank without committing the results.
+    /// Each transaction observes the account writes of the transactions before it. Simulation
+    /// stops at the first transaction that fails, so the returned results may be shorter than
+    /// `transactions`.
+    pub fn simulate_bundle(
+        &self,
+        transactions: &[SanitizedTransaction],
+    ) -> Vec<TransactionSimulationResult> {
+        assert!(self.is_frozen(), "simulation bank must be frozen");
+
+        self.simulate_bundle_unchecked(transactions)
+    }
+
+    /// Run a sequence of transactions against a bank without committing the results; does not
+    /// check if the bank is frozen, enabling use in single-Bank test frameworks
+    pub fn simulate_bundle_unchecked(
+        &self,
+        transactions: &[SanitizedTransaction],
+    ) -> Vec<TransactionSimulationResult> {
+        // Writes are carried between transactions as account overrides rather than by
+        // committing to a child bank, so accounts-db never sees state for a slot that a real
+        // bank may later occupy. Programs deployed, upgraded or closed along the way are
+        // carried the same way replay carries them between the transactions of a batch, so
+        // they are subject to the same delayed visibility.
+        let mut bundle_accounts = HashMap::<Pubkey, AccountSharedData>::new();
+        let mut bundle_programs = LoadedProgramsForTxBatch::default();
+        let mut results = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            // Lookup tables are resolved again, since earlier transactions may have modified them.
+            let transaction = match self.load_addresses_for_bundle(transaction, &bundle_accounts) {
+                Ok(transaction) => transaction,
+                Err(err) => {
+                    results.push(TransactionSimulationResult {
+                        result: Err(err),
+                        logs: vec![],
+                        post_simulation_accounts: vec![],
+                        units_consumed: 0,
+                        return_data: None,
+                    });
+                    break;
+                }
+            };
+            let message = transaction.message();
+            let account_overrides = self.get_account_overrides_for_simulation_with(
+                &message.account_keys(),
+                &bundle_accounts,
+            );
+
+            let (simulation_result, programs_modified_by_tx) = self
+                .simulate_transaction_with_overrides(
+                    &transaction,
+                    &account_overrides,
+                    Some(&bundle_programs),
+                );
+            let failed = simulation_result.result.is_err();
+            if !failed {
+                for (index, (pubkey, account)) in simulation_result
+                    .post_simulation_accounts
+                    .iter()
+                    .enumerate()
+                {
+                    if message.is_writable(index) {
+                        bundle_accounts.insert(*pubkey, account.clone());
+                    }
+                }
+                bundle_programs.merge(&programs_modified_by_tx);
+            }
+            results.push(simulation_result);
+            if failed {
+                break;
+            }
+        }
+        results
+    }
+
+    /// Resolves the lookup tables of `transaction` against the bank, preferring the tables in
+    /// `bundle_accounts`
+    fn load_addresses_for_bundle<'a>(
+        &self,
+        transaction: &'a SanitizedTransaction,
+        bundle_accounts: &HashMap<Pubkey, AccountSharedData>,
+    ) -> Result<Cow<'a, SanitizedTransaction>> {
+        let SanitizedMessage::V0(message) = transaction.message() else {
+            return Ok(Cow::Borrowed(transaction));
+        };
+        let address_table_lookups = &message.message.address_table_lookups;
+        if !address_table_lookups
+            .iter()
+            .any(|lookup| bundle_accounts.contains_key(&lookup.account_key))
+        {
+            return Ok(Cow::Borrowed(transaction));
+        }
+        let loaded_addresses =
+            self.load_addresses_with_overrides(address_table_lookups, bundle_accounts)?;
+        SanitizedTransaction::try_create(
+            transaction.to_versioned_transaction(),
+            MessageHash::Precomputed(*transaction.message_hash()),
+            Some(transaction.is_simple_vote_transaction()),
+            SimpleAddressLoader::Enabled(loaded_addresses),
+        )
+        .map(Cow::Owned)
+    }
+
+    /// Simulates `transaction`, returning along with the result the programs it modified
+    fn simulate_transaction_with_overrides(
+        &self,
+        transaction: &SanitizedTransaction,
+        account_overrides: &AccountOverrides,
+        programs_modified_by_bundle: Option<&LoadedProgramsForTxBatch>,
+    ) -> (TransactionSimulationResult, LoadedProgramsForTxBatch) {
+        let number_of_accounts = transaction.message().account_keys().len();
+        let batch = self.prepare_unlocked_batch_from_single_tx(transaction);
         let mut timings = ExecuteTimings::default();
 
         let LoadAndExecuteTransactionsOutput {
             loaded_transactions,
             mut execution_results,
             ..
-        } = self.load_and_execute_transactions(
+        } = self.load_and_execute_transactions_with_programs(
             &batch,
             // After simulation, transactions will need to be forwarded to the leader
             // for processing. During forwarding, the transaction could expire if the
@@ -4369,8 +4542,9 @@ impl Bank {
             true,
             true,
             &mut timings,
-            Some(&account_overrides),
+            Some(account_overrides),
             None,
+            programs_modified_by_bundle,
         );
 
         let post_simulation_accounts = loaded_transactions
@@ -4400,21 +4574,46 @@ impl Bank {
 
         let execution_result = execution_results.pop().unwrap();
         let flattened_result = execution_result.flattened_result();
-        let (logs, return_data) = match execution_result {
-            TransactionExecutionResult::Executed { details, .. } => {
-                (details.log_messages, details.return_data)
+        let (logs, return_data, programs_modified_by_tx) = match execution_result {
+            TransactionExecutionResult::Executed {
+                details,
+                programs_modified_by_tx,
+                ..
+            } => (
+                details.log_messages,
+                details.return_data,
+                *programs_modified_by_tx,
+            ),
+            TransactionExecutionResult::NotExecuted(_) => {
+                (None, None, LoadedProgramsForTxBatch::default())
             }
-            TransactionExecutionResult::NotExecuted(_) => (None, None),
         };
         let logs = logs.unwrap_or_default();
 
-        TransactionSimulationResult {
-            result: flattened_result,
-            logs,
-            post_simulation_accounts,
-            units_consumed,
-            return_data,
+        (
+            TransactionSimulationResult {
+                result: flattened_result,
+                logs,
+                post_simulation_accounts,
+                units_consumed,
+                return_data,
+            },
+            programs_modified_by_tx,
+        )
+    }
+
+    fn get_account_overrides_for_simulation_with(
+        &self,
+        account_keys: &AccountKeys,
+        overridden_accounts: &HashMap<Pubkey, AccountSharedData>,
+    ) -> AccountOverrides {
+        let mut account_overrides = self.get_account_overrides_for_simulation(account_keys);
+        // Every override is passed through, not just the transaction's own keys, since
+        // upgradeable programs execute from a programdata account the transaction may not list
+        for (pubkey, account) in overridden_accounts {
+            account_overrides.set_account(pubkey, Some(account.clone()));
         }
+        account_overrides
     }
 
     fn get_account_overrides_for_simulation(&self, account_keys: &AccountKeys) -> AccountOverrides {
@@ -4630,8 +4829,18 @@ impl Bank {
         }
     }
 
-    fn load_program_accounts(&self, pubkey: &Pubkey) -> ProgramAccountLoadResult {
-        let program_account = match self.get_account_with_fixed_root(pubkey) {
+    fn load_program_accounts(
+        &self,
+        pubkey: &Pubkey,
+        account_overrides: Option<&AccountOverrides>,
+    ) -> ProgramAccountLoadResult {
+        let get_account = |pubkey: &Pubkey| {
+            account_overrides
+                .and_then(|overrides| overrides.get(pubkey))
+                .cloned()
+                .or_else(|| self.get_account_with_fixed_root(pubkey))
+        };
+        let program_account = match get_account(pubkey) {
             None => return ProgramAccountLoadResult::AccountNotFound,
             Some(account) => account,
         };
@@ -4658,7 +4867,7 @@ impl Bank {
             programdata_address,
         }) = program_account.state()
         {
-            let programdata_account = match self.get_account_with_fixed_root(&programdata_address) {
+            let programdata_account = match get_account(&programdata_address) {
                 None => return ProgramAccountLoadResult::AccountNotFound,
                 Some(account) => account,
             };
@@ -4679,6 +4888,15 @@ impl Bank {
     }
 
     pub fn load_program(&self, pubkey: &Pubkey, reload: bool) -> Arc<LoadedProgram> {
+        self.load_program_with_account_overrides(pubkey, reload, None)
+    }
+
+    fn load_program_with_account_overrides(
+        &self,
+        pubkey: &Pubkey,
+        reload: bool,
+        account_overrides: Option<&AccountOverrides>,
+    ) -> Arc<LoadedProgram> {
         let loaded_programs_cache = self.loaded_programs_cache.read().unwrap();
         let environments = loaded_programs_cache.get_environments_for_epoch(self.epoch);
 
@@ -4687,7 +4905,7 @@ impl Bank {
             ..LoadProgramMetrics::default()
         };
 
-        let loaded_program = match self.load_program_accounts(pubkey) {
+        let loaded_program = match self.load_program_accounts(pubkey, account_overrides) {
             ProgramAccountLoadResult::AccountNotFound => Ok(LoadedProgram::new_tombstone(
                 self.slot,
                 LoadedProgramType::Closed,
@@ -5078,6 +5296,71 @@ impl Bank {
         loaded_programs_for_txs
     }
 
+    /// Has the configured `MissingAccountsLoader` fetch the accounts among `pubkeys` that this
+    /// bank is missing, ahead of processing the transactions that reference them
+    pub fn prefetch_missing_accounts(&self, pubkeys: &[Pubkey]) {
+        let Some(loader) = self.runtime_config.missing_accounts_loader.as_ref() else {
+            return;
+        };
+        let missing_pubkeys: Vec<Pubkey> = pubkeys
+            .iter()
+            .filter(|pubkey| self.get_account_with_fixed_root(pubkey).is_none())
+            .copied()
+            .collect();
+        if !missing_pubkeys.is_empty() {
+            loader.prefetch_accounts(&missing_pubkeys);
+        }
+    }
+
+    /// Loads the accounts referenced by `sanitized_txs` that this bank is missing from the
+    /// configured `MissingAccountsLoader`. A bank that is still being filled stores them, while
+    /// a frozen bank returns them so they can be passed as account overrides, e.g. to simulate
+    /// a transaction.
+    fn load_missing_accounts(
+        &self,
+        sanitized_txs: &[SanitizedTransaction],
+    ) -> Vec<(Pubkey, AccountSharedData)> {
+        let Some(loader) = self.runtime_config.missing_accounts_loader.as_ref() else {
+            return vec![];
+        };
+        let _lock = self.load_missing_accounts_lock.lock().unwrap();
+
+        let mut seen = HashSet::new();
+        let missing_pubkeys: Vec<Pubkey> = sanitized_txs
+            .iter()
+            .flat_map(|tx| {
+                tx.message()
+                    .account_keys()
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|pubkey| seen.insert(*pubkey))
+            .filter(|pubkey| self.get_account_with_fixed_root(pubkey).is_none())
+            .collect();
+        if missing_pubkeys.is_empty() {
+            return vec![];
+        }
+        let missing_accounts: Vec<_> = loader
+            .load_missing_accounts(&missing_pubkeys)
+            .into_iter()
+            // Never replace the bank's own state, e.g. a programdata account modified locally
+            .filter(|(pubkey, _)| self.get_account_with_fixed_root(pubkey).is_none())
+            .collect();
+        if self.freeze_started() {
+            return missing_accounts;
+        }
+        for (pubkey, account) in &missing_accounts {
+            self.store_account_and_update_capitalization(pubkey, account);
+        }
+        let stored_pubkeys: Vec<Pubkey> = missing_accounts
+            .into_iter()
+            .map(|(pubkey, _)| pubkey)
+            .collect();
+        loader.accounts_stored(&stored_pubkeys);
+        vec![]
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn load_and_execute_transactions(
         &self,
@@ -5089,11 +5372,50 @@ impl Bank {
         timings: &mut ExecuteTimings,
         account_overrides: Option<&AccountOverrides>,
         log_messages_bytes_limit: Option<usize>,
+    ) -> LoadAndExecuteTransactionsOutput {
+        self.load_and_execute_transactions_with_programs(
+            batch,
+            max_age,
+            enable_cpi_recording,
+            enable_log_recording,
+            enable_return_data_recording,
+            timings,
+            account_overrides,
+            log_messages_bytes_limit,
+            None,
+        )
+    }
+
+    /// Like `load_and_execute_transactions`, but the transactions also see the programs in
+    /// `programs_modified_by_bundle`, i.e. those modified by earlier transactions of a simulated
+    /// bundle
+    #[allow(clippy::too_many_arguments)]
+    fn load_and_execute_transactions_with_programs(
+        &self,
+        batch: &TransactionBatch,
+        max_age: usize,
+        enable_cpi_recording: bool,
+        enable_log_recording: bool,
+        enable_return_data_recording: bool,
+        timings: &mut ExecuteTimings,
+        account_overrides: Option<&AccountOverrides>,
+        log_messages_bytes_limit: Option<usize>,
+        programs_modified_by_bundle: Option<&LoadedProgramsForTxBatch>,
     ) -> LoadAndExecuteTransactionsOutput {
         let sanitized_txs = batch.sanitized_transactions();
         debug!("processing transactions: {}", sanitized_txs.len());
         let mut error_counters = TransactionErrorMetrics::default();
 
+        let missing_accounts = self.load_missing_accounts(sanitized_txs);
+        let missing_account_overrides = (!missing_accounts.is_empty()).then(|| {
+            let mut account_overrides = account_overrides.cloned().unwrap_or_default();
+            for (pubkey, account) in &missing_accounts {
+                account_overrides.set_account(pubkey, Some(account.clone()));
+            }
+            account_overrides
+        });
+        let account_overrides = missing_account_overrides.as_ref().or(account_overrides);
+
         let retryable_transaction_indexes: Vec<_> = batch
             .lock_results()
             .iter()
@@ -5157,9 +5479,64 @@ impl Bank {
             program_accounts_map.insert(*builtin_program, (&native_loader, 0));
         }
 
-        let programs_loaded_for_tx_batch = Rc::new(RefCell::new(
-            self.replenish_program_cache(&program_accounts_map),
-        ));
+        let mut programs_loaded_for_tx_batch = self.replenish_program_cache(&program_accounts_map);
+        // Missing programs passed as account overrides are unknown to accounts-db. They are only
+        // added after replenishing the global cache, which would otherwise cache them as closed.
+        for (pubkey, account) in &missing_accounts {
+            if let Some(owner) = PROGRAM_OWNERS
+                .iter()
+                .find(|owner| *owner == account.owner())
+            {
+                if account.executable() {
+                    program_accounts_map.entry(*pubkey).or_insert((owner, 0));
+                }
+            }
+        }
+        if let Some(account_overrides) = account_overrides {
+            // Likewise programs only found in the overrides, e.g. deployed by an earlier
+            // transaction of a simulated bundle.
+            for pubkey in sanitized_txs
+                .iter()
+                .flat_map(|tx| tx.message().account_keys().iter())
+            {
+                let Some(account) = account_overrides.get(pubkey) else {
+                    continue;
+                };
+                if let Some(owner) = PROGRAM_OWNERS
+                    .iter()
+                    .find(|owner| *owner == account.owner())
+                {
+                    if account.executable() {
+                        program_accounts_map.entry(*pubkey).or_insert((owner, 0));
+                    }
+                }
+            }
+            // Programs whose accounts are overridden are compiled for this batch only, so that
+            // the global cache never holds a program that does not match the bank's accounts.
+            for (program_id, (owner, _count)) in program_accounts_map.iter() {
+                let is_overridden = account_overrides.get(program_id).is_some()
+                    || (bpf_loader_upgradeable::check_id(owner)
+                        && account_overrides
+                            .get(&bpf_loader_upgradeable::get_program_data_address(
+                                program_id,
+                            ))
+                            .is_some());
+                if is_overridden {
+                    programs_loaded_for_tx_batch.replenish(
+                        *program_id,
+                        self.load_program_with_account_overrides(
+                            program_id,
+                            false,
+                            Some(account_overrides),
+                        ),
+                    );
+                }
+            }
+        }
+        if let Some(programs_modified_by_bundle) = programs_modified_by_bundle {
+            programs_loaded_for_tx_batch.merge(programs_modified_by_bundle);
+        }
+        let programs_loaded_for_tx_batch = Rc::new(RefCell::new(programs_loaded_for_tx_batch));
 
         let mut load_time = Measure::start("accounts_load");
         let mut loaded_transactions = self.rc.accounts.load_accounts(
@@ -6499,7 +6876,7 @@ impl Bank {
             &batch,
             MAX_PROCESSING_AGE,
             false, // collect_balances
-            false, // enable_cpi_recording
+            true,  // enable_cpi_recording
             true,  // enable_log_recording
             true,  // enable_return_data_recording
             &mut ExecuteTimings::default(),
@@ -1,19 +1,36 @@
 use {
     super::Bank,
+    solana_accounts_db::accounts::Accounts,
     solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
         address_lookup_table::error::AddressLookupError,
         message::{
             v0::{LoadedAddresses, MessageAddressTableLookup},
             AddressLoaderError,
         },
+        pubkey::Pubkey,
+        slot_hashes::SlotHashes,
         transaction::AddressLoader,
     },
+    std::collections::HashMap,
 };
 
 impl AddressLoader for &Bank {
     fn load_addresses(
         self,
         address_table_lookups: &[MessageAddressTableLookup],
+    ) -> Result<LoadedAddresses, AddressLoaderError> {
+        self.load_addresses_with_overrides(address_table_lookups, &HashMap::default())
+    }
+}
+
+impl Bank {
+    /// Like `load_addresses`, but takes lookup tables from `overridden_accounts` where present,
+    /// e.g. tables modified by earlier transactions of a simulated bundle
+    pub(super) fn load_addresses_with_overrides(
+        &self,
+        address_table_lookups: &[MessageAddressTableLookup],
+        overridden_accounts: &HashMap<Pubkey, AccountSharedData>,
     ) -> Result<LoadedAddresses, AddressLoaderError> {
         let slot_hashes = self
             .sysvar_cache
@@ -25,12 +42,66 @@ impl AddressLoader for &Bank {
         Ok(address_table_lookups
             .iter()
             .map(|address_table_lookup| {
-                self.rc.accounts.load_lookup_table_addresses(
+                if let Some(table_account) =
+                    overridden_accounts.get(&address_table_lookup.account_key)
+                {
+                    // Closed by an earlier transaction
+                    if table_account.lamports() == 0 {
+                        return Err(AddressLookupError::LookupTableAccountNotFound);
+                    }
+                    return Accounts::lookup_table_addresses(
+                        table_account,
+                        self.slot(),
+                        address_table_lookup,
+                        &slot_hashes,
+                    );
+                }
+                match self.rc.accounts.load_lookup_table_addresses(
                     &self.ancestors,
                     address_table_lookup,
                     &slot_hashes,
-                )
+                ) {
+                    Err(AddressLookupError::LookupTableAccountNotFound) => {
+                        self.load_missing_lookup_table_addresses(address_table_lookup, &slot_hashes)
+                    }
+                    result => result,
+                }
             })
             .collect::<Result<_, AddressLookupError>>()?)
     }
+
+    /// Loads a lookup table the bank is missing from the configured `MissingAccountsLoader`
+    fn load_missing_lookup_table_addresses(
+        &self,
+        address_table_lookup: &MessageAddressTableLookup,
+        slot_hashes: &SlotHashes,
+    ) -> Result<LoadedAddresses, AddressLookupError> {
+        let Some(loader) = self.runtime_config.missing_accounts_loader.as_ref() else {
+            return Err(AddressLookupError::LookupTableAccountNotFound);
+        };
+        let table_key = &address_table_lookup.account_key;
+        let _lock = self.load_missing_accounts_lock.lock().unwrap();
+        let table_account = match self.get_account_with_fixed_root(table_key) {
+            // Loaded by a concurrent batch in the meantime
+            Some(table_account) => table_account,
+            None => {
+                let (_, table_account) = loader
+                    .load_missing_accounts(&[*table_key])
+                    .into_iter()
+                    .find(|(pubkey, _)| pubkey == table_key)
+                    .ok_or(AddressLookupError::LookupTableAccountNotFound)?;
+                if !self.freeze_started() {
+                    self.store_account_and_update_capitalization(table_key, &table_account);
+                    loader.accounts_stored(&[*table_key]);
+                }
+                table_account
+            }
+        };
+        Accounts::lookup_table_addresses(
+            &table_account,
+            self.slot(),
+            address_table_lookup,
+            slot_hashes,
+        )
+    }
 }
@@ -127,7 +127,7 @@ use {
                 AtomicBool, AtomicU64,
                 Ordering::{Relaxed, Release},
             },
-            Arc,
+            Arc, Mutex,
         },
         thread::Builder,
         time::{Duration, Instant},
@@ -7243,6 +7243,89 @@ fn test_bank_load_program() {
     );
 }
 
+#[test]
+fn test_simulate_bundle_deploy_and_invoke() {
+    let (genesis_config, mint_keypair) = create_genesis_config(1_000_000_000_000);
+    let mut bank = Bank::new_for_tests(&genesis_config);
+    bank.feature_set = Arc::new(FeatureSet::all_enabled());
+    let program_keypair = Keypair::new();
+    let upgrade_authority_keypair = Keypair::new();
+    let buffer_address = Pubkey::new_unique();
+
+    let mut file = File::open("../programs/bpf_loader/test_elfs/out/noop_aligned.so")
+        .expect("file open failed");
+    let mut elf = Vec::new();
+    file.read_to_end(&mut elf).unwrap();
+    let mut buffer_account = AccountSharedData::new(
+        bank.get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_buffer(
+            elf.len(),
+        )),
+        UpgradeableLoaderState::size_of_buffer(elf.len()),
+        &bpf_loader_upgradeable::id(),
+    );
+    buffer_account
+        .set_state(&UpgradeableLoaderState::Buffer {
+            authority_address: Some(upgrade_authority_keypair.pubkey()),
+        })
+        .unwrap();
+    buffer_account.data_as_mut_slice()[UpgradeableLoaderState::size_of_buffer_metadata()..]
+        .copy_from_slice(&elf);
+    bank.store_account(&buffer_address, &buffer_account);
+
+    let blockhash = bank.last_blockhash();
+    let deploy = Transaction::new(
+        &[&mint_keypair, &program_keypair, &upgrade_authority_keypair],
+        Message::new(
+            &bpf_loader_upgradeable::deploy_with_max_program_len(
+                &mint_keypair.pubkey(),
+                &program_keypair.pubkey(),
+                &buffer_address,
+                &upgrade_authority_keypair.pubkey(),
+                bank.get_minimum_balance_for_rent_exemption(
+                    UpgradeableLoaderState::size_of_program(),
+                ),
+                elf.len(),
+            )
+            .unwrap(),
+            Some(&mint_keypair.pubkey()),
+        ),
+        blockhash,
+    );
+    let invoke = Transaction::new(
+        &[&mint_keypair],
+        Message::new(
+            &[Instruction::new_with_bytes(
+                program_keypair.pubkey(),
+                &[],
+                vec![],
+            )],
+            Some(&mint_keypair.pubkey()),
+        ),
+        blockhash,
+    );
+    let transactions: Vec<_> = [&deploy, &invoke]
+        .into_iter()
+        .map(|tx| SanitizedTransaction::from_transaction_for_tests(tx.clone()))
+        .collect();
+
+    // The invoke finds the program deployed by the first transaction, which like in replay
+    // only becomes visible in the next slot.
+    let results = bank.simulate_bundle_unchecked(&transactions);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].result, Ok(()));
+    let not_deployed = TransactionError::InstructionError(0, InstructionError::InvalidAccountData);
+    assert_eq!(results[1].result, Err(not_deployed.clone()));
+    assert!(results[1]
+        .logs
+        .iter()
+        .any(|log| log == "Program is not deployed"));
+    assert_eq!(bank.get_account(&program_keypair.pubkey()), None);
+
+    // Replay gives the same results for the same transactions in one slot.
+    assert_eq!(bank.process_transaction(&deploy), Ok(()));
+    assert_eq!(bank.process_transaction(&invoke), Err(not_deployed));
+}
+
 #[test]
 fn test_bpf_loader_upgradeable_deploy_with_max_len() {
     let (genesis_config, mint_keypair) = create_genesis_config(1_000_000_000);
@@ -14137,3 +14220,79 @@ fn test_last_restart_slot() {
     assert!(!last_restart_slot_dirty(&bank7));
     assert_eq!(get_last_restart_slot(&bank7), Some(6));
 }
+
+#[derive(Debug)]
+struct TestMissingAccountsLoader {
+    accounts: HashMap<Pubkey, AccountSharedData>,
+    stored: Mutex<HashSet<Pubkey>>,
+}
+
+impl crate::runtime_config::MissingAccountsLoader for TestMissingAccountsLoader {
+    fn prefetch_accounts(&self, _pubkeys: &[Pubkey]) {}
+
+    fn load_missing_accounts(&self, pubkeys: &[Pubkey]) -> Vec<(Pubkey, AccountSharedData)> {
+        let stored = self.stored.lock().unwrap();
+        pubkeys
+            .iter()
+            .filter(|pubkey| !stored.contains(pubkey))
+            .filter_map(|pubkey| {
+                self.accounts
+                    .get(pubkey)
+                    .map(|account| (*pubkey, account.clone()))
+            })
+            .collect()
+    }
+
+    fn accounts_stored(&self, pubkeys: &[Pubkey]) {
+        self.stored.lock().unwrap().extend(pubkeys);
+    }
+}
+
+#[test]
+fn test_missing_accounts_loader() {
+    let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+    let from_keypair = Keypair::new();
+    let missing_account = AccountSharedData::new(1_000_000, 0, &system_program::id());
+    let loader = TestMissingAccountsLoader {
+        accounts: HashMap::from([(from_keypair.pubkey(), missing_account)]),
+        stored: Mutex::default(),
+    };
+    let bank = Arc::new(Bank::new_with_runtime_config_for_tests(
+        &genesis_config,
+        Arc::new(RuntimeConfig {
+            missing_accounts_loader: Some(Arc::new(loader)),
+            ..RuntimeConfig::default()
+        }),
+    ));
+    let to_pubkey = Pubkey::new_unique();
+    bank.transfer(1, &mint_keypair, &to_pubkey).unwrap();
+    bank.freeze();
+
+    // A frozen bank passes missing accounts to the simulation without storing them
+    let transaction =
+        system_transaction::transfer(&from_keypair, &to_pubkey, 500, bank.last_blockhash());
+    let simulation_result = bank.simulate_transaction_unchecked(
+        SanitizedTransaction::from_transaction_for_tests(transaction),
+    );
+    assert_eq!(simulation_result.result, Ok(()));
+    assert_eq!(bank.get_account(&from_keypair.pubkey()), None);
+
+    // A bank that is still being filled stores them and accounts for their lamports
+    let bank = Bank::new_from_parent(bank, &Pubkey::default(), 1);
+    let capitalization = bank.capitalization();
+    bank.transfer(500, &from_keypair, &to_pubkey).unwrap();
+    assert_eq!(bank.get_balance(&to_pubkey), 501);
+    assert_eq!(bank.capitalization(), capitalization + 1_000_000);
+    assert!(bank.get_account(&from_keypair.pubkey()).is_some());
+
+    // Accounts closed locally aren't loaded again
+    let capitalization = bank.capitalization();
+    bank.transfer(999_500, &from_keypair, &to_pubkey).unwrap();
+    assert_eq!(bank.get_account(&from_keypair.pubkey()), None);
+    assert_eq!(
+        bank.transfer(1, &from_keypair, &to_pubkey),
+        Err(TransactionError::AccountNotFound)
+    );
+    assert_eq!(bank.get_account(&from_keypair.pubkey()), None);
+    assert_eq!(bank.capitalization(), capitalization);
+}
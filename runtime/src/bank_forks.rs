@@ -190,6 +190,8 @@ impl BankForks {
         bank.check_program_modification_slot =
             self.root.load(Ordering::Relaxed) < self.highest_slot_at_startup;
 
+        bank.apply_bank_overrides();
+
         let bank = Arc::new(bank);
         let prev = self.banks.insert(bank.slot(), bank.clone());
         assert!(prev.is_none());
@@ -673,13 +675,16 @@ mod tests {
         super::*,
         crate::{
             bank::test_utils::update_vote_account_timestamp,
+            bank_overrides::BankOverrides,
             genesis_utils::{
                 create_genesis_config, create_genesis_config_with_leader, GenesisConfigInfo,
             },
+            runtime_config::RuntimeConfig,
         },
         assert_matches::assert_matches,
         solana_accounts_db::epoch_accounts_hash::EpochAccountsHash,
         solana_sdk::{
+            account::{AccountSharedData, ReadableAccount},
             clock::UnixTimestamp,
             epoch_schedule::EpochSchedule,
             hash::Hash,
@@ -702,6 +707,52 @@ mod tests {
         assert_eq!(bank_forks.working_bank().tick_height(), 1);
     }
 
+    #[test]
+    fn test_bank_forks_insert_applies_bank_overrides() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank_overrides = Arc::new(BankOverrides::default());
+        let bank = Bank::new_with_runtime_config_for_tests(
+            &genesis_config,
+            Arc::new(RuntimeConfig {
+                bank_overrides: Some(bank_overrides.clone()),
+                ..RuntimeConfig::default()
+            }),
+        );
+        let mut bank_forks = BankForks::new(bank);
+        let capitalization = bank_forks[0].capitalization();
+
+        let pubkey = Pubkey::new_unique();
+        let account = AccountSharedData::new(42, 0, &Pubkey::default());
+        bank_overrides.set_account(pubkey, account.clone());
+        let unix_timestamp: UnixTimestamp = 4_102_444_800; // 2100-01-01
+        bank_overrides.set_unix_timestamp(unix_timestamp);
+
+        let bank0 = bank_forks[0].clone();
+        let bank1 = bank_forks.insert(Bank::new_from_parent(bank0, &Pubkey::default(), 1));
+        assert_eq!(bank1.get_account(&pubkey), Some(account));
+        assert_eq!(bank1.capitalization(), capitalization + 42);
+        assert!(bank1.clock().unix_timestamp >= unix_timestamp);
+        assert!(bank_overrides.take_accounts().is_empty());
+
+        // The timestamp sticks to the override until it is cleared
+        let bank2 = bank_forks.insert(Bank::new_from_parent(bank1, &Pubkey::default(), 2));
+        assert!(bank2.clock().unix_timestamp >= unix_timestamp);
+        bank_overrides.clear_unix_timestamp();
+        let bank3 = bank_forks.insert(Bank::new_from_parent(bank2, &Pubkey::default(), 3));
+        assert_eq!(bank_overrides.unix_timestamp(), None);
+        assert!(bank3.clock().unix_timestamp >= unix_timestamp);
+
+        // A frozen bank, e.g. a warped one, leaves the changes to the next bank
+        let account = AccountSharedData::new(43, 0, &Pubkey::default());
+        bank_overrides.set_account(pubkey, account.clone());
+        let bank4 = Bank::new_from_parent(bank3, &Pubkey::default(), 4);
+        bank4.freeze();
+        let bank4 = bank_forks.insert(bank4);
+        assert_eq!(bank4.get_account(&pubkey).unwrap().lamports(), 42);
+        let bank5 = bank_forks.insert(Bank::new_from_parent(bank4, &Pubkey::default(), 5));
+        assert_eq!(bank5.get_account(&pubkey), Some(account));
+    }
+
     #[test]
     fn test_bank_forks_new_from_banks() {
         let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
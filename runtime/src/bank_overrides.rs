@@ -0,0 +1,85 @@
+//! State changes requested while a cluster is running, e.g. through `solana-test-validator`'s
+//! admin RPC. They are applied to banks as they are inserted into `BankForks`, before any
+//! transaction is processed by them. Warps are applied by `ReplayStage` instead, which is the
+//! only one able to move PoH and fork choice ahead.
+
+use {
+    solana_sdk::{
+        account::AccountSharedData,
+        clock::{Slot, UnixTimestamp},
+        pubkey::Pubkey,
+        timing::timestamp,
+    },
+    std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
+};
+
+#[derive(Debug, Default)]
+pub struct BankOverrides {
+    // Stored by the next bank, descendants inherit them from there
+    accounts: Mutex<Vec<(Pubkey, AccountSharedData)>>,
+    // Offset from the wall clock that every bank's `Clock::unix_timestamp` is set to
+    unix_timestamp_offset: RwLock<Option<UnixTimestamp>>,
+    // Whether the next bank's `Clock::unix_timestamp` returns to the stake-weighted estimate
+    unix_timestamp_cleared: AtomicBool,
+    // Slot of the next leader bank of a sole leader, the slots in between are skipped
+    warp_slot: Mutex<Option<Slot>>,
+}
+
+impl BankOverrides {
+    /// Replaces `pubkey`'s account in the next bank, issuing or burning lamports as needed
+    pub fn set_account(&self, pubkey: Pubkey, account: AccountSharedData) {
+        self.accounts.lock().unwrap().push((pubkey, account));
+    }
+
+    /// Sets the next bank's `Clock::unix_timestamp` to `unix_timestamp`. Later banks keep
+    /// advancing with the wall clock from there, rather than falling back to the vote-based
+    /// estimate.
+    pub fn set_unix_timestamp(&self, unix_timestamp: UnixTimestamp) {
+        let now = (timestamp() / 1000) as UnixTimestamp;
+        let mut unix_timestamp_offset = self.unix_timestamp_offset.write().unwrap();
+        *unix_timestamp_offset = Some(unix_timestamp.saturating_sub(now));
+        self.unix_timestamp_cleared.store(false, Ordering::Relaxed);
+    }
+
+    /// Sets the next bank's `Clock::unix_timestamp` back to the stake-weighted estimate of the
+    /// validators' votes, which later banks keep following. Banks never move the clock backwards
+    /// on their own, so this also undoes a timestamp set in the future. The estimate is bounded
+    /// relative to the epoch's start timestamp, which stays overridden if the epoch started
+    /// while the override was set.
+    pub fn clear_unix_timestamp(&self) {
+        let mut unix_timestamp_offset = self.unix_timestamp_offset.write().unwrap();
+        *unix_timestamp_offset = None;
+        self.unix_timestamp_cleared.store(true, Ordering::Relaxed);
+    }
+
+    /// Warps a validator that is the only leader of its cluster to `slot`: its next leader bank
+    /// is `slot`, built on a bank warped from its current fork. Warping to the first slot of an
+    /// epoch runs the epoch boundary, e.g. to pay epoch rewards. The skipped slots are never in
+    /// the ledger, so the validator can only restart from a snapshot taken after the warp.
+    pub fn warp_to_slot(&self, slot: Slot) {
+        *self.warp_slot.lock().unwrap() = Some(slot);
+    }
+
+    /// Takes the slot requested through `warp_to_slot`, for the replay stage to warp to
+    pub fn take_warp_slot(&self) -> Option<Slot> {
+        self.warp_slot.lock().unwrap().take()
+    }
+
+    pub(crate) fn take_accounts(&self) -> Vec<(Pubkey, AccountSharedData)> {
+        std::mem::take(&mut self.accounts.lock().unwrap())
+    }
+
+    pub(crate) fn take_unix_timestamp_cleared(&self) -> bool {
+        self.unix_timestamp_cleared.swap(false, Ordering::Relaxed)
+    }
+
+    pub(crate) fn unix_timestamp(&self) -> Option<UnixTimestamp> {
+        self.unix_timestamp_offset.read().unwrap().map(|offset| {
+            let now = (timestamp() / 1000) as UnixTimestamp;
+            now.saturating_add(offset)
+        })
+    }
+}
@@ -8,6 +8,7 @@ pub mod accounts_background_service;
 pub mod bank;
 pub mod bank_client;
 pub mod bank_forks;
+pub mod bank_overrides;
 pub mod bank_utils;
 pub mod builtins;
 pub mod commitment;
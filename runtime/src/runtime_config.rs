@@ -1,4 +1,28 @@
-use solana_program_runtime::compute_budget::ComputeBudget;
+use {
+    crate::bank_overrides::BankOverrides,
+    solana_program_runtime::compute_budget::ComputeBudget,
+    solana_sdk::{account::AccountSharedData, pubkey::Pubkey},
+    std::{fmt::Debug, sync::Arc},
+};
+
+/// Supplies accounts that transactions reference but the bank does not have, e.g. by fetching
+/// them from another cluster.
+pub trait MissingAccountsLoader: Debug + Send + Sync {
+    /// Fetches the accounts among `pubkeys` that weren't fetched before. This may block, so it's
+    /// called before the transactions referencing them are processed, e.g. when RPC receives
+    /// them.
+    fn prefetch_accounts(&self, pubkeys: &[Pubkey]);
+
+    /// Returns the fetched accounts among `pubkeys` that no bank has stored yet. Upgradeable
+    /// programs should be returned along with their programdata accounts. This is called while
+    /// processing transactions and must not block: accounts that weren't prefetched should be
+    /// fetched in the background, for later transactions.
+    fn load_missing_accounts(&self, pubkeys: &[Pubkey]) -> Vec<(Pubkey, AccountSharedData)>;
+
+    /// Records that a bank stored `pubkeys`. They are never loaded again, since a bank missing
+    /// them afterwards means they were closed locally.
+    fn accounts_stored(&self, pubkeys: &[Pubkey]);
+}
 
 /// Encapsulates flags that can be used to tweak the runtime behavior.
 #[derive(AbiExample, Debug, Default, Clone)]
@@ -6,4 +30,6 @@ pub struct RuntimeConfig {
     pub compute_budget: Option<ComputeBudget>,
     pub log_messages_bytes_limit: Option<usize>,
     pub transaction_account_lock_limit: Option<usize>,
+    pub missing_accounts_loader: Option<Arc<dyn MissingAccountsLoader>>,
+    pub bank_overrides: Option<Arc<BankOverrides>>,
 }
@@ -52,6 +52,43 @@ impl<'a> SnapshotMinimizer<'a> {
         starting_slot: Slot,
         ending_slot: Slot,
         transaction_account_set: DashSet<Pubkey>,
+    ) {
+        Self::minimize_with(
+            bank,
+            starting_slot,
+            ending_slot,
+            transaction_account_set,
+            true, // include_rent_collection_accounts
+        );
+    }
+
+    /// Removes all accounts not necessary for executing specific transactions on top of the
+    /// snapshot at `starting_slot`. `transaction_account_set` should contain the accounts used
+    /// by those transactions.
+    ///
+    /// Unlike `minimize`, accounts that are only needed to collect rent while replaying whole
+    /// slots are removed too, so bank hashes of replayed slots will not match the cluster's.
+    /// Vote and stake accounts are kept since loading the snapshot requires them.
+    pub fn minimize_for_transactions(
+        bank: &'a Bank,
+        starting_slot: Slot,
+        transaction_account_set: DashSet<Pubkey>,
+    ) {
+        Self::minimize_with(
+            bank,
+            starting_slot,
+            starting_slot,
+            transaction_account_set,
+            false, // include_rent_collection_accounts
+        );
+    }
+
+    fn minimize_with(
+        bank: &'a Bank,
+        starting_slot: Slot,
+        ending_slot: Slot,
+        transaction_account_set: DashSet<Pubkey>,
+        include_rent_collection_accounts: bool,
     ) {
         let minimizer = SnapshotMinimizer {
             bank,
@@ -66,10 +103,12 @@ impl<'a> SnapshotMinimizer<'a> {
         minimizer.add_accounts(Self::get_static_runtime_accounts, "static runtime accounts");
         minimizer.add_accounts(Self::get_sdk_accounts, "sdk accounts");
 
-        minimizer.add_accounts(
-            Self::get_rent_collection_accounts,
-            "rent collection accounts",
-        );
+        if include_rent_collection_accounts {
+            minimizer.add_accounts(
+                Self::get_rent_collection_accounts,
+                "rent collection accounts",
+            );
+        }
         minimizer.add_accounts(Self::get_vote_accounts, "vote accounts");
         minimizer.add_accounts(Self::get_stake_accounts, "stake accounts");
         minimizer.add_accounts(Self::get_owner_accounts, "owner accounts");
@@ -690,4 +729,30 @@ mod tests {
             minimizer.minimized_account_set.len() + num_accounts_per_slot
         ); // snapshot slot is untouched, so still has all 300 accounts
     }
+
+    #[test]
+    fn test_minimize_for_transactions() {
+        solana_logger::setup();
+
+        let (genesis_config, _) = create_genesis_config(1_000_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+
+        let used_pubkey = solana_sdk::pubkey::new_rand();
+        let unused_pubkey = solana_sdk::pubkey::new_rand();
+        let account = AccountSharedData::new(1, 0, &Pubkey::default());
+        bank0.store_account(&used_pubkey, &account);
+        bank0.store_account(&unused_pubkey, &account);
+        bank0.squash();
+        bank0.force_flush_accounts_cache();
+
+        let bank1 = Bank::new_from_parent(bank0, &Pubkey::default(), 1);
+        bank1.squash();
+
+        let transaction_account_set = DashSet::new();
+        transaction_account_set.insert(used_pubkey);
+        SnapshotMinimizer::minimize_for_transactions(&bank1, bank1.slot(), transaction_account_set);
+
+        assert!(bank1.get_account(&used_pubkey).is_some());
+        assert!(bank1.get_account(&unused_pubkey).is_none());
+    }
 }
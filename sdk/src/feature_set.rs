@@ -724,6 +724,10 @@ pub mod update_hashes_per_tick6 {
     solana_sdk::declare_id!("FKu1qYwLQSiehz644H6Si65U5ZQ2cp9GxsyFUfYcuADv");
 }
 
+pub mod batched_shred_repair_requests {
+    solana_sdk::declare_id!("8622FV2AUkvqA4EaPxafeTEhEEqE8wTP31JRMwEkAmWy");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: HashMap<Pubkey, &'static str> = [
@@ -900,6 +904,7 @@ lazy_static! {
         (update_hashes_per_tick4::id(), "Update desired hashes per tick to 7.6M"),
         (update_hashes_per_tick5::id(), "Update desired hashes per tick to 9.2M"),
         (update_hashes_per_tick6::id(), "Update desired hashes per tick to 10M"),
+        (batched_shred_repair_requests::id(), "request missing shreds of a slot in batches over QUIC repair"),
         /*************** ADD NEW FEATURES HERE ***************/
     ]
     .iter()
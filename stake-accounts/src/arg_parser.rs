@@ -1,7 +1,7 @@
 use {
     crate::args::{
-        Args, AuthorizeArgs, Command, CountArgs, MoveArgs, NewArgs, QueryArgs, RebaseArgs,
-        SetLockupArgs,
+        Args, AuthorizeArgs, Command, CountArgs, LookupTableArgs, MoveArgs, NewArgs, QueryArgs,
+        RebaseArgs, SetLockupArgs,
     },
     clap::{
         crate_description, crate_name, value_t, value_t_or_exit, App, Arg, ArgMatches, SubCommand,
@@ -129,6 +129,25 @@ fn num_accounts_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Number of derived stake accounts")
 }
 
+fn lookup_table_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("lookup_table")
+        .long("lookup-table")
+        .takes_value(true)
+        .value_name("LOOKUP_TABLE_ADDRESS")
+        .validator(is_valid_pubkey)
+        .help("Send v0 transactions loading stake account addresses from this lookup table")
+}
+
+fn create_lookup_table_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("create_lookup_table")
+        .long("create-lookup-table")
+        .conflicts_with("lookup_table")
+        .help(
+            "Send v0 transactions, creating lookup tables for the stake account addresses \
+             with the fee payer as authority",
+        )
+}
+
 pub(crate) fn get_matches<'a, I, T>(args: I) -> ArgMatches<'a>
 where
     I: IntoIterator<Item = T>,
@@ -230,7 +249,9 @@ where
                 .arg(withdraw_authority_arg())
                 .arg(new_stake_authority_arg())
                 .arg(new_withdraw_authority_arg())
-                .arg(num_accounts_arg()),
+                .arg(num_accounts_arg())
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("set-lockup")
@@ -253,7 +274,9 @@ where
                         .takes_value(true)
                         .value_name("NUMBER")
                         .help("Years to unlock after the cliff"),
-                ),
+                )
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("rebase")
@@ -262,7 +285,9 @@ where
                 .arg(base_pubkey_arg().index(1))
                 .arg(new_base_keypair_arg().index(2))
                 .arg(stake_authority_arg())
-                .arg(num_accounts_arg()),
+                .arg(num_accounts_arg())
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("move")
@@ -274,7 +299,9 @@ where
                 .arg(withdraw_authority_arg())
                 .arg(new_stake_authority_arg())
                 .arg(new_withdraw_authority_arg())
-                .arg(num_accounts_arg()),
+                .arg(num_accounts_arg())
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .get_matches_from(args)
 }
@@ -304,6 +331,16 @@ fn parse_query_args(matches: &ArgMatches<'_>) -> QueryArgs<String> {
     }
 }
 
+fn parse_lookup_table_args(matches: &ArgMatches<'_>) -> Option<LookupTableArgs<String>> {
+    if matches.is_present("create_lookup_table") {
+        Some(LookupTableArgs::Create)
+    } else {
+        value_t!(matches, "lookup_table", String)
+            .ok()
+            .map(LookupTableArgs::Existing)
+    }
+}
+
 fn parse_authorize_args(matches: &ArgMatches<'_>) -> AuthorizeArgs<String, String> {
     AuthorizeArgs {
         fee_payer: value_t_or_exit!(matches, "fee_payer", String),
@@ -313,6 +350,7 @@ fn parse_authorize_args(matches: &ArgMatches<'_>) -> AuthorizeArgs<String, Strin
         new_stake_authority: value_t_or_exit!(matches, "new_stake_authority", String),
         new_withdraw_authority: value_t_or_exit!(matches, "new_withdraw_authority", String),
         num_accounts: value_t_or_exit!(matches, "num_accounts", usize),
+        lookup_table_args: parse_lookup_table_args(matches),
     }
 }
 
@@ -327,6 +365,7 @@ fn parse_set_lockup_args(matches: &ArgMatches<'_>) -> SetLockupArgs<String, Stri
         num_accounts: value_t_or_exit!(matches, "num_accounts", usize),
         no_wait: matches.is_present("no_wait"),
         unlock_years: value_t!(matches, "unlock_years", f64).ok(),
+        lookup_table_args: parse_lookup_table_args(matches),
     }
 }
 
@@ -337,6 +376,7 @@ fn parse_rebase_args(matches: &ArgMatches<'_>) -> RebaseArgs<String, String> {
         new_base_keypair: value_t_or_exit!(matches, "new_base_keypair", String),
         stake_authority: value_t_or_exit!(matches, "stake_authority", String),
         num_accounts: value_t_or_exit!(matches, "num_accounts", usize),
+        lookup_table_args: parse_lookup_table_args(matches),
     }
 }
 
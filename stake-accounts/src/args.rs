@@ -29,6 +29,14 @@ pub(crate) struct QueryArgs<P> {
     pub num_accounts: usize,
 }
 
+/// How v0 transactions find the address lookup table to load stake account addresses from
+pub(crate) enum LookupTableArgs<P> {
+    /// Use an existing, active lookup table
+    Existing(P),
+    /// Create lookup tables holding the stake accounts' addresses, with the fee payer as authority
+    Create,
+}
+
 pub(crate) struct AuthorizeArgs<P, K> {
     pub fee_payer: K,
     pub base_pubkey: P,
@@ -37,6 +45,7 @@ pub(crate) struct AuthorizeArgs<P, K> {
     pub new_stake_authority: P,
     pub new_withdraw_authority: P,
     pub num_accounts: usize,
+    pub lookup_table_args: Option<LookupTableArgs<P>>,
 }
 
 pub(crate) struct SetLockupArgs<P, K> {
@@ -49,6 +58,7 @@ pub(crate) struct SetLockupArgs<P, K> {
     pub num_accounts: usize,
     pub no_wait: bool,
     pub unlock_years: Option<f64>,
+    pub lookup_table_args: Option<LookupTableArgs<P>>,
 }
 
 pub(crate) struct RebaseArgs<P, K> {
@@ -57,6 +67,7 @@ pub(crate) struct RebaseArgs<P, K> {
     pub new_base_keypair: K,
     pub stake_authority: K,
     pub num_accounts: usize,
+    pub lookup_table_args: Option<LookupTableArgs<P>>,
 }
 
 pub(crate) struct MoveArgs<P, K> {
@@ -160,6 +171,22 @@ fn resolve_new_base_keypair(
     signer_from_path(&matches, key_url, "new base pubkey", wallet_manager)
 }
 
+fn resolve_lookup_table_args(
+    wallet_manager: &mut Option<Rc<RemoteWalletManager>>,
+    args: &Option<LookupTableArgs<String>>,
+) -> Result<Option<LookupTableArgs<Pubkey>>, Box<dyn Error>> {
+    let matches = ArgMatches::default();
+    let lookup_table_args = match args {
+        None => None,
+        Some(LookupTableArgs::Existing(key_url)) => {
+            let pubkey = pubkey_from_path(&matches, key_url, "lookup table", wallet_manager)?;
+            Some(LookupTableArgs::Existing(pubkey))
+        }
+        Some(LookupTableArgs::Create) => Some(LookupTableArgs::Create),
+    };
+    Ok(lookup_table_args)
+}
+
 fn resolve_authorize_args(
     wallet_manager: &mut Option<Rc<RemoteWalletManager>>,
     args: &AuthorizeArgs<String, String>,
@@ -178,6 +205,7 @@ fn resolve_authorize_args(
             &args.new_withdraw_authority,
         )?,
         num_accounts: args.num_accounts,
+        lookup_table_args: resolve_lookup_table_args(wallet_manager, &args.lookup_table_args)?,
     };
     Ok(resolved_args)
 }
@@ -196,6 +224,7 @@ fn resolve_set_lockup_args(
         num_accounts: args.num_accounts,
         no_wait: args.no_wait,
         unlock_years: args.unlock_years,
+        lookup_table_args: resolve_lookup_table_args(wallet_manager, &args.lookup_table_args)?,
     };
     Ok(resolved_args)
 }
@@ -210,6 +239,7 @@ fn resolve_rebase_args(
         new_base_keypair: resolve_new_base_keypair(wallet_manager, &args.new_base_keypair)?,
         stake_authority: resolve_stake_authority(wallet_manager, &args.stake_authority)?,
         num_accounts: args.num_accounts,
+        lookup_table_args: resolve_lookup_table_args(wallet_manager, &args.lookup_table_args)?,
     };
     Ok(resolved_args)
 }
@@ -0,0 +1,70 @@
+//! Address lookup table support. Stake account addresses are loaded from lookup tables by v0
+//! transactions, which lets a single transaction update several stake accounts.
+use {
+    crate::args::LookupTableArgs,
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::client_error::Error as ClientError,
+    solana_rpc_client_lookup_table_utils::{
+        create_lookup_tables, fetch_lookup_table, lookup_table_candidates,
+    },
+    solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
+        message::{Message, VersionedMessage},
+        pubkey::Pubkey,
+        signature::{Signature, Signer},
+        transaction::VersionedTransaction,
+    },
+};
+
+/// Returns the lookup tables that v0 transactions for `messages` should load addresses from.
+pub(crate) fn get_lookup_tables(
+    client: &RpcClient,
+    lookup_table_args: &LookupTableArgs<Pubkey>,
+    fee_payer: &dyn Signer,
+    messages: &[Message],
+) -> Result<Vec<AddressLookupTableAccount>, ClientError> {
+    match lookup_table_args {
+        LookupTableArgs::Existing(lookup_table_address) => {
+            Ok(vec![fetch_lookup_table::<ClientError>(
+                client,
+                lookup_table_address,
+            )?])
+        }
+        LookupTableArgs::Create => {
+            let lookup_tables = create_lookup_tables::<ClientError>(
+                client,
+                fee_payer,
+                &lookup_table_candidates(messages),
+                false,
+            )?;
+            for lookup_table in &lookup_tables {
+                println!("Lookup table: {}", lookup_table.key);
+            }
+            Ok(lookup_tables)
+        }
+    }
+}
+
+/// Signs `message` with the subset of `signers` it requires and sends it.
+pub(crate) fn send_and_confirm_versioned_message(
+    client: &RpcClient,
+    mut message: VersionedMessage,
+    signers: &[&dyn Signer],
+    no_wait: bool,
+) -> Result<Signature, ClientError> {
+    let required_signers =
+        &message.static_account_keys()[..usize::from(message.header().num_required_signatures)];
+    let signers: Vec<&dyn Signer> = signers
+        .iter()
+        .filter(|signer| required_signers.contains(&signer.pubkey()))
+        .copied()
+        .collect();
+
+    message.set_recent_blockhash(client.get_latest_blockhash()?);
+    let transaction = VersionedTransaction::try_new(message, &signers)?;
+    if no_wait {
+        client.send_transaction(&transaction)
+    } else {
+        client.send_and_confirm_transaction_with_spinner(&transaction)
+    }
+}
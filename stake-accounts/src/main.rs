@@ -1,18 +1,21 @@
 #![allow(clippy::arithmetic_side_effects)]
 mod arg_parser;
 mod args;
+mod lookup_table;
 mod stake_accounts;
 
 use {
     crate::{
         arg_parser::parse_args,
         args::{
-            resolve_command, AuthorizeArgs, Command, MoveArgs, NewArgs, RebaseArgs, SetLockupArgs,
+            resolve_command, AuthorizeArgs, Command, LookupTableArgs, MoveArgs, NewArgs,
+            RebaseArgs, SetLockupArgs,
         },
     },
     solana_cli_config::Config,
     solana_rpc_client::rpc_client::RpcClient,
     solana_rpc_client_api::client_error::Error as ClientError,
+    solana_rpc_client_lookup_table_utils::{self as lookup_table_utils, BatchedMessage},
     solana_sdk::{
         message::Message,
         native_token::lamports_to_sol,
@@ -107,7 +110,14 @@ fn process_authorize_stake_accounts(
         &*args.stake_authority,
         &*args.withdraw_authority,
     ]);
-    send_and_confirm_messages(client, messages, &signers, false)?;
+    send_messages(
+        client,
+        messages,
+        &signers,
+        &*args.fee_payer,
+        &args.lookup_table_args,
+        false,
+    )?;
     Ok(())
 }
 
@@ -136,7 +146,14 @@ fn process_lockup_stake_accounts(
         return Ok(());
     }
     let signers = unique_signers(vec![&*args.fee_payer, &*args.custodian]);
-    send_and_confirm_messages(client, messages, &signers, args.no_wait)?;
+    send_messages(
+        client,
+        messages,
+        &signers,
+        &*args.fee_payer,
+        &args.lookup_table_args,
+        args.no_wait,
+    )?;
     Ok(())
 }
 
@@ -163,7 +180,14 @@ fn process_rebase_stake_accounts(
         &*args.new_base_keypair,
         &*args.stake_authority,
     ]);
-    send_and_confirm_messages(client, messages, &signers, false)?;
+    send_messages(
+        client,
+        messages,
+        &signers,
+        &*args.fee_payer,
+        &args.lookup_table_args,
+        false,
+    )?;
     Ok(())
 }
 
@@ -196,11 +220,18 @@ fn process_move_stake_accounts(
         &*args.stake_authority,
         &*authorize_args.withdraw_authority,
     ]);
-    send_and_confirm_messages(client, messages, &signers, false)?;
+    send_messages(
+        client,
+        messages,
+        &signers,
+        &*args.fee_payer,
+        &args.lookup_table_args,
+        false,
+    )?;
     Ok(())
 }
 
-fn send_and_confirm_message<S: Signers>(
+fn send_and_confirm_message<S: Signers + ?Sized>(
     client: &RpcClient,
     message: Message,
     signers: &S,
@@ -218,7 +249,7 @@ fn send_and_confirm_message<S: Signers>(
     }
 }
 
-fn send_and_confirm_messages<S: Signers>(
+fn send_and_confirm_messages<S: Signers + ?Sized>(
     client: &RpcClient,
     messages: Vec<Message>,
     signers: &S,
@@ -233,6 +264,36 @@ fn send_and_confirm_messages<S: Signers>(
     Ok(signatures)
 }
 
+// Send legacy transactions, or v0 transactions packing several messages each when a lookup
+// table is requested
+fn send_messages(
+    client: &RpcClient,
+    messages: Vec<Message>,
+    signers: &[&dyn Signer],
+    fee_payer: &dyn Signer,
+    lookup_table_args: &Option<LookupTableArgs<Pubkey>>,
+    no_wait: bool,
+) -> Result<Vec<Signature>, ClientError> {
+    let Some(lookup_table_args) = lookup_table_args else {
+        return send_and_confirm_messages(client, messages, signers, no_wait);
+    };
+    let lookup_tables =
+        lookup_table::get_lookup_tables(client, lookup_table_args, fee_payer, &messages)?;
+    let batches = lookup_table_utils::batch_messages::<ClientError>(
+        &messages,
+        &fee_payer.pubkey(),
+        &lookup_tables,
+    )?;
+    let mut signatures = vec![];
+    for BatchedMessage { message, .. } in batches {
+        let signature =
+            lookup_table::send_and_confirm_versioned_message(client, message, signers, no_wait)?;
+        signatures.push(signature);
+        println!("{signature}");
+    }
+    Ok(signatures)
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let command_args = parse_args(env::args_os());
     let config = Config::load(&command_args.config_file).unwrap_or_default();
@@ -0,0 +1,366 @@
+//! Loads accounts the test validator is missing from another cluster the first time a
+//! transaction references them. Accounts are fetched when RPC receives the transaction, or in
+//! the background when a transaction referencing them reaches banking first.
+use {
+    crossbeam_channel::{unbounded, Sender},
+    log::*,
+    solana_cli_output::CliAccount,
+    solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS,
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_runtime::runtime_config::MissingAccountsLoader,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        pubkey::Pubkey,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+        fs::{self, File},
+        io::{self, Write},
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, Mutex},
+        thread::Builder,
+    },
+};
+
+pub struct RpcMissingAccountsLoader {
+    fetcher: Arc<AccountsFetcher>,
+    // Accounts that transactions referenced before they were prefetched, fetched by a background
+    // thread so that banking never waits for RPC
+    background_fetch_sender: Sender<Vec<Pubkey>>,
+}
+
+struct AccountsFetcher {
+    rpc_client: RpcClient,
+    cache_path: Option<PathBuf>,
+    // `None` marks accounts that don't exist on the cluster either
+    cache: Mutex<HashMap<Pubkey, Option<AccountSharedData>>>,
+    // Accounts stored by a bank, which are never loaded again even if they are closed locally
+    stored: Mutex<HashSet<Pubkey>>,
+}
+
+impl fmt::Debug for RpcMissingAccountsLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcMissingAccountsLoader")
+            .field("url", &self.fetcher.rpc_client.url())
+            .field("cache_path", &self.fetcher.cache_path)
+            .finish()
+    }
+}
+
+impl RpcMissingAccountsLoader {
+    /// Fetches missing accounts from `rpc_client`'s cluster. Fetched accounts are recorded in
+    /// `cache_path`, in the format of `solana account --output json`, and accounts already in
+    /// the file are never fetched again. Accounts closed locally are only remembered until the
+    /// validator restarts, so the cache file should be removed along with the ledger.
+    pub fn new(rpc_client: RpcClient, cache_path: Option<PathBuf>) -> Result<Self, String> {
+        let cache = match &cache_path {
+            Some(cache_path) if cache_path.exists() => read_cache(cache_path)?,
+            _ => HashMap::new(),
+        };
+        let fetcher = Arc::new(AccountsFetcher {
+            rpc_client,
+            cache_path,
+            cache: Mutex::new(cache),
+            stored: Mutex::default(),
+        });
+        let (background_fetch_sender, background_fetch_receiver) = unbounded::<Vec<Pubkey>>();
+        {
+            let fetcher = fetcher.clone();
+            Builder::new()
+                .name("solLazyClone".to_string())
+                .spawn(move || {
+                    // Exits once the loader is dropped
+                    for pubkeys in background_fetch_receiver {
+                        fetcher.prefetch(&pubkeys);
+                    }
+                })
+                .map_err(|err| format!("Unable to spawn the lazy clone thread: {err}"))?;
+        }
+        Ok(Self {
+            fetcher,
+            background_fetch_sender,
+        })
+    }
+}
+
+impl AccountsFetcher {
+    /// Fetches the accounts among `pubkeys` that aren't cached yet, and the programdata accounts
+    /// of the upgradeable programs among them. The cache isn't locked while RPC requests are
+    /// pending.
+    fn prefetch(&self, pubkeys: &[Pubkey]) {
+        let unknown_pubkeys = self.unknown_pubkeys(pubkeys.iter().copied());
+        if unknown_pubkeys.is_empty() {
+            return;
+        }
+        let mut fetched_accounts = self.fetch_accounts(&unknown_pubkeys);
+
+        // Upgradeable programs are useless without their programdata accounts
+        let programdata_addresses = self.unknown_pubkeys(
+            fetched_accounts
+                .iter()
+                .filter_map(|(_, account)| account.as_ref().and_then(programdata_address)),
+        );
+        fetched_accounts.extend(self.fetch_accounts(&programdata_addresses));
+
+        let mut cache = self.cache.lock().unwrap();
+        for (pubkey, account) in fetched_accounts {
+            // Keep the account fetched first if another thread fetched it in the meantime
+            cache.entry(pubkey).or_insert(account);
+        }
+        self.write_cache(&cache);
+    }
+
+    fn unknown_pubkeys(&self, pubkeys: impl Iterator<Item = Pubkey>) -> Vec<Pubkey> {
+        let cache = self.cache.lock().unwrap();
+        let mut seen = HashSet::new();
+        pubkeys
+            .filter(|pubkey| !cache.contains_key(pubkey) && seen.insert(*pubkey))
+            .collect()
+    }
+
+    fn fetch_accounts(&self, pubkeys: &[Pubkey]) -> Vec<(Pubkey, Option<AccountSharedData>)> {
+        let mut fetched_accounts = vec![];
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            info!("Fetching {:?} over RPC...", chunk);
+            match self.rpc_client.get_multiple_accounts(chunk) {
+                Ok(accounts) => {
+                    for (pubkey, account) in chunk.iter().zip(accounts) {
+                        let account = account.map(|account| {
+                            let mut account = AccountSharedData::from(account);
+                            reset_programdata_slot(&mut account);
+                            account
+                        });
+                        fetched_accounts.push((*pubkey, account));
+                    }
+                }
+                // Not cached, so the accounts are fetched again by the next transaction
+                Err(err) => warn!("Failed to fetch {:?}: {}", chunk, err),
+            }
+        }
+        fetched_accounts
+    }
+
+    fn write_cache(&self, cache: &HashMap<Pubkey, Option<AccountSharedData>>) {
+        let Some(cache_path) = &self.cache_path else {
+            return;
+        };
+        let accounts: Vec<CliAccount> = cache
+            .iter()
+            .filter_map(|(pubkey, account)| {
+                account
+                    .as_ref()
+                    .map(|account| CliAccount::new(pubkey, account, true))
+            })
+            .collect();
+        let result = serde_json::to_string_pretty(&accounts)
+            .map_err(|err| err.to_string())
+            .and_then(|json| write_cache_file(cache_path, &json).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            warn!("Failed to write {}: {}", cache_path.display(), err);
+        }
+    }
+
+    /// Returns the cached accounts among `pubkeys` that weren't stored yet, and the pubkeys
+    /// that aren't cached
+    fn cached_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> (Vec<(Pubkey, AccountSharedData)>, Vec<Pubkey>) {
+        let cache = self.cache.lock().unwrap();
+        let stored = self.stored.lock().unwrap();
+        let mut accounts = vec![];
+        let mut unknown_pubkeys = vec![];
+        let mut add_account = |pubkey: &Pubkey, accounts: &mut Vec<_>| match cache.get(pubkey) {
+            Some(Some(account)) if !stored.contains(pubkey) => {
+                accounts.push((*pubkey, account.clone()));
+            }
+            Some(_) => (),
+            None => unknown_pubkeys.push(*pubkey),
+        };
+        for pubkey in pubkeys {
+            add_account(pubkey, &mut accounts);
+        }
+        let programdata_addresses: Vec<Pubkey> = accounts
+            .iter()
+            .filter_map(|(_, account)| programdata_address(account))
+            .filter(|programdata_address| !pubkeys.contains(programdata_address))
+            .collect();
+        for programdata_address in &programdata_addresses {
+            add_account(programdata_address, &mut accounts);
+        }
+        (accounts, unknown_pubkeys)
+    }
+}
+
+impl MissingAccountsLoader for RpcMissingAccountsLoader {
+    fn prefetch_accounts(&self, pubkeys: &[Pubkey]) {
+        self.fetcher.prefetch(pubkeys);
+    }
+
+    fn load_missing_accounts(&self, pubkeys: &[Pubkey]) -> Vec<(Pubkey, AccountSharedData)> {
+        let (accounts, unknown_pubkeys) = self.fetcher.cached_accounts(pubkeys);
+        if !unknown_pubkeys.is_empty() {
+            // The thread only exits once the loader is dropped
+            let _ = self.background_fetch_sender.send(unknown_pubkeys);
+        }
+        accounts
+    }
+
+    fn accounts_stored(&self, pubkeys: &[Pubkey]) {
+        self.fetcher.stored.lock().unwrap().extend(pubkeys);
+    }
+}
+
+/// Writes a temporary file first and renames it, so that an interrupted write never leaves
+/// a truncated cache behind
+fn write_cache_file(cache_path: &Path, json: &str) -> io::Result<()> {
+    let mut tmp_path = cache_path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, cache_path)
+}
+
+fn read_cache(cache_path: &Path) -> Result<HashMap<Pubkey, Option<AccountSharedData>>, String> {
+    let json = fs::read_to_string(cache_path)
+        .map_err(|err| format!("Unable to read {}: {}", cache_path.display(), err))?;
+    let accounts: Vec<CliAccount> = serde_json::from_str(&json)
+        .map_err(|err| format!("Unable to deserialize {}: {}", cache_path.display(), err))?;
+    accounts
+        .into_iter()
+        .map(|account| {
+            let pubkey = Pubkey::from_str(&account.keyed_account.pubkey)
+                .map_err(|err| format!("Invalid address in {}: {}", cache_path.display(), err))?;
+            let account = account
+                .keyed_account
+                .account
+                .decode::<AccountSharedData>()
+                .ok_or_else(|| format!("Unable to decode {pubkey} in {}", cache_path.display()))?;
+            Ok((pubkey, Some(account)))
+        })
+        .collect()
+}
+
+fn programdata_address(account: &AccountSharedData) -> Option<Pubkey> {
+    if !bpf_loader_upgradeable::check_id(account.owner()) {
+        return None;
+    }
+    match account.deserialize_data() {
+        Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) => Some(programdata_address),
+        _ => None,
+    }
+}
+
+// Programs deployed at a later slot than the local cluster's aren't visible yet, so fetched
+// programs are made to look like they were deployed at genesis
+fn reset_programdata_slot(account: &mut AccountSharedData) {
+    if !bpf_loader_upgradeable::check_id(account.owner()) {
+        return;
+    }
+    if let Ok(UpgradeableLoaderState::ProgramData {
+        upgrade_authority_address,
+        ..
+    }) = account.deserialize_data()
+    {
+        let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+        if let Some(metadata) = account.data_as_mut_slice().get_mut(..metadata_len) {
+            let _ = bincode::serialize_into(
+                metadata,
+                &UpgradeableLoaderState::ProgramData {
+                    slot: 0,
+                    upgrade_authority_address,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::system_program};
+
+    #[test]
+    fn test_reset_programdata_slot() {
+        let upgrade_authority_address = Some(Pubkey::new_unique());
+        let mut data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 42,
+            upgrade_authority_address,
+        })
+        .unwrap();
+        data.resize(
+            UpgradeableLoaderState::size_of_programdata_metadata() + 8,
+            7,
+        );
+        let mut account = AccountSharedData::from(solana_sdk::account::Account {
+            lamports: 1,
+            data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        });
+        reset_programdata_slot(&mut account);
+        assert_eq!(
+            account
+                .deserialize_data::<UpgradeableLoaderState>()
+                .unwrap(),
+            UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address,
+            }
+        );
+        assert_eq!(
+            &account.data()[UpgradeableLoaderState::size_of_programdata_metadata()..],
+            &[7; 8]
+        );
+
+        let system_account = AccountSharedData::new(1, 0, &system_program::id());
+        assert_eq!(programdata_address(&system_account), None);
+    }
+
+    #[test]
+    fn test_cache_file() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache_path = cache_dir.path().join("accounts.json");
+        let pubkey = Pubkey::new_unique();
+        let account = AccountSharedData::new(42, 3, &system_program::id());
+
+        // The url is never contacted since all requested accounts are cached
+        let loader = RpcMissingAccountsLoader::new(
+            RpcClient::new("http://127.0.0.1:1".to_string()),
+            Some(cache_path.clone()),
+        )
+        .unwrap();
+        loader
+            .fetcher
+            .cache
+            .lock()
+            .unwrap()
+            .insert(pubkey, Some(account.clone()));
+        loader
+            .fetcher
+            .write_cache(&loader.fetcher.cache.lock().unwrap());
+        assert!(!cache_dir.path().join("accounts.json.tmp").exists());
+
+        let loader = RpcMissingAccountsLoader::new(
+            RpcClient::new("http://127.0.0.1:1".to_string()),
+            Some(cache_path),
+        )
+        .unwrap();
+        loader.prefetch_accounts(&[pubkey]);
+        assert_eq!(
+            loader.load_missing_accounts(&[pubkey]),
+            vec![(pubkey, account)]
+        );
+
+        // Accounts stored by a bank aren't loaded again once they are closed locally
+        loader.accounts_stored(&[pubkey]);
+        assert_eq!(loader.load_missing_accounts(&[pubkey]), vec![]);
+    }
+}
@@ -33,8 +33,11 @@ use {
     solana_rpc::{rpc::JsonRpcConfig, rpc_pubsub_service::PubSubConfig},
     solana_rpc_client::{nonblocking, rpc_client::RpcClient},
     solana_runtime::{
-        bank_forks::BankForks, genesis_utils::create_genesis_config_with_leader_ex,
-        runtime_config::RuntimeConfig, snapshot_config::SnapshotConfig,
+        bank_forks::BankForks,
+        bank_overrides::BankOverrides,
+        genesis_utils::create_genesis_config_with_leader_ex,
+        runtime_config::{MissingAccountsLoader, RuntimeConfig},
+        snapshot_config::SnapshotConfig,
     },
     solana_sdk::{
         account::{Account, AccountSharedData},
@@ -72,6 +75,9 @@ use {
     tokio::time::sleep,
 };
 
+mod lazy_clone;
+pub use lazy_clone::RpcMissingAccountsLoader;
+
 #[derive(Clone)]
 pub struct AccountInfo<'a> {
     pub address: Option<Pubkey>,
@@ -94,6 +100,14 @@ pub struct UpgradeableProgramInfo {
     pub program_path: PathBuf,
 }
 
+#[derive(Clone)]
+pub struct LoaderV4ProgramInfo {
+    pub program_id: Pubkey,
+    /// `None` deploys the program finalized
+    pub authority: Option<Pubkey>,
+    pub program_path: PathBuf,
+}
+
 #[derive(Debug)]
 pub struct TestValidatorNodeConfig {
     gossip_addr: SocketAddr,
@@ -130,6 +144,7 @@ pub struct TestValidatorGenesis {
     #[allow(deprecated)]
     programs: Vec<ProgramInfo>,
     upgradeable_programs: Vec<UpgradeableProgramInfo>,
+    loader_v4_programs: Vec<LoaderV4ProgramInfo>,
     ticks_per_slot: Option<u64>,
     epoch_schedule: Option<EpochSchedule>,
     node_config: TestValidatorNodeConfig,
@@ -144,6 +159,8 @@ pub struct TestValidatorGenesis {
     compute_unit_limit: Option<u64>,
     pub log_messages_bytes_limit: Option<usize>,
     pub transaction_account_lock_limit: Option<usize>,
+    missing_accounts_loader: Option<Arc<dyn MissingAccountsLoader>>,
+    bank_overrides: Arc<BankOverrides>,
     pub tpu_enable_udp: bool,
     pub geyser_plugin_manager: Arc<RwLock<GeyserPluginManager>>,
     admin_rpc_service_post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
@@ -164,6 +181,7 @@ impl Default for TestValidatorGenesis {
             #[allow(deprecated)]
             programs: Vec::<ProgramInfo>::default(),
             upgradeable_programs: Vec::<UpgradeableProgramInfo>::default(),
+            loader_v4_programs: Vec::<LoaderV4ProgramInfo>::default(),
             ticks_per_slot: Option::<u64>::default(),
             epoch_schedule: Option::<EpochSchedule>::default(),
             node_config: TestValidatorNodeConfig::default(),
@@ -178,6 +196,8 @@ impl Default for TestValidatorGenesis {
             compute_unit_limit: Option::<u64>::default(),
             log_messages_bytes_limit: Option::<usize>::default(),
             transaction_account_lock_limit: Option::<usize>::default(),
+            missing_accounts_loader: Option::<Arc<dyn MissingAccountsLoader>>::default(),
+            bank_overrides: Arc::<BankOverrides>::default(),
             tpu_enable_udp: DEFAULT_TPU_ENABLE_UDP,
             geyser_plugin_manager: Arc::new(RwLock::new(GeyserPluginManager::new())),
             admin_rpc_service_post_init:
@@ -365,6 +385,25 @@ impl TestValidatorGenesis {
         Ok(self)
     }
 
+    /// Changes to apply to the running validator's banks, e.g. to replace an account or set the
+    /// clock's unix timestamp once the validator has started
+    pub fn bank_overrides(&self) -> Arc<BankOverrides> {
+        self.bank_overrides.clone()
+    }
+
+    /// Fetches accounts from `rpc_client`'s cluster the first time a transaction references
+    /// them, instead of requiring them to be cloned upfront. Fetched accounts are saved to
+    /// `cache_path`, if any, so they are only fetched once across restarts.
+    pub fn lazy_clone_accounts(
+        &mut self,
+        rpc_client: RpcClient,
+        cache_path: Option<PathBuf>,
+    ) -> Result<&mut Self, String> {
+        let loader = RpcMissingAccountsLoader::new(rpc_client, cache_path)?;
+        self.missing_accounts_loader = Some(Arc::new(loader));
+        Ok(self)
+    }
+
     pub fn add_accounts_from_json_files(
         &mut self,
         accounts: &[AccountInfo],
@@ -525,6 +564,38 @@ impl TestValidatorGenesis {
         self
     }
 
+    /// Add a SBF program owned by loader-v4 to the test environment.
+    ///
+    /// `program_name` will also be used to locate the SBF shared object in the current or fixtures
+    /// directory. Without an `authority` the program is deployed finalized.
+    pub fn add_loader_v4_program(
+        &mut self,
+        program_name: &str,
+        program_id: Pubkey,
+        authority: Option<Pubkey>,
+    ) -> &mut Self {
+        let program_path = solana_program_test::find_file(&format!("{program_name}.so"))
+            .unwrap_or_else(|| panic!("Unable to locate program {program_name}"));
+
+        self.loader_v4_programs.push(LoaderV4ProgramInfo {
+            program_id,
+            authority,
+            program_path,
+        });
+        self
+    }
+
+    /// Add a list of loader-v4 programs to the test environment.
+    pub fn add_loader_v4_programs_with_path(
+        &mut self,
+        programs: &[LoaderV4ProgramInfo],
+    ) -> &mut Self {
+        for program in programs {
+            self.loader_v4_programs.push(program.clone());
+        }
+        self
+    }
+
     /// Start a test validator with the address of the mint account that will receive tokens
     /// created at genesis.
     ///
@@ -767,6 +838,17 @@ impl TestValidator {
                 }),
             );
         }
+        for loader_v4_program in &config.loader_v4_programs {
+            let data = solana_program_test::read_file(&loader_v4_program.program_path);
+            accounts.insert(
+                loader_v4_program.program_id,
+                solana_program_test::programs::loader_v4_program_account(
+                    &data,
+                    loader_v4_program.authority,
+                    &Rent::default(),
+                ),
+            );
+        }
 
         let mut genesis_config = create_genesis_config_with_leader_ex(
             mint_lamports,
@@ -924,6 +1006,8 @@ impl TestValidator {
                 }),
             log_messages_bytes_limit: config.log_messages_bytes_limit,
             transaction_account_lock_limit: config.transaction_account_lock_limit,
+            missing_accounts_loader: config.missing_accounts_loader.clone(),
+            bank_overrides: Some(config.bank_overrides.clone()),
         };
 
         let mut validator_config = ValidatorConfig {
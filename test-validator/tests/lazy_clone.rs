@@ -0,0 +1,94 @@
+use {
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        fee::FeeStructure,
+        native_token::sol_to_lamports,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_transaction,
+    },
+    solana_streamer::socket::SocketAddrSpace,
+    solana_test_validator::{TestValidator, TestValidatorGenesis},
+};
+
+fn transfer(rpc_client: &RpcClient, from_keypair: &Keypair, to_pubkey: &Pubkey, lamports: u64) {
+    let transaction = system_transaction::transfer(
+        from_keypair,
+        to_pubkey,
+        lamports,
+        rpc_client.get_latest_blockhash().unwrap(),
+    );
+    rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .unwrap();
+}
+
+#[test]
+fn test_lazy_clone_accounts() {
+    solana_logger::setup();
+    let fee = FeeStructure::default().get_max_fee(1, 0);
+
+    // The upstream cluster is another local validator holding the account to clone
+    let upstream_mint_keypair = Keypair::new();
+    let upstream = TestValidator::with_no_fees(
+        upstream_mint_keypair.pubkey(),
+        None,
+        SocketAddrSpace::Unspecified,
+    );
+    let upstream_rpc_client =
+        RpcClient::new_with_commitment(upstream.rpc_url(), CommitmentConfig::processed());
+    let from_keypair = Keypair::new();
+    let lamports = sol_to_lamports(1.0);
+    transfer(
+        &upstream_rpc_client,
+        &upstream_mint_keypair,
+        &from_keypair.pubkey(),
+        lamports,
+    );
+
+    let mut genesis = TestValidatorGenesis::default();
+    genesis
+        .lazy_clone_accounts(
+            RpcClient::new_with_commitment(upstream.rpc_url(), CommitmentConfig::processed()),
+            None,
+        )
+        .unwrap();
+    let (test_validator, _mint_keypair) = genesis.start();
+    let rpc_client =
+        RpcClient::new_with_commitment(test_validator.rpc_url(), CommitmentConfig::processed());
+
+    // The account is fetched from upstream the first time a transaction references it
+    let to_pubkey = Pubkey::new_unique();
+    transfer(&rpc_client, &from_keypair, &to_pubkey, 1_000_000);
+    assert_eq!(rpc_client.get_balance(&to_pubkey).unwrap(), 1_000_000);
+    let balance = lamports - 1_000_000 - fee;
+    assert_eq!(
+        rpc_client.get_balance(&from_keypair.pubkey()).unwrap(),
+        balance
+    );
+    assert_eq!(
+        upstream_rpc_client
+            .get_balance(&from_keypair.pubkey())
+            .unwrap(),
+        lamports
+    );
+
+    // Once closed locally, the account isn't fetched again
+    transfer(&rpc_client, &from_keypair, &to_pubkey, balance - fee);
+    assert_eq!(rpc_client.get_balance(&from_keypair.pubkey()).unwrap(), 0);
+    let transaction = system_transaction::transfer(
+        &from_keypair,
+        &to_pubkey,
+        1,
+        rpc_client.get_latest_blockhash().unwrap(),
+    );
+    assert!(rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .is_err());
+    assert_eq!(rpc_client.get_balance(&from_keypair.pubkey()).unwrap(), 0);
+    assert_eq!(
+        rpc_client.get_balance(&to_pubkey).unwrap(),
+        1_000_000 + balance - fee
+    );
+}
@@ -1,13 +1,13 @@
 use {
     crate::args::{
-        Args, BalancesArgs, Command, DistributeTokensArgs, SenderStakeArgs, SplTokenArgs,
-        StakeArgs, TransactionLogArgs,
+        Args, BalancesArgs, Command, DistributeTokensArgs, LookupTableArgs, SenderStakeArgs,
+        SplTokenArgs, StakeArgs, TransactionLogArgs,
     },
     clap::{
         crate_description, crate_name, value_t, value_t_or_exit, App, Arg, ArgMatches, SubCommand,
     },
     solana_clap_utils::{
-        input_parsers::{pubkey_of_signer, value_of},
+        input_parsers::{pubkey_of, pubkey_of_signer, value_of},
         input_validators::{is_amount, is_url_or_moniker, is_valid_pubkey, is_valid_signer},
         keypair::{pubkey_from_path, signer_from_path},
     },
@@ -17,6 +17,28 @@ use {
     std::{error::Error, ffi::OsString, process::exit},
 };
 
+fn lookup_table_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("lookup_table")
+        .long("lookup-table")
+        .takes_value(true)
+        .value_name("LOOKUP_TABLE_ADDRESS")
+        .validator(is_valid_pubkey)
+        .help(
+            "Send v0 transactions that load addresses from this lookup table and pay as many \
+            recipients per transaction as fit",
+        )
+}
+
+fn create_lookup_table_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("create_lookup_table")
+        .long("create-lookup-table")
+        .conflicts_with("lookup_table")
+        .help(
+            "Create lookup tables holding the recipients' addresses, owned by the fee payer, and \
+            send v0 transactions that pay as many recipients per transaction as fit",
+        )
+}
+
 fn get_matches<'a, I, T>(args: I) -> ArgMatches<'a>
 where
     I: IntoIterator<Item = T>,
@@ -109,7 +131,9 @@ where
                         .value_name("KEYPAIR")
                         .validator(is_valid_signer)
                         .help("Fee payer"),
-                ),
+                )
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("create-stake")
@@ -180,7 +204,9 @@ where
                         .value_name("KEYPAIR")
                         .validator(is_valid_signer)
                         .help("Fee payer"),
-                ),
+                )
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("distribute-stake")
@@ -278,7 +304,9 @@ where
                         .value_name("KEYPAIR")
                         .validator(is_valid_signer)
                         .help("Fee payer"),
-                ),
+                )
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("distribute-spl-tokens")
@@ -350,7 +378,9 @@ where
                         .value_name("KEYPAIR")
                         .validator(is_valid_signer)
                         .help("Fee payer"),
-                ),
+                )
+                .arg(lookup_table_arg())
+                .arg(create_lookup_table_arg()),
         )
         .subcommand(
             SubCommand::with_name("balances")
@@ -408,6 +438,14 @@ where
         .get_matches_from(args)
 }
 
+fn parse_lookup_table_args(matches: &ArgMatches<'_>) -> Option<LookupTableArgs> {
+    if matches.is_present("create_lookup_table") {
+        Some(LookupTableArgs::Create)
+    } else {
+        pubkey_of(matches, "lookup_table").map(LookupTableArgs::Existing)
+    }
+}
+
 fn parse_distribute_tokens_args(
     matches: &ArgMatches<'_>,
 ) -> Result<DistributeTokensArgs, Box<dyn Error>> {
@@ -440,6 +478,7 @@ fn parse_distribute_tokens_args(
         stake_args: None,
         spl_token_args: None,
         transfer_amount: value_of(matches, "transfer_amount").map(sol_to_lamports),
+        lookup_table_args: parse_lookup_table_args(matches),
     })
 }
 
@@ -492,6 +531,7 @@ fn parse_create_stake_args(
         stake_args: Some(stake_args),
         spl_token_args: None,
         transfer_amount: None,
+        lookup_table_args: parse_lookup_table_args(matches),
     })
 }
 
@@ -576,6 +616,7 @@ fn parse_distribute_stake_args(
         stake_args: Some(stake_args),
         spl_token_args: None,
         transfer_amount: None,
+        lookup_table_args: parse_lookup_table_args(matches),
     })
 }
 
@@ -622,6 +663,7 @@ fn parse_distribute_spl_tokens_args(
             ..SplTokenArgs::default()
         }),
         transfer_amount: value_of(matches, "transfer_amount"),
+        lookup_table_args: parse_lookup_table_args(matches),
     })
 }
 
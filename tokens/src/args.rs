@@ -24,6 +24,16 @@ pub struct DistributeTokensArgs {
     pub stake_args: Option<StakeArgs>,
     pub spl_token_args: Option<SplTokenArgs>,
     pub transfer_amount: Option<u64>,
+    pub lookup_table_args: Option<LookupTableArgs>,
+}
+
+/// Send v0 transactions that load the recipients' addresses from address lookup tables,
+/// which lets several allocations share a transaction.
+pub enum LookupTableArgs {
+    /// Use an existing lookup table
+    Existing(Pubkey),
+    /// Create lookup tables holding the recipients' addresses, owned by the fee payer
+    Create,
 }
 
 #[derive(Default)]
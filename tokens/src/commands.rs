@@ -1,7 +1,8 @@
 use {
     crate::{
         args::{
-            BalancesArgs, DistributeTokensArgs, SenderStakeArgs, StakeArgs, TransactionLogArgs,
+            BalancesArgs, DistributeTokensArgs, LookupTableArgs, SenderStakeArgs, StakeArgs,
+            TransactionLogArgs,
         },
         db::{self, TransactionInfo},
         spl_token::*,
@@ -21,6 +22,7 @@ use {
         config::RpcSendTransactionConfig,
         request::{MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_MULTIPLE_ACCOUNTS},
     },
+    solana_rpc_client_lookup_table_utils::{self as lookup_table_utils, BatchedMessage},
     solana_sdk::{
         clock::Slot,
         commitment_config::CommitmentConfig,
@@ -34,13 +36,14 @@ use {
             state::{Authorized, Lockup, StakeAuthorize, StakeStateV2},
         },
         system_instruction,
-        transaction::Transaction,
+        transaction::{Transaction, VersionedTransaction},
     },
     solana_transaction_status::TransactionStatus,
     spl_associated_token_account::get_associated_token_address,
     spl_token::solana_program::program_error::ProgramError,
     std::{
         cmp::{self},
+        collections::HashSet,
         io,
         sync::{
             atomic::{AtomicBool, Ordering},
@@ -116,6 +119,8 @@ pub enum Error {
     ProgramError(#[from] ProgramError),
     #[error("Exit signal received")]
     ExitSignal,
+    #[error("Lookup table error: {0}")]
+    LookupTableError(#[from] lookup_table_utils::Error),
 }
 
 fn merge_allocations(allocations: &[Allocation]) -> Vec<Allocation> {
@@ -379,6 +384,30 @@ fn build_messages(
     Ok(())
 }
 
+fn push_allocation_signers<'a>(
+    signers: &mut Vec<&'a dyn Signer>,
+    allocation: &Allocation,
+    args: &'a DistributeTokensArgs,
+    new_stake_account_keypair: &'a Keypair,
+) -> Result<(), Error> {
+    if let Some(stake_args) = &args.stake_args {
+        signers.push(new_stake_account_keypair);
+        if let Some(sender_stake_args) = &stake_args.sender_stake_args {
+            signers.push(&*sender_stake_args.stake_authority);
+            signers.push(&*sender_stake_args.withdraw_authority);
+            signers.push(new_stake_account_keypair);
+            if !allocation.lockup_date.is_empty() {
+                if let Some(lockup_authority) = &sender_stake_args.lockup_authority {
+                    signers.push(&**lockup_authority);
+                } else {
+                    return Err(Error::MissingLockupAuthority);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn send_messages(
     client: &RpcClient,
     db: &mut PickleDb,
@@ -398,21 +427,7 @@ fn send_messages(
         let new_stake_account_address = new_stake_account_keypair.pubkey();
 
         let mut signers = vec![&*args.fee_payer, &*args.sender_keypair];
-        if let Some(stake_args) = &args.stake_args {
-            signers.push(&new_stake_account_keypair);
-            if let Some(sender_stake_args) = &stake_args.sender_stake_args {
-                signers.push(&*sender_stake_args.stake_authority);
-                signers.push(&*sender_stake_args.withdraw_authority);
-                signers.push(&new_stake_account_keypair);
-                if !allocation.lockup_date.is_empty() {
-                    if let Some(lockup_authority) = &sender_stake_args.lockup_authority {
-                        signers.push(&**lockup_authority);
-                    } else {
-                        return Err(Error::MissingLockupAuthority);
-                    }
-                }
-            }
-        }
+        push_allocation_signers(&mut signers, allocation, args, &new_stake_account_keypair)?;
         let signers = unique_signers(signers);
         let result: ClientResult<(Transaction, u64)> = {
             if args.dry_run {
@@ -452,6 +467,88 @@ fn send_messages(
     Ok(())
 }
 
+fn send_batched_messages(
+    client: &RpcClient,
+    db: &mut PickleDb,
+    allocations: &[Allocation],
+    args: &DistributeTokensArgs,
+    exit: Arc<AtomicBool>,
+    batches: Vec<BatchedMessage>,
+    stake_extras: StakeExtras,
+) -> Result<(), Error> {
+    for BatchedMessage {
+        mut message,
+        messages: range,
+    } in batches
+    {
+        if exit.load(Ordering::SeqCst) {
+            db.dump()?;
+            return Err(Error::ExitSignal);
+        }
+        let batch_allocations = &allocations[range.clone()];
+        let batch_stake_extras = &stake_extras[range];
+
+        let mut signers = vec![&*args.fee_payer, &*args.sender_keypair];
+        for (allocation, (new_stake_account_keypair, _)) in
+            batch_allocations.iter().zip(batch_stake_extras)
+        {
+            push_allocation_signers(&mut signers, allocation, args, new_stake_account_keypair)?;
+        }
+        let signers = unique_signers(signers);
+        let result: ClientResult<(VersionedTransaction, u64)> = {
+            if args.dry_run {
+                let num_signatures = usize::from(message.header().num_required_signatures);
+                let transaction = VersionedTransaction {
+                    signatures: vec![Signature::default(); num_signatures],
+                    message,
+                };
+                Ok((transaction, std::u64::MAX))
+            } else {
+                client
+                    .get_latest_blockhash_with_commitment(CommitmentConfig::default())
+                    .and_then(|(blockhash, last_valid_block_height)| {
+                        message.set_recent_blockhash(blockhash);
+                        let transaction = VersionedTransaction::try_new(message, &signers)?;
+                        let config = RpcSendTransactionConfig {
+                            skip_preflight: true,
+                            ..RpcSendTransactionConfig::default()
+                        };
+                        client.send_transaction_with_config(&transaction, config)?;
+                        Ok((transaction, last_valid_block_height))
+                    })
+            }
+        };
+        match result {
+            Ok((transaction, last_valid_block_height)) => {
+                for (batch_index, (allocation, (new_stake_account_keypair, lockup_date))) in
+                    batch_allocations.iter().zip(batch_stake_extras).enumerate()
+                {
+                    let new_stake_account_address = new_stake_account_keypair.pubkey();
+                    let new_stake_account_address_option =
+                        args.stake_args.as_ref().map(|_| &new_stake_account_address);
+                    db::set_batched_transaction_info(
+                        db,
+                        &allocation.recipient.parse().unwrap(),
+                        allocation.amount,
+                        &transaction,
+                        batch_index,
+                        new_stake_account_address_option,
+                        false,
+                        last_valid_block_height,
+                        *lockup_date,
+                    )?;
+                }
+            }
+            Err(e) => {
+                for allocation in batch_allocations {
+                    eprintln!("Error sending tokens to {}: {}", allocation.recipient, e);
+                }
+            }
+        };
+    }
+    Ok(())
+}
+
 fn distribute_allocations(
     client: &RpcClient,
     db: &mut PickleDb,
@@ -480,7 +577,37 @@ fn distribute_allocations(
         check_payer_balances(&messages, allocations, client, args)?;
     }
 
-    send_messages(client, db, allocations, args, exit, messages, stake_extras)?;
+    if let Some(lookup_table_args) = &args.lookup_table_args {
+        let lookup_tables = match lookup_table_args {
+            LookupTableArgs::Existing(lookup_table_address) => {
+                vec![lookup_table_utils::fetch_lookup_table::<Error>(
+                    client,
+                    lookup_table_address,
+                )?]
+            }
+            LookupTableArgs::Create => {
+                let addresses = lookup_table_utils::lookup_table_candidates(&messages);
+                let lookup_tables = lookup_table_utils::create_lookup_tables::<Error>(
+                    client,
+                    &*args.fee_payer,
+                    &addresses,
+                    args.dry_run,
+                )?;
+                for lookup_table in &lookup_tables {
+                    println!("Lookup table: {}", lookup_table.key);
+                }
+                lookup_tables
+            }
+        };
+        let batches = lookup_table_utils::batch_messages::<Error>(
+            &messages,
+            &args.fee_payer.pubkey(),
+            &lookup_tables,
+        )?;
+        send_batched_messages(client, db, allocations, args, exit, batches, stake_extras)?;
+    } else {
+        send_messages(client, db, allocations, args, exit, messages, stake_extras)?;
+    }
 
     db.dump()?;
     Ok(())
@@ -682,10 +809,14 @@ fn update_finalized_transactions(
     exit: Arc<AtomicBool>,
 ) -> Result<Option<usize>, Error> {
     let transaction_infos = db::read_transaction_infos(db);
+    // Transactions paying several recipients are listed once per recipient
+    let mut seen_signatures = HashSet::new();
     let unconfirmed_transactions: Vec<_> = transaction_infos
         .iter()
         .filter_map(|info| {
-            if info.finalized_date.is_some() {
+            if info.finalized_date.is_some()
+                || !seen_signatures.insert(info.transaction.signatures[0])
+            {
                 None
             } else {
                 Some((&info.transaction, info.last_valid_block_height))
@@ -726,7 +857,7 @@ fn log_transaction_confirmations(
     client: &RpcClient,
     db: &mut PickleDb,
     exit: Arc<AtomicBool>,
-    unconfirmed_transactions: Vec<(&Transaction, Slot)>,
+    unconfirmed_transactions: Vec<(&VersionedTransaction, Slot)>,
     statuses: Vec<Option<TransactionStatus>>,
     confirmations: &mut Option<usize>,
 ) -> Result<(), Error> {
@@ -972,6 +1103,7 @@ pub fn test_process_distribute_tokens_with_client(
         stake_args: None,
         spl_token_args: None,
         transfer_amount,
+        lookup_table_args: None,
     };
     let confirmations = process_allocations(client, &args, exit.clone()).unwrap();
     assert_eq!(confirmations, None);
@@ -1079,6 +1211,7 @@ pub fn test_process_create_stake_with_client(client: &RpcClient, sender_keypair:
         spl_token_args: None,
         sender_keypair: Box::new(sender_keypair),
         transfer_amount: None,
+        lookup_table_args: None,
     };
     let confirmations = process_allocations(client, &args, exit.clone()).unwrap();
     assert_eq!(confirmations, None);
@@ -1211,6 +1344,7 @@ pub fn test_process_distribute_stake_with_client(client: &RpcClient, sender_keyp
         spl_token_args: None,
         sender_keypair: Box::new(sender_keypair),
         transfer_amount: None,
+        lookup_table_args: None,
     };
     let confirmations = process_allocations(client, &args, exit.clone()).unwrap();
     assert_eq!(confirmations, None);
@@ -1299,6 +1433,77 @@ mod tests {
         test_process_distribute_tokens_with_client(&client, alice, Some(sol_to_lamports(1.5)));
     }
 
+    #[test]
+    fn test_process_token_allocations_with_lookup_table() {
+        let alice = Keypair::new();
+        let test_validator = simple_test_validator_no_fees(alice.pubkey());
+        let url = test_validator.rpc_url();
+        let client = RpcClient::new_with_commitment(url, CommitmentConfig::processed());
+        let exit = Arc::new(AtomicBool::default());
+
+        let fee_payer = Keypair::new();
+        let transaction =
+            transfer(&client, sol_to_lamports(1.0), &alice, &fee_payer.pubkey()).unwrap();
+        client
+            .send_and_confirm_transaction_with_spinner(&transaction)
+            .unwrap();
+
+        let recipients: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+        let allocations_file = NamedTempFile::new().unwrap();
+        let input_csv = allocations_file.path().to_str().unwrap().to_string();
+        let mut wtr = csv::WriterBuilder::new().from_writer(allocations_file);
+        wtr.write_record(["recipient", "amount"]).unwrap();
+        for recipient in &recipients {
+            wtr.write_record([recipient.to_string(), "1".to_string()])
+                .unwrap();
+        }
+        wtr.flush().unwrap();
+
+        let dir = tempdir().unwrap();
+        let transaction_db = dir
+            .path()
+            .join("transactions.db")
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let args = DistributeTokensArgs {
+            sender_keypair: Box::new(alice),
+            fee_payer: Box::new(fee_payer),
+            dry_run: false,
+            input_csv,
+            transaction_db: transaction_db.clone(),
+            output_path: None,
+            stake_args: None,
+            spl_token_args: None,
+            transfer_amount: None,
+            lookup_table_args: Some(LookupTableArgs::Create),
+        };
+        let confirmations = process_allocations(&client, &args, exit.clone()).unwrap();
+        assert_eq!(confirmations, None);
+
+        let transaction_infos =
+            db::read_transaction_infos(&db::open_db(&transaction_db, true).unwrap());
+        assert_eq!(transaction_infos.len(), recipients.len());
+        assert!(transaction_infos
+            .iter()
+            .all(|info| info.finalized_date.is_some()));
+        let signatures: HashSet<_> = transaction_infos
+            .iter()
+            .map(|info| info.transaction.signatures[0])
+            .collect();
+        assert!(signatures.len() < recipients.len());
+        for recipient in &recipients {
+            assert_eq!(client.get_balance(recipient).unwrap(), sol_to_lamports(1.0));
+        }
+
+        // Running it again doesn't pay anybody twice
+        process_allocations(&client, &args, exit).unwrap();
+        let transaction_infos =
+            db::read_transaction_infos(&db::open_db(&transaction_db, true).unwrap());
+        assert_eq!(transaction_infos.len(), recipients.len());
+    }
+
     fn simple_test_validator_no_fees(pubkey: Pubkey) -> TestValidator {
         let test_validator =
             TestValidator::with_no_fees(pubkey, None, SocketAddrSpace::Unspecified);
@@ -1581,6 +1786,7 @@ mod tests {
             spl_token_args: None,
             sender_keypair: Box::new(Keypair::new()),
             transfer_amount: None,
+            lookup_table_args: None,
         };
         let lockup_date = lockup_date_str.parse().unwrap();
         let instructions = distribution_instructions(
@@ -1630,6 +1836,7 @@ mod tests {
             stake_args,
             spl_token_args: None,
             transfer_amount: None,
+            lookup_table_args: None,
         };
         (allocations, args)
     }
@@ -2107,6 +2314,7 @@ mod tests {
             stake_args: None,
             spl_token_args: None,
             transfer_amount: None,
+            lookup_table_args: None,
         };
         let allocation = Allocation {
             recipient: recipient.to_string(),
@@ -2175,7 +2383,7 @@ mod tests {
                 amount,
                 new_stake_account_address: None,
                 finalized_date: None,
-                transaction,
+                transaction: transaction.into(),
                 last_valid_block_height,
                 lockup_date: None,
             }
@@ -2229,6 +2437,7 @@ mod tests {
             stake_args: None,
             spl_token_args: None,
             transfer_amount: None,
+            lookup_table_args: None,
         };
         let allocation = Allocation {
             recipient: recipient.to_string(),
@@ -2279,7 +2488,7 @@ mod tests {
             amount,
             new_stake_account_address: None,
             finalized_date: None,
-            transaction,
+            transaction: transaction.into(),
             last_valid_block_height,
             lockup_date: None,
         }));
@@ -2288,7 +2497,7 @@ mod tests {
             amount,
             new_stake_account_address: None,
             finalized_date: None,
-            transaction: Transaction::new_unsigned(message),
+            transaction: Transaction::new_unsigned(message).into(),
             last_valid_block_height: std::u64::MAX,
             lockup_date: None,
         }));
@@ -2345,6 +2554,7 @@ mod tests {
             stake_args: None,
             spl_token_args: None,
             transfer_amount: None,
+            lookup_table_args: None,
         };
 
         let exit = Arc::new(AtomicBool::new(false));
@@ -2412,7 +2622,7 @@ mod tests {
             &client,
             &mut db,
             Arc::new(AtomicBool::new(false)),
-            vec![(&transaction, 111)],
+            vec![(&transaction.clone().into(), 111)],
             vec![Some(TransactionStatus {
                 slot: 40,
                 confirmations: Some(15),
@@ -2432,7 +2642,7 @@ mod tests {
             &client,
             &mut db,
             exit,
-            vec![(&transaction, 111)],
+            vec![(&transaction.into(), 111)],
             vec![Some(TransactionStatus {
                 slot: 55,
                 confirmations: None,
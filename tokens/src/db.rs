@@ -2,7 +2,12 @@ use {
     chrono::prelude::*,
     pickledb::{error::Error, PickleDb, PickleDbDumpPolicy},
     serde::{Deserialize, Serialize},
-    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature, transaction::Transaction},
+    solana_sdk::{
+        clock::Slot,
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::{Transaction, VersionedTransaction},
+    },
     solana_transaction_status::TransactionStatus,
     std::{cmp::Ordering, fs, io, path::Path},
 };
@@ -13,7 +18,8 @@ pub struct TransactionInfo {
     pub amount: u64,
     pub new_stake_account_address: Option<Pubkey>,
     pub finalized_date: Option<DateTime<Utc>>,
-    pub transaction: Transaction,
+    #[serde(with = "stored_transaction")]
+    pub transaction: VersionedTransaction,
     pub last_valid_block_height: Slot,
     pub lockup_date: Option<DateTime<Utc>>,
 }
@@ -39,13 +45,48 @@ impl Default for TransactionInfo {
             amount: 0,
             new_stake_account_address: None,
             finalized_date: None,
-            transaction,
+            transaction: transaction.into(),
             last_valid_block_height: 0,
             lockup_date: None,
         }
     }
 }
 
+// Legacy transactions are stored as `Transaction`s so that databases written before
+// versioned transactions were supported can still be read, and vice versa.
+mod stored_transaction {
+    use {
+        serde::{Deserialize, Deserializer, Serialize, Serializer},
+        solana_sdk::transaction::{Transaction, VersionedTransaction},
+    };
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredTransaction {
+        Legacy(Transaction),
+        Versioned(VersionedTransaction),
+    }
+
+    pub fn serialize<S: Serializer>(
+        transaction: &VersionedTransaction,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match transaction.clone().into_legacy_transaction() {
+            Some(transaction) => transaction.serialize(serializer),
+            None => transaction.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<VersionedTransaction, D::Error> {
+        Ok(match StoredTransaction::deserialize(deserializer)? {
+            StoredTransaction::Legacy(transaction) => transaction.into(),
+            StoredTransaction::Versioned(transaction) => transaction,
+        })
+    }
+}
+
 pub fn open_db(path: &str, dry_run: bool) -> Result<PickleDb, Error> {
     let policy = if dry_run {
         PickleDbDumpPolicy::NeverDump
@@ -112,6 +153,53 @@ pub fn set_transaction_info(
     finalized: bool,
     last_valid_block_height: u64,
     lockup_date: Option<DateTime<Utc>>,
+) -> Result<(), Error> {
+    set_batched_transaction_info(
+        db,
+        recipient,
+        amount,
+        &transaction.clone().into(),
+        0,
+        new_stake_account_address,
+        finalized,
+        last_valid_block_height,
+        lockup_date,
+    )
+}
+
+// Transactions that pay several recipients are stored once per recipient. The first
+// recipient is keyed by the transaction signature, the following ones by the signature and
+// their position in the batch.
+fn transaction_info_key(signature: &Signature, batch_index: usize) -> String {
+    if batch_index == 0 {
+        signature.to_string()
+    } else {
+        format!("{signature}:{batch_index}")
+    }
+}
+
+fn transaction_info_keys(db: &PickleDb, signature: &Signature) -> Vec<String> {
+    let mut keys = vec![transaction_info_key(signature, 0)];
+    loop {
+        let key = transaction_info_key(signature, keys.len());
+        if !db.exists(&key) {
+            return keys;
+        }
+        keys.push(key);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn set_batched_transaction_info(
+    db: &mut PickleDb,
+    recipient: &Pubkey,
+    amount: u64,
+    transaction: &VersionedTransaction,
+    batch_index: usize,
+    new_stake_account_address: Option<&Pubkey>,
+    finalized: bool,
+    last_valid_block_height: u64,
+    lockup_date: Option<DateTime<Utc>>,
 ) -> Result<(), Error> {
     let finalized_date = if finalized { Some(Utc::now()) } else { None };
     let transaction_info = TransactionInfo {
@@ -124,7 +212,10 @@ pub fn set_transaction_info(
         lockup_date,
     };
     let signature = transaction.signatures[0];
-    db.set(&signature.to_string(), &transaction_info)?;
+    db.set(
+        &transaction_info_key(&signature, batch_index),
+        &transaction_info,
+    )?;
     Ok(())
 }
 
@@ -167,14 +258,19 @@ pub fn update_finalized_transaction(
         eprintln!("Error in transaction with signature {signature}: {e}");
         eprintln!("Discarding transaction record");
         eprintln!();
-        db.rem(&signature.to_string())?;
+        for key in transaction_info_keys(db, signature) {
+            db.rem(&key)?;
+        }
         return Ok(None);
     }
 
     // Transaction is rooted. Set the finalized date in the database.
-    let mut transaction_info = db.get::<TransactionInfo>(&signature.to_string()).unwrap();
-    transaction_info.finalized_date = Some(Utc::now());
-    db.set(&signature.to_string(), &transaction_info)?;
+    let finalized_date = Utc::now();
+    for key in transaction_info_keys(db, signature) {
+        let mut transaction_info = db.get::<TransactionInfo>(&key).unwrap();
+        transaction_info.finalized_date = Some(finalized_date);
+        db.set(&key, &transaction_info)?;
+    }
     Ok(None)
 }
 
@@ -211,7 +307,12 @@ mod tests {
         super::*,
         assert_matches::assert_matches,
         csv::{ReaderBuilder, Trim},
-        solana_sdk::transaction::TransactionError,
+        solana_sdk::{
+            hash::Hash,
+            message::{v0, VersionedMessage},
+            system_instruction,
+            transaction::TransactionError,
+        },
         solana_transaction_status::TransactionConfirmationStatus,
         tempfile::NamedTempFile,
     };
@@ -377,4 +478,111 @@ mod tests {
             .finalized_date
             .is_some());
     }
+
+    #[test]
+    fn test_stored_transaction_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_file = dir.path().join("test.db").to_str().unwrap().to_string();
+        let mut db = open_db(&db_file, false).unwrap();
+
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let instructions = [system_instruction::transfer(&payer, &recipient, 42)];
+        let legacy_transaction = Transaction::new_unsigned(solana_sdk::message::Message::new(
+            &instructions,
+            Some(&payer),
+        ));
+        let v0_transaction = VersionedTransaction {
+            signatures: vec![Signature::new_unique()],
+            message: VersionedMessage::V0(
+                v0::Message::try_compile(&payer, &instructions, &[], Hash::new_unique()).unwrap(),
+            ),
+        };
+        set_transaction_info(
+            &mut db,
+            &recipient,
+            42,
+            &legacy_transaction,
+            None,
+            false,
+            0,
+            None,
+        )
+        .unwrap();
+        set_batched_transaction_info(
+            &mut db,
+            &recipient,
+            42,
+            &v0_transaction,
+            1,
+            None,
+            false,
+            0,
+            None,
+        )
+        .unwrap();
+        db.dump().unwrap();
+
+        let db = open_db(&db_file, true).unwrap();
+        let mut transactions: Vec<_> = read_transaction_infos(&db)
+            .into_iter()
+            .map(|info| info.transaction)
+            .collect();
+        transactions.sort_by_key(|transaction| transaction.signatures[0]);
+        let mut expected = vec![legacy_transaction.into(), v0_transaction];
+        expected.sort_by_key(|transaction: &VersionedTransaction| transaction.signatures[0]);
+        assert_eq!(transactions, expected);
+    }
+
+    #[test]
+    fn test_update_finalized_batched_transaction() {
+        let mut db =
+            PickleDb::new_yaml(NamedTempFile::new().unwrap(), PickleDbDumpPolicy::NeverDump);
+        let signature = Signature::default();
+        let transaction = TransactionInfo::default().transaction;
+        for batch_index in 0..3 {
+            set_batched_transaction_info(
+                &mut db,
+                &Pubkey::new_unique(),
+                batch_index as u64,
+                &transaction,
+                batch_index,
+                None,
+                false,
+                0,
+                None,
+            )
+            .unwrap();
+        }
+        assert_eq!(transaction_info_keys(&db, &signature).len(), 3);
+
+        let transaction_status = TransactionStatus {
+            slot: 0,
+            confirmations: None,
+            err: None,
+            status: Ok(()),
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        };
+        assert_eq!(
+            update_finalized_transaction(&mut db, &signature, Some(transaction_status), 0, 0)
+                .unwrap(),
+            None
+        );
+        let transaction_infos = read_transaction_infos(&db);
+        assert_eq!(transaction_infos.len(), 3);
+        assert!(transaction_infos
+            .iter()
+            .all(|info| info.finalized_date.is_some()));
+
+        // A failed batch discards the records of every recipient
+        let transaction_status = TransactionStatus {
+            slot: 0,
+            confirmations: None,
+            err: Some(TransactionError::AccountNotFound),
+            status: Ok(()),
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        };
+        update_finalized_transaction(&mut db, &signature, Some(transaction_status), 0, 0).unwrap();
+        assert!(read_transaction_infos(&db).is_empty());
+    }
 }
@@ -0,0 +1,200 @@
+//! A command-line executable for simulating shred propagation over turbine
+//! retransmit trees, to evaluate fanout changes against a stake distribution.
+
+use {
+    clap::{crate_description, crate_name, value_t_or_exit, values_t_or_exit, App, Arg},
+    rand::{Rng, SeedableRng},
+    rand_chacha::ChaChaRng,
+    solana_clap_utils::input_validators::{is_parsable, is_within_range},
+    solana_sdk::pubkey::Pubkey,
+    solana_turbine::{
+        cluster_nodes::DATA_PLANE_FANOUT,
+        simulator::{simulate, SimulationConfig, STAKE_THRESHOLDS},
+    },
+    std::{collections::HashMap, error, fs::File, process::exit, str::FromStr},
+};
+
+// Reads a JSON object mapping node identities to their stake in lamports, as
+// written by `solana-ledger-tool staked-nodes`.
+fn read_stakes(path: &str) -> Result<HashMap<Pubkey, u64>, Box<dyn error::Error>> {
+    let stakes: HashMap<String, u64> = serde_json::from_reader(File::open(path)?)?;
+    stakes
+        .into_iter()
+        .map(|(pubkey, stake)| Ok((Pubkey::from_str(&pubkey)?, stake)))
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    solana_logger::setup_with_default("solana=info");
+
+    let default_fanout = DATA_PLANE_FANOUT.to_string();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(solana_version::version!())
+        .arg(
+            Arg::with_name("stakes")
+                .long("stakes")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(true)
+                .help(
+                    "JSON file mapping node identities to their stake in lamports, \
+                     as written by `solana-ledger-tool staked-nodes`",
+                ),
+        )
+        .arg(
+            Arg::with_name("fanout")
+                .long("fanout")
+                .value_name("FANOUT")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .default_value(&default_fanout)
+                .validator(|value| is_within_range::<usize, _>(value, 1..))
+                .help("Turbine fanout to simulate; may be specified multiple times to compare"),
+        )
+        .arg(
+            Arg::with_name("num_slots")
+                .long("num-slots")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("100")
+                .validator(is_parsable::<usize>)
+                .help("Number of slots to simulate, each with a leader sampled by stake"),
+        )
+        .arg(
+            Arg::with_name("shreds_per_slot")
+                .long("shreds-per-slot")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("64")
+                .validator(is_parsable::<u32>)
+                .help("Number of shreds to simulate in each slot"),
+        )
+        .arg(
+            Arg::with_name("hop_latency_ms")
+                .long("hop-latency-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .default_value("20")
+                .validator(is_parsable::<u64>)
+                .help("Minimum latency of each hop down the tree"),
+        )
+        .arg(
+            Arg::with_name("hop_latency_jitter_ms")
+                .long("hop-latency-jitter-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .default_value("80")
+                .validator(is_parsable::<u64>)
+                .help("Maximum latency added to each hop, drawn uniformly at random"),
+        )
+        .arg(
+            Arg::with_name("packet_loss_rate")
+                .long("packet-loss-rate")
+                .value_name("RATE")
+                .takes_value(true)
+                .default_value("0.01")
+                .validator(|value| match value.parse::<f64>() {
+                    Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(()),
+                    Ok(rate) => Err(format!("packet loss rate {rate} not within [0, 1]")),
+                    Err(err) => Err(format!("error parsing '{value}': {err}")),
+                })
+                .help("Probability that each hop down the tree loses the shred"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Seed for the simulation; random if not specified"),
+        )
+        .get_matches();
+
+    let path = value_t_or_exit!(matches, "stakes", String);
+    let stakes = match read_stakes(&path) {
+        Ok(stakes) => stakes,
+        Err(err) => {
+            eprintln!("Failed to read stakes from {path}: {err}");
+            exit(1);
+        }
+    };
+    let num_nodes = stakes.values().filter(|stake| **stake > 0).count();
+    let total_stake: u64 = stakes.values().sum();
+    if num_nodes == 0 {
+        eprintln!("No staked nodes in {path}");
+        exit(1);
+    }
+    let num_slots = value_t_or_exit!(matches, "num_slots", usize);
+    let shreds_per_slot = value_t_or_exit!(matches, "shreds_per_slot", u32);
+    let hop_latency_ms = value_t_or_exit!(matches, "hop_latency_ms", u64);
+    let hop_latency_jitter_ms = value_t_or_exit!(matches, "hop_latency_jitter_ms", u64);
+    let packet_loss_rate = value_t_or_exit!(matches, "packet_loss_rate", f64);
+    let seed = matches
+        .value_of("seed")
+        .map(|seed| seed.parse().unwrap())
+        .unwrap_or_else(|| rand::thread_rng().gen());
+
+    println!("{num_nodes} staked nodes, {total_stake} lamports total stake, seed {seed}");
+    println!(
+        "{num_slots} slots of {shreds_per_slot} shreds, {hop_latency_ms}-{}ms per hop, \
+         {:.2}% packet loss",
+        hop_latency_ms.saturating_add(hop_latency_jitter_ms),
+        packet_loss_rate * 100.0,
+    );
+    println!();
+    // For each fraction of stake: median and p90 time for shreds to reach it,
+    // and the fraction of shreds which did.
+    let thresholds: String = STAKE_THRESHOLDS
+        .iter()
+        .map(|threshold| {
+            format!(
+                "  {:>22}",
+                format!("{:.0}% p50/p90/reached", threshold * 100.0)
+            )
+        })
+        .collect();
+    println!(
+        "{:>8} {:>6} {:>10} {:>10} {:>10}{thresholds}",
+        "Fanout", "Hops", "Nodes", "Stake", "Min stake",
+    );
+    for fanout in values_t_or_exit!(matches, "fanout", usize) {
+        let config = SimulationConfig {
+            fanout,
+            num_slots,
+            shreds_per_slot,
+            hop_latency_ms: hop_latency_ms..=hop_latency_ms.saturating_add(hop_latency_jitter_ms),
+            packet_loss_rate,
+        };
+        // Same seed for each fanout, so that they see the same slot leaders.
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let report = simulate(&mut rng, &stakes, &config);
+        let arrivals: String = (0..STAKE_THRESHOLDS.len())
+            .map(|k| {
+                let percentile = |percentile| {
+                    report
+                        .arrival_percentile_ms(k, percentile)
+                        .map(|arrival| format!("{arrival}ms"))
+                        .unwrap_or_else(|| "-".to_string())
+                };
+                let arrival = format!(
+                    "{}/{}/{:.1}%",
+                    percentile(50),
+                    percentile(90),
+                    report.fraction_reaching(k) * 100.0,
+                );
+                format!("  {arrival:>22}")
+            })
+            .collect();
+        println!(
+            "{:>8} {:>6} {:>9.2}% {:>9.2}% {:>9.2}%{arrivals}",
+            fanout,
+            report.max_num_hops(),
+            report.mean_nodes_reached() * 100.0,
+            report.mean_stake_reached() * 100.0,
+            report.min_stake_reached() * 100.0,
+        );
+    }
+    Ok(())
+}
@@ -36,7 +36,7 @@ use {
     thiserror::Error,
 };
 
-const DATA_PLANE_FANOUT: usize = 200;
+pub const DATA_PLANE_FANOUT: usize = 200;
 pub(crate) const MAX_NUM_TURBINE_HOPS: usize = 4;
 
 #[derive(Debug, Error)]
@@ -233,6 +233,26 @@ impl ClusterNodes<RetransmitStage> {
             addrs,
         })
     }
+
+    /// Returns all nodes but the slot leader, along with their stakes, in the
+    /// order they are laid out on the shred's retransmit tree; see
+    /// get_retransmit_children for the layout.
+    pub fn get_retransmit_tree(
+        &self,
+        slot_leader: &Pubkey,
+        shred: &ShredId,
+    ) -> Vec<(Pubkey, /*stake:*/ u64)> {
+        let mut weighted_shuffle = self.weighted_shuffle.clone();
+        if let Some(index) = self.index.get(slot_leader) {
+            weighted_shuffle.remove_index(*index);
+        }
+        let mut rng = ChaChaRng::from_seed(shred.seed(slot_leader));
+        weighted_shuffle
+            .shuffle(&mut rng)
+            .map(|index| &self.nodes[index])
+            .map(|node| (node.pubkey(), node.stake))
+            .collect()
+    }
 }
 
 pub fn new_cluster_nodes<T: 'static>(
@@ -241,6 +261,35 @@ pub fn new_cluster_nodes<T: 'static>(
 ) -> ClusterNodes<T> {
     let self_pubkey = cluster_info.id();
     let nodes = get_nodes(cluster_info, stakes);
+    make_cluster_nodes(self_pubkey, nodes)
+}
+
+/// Cluster nodes made up of the staked nodes alone, none of which has
+/// contact-info, as seen by `self_pubkey`. Used to simulate turbine trees for a
+/// stake distribution.
+pub fn new_cluster_nodes_from_stakes<T: 'static>(
+    self_pubkey: Pubkey,
+    stakes: &HashMap<Pubkey, u64>,
+) -> ClusterNodes<T> {
+    let nodes = std::iter::once(Node {
+        node: NodeId::from(self_pubkey),
+        stake: stakes.get(&self_pubkey).copied().unwrap_or_default(),
+    })
+    .chain(
+        stakes
+            .iter()
+            .filter(|(pubkey, stake)| **stake > 0 && **pubkey != self_pubkey)
+            .map(|(&pubkey, &stake)| Node {
+                node: NodeId::from(pubkey),
+                stake,
+            }),
+    )
+    .sorted_by_key(|node| Reverse((node.stake, node.pubkey())))
+    .collect();
+    make_cluster_nodes(self_pubkey, nodes)
+}
+
+fn make_cluster_nodes<T: 'static>(self_pubkey: Pubkey, nodes: Vec<Node>) -> ClusterNodes<T> {
     let index: HashMap<_, _> = nodes
         .iter()
         .enumerate()
@@ -314,6 +363,16 @@ fn get_retransmit_peers<T: Copy>(
     index: usize, // Local node's index withing the nodes slice.
     nodes: &[T],
 ) -> impl Iterator<Item = T> + '_ {
+    get_retransmit_children(fanout, index, nodes.len()).map(|i| nodes[i])
+}
+
+/// Returns indices of the nodes which the node at `index` of the retransmit
+/// tree retransmits shreds to, out of `num_nodes` nodes in the tree.
+pub fn get_retransmit_children(
+    fanout: usize,
+    index: usize,
+    num_nodes: usize,
+) -> impl Iterator<Item = usize> {
     // Node's index within its neighborhood.
     let offset = index.saturating_sub(1) % fanout;
     // First node in the neighborhood.
@@ -322,9 +381,7 @@ fn get_retransmit_peers<T: Copy>(
     (anchor * fanout + offset + 1..)
         .step_by(step)
         .take(fanout)
-        .map(|i| nodes.get(i))
-        .while_some()
-        .copied()
+        .take_while(move |&i| i < num_nodes)
 }
 
 impl<T> ClusterNodesCache<T> {
@@ -527,7 +584,10 @@ fn check_feature_activation(feature: &Pubkey, shred_slot: Slot, root_bank: &Bank
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        solana_ledger::shred::{Shred, ShredFlags},
+    };
 
     #[test]
     fn test_cluster_nodes_retransmit() {
@@ -681,4 +741,61 @@ mod tests {
             assert_eq!(retransmit_peers.next(), None);
         }
     }
+
+    #[test]
+    fn test_get_retransmit_tree() {
+        let mut rng = rand::thread_rng();
+        let stakes: HashMap<Pubkey, u64> =
+            repeat_with(|| (Pubkey::new_unique(), rng.gen_range(1..20)))
+                .take(500)
+                .collect();
+        let pubkeys: Vec<Pubkey> = stakes.keys().copied().collect();
+        let slot_leader = pubkeys[0];
+        let shred = Shred::new_from_data(
+            rng.gen_range(1..100_000), // slot
+            rng.gen_range(0..1_000),   // index
+            1,                         // parent_offset
+            &[],                       // data
+            ShredFlags::empty(),
+            0, // reference_tick
+            0, // version
+            0, // fec_set_index
+        )
+        .id();
+        let fanout = 4;
+        let tree = new_cluster_nodes_from_stakes::<RetransmitStage>(slot_leader, &stakes)
+            .get_retransmit_tree(&slot_leader, &shred);
+        assert_eq!(tree.len(), stakes.len() - 1);
+        let index: HashMap<Pubkey, usize> = tree
+            .iter()
+            .enumerate()
+            .map(|(k, (pubkey, _))| (*pubkey, k))
+            .collect();
+        // The tree agrees with the peers each node retransmits to.
+        for pubkey in &pubkeys[1..] {
+            let cluster_nodes = new_cluster_nodes_from_stakes::<RetransmitStage>(*pubkey, &stakes);
+            assert_eq!(
+                cluster_nodes.get_retransmit_tree(&slot_leader, &shred),
+                tree
+            );
+            let RetransmitPeers {
+                root_distance,
+                children,
+                ..
+            } = cluster_nodes
+                .get_retransmit_peers(&slot_leader, &shred, fanout)
+                .unwrap();
+            let k = index[pubkey];
+            assert_eq!(root_distance == 0, k == 0);
+            assert_eq!(
+                children
+                    .iter()
+                    .map(|node| node.pubkey())
+                    .collect::<Vec<_>>(),
+                get_retransmit_children(fanout, k, tree.len())
+                    .map(|k| tree[k].0)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
 }
@@ -0,0 +1,266 @@
+//! Simulates shred propagation over turbine retransmit trees for a stake
+//! distribution, so that fanout changes can be evaluated before deployment.
+//!
+//! Slot leaders are sampled by stake. Each hop of a shred, including the one
+//! from the slot leader to the root node, takes a latency drawn uniformly from
+//! a range and is lost with a fixed probability, in which case the shred does
+//! not reach the receiving node's subtree. Repair and erasure recovery are not
+//! modeled, nor are nodes outside the stake distribution.
+
+use {
+    crate::{
+        cluster_nodes::{get_retransmit_children, new_cluster_nodes_from_stakes},
+        retransmit_stage::RetransmitStage,
+    },
+    rand::{distributions::WeightedIndex, prelude::Distribution, Rng},
+    solana_ledger::shred::{Shred, ShredFlags},
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{collections::HashMap, ops::RangeInclusive},
+};
+
+/// Fractions of stake for which the time a shred took to reach them is
+/// reported.
+pub const STAKE_THRESHOLDS: [f64; 3] = [0.5, 2.0 / 3.0, 0.9];
+
+pub struct SimulationConfig {
+    pub fanout: usize,
+    pub num_slots: usize,
+    pub shreds_per_slot: u32,
+    pub hop_latency_ms: RangeInclusive<u64>,
+    pub packet_loss_rate: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShredPropagation {
+    /// Fraction of the nodes other than the slot leader which the shred
+    /// reached, and the fraction of their stake.
+    pub nodes_reached: f64,
+    pub stake_reached: f64,
+    /// Maximum number of hops from the root node to a node the shred reached.
+    pub num_hops: usize,
+    /// Milliseconds from broadcast until the shred reached each of
+    /// STAKE_THRESHOLDS of stake, if it did.
+    pub stake_arrival_ms: [Option<u64>; STAKE_THRESHOLDS.len()],
+}
+
+#[derive(Debug, Default)]
+pub struct SimulationReport {
+    pub shreds: Vec<ShredPropagation>,
+}
+
+impl SimulationReport {
+    pub fn mean_stake_reached(&self) -> f64 {
+        mean(self.shreds.iter().map(|shred| shred.stake_reached))
+    }
+
+    pub fn min_stake_reached(&self) -> f64 {
+        self.shreds
+            .iter()
+            .map(|shred| shred.stake_reached)
+            .min_by(f64::total_cmp)
+            .unwrap_or_default()
+    }
+
+    pub fn mean_nodes_reached(&self) -> f64 {
+        mean(self.shreds.iter().map(|shred| shred.nodes_reached))
+    }
+
+    pub fn max_num_hops(&self) -> usize {
+        self.shreds
+            .iter()
+            .map(|shred| shred.num_hops)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Fraction of shreds which reached the k-th of STAKE_THRESHOLDS.
+    pub fn fraction_reaching(&self, k: usize) -> f64 {
+        let count = self
+            .shreds
+            .iter()
+            .filter(|shred| shred.stake_arrival_ms[k].is_some())
+            .count();
+        ratio(count as u64, self.shreds.len() as u64)
+    }
+
+    /// Percentile of the time shreds took to reach the k-th of
+    /// STAKE_THRESHOLDS, among the shreds which did.
+    pub fn arrival_percentile_ms(&self, k: usize, percentile: usize) -> Option<u64> {
+        let mut arrivals: Vec<u64> = self
+            .shreds
+            .iter()
+            .filter_map(|shred| shred.stake_arrival_ms[k])
+            .collect();
+        if arrivals.is_empty() {
+            return None;
+        }
+        arrivals.sort_unstable();
+        let index = (arrivals.len() - 1) * percentile.min(100) / 100;
+        Some(arrivals[index])
+    }
+}
+
+pub fn simulate<R: Rng>(
+    rng: &mut R,
+    stakes: &HashMap<Pubkey, u64>,
+    config: &SimulationConfig,
+) -> SimulationReport {
+    let mut staked_nodes: Vec<(Pubkey, u64)> = stakes
+        .iter()
+        .filter(|(_, stake)| **stake > 0)
+        .map(|(pubkey, stake)| (*pubkey, *stake))
+        .collect();
+    // Sorted, so that slot leaders only depend on the rng.
+    staked_nodes.sort_unstable();
+    let Ok(leaders) = WeightedIndex::new(staked_nodes.iter().map(|(_, stake)| stake)) else {
+        return SimulationReport::default();
+    };
+    // Retransmit trees are the same from the point of view of any node.
+    let cluster_nodes = new_cluster_nodes_from_stakes::<RetransmitStage>(staked_nodes[0].0, stakes);
+    let mut shreds = Vec::with_capacity(config.num_slots * config.shreds_per_slot as usize);
+    for slot in 1..=config.num_slots as Slot {
+        let (slot_leader, _) = staked_nodes[leaders.sample(rng)];
+        for index in 0..config.shreds_per_slot {
+            let shred = Shred::new_from_data(
+                slot,
+                index,
+                1,   // parent_offset
+                &[], // data
+                ShredFlags::empty(),
+                0, // reference_tick
+                0, // version
+                0, // fec_set_index
+            );
+            let tree = cluster_nodes.get_retransmit_tree(&slot_leader, &shred.id());
+            shreds.push(propagate(rng, &tree, config));
+        }
+    }
+    SimulationReport { shreds }
+}
+
+// Sends a shred down the retransmit tree, starting with the slot leader
+// broadcasting it to the root node.
+fn propagate<R: Rng>(
+    rng: &mut R,
+    tree: &[(Pubkey, /*stake:*/ u64)],
+    config: &SimulationConfig,
+) -> ShredPropagation {
+    let hop = |rng: &mut R| -> Option<u64> {
+        (!rng.gen_bool(config.packet_loss_rate))
+            .then(|| rng.gen_range(config.hop_latency_ms.clone()))
+    };
+    // Arrival time and number of hops from the root node.
+    let mut arrivals: Vec<Option<(u64, usize)>> = vec![None; tree.len()];
+    if let Some(arrival) = arrivals.first_mut() {
+        *arrival = hop(rng).map(|latency| (latency, 0));
+    }
+    for index in 0..tree.len() {
+        let Some((arrival, num_hops)) = arrivals[index] else {
+            continue;
+        };
+        for child in get_retransmit_children(config.fanout, index, tree.len()) {
+            arrivals[child] = hop(rng).map(|latency| (arrival + latency, num_hops + 1));
+        }
+    }
+    let total_stake: u64 = tree.iter().map(|(_, stake)| stake).sum();
+    let mut reached: Vec<(
+        /*arrival:*/ u64,
+        /*stake:*/ u64,
+        /*num_hops:*/ usize,
+    )> = tree
+        .iter()
+        .zip(&arrivals)
+        .filter_map(|((_, stake), arrival)| {
+            let (arrival, num_hops) = (*arrival)?;
+            Some((arrival, *stake, num_hops))
+        })
+        .collect();
+    reached.sort_unstable();
+    let mut stake_arrival_ms = [None; STAKE_THRESHOLDS.len()];
+    let mut stake_reached = 0;
+    for (arrival, stake, _) in &reached {
+        stake_reached += stake;
+        for (threshold, stake_arrival) in STAKE_THRESHOLDS.iter().zip(&mut stake_arrival_ms) {
+            if stake_arrival.is_none() && stake_reached as f64 >= threshold * total_stake as f64 {
+                *stake_arrival = Some(*arrival);
+            }
+        }
+    }
+    ShredPropagation {
+        nodes_reached: ratio(reached.len() as u64, tree.len() as u64),
+        stake_reached: ratio(stake_reached, total_stake),
+        num_hops: reached
+            .iter()
+            .map(|(_, _, num_hops)| *num_hops)
+            .max()
+            .unwrap_or_default(),
+        stake_arrival_ms,
+    }
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn mean(values: impl ExactSizeIterator<Item = f64>) -> f64 {
+    let count = values.len();
+    if count == 0 {
+        0.0
+    } else {
+        values.sum::<f64>() / count as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::SeedableRng, rand_chacha::ChaChaRng, std::iter::repeat_with};
+
+    fn make_config(fanout: usize, packet_loss_rate: f64) -> SimulationConfig {
+        SimulationConfig {
+            fanout,
+            num_slots: 4,
+            shreds_per_slot: 8,
+            hop_latency_ms: 10..=10,
+            packet_loss_rate,
+        }
+    }
+
+    #[test]
+    fn test_simulate() {
+        let mut rng = ChaChaRng::from_seed([7u8; 32]);
+        let stakes: HashMap<Pubkey, u64> = repeat_with(|| (Pubkey::new_unique(), 100))
+            .take(50)
+            .collect();
+
+        // With no losses, all shreds reach all nodes. A fanout as large as the
+        // cluster only needs the root node and the first layer.
+        let report = simulate(&mut rng, &stakes, &make_config(200, 0.0));
+        assert_eq!(report.shreds.len(), 32);
+        assert_eq!(report.mean_stake_reached(), 1.0);
+        assert_eq!(report.min_stake_reached(), 1.0);
+        assert_eq!(report.mean_nodes_reached(), 1.0);
+        assert_eq!(report.max_num_hops(), 1);
+        for k in 0..STAKE_THRESHOLDS.len() {
+            assert_eq!(report.fraction_reaching(k), 1.0);
+            assert_eq!(report.arrival_percentile_ms(k, 50), Some(20));
+        }
+
+        // A smaller fanout takes more hops, and so longer.
+        let report = simulate(&mut rng, &stakes, &make_config(2, 0.0));
+        assert_eq!(report.mean_stake_reached(), 1.0);
+        assert_eq!(report.max_num_hops(), 5);
+        assert_eq!(report.arrival_percentile_ms(2, 100), Some(60));
+
+        // Nothing arrives if all packets are lost.
+        let report = simulate(&mut rng, &stakes, &make_config(200, 1.0));
+        assert_eq!(report.mean_stake_reached(), 0.0);
+        for k in 0..STAKE_THRESHOLDS.len() {
+            assert_eq!(report.fraction_reaching(k), 0.0);
+            assert_eq!(report.arrival_percentile_ms(k, 50), None);
+        }
+    }
+}
@@ -9,6 +9,7 @@ use {
     jsonrpc_server_utils::tokio,
     log::*,
     serde::{de::Deserializer, Deserialize, Serialize},
+    solana_account_decoder::UiAccount,
     solana_accounts_db::accounts_index::AccountIndex,
     solana_core::{
         admin_rpc_post_init::AdminRpcRequestMetadataPostInit,
@@ -19,7 +20,10 @@ use {
     solana_gossip::contact_info::{ContactInfo, Protocol, SOCKET_ADDR_UNSPECIFIED},
     solana_rpc::rpc::verify_pubkey,
     solana_rpc_client_api::{config::RpcAccountIndex, custom_error::RpcCustomError},
+    solana_runtime::{bank::Bank, bank_overrides::BankOverrides},
     solana_sdk::{
+        account::AccountSharedData,
+        clock::{Epoch, Slot, UnixTimestamp},
         exit::Exit,
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
@@ -47,6 +51,7 @@ pub struct AdminRpcRequestMetadata {
     pub staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
     pub post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
     pub rpc_to_plugin_manager_sender: Option<Sender<GeyserPluginManagerRequest>>,
+    pub bank_overrides: Option<Arc<BankOverrides>>,
 }
 
 impl Metadata for AdminRpcRequestMetadata {}
@@ -64,6 +69,21 @@ impl AdminRpcRequestMetadata {
             ))
         }
     }
+
+    fn with_bank_overrides<F, R>(&self, func: F) -> Result<R>
+    where
+        F: FnOnce(&BankOverrides) -> Result<R>,
+    {
+        if let Some(bank_overrides) = self.bank_overrides.as_ref() {
+            func(bank_overrides)
+        } else {
+            Err(jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: "Only supported by solana-test-validator".to_string(),
+                data: None,
+            })
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -233,6 +253,30 @@ pub trait AdminRpc {
         meta: Self::Metadata,
         public_tpu_forwards_addr: SocketAddr,
     ) -> Result<()>;
+
+    #[rpc(meta, name = "setAccount")]
+    fn set_account(
+        &self,
+        meta: Self::Metadata,
+        pubkey_str: String,
+        account: UiAccount,
+    ) -> Result<()>;
+
+    #[rpc(meta, name = "setClockUnixTimestamp")]
+    fn set_clock_unix_timestamp(
+        &self,
+        meta: Self::Metadata,
+        unix_timestamp: Option<UnixTimestamp>,
+    ) -> Result<()>;
+
+    #[rpc(meta, name = "warpSlot")]
+    fn warp_slot(&self, meta: Self::Metadata, slot: Slot) -> Result<Slot>;
+
+    #[rpc(meta, name = "warpEpoch")]
+    fn warp_epoch(&self, meta: Self::Metadata, epoch: Epoch) -> Result<Slot>;
+
+    #[rpc(meta, name = "triggerEpochRewards")]
+    fn trigger_epoch_rewards(&self, meta: Self::Metadata) -> Result<Slot>;
 }
 
 pub struct AdminRpcImpl;
@@ -643,6 +687,69 @@ impl AdminRpc for AdminRpcImpl {
             Ok(())
         })
     }
+
+    fn set_account(
+        &self,
+        meta: Self::Metadata,
+        pubkey_str: String,
+        account: UiAccount,
+    ) -> Result<()> {
+        debug!("set_account rpc request received: {pubkey_str}");
+
+        let pubkey = verify_pubkey(&pubkey_str)?;
+        let account = account.decode::<AccountSharedData>().ok_or_else(|| {
+            jsonrpc_core::error::Error::invalid_params("Unable to decode account")
+        })?;
+        meta.with_bank_overrides(|bank_overrides| {
+            bank_overrides.set_account(pubkey, account);
+            warn!("Account {pubkey} will be replaced in the next bank");
+            Ok(())
+        })
+    }
+
+    fn set_clock_unix_timestamp(
+        &self,
+        meta: Self::Metadata,
+        unix_timestamp: Option<UnixTimestamp>,
+    ) -> Result<()> {
+        debug!("set_clock_unix_timestamp rpc request received: {unix_timestamp:?}");
+
+        meta.with_bank_overrides(|bank_overrides| {
+            if let Some(unix_timestamp) = unix_timestamp {
+                bank_overrides.set_unix_timestamp(unix_timestamp);
+                warn!("Clock unix timestamp set to {unix_timestamp}");
+            } else {
+                bank_overrides.clear_unix_timestamp();
+                warn!("Clock unix timestamp override cleared");
+            }
+            Ok(())
+        })
+    }
+
+    fn warp_slot(&self, meta: Self::Metadata, slot: Slot) -> Result<Slot> {
+        debug!("warp_slot rpc request received: {slot}");
+
+        AdminRpcImpl::warp_to_slot(meta, |_working_bank| slot)
+    }
+
+    fn warp_epoch(&self, meta: Self::Metadata, epoch: Epoch) -> Result<Slot> {
+        debug!("warp_epoch rpc request received: {epoch}");
+
+        AdminRpcImpl::warp_to_slot(meta, |working_bank| {
+            working_bank.epoch_schedule().get_first_slot_in_epoch(epoch)
+        })
+    }
+
+    fn trigger_epoch_rewards(&self, meta: Self::Metadata) -> Result<Slot> {
+        debug!("trigger_epoch_rewards rpc request received");
+
+        // Rewards are calculated at the epoch boundary
+        AdminRpcImpl::warp_to_slot(meta, |working_bank| {
+            working_bank
+                .epoch_schedule()
+                .get_first_slot_in_epoch(working_bank.epoch() + 1)
+        })
+    }
 }
 
 impl AdminRpcImpl {
@@ -690,6 +797,28 @@ impl AdminRpcImpl {
             Ok(())
         })
     }
+
+    fn warp_to_slot<F>(meta: AdminRpcRequestMetadata, get_slot: F) -> Result<Slot>
+    where
+        F: FnOnce(&Bank) -> Slot,
+    {
+        meta.with_bank_overrides(|bank_overrides| {
+            let working_bank = meta.with_post_init(|post_init| {
+                Ok(post_init.bank_forks.read().unwrap().working_bank())
+            })?;
+            let slot = get_slot(&working_bank);
+            // The slot before `slot` is warped to, from a bank before it
+            if slot <= working_bank.slot().saturating_add(1) {
+                return Err(jsonrpc_core::error::Error::invalid_params(format!(
+                    "Slot {slot} must be after slot {}",
+                    working_bank.slot().saturating_add(1)
+                )));
+            }
+            bank_overrides.warp_to_slot(slot);
+            warn!("Warping to slot {slot}");
+            Ok(slot)
+        })
+    }
 }
 
 fn rpc_account_index_from_account_index(account_index: &AccountIndex) -> RpcAccountIndex {
@@ -818,8 +947,12 @@ pub fn load_staked_nodes_overrides(
 mod tests {
     use {
         super::*,
-        serde_json::Value,
-        solana_accounts_db::{accounts_index::AccountSecondaryIndexes, inline_spl_token},
+        serde_json::{json, Value},
+        solana_account_decoder::UiAccountEncoding,
+        solana_accounts_db::{
+            accounts_db::AccountShrinkThreshold, accounts_index::AccountSecondaryIndexes,
+            inline_spl_token,
+        },
         solana_core::consensus::tower_storage::NullTowerStorage,
         solana_gossip::cluster_info::ClusterInfo,
         solana_ledger::genesis_utils::{create_genesis_config, GenesisConfigInfo},
@@ -827,6 +960,7 @@ mod tests {
         solana_runtime::{
             bank::{Bank, BankTestConfig},
             bank_forks::BankForks,
+            runtime_config::RuntimeConfig,
         },
         solana_sdk::{
             account::{Account, AccountSharedData},
@@ -844,6 +978,7 @@ mod tests {
     #[derive(Default)]
     struct TestConfig {
         account_indexes: AccountSecondaryIndexes,
+        bank_overrides: Option<Arc<BankOverrides>>,
     }
 
     struct RpcHandler {
@@ -870,9 +1005,15 @@ mod tests {
             ));
             let exit = Arc::new(AtomicBool::new(false));
             let validator_exit = create_validator_exit(exit);
-            let (bank_forks, vote_keypair) = new_bank_forks_with_config(BankTestConfig {
-                secondary_indexes: config.account_indexes,
-            });
+            let (bank_forks, vote_keypair) = new_bank_forks_with_config(
+                BankTestConfig {
+                    secondary_indexes: config.account_indexes,
+                },
+                Arc::new(RuntimeConfig {
+                    bank_overrides: config.bank_overrides.clone(),
+                    ..RuntimeConfig::default()
+                }),
+            );
             let vote_account = vote_keypair.pubkey();
             let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
             let repair_whitelist = Arc::new(RwLock::new(HashSet::new()));
@@ -891,6 +1032,7 @@ mod tests {
                 }))),
                 staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
                 rpc_to_plugin_manager_sender: None,
+                bank_overrides: config.bank_overrides,
             };
             let mut io = MetaIoHandler::default();
             io.extend_with(AdminRpcImpl.to_delegate());
@@ -905,10 +1047,31 @@ mod tests {
         fn root_bank(&self) -> Arc<Bank> {
             self.bank_forks.read().unwrap().root_bank()
         }
+
+        fn new_working_bank(&self) -> Arc<Bank> {
+            let parent = self.bank_forks.read().unwrap().working_bank();
+            let slot = parent.slot() + 1;
+            let bank = Bank::new_from_parent(parent, &Pubkey::default(), slot);
+            self.bank_forks.write().unwrap().insert(bank)
+        }
+
+        fn handle_request(&self, method: &str, params: Value) -> Value {
+            let req = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            })
+            .to_string();
+            let res = self.io.handle_request_sync(&req, self.meta.clone());
+            serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization")
+        }
     }
 
     fn new_bank_forks_with_config(
         config: BankTestConfig,
+        runtime_config: Arc<RuntimeConfig>,
     ) -> (Arc<RwLock<BankForks>>, Arc<Keypair>) {
         let GenesisConfigInfo {
             genesis_config,
@@ -916,7 +1079,13 @@ mod tests {
             ..
         } = create_genesis_config(1_000_000_000);
 
-        let bank = Bank::new_for_tests_with_config(&genesis_config, config);
+        let bank = Bank::new_with_paths_for_tests(
+            &genesis_config,
+            runtime_config,
+            Vec::new(),
+            config.secondary_indexes,
+            AccountShrinkThreshold::default(),
+        );
         (
             Arc::new(RwLock::new(BankForks::new(bank))),
             Arc::new(voting_keypair),
@@ -940,7 +1109,10 @@ mod tests {
             };
 
             // RPC & Bank Setup
-            let rpc = RpcHandler::start_with_config(TestConfig { account_indexes });
+            let rpc = RpcHandler::start_with_config(TestConfig {
+                account_indexes,
+                ..TestConfig::default()
+            });
 
             let bank = rpc.root_bank();
             let RpcHandler { io, meta, .. } = rpc;
@@ -1228,4 +1400,118 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_set_account() {
+        let pubkey = Pubkey::new_unique();
+        let account = AccountSharedData::from(Account {
+            lamports: 42,
+            data: vec![1, 2, 3],
+            owner: system_program::id(),
+            ..Account::default()
+        });
+        let ui_account =
+            UiAccount::encode(&pubkey, &account, UiAccountEncoding::Base64, None, None);
+
+        // Only the test validator's banks can be modified
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let result = rpc.handle_request("setAccount", json!([pubkey.to_string(), ui_account]));
+        assert_eq!(
+            result["error"]["code"],
+            json!(ErrorCode::InvalidRequest.code())
+        );
+
+        let rpc = RpcHandler::start_with_config(TestConfig {
+            bank_overrides: Some(Arc::<BankOverrides>::default()),
+            ..TestConfig::default()
+        });
+        let result = rpc.handle_request("setAccount", json!(["invalid", ui_account]));
+        assert_eq!(
+            result["error"]["code"],
+            json!(ErrorCode::InvalidParams.code())
+        );
+
+        let capitalization = rpc.root_bank().capitalization();
+        let result = rpc.handle_request("setAccount", json!([pubkey.to_string(), ui_account]));
+        assert_eq!(result["result"], Value::Null);
+        assert!(result.get("error").is_none());
+        let bank = rpc.new_working_bank();
+        assert_eq!(bank.get_account(&pubkey), Some(account));
+        assert_eq!(bank.capitalization(), capitalization + 42);
+
+        // The account is only replaced once, later banks inherit it
+        let account = AccountSharedData::new(43, 0, &system_program::id());
+        bank.store_account(&pubkey, &account);
+        let bank = rpc.new_working_bank();
+        assert_eq!(bank.get_account(&pubkey), Some(account));
+    }
+
+    #[test]
+    fn test_set_clock_unix_timestamp() {
+        let rpc = RpcHandler::start_with_config(TestConfig {
+            bank_overrides: Some(Arc::<BankOverrides>::default()),
+            ..TestConfig::default()
+        });
+        let estimated_unix_timestamp = rpc.new_working_bank().clock().unix_timestamp;
+
+        let unix_timestamp = estimated_unix_timestamp + 1_000_000;
+        let result = rpc.handle_request("setClockUnixTimestamp", json!([unix_timestamp]));
+        assert_eq!(result["result"], Value::Null);
+        // Later banks follow the wall clock from the requested timestamp
+        for _ in 0..2 {
+            let bank_unix_timestamp = rpc.new_working_bank().clock().unix_timestamp;
+            assert!(bank_unix_timestamp >= unix_timestamp);
+            assert!(bank_unix_timestamp < unix_timestamp + 60);
+        }
+
+        // Clearing the override moves the clock back to the estimate
+        let result = rpc.handle_request("setClockUnixTimestamp", json!([null]));
+        assert_eq!(result["result"], Value::Null);
+        let bank_unix_timestamp = rpc.new_working_bank().clock().unix_timestamp;
+        assert!(bank_unix_timestamp < unix_timestamp);
+        assert!(bank_unix_timestamp >= estimated_unix_timestamp);
+    }
+
+    #[test]
+    fn test_warp() {
+        // Only the test validator can warp
+        let rpc = RpcHandler::start_with_config(TestConfig::default());
+        let result = rpc.handle_request("warpSlot", json!([100]));
+        assert_eq!(
+            result["error"]["code"],
+            json!(ErrorCode::InvalidRequest.code())
+        );
+
+        let bank_overrides = Arc::<BankOverrides>::default();
+        let rpc = RpcHandler::start_with_config(TestConfig {
+            bank_overrides: Some(bank_overrides.clone()),
+            ..TestConfig::default()
+        });
+        let working_slot = rpc.new_working_bank().slot();
+
+        // The slot before the target must be skipped
+        for slot in [working_slot, working_slot + 1] {
+            let result = rpc.handle_request("warpSlot", json!([slot]));
+            assert_eq!(
+                result["error"]["code"],
+                json!(ErrorCode::InvalidParams.code())
+            );
+            assert_eq!(bank_overrides.take_warp_slot(), None);
+        }
+        let result = rpc.handle_request("warpSlot", json!([working_slot + 2]));
+        assert_eq!(result["result"], json!(working_slot + 2));
+        assert_eq!(bank_overrides.take_warp_slot(), Some(working_slot + 2));
+
+        let epoch_schedule = rpc.root_bank().epoch_schedule().clone();
+        let result = rpc.handle_request("warpEpoch", json!([3]));
+        let slot = epoch_schedule.get_first_slot_in_epoch(3);
+        assert_eq!(result["result"], json!(slot));
+        assert_eq!(bank_overrides.take_warp_slot(), Some(slot));
+
+        // Rewards are paid from the first slot of the next epoch
+        let result = rpc.handle_request("triggerEpochRewards", json!([]));
+        let slot = epoch_schedule.get_first_slot_in_epoch(1);
+        assert_eq!(result["result"], json!(slot));
+        assert_eq!(bank_overrides.take_warp_slot(), Some(slot));
+    }
 }
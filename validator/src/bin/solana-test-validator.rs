@@ -248,6 +248,33 @@ fn main() {
         }
     }
 
+    let mut loader_v4_programs_to_load = vec![];
+    if let Some(values) = matches.values_of("loader_v4_program") {
+        for (address, program, authority) in values.into_iter().tuples::<(&str, &str, &str)>() {
+            let address = parse_address(address, "address");
+            let program_path = parse_program_path(program);
+            let authority = if authority == "none" {
+                None
+            } else {
+                Some(
+                    authority
+                        .parse::<Pubkey>()
+                        .or_else(|_| read_keypair_file(authority).map(|keypair| keypair.pubkey()))
+                        .unwrap_or_else(|err| {
+                            println!("Error: invalid authority {authority}: {err}");
+                            exit(1);
+                        }),
+                )
+            };
+
+            loader_v4_programs_to_load.push(LoaderV4ProgramInfo {
+                program_id: address,
+                authority,
+                program_path,
+            });
+        }
+    }
+
     let mut accounts_to_load = vec![];
     if let Some(values) = matches.values_of("account") {
         for (address, filename) in values.into_iter().tuples() {
@@ -405,6 +432,7 @@ fn main() {
             post_init: admin_service_post_init,
             tower_storage: tower_storage.clone(),
             rpc_to_plugin_manager_sender,
+            bank_overrides: Some(genesis.bank_overrides()),
         },
     );
     let dashboard = if output == Output::Dashboard {
@@ -449,6 +477,7 @@ fn main() {
         })
         .rpc_port(rpc_port)
         .add_upgradeable_programs_with_path(&upgradeable_programs_to_load)
+        .add_loader_v4_programs_with_path(&loader_v4_programs_to_load)
         .add_accounts_from_json_files(&accounts_to_load)
         .unwrap_or_else(|e| {
             println!("Error: add_accounts_from_json_files failed: {e}");
@@ -508,6 +537,20 @@ fn main() {
         }
     }
 
+    if matches.is_present("lazy_clone") {
+        let rpc_client = RpcClient::new(
+            cluster_rpc_client
+                .as_ref()
+                .expect("bug: --url argument missing?")
+                .url(),
+        );
+        let cache_path = value_t!(matches, "lazy_clone_cache", PathBuf).ok();
+        if let Err(e) = genesis.lazy_clone_accounts(rpc_client, cache_path) {
+            println!("Error: lazy_clone_accounts failed: {e}");
+            exit(1);
+        }
+    }
+
     if let Some(warp_slot) = warp_slot {
         genesis.warp_slot(warp_slot);
     }
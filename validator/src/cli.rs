@@ -24,7 +24,10 @@ use {
         validator::{BlockProductionMethod, BlockVerificationMethod},
     },
     solana_faucet::faucet::{self, FAUCET_PORT},
-    solana_ledger::use_snapshot_archives_at_startup,
+    solana_ledger::{
+        blockstore_options::{DEFAULT_ZSTD_COMPRESSION_LEVEL, MAX_ZSTD_DICTIONARY_SIZE},
+        use_snapshot_archives_at_startup,
+    },
     solana_net_utils::{MINIMUM_VALIDATOR_PORT_RANGE_WIDTH, VALIDATOR_PORT_RANGE},
     solana_rpc::{rpc::MAX_REQUEST_BODY_SIZE, rpc_pubsub_service::PubSubConfig},
     solana_rpc_client_api::request::MAX_MULTIPLE_ACCOUNTS,
@@ -527,6 +530,18 @@ pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
                 .default_value(&default_args.contact_debug_interval)
                 .help("Milliseconds between printing contact debug from gossip."),
         )
+        .arg(
+            Arg::with_name("gossip_egress_budget")
+                .long("gossip-egress-budget")
+                .value_name("BYTES_PER_SECOND")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Cap the bytes per second sent in gossip push messages, pull \
+                       requests and pull responses. Push fanout adapts to the budget, and \
+                       values that still do not fit are pushed in a later round. Pull \
+                       requests that do not fit are dropped, lowest staked peers first \
+                       [default: unlimited]"),
+        )
         .arg(
             Arg::with_name("no_poh_speed_test")
                 .long("no-poh-speed-test")
@@ -616,12 +631,43 @@ pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
                 .long("rocksdb-ledger-compression")
                 .value_name("COMPRESSION_TYPE")
                 .takes_value(true)
-                .possible_values(&["none", "lz4", "snappy", "zlib"])
+                .possible_values(&["none", "lz4", "snappy", "zlib", "zstd"])
                 .default_value(&default_args.rocksdb_ledger_compression)
                 .help("The compression algorithm that is used to compress \
                        transaction status data.  \
                        Turning on compression can save ~10% of the ledger size."),
         )
+        .arg(
+            Arg::with_name("rocksdb_ledger_zstd_level")
+                .hidden(hidden_unless_forced())
+                .long("rocksdb-ledger-zstd-level")
+                .value_name("LEVEL")
+                .takes_value(true)
+                .validator(|value| match value.parse::<i32>() {
+                    Ok(level) if (1..=22).contains(&level) => Ok(()),
+                    _ => Err(format!("{value} is not a zstd level between 1 and 22")),
+                })
+                .default_value(&default_args.rocksdb_ledger_zstd_level)
+                .help("The zstd compression level, from 1 (fastest) to 22 (smallest). \
+                       Only used with --rocksdb-ledger-compression zstd"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_ledger_zstd_dictionary_size")
+                .hidden(hidden_unless_forced())
+                .long("rocksdb-ledger-zstd-dictionary-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(|value| match value.parse::<u32>() {
+                    Ok(size) if size <= MAX_ZSTD_DICTIONARY_SIZE => Ok(()),
+                    _ => Err(format!(
+                        "{value} is not a dictionary size between 0 and {MAX_ZSTD_DICTIONARY_SIZE}"
+                    )),
+                })
+                .default_value(&default_args.rocksdb_ledger_zstd_dictionary_size)
+                .help("The size of the zstd dictionary trained for each new file of \
+                       transaction status data, 0 to disable dictionary compression. \
+                       Only used with --rocksdb-ledger-compression zstd"),
+        )
         .arg(
             Arg::with_name("rocksdb_perf_sample_interval")
                 .hidden(hidden_unless_forced())
@@ -761,6 +807,15 @@ pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
                        from validators in the list will be prioritized over requests from other validators. \
                        [default: all validators]")
         )
+        .arg(
+            Arg::with_name("repair_archive_dir")
+                .long("repair-archive-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Directory of archives written by `solana-ledger-tool export-archive`. \
+                       Rooted slots which stay missing after regular repair are read from \
+                       these archives, if they hold them"),
+        )
         .arg(
             Arg::with_name("gossip_validators")
                 .long("gossip-validator")
@@ -1962,6 +2017,8 @@ pub struct DefaultArgs {
 
     pub rocksdb_shred_compaction: String,
     pub rocksdb_ledger_compression: String,
+    pub rocksdb_ledger_zstd_level: String,
+    pub rocksdb_ledger_zstd_dictionary_size: String,
     pub rocksdb_perf_sample_interval: String,
 
     pub accounts_shrink_optimize_total_space: String,
@@ -2046,6 +2103,8 @@ impl DefaultArgs {
             snapshot_version: SnapshotVersion::default(),
             rocksdb_shred_compaction: "level".to_string(),
             rocksdb_ledger_compression: "none".to_string(),
+            rocksdb_ledger_zstd_level: DEFAULT_ZSTD_COMPRESSION_LEVEL.to_string(),
+            rocksdb_ledger_zstd_dictionary_size: "0".to_string(),
             rocksdb_perf_sample_interval: "0".to_string(),
             accounts_shrink_optimize_total_space: DEFAULT_ACCOUNTS_SHRINK_OPTIMIZE_TOTAL_SPACE
                 .to_string(),
@@ -2268,6 +2327,20 @@ pub fn test_app<'a>(version: &'a str, default_args: &'a DefaultTestArgs) -> App<
                        Upgrade authority set to \"none\" disables upgrades",
                 ),
         )
+        .arg(
+            Arg::with_name("loader_v4_program")
+                .long("loader-v4-program")
+                .value_names(&["ADDRESS_OR_KEYPAIR", "SBF_PROGRAM.SO", "AUTHORITY"])
+                .takes_value(true)
+                .number_of_values(3)
+                .multiple(true)
+                .help(
+                    "Add a SBF program owned by loader-v4 to the genesis configuration. \
+                       If the ledger already exists then this parameter is silently ignored. \
+                       First and third arguments can be a pubkey string or path to a keypair. \
+                       Authority set to \"none\" deploys the program finalized",
+                ),
+        )
         .arg(
             Arg::with_name("account")
                 .long("account")
@@ -2429,6 +2502,28 @@ pub fn test_app<'a>(version: &'a str, default_args: &'a DefaultTestArgs) -> App<
                      If the ledger already exists then this parameter is silently ignored",
                 ),
         )
+        .arg(
+            Arg::with_name("lazy_clone")
+                .long("lazy-clone")
+                .takes_value(false)
+                .requires("json_rpc_url")
+                .help(
+                    "Copy accounts from the cluster referenced by the --url argument the \
+                     first time a transaction references them. Upgradeable programs are \
+                     copied along with their executable data",
+                ),
+        )
+        .arg(
+            Arg::with_name("lazy_clone_cache")
+                .long("lazy-clone-cache")
+                .value_name("FILE")
+                .takes_value(true)
+                .requires("lazy_clone")
+                .help(
+                    "Save accounts copied by --lazy-clone to FILE and load them from it on \
+                     later runs instead of fetching them again",
+                ),
+        )
         .arg(
             Arg::with_name("warp_slot")
                 .required(false)
@@ -33,8 +33,8 @@ use {
     solana_gossip::{cluster_info::Node, legacy_contact_info::LegacyContactInfo as ContactInfo},
     solana_ledger::{
         blockstore_options::{
-            BlockstoreCompressionType, BlockstoreRecoveryMode, LedgerColumnOptions,
-            ShredStorageType,
+            BlockstoreCompressionType, BlockstoreRecoveryMode, BlockstoreZstdOptions,
+            LedgerColumnOptions, ShredStorageType,
         },
         use_snapshot_archives_at_startup::{self, UseSnapshotArchivesAtStartup},
     },
@@ -1349,12 +1349,14 @@ pub fn main() {
         known_validators,
         repair_validators,
         repair_whitelist,
+        repair_archive_dir: value_t!(matches, "repair_archive_dir", PathBuf).ok(),
         gossip_validators,
         wal_recovery_mode,
         run_verification: !(matches.is_present("skip_poh_verify")
             || matches.is_present("skip_startup_ledger_verification")),
         debug_keys,
         contact_debug_interval,
+        gossip_egress_budget: value_t!(matches, "gossip_egress_budget", u64).ok(),
         send_transaction_service_config: send_transaction_service::Config {
             retry_rate_ms: rpc_send_retry_rate_ms,
             leader_forward_count: value_t_or_exit!(
@@ -1405,6 +1407,7 @@ pub fn main() {
             use_snapshot_archives_at_startup::cli::NAME,
             UseSnapshotArchivesAtStartup
         ),
+        wen_restart_proto_path: value_t!(matches, "wen_restart", PathBuf).ok(),
         ..ValidatorConfig::default()
     };
 
@@ -1641,6 +1644,14 @@ pub fn main() {
                 "snappy" => BlockstoreCompressionType::Snappy,
                 "lz4" => BlockstoreCompressionType::Lz4,
                 "zlib" => BlockstoreCompressionType::Zlib,
+                "zstd" => BlockstoreCompressionType::Zstd(BlockstoreZstdOptions {
+                    level: value_t_or_exit!(matches, "rocksdb_ledger_zstd_level", i32),
+                    max_dict_bytes: value_t_or_exit!(
+                        matches,
+                        "rocksdb_ledger_zstd_dictionary_size",
+                        u32
+                    ),
+                }),
                 _ => panic!("Unsupported ledger_compression: {ledger_compression_string}"),
             },
         },
@@ -1708,6 +1719,7 @@ pub fn main() {
             tower_storage: validator_config.tower_storage.clone(),
             staked_nodes_overrides,
             rpc_to_plugin_manager_sender,
+            bank_overrides: None,
         },
     );
 
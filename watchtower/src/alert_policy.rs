@@ -0,0 +1,114 @@
+//! Deduplication and cooldown of failure notifications
+
+use {
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeSet, HashMap},
+        time::{Duration, Instant},
+    },
+};
+
+struct Notification {
+    /// Validators the last notification of the check was about, `None` once the check recovered
+    validators: Option<BTreeSet<Pubkey>>,
+    sent: Instant,
+}
+
+/// Decides which failure notifications are sent. A check does not notify again while it keeps
+/// failing for the same validators, whatever the numbers in its message, and notifies at most
+/// once per cooldown period.
+pub struct AlertPolicy {
+    cooldown: Duration,
+    notifications: HashMap<String, Notification>,
+}
+
+impl AlertPolicy {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            notifications: HashMap::new(),
+        }
+    }
+
+    /// Returns whether a notification should be sent for the check failing for `validators`,
+    /// and records it if so. Checks of the cluster as a whole fail for no validator.
+    pub fn should_notify(&mut self, test_name: &str, validators: &[Pubkey], now: Instant) -> bool {
+        let validators: BTreeSet<Pubkey> = validators.iter().copied().collect();
+        if let Some(notification) = self.notifications.get(test_name) {
+            if notification.validators.as_ref() == Some(&validators)
+                || now.duration_since(notification.sent) < self.cooldown
+            {
+                return false;
+            }
+        }
+        self.notifications.insert(
+            test_name.to_string(),
+            Notification {
+                validators: Some(validators),
+                sent: now,
+            },
+        );
+        true
+    }
+
+    /// Forgets the validators of the checks which are no longer failing, so that they notify
+    /// again should they fail again. Their cooldown still applies.
+    pub fn resolve_except<'a>(&mut self, failing_test_names: impl IntoIterator<Item = &'a str>) {
+        let failing_test_names: Vec<_> = failing_test_names.into_iter().collect();
+        for (test_name, notification) in self.notifications.iter_mut() {
+            if !failing_test_names.contains(&test_name.as_str()) {
+                notification.validators = None;
+            }
+        }
+    }
+
+    /// Whether a notification was sent for a check which has not recovered yet
+    pub fn has_unresolved(&self) -> bool {
+        self.notifications
+            .values()
+            .any(|notification| notification.validators.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deduplication() {
+        let mut alert_policy = AlertPolicy::new(Duration::ZERO);
+        let now = Instant::now();
+        let validator_a = Pubkey::new_unique();
+        let validator_b = Pubkey::new_unique();
+        assert!(alert_policy.should_notify("balance", &[validator_a], now));
+        assert!(!alert_policy.should_notify("balance", &[validator_a], now));
+        assert!(alert_policy.should_notify("balance", &[validator_a, validator_b], now));
+        assert!(!alert_policy.should_notify("balance", &[validator_b, validator_a], now));
+        assert!(alert_policy.should_notify("skip-rate", &[validator_a], now));
+        assert!(alert_policy.should_notify("transaction-count", &[], now));
+        assert!(!alert_policy.should_notify("transaction-count", &[], now));
+        assert!(alert_policy.has_unresolved());
+
+        alert_policy.resolve_except(["skip-rate", "transaction-count"]);
+        assert!(alert_policy.should_notify("balance", &[validator_a, validator_b], now));
+        alert_policy.resolve_except([]);
+        assert!(!alert_policy.has_unresolved());
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let cooldown = Duration::from_secs(60);
+        let mut alert_policy = AlertPolicy::new(cooldown);
+        let now = Instant::now();
+        let validator_a = Pubkey::new_unique();
+        let validator_b = Pubkey::new_unique();
+        assert!(alert_policy.should_notify("balance", &[validator_a], now));
+        assert!(!alert_policy.should_notify("balance", &[validator_b], now + cooldown / 2));
+        assert!(alert_policy.should_notify("balance", &[validator_b], now + cooldown));
+        assert!(!alert_policy.should_notify("balance", &[validator_b], now + cooldown * 3));
+
+        alert_policy.resolve_except([]);
+        assert!(!alert_policy.should_notify("balance", &[validator_a], now + cooldown * 3 / 2));
+        assert!(alert_policy.should_notify("balance", &[validator_a], now + cooldown * 2));
+    }
+}
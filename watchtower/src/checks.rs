@@ -0,0 +1,288 @@
+//! Validator lifecycle checks, evaluated on every iteration for the monitored validators
+
+use {
+    solana_rpc_client_api::response::{
+        RpcBlockProduction, RpcContactInfo, RpcVoteAccountInfo, RpcVoteAccountStatus,
+    },
+    solana_sdk::{clock::Epoch, pubkey::Pubkey},
+    std::collections::HashMap,
+};
+
+fn find_vote_account<'a>(
+    vote_accounts: &'a RpcVoteAccountStatus,
+    validator_identity: &Pubkey,
+) -> Option<&'a RpcVoteAccountInfo> {
+    let validator_identity = validator_identity.to_string();
+    vote_accounts
+        .current
+        .iter()
+        .chain(vote_accounts.delinquent.iter())
+        .find(|vote_account| vote_account.node_pubkey == validator_identity)
+}
+
+/// The most recent epoch any vote account earned credits in
+fn current_epoch(vote_accounts: &RpcVoteAccountStatus) -> Option<Epoch> {
+    vote_accounts
+        .current
+        .iter()
+        .chain(vote_accounts.delinquent.iter())
+        .filter_map(|vote_account| vote_account.epoch_credits.last())
+        .map(|(epoch, _credits, _previous_credits)| *epoch)
+        .max()
+}
+
+fn epoch_vote_credits(vote_account: &RpcVoteAccountInfo, epoch: Epoch) -> u64 {
+    vote_account
+        .epoch_credits
+        .iter()
+        .rev()
+        .find(|(credits_epoch, _credits, _previous_credits)| *credits_epoch == epoch)
+        .map(|(_epoch, credits, previous_credits)| credits.saturating_sub(*previous_credits))
+        .unwrap_or_default()
+}
+
+/// Alerts when the validator earned fewer vote credits in the current epoch than
+/// `threshold_percent` of the average of the non-delinquent validators
+pub fn check_vote_credits(
+    vote_accounts: &RpcVoteAccountStatus,
+    validator_identity: &Pubkey,
+    threshold_percent: u8,
+) -> Option<String> {
+    let epoch = current_epoch(vote_accounts)?;
+    let vote_account = find_vote_account(vote_accounts, validator_identity)?;
+    if vote_accounts.current.is_empty() {
+        return None;
+    }
+    let total_credits: u64 = vote_accounts
+        .current
+        .iter()
+        .map(|vote_account| epoch_vote_credits(vote_account, epoch))
+        .sum();
+    let average_credits = total_credits as f64 / vote_accounts.current.len() as f64;
+    if average_credits == 0. {
+        return None;
+    }
+
+    let credits = epoch_vote_credits(vote_account, epoch);
+    let credits_percent = credits as f64 * 100. / average_credits;
+    (credits_percent < threshold_percent as f64).then(|| {
+        format!(
+            "earned {credits} vote credits in epoch {epoch}, {credits_percent:.2}% of the \
+             cluster average of {average_credits:.0}"
+        )
+    })
+}
+
+/// Alerts when the validator skipped more than `threshold_percent` of its leader slots in the
+/// range covered by `block_production`, once it has had at least `minimum_leader_slots`
+pub fn check_skip_rate(
+    block_production: &RpcBlockProduction,
+    validator_identity: &Pubkey,
+    threshold_percent: u8,
+    minimum_leader_slots: usize,
+) -> Option<String> {
+    let (leader_slots, blocks_produced) = block_production
+        .by_identity
+        .get(&validator_identity.to_string())?;
+    if *leader_slots == 0 || *leader_slots < minimum_leader_slots {
+        return None;
+    }
+    let skipped_slots = leader_slots.saturating_sub(*blocks_produced);
+    let skip_rate = skipped_slots as f64 * 100. / *leader_slots as f64;
+    (skip_rate > threshold_percent as f64).then(|| {
+        format!(
+            "skipped {skipped_slots} of {leader_slots} leader slots ({skip_rate:.2}%) in slots \
+             {}..={}",
+            block_production.range.first_slot, block_production.range.last_slot,
+        )
+    })
+}
+
+/// Alerts when the validator runs a different version than the majority of the cluster stake
+pub fn check_version(
+    cluster_nodes: &[RpcContactInfo],
+    vote_accounts: &RpcVoteAccountStatus,
+    validator_identity: &Pubkey,
+) -> Option<String> {
+    let mut node_stakes: HashMap<&str, u64> = HashMap::new();
+    for vote_account in vote_accounts
+        .current
+        .iter()
+        .chain(vote_accounts.delinquent.iter())
+    {
+        *node_stakes
+            .entry(vote_account.node_pubkey.as_str())
+            .or_default() += vote_account.activated_stake;
+    }
+
+    let mut version_stakes: HashMap<&str, u64> = HashMap::new();
+    for node in cluster_nodes {
+        if let Some(version) = &node.version {
+            *version_stakes.entry(version.as_str()).or_default() += node_stakes
+                .get(node.pubkey.as_str())
+                .copied()
+                .unwrap_or_default();
+        }
+    }
+    let (cluster_version, _stake) =
+        version_stakes
+            .into_iter()
+            .max_by(|(version_a, stake_a), (version_b, stake_b)| {
+                stake_a.cmp(stake_b).then_with(|| version_a.cmp(version_b))
+            })?;
+
+    let validator_identity = validator_identity.to_string();
+    let node = cluster_nodes
+        .iter()
+        .find(|node| node.pubkey == validator_identity)?;
+    match &node.version {
+        Some(version) if version == cluster_version => None,
+        Some(version) => Some(format!(
+            "running version {version}, the majority of the cluster stake runs {cluster_version}"
+        )),
+        None => Some(format!(
+            "running an unknown version, the majority of the cluster stake runs {cluster_version}"
+        )),
+    }
+}
+
+/// Remembers the commission of the monitored vote accounts to report changes
+#[derive(Default)]
+pub struct CommissionTracker {
+    commissions: HashMap<Pubkey, (String, u8)>,
+}
+
+impl CommissionTracker {
+    /// Returns a description of the change if the validator's commission differs from the one
+    /// seen on the previous call
+    pub fn update(
+        &mut self,
+        vote_accounts: &RpcVoteAccountStatus,
+        validator_identity: &Pubkey,
+    ) -> Option<String> {
+        let vote_account = find_vote_account(vote_accounts, validator_identity)?;
+        let (previous_vote_pubkey, previous_commission) = self.commissions.insert(
+            *validator_identity,
+            (vote_account.vote_pubkey.clone(), vote_account.commission),
+        )?;
+        (previous_vote_pubkey == vote_account.vote_pubkey
+            && previous_commission != vote_account.commission)
+            .then(|| {
+                format!(
+                    "commission of vote account {} changed from {previous_commission}% to {}%",
+                    vote_account.vote_pubkey, vote_account.commission,
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_rpc_client_api::response::RpcBlockProductionRange};
+
+    fn vote_account(
+        node_pubkey: &Pubkey,
+        activated_stake: u64,
+        commission: u8,
+        epoch_credits: Vec<(Epoch, u64, u64)>,
+    ) -> RpcVoteAccountInfo {
+        RpcVoteAccountInfo {
+            vote_pubkey: Pubkey::new_unique().to_string(),
+            node_pubkey: node_pubkey.to_string(),
+            activated_stake,
+            commission,
+            epoch_vote_account: true,
+            epoch_credits,
+            last_vote: 0,
+            root_slot: 0,
+        }
+    }
+
+    fn contact_info(pubkey: &Pubkey, version: Option<&str>) -> RpcContactInfo {
+        RpcContactInfo {
+            pubkey: pubkey.to_string(),
+            gossip: None,
+            tpu: None,
+            tpu_quic: None,
+            rpc: None,
+            pubsub: None,
+            version: version.map(str::to_string),
+            feature_set: None,
+            shred_version: None,
+        }
+    }
+
+    #[test]
+    fn test_check_vote_credits() {
+        let validator = Pubkey::new_unique();
+        let vote_accounts = RpcVoteAccountStatus {
+            current: vec![
+                vote_account(&validator, 1, 0, vec![(4, 900, 800), (5, 950, 900)]),
+                vote_account(&Pubkey::new_unique(), 1, 0, vec![(5, 1_100, 1_000)]),
+                vote_account(&Pubkey::new_unique(), 1, 0, vec![(5, 1_100, 1_000)]),
+            ],
+            delinquent: vec![],
+        };
+        // 50 credits against an average of 83
+        assert!(check_vote_credits(&vote_accounts, &validator, 80).is_some());
+        assert!(check_vote_credits(&vote_accounts, &validator, 50).is_none());
+        assert!(check_vote_credits(&vote_accounts, &Pubkey::new_unique(), 80).is_none());
+    }
+
+    #[test]
+    fn test_check_skip_rate() {
+        let validator = Pubkey::new_unique();
+        let block_production = RpcBlockProduction {
+            by_identity: HashMap::from([(validator.to_string(), (8, 6))]),
+            range: RpcBlockProductionRange {
+                first_slot: 0,
+                last_slot: 100,
+            },
+        };
+        assert!(check_skip_rate(&block_production, &validator, 20, 4).is_some());
+        assert!(check_skip_rate(&block_production, &validator, 25, 4).is_none());
+        assert!(check_skip_rate(&block_production, &validator, 20, 10).is_none());
+    }
+
+    #[test]
+    fn test_check_version() {
+        let validator = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let vote_accounts = RpcVoteAccountStatus {
+            current: vec![
+                vote_account(&validator, 10, 0, vec![]),
+                vote_account(&other, 20, 0, vec![]),
+            ],
+            delinquent: vec![],
+        };
+        let cluster_nodes = vec![
+            contact_info(&validator, Some("1.17.0")),
+            contact_info(&other, Some("1.18.0")),
+        ];
+        assert!(check_version(&cluster_nodes, &vote_accounts, &validator).is_some());
+        assert!(check_version(&cluster_nodes, &vote_accounts, &other).is_none());
+    }
+
+    #[test]
+    fn test_commission_tracker() {
+        let validator = Pubkey::new_unique();
+        let mut vote_accounts = RpcVoteAccountStatus {
+            current: vec![vote_account(&validator, 1, 5, vec![])],
+            delinquent: vec![],
+        };
+        let mut commission_tracker = CommissionTracker::default();
+        assert!(commission_tracker
+            .update(&vote_accounts, &validator)
+            .is_none());
+        assert!(commission_tracker
+            .update(&vote_accounts, &validator)
+            .is_none());
+        vote_accounts.current[0].commission = 100;
+        assert!(commission_tracker
+            .update(&vote_accounts, &validator)
+            .is_some());
+        assert!(commission_tracker
+            .update(&vote_accounts, &validator)
+            .is_none());
+    }
+}
@@ -1,7 +1,12 @@
 //! A command-line executable for monitoring the health of a cluster
 #![allow(clippy::arithmetic_side_effects)]
 
+mod alert_policy;
+mod checks;
+
 use {
+    alert_policy::AlertPolicy,
+    checks::CommissionTracker,
     clap::{crate_description, crate_name, value_t, value_t_or_exit, App, Arg},
     log::*,
     solana_clap_utils::{
@@ -13,7 +18,10 @@ use {
     solana_metrics::{datapoint_error, datapoint_info},
     solana_notifier::{NotificationType, Notifier},
     solana_rpc_client::rpc_client::RpcClient,
-    solana_rpc_client_api::{client_error, response::RpcVoteAccountStatus},
+    solana_rpc_client_api::{
+        client_error,
+        response::{RpcBlockProduction, RpcContactInfo, RpcVoteAccountStatus},
+    },
     solana_sdk::{
         hash::Hash,
         native_token::{sol_to_lamports, Sol},
@@ -36,6 +44,14 @@ struct Config {
     minimum_validator_identity_balance: u64,
     monitor_active_stake: bool,
     active_stake_alert_threshold: u8,
+    monitor_vote_credits: bool,
+    vote_credits_alert_threshold: u8,
+    monitor_skip_rate: bool,
+    skip_rate_alert_threshold: u8,
+    skip_rate_minimum_leader_slots: usize,
+    monitor_commission: bool,
+    monitor_version: bool,
+    notification_cooldown: Duration,
     unhealthy_threshold: usize,
     validator_identity_pubkeys: Vec<Pubkey>,
     name_suffix: String,
@@ -151,6 +167,67 @@ fn get_config() -> Config {
                 .default_value("80")
                 .help("Alert when the current stake for the cluster drops below this value"),
         )
+        .arg(
+            Arg::with_name("monitor_vote_credits")
+                .long("monitor-vote-credits")
+                .takes_value(false)
+                .help("Alert when a monitored validator earns fewer vote credits in the current epoch than the percentage of the cluster average specified by --vote-credits-alert-threshold"),
+        )
+        .arg(
+            Arg::with_name("vote_credits_alert_threshold")
+                .long("vote-credits-alert-threshold")
+                .value_name("PERCENTAGE")
+                .takes_value(true)
+                .validator(is_valid_percentage)
+                .default_value("80")
+                .help("Alert when the vote credits of a monitored validator drop below this percentage of the cluster average"),
+        )
+        .arg(
+            Arg::with_name("monitor_skip_rate")
+                .long("monitor-skip-rate")
+                .takes_value(false)
+                .help("Alert when a monitored validator skips more of its leader slots in the current epoch than the amount specified by --skip-rate-alert-threshold"),
+        )
+        .arg(
+            Arg::with_name("skip_rate_alert_threshold")
+                .long("skip-rate-alert-threshold")
+                .value_name("PERCENTAGE")
+                .takes_value(true)
+                .validator(is_valid_percentage)
+                .default_value("25")
+                .help("Alert when a monitored validator skips more than this percentage of its leader slots"),
+        )
+        .arg(
+            Arg::with_name("skip_rate_minimum_leader_slots")
+                .long("skip-rate-minimum-leader-slots")
+                .value_name("COUNT")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value("8")
+                .help("Only check the skip rate of a monitored validator once it had at least this many leader slots in the current epoch"),
+        )
+        .arg(
+            Arg::with_name("monitor_commission")
+                .long("monitor-commission")
+                .takes_value(false)
+                .help("Notify when the commission of a monitored validator's vote account changes"),
+        )
+        .arg(
+            Arg::with_name("monitor_version")
+                .long("monitor-version")
+                .takes_value(false)
+                .help("Alert when a monitored validator runs a different version than the majority of the cluster stake"),
+        )
+        .arg(
+            Arg::with_name("notification_cooldown")
+                .long("notification-cooldown")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .default_value("300")
+                .help("Minimum time between two notifications of the same failing check. \
+                    A check does not notify again while it keeps failing for the same validators"),
+        )
         .arg(
             Arg::with_name("ignore_http_bad_gateway")
                 .long("ignore-http-bad-gateway")
@@ -195,6 +272,17 @@ fn get_config() -> Config {
     let monitor_active_stake = matches.is_present("monitor_active_stake");
     let active_stake_alert_threshold =
         value_t_or_exit!(matches, "active_stake_alert_threshold", u8);
+    let monitor_vote_credits = matches.is_present("monitor_vote_credits");
+    let vote_credits_alert_threshold =
+        value_t_or_exit!(matches, "vote_credits_alert_threshold", u8);
+    let monitor_skip_rate = matches.is_present("monitor_skip_rate");
+    let skip_rate_alert_threshold = value_t_or_exit!(matches, "skip_rate_alert_threshold", u8);
+    let skip_rate_minimum_leader_slots =
+        value_t_or_exit!(matches, "skip_rate_minimum_leader_slots", usize);
+    let monitor_commission = matches.is_present("monitor_commission");
+    let monitor_version = matches.is_present("monitor_version");
+    let notification_cooldown =
+        Duration::from_secs(value_t_or_exit!(matches, "notification_cooldown", u64));
     let ignore_http_bad_gateway = matches.is_present("ignore_http_bad_gateway");
 
     let name_suffix = value_t_or_exit!(matches, "name_suffix", String);
@@ -208,6 +296,14 @@ fn get_config() -> Config {
         minimum_validator_identity_balance,
         monitor_active_stake,
         active_stake_alert_threshold,
+        monitor_vote_credits,
+        vote_credits_alert_threshold,
+        monitor_skip_rate,
+        skip_rate_alert_threshold,
+        skip_rate_minimum_leader_slots,
+        monitor_commission,
+        monitor_version,
+        notification_cooldown,
         unhealthy_threshold,
         validator_identity_pubkeys,
         name_suffix,
@@ -221,10 +317,32 @@ fn get_config() -> Config {
     config
 }
 
-fn get_cluster_info(
-    config: &Config,
-    rpc_client: &RpcClient,
-) -> client_error::Result<(u64, Hash, RpcVoteAccountStatus, HashMap<Pubkey, u64>)> {
+struct ClusterInfo {
+    transaction_count: u64,
+    recent_blockhash: Hash,
+    vote_accounts: RpcVoteAccountStatus,
+    validator_balances: HashMap<Pubkey, u64>,
+    /// Only fetched when monitoring the skip rate
+    block_production: Option<RpcBlockProduction>,
+    /// Only fetched when monitoring versions
+    cluster_nodes: Option<Vec<RpcContactInfo>>,
+}
+
+/// The errors of a validator check, along with the validators they are about
+#[derive(Default)]
+struct ValidatorErrors {
+    validators: Vec<Pubkey>,
+    errors: Vec<String>,
+}
+
+impl ValidatorErrors {
+    fn push(&mut self, validator: Pubkey, error: String) {
+        self.validators.push(validator);
+        self.errors.push(error);
+    }
+}
+
+fn get_cluster_info(config: &Config, rpc_client: &RpcClient) -> client_error::Result<ClusterInfo> {
     let transaction_count = rpc_client.get_transaction_count()?;
     let recent_blockhash = rpc_client.get_latest_blockhash()?;
     let vote_accounts = rpc_client.get_vote_accounts()?;
@@ -237,12 +355,26 @@ fn get_cluster_info(
         );
     }
 
-    Ok((
+    let monitors_validators = !config.validator_identity_pubkeys.is_empty();
+    let block_production = if config.monitor_skip_rate && monitors_validators {
+        Some(rpc_client.get_block_production()?.value)
+    } else {
+        None
+    };
+    let cluster_nodes = if config.monitor_version && monitors_validators {
+        Some(rpc_client.get_cluster_nodes()?)
+    } else {
+        None
+    };
+
+    Ok(ClusterInfo {
         transaction_count,
         recent_blockhash,
         vote_accounts,
         validator_balances,
-    ))
+        block_production,
+        cluster_nodes,
+    })
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
@@ -255,14 +387,23 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let notifier = Notifier::default();
     let mut last_transaction_count = 0;
     let mut last_recent_blockhash = Hash::default();
-    let mut last_notification_msg = "".into();
+    let mut alert_policy = AlertPolicy::new(config.notification_cooldown);
+    let mut commission_tracker = CommissionTracker::default();
     let mut num_consecutive_failures = 0;
     let mut last_success = Instant::now();
     let mut incident = Hash::new_unique();
 
     loop {
-        let failure = match get_cluster_info(&config, &rpc_client) {
-            Ok((transaction_count, recent_blockhash, vote_accounts, validator_balances)) => {
+        let mut events = vec![];
+        let failures = match get_cluster_info(&config, &rpc_client) {
+            Ok(ClusterInfo {
+                transaction_count,
+                recent_blockhash,
+                vote_accounts,
+                validator_balances,
+                block_production,
+                cluster_nodes,
+            }) => {
                 info!("Current transaction count: {}", transaction_count);
                 info!("Recent blockhash: {}", recent_blockhash);
                 info!("Current validator count: {}", vote_accounts.current.len());
@@ -299,6 +440,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 } else {
                     failures.push((
                         "transaction-count",
+                        vec![],
                         format!(
                             "Transaction count is not advancing: {transaction_count} <= {last_transaction_count}"
                         ),
@@ -310,6 +452,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 } else {
                     failures.push((
                         "recent-blockhash",
+                        vec![],
                         format!("Unable to get new blockhash: {recent_blockhash}"),
                     ));
                 }
@@ -319,11 +462,16 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 {
                     failures.push((
                         "current-stake",
+                        vec![],
                         format!("Current stake is {current_stake_percent:.2}%"),
                     ));
                 }
 
-                let mut validator_errors = vec![];
+                let mut validator_errors = ValidatorErrors::default();
+                let mut balance_errors = ValidatorErrors::default();
+                let mut vote_credits_errors = ValidatorErrors::default();
+                let mut skip_rate_errors = ValidatorErrors::default();
+                let mut version_errors = ValidatorErrors::default();
                 for validator_identity in config.validator_identity_pubkeys.iter() {
                     let formatted_validator_identity = format_labeled_address(
                         &validator_identity.to_string(),
@@ -334,36 +482,99 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                         .iter()
                         .any(|vai| vai.node_pubkey == *validator_identity.to_string())
                     {
-                        validator_errors.push(format!("{formatted_validator_identity} delinquent"));
+                        validator_errors.push(
+                            *validator_identity,
+                            format!("{formatted_validator_identity} delinquent"),
+                        );
                     } else if !vote_accounts
                         .current
                         .iter()
                         .any(|vai| vai.node_pubkey == *validator_identity.to_string())
                     {
-                        validator_errors.push(format!("{formatted_validator_identity} missing"));
+                        validator_errors.push(
+                            *validator_identity,
+                            format!("{formatted_validator_identity} missing"),
+                        );
                     }
 
                     if let Some(balance) = validator_balances.get(validator_identity) {
                         if *balance < config.minimum_validator_identity_balance {
-                            failures.push((
-                                "balance",
+                            balance_errors.push(
+                                *validator_identity,
                                 format!("{} has {}", formatted_validator_identity, Sol(*balance)),
+                            );
+                        }
+                    }
+
+                    if config.monitor_vote_credits {
+                        if let Some(err) = checks::check_vote_credits(
+                            &vote_accounts,
+                            validator_identity,
+                            config.vote_credits_alert_threshold,
+                        ) {
+                            vote_credits_errors.push(
+                                *validator_identity,
+                                format!("{formatted_validator_identity} {err}"),
+                            );
+                        }
+                    }
+
+                    if let Some(block_production) = &block_production {
+                        if let Some(err) = checks::check_skip_rate(
+                            block_production,
+                            validator_identity,
+                            config.skip_rate_alert_threshold,
+                            config.skip_rate_minimum_leader_slots,
+                        ) {
+                            skip_rate_errors.push(
+                                *validator_identity,
+                                format!("{formatted_validator_identity} {err}"),
+                            );
+                        }
+                    }
+
+                    if let Some(cluster_nodes) = &cluster_nodes {
+                        if let Some(err) =
+                            checks::check_version(cluster_nodes, &vote_accounts, validator_identity)
+                        {
+                            version_errors.push(
+                                *validator_identity,
+                                format!("{formatted_validator_identity} {err}"),
+                            );
+                        }
+                    }
+
+                    if config.monitor_commission {
+                        if let Some(change) =
+                            commission_tracker.update(&vote_accounts, validator_identity)
+                        {
+                            events.push((
+                                "commission",
+                                format!("{formatted_validator_identity} {change}"),
                             ));
                         }
                     }
                 }
 
-                if !validator_errors.is_empty() {
-                    failures.push(("delinquent", validator_errors.join(",")));
+                for (test_name, ValidatorErrors { validators, errors }, separator) in [
+                    ("delinquent", validator_errors, ","),
+                    ("balance", balance_errors, ", "),
+                    ("vote-credits", vote_credits_errors, ", "),
+                    ("skip-rate", skip_rate_errors, ", "),
+                    ("version", version_errors, ", "),
+                ] {
+                    if !errors.is_empty() {
+                        failures.push((test_name, validators, errors.join(separator)));
+                    }
                 }
 
-                for failure in failures.iter() {
-                    error!("{} sanity failure: {}", failure.0, failure.1);
+                for (test_name, _validators, error_message) in failures.iter() {
+                    error!("{} sanity failure: {}", test_name, error_message);
                 }
-                failures.into_iter().next() // Only report the first failure if any
+                failures
             }
             Err(err) => {
-                let mut failure = Some(("rpc-error", err.to_string()));
+                let mut failure = vec![("rpc-error", vec![], err.to_string())];
 
                 if let client_error::ErrorKind::Reqwest(reqwest_err) = err.kind() {
                     if let Some(client_error::reqwest::StatusCode::BAD_GATEWAY) =
@@ -371,7 +582,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     {
                         if config.ignore_http_bad_gateway {
                             warn!("Error suppressed: {}", err);
-                            failure = None;
+                            failure.clear();
                         }
                     }
                 }
@@ -379,32 +590,60 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             }
         };
 
-        if let Some((failure_test_name, failure_error_message)) = &failure {
+        // Events are not a lasting failure, so they are notified right away as their own incident
+        for (event_name, event_message) in events {
             let notification_msg = format!(
-                "solana-watchtower{}: Error: {}: {}",
-                config.name_suffix, failure_test_name, failure_error_message
+                "solana-watchtower{}: {}: {}",
+                config.name_suffix, event_name, event_message
             );
+            info!("{}", notification_msg);
+            notifier.send(
+                &notification_msg,
+                &NotificationType::Trigger {
+                    incident: Hash::new_unique(),
+                },
+            );
+            datapoint_info!(
+                "watchtower-event",
+                ("event", event_name, String),
+                ("msg", event_message, String)
+            );
+        }
+
+        if !failures.is_empty() {
             num_consecutive_failures += 1;
             if num_consecutive_failures > config.unhealthy_threshold {
                 datapoint_info!("watchtower-sanity", ("ok", false, bool));
-                if last_notification_msg != notification_msg {
-                    notifier.send(&notification_msg, &NotificationType::Trigger { incident });
+                alert_policy.resolve_except(failures.iter().map(|(test_name, _, _)| *test_name));
+                let now = Instant::now();
+                for (failure_test_name, failure_validators, failure_error_message) in &failures {
+                    let notification_msg = format!(
+                        "solana-watchtower{}: Error: {}: {}",
+                        config.name_suffix, failure_test_name, failure_error_message
+                    );
+                    if alert_policy.should_notify(failure_test_name, failure_validators, now) {
+                        notifier.send(&notification_msg, &NotificationType::Trigger { incident });
+                    }
+                    datapoint_error!(
+                        "watchtower-sanity-failure",
+                        ("test", failure_test_name, String),
+                        ("err", failure_error_message, String)
+                    );
                 }
-                datapoint_error!(
-                    "watchtower-sanity-failure",
-                    ("test", failure_test_name, String),
-                    ("err", failure_error_message, String)
-                );
-                last_notification_msg = notification_msg;
             } else {
-                info!(
-                    "Failure {} of {}: {}",
-                    num_consecutive_failures, config.unhealthy_threshold, notification_msg
-                );
+                for (failure_test_name, _, failure_error_message) in &failures {
+                    info!(
+                        "Failure {} of {}: {}: {}",
+                        num_consecutive_failures,
+                        config.unhealthy_threshold,
+                        failure_test_name,
+                        failure_error_message
+                    );
+                }
             }
         } else {
             datapoint_info!("watchtower-sanity", ("ok", true, bool));
-            if !last_notification_msg.is_empty() {
+            if alert_policy.has_unresolved() {
                 let alarm_duration = Instant::now().duration_since(last_success);
                 let alarm_duration = alarm_duration - config.interval; // Subtract the period before the first error
                 let alarm_duration = Duration::from_secs(alarm_duration.as_secs()); // Drop milliseconds in message
@@ -419,7 +658,7 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     &NotificationType::Resolve { incident },
                 );
             }
-            last_notification_msg = "".into();
+            alert_policy.resolve_except([]);
             last_success = Instant::now();
             num_consecutive_failures = 0;
             incident = Hash::new_unique();
@@ -0,0 +1,300 @@
+//! Aggregates the heaviest forks received from gossip during a wen_restart and decides whether
+//! a supermajority of the stake agrees with the heaviest fork of this node
+
+use {
+    crate::{
+        last_voted_fork_slots_aggregate::LastVotedForkSlotsAggregate,
+        solana::wen_restart_proto::RestartHeaviestForkRecord,
+    },
+    log::*,
+    solana_gossip::crds_value::RestartHeaviestFork,
+    solana_runtime::epoch_stakes::EpochStakes,
+    solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey},
+    std::{collections::HashMap, str::FromStr},
+};
+
+pub struct HeaviestForkAggregate {
+    // The stakes of the epoch of the heaviest fork slot of this node, the fork all validators
+    // need to agree on.
+    epoch_stakes: EpochStakes,
+    my_pubkey: Pubkey,
+    my_heaviest_fork: (Slot, Hash),
+    heaviest_forks: HashMap<Pubkey, RestartHeaviestFork>,
+}
+
+impl HeaviestForkAggregate {
+    pub(crate) fn new(
+        epoch_stakes: &EpochStakes,
+        my_heaviest_fork_slot: Slot,
+        my_heaviest_fork_hash: Hash,
+        my_pubkey: &Pubkey,
+    ) -> Self {
+        Self {
+            epoch_stakes: epoch_stakes.clone(),
+            my_pubkey: *my_pubkey,
+            my_heaviest_fork: (my_heaviest_fork_slot, my_heaviest_fork_hash),
+            heaviest_forks: HashMap::new(),
+        }
+    }
+
+    /// Aggregates a record previously written to the progress file
+    pub(crate) fn aggregate_from_record(
+        &mut self,
+        key_string: &str,
+        record: &RestartHeaviestForkRecord,
+    ) -> Result<Option<RestartHeaviestForkRecord>, Box<dyn std::error::Error>> {
+        let heaviest_fork = RestartHeaviestFork {
+            from: Pubkey::from_str(key_string)?,
+            wallclock: record.wallclock,
+            last_slot: record.slot,
+            last_slot_hash: Hash::from_str(&record.bankhash)?,
+            observed_stake: record.observed_stake,
+            shred_version: record.shred_version as u16,
+        };
+        Ok(self.aggregate(heaviest_fork))
+    }
+
+    /// Aggregates the heaviest fork of a validator, replacing any previous entry from the same
+    /// validator. Returns the record to persist if anything changed.
+    pub(crate) fn aggregate(
+        &mut self,
+        heaviest_fork: RestartHeaviestFork,
+    ) -> Option<RestartHeaviestForkRecord> {
+        let from = heaviest_fork.from;
+        if from == self.my_pubkey {
+            return None;
+        }
+        if LastVotedForkSlotsAggregate::validator_stake(&self.epoch_stakes, &from) == 0 {
+            warn!(
+                "Gossip should not accept zero-stake RestartHeaviestFork from {:?}",
+                from
+            );
+            return None;
+        }
+        if self.heaviest_forks.get(&from) == Some(&heaviest_fork) {
+            return None;
+        }
+        let record = RestartHeaviestForkRecord {
+            slot: heaviest_fork.last_slot,
+            bankhash: heaviest_fork.last_slot_hash.to_string(),
+            observed_stake: heaviest_fork.observed_stake,
+            shred_version: heaviest_fork.shred_version as u32,
+            wallclock: heaviest_fork.wallclock,
+        };
+        if (heaviest_fork.last_slot, heaviest_fork.last_slot_hash) != self.my_heaviest_fork {
+            warn!(
+                "wen_restart heaviest fork {} {} of {} differs from ours {:?}",
+                heaviest_fork.last_slot, heaviest_fork.last_slot_hash, from, self.my_heaviest_fork
+            );
+        }
+        self.heaviest_forks.insert(from, heaviest_fork);
+        Some(record)
+    }
+
+    fn percent_of_total_stake(&self, stake: u64) -> f64 {
+        let total_stake = self.epoch_stakes.total_stake();
+        if total_stake == 0 {
+            return 0.0;
+        }
+        stake as f64 / total_stake as f64 * 100.0
+    }
+
+    /// Percent of the stake which selected the same heaviest fork as this node, including this
+    /// node
+    pub(crate) fn agreeing_percent(&self) -> f64 {
+        let stake = self
+            .heaviest_forks
+            .values()
+            .filter(|fork| (fork.last_slot, fork.last_slot_hash) == self.my_heaviest_fork)
+            .map(|fork| {
+                LastVotedForkSlotsAggregate::validator_stake(&self.epoch_stakes, &fork.from)
+            })
+            .chain([LastVotedForkSlotsAggregate::validator_stake(
+                &self.epoch_stakes,
+                &self.my_pubkey,
+            )])
+            .sum();
+        self.percent_of_total_stake(stake)
+    }
+
+    /// Percent of the stake which selected a different heaviest fork than this node
+    pub(crate) fn disagreeing_percent(&self) -> f64 {
+        let stake = self
+            .heaviest_forks
+            .values()
+            .filter(|fork| (fork.last_slot, fork.last_slot_hash) != self.my_heaviest_fork)
+            .map(|fork| {
+                LastVotedForkSlotsAggregate::validator_stake(&self.epoch_stakes, &fork.from)
+            })
+            .sum();
+        self.percent_of_total_stake(stake)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime::{
+            bank::Bank,
+            genesis_utils::{
+                create_genesis_config_with_vote_accounts, GenesisConfigInfo, ValidatorVoteKeypairs,
+            },
+        },
+        solana_sdk::{signature::Signer, timing::timestamp},
+    };
+
+    const TOTAL_VALIDATOR_COUNT: usize = 10;
+    const MY_INDEX: usize = 9;
+    const SHRED_VERSION: u16 = 52;
+
+    struct TestAggregateInitResult {
+        pub heaviest_fork_aggregate: HeaviestForkAggregate,
+        pub validator_voting_keypairs: Vec<ValidatorVoteKeypairs>,
+        pub heaviest_fork_slot: Slot,
+        pub heaviest_fork_hash: Hash,
+    }
+
+    fn test_aggregate_init() -> TestAggregateInitResult {
+        solana_logger::setup();
+        let validator_voting_keypairs: Vec<_> = (0..TOTAL_VALIDATOR_COUNT)
+            .map(|_| ValidatorVoteKeypairs::new_rand())
+            .collect();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            10_000,
+            &validator_voting_keypairs,
+            vec![100; validator_voting_keypairs.len()],
+        );
+        let bank = Bank::new_for_tests(&genesis_config);
+        let heaviest_fork_slot = bank.slot() + 3;
+        let heaviest_fork_hash = Hash::new_unique();
+        TestAggregateInitResult {
+            heaviest_fork_aggregate: HeaviestForkAggregate::new(
+                bank.epoch_stakes(bank.epoch()).unwrap(),
+                heaviest_fork_slot,
+                heaviest_fork_hash,
+                &validator_voting_keypairs[MY_INDEX].node_keypair.pubkey(),
+            ),
+            validator_voting_keypairs,
+            heaviest_fork_slot,
+            heaviest_fork_hash,
+        }
+    }
+
+    fn new_heaviest_fork(
+        from: Pubkey,
+        last_slot: Slot,
+        last_slot_hash: Hash,
+    ) -> RestartHeaviestFork {
+        RestartHeaviestFork {
+            from,
+            wallclock: timestamp(),
+            last_slot,
+            last_slot_hash,
+            observed_stake: 900,
+            shred_version: SHRED_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let mut test_state = test_aggregate_init();
+        let aggregate = &mut test_state.heaviest_fork_aggregate;
+        assert_eq!(aggregate.agreeing_percent(), 10.0);
+        assert_eq!(aggregate.disagreeing_percent(), 0.0);
+
+        for validator_voting_keypair in test_state.validator_voting_keypairs.iter().take(6) {
+            let heaviest_fork = new_heaviest_fork(
+                validator_voting_keypair.node_keypair.pubkey(),
+                test_state.heaviest_fork_slot,
+                test_state.heaviest_fork_hash,
+            );
+            assert_eq!(
+                aggregate.aggregate(heaviest_fork.clone()),
+                Some(RestartHeaviestForkRecord {
+                    slot: test_state.heaviest_fork_slot,
+                    bankhash: test_state.heaviest_fork_hash.to_string(),
+                    observed_stake: 900,
+                    shred_version: SHRED_VERSION as u32,
+                    wallclock: heaviest_fork.wallclock,
+                })
+            );
+            // Receiving the same message again changes nothing.
+            assert!(aggregate.aggregate(heaviest_fork).is_none());
+        }
+        assert_eq!(aggregate.agreeing_percent(), 70.0);
+
+        // A different bank hash for the same slot is a different fork.
+        let disagreeing_validator = test_state.validator_voting_keypairs[6]
+            .node_keypair
+            .pubkey();
+        assert!(aggregate
+            .aggregate(new_heaviest_fork(
+                disagreeing_validator,
+                test_state.heaviest_fork_slot,
+                Hash::new_unique(),
+            ))
+            .is_some());
+        assert_eq!(aggregate.agreeing_percent(), 70.0);
+        assert_eq!(aggregate.disagreeing_percent(), 10.0);
+
+        // An updated heaviest fork from the same validator replaces the previous one.
+        assert!(aggregate
+            .aggregate(new_heaviest_fork(
+                disagreeing_validator,
+                test_state.heaviest_fork_slot,
+                test_state.heaviest_fork_hash,
+            ))
+            .is_some());
+        assert_eq!(aggregate.agreeing_percent(), 80.0);
+        assert_eq!(aggregate.disagreeing_percent(), 0.0);
+
+        // Messages from this node or from unstaked validators are ignored.
+        let my_pubkey = test_state.validator_voting_keypairs[MY_INDEX]
+            .node_keypair
+            .pubkey();
+        assert!(aggregate
+            .aggregate(new_heaviest_fork(my_pubkey, 1, Hash::new_unique()))
+            .is_none());
+        assert!(aggregate
+            .aggregate(new_heaviest_fork(
+                Pubkey::new_unique(),
+                1,
+                Hash::new_unique()
+            ))
+            .is_none());
+        assert_eq!(aggregate.agreeing_percent(), 80.0);
+        assert_eq!(aggregate.disagreeing_percent(), 0.0);
+    }
+
+    #[test]
+    fn test_aggregate_from_record() {
+        let mut test_state = test_aggregate_init();
+        let aggregate = &mut test_state.heaviest_fork_aggregate;
+        let record = RestartHeaviestForkRecord {
+            slot: test_state.heaviest_fork_slot,
+            bankhash: test_state.heaviest_fork_hash.to_string(),
+            observed_stake: 900,
+            shred_version: SHRED_VERSION as u32,
+            wallclock: timestamp(),
+        };
+        let key_string = test_state.validator_voting_keypairs[0]
+            .node_keypair
+            .pubkey()
+            .to_string();
+        assert_eq!(
+            aggregate
+                .aggregate_from_record(&key_string, &record)
+                .unwrap(),
+            Some(record.clone()),
+        );
+        assert_eq!(aggregate.agreeing_percent(), 20.0);
+        assert!(aggregate
+            .aggregate_from_record(&key_string, &record)
+            .unwrap()
+            .is_none());
+        assert!(aggregate
+            .aggregate_from_record("invalid pubkey", &record)
+            .is_err());
+    }
+}
@@ -0,0 +1,516 @@
+//! Aggregates the last voted fork slots received from gossip during a wen_restart and decides
+//! which slots need to be repaired before the heaviest fork can be selected
+
+use {
+    crate::solana::wen_restart_proto::LastVotedForkSlotsRecord,
+    log::*,
+    solana_gossip::crds_value::RestartLastVotedForkSlots,
+    solana_runtime::epoch_stakes::EpochStakes,
+    solana_sdk::{
+        clock::{Epoch, Slot},
+        epoch_schedule::EpochSchedule,
+        hash::Hash,
+        pubkey::Pubkey,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        str::FromStr,
+    },
+};
+
+pub struct LastVotedForkSlotsAggregate {
+    root_slot: Slot,
+    repair_threshold: f64,
+    epoch_schedule: EpochSchedule,
+    // The stakes of the epochs the last voted forks may reach, each slot is weighted by the
+    // stakes of its own epoch. Slots in later epochs are ignored.
+    epoch_stakes_map: HashMap<Epoch, EpochStakes>,
+    my_pubkey: Pubkey,
+    last_voted_fork_slots: HashMap<Pubkey, RestartLastVotedForkSlots>,
+    slots_stake_map: HashMap<Slot, u64>,
+    active_peers: HashSet<Pubkey>,
+    slots_to_repair: HashSet<Slot>,
+}
+
+impl LastVotedForkSlotsAggregate {
+    pub(crate) fn new(
+        root_slot: Slot,
+        repair_threshold: f64,
+        epoch_schedule: &EpochSchedule,
+        epoch_stakes_map: HashMap<Epoch, EpochStakes>,
+        last_voted_fork_slots: &[Slot],
+        my_pubkey: &Pubkey,
+    ) -> Self {
+        let mut aggregate = Self {
+            root_slot,
+            repair_threshold,
+            epoch_schedule: epoch_schedule.clone(),
+            epoch_stakes_map,
+            my_pubkey: *my_pubkey,
+            last_voted_fork_slots: HashMap::new(),
+            slots_stake_map: HashMap::new(),
+            active_peers: HashSet::from([*my_pubkey]),
+            slots_to_repair: HashSet::new(),
+        };
+        for slot in last_voted_fork_slots {
+            if *slot > root_slot {
+                aggregate.add_stake(*slot, my_pubkey);
+            }
+        }
+        aggregate
+    }
+
+    pub(crate) fn validator_stake(epoch_stakes: &EpochStakes, pubkey: &Pubkey) -> u64 {
+        epoch_stakes
+            .node_id_to_vote_accounts()
+            .get(pubkey)
+            .map(|node| node.total_stake)
+            .unwrap_or_default()
+    }
+
+    fn epoch_stakes(&self, slot: Slot) -> Option<&EpochStakes> {
+        self.epoch_stakes_map
+            .get(&self.epoch_schedule.get_epoch(slot))
+    }
+
+    fn threshold_stake(&self, epoch_stakes: &EpochStakes) -> u64 {
+        (epoch_stakes.total_stake() as f64 * self.repair_threshold) as u64
+    }
+
+    fn add_stake(&mut self, slot: Slot, pubkey: &Pubkey) {
+        let Some(epoch_stakes) = self.epoch_stakes(slot) else {
+            return;
+        };
+        let stake = Self::validator_stake(epoch_stakes, pubkey);
+        let threshold_stake = self.threshold_stake(epoch_stakes);
+        let entry = self.slots_stake_map.entry(slot).or_insert(0);
+        *entry = entry.saturating_add(stake);
+        if *entry >= threshold_stake {
+            self.slots_to_repair.insert(slot);
+        }
+    }
+
+    fn remove_stake(&mut self, slot: Slot, pubkey: &Pubkey) {
+        let Some(epoch_stakes) = self.epoch_stakes(slot) else {
+            return;
+        };
+        let stake = Self::validator_stake(epoch_stakes, pubkey);
+        let threshold_stake = self.threshold_stake(epoch_stakes);
+        if let Some(entry) = self.slots_stake_map.get_mut(&slot) {
+            *entry = entry.saturating_sub(stake);
+            if *entry < threshold_stake {
+                self.slots_to_repair.remove(&slot);
+            }
+        }
+    }
+
+    /// Slots after the root within the epochs whose stakes are known
+    fn slots_after_root(&self, last_voted_fork_slots: &RestartLastVotedForkSlots) -> Vec<Slot> {
+        let (known_slots, unknown_slots): (Vec<Slot>, Vec<Slot>) = last_voted_fork_slots
+            .to_slots(self.root_slot)
+            .into_iter()
+            .filter(|slot| *slot > self.root_slot)
+            .partition(|slot| self.epoch_stakes(*slot).is_some());
+        if !unknown_slots.is_empty() {
+            warn!(
+                "Ignoring slots {:?} from {:?} in epochs without known stakes",
+                unknown_slots, last_voted_fork_slots.from
+            );
+        }
+        known_slots
+    }
+
+    /// Aggregates a record previously written to the progress file
+    pub(crate) fn aggregate_from_record(
+        &mut self,
+        key_string: &str,
+        record: &LastVotedForkSlotsRecord,
+    ) -> Result<Option<LastVotedForkSlotsRecord>, Box<dyn std::error::Error>> {
+        let from = Pubkey::from_str(key_string)?;
+        let last_voted_hash = Hash::from_str(&record.last_vote_bankhash)?;
+        let mut last_voted_fork_slots = RestartLastVotedForkSlots::new(
+            from,
+            record.wallclock,
+            last_voted_hash,
+            record.shred_version as u16,
+        );
+        last_voted_fork_slots.fill(&record.last_voted_fork_slots);
+        Ok(self.aggregate(last_voted_fork_slots))
+    }
+
+    /// Aggregates the last voted fork slots of a validator, replacing any previous entry from
+    /// the same validator. Returns the record to persist if anything changed.
+    pub(crate) fn aggregate(
+        &mut self,
+        new_slots: RestartLastVotedForkSlots,
+    ) -> Option<LastVotedForkSlotsRecord> {
+        let from = new_slots.from;
+        if from == self.my_pubkey {
+            return None;
+        }
+        if self
+            .epoch_stakes_map
+            .values()
+            .all(|epoch_stakes| Self::validator_stake(epoch_stakes, &from) == 0)
+        {
+            warn!(
+                "Gossip should not accept zero-stake RestartLastVotedFork from {:?}",
+                from
+            );
+            return None;
+        }
+        let new_slots_vec = self.slots_after_root(&new_slots);
+        let record = LastVotedForkSlotsRecord {
+            last_voted_fork_slots: new_slots_vec.clone(),
+            last_vote_bankhash: new_slots.last_voted_hash.to_string(),
+            shred_version: new_slots.shred_version as u32,
+            wallclock: new_slots.wallclock,
+        };
+        let new_slots_set: HashSet<Slot> = HashSet::from_iter(new_slots_vec);
+        let old_slots_set = match self.last_voted_fork_slots.insert(from, new_slots.clone()) {
+            Some(old_slots) => {
+                if old_slots == new_slots {
+                    return None;
+                }
+                HashSet::from_iter(self.slots_after_root(&old_slots))
+            }
+            None => HashSet::new(),
+        };
+        self.active_peers.insert(from);
+        for slot in old_slots_set.difference(&new_slots_set) {
+            self.remove_stake(*slot, &from);
+        }
+        for slot in new_slots_set.difference(&old_slots_set) {
+            self.add_stake(*slot, &from);
+        }
+        Some(record)
+    }
+
+    fn active_stake(&self, epoch_stakes: &EpochStakes) -> u64 {
+        self.active_peers
+            .iter()
+            .map(|pubkey| Self::validator_stake(epoch_stakes, pubkey))
+            .sum()
+    }
+
+    /// Stake in the root epoch of the validators which sent their last voted fork slots,
+    /// including this node
+    pub(crate) fn total_active_stake(&self) -> u64 {
+        self.epoch_stakes(self.root_slot)
+            .map(|epoch_stakes| self.active_stake(epoch_stakes))
+            .unwrap_or_default()
+    }
+
+    /// Percent of the stake which sent their last voted fork slots, in the root epoch and in
+    /// every later epoch a received slot belongs to, whichever is lowest
+    pub(crate) fn active_percent(&self) -> f64 {
+        let epochs: HashSet<Epoch> = self
+            .slots_stake_map
+            .keys()
+            .chain([&self.root_slot])
+            .map(|slot| self.epoch_schedule.get_epoch(*slot))
+            .collect();
+        epochs
+            .into_iter()
+            .map(|epoch| match self.epoch_stakes_map.get(&epoch) {
+                Some(epoch_stakes) if epoch_stakes.total_stake() > 0 => {
+                    self.active_stake(epoch_stakes) as f64 / epoch_stakes.total_stake() as f64
+                        * 100.0
+                }
+                _ => 0.0,
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Stake of the validators which have the slot on their last voted fork
+    pub(crate) fn slot_stake(&self, slot: Slot) -> u64 {
+        self.slots_stake_map.get(&slot).copied().unwrap_or_default()
+    }
+
+    /// Slots on the last voted fork of at least `repair_threshold` of the stake
+    pub(crate) fn slots_to_repair_iter(&self) -> impl Iterator<Item = &Slot> {
+        self.slots_to_repair.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            last_voted_fork_slots_aggregate::LastVotedForkSlotsAggregate,
+            solana::wen_restart_proto::LastVotedForkSlotsRecord,
+        },
+        solana_gossip::crds_value::RestartLastVotedForkSlots,
+        solana_program::{clock::Slot, pubkey::Pubkey},
+        solana_runtime::{
+            bank::Bank,
+            genesis_utils::{
+                create_genesis_config_with_vote_accounts, GenesisConfigInfo, ValidatorVoteKeypairs,
+            },
+        },
+        solana_sdk::{
+            epoch_schedule::{EpochSchedule, MINIMUM_SLOTS_PER_EPOCH},
+            hash::Hash,
+            signature::Signer,
+            timing::timestamp,
+        },
+        std::collections::HashMap,
+    };
+
+    const TOTAL_VALIDATOR_COUNT: u16 = 10;
+    const MY_INDEX: usize = 9;
+    const REPAIR_THRESHOLD: f64 = 0.42;
+    const SHRED_VERSION: u16 = 52;
+
+    struct TestAggregateInitResult {
+        pub slots_aggregate: LastVotedForkSlotsAggregate,
+        pub validator_voting_keypairs: Vec<ValidatorVoteKeypairs>,
+        pub root_slot: Slot,
+    }
+
+    fn test_aggregate_init() -> TestAggregateInitResult {
+        solana_logger::setup();
+        let validator_voting_keypairs: Vec<_> = (0..TOTAL_VALIDATOR_COUNT)
+            .map(|_| ValidatorVoteKeypairs::new_rand())
+            .collect();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            10_000,
+            &validator_voting_keypairs,
+            vec![100; validator_voting_keypairs.len()],
+        );
+        let bank = Bank::new_for_tests(&genesis_config);
+        let root_slot = bank.slot();
+        let last_voted_fork_slots = vec![root_slot + 1, root_slot + 2, root_slot + 3];
+        TestAggregateInitResult {
+            slots_aggregate: LastVotedForkSlotsAggregate::new(
+                root_slot,
+                REPAIR_THRESHOLD,
+                bank.epoch_schedule(),
+                bank.epoch_stakes_map().clone(),
+                &last_voted_fork_slots,
+                &validator_voting_keypairs[MY_INDEX].node_keypair.pubkey(),
+            ),
+            validator_voting_keypairs,
+            root_slot,
+        }
+    }
+
+    fn new_last_voted_fork_slots(
+        from: Pubkey,
+        slots: &[Slot],
+        last_voted_hash: Hash,
+    ) -> RestartLastVotedForkSlots {
+        let mut last_voted_fork_slots =
+            RestartLastVotedForkSlots::new(from, timestamp(), last_voted_hash, SHRED_VERSION);
+        last_voted_fork_slots.fill(slots);
+        last_voted_fork_slots
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let mut test_state = test_aggregate_init();
+        let root_slot = test_state.root_slot;
+        let initial_num_active_validators = 3;
+        for validator_voting_keypair in test_state
+            .validator_voting_keypairs
+            .iter()
+            .take(initial_num_active_validators)
+        {
+            let pubkey = validator_voting_keypair.node_keypair.pubkey();
+            assert!(test_state
+                .slots_aggregate
+                .aggregate(new_last_voted_fork_slots(
+                    pubkey,
+                    &[root_slot + 1, root_slot + 2, root_slot + 3],
+                    Hash::default(),
+                ))
+                .is_some());
+        }
+        assert_eq!(
+            test_state.slots_aggregate.active_percent(),
+            (initial_num_active_validators + 1) as f64 / TOTAL_VALIDATOR_COUNT as f64 * 100.0
+        );
+        // 40% of the stake is below the repair threshold.
+        assert!(test_state
+            .slots_aggregate
+            .slots_to_repair_iter()
+            .next()
+            .is_none());
+
+        let new_active_validator = test_state.validator_voting_keypairs
+            [initial_num_active_validators + 1]
+            .node_keypair
+            .pubkey();
+        let last_voted_fork_slots =
+            new_last_voted_fork_slots(new_active_validator, &[root_slot + 1], Hash::default());
+        assert_eq!(
+            test_state
+                .slots_aggregate
+                .aggregate(last_voted_fork_slots.clone()),
+            Some(LastVotedForkSlotsRecord {
+                last_voted_fork_slots: vec![root_slot + 1],
+                last_vote_bankhash: Hash::default().to_string(),
+                shred_version: SHRED_VERSION as u32,
+                wallclock: last_voted_fork_slots.wallclock,
+            }),
+        );
+        let mut slots_to_repair: Vec<Slot> = test_state
+            .slots_aggregate
+            .slots_to_repair_iter()
+            .cloned()
+            .collect();
+        slots_to_repair.sort();
+        assert_eq!(slots_to_repair, vec![root_slot + 1]);
+
+        // Receiving the same message again changes nothing.
+        assert!(test_state
+            .slots_aggregate
+            .aggregate(last_voted_fork_slots)
+            .is_none());
+
+        // An updated vote from the same validator replaces the previous one.
+        assert!(test_state
+            .slots_aggregate
+            .aggregate(new_last_voted_fork_slots(
+                new_active_validator,
+                &[root_slot + 1, root_slot + 2],
+                Hash::new_unique(),
+            ))
+            .is_some());
+        let mut slots_to_repair: Vec<Slot> = test_state
+            .slots_aggregate
+            .slots_to_repair_iter()
+            .cloned()
+            .collect();
+        slots_to_repair.sort();
+        assert_eq!(slots_to_repair, vec![root_slot + 1, root_slot + 2]);
+        assert_eq!(test_state.slots_aggregate.active_percent(), 50.0);
+
+        // Messages from this node or from unstaked validators are ignored.
+        let my_pubkey = test_state.validator_voting_keypairs[MY_INDEX]
+            .node_keypair
+            .pubkey();
+        assert!(test_state
+            .slots_aggregate
+            .aggregate(new_last_voted_fork_slots(
+                my_pubkey,
+                &[root_slot + 4],
+                Hash::default(),
+            ))
+            .is_none());
+        assert!(test_state
+            .slots_aggregate
+            .aggregate(new_last_voted_fork_slots(
+                Pubkey::new_unique(),
+                &[root_slot + 4],
+                Hash::default(),
+            ))
+            .is_none());
+        assert_eq!(test_state.slots_aggregate.active_percent(), 50.0);
+    }
+
+    #[test]
+    fn test_aggregate_from_record() {
+        let mut test_state = test_aggregate_init();
+        let root_slot = test_state.root_slot;
+        let last_vote_bankhash = Hash::new_unique();
+        let record = LastVotedForkSlotsRecord {
+            last_voted_fork_slots: vec![root_slot + 1, root_slot + 2],
+            last_vote_bankhash: last_vote_bankhash.to_string(),
+            shred_version: SHRED_VERSION as u32,
+            wallclock: timestamp(),
+        };
+        let key_string = test_state.validator_voting_keypairs[0]
+            .node_keypair
+            .pubkey()
+            .to_string();
+        assert_eq!(
+            test_state
+                .slots_aggregate
+                .aggregate_from_record(&key_string, &record)
+                .unwrap(),
+            Some(record.clone()),
+        );
+        assert_eq!(test_state.slots_aggregate.active_percent(), 20.0);
+        assert!(test_state
+            .slots_aggregate
+            .aggregate_from_record(&key_string, &record)
+            .unwrap()
+            .is_none());
+        assert!(test_state
+            .slots_aggregate
+            .aggregate_from_record("invalid pubkey", &record)
+            .is_err());
+    }
+
+    #[test]
+    fn test_aggregate_across_epoch_boundary() {
+        solana_logger::setup();
+        let validator_voting_keypairs: Vec<_> = (0..TOTAL_VALIDATOR_COUNT)
+            .map(|_| ValidatorVoteKeypairs::new_rand())
+            .collect();
+        let new_epoch_stakes = |stakes: Vec<u64>| {
+            let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+                10_000,
+                &validator_voting_keypairs,
+                stakes,
+            );
+            let bank = Bank::new_for_tests(&genesis_config);
+            bank.epoch_stakes(bank.epoch()).unwrap().clone()
+        };
+        // The first validator holds more than the repair threshold in the second epoch only.
+        let mut next_epoch_stakes = vec![100; validator_voting_keypairs.len()];
+        next_epoch_stakes[0] = 1_000;
+        let epoch_schedule =
+            EpochSchedule::custom(MINIMUM_SLOTS_PER_EPOCH, MINIMUM_SLOTS_PER_EPOCH, false);
+        let root_slot = MINIMUM_SLOTS_PER_EPOCH - 2;
+        let last_slot_in_epoch = root_slot + 1;
+        let first_slot_in_next_epoch = root_slot + 2;
+        let unknown_epoch_slot = first_slot_in_next_epoch + MINIMUM_SLOTS_PER_EPOCH;
+        let mut slots_aggregate = LastVotedForkSlotsAggregate::new(
+            root_slot,
+            REPAIR_THRESHOLD,
+            &epoch_schedule,
+            HashMap::from([
+                (
+                    0,
+                    new_epoch_stakes(vec![100; validator_voting_keypairs.len()]),
+                ),
+                (1, new_epoch_stakes(next_epoch_stakes)),
+            ]),
+            &[last_slot_in_epoch, first_slot_in_next_epoch],
+            &validator_voting_keypairs[MY_INDEX].node_keypair.pubkey(),
+        );
+
+        let last_voted_fork_slots = new_last_voted_fork_slots(
+            validator_voting_keypairs[0].node_keypair.pubkey(),
+            &[
+                last_slot_in_epoch,
+                first_slot_in_next_epoch,
+                unknown_epoch_slot,
+            ],
+            Hash::default(),
+        );
+        // Slots in epochs without known stakes are dropped.
+        assert_eq!(
+            slots_aggregate
+                .aggregate(last_voted_fork_slots)
+                .unwrap()
+                .last_voted_fork_slots,
+            vec![last_slot_in_epoch, first_slot_in_next_epoch],
+        );
+        assert_eq!(slots_aggregate.slot_stake(last_slot_in_epoch), 200);
+        assert_eq!(slots_aggregate.slot_stake(first_slot_in_next_epoch), 1_100);
+        assert_eq!(slots_aggregate.slot_stake(unknown_epoch_slot), 0);
+        assert_eq!(
+            slots_aggregate
+                .slots_to_repair_iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![first_slot_in_next_epoch],
+        );
+        // The lowest active stake over both epochs.
+        assert_eq!(slots_aggregate.active_percent(), 20.0);
+        assert_eq!(slots_aggregate.total_active_stake(), 200);
+    }
+}
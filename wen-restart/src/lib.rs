@@ -4,4 +4,6 @@ pub(crate) mod solana {
     }
 }
 
+pub(crate) mod heaviest_fork_aggregate;
+pub(crate) mod last_voted_fork_slots_aggregate;
 pub mod wen_restart;
@@ -1,152 +1,1158 @@
 //! The `wen-restart` module handles automatic repair during a cluster restart
+//!
+//! A restart goes through the following phases, each of them is recorded in the progress file
+//! so a validator restarted in the middle of the protocol resumes where it stopped:
+//! 1. Push the last voted fork of this node to gossip.
+//! 2. Aggregate the last voted forks of the other validators until enough stake participates,
+//!    repairing the blocks on the fork of at least `REPAIR_THRESHOLD` of the stake.
+//! 3. Select the heaviest fork out of the repaired blocks, following at each fork the block on
+//!    the last voted fork of the most stake. Push it to gossip and wait for the validators with a
+//!    supermajority of the stake to select the same fork.
+//! 4. Register a hard fork at the heaviest fork slot and generate a snapshot for it.
+//!
+//! The validator then exits and, once restarted, waits for a supermajority of the stake on the
+//! new snapshot slot, bank hash and shred version.
 
 use {
-    crate::solana::wen_restart_proto::{
-        MyLastVotedForkSlots, State as RestartState, WenRestartProgress,
+    crate::{
+        heaviest_fork_aggregate::HeaviestForkAggregate,
+        last_voted_fork_slots_aggregate::LastVotedForkSlotsAggregate,
+        solana::wen_restart_proto::{
+            GenerateSnapshotRecord, HeaviestForkAggregateRecord, HeaviestForkRecord,
+            LastVotedForkSlotsAggregateRecord, MyLastVotedForkSlots, State as RestartState,
+            WenRestartProgress,
+        },
     },
     log::*,
     prost::Message,
-    solana_gossip::{cluster_info::ClusterInfo, epoch_slots::MAX_SLOTS_PER_ENTRY},
+    solana_gossip::{cluster_info::ClusterInfo, crds::Cursor, epoch_slots::MAX_SLOTS_PER_ENTRY},
     solana_ledger::{ancestor_iterator::AncestorIterator, blockstore::Blockstore},
+    solana_runtime::{
+        bank_forks::BankForks, snapshot_archive_info::SnapshotArchiveInfoGetter,
+        snapshot_bank_utils::bank_to_full_snapshot_archive, snapshot_config::SnapshotConfig,
+    },
+    solana_sdk::{
+        clock::{Epoch, Slot},
+        hash::Hash,
+        shred_version::compute_shred_version,
+        timing::timestamp,
+    },
     solana_vote_program::vote_state::VoteTransaction,
     std::{
-        fs::File,
+        cmp::Reverse,
+        collections::{HashMap, HashSet},
+        fs::{read, rename, File},
         io::{Error, Write},
         path::PathBuf,
-        sync::Arc,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, RwLock,
+        },
+        thread::sleep,
+        time::Duration,
     },
+    thiserror::Error,
 };
 
-pub fn wait_for_wen_restart(
+// If >42% of the validators have this block, repair this block locally.
+pub const REPAIR_THRESHOLD: f64 = 0.42;
+// Time to wait between two rounds of reading gossip or checking for replayed banks.
+const GOSSIP_SLEEP_MILLIS: u64 = 1_000;
+const BANK_FROZEN_SLEEP_MILLIS: u64 = 100;
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum WenRestartError {
+    #[error("Block {0} not found in blockstore")]
+    BlockNotFound(Slot),
+    #[error("Block {0} is not full")]
+    BlockNotFull(Slot),
+    #[error("Exiting")]
+    Exiting,
+    #[error("Not enough stake left to agree on heaviest fork {0} {1} for a supermajority")]
+    HeaviestForkDisagreement(Slot, Hash),
+    #[error("Heaviest fork {0} is the local root, a hard fork cannot be registered on it")]
+    HeaviestForkIsRoot(Slot),
+    #[error("Stakes of epoch {0} are unknown to the root bank")]
+    MissingEpochStakes(Epoch),
+    #[error("Missing last vote, wen_restart doesn't work if local tower is wiped")]
+    MissingLastVote,
+    #[error("Progress file in state {0:?} is missing {1}")]
+    MissingRecord(RestartState, &'static str),
+}
+
+/// Where a validator configured with `--wen-restart` is in the protocol
+#[derive(Debug, PartialEq, Eq)]
+pub enum WenRestartStatus {
+    /// The protocol has not started or not reached the snapshot yet
+    InProgress,
+    /// The restart snapshot was generated, the validator needs to wait for a supermajority of
+    /// the stake to restart on it
+    WaitingForSupermajority {
+        slot: Slot,
+        bankhash: Hash,
+        shred_version: u16,
+    },
+    /// The cluster restarted
+    Done,
+}
+
+pub struct WenRestartConfig {
+    pub wen_restart_path: PathBuf,
+    pub last_vote: VoteTransaction,
+    pub blockstore: Arc<Blockstore>,
+    pub cluster_info: Arc<ClusterInfo>,
+    pub bank_forks: Arc<RwLock<BankForks>>,
+    /// Slots RepairService should repair instead of following the usual repair weights
+    pub wen_restart_repair_slots: Option<Arc<RwLock<Vec<Slot>>>>,
+    pub wait_for_supermajority_threshold_percent: u64,
+    pub snapshot_config: SnapshotConfig,
+    pub genesis_config_hash: Hash,
+    pub exit: Arc<AtomicBool>,
+}
+
+pub fn wait_for_wen_restart(config: WenRestartConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let mut progress = if config.wen_restart_path.exists() {
+        read_wen_restart_records(&config.wen_restart_path)?
+    } else {
+        WenRestartProgress::default()
+    };
+    loop {
+        match progress.state() {
+            RestartState::Init => {
+                // repair and restart option does not work without last voted slot.
+                let last_vote_slot = config
+                    .last_vote
+                    .last_voted_slot()
+                    .ok_or(WenRestartError::MissingLastVote)?;
+                progress.my_last_voted_fork_slots = Some(MyLastVotedForkSlots {
+                    last_vote_slot,
+                    last_vote_bankhash: config.last_vote.hash().to_string(),
+                    shred_version: config.cluster_info.my_shred_version() as u32,
+                });
+                progress.set_state(RestartState::LastVotedForkSlots);
+            }
+            RestartState::LastVotedForkSlots => {
+                let last_voted_fork = send_restart_last_voted_fork_slots(&config, &progress)?;
+                aggregate_restart_last_voted_fork_slots(&config, &last_voted_fork, &mut progress)?;
+                progress.set_state(RestartState::HeaviestFork);
+            }
+            RestartState::HeaviestFork => {
+                // The heaviest fork is only selected once, a validator restarted in this phase
+                // keeps pushing the same one.
+                if progress.my_heaviest_fork.is_none() {
+                    progress.my_heaviest_fork = Some(find_heaviest_fork(&config, &progress)?);
+                    write_wen_restart_records(&config.wen_restart_path, &progress)?;
+                }
+                aggregate_restart_heaviest_fork(&config, &mut progress)?;
+                progress.set_state(RestartState::GeneratingSnapshot);
+            }
+            RestartState::GeneratingSnapshot => {
+                let heaviest_fork =
+                    progress
+                        .my_heaviest_fork
+                        .as_ref()
+                        .ok_or(WenRestartError::MissingRecord(
+                            progress.state(),
+                            "my_heaviest_fork",
+                        ))?;
+                progress.my_snapshot = Some(generate_snapshot(&config, heaviest_fork)?);
+                progress.set_state(RestartState::FinishedSnapshot);
+            }
+            RestartState::FinishedSnapshot => {
+                progress.set_state(RestartState::WaitingForSupermajority);
+                write_wen_restart_records(&config.wen_restart_path, &progress)?;
+                return Ok(());
+            }
+            RestartState::WaitingForSupermajority | RestartState::Done => return Ok(()),
+        }
+        write_wen_restart_records(&config.wen_restart_path, &progress)?;
+    }
+}
+
+/// Reads the status of the protocol from the progress file, a missing file means the protocol
+/// has not started yet
+pub fn wen_restart_status(
     wen_restart_path: &PathBuf,
-    last_vote: VoteTransaction,
-    blockstore: Arc<Blockstore>,
-    cluster_info: Arc<ClusterInfo>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // repair and restart option does not work without last voted slot.
-    let last_vote_slot = last_vote
-        .last_voted_slot()
-        .expect("wen_restart doesn't work if local tower is wiped");
-    let mut last_vote_fork: Vec<u64> = AncestorIterator::new_inclusive(last_vote_slot, &blockstore)
-        .take(MAX_SLOTS_PER_ENTRY)
-        .collect();
+) -> Result<WenRestartStatus, Box<dyn std::error::Error>> {
+    if !wen_restart_path.exists() {
+        return Ok(WenRestartStatus::InProgress);
+    }
+    let progress = read_wen_restart_records(wen_restart_path)?;
+    match progress.state() {
+        RestartState::WaitingForSupermajority => {
+            let snapshot = progress
+                .my_snapshot
+                .as_ref()
+                .ok_or(WenRestartError::MissingRecord(
+                    progress.state(),
+                    "my_snapshot",
+                ))?;
+            Ok(WenRestartStatus::WaitingForSupermajority {
+                slot: snapshot.slot,
+                bankhash: Hash::from_str(&snapshot.bankhash)?,
+                shred_version: snapshot.shred_version as u16,
+            })
+        }
+        RestartState::Done => Ok(WenRestartStatus::Done),
+        _ => Ok(WenRestartStatus::InProgress),
+    }
+}
+
+/// Records that the cluster restarted after waiting for a supermajority
+pub fn mark_wen_restart_done(wen_restart_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let mut progress = read_wen_restart_records(wen_restart_path)?;
+    progress.set_state(RestartState::Done);
+    write_wen_restart_records(wen_restart_path, &progress)?;
+    Ok(())
+}
+
+fn last_voted_fork(blockstore: &Blockstore, last_vote_slot: Slot) -> Vec<Slot> {
+    let mut last_voted_fork: Vec<Slot> =
+        AncestorIterator::new_inclusive(last_vote_slot, blockstore)
+            .take(MAX_SLOTS_PER_ENTRY)
+            .collect();
+    last_voted_fork.reverse();
+    last_voted_fork
+}
+
+fn send_restart_last_voted_fork_slots(
+    config: &WenRestartConfig,
+    progress: &WenRestartProgress,
+) -> Result<Vec<Slot>, Box<dyn std::error::Error>> {
+    let my_last_voted_fork_slots =
+        progress
+            .my_last_voted_fork_slots
+            .as_ref()
+            .ok_or(WenRestartError::MissingRecord(
+                progress.state(),
+                "my_last_voted_fork_slots",
+            ))?;
+    let last_voted_fork =
+        last_voted_fork(&config.blockstore, my_last_voted_fork_slots.last_vote_slot);
     info!(
         "wen_restart last voted fork {} {:?}",
-        last_vote_slot, last_vote_fork
+        my_last_voted_fork_slots.last_vote_slot, last_voted_fork
+    );
+    config.cluster_info.push_restart_last_voted_fork_slots(
+        &last_voted_fork,
+        Hash::from_str(&my_last_voted_fork_slots.last_vote_bankhash)?,
+    )?;
+    Ok(last_voted_fork)
+}
+
+/// Creates the aggregate of the last voted fork slots, including the ones already received
+/// before the validator restarted
+fn new_last_voted_fork_slots_aggregate(
+    config: &WenRestartConfig,
+    last_voted_fork: &[Slot],
+    progress: &WenRestartProgress,
+) -> Result<LastVotedForkSlotsAggregate, Box<dyn std::error::Error>> {
+    let root_bank = config.bank_forks.read().unwrap().root_bank();
+    // The root bank knows the stakes up to its leader schedule epoch, later slots can't have
+    // been produced by a leader this node agrees on.
+    let leader_schedule_epoch = root_bank.get_leader_schedule_epoch(root_bank.slot());
+    let epoch_stakes_map = (root_bank.epoch()..=leader_schedule_epoch)
+        .filter_map(|epoch| Some((epoch, root_bank.epoch_stakes(epoch)?.clone())))
+        .collect();
+    let mut aggregate = LastVotedForkSlotsAggregate::new(
+        root_bank.slot(),
+        REPAIR_THRESHOLD,
+        root_bank.epoch_schedule(),
+        epoch_stakes_map,
+        last_voted_fork,
+        &config.cluster_info.id(),
+    );
+    if let Some(aggregate_record) = &progress.last_voted_fork_slots_aggregate {
+        for (key_string, record) in &aggregate_record.received {
+            aggregate.aggregate_from_record(key_string, record)?;
+        }
+    }
+    Ok(aggregate)
+}
+
+fn aggregate_restart_last_voted_fork_slots(
+    config: &WenRestartConfig,
+    last_voted_fork: &[Slot],
+    progress: &mut WenRestartProgress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let root_slot = config.bank_forks.read().unwrap().root();
+    let mut aggregate = new_last_voted_fork_slots_aggregate(config, last_voted_fork, progress)?;
+    let mut received = progress
+        .last_voted_fork_slots_aggregate
+        .take()
+        .map(|aggregate_record| aggregate_record.received)
+        .unwrap_or_default();
+    let mut cursor = Cursor::default();
+    let mut is_full_slots = HashSet::new();
+    loop {
+        if config.exit.load(Ordering::Relaxed) {
+            return Err(WenRestartError::Exiting.into());
+        }
+        let start = timestamp();
+        let mut updated = false;
+        for new_last_voted_fork_slots in config
+            .cluster_info
+            .get_restart_last_voted_fork_slots(&mut cursor)
+        {
+            let from = new_last_voted_fork_slots.from.to_string();
+            if let Some(record) = aggregate.aggregate(new_last_voted_fork_slots) {
+                received.insert(from, record);
+                updated = true;
+            }
+        }
+        if updated {
+            progress.last_voted_fork_slots_aggregate = Some(LastVotedForkSlotsAggregateRecord {
+                received: received.clone(),
+            });
+            write_wen_restart_records(&config.wen_restart_path, progress)?;
+        }
+
+        // Because all operations on the aggregate are called from this single thread, we can
+        // fetch all results separately without worrying about them being out of sync.
+        let active_percent = aggregate.active_percent();
+        let mut filtered_slots: Vec<Slot> = aggregate
+            .slots_to_repair_iter()
+            .filter(|slot| {
+                if **slot <= root_slot || is_full_slots.contains(*slot) {
+                    return false;
+                }
+                if config.blockstore.is_full(**slot) {
+                    is_full_slots.insert(**slot);
+                    false
+                } else {
+                    true
+                }
+            })
+            .cloned()
+            .collect();
+        filtered_slots.sort();
+        info!(
+            "Active peers: {} Slots to repair: {:?}",
+            active_percent, &filtered_slots
+        );
+        if let Some(wen_restart_repair_slots) = &config.wen_restart_repair_slots {
+            *wen_restart_repair_slots.write().unwrap() = filtered_slots.clone();
+        }
+        if filtered_slots.is_empty()
+            && active_percent >= config.wait_for_supermajority_threshold_percent as f64
+        {
+            progress.last_voted_fork_slots_aggregate =
+                Some(LastVotedForkSlotsAggregateRecord { received });
+            return Ok(());
+        }
+        let elapsed = timestamp().saturating_sub(start);
+        let time_left = GOSSIP_SLEEP_MILLIS.saturating_sub(elapsed);
+        if time_left > 0 {
+            sleep(Duration::from_millis(time_left));
+        }
+    }
+}
+
+/// Walks down from the root, following at each fork the child on the last voted fork of the
+/// most stake, and returns the last slot reached. `slots` are the candidate blocks along with
+/// their parent and the stake which voted on them, blocks which do not descend from the root
+/// through other candidates are ignored. Ties go to the lowest slot, so that all validators
+/// select the same fork out of the same votes.
+fn heaviest_fork_slot(
+    root_slot: Slot,
+    slots: impl IntoIterator<Item = (Slot, /*parent:*/ Slot, /*stake:*/ u64)>,
+) -> Slot {
+    let mut children: HashMap<Slot, Vec<(Slot, u64)>> = HashMap::new();
+    for (slot, parent, stake) in slots {
+        children.entry(parent).or_default().push((slot, stake));
+    }
+    let mut heaviest_fork_slot = root_slot;
+    while let Some((slot, _)) = children.get(&heaviest_fork_slot).and_then(|children| {
+        children
+            .iter()
+            .max_by_key(|(slot, stake)| (*stake, Reverse(*slot)))
+    }) {
+        heaviest_fork_slot = *slot;
+    }
+    heaviest_fork_slot
+}
+
+/// Selects the heaviest fork out of the slots repaired in the previous phase, which are on the
+/// last voted fork of at least `REPAIR_THRESHOLD` of the stake. If these slots fork, the fork on
+/// the last voted fork of the most stake is followed.
+fn find_heaviest_fork(
+    config: &WenRestartConfig,
+    progress: &WenRestartProgress,
+) -> Result<HeaviestForkRecord, Box<dyn std::error::Error>> {
+    let my_last_voted_fork_slots =
+        progress
+            .my_last_voted_fork_slots
+            .as_ref()
+            .ok_or(WenRestartError::MissingRecord(
+                progress.state(),
+                "my_last_voted_fork_slots",
+            ))?;
+    let last_voted_fork =
+        last_voted_fork(&config.blockstore, my_last_voted_fork_slots.last_vote_slot);
+    let aggregate = new_last_voted_fork_slots_aggregate(config, &last_voted_fork, progress)?;
+    let root_slot = config.bank_forks.read().unwrap().root();
+    let mut slots = Vec::new();
+    for slot in aggregate.slots_to_repair_iter() {
+        if *slot <= root_slot {
+            continue;
+        }
+        let meta = config
+            .blockstore
+            .meta(*slot)?
+            .ok_or(WenRestartError::BlockNotFound(*slot))?;
+        if !meta.is_full() {
+            return Err(WenRestartError::BlockNotFull(*slot).into());
+        }
+        if let Some(parent_slot) = meta.parent_slot {
+            slots.push((*slot, parent_slot, aggregate.slot_stake(*slot)));
+        }
+    }
+    let heaviest_fork_slot = heaviest_fork_slot(root_slot, slots);
+
+    // ReplayStage keeps replaying the repaired blocks, wait for it to freeze the bank.
+    let bankhash = loop {
+        if config.exit.load(Ordering::Relaxed) {
+            return Err(WenRestartError::Exiting.into());
+        }
+        if let Some(bank) = config.bank_forks.read().unwrap().get(heaviest_fork_slot) {
+            if bank.is_frozen() {
+                break bank.hash();
+            }
+        }
+        sleep(Duration::from_millis(BANK_FROZEN_SLEEP_MILLIS));
+    };
+    info!(
+        "wen_restart heaviest fork {} {}",
+        heaviest_fork_slot, bankhash
+    );
+    Ok(HeaviestForkRecord {
+        slot: heaviest_fork_slot,
+        bankhash: bankhash.to_string(),
+        total_active_stake: aggregate.total_active_stake(),
+    })
+}
+
+/// Pushes the heaviest fork of this node to gossip and aggregates the heaviest forks of the
+/// other validators until the validators with `wait_for_supermajority_threshold_percent` of the
+/// stake selected the same fork. Fails once that is no longer possible, the operators then
+/// need to agree on the fork to restart from.
+fn aggregate_restart_heaviest_fork(
+    config: &WenRestartConfig,
+    progress: &mut WenRestartProgress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let my_heaviest_fork =
+        progress
+            .my_heaviest_fork
+            .as_ref()
+            .ok_or(WenRestartError::MissingRecord(
+                progress.state(),
+                "my_heaviest_fork",
+            ))?;
+    let heaviest_fork_slot = my_heaviest_fork.slot;
+    let heaviest_fork_hash = Hash::from_str(&my_heaviest_fork.bankhash)?;
+    config.cluster_info.push_restart_heaviest_fork(
+        heaviest_fork_slot,
+        heaviest_fork_hash,
+        my_heaviest_fork.total_active_stake,
     );
-    last_vote_fork.reverse();
-    // Todo(wen): add the following back in after Gossip code is checked in.
-    //    cluster_info.push_last_voted_fork_slots(&last_voted_fork, last_vote.hash());
-    // The rest of the protocol will be in another PR.
-    let current_progress = WenRestartProgress {
-        state: RestartState::Init.into(),
-        my_last_voted_fork_slots: Some(MyLastVotedForkSlots {
-            last_vote_slot,
-            last_vote_bankhash: last_vote.hash().to_string(),
-            shred_version: cluster_info.my_shred_version() as u32,
-        }),
+
+    let root_bank = config.bank_forks.read().unwrap().root_bank();
+    let heaviest_fork_epoch = root_bank.epoch_schedule().get_epoch(heaviest_fork_slot);
+    let epoch_stakes = root_bank
+        .epoch_stakes(heaviest_fork_epoch)
+        .ok_or(WenRestartError::MissingEpochStakes(heaviest_fork_epoch))?;
+    let mut aggregate = HeaviestForkAggregate::new(
+        epoch_stakes,
+        heaviest_fork_slot,
+        heaviest_fork_hash,
+        &config.cluster_info.id(),
+    );
+    let mut received = progress
+        .heaviest_fork_aggregate
+        .take()
+        .map(|aggregate_record| aggregate_record.received)
+        .unwrap_or_default();
+    for (key_string, record) in &received {
+        aggregate.aggregate_from_record(key_string, record)?;
+    }
+    let threshold_percent = config.wait_for_supermajority_threshold_percent as f64;
+    let mut cursor = Cursor::default();
+    loop {
+        if config.exit.load(Ordering::Relaxed) {
+            return Err(WenRestartError::Exiting.into());
+        }
+        let start = timestamp();
+        let mut updated = false;
+        for heaviest_fork in config.cluster_info.get_restart_heaviest_fork(&mut cursor) {
+            let from = heaviest_fork.from.to_string();
+            if let Some(record) = aggregate.aggregate(heaviest_fork) {
+                received.insert(from, record);
+                updated = true;
+            }
+        }
+        if updated {
+            progress.heaviest_fork_aggregate = Some(HeaviestForkAggregateRecord {
+                received: received.clone(),
+            });
+            write_wen_restart_records(&config.wen_restart_path, progress)?;
+        }
+
+        let agreeing_percent = aggregate.agreeing_percent();
+        let disagreeing_percent = aggregate.disagreeing_percent();
+        info!(
+            "wen_restart heaviest fork {} {} agreeing stake: {}% disagreeing stake: {}%",
+            heaviest_fork_slot, heaviest_fork_hash, agreeing_percent, disagreeing_percent
+        );
+        if agreeing_percent >= threshold_percent {
+            progress.heaviest_fork_aggregate = Some(HeaviestForkAggregateRecord { received });
+            return Ok(());
+        }
+        if 100.0 - disagreeing_percent < threshold_percent {
+            progress.heaviest_fork_aggregate = Some(HeaviestForkAggregateRecord { received });
+            return Err(WenRestartError::HeaviestForkDisagreement(
+                heaviest_fork_slot,
+                heaviest_fork_hash,
+            )
+            .into());
+        }
+        let elapsed = timestamp().saturating_sub(start);
+        let time_left = GOSSIP_SLEEP_MILLIS.saturating_sub(elapsed);
+        if time_left > 0 {
+            sleep(Duration::from_millis(time_left));
+        }
+    }
+}
+
+/// Registers a hard fork at the heaviest fork slot and generates a full snapshot there
+fn generate_snapshot(
+    config: &WenRestartConfig,
+    heaviest_fork: &HeaviestForkRecord,
+) -> Result<GenerateSnapshotRecord, Box<dyn std::error::Error>> {
+    let new_root_slot = heaviest_fork.slot;
+    let (root_bank, new_root_bank) = {
+        let bank_forks = config.bank_forks.read().unwrap();
+        (
+            bank_forks.root_bank(),
+            bank_forks
+                .get(new_root_slot)
+                .ok_or(WenRestartError::BlockNotFound(new_root_slot))?,
+        )
     };
-    write_wen_restart_records(wen_restart_path, current_progress)?;
-    Ok(())
+    if new_root_slot <= root_bank.slot() {
+        return Err(WenRestartError::HeaviestForkIsRoot(new_root_slot).into());
+    }
+    std::fs::create_dir_all(&config.snapshot_config.bank_snapshots_dir)?;
+    // The hard forks are shared with the descendants of the root bank, unlike the frozen
+    // heaviest fork bank the root bank accepts a hard fork in the future.
+    root_bank.register_hard_fork(new_root_slot);
+    let archive_info = bank_to_full_snapshot_archive(
+        &config.snapshot_config.bank_snapshots_dir,
+        &new_root_bank,
+        Some(config.snapshot_config.snapshot_version),
+        &config.snapshot_config.full_snapshot_archives_dir,
+        &config.snapshot_config.incremental_snapshot_archives_dir,
+        config.snapshot_config.archive_format,
+        config
+            .snapshot_config
+            .maximum_full_snapshot_archives_to_retain,
+        config
+            .snapshot_config
+            .maximum_incremental_snapshot_archives_to_retain,
+    )?;
+    let shred_version = compute_shred_version(
+        &config.genesis_config_hash,
+        Some(&new_root_bank.hard_forks()),
+    );
+    info!(
+        "wen_restart generated snapshot {:?} at slot {} bank hash {} shred version {}",
+        archive_info.path(),
+        new_root_slot,
+        new_root_bank.hash(),
+        shred_version
+    );
+    Ok(GenerateSnapshotRecord {
+        path: archive_info.path().display().to_string(),
+        slot: new_root_slot,
+        // The bank is rehashed with the hard fork when the snapshot is generated.
+        bankhash: new_root_bank.hash().to_string(),
+        shred_version: shred_version as u32,
+    })
+}
+
+fn read_wen_restart_records(
+    records_path: &PathBuf,
+) -> Result<WenRestartProgress, Box<dyn std::error::Error>> {
+    let buffer = read(records_path)?;
+    let progress = WenRestartProgress::decode(buffer.as_slice())?;
+    info!("read record {:?}", progress);
+    Ok(progress)
 }
 
 fn write_wen_restart_records(
     records_path: &PathBuf,
-    new_progress: WenRestartProgress,
+    new_progress: &WenRestartProgress,
 ) -> Result<(), Error> {
-    // overwrite anything if exists
-    let mut file = File::create(records_path)?;
     info!("writing new record {:?}", new_progress);
     let mut buf = Vec::with_capacity(new_progress.encoded_len());
     new_progress.encode(&mut buf)?;
-    file.write_all(&buf)?;
+    // Write a sibling file and rename it over the records, so a crash never leaves them partially
+    // written.
+    let mut tmp_path = records_path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    {
+        // overwrite anything if exists
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&buf)?;
+        file.sync_all()?;
+    }
+    rename(&tmp_path, records_path)?;
     Ok(())
 }
+
 #[cfg(test)]
 mod tests {
     use {
         crate::wen_restart::*,
+        solana_accounts_db::{
+            accounts_db::{AccountShrinkThreshold, ACCOUNTS_DB_CONFIG_FOR_TESTING},
+            accounts_index::AccountSecondaryIndexes,
+        },
         solana_entry::entry,
-        solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
+        solana_gossip::{
+            cluster_info::ClusterInfo,
+            contact_info::ContactInfo,
+            crds::GossipRoute,
+            crds_value::{CrdsData, CrdsValue, RestartHeaviestFork, RestartLastVotedForkSlots},
+        },
         solana_ledger::{blockstore, get_tmp_ledger_path_auto_delete},
         solana_program::{hash::Hash, vote::state::Vote},
+        solana_runtime::{
+            bank::Bank,
+            genesis_utils::{
+                create_genesis_config_with_vote_accounts, GenesisConfigInfo, ValidatorVoteKeypairs,
+            },
+            runtime_config::RuntimeConfig,
+            snapshot_archive_info::FullSnapshotArchiveInfo,
+            snapshot_bank_utils::bank_from_snapshot_archives,
+            snapshot_utils::create_tmp_accounts_dir_for_tests,
+        },
         solana_sdk::{
+            genesis_config::GenesisConfig,
+            pubkey::Pubkey,
             signature::{Keypair, Signer},
             timing::timestamp,
         },
         solana_streamer::socket::SocketAddrSpace,
-        std::{fs::read, sync::Arc},
+        std::{collections::HashMap, fs::read, thread::Builder},
+        tempfile::TempDir,
     };
 
-    #[test]
-    fn test_wen_restart_normal_flow() {
+    const SHRED_VERSION: u16 = 2;
+    const TOTAL_VALIDATOR_COUNT: usize = 4;
+
+    struct WenRestartTestInitResult {
+        pub validator_voting_keypairs: Vec<ValidatorVoteKeypairs>,
+        pub cluster_info: Arc<ClusterInfo>,
+        pub blockstore: Arc<Blockstore>,
+        pub bank_forks: Arc<RwLock<BankForks>>,
+        pub genesis_config: GenesisConfig,
+        pub snapshot_config: SnapshotConfig,
+        // Keeps the snapshot directories alive until the end of the test.
+        pub _snapshot_dirs: TempDir,
+    }
+
+    fn wen_restart_test_init(ledger_path: &TempDir) -> WenRestartTestInitResult {
         solana_logger::setup();
-        let node_keypair = Arc::new(Keypair::new());
+        let validator_voting_keypairs: Vec<_> = (0..TOTAL_VALIDATOR_COUNT)
+            .map(|_| ValidatorVoteKeypairs::new_rand())
+            .collect();
+        let node_keypair = Arc::new(validator_voting_keypairs[0].node_keypair.insecure_clone());
         let cluster_info = Arc::new(ClusterInfo::new(
             {
                 let mut contact_info =
                     ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp());
-                contact_info.set_shred_version(2);
+                contact_info.set_shred_version(SHRED_VERSION);
                 contact_info
             },
             node_keypair,
             SocketAddrSpace::Unspecified,
         ));
-        let ledger_path = get_tmp_ledger_path_auto_delete!();
-        let mut wen_restart_proto_path = ledger_path.path().to_path_buf();
-        wen_restart_proto_path.push("wen_restart_status.proto");
         let blockstore = Arc::new(blockstore::Blockstore::open(ledger_path.path()).unwrap());
-        let expected_slots = 400;
-        let last_vote_slot = (MAX_SLOTS_PER_ENTRY + expected_slots).try_into().unwrap();
-        let last_parent = (MAX_SLOTS_PER_ENTRY >> 1).try_into().unwrap();
-        for i in 0..expected_slots {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            10_000,
+            &validator_voting_keypairs,
+            vec![100; TOTAL_VALIDATOR_COUNT],
+        );
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new_for_tests(
+            &genesis_config,
+        ))));
+        let snapshot_dirs = TempDir::new().unwrap();
+        let snapshot_config = SnapshotConfig {
+            bank_snapshots_dir: snapshot_dirs.path().join("bank_snapshots"),
+            full_snapshot_archives_dir: snapshot_dirs.path().to_path_buf(),
+            incremental_snapshot_archives_dir: snapshot_dirs.path().to_path_buf(),
+            ..SnapshotConfig::default()
+        };
+        WenRestartTestInitResult {
+            validator_voting_keypairs,
+            cluster_info,
+            blockstore,
+            bank_forks,
+            genesis_config,
+            snapshot_config,
+            _snapshot_dirs: snapshot_dirs,
+        }
+    }
+
+    fn insert_slots_into_blockstore(
+        blockstore: &Blockstore,
+        first_parent: Slot,
+        slots: &[Slot],
+        is_full_slot: bool,
+    ) {
+        let mut parent_slot = first_parent;
+        for slot in slots {
             let entries = entry::create_ticks(1, 0, Hash::default());
-            let parent_slot = if i > 0 {
-                (MAX_SLOTS_PER_ENTRY + i).try_into().unwrap()
-            } else {
-                last_parent
-            };
             let shreds = blockstore::entries_to_test_shreds(
                 &entries,
-                (MAX_SLOTS_PER_ENTRY + i + 1).try_into().unwrap(),
+                *slot,
                 parent_slot,
-                false,
+                is_full_slot,
                 0,
                 true, // merkle_variant
             );
             blockstore.insert_shreds(shreds, None, false).unwrap();
+            parent_slot = *slot;
         }
-        // link directly to slot 1 whose distance to last_vote > MAX_SLOTS_PER_ENTRY so it will not be included.
-        let entries = entry::create_ticks(1, 0, Hash::default());
-        let shreds = blockstore::entries_to_test_shreds(
-            &entries,
-            last_parent,
-            1,
-            false,
-            0,
-            true, // merkle_variant
-        );
-        blockstore.insert_shreds(shreds, None, false).unwrap();
+    }
+
+    fn wen_restart_config(
+        test_state: &WenRestartTestInitResult,
+        wen_restart_path: &PathBuf,
+        last_vote: VoteTransaction,
+        exit: Arc<AtomicBool>,
+    ) -> WenRestartConfig {
+        WenRestartConfig {
+            wen_restart_path: wen_restart_path.clone(),
+            last_vote,
+            blockstore: test_state.blockstore.clone(),
+            cluster_info: test_state.cluster_info.clone(),
+            bank_forks: test_state.bank_forks.clone(),
+            wen_restart_repair_slots: Some(Arc::new(RwLock::new(Vec::new()))),
+            wait_for_supermajority_threshold_percent: 80,
+            snapshot_config: test_state.snapshot_config.clone(),
+            genesis_config_hash: test_state.genesis_config.hash(),
+            exit,
+        }
+    }
+
+    fn insert_into_gossip(cluster_info: &ClusterInfo, data: CrdsData, keypair: &Keypair) {
+        let mut gossip_crds = cluster_info.gossip.crds.write().unwrap();
+        assert!(gossip_crds
+            .insert(
+                CrdsValue::new_signed(data, keypair),
+                timestamp(),
+                GossipRoute::LocalMessage,
+            )
+            .is_ok());
+    }
+
+    /// Pushes the last voted fork of the given validators, and their heaviest fork if any
+    fn push_peer_restart_messages(
+        test_state: &WenRestartTestInitResult,
+        validators: std::ops::Range<usize>,
+        fork_slots: &[Slot],
+        last_vote_bankhash: Hash,
+        heaviest_fork: Option<(Slot, Hash)>,
+    ) {
+        for validator_voting_keypairs in &test_state.validator_voting_keypairs[validators] {
+            let node_keypair = &validator_voting_keypairs.node_keypair;
+            let mut last_voted_fork_slots = RestartLastVotedForkSlots::new(
+                node_keypair.pubkey(),
+                timestamp(),
+                last_vote_bankhash,
+                SHRED_VERSION,
+            );
+            last_voted_fork_slots.fill(fork_slots);
+            insert_into_gossip(
+                &test_state.cluster_info,
+                CrdsData::RestartLastVotedForkSlots(last_voted_fork_slots),
+                node_keypair,
+            );
+            if let Some((last_slot, last_slot_hash)) = heaviest_fork {
+                insert_into_gossip(
+                    &test_state.cluster_info,
+                    CrdsData::RestartHeaviestFork(RestartHeaviestFork {
+                        from: node_keypair.pubkey(),
+                        wallclock: timestamp(),
+                        last_slot,
+                        last_slot_hash,
+                        observed_stake: 400,
+                        shred_version: SHRED_VERSION,
+                    }),
+                    node_keypair,
+                );
+            }
+        }
+    }
+
+    /// Inserts full blocks for the slots, each a child of the previous one, and replays them
+    /// the way ReplayStage would. Returns the bank hashes.
+    fn insert_and_replay_fork(
+        test_state: &WenRestartTestInitResult,
+        first_parent: Slot,
+        fork_slots: &[Slot],
+    ) -> HashMap<Slot, Hash> {
+        insert_slots_into_blockstore(&test_state.blockstore, first_parent, fork_slots, true);
+        let mut bank_hashes = HashMap::new();
+        let mut bank_forks = test_state.bank_forks.write().unwrap();
+        let mut parent_slot = first_parent;
+        for slot in fork_slots {
+            let parent = bank_forks.get(parent_slot).unwrap();
+            let bank = bank_forks.insert(Bank::new_from_parent(parent, &Pubkey::default(), *slot));
+            bank.fill_bank_with_ticks_for_tests();
+            bank.freeze();
+            bank_hashes.insert(*slot, bank.hash());
+            parent_slot = *slot;
+        }
+        bank_hashes
+    }
+
+    fn read_progress(wen_restart_path: &PathBuf) -> WenRestartProgress {
+        let buffer = read(wen_restart_path).unwrap();
+        WenRestartProgress::decode(buffer.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_wen_restart_last_voted_fork_slots() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let test_state = wen_restart_test_init(&ledger_path);
+        let wen_restart_proto_path = ledger_path.path().join("wen_restart_status.proto");
+        let expected_slots = 400;
+        let last_vote_slot = (MAX_SLOTS_PER_ENTRY + expected_slots).try_into().unwrap();
+        let last_parent = (MAX_SLOTS_PER_ENTRY >> 1).try_into().unwrap();
+        // Blocks are not full, so wen_restart keeps waiting for them to be repaired.
+        insert_slots_into_blockstore(&test_state.blockstore, 1, &[last_parent], false);
+        let fork_slots: Vec<Slot> = (0..expected_slots)
+            .map(|i| (MAX_SLOTS_PER_ENTRY + i + 1).try_into().unwrap())
+            .collect();
+        insert_slots_into_blockstore(&test_state.blockstore, last_parent, &fork_slots, false);
         let last_vote_bankhash = Hash::new_unique();
-        assert!(wait_for_wen_restart(
+        let exit = Arc::new(AtomicBool::new(false));
+        let config = wen_restart_config(
+            &test_state,
             &wen_restart_proto_path,
             VoteTransaction::from(Vote::new(vec![last_vote_slot], last_vote_bankhash)),
-            blockstore,
-            cluster_info
-        )
-        .is_ok());
-        let buffer = read(wen_restart_proto_path).unwrap();
-        let progress = WenRestartProgress::decode(&mut std::io::Cursor::new(buffer)).unwrap();
+            exit.clone(),
+        );
+        let wen_restart_thread_handle = Builder::new()
+            .name("solana-wen-restart".to_string())
+            .spawn(move || {
+                assert_eq!(
+                    wait_for_wen_restart(config)
+                        .unwrap_err()
+                        .downcast::<WenRestartError>()
+                        .unwrap(),
+                    Box::new(WenRestartError::Exiting)
+                );
+            })
+            .unwrap();
+
+        let mut cursor = Cursor::default();
+        let my_last_voted_fork_slots = loop {
+            if let Some(slots) = test_state
+                .cluster_info
+                .get_restart_last_voted_fork_slots(&mut cursor)
+                .pop()
+            {
+                break slots;
+            }
+            sleep(Duration::from_millis(100));
+        };
+        let mut expected_fork = vec![1, last_parent];
+        expected_fork.extend(&fork_slots);
+        assert_eq!(my_last_voted_fork_slots.to_slots(0), expected_fork);
+        assert_eq!(my_last_voted_fork_slots.last_voted_hash, last_vote_bankhash);
+
+        // Another validator voted on the same fork, the missing blocks get repaired.
+        let peer_keypair = &test_state.validator_voting_keypairs[1].node_keypair;
+        push_peer_restart_messages(&test_state, 1..2, &expected_fork, last_vote_bankhash, None);
+        let mut expected_repair_slots = expected_fork.clone();
+        expected_repair_slots.retain(|slot| *slot > 0);
+        loop {
+            let progress = read_progress(&wen_restart_proto_path);
+            if progress
+                .last_voted_fork_slots_aggregate
+                .map(|aggregate| aggregate.received.len())
+                == Some(1)
+            {
+                break;
+            }
+            sleep(Duration::from_millis(100));
+        }
+        exit.store(true, Ordering::Relaxed);
+        wen_restart_thread_handle.join().unwrap();
+
+        let progress = read_progress(&wen_restart_proto_path);
+        assert_eq!(progress.state(), RestartState::LastVotedForkSlots);
         assert_eq!(
-            progress,
-            WenRestartProgress {
-                state: RestartState::Init.into(),
-                my_last_voted_fork_slots: Some(MyLastVotedForkSlots {
-                    last_vote_slot,
-                    last_vote_bankhash: last_vote_bankhash.to_string(),
-                    shred_version: 2,
-                }),
+            progress.my_last_voted_fork_slots,
+            Some(MyLastVotedForkSlots {
+                last_vote_slot,
+                last_vote_bankhash: last_vote_bankhash.to_string(),
+                shred_version: SHRED_VERSION as u32,
+            })
+        );
+        let received = progress.last_voted_fork_slots_aggregate.unwrap().received;
+        assert_eq!(
+            received[&peer_keypair.pubkey().to_string()].last_voted_fork_slots,
+            expected_repair_slots
+        );
+        assert_eq!(
+            wen_restart_status(&wen_restart_proto_path).unwrap(),
+            WenRestartStatus::InProgress
+        );
+    }
+
+    #[test]
+    fn test_wen_restart_normal_flow() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let test_state = wen_restart_test_init(&ledger_path);
+        let wen_restart_proto_path = ledger_path.path().join("wen_restart_status.proto");
+        let fork_slots: Vec<Slot> = (1..=4).collect();
+        let bank_hashes = insert_and_replay_fork(&test_state, 0, &fork_slots);
+        let last_vote_slot = *fork_slots.last().unwrap();
+        let last_vote_bankhash = bank_hashes[&last_vote_slot];
+        // All other validators voted on the fork, and selected it as the heaviest fork.
+        push_peer_restart_messages(
+            &test_state,
+            1..TOTAL_VALIDATOR_COUNT,
+            &fork_slots,
+            last_vote_bankhash,
+            Some((last_vote_slot, last_vote_bankhash)),
+        );
+        let config = wen_restart_config(
+            &test_state,
+            &wen_restart_proto_path,
+            VoteTransaction::from(Vote::new(vec![last_vote_slot], last_vote_bankhash)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert!(wait_for_wen_restart(config).is_ok());
+
+        let progress = read_progress(&wen_restart_proto_path);
+        assert_eq!(progress.state(), RestartState::WaitingForSupermajority);
+        assert_eq!(
+            progress.my_heaviest_fork,
+            Some(HeaviestForkRecord {
+                slot: last_vote_slot,
+                bankhash: last_vote_bankhash.to_string(),
+                total_active_stake: 400,
+            })
+        );
+        assert_eq!(
+            progress.heaviest_fork_aggregate.unwrap().received.len(),
+            TOTAL_VALIDATOR_COUNT - 1
+        );
+        let snapshot = progress.my_snapshot.unwrap();
+        assert_eq!(snapshot.slot, last_vote_slot);
+        assert!(PathBuf::from(&snapshot.path).exists());
+        // The hard fork changes both the bank hash and the shred version.
+        let bankhash = Hash::from_str(&snapshot.bankhash).unwrap();
+        assert_ne!(bankhash, last_vote_bankhash);
+        assert_ne!(snapshot.shred_version as u16, SHRED_VERSION);
+        assert_eq!(
+            wen_restart_status(&wen_restart_proto_path).unwrap(),
+            WenRestartStatus::WaitingForSupermajority {
+                slot: last_vote_slot,
+                bankhash,
+                shred_version: snapshot.shred_version as u16,
             }
+        );
+
+        // Resuming a finished restart is a no-op.
+        let config = wen_restart_config(
+            &test_state,
+            &wen_restart_proto_path,
+            VoteTransaction::from(Vote::new(vec![last_vote_slot], last_vote_bankhash)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert!(wait_for_wen_restart(config).is_ok());
+        assert_eq!(
+            read_progress(&wen_restart_proto_path).state(),
+            RestartState::WaitingForSupermajority
+        );
+
+        mark_wen_restart_done(&wen_restart_proto_path).unwrap();
+        assert_eq!(
+            wen_restart_status(&wen_restart_proto_path).unwrap(),
+            WenRestartStatus::Done
+        );
+    }
+
+    #[test]
+    fn test_generate_snapshot() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let test_state = wen_restart_test_init(&ledger_path);
+        let wen_restart_proto_path = ledger_path.path().join("wen_restart_status.proto");
+        let bank_hashes = insert_and_replay_fork(&test_state, 0, &[1, 2, 3]);
+        let config = wen_restart_config(
+            &test_state,
+            &wen_restart_proto_path,
+            VoteTransaction::from(Vote::new(vec![3], bank_hashes[&3])),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let heaviest_fork = HeaviestForkRecord {
+            slot: 3,
+            bankhash: bank_hashes[&3].to_string(),
+            total_active_stake: 400,
+        };
+        let snapshot = generate_snapshot(&config, &heaviest_fork).unwrap();
+        assert_eq!(snapshot.slot, 3);
+
+        // A validator restarting from the snapshot ends up on the bank hash and shred version
+        // wen_restart reported.
+        let (_accounts_tmp_dir, accounts_dir) = create_tmp_accounts_dir_for_tests();
+        let bank_snapshots_dir = TempDir::new().unwrap();
+        let archive_info =
+            FullSnapshotArchiveInfo::new_from_path(PathBuf::from(&snapshot.path)).unwrap();
+        let (bank, _) = bank_from_snapshot_archives(
+            &[accounts_dir],
+            bank_snapshots_dir.path(),
+            &archive_info,
+            None,
+            &test_state.genesis_config,
+            &RuntimeConfig::default(),
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+            None,
+            AccountShrinkThreshold::default(),
+            false,
+            false,
+            false,
+            false,
+            Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+            None,
+            Arc::default(),
         )
+        .unwrap();
+        assert_eq!(bank.slot(), 3);
+        assert_eq!(bank.hash().to_string(), snapshot.bankhash);
+        assert_ne!(bank.hash(), bank_hashes[&3]);
+        assert_eq!(
+            compute_shred_version(&test_state.genesis_config.hash(), Some(&bank.hard_forks())),
+            snapshot.shred_version as u16
+        );
+    }
+
+    #[test]
+    fn test_heaviest_fork_slot() {
+        // No candidate block, the root is the heaviest fork.
+        assert_eq!(heaviest_fork_slot(10, []), 10);
+        // 10 - 11 - 12 - 14
+        //        \- 13 - 15
+        //         \- 16
+        let slots = [
+            (11, 10, 100),
+            (12, 11, 60),
+            (14, 12, 60),
+            (13, 11, 40),
+            (15, 13, 40),
+            (16, 11, 50),
+            // Does not descend from the root.
+            (17, 9, 100),
+        ];
+        assert_eq!(heaviest_fork_slot(10, slots), 14);
+        // Ties go to the lowest slot.
+        assert_eq!(heaviest_fork_slot(11, [(13, 11, 50), (12, 11, 50)]), 12);
+    }
+
+    #[test]
+    fn test_wen_restart_heaviest_fork_by_stake() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let test_state = wen_restart_test_init(&ledger_path);
+        let wen_restart_proto_path = ledger_path.path().join("wen_restart_status.proto");
+        // 0 - 1 - 2
+        //      \- 3
+        let mut bank_hashes = insert_and_replay_fork(&test_state, 0, &[1, 2]);
+        bank_hashes.extend(insert_and_replay_fork(&test_state, 1, &[3]));
+        // This node and another validator voted on 2, the other two on 3. Both forks are
+        // repaired, this node selects 2 out of the tie.
+        push_peer_restart_messages(&test_state, 1..2, &[1, 2], bank_hashes[&2], None);
+        push_peer_restart_messages(&test_state, 2..4, &[1, 3], bank_hashes[&3], None);
+        let config = wen_restart_config(
+            &test_state,
+            &wen_restart_proto_path,
+            VoteTransaction::from(Vote::new(vec![2], bank_hashes[&2])),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let heaviest_fork_hash = bank_hashes[&2];
+        let wen_restart_thread_handle = Builder::new()
+            .name("solana-wen-restart".to_string())
+            .spawn(move || {
+                assert_eq!(
+                    wait_for_wen_restart(config)
+                        .unwrap_err()
+                        .downcast::<WenRestartError>()
+                        .unwrap(),
+                    Box::new(WenRestartError::HeaviestForkDisagreement(
+                        2,
+                        heaviest_fork_hash
+                    ))
+                );
+            })
+            .unwrap();
+        let mut cursor = Cursor::default();
+        let my_heaviest_fork = loop {
+            if let Some(heaviest_fork) = test_state
+                .cluster_info
+                .get_restart_heaviest_fork(&mut cursor)
+                .pop()
+            {
+                break heaviest_fork;
+            }
+            sleep(Duration::from_millis(100));
+        };
+        assert_eq!(my_heaviest_fork.last_slot, 2);
+        assert_eq!(my_heaviest_fork.last_slot_hash, bank_hashes[&2]);
+        assert_eq!(my_heaviest_fork.observed_stake, 400);
+
+        // Once a validator selected 3, the rest of the stake cannot reach the supermajority
+        // threshold of 80% on 2.
+        let node_keypair = &test_state.validator_voting_keypairs[2].node_keypair;
+        insert_into_gossip(
+            &test_state.cluster_info,
+            CrdsData::RestartHeaviestFork(RestartHeaviestFork {
+                from: node_keypair.pubkey(),
+                wallclock: timestamp(),
+                last_slot: 3,
+                last_slot_hash: bank_hashes[&3],
+                observed_stake: 400,
+                shred_version: SHRED_VERSION,
+            }),
+            node_keypair,
+        );
+        wen_restart_thread_handle.join().unwrap();
+        let progress = read_progress(&wen_restart_proto_path);
+        assert_eq!(progress.state(), RestartState::HeaviestFork);
+        assert_eq!(progress.my_heaviest_fork.unwrap().slot, 2);
+        assert!(progress.my_snapshot.is_none());
+    }
+
+    #[test]
+    fn test_write_wen_restart_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let wen_restart_path = dir.path().join("wen_restart_progress.proto");
+        let mut progress = WenRestartProgress {
+            state: RestartState::Init.into(),
+            ..WenRestartProgress::default()
+        };
+        write_wen_restart_records(&wen_restart_path, &progress).unwrap();
+        progress.set_state(RestartState::LastVotedForkSlots);
+        write_wen_restart_records(&wen_restart_path, &progress).unwrap();
+        assert_eq!(read_progress(&wen_restart_path), progress);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
     }
 }